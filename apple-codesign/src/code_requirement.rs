@@ -25,12 +25,15 @@ on 4 byte boundaries.
 
 use {
     crate::{
+        apple_certificates::KnownCertificate,
+        certificate::AppleCertificate,
         embedded_signature::{
-            read_and_validate_blob_header, CodeSigningMagic, RequirementBlob, RequirementSetBlob,
+            read_and_validate_blob_header, Blob, CodeSigningMagic, CodeSigningSlot, DigestType,
+            EmbeddedSignature, RequirementBlob, RequirementSetBlob,
         },
         error::AppleCodesignError,
     },
-    bcder::Oid,
+    bcder::{ConstOid, Oid},
     chrono::TimeZone,
     scroll::{IOwrite, Pread},
     std::{
@@ -40,8 +43,14 @@ use {
         io::Write,
         ops::{Deref, DerefMut},
     },
+    x509_certificate::{certificate::certificate_is_subset_of, rfc4519, CapturedX509Certificate},
 };
 
+/// Certificate policies X.509 extension.
+///
+/// 2.5.29.32
+const OID_CERTIFICATE_POLICIES: ConstOid = Oid(&[85, 29, 32]);
+
 const OPCODE_FLAG_MASK: u32 = 0xff000000;
 const OPCODE_VALUE_MASK: u32 = 0x00ffffff;
 
@@ -216,6 +225,14 @@ impl<'a> Display for CodeRequirementValue<'a> {
 }
 
 impl<'a> CodeRequirementValue<'a> {
+    /// Obtain the raw bytes constituting this value.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::String(s) => s.as_bytes(),
+            Self::Bytes(data) => data,
+        }
+    }
+
     /// Write the encoded version of this value somewhere.
     ///
     /// Binary encoding is u32 of length, then raw bytes, then NULL padding to next u32.
@@ -1345,6 +1362,535 @@ impl<'a> TryFrom<&CodeRequirements<'a>> for RequirementBlob<'static> {
     }
 }
 
+/// Signing context against which code requirement expressions are evaluated.
+///
+/// Instances are typically constructed from an [EmbeddedSignature] via
+/// [Self::from_embedded_signature]. But all fields are public so callers can
+/// construct or tweak instances as needed.
+#[derive(Clone, Debug, Default)]
+pub struct CodeRequirementContext {
+    /// The signing identifier.
+    pub identifier: Option<String>,
+
+    /// Digests of all code directories in the signature.
+    ///
+    /// Each digest is computed using the digest type of its code directory.
+    pub code_directory_hashes: Vec<Vec<u8>>,
+
+    /// The platform identifier recorded in the code directory.
+    pub platform: Option<u32>,
+
+    /// The certificate chain of the signer.
+    ///
+    /// The first certificate is the leaf / signing certificate and the last
+    /// certificate is the anchor.
+    pub certificates: Vec<CapturedX509Certificate>,
+
+    /// The parsed `Info.plist` of the entity being evaluated.
+    pub info_plist: Option<plist::Dictionary>,
+
+    /// The parsed entitlements of the entity being evaluated.
+    pub entitlements: Option<plist::Dictionary>,
+
+    /// Whether a notarization ticket is attached to the entity.
+    pub notarized: bool,
+}
+
+impl CodeRequirementContext {
+    /// Construct an instance from an embedded signature.
+    ///
+    /// The `Info.plist` isn't part of the embedded signature, so it must be
+    /// provided separately if available.
+    pub fn from_embedded_signature(
+        signature: &EmbeddedSignature,
+        info_plist: Option<plist::Dictionary>,
+    ) -> Result<Self, AppleCodesignError> {
+        let cd = signature
+            .code_directory()?
+            .ok_or(AppleCodesignError::BinaryNoCodeSignature)?;
+
+        let code_directory_hashes = signature
+            .all_code_directories()?
+            .into_iter()
+            .map(|(_, cd)| cd.digest_type.digest_data(&cd.to_blob_bytes()?))
+            .collect::<Result<Vec<_>, AppleCodesignError>>()?;
+
+        let certificates = if let Some(signed_data) = signature.signed_data()? {
+            if let Some((issuer, serial)) = signed_data
+                .signers()
+                .next()
+                .and_then(|signer| signer.certificate_issuer_and_serial())
+            {
+                if let Some(leaf) = signed_data.certificates().find(|cert| {
+                    certificate_is_subset_of(
+                        serial,
+                        issuer,
+                        cert.serial_number_asn1(),
+                        cert.issuer_name(),
+                    )
+                }) {
+                    let mut chain = vec![leaf.clone()];
+                    chain.extend(
+                        leaf.resolve_signing_chain(signed_data.certificates())
+                            .into_iter()
+                            .cloned(),
+                    );
+
+                    chain
+                } else {
+                    vec![]
+                }
+            } else {
+                vec![]
+            }
+        } else {
+            vec![]
+        };
+
+        let entitlements = if let Some(entitlements) = signature.entitlements()? {
+            let value = plist::Value::from_reader_xml(entitlements.as_str().as_bytes())
+                .map_err(AppleCodesignError::PlistParseXml)?;
+
+            value.into_dictionary()
+        } else {
+            None
+        };
+
+        Ok(Self {
+            identifier: Some(cd.ident.to_string()),
+            code_directory_hashes,
+            platform: if cd.platform != 0 {
+                Some(cd.platform as u32)
+            } else {
+                None
+            },
+            certificates,
+            info_plist,
+            entitlements,
+            notarized: signature.find_slot(CodeSigningSlot::Ticket).is_some(),
+        })
+    }
+
+    /// Resolve the certificate in a given slot of the certificate chain.
+    ///
+    /// Non-negative slots count from the leaf certificate. Negative slots count
+    /// from the anchor, with `-1` being the anchor itself.
+    pub fn certificate_at_slot(&self, slot: i32) -> Option<&CapturedX509Certificate> {
+        if slot >= 0 {
+            self.certificates.get(slot as usize)
+        } else {
+            let index = self.certificates.len() as i64 + slot as i64;
+
+            if index >= 0 {
+                self.certificates.get(index as usize)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Convert a plist value to a value that can be matched against.
+fn plist_value_to_requirement_value(value: &plist::Value) -> Option<CodeRequirementValue<'_>> {
+    match value {
+        plist::Value::String(s) => Some(CodeRequirementValue::String(s.as_str().into())),
+        plist::Value::Data(data) => Some(CodeRequirementValue::Bytes(data.as_slice().into())),
+        plist::Value::Boolean(v) => Some(CodeRequirementValue::from(v.to_string())),
+        plist::Value::Integer(v) => Some(CodeRequirementValue::from(v.to_string())),
+        plist::Value::Real(v) => Some(CodeRequirementValue::from(v.to_string())),
+        _ => None,
+    }
+}
+
+/// Resolve the value of a `subject.*` or `issuer.*` certificate field.
+fn certificate_field_value(
+    cert: &CapturedX509Certificate,
+    field: &str,
+) -> Option<CodeRequirementValue<'static>> {
+    let (name, attribute) = field.split_once('.')?;
+
+    let name = match name {
+        "subject" => cert.subject_name(),
+        "issuer" => cert.issuer_name(),
+        _ => return None,
+    };
+
+    let oid = match attribute {
+        "CN" => rfc4519::OID_COMMON_NAME,
+        "C" => rfc4519::OID_COUNTRY_NAME,
+        "L" => rfc4519::OID_LOCALITY_NAME,
+        "ST" => rfc4519::OID_STATE_PROVINCE_NAME,
+        "O" => rfc4519::OID_ORGANIZATION_NAME,
+        "OU" => rfc4519::OID_ORGANIZATIONAL_UNIT_NAME,
+        _ => return None,
+    };
+
+    name.find_first_attribute_string(Oid(oid.as_ref().into()))
+        .ok()
+        .flatten()
+        .map(CodeRequirementValue::from)
+}
+
+/// Parse the value of a certificate extension holding a date.
+///
+/// The extension value is expected to be a DER encoded `UTCTime` or `GeneralizedTime`.
+fn certificate_extension_date(
+    data: &[u8],
+) -> Result<chrono::DateTime<chrono::Utc>, AppleCodesignError> {
+    bcder::decode::Constructed::decode(data, bcder::Mode::Der, |cons| {
+        x509_certificate::asn1time::Time::take_from(cons)
+    })
+    .map(chrono::DateTime::<chrono::Utc>::from)
+    .map_err(|_| AppleCodesignError::RequirementMalformed("certificate extension is not a date"))
+}
+
+/// The outcome of evaluating a code requirement expression.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CodeRequirementEvaluation {
+    /// The requirement holds.
+    Satisfied,
+
+    /// The requirement does not hold.
+    Unsatisfied {
+        /// The clause of the expression that failed.
+        clause: String,
+        /// Why the clause failed.
+        reason: String,
+    },
+}
+
+impl Display for CodeRequirementEvaluation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Satisfied => f.write_str("satisfied"),
+            Self::Unsatisfied { clause, reason } => {
+                f.write_fmt(format_args!("`{}` not satisfied: {}", clause, reason))
+            }
+        }
+    }
+}
+
+impl CodeRequirementEvaluation {
+    /// Whether the requirement holds.
+    pub fn is_satisfied(&self) -> bool {
+        matches!(self, Self::Satisfied)
+    }
+}
+
+impl<'a> CodeRequirementExpression<'a> {
+    /// Evaluate this expression against a signing context.
+    ///
+    /// We don't have access to the system's trust settings. So `anchor trusted`
+    /// and `certificate <slot> trusted` only consider certificates known to be
+    /// Apple certificate authorities as trusted. Named anchors, named code, and
+    /// legacy developer ID expressions are not supported and never hold.
+    ///
+    /// Errors are returned if evaluation could not be performed, such as when a
+    /// match operator can't be applied to the value being matched.
+    pub fn evaluate(
+        &self,
+        context: &CodeRequirementContext,
+    ) -> Result<CodeRequirementEvaluation, AppleCodesignError> {
+        Ok(match self {
+            Self::False => self.check(false, || "expression is always false".into()),
+            Self::True => CodeRequirementEvaluation::Satisfied,
+            Self::Identifier(value) => self.check(
+                context.identifier.as_deref() == Some(value.as_ref()),
+                || format!("identifier is {:?}", context.identifier),
+            ),
+            Self::AnchorApple => self.check(
+                context
+                    .certificates
+                    .last()
+                    .map(|cert| cert.is_apple_root_ca())
+                    .unwrap_or(false)
+                    && context
+                        .certificates
+                        .first()
+                        .and_then(|cert| cert.subject_name().iter_organization().next())
+                        .and_then(|atv| atv.to_string().ok())
+                        .as_deref()
+                        == Some("Apple Inc."),
+                || "code is not signed by Apple".into(),
+            ),
+            Self::AnchorCertificateHash(slot, digest) => {
+                match self.certificate_at_slot(context, *slot) {
+                    Ok(cert) => {
+                        let actual = DigestType::Sha1.digest_data(cert.constructed_data())?;
+
+                        self.check(actual == digest.as_ref(), || {
+                            format!("certificate has SHA-1 digest {}", hex::encode(&actual))
+                        })
+                    }
+                    Err(res) => res,
+                }
+            }
+            Self::InfoKeyValueLegacy(key, value) => {
+                let actual = context
+                    .info_plist
+                    .as_ref()
+                    .and_then(|plist| plist.get(key.as_ref()))
+                    .and_then(|v| v.as_string());
+
+                self.check(actual == Some(value.as_ref()), || {
+                    format!("Info.plist value is {:?}", actual)
+                })
+            }
+            Self::And(a, b) => match a.evaluate(context)? {
+                CodeRequirementEvaluation::Satisfied => b.evaluate(context)?,
+                res => res,
+            },
+            Self::Or(a, b) => match a.evaluate(context)? {
+                CodeRequirementEvaluation::Satisfied => CodeRequirementEvaluation::Satisfied,
+                a => match b.evaluate(context)? {
+                    CodeRequirementEvaluation::Satisfied => CodeRequirementEvaluation::Satisfied,
+                    b => self.check(false, || {
+                        format!("neither alternative is satisfied ({}; {})", a, b)
+                    }),
+                },
+            },
+            Self::CodeDirectoryHash(digest) => self.check(
+                context
+                    .code_directory_hashes
+                    .iter()
+                    .any(|cdhash| cdhash.starts_with(digest) && !digest.is_empty()),
+                || "no code directory has this hash".into(),
+            ),
+            Self::Not(expr) => self.check(!expr.evaluate(context)?.is_satisfied(), || {
+                "negated expression is satisfied".into()
+            }),
+            Self::InfoPlistKeyField(key, expr) => {
+                let value = context
+                    .info_plist
+                    .as_ref()
+                    .and_then(|plist| plist.get(key.as_ref()))
+                    .and_then(plist_value_to_requirement_value);
+
+                self.check(expr.matches(value.as_ref())?, || {
+                    format!("Info.plist value is {:?}", value)
+                })
+            }
+            Self::CertificateField(slot, field, expr) => {
+                match self.certificate_at_slot(context, *slot) {
+                    Ok(cert) => {
+                        let value = certificate_field_value(cert, field);
+
+                        self.check(expr.matches(value.as_ref())?, || {
+                            format!("certificate field value is {:?}", value)
+                        })
+                    }
+                    Err(res) => res,
+                }
+            }
+            Self::CertificateTrusted(slot) => match self.certificate_at_slot(context, *slot) {
+                Ok(cert) => self.check(KnownCertificate::all().contains(&cert), || {
+                    "certificate is not a known trusted certificate".into()
+                }),
+                Err(res) => res,
+            },
+            Self::AnchorTrusted => self.check(
+                context
+                    .certificates
+                    .last()
+                    .map(|cert| KnownCertificate::all().contains(&cert))
+                    .unwrap_or(false),
+                || "anchor is not a known trusted certificate".into(),
+            ),
+            Self::CertificateGeneric(slot, oid, expr) => match self
+                .certificate_at_slot(context, *slot)
+            {
+                Ok(cert) => {
+                    let value = cert
+                        .iter_extensions()
+                        .find(|extension| extension.id.as_ref() == oid.as_ref())
+                        .map(|extension| {
+                            CodeRequirementValue::Bytes(extension.value.to_bytes().to_vec().into())
+                        });
+
+                    self.check(expr.matches(value.as_ref())?, || {
+                        format!("certificate extension value is {:?}", value)
+                    })
+                }
+                Err(res) => res,
+            },
+            Self::AnchorAppleGeneric => self.check(
+                context
+                    .certificates
+                    .first()
+                    .map(|cert| cert.chains_to_apple_root_ca())
+                    .unwrap_or(false),
+                || "certificate chain does not lead to an Apple root CA".into(),
+            ),
+            Self::EntitlementsKey(key, expr) => {
+                let value = context
+                    .entitlements
+                    .as_ref()
+                    .and_then(|plist| plist.get(key.as_ref()))
+                    .and_then(plist_value_to_requirement_value);
+
+                self.check(expr.matches(value.as_ref())?, || {
+                    format!("entitlement value is {:?}", value)
+                })
+            }
+            Self::CertificatePolicy(slot, oid, expr) => {
+                match self.certificate_at_slot(context, *slot) {
+                    Ok(cert) => {
+                        // Policy OIDs are DER encoded within the certificate policies extension.
+                        // So look for the encoded OID within the extension value.
+                        let mut needle = vec![0x06, oid.as_ref().len() as u8];
+                        needle.extend_from_slice(oid.as_ref());
+
+                        let value = cert
+                            .iter_extensions()
+                            .filter(|extension| {
+                                extension.id.as_ref() == OID_CERTIFICATE_POLICIES.as_ref()
+                            })
+                            .find(|extension| {
+                                extension
+                                    .value
+                                    .to_bytes()
+                                    .windows(needle.len())
+                                    .any(|window| window == needle)
+                            })
+                            .map(|_| CodeRequirementValue::Bytes(oid.as_ref().to_vec().into()));
+
+                        self.check(expr.matches(value.as_ref())?, || {
+                            "certificate policy not present".into()
+                        })
+                    }
+                    Err(res) => res,
+                }
+            }
+            Self::NamedAnchor(_) | Self::NamedCode(_) | Self::LegacyDeveloperId => {
+                self.check(false, || "expression type not supported".into())
+            }
+            Self::Platform(platform) => self.check(context.platform == Some(*platform), || {
+                format!("platform is {:?}", context.platform)
+            }),
+            Self::Notarized => self.check(context.notarized, || "not notarized".into()),
+            Self::CertificateFieldDate(slot, oid, expr) => match self
+                .certificate_at_slot(context, *slot)
+            {
+                Ok(cert) => {
+                    let value = cert
+                        .iter_extensions()
+                        .find(|extension| extension.id.as_ref() == oid.as_ref())
+                        .map(|extension| certificate_extension_date(&extension.value.to_bytes()))
+                        .transpose()?;
+
+                    self.check(expr.matches_date(value.as_ref())?, || {
+                        format!("certificate extension date is {:?}", value)
+                    })
+                }
+                Err(res) => res,
+            },
+        })
+    }
+
+    fn check(&self, ok: bool, reason: impl FnOnce() -> String) -> CodeRequirementEvaluation {
+        if ok {
+            CodeRequirementEvaluation::Satisfied
+        } else {
+            CodeRequirementEvaluation::Unsatisfied {
+                clause: self.to_string(),
+                reason: reason(),
+            }
+        }
+    }
+
+    fn certificate_at_slot<'c>(
+        &self,
+        context: &'c CodeRequirementContext,
+        slot: i32,
+    ) -> Result<&'c CapturedX509Certificate, CodeRequirementEvaluation> {
+        context
+            .certificate_at_slot(slot)
+            .ok_or_else(|| CodeRequirementEvaluation::Unsatisfied {
+                clause: self.to_string(),
+                reason: format!("no certificate in slot {}", format_certificate_slot(slot)),
+            })
+    }
+}
+
+impl<'a> CodeRequirementMatchExpression<'a> {
+    /// Whether a value satisfies this match expression.
+    ///
+    /// `None` represents the absence of a value.
+    ///
+    /// Comparisons are performed on the raw bytes of values. Values don't carry
+    /// dates, so timestamp operators result in an error.
+    pub fn matches(
+        &self,
+        value: Option<&CodeRequirementValue>,
+    ) -> Result<bool, AppleCodesignError> {
+        let value = value.map(|v| v.as_bytes());
+
+        Ok(match (self, value) {
+            (
+                Self::On(_)
+                | Self::Before(_)
+                | Self::After(_)
+                | Self::OnOrBefore(_)
+                | Self::OnOrAfter(_),
+                _,
+            ) => {
+                return Err(AppleCodesignError::RequirementEvaluationUnsupported(
+                    "timestamp comparison of non-date value",
+                ))
+            }
+            (Self::Exists, value) => value.is_some(),
+            (Self::Absent, value) => value.is_none(),
+            (_, None) => false,
+            (Self::Equal(wanted), Some(value)) => value == wanted.as_bytes(),
+            (Self::Contains(wanted), Some(value)) => {
+                let wanted = wanted.as_bytes();
+                wanted.is_empty() || value.windows(wanted.len()).any(|w| w == wanted)
+            }
+            (Self::BeginsWith(wanted), Some(value)) => value.starts_with(wanted.as_bytes()),
+            (Self::EndsWith(wanted), Some(value)) => value.ends_with(wanted.as_bytes()),
+            (Self::LessThan(wanted), Some(value)) => value < wanted.as_bytes(),
+            (Self::GreaterThan(wanted), Some(value)) => value > wanted.as_bytes(),
+            (Self::LessThanEqual(wanted), Some(value)) => value <= wanted.as_bytes(),
+            (Self::GreaterThanEqual(wanted), Some(value)) => value >= wanted.as_bytes(),
+        })
+    }
+
+    /// Whether a date satisfies this match expression.
+    ///
+    /// `None` represents the absence of a value. Only existence and timestamp
+    /// operators can be applied to dates. Other operators result in an error.
+    pub fn matches_date(
+        &self,
+        value: Option<&chrono::DateTime<chrono::Utc>>,
+    ) -> Result<bool, AppleCodesignError> {
+        Ok(match (self, value) {
+            (Self::Exists, value) => value.is_some(),
+            (Self::Absent, value) => value.is_none(),
+            (
+                Self::Equal(_)
+                | Self::Contains(_)
+                | Self::BeginsWith(_)
+                | Self::EndsWith(_)
+                | Self::LessThan(_)
+                | Self::GreaterThan(_)
+                | Self::LessThanEqual(_)
+                | Self::GreaterThanEqual(_),
+                _,
+            ) => {
+                return Err(AppleCodesignError::RequirementEvaluationUnsupported(
+                    "non-timestamp comparison of date value",
+                ))
+            }
+            (_, None) => false,
+            (Self::On(wanted), Some(value)) => value == wanted,
+            (Self::Before(wanted), Some(value)) => value < wanted,
+            (Self::After(wanted), Some(value)) => value > wanted,
+            (Self::OnOrBefore(wanted), Some(value)) => value <= wanted,
+            (Self::OnOrAfter(wanted), Some(value)) => value >= wanted,
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -2012,4 +2558,218 @@ mod test {
         assert!(data.is_empty());
         verify_roundtrip(&els, &source);
     }
+
+    #[test]
+    fn evaluate_identifier() {
+        let context = CodeRequirementContext {
+            identifier: Some("foo.bar".into()),
+            ..Default::default()
+        };
+
+        assert!(CodeRequirementExpression::Identifier("foo.bar".into())
+            .evaluate(&context)
+            .unwrap()
+            .is_satisfied());
+
+        let expr = CodeRequirementExpression::Identifier("foo.baz".into());
+        assert_eq!(
+            expr.evaluate(&context).unwrap(),
+            CodeRequirementEvaluation::Unsatisfied {
+                clause: "identifier \"foo.baz\"".into(),
+                reason: "identifier is Some(\"foo.bar\")".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn evaluate_logical() {
+        let context = CodeRequirementContext {
+            identifier: Some("foo.bar".into()),
+            ..Default::default()
+        };
+
+        let satisfied = CodeRequirementExpression::Identifier("foo.bar".into());
+        let unsatisfied = CodeRequirementExpression::Identifier("foo.baz".into());
+
+        let expr = CodeRequirementExpression::And(
+            Box::new(satisfied.clone()),
+            Box::new(unsatisfied.clone()),
+        );
+        assert!(matches!(
+            expr.evaluate(&context).unwrap(),
+            CodeRequirementEvaluation::Unsatisfied { clause, .. } if clause == unsatisfied.to_string()
+        ));
+
+        let expr = CodeRequirementExpression::Or(
+            Box::new(unsatisfied.clone()),
+            Box::new(satisfied.clone()),
+        );
+        assert!(expr.evaluate(&context).unwrap().is_satisfied());
+
+        let expr = CodeRequirementExpression::Not(Box::new(unsatisfied));
+        assert!(expr.evaluate(&context).unwrap().is_satisfied());
+
+        let expr = CodeRequirementExpression::Not(Box::new(satisfied));
+        assert!(!expr.evaluate(&context).unwrap().is_satisfied());
+    }
+
+    #[test]
+    fn evaluate_logical_short_circuits() {
+        let mut info_plist = plist::Dictionary::new();
+        info_plist.insert("CFBundleName".into(), plist::Value::String("MyApp".into()));
+
+        let context = CodeRequirementContext {
+            identifier: Some("foo.bar".into()),
+            info_plist: Some(info_plist),
+            ..Default::default()
+        };
+
+        let satisfied = CodeRequirementExpression::Identifier("foo.bar".into());
+        let unsatisfied = CodeRequirementExpression::Identifier("foo.baz".into());
+        // Strings can't be compared against timestamps.
+        let error = CodeRequirementExpression::InfoPlistKeyField(
+            "CFBundleName".into(),
+            CodeRequirementMatchExpression::Before(chrono::Utc.timestamp(1616890416, 0)),
+        );
+        assert!(error.evaluate(&context).is_err());
+
+        let expr =
+            CodeRequirementExpression::Or(Box::new(satisfied.clone()), Box::new(error.clone()));
+        assert!(expr.evaluate(&context).unwrap().is_satisfied());
+
+        let expr =
+            CodeRequirementExpression::Or(Box::new(unsatisfied.clone()), Box::new(error.clone()));
+        assert!(expr.evaluate(&context).is_err());
+
+        let expr = CodeRequirementExpression::And(Box::new(unsatisfied), Box::new(error.clone()));
+        assert!(!expr.evaluate(&context).unwrap().is_satisfied());
+
+        let expr = CodeRequirementExpression::And(Box::new(satisfied), Box::new(error));
+        assert!(expr.evaluate(&context).is_err());
+    }
+
+    #[test]
+    fn evaluate_code_directory_hash() {
+        let cdhash =
+            hex::decode("deadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeef")
+                .unwrap();
+
+        let context = CodeRequirementContext {
+            code_directory_hashes: vec![cdhash.clone()],
+            ..Default::default()
+        };
+
+        assert!(
+            CodeRequirementExpression::CodeDirectoryHash(cdhash[0..20].to_vec().into())
+                .evaluate(&context)
+                .unwrap()
+                .is_satisfied()
+        );
+        assert!(
+            !CodeRequirementExpression::CodeDirectoryHash(vec![0u8; 20].into())
+                .evaluate(&context)
+                .unwrap()
+                .is_satisfied()
+        );
+    }
+
+    #[test]
+    fn evaluate_info_plist() {
+        let mut info_plist = plist::Dictionary::new();
+        info_plist.insert("CFBundleName".into(), plist::Value::String("MyApp".into()));
+
+        let context = CodeRequirementContext {
+            info_plist: Some(info_plist),
+            ..Default::default()
+        };
+
+        let eval = |key: &str, expr: CodeRequirementMatchExpression| {
+            CodeRequirementExpression::InfoPlistKeyField(key.into(), expr)
+                .evaluate(&context)
+                .unwrap()
+                .is_satisfied()
+        };
+
+        assert!(eval("CFBundleName", CodeRequirementMatchExpression::Exists));
+        assert!(!eval(
+            "CFBundleName",
+            CodeRequirementMatchExpression::Absent
+        ));
+        assert!(eval("Missing", CodeRequirementMatchExpression::Absent));
+        assert!(eval(
+            "CFBundleName",
+            CodeRequirementMatchExpression::Equal("MyApp".into())
+        ));
+        assert!(eval(
+            "CFBundleName",
+            CodeRequirementMatchExpression::BeginsWith("My".into())
+        ));
+        assert!(eval(
+            "CFBundleName",
+            CodeRequirementMatchExpression::Contains("yA".into())
+        ));
+        assert!(!eval(
+            "CFBundleName",
+            CodeRequirementMatchExpression::EndsWith("My".into())
+        ));
+        assert!(!eval(
+            "Missing",
+            CodeRequirementMatchExpression::Equal("MyApp".into())
+        ));
+    }
+
+    #[test]
+    fn evaluate_match_dates() {
+        let when = chrono::Utc.timestamp(1616890416, 0);
+        let earlier = chrono::Utc.timestamp(1616890415, 0);
+        let later = chrono::Utc.timestamp(1616890417, 0);
+
+        let eval = |expr: CodeRequirementMatchExpression| expr.matches_date(Some(&when)).unwrap();
+
+        assert!(eval(CodeRequirementMatchExpression::Exists));
+        assert!(!eval(CodeRequirementMatchExpression::Absent));
+        assert!(eval(CodeRequirementMatchExpression::On(when)));
+        assert!(!eval(CodeRequirementMatchExpression::On(later)));
+        assert!(eval(CodeRequirementMatchExpression::Before(later)));
+        assert!(!eval(CodeRequirementMatchExpression::Before(when)));
+        assert!(eval(CodeRequirementMatchExpression::After(earlier)));
+        assert!(!eval(CodeRequirementMatchExpression::After(when)));
+        assert!(eval(CodeRequirementMatchExpression::OnOrBefore(when)));
+        assert!(!eval(CodeRequirementMatchExpression::OnOrBefore(earlier)));
+        assert!(eval(CodeRequirementMatchExpression::OnOrAfter(when)));
+        assert!(!eval(CodeRequirementMatchExpression::OnOrAfter(later)));
+
+        assert!(!CodeRequirementMatchExpression::Before(later)
+            .matches_date(None)
+            .unwrap());
+        assert!(CodeRequirementMatchExpression::Equal("foo".into())
+            .matches_date(Some(&when))
+            .is_err());
+
+        // Non-date values can't be compared against timestamps.
+        assert!(CodeRequirementMatchExpression::Before(later)
+            .matches(Some(&"foo".into()))
+            .is_err());
+    }
+
+    #[test]
+    fn certificate_extension_dates() {
+        // GeneralizedTime.
+        let mut data = vec![0x18, 15];
+        data.extend_from_slice(b"20210328001336Z");
+        assert_eq!(
+            certificate_extension_date(&data).unwrap(),
+            chrono::Utc.timestamp(1616890416, 0)
+        );
+
+        // UTCTime.
+        let mut data = vec![0x17, 13];
+        data.extend_from_slice(b"210328001336Z");
+        assert_eq!(
+            certificate_extension_date(&data).unwrap(),
+            chrono::Utc.timestamp(1616890416, 0)
+        );
+
+        assert!(certificate_extension_date(&[0x04, 0x01, 0x00]).is_err());
+    }
 }
@@ -33,7 +33,7 @@ use {
 };
 
 #[derive(Clone, PartialEq)]
-enum FilesValue {
+pub(crate) enum FilesValue {
     Required(Vec<u8>),
     Optional(Vec<u8>),
 }
@@ -141,13 +141,13 @@ impl From<&FilesValue> for Value {
 }
 
 #[derive(Clone, PartialEq)]
-struct Files2Value {
-    cdhash: Option<Vec<u8>>,
-    hash: Option<Vec<u8>>,
-    hash2: Option<Vec<u8>>,
-    optional: Option<bool>,
-    requirement: Option<String>,
-    symlink: Option<String>,
+pub(crate) struct Files2Value {
+    pub(crate) cdhash: Option<Vec<u8>>,
+    pub(crate) hash: Option<Vec<u8>>,
+    pub(crate) hash2: Option<Vec<u8>>,
+    pub(crate) optional: Option<bool>,
+    pub(crate) requirement: Option<String>,
+    pub(crate) symlink: Option<String>,
 }
 
 impl std::fmt::Debug for Files2Value {
@@ -745,6 +745,16 @@ impl CodeResources {
         Ok(())
     }
 
+    /// Obtain the sealed entries in the `<files>` section.
+    pub(crate) fn files(&self) -> &BTreeMap<String, FilesValue> {
+        &self.files
+    }
+
+    /// Obtain the sealed entries in the `<files2>` section.
+    pub(crate) fn files2(&self) -> &BTreeMap<String, Files2Value> {
+        &self.files2
+    }
+
     /// Add a rule to this instance in the `<rules>` section.
     pub fn add_rule(&mut self, rule: CodeResourcesRule) {
         self.rules.insert(
@@ -143,6 +143,9 @@ pub enum AppleCodesignError {
     #[error("code requirement data malformed: {0}")]
     RequirementMalformed(&'static str),
 
+    #[error("code requirement evaluation not supported: {0}")]
+    RequirementEvaluationUnsupported(&'static str),
+
     #[error("plist error in code resources: {0}")]
     ResourcesPlist(plist::Error),
 
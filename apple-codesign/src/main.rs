@@ -72,6 +72,7 @@ use {
         macho::{find_macho_targeting, find_signature_data, AppleSignable},
         reader::SignatureReader,
         remote_signing::{
+            relay::RelayServer,
             session_negotiation::{
                 create_session_joiner, PublicKeyInitiator, SessionInitiatePeer, SessionJoinState,
                 SharedSecretInitiator,
@@ -274,6 +275,21 @@ quoted. The differences, however, should not matter to the parser or result
 in a different binary serialization.
 ";
 
+const REMOTE_SIGNING_RELAY_ABOUT: &str = "\
+Run a remote signing relay server.
+
+Remote signing peers communicate through a relay server. By default, they
+use a public relay operated by the maintainer of this project. This command
+runs a relay speaking the same protocol, allowing you to keep signing traffic
+on infrastructure you control.
+
+The relay speaks plain ws://. Put it behind a TLS terminating reverse proxy
+if wss:// is desired. Point clients at the relay via --remote-signing-url.
+
+Messages between peers are end-to-end encrypted and the relay is unable to
+read them.
+";
+
 const SIGN_ABOUT: &str = "\
 Adds code signatures to a signable entity.
 
@@ -2068,6 +2084,28 @@ fn command_remote_sign(args: &ArgMatches) -> Result<(), AppleCodesignError> {
     Ok(())
 }
 
+fn command_remote_signing_relay(args: &ArgMatches) -> Result<(), AppleCodesignError> {
+    let listen = args
+        .value_of("listen")
+        .expect("clap should have validated argument");
+    let max_session_ttl = u64::from_str(
+        args.value_of("max_session_ttl")
+            .expect("clap should have validated argument"),
+    )
+    .map_err(|_| AppleCodesignError::CliBadArgument)?;
+
+    let mut server = RelayServer::bind(listen)?;
+    server.set_max_session_ttl(max_session_ttl);
+
+    if let Some(motd) = args.value_of("motd") {
+        server.set_motd(motd);
+    }
+
+    server.run()?;
+
+    Ok(())
+}
+
 fn command_sign(args: &ArgMatches) -> Result<(), AppleCodesignError> {
     let mut settings = SigningSettings::default();
 
@@ -2409,9 +2447,18 @@ fn command_verify(args: &ArgMatches) -> Result<(), AppleCodesignError> {
         .value_of("path")
         .ok_or(AppleCodesignError::CliBadArgument)?;
 
-    let data = std::fs::read(path)?;
+    let mut problems = verify::verify_path(path);
 
-    let problems = verify::verify_macho_data(&data);
+    if let Some(requirements_path) = args.value_of("code_requirements_path") {
+        let code_requirements_data = std::fs::read(requirements_path)?;
+        let reqs = CodeRequirements::parse_blob(&code_requirements_data)?.0;
+
+        for expr in reqs.iter() {
+            problems.extend(verify::verify_path_code_requirement(path, Some(expr)));
+        }
+    } else if args.is_present("check_designated_requirement") {
+        problems.extend(verify::verify_path_code_requirement(path, None));
+    }
 
     for problem in &problems {
         println!("{}", problem);
@@ -2804,6 +2851,32 @@ fn main_impl() -> Result<(), AppleCodesignError> {
             ),
     ));
 
+    let app = app.subcommand(
+        Command::new("remote-signing-relay")
+            .about("Run a remote signing relay server")
+            .long_about(REMOTE_SIGNING_RELAY_ABOUT)
+            .arg(
+                Arg::new("listen")
+                    .long("listen")
+                    .takes_value(true)
+                    .default_value("127.0.0.1:8080")
+                    .help("Address and port to listen on"),
+            )
+            .arg(
+                Arg::new("max_session_ttl")
+                    .long("max-session-ttl")
+                    .takes_value(true)
+                    .default_value("3600")
+                    .help("Maximum lifetime of a signing session, in seconds"),
+            )
+            .arg(
+                Arg::new("motd")
+                    .long("motd")
+                    .takes_value(true)
+                    .help("Message of the day to send to connecting clients"),
+            ),
+    );
+
     let app = app
         .subcommand(
             add_certificate_source_args(Command::new("sign")
@@ -2942,7 +3015,18 @@ fn main_impl() -> Result<(), AppleCodesignError> {
             .arg(
                 Arg::new("path")
                     .required(true)
-                    .help("Path of Mach-O binary to examine"),
+                    .help("Path of Mach-O binary, bundle, DMG, or XAR to examine"),
+            )
+            .arg(
+                Arg::new("check_designated_requirement")
+                    .long("check-designated-requirement")
+                    .help("Evaluate the entity's designated requirement against its signature"),
+            )
+            .arg(
+                Arg::new("code_requirements_path")
+                    .long("code-requirements-path")
+                    .takes_value(true)
+                    .help("Path to a file containing binary code requirements data to evaluate against the entity's signature"),
             ),
     );
 
@@ -3001,6 +3085,7 @@ fn main_impl() -> Result<(), AppleCodesignError> {
         }
         Some(("print-signature-info", args)) => command_print_signature_info(args),
         Some(("remote-sign", args)) => command_remote_sign(args),
+        Some(("remote-signing-relay", args)) => command_remote_signing_relay(args),
         Some(("sign", args)) => command_sign(args),
         Some(("smartcard-generate-key", args)) => command_smartcard_generate_key(args),
         Some(("smartcard-import", args)) => command_smartcard_import(args),
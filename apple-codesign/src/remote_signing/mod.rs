@@ -4,6 +4,7 @@
 
 //! Remote signing support.
 
+pub mod relay;
 pub mod session_negotiation;
 
 use {
@@ -87,11 +88,14 @@ pub enum RemoteSignError {
     #[error("websocket error: {0}")]
     Websocket(#[from] tungstenite::Error),
 
+    #[error("websocket handshake error: {0}")]
+    WebsocketHandshake(String),
+
     #[error("X.509 certificate handler error: {0}")]
     X509(#[from] X509CertificateError),
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "kebab-case")]
 enum ApiMethod {
     Hello,
@@ -135,7 +139,7 @@ enum ClientPayload {
     },
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "kebab-case")]
 enum ServerMessageType {
     Error,
@@ -148,7 +152,7 @@ enum ServerMessageType {
 }
 
 /// Websocket message sent from server to client.
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 struct ServerMessage {
     /// ID of request responsible for this message.
     request_id: Option<String>,
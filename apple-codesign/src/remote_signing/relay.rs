@@ -0,0 +1,686 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Remote signing relay server.
+//!
+//! The relay server brokers websocket connections between a signing initiator
+//! and a signer. It speaks the same protocol as the default public relay server
+//! ([super::DEFAULT_SERVER_URL]), so it can be run on internal infrastructure
+//! to avoid sending signing traffic through a third party.
+//!
+//! The relay never sees plaintext signing requests: peer messages are encrypted
+//! with keys negotiated between the peers and are forwarded as opaque strings.
+//!
+//! The server speaks plain `ws://`. If you need `wss://`, run it behind a
+//! TLS terminating reverse proxy.
+
+use {
+    super::{ApiMethod, RemoteSignError, ServerMessage, ServerMessageType},
+    log::{info, warn},
+    serde::Deserialize,
+    std::{
+        collections::HashMap,
+        net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            mpsc::{channel, Receiver, Sender},
+            Arc, Mutex,
+        },
+        time::{Duration, Instant},
+    },
+    tungstenite::protocol::{Message, WebSocket},
+};
+
+/// API methods advertised to clients in the greeting message.
+const SUPPORTED_APIS: [&str; 5] = [
+    "hello",
+    "create-session",
+    "join-session",
+    "send-message",
+    "goodbye",
+];
+
+/// Default maximum lifetime of a signing session, in seconds.
+pub const DEFAULT_MAX_SESSION_TTL: u64 = 3600;
+
+/// How long connection handlers block reading before servicing outbound messages.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+type ConnectionId = u64;
+
+/// A websocket message sent from a client to the server.
+#[derive(Clone, Debug, Deserialize)]
+struct ClientRequest {
+    request_id: String,
+    api: ApiMethod,
+    payload: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct CreateSessionRequest {
+    session_id: String,
+    ttl: u64,
+    context: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct JoinSessionRequest {
+    session_id: String,
+    context: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct SendMessageRequest {
+    session_id: String,
+    message: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct GoodbyeRequest {
+    session_id: String,
+    reason: Option<String>,
+}
+
+/// A signing session being brokered by the relay.
+struct Session {
+    initiator: ConnectionId,
+    initiator_context: Option<String>,
+    signer: Option<ConnectionId>,
+    expires: Instant,
+}
+
+impl Session {
+    fn ttl(&self) -> u64 {
+        self.expires
+            .saturating_duration_since(Instant::now())
+            .as_secs()
+    }
+
+    /// Resolve the other participant in this session.
+    fn peer_of(&self, connection: ConnectionId) -> Option<Option<ConnectionId>> {
+        if connection == self.initiator {
+            Some(self.signer)
+        } else if Some(connection) == self.signer {
+            Some(Some(self.initiator))
+        } else {
+            None
+        }
+    }
+}
+
+/// Mutable state shared between all connections.
+#[derive(Default)]
+struct RelayState {
+    connections: HashMap<ConnectionId, Sender<String>>,
+    sessions: HashMap<String, Session>,
+}
+
+impl RelayState {
+    fn send(&self, connection: ConnectionId, message: ServerMessage) {
+        if let Some(sender) = self.connections.get(&connection) {
+            match serde_json::to_string(&message) {
+                // The receiver going away means the connection is being torn down,
+                // which is handled separately.
+                Ok(body) => {
+                    sender.send(body).ok();
+                }
+                Err(e) => {
+                    warn!("error serializing server message: {}", e);
+                }
+            }
+        }
+    }
+
+    fn send_error(
+        &self,
+        connection: ConnectionId,
+        request_id: Option<String>,
+        code: &str,
+        message: impl ToString,
+    ) {
+        self.send(
+            connection,
+            ServerMessage {
+                request_id,
+                typ: ServerMessageType::Error,
+                ttl: None,
+                payload: Some(serde_json::json!({
+                    "code": code,
+                    "message": message.to_string(),
+                })),
+            },
+        );
+    }
+
+    fn close_session(
+        &mut self,
+        session_id: &str,
+        reason: &str,
+        request_id: Option<(ConnectionId, String)>,
+    ) {
+        if let Some(session) = self.sessions.remove(session_id) {
+            info!("closing session {}: {}", session_id, reason);
+
+            for connection in std::iter::once(session.initiator).chain(session.signer) {
+                let request_id = match &request_id {
+                    Some((requester, request_id)) if *requester == connection => {
+                        Some(request_id.clone())
+                    }
+                    _ => None,
+                };
+
+                self.send(
+                    connection,
+                    ServerMessage {
+                        request_id,
+                        typ: ServerMessageType::SessionClosed,
+                        ttl: None,
+                        payload: Some(serde_json::json!({ "reason": reason })),
+                    },
+                );
+            }
+        }
+    }
+
+    fn expire_sessions(&mut self) {
+        let now = Instant::now();
+
+        let expired = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| session.expires <= now)
+            .map(|(id, _)| id.clone())
+            .collect::<Vec<_>>();
+
+        for session_id in expired {
+            self.close_session(&session_id, "session expired", None);
+        }
+    }
+
+    /// Unregister a connection, closing any sessions it participates in.
+    fn remove_connection(&mut self, connection: ConnectionId) {
+        let sessions = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| session.peer_of(connection).is_some())
+            .map(|(id, _)| id.clone())
+            .collect::<Vec<_>>();
+
+        self.connections.remove(&connection);
+
+        for session_id in sessions {
+            self.close_session(&session_id, "peer disconnected", None);
+        }
+    }
+
+    fn handle_text(&mut self, connection: ConnectionId, text: &str, config: &RelayConfig) {
+        self.expire_sessions();
+
+        let request = match serde_json::from_str::<ClientRequest>(text) {
+            Ok(request) => request,
+            Err(e) => {
+                self.send_error(connection, None, "bad-request", e);
+                return;
+            }
+        };
+
+        if let Err(e) = self.handle_request(connection, &request, config) {
+            self.send_error(connection, Some(request.request_id), "bad-request", e);
+        }
+    }
+
+    fn handle_request(
+        &mut self,
+        connection: ConnectionId,
+        request: &ClientRequest,
+        config: &RelayConfig,
+    ) -> Result<(), serde_json::Error> {
+        let request_id = Some(request.request_id.clone());
+        let payload = request.payload.clone().unwrap_or(serde_json::Value::Null);
+
+        match request.api {
+            ApiMethod::Hello => {
+                self.send(
+                    connection,
+                    ServerMessage {
+                        request_id,
+                        typ: ServerMessageType::Greeting,
+                        ttl: None,
+                        payload: Some(serde_json::json!({
+                            "apis": SUPPORTED_APIS,
+                            "motd": config.motd,
+                        })),
+                    },
+                );
+            }
+            ApiMethod::CreateSession => {
+                let payload = serde_json::from_value::<CreateSessionRequest>(payload)?;
+
+                if self.sessions.contains_key(&payload.session_id) {
+                    self.send_error(
+                        connection,
+                        request_id,
+                        "session-exists",
+                        "a session with the requested ID already exists",
+                    );
+                    return Ok(());
+                }
+
+                let ttl = payload.ttl.min(config.max_session_ttl);
+                let session = Session {
+                    initiator: connection,
+                    initiator_context: payload.context,
+                    signer: None,
+                    expires: Instant::now() + Duration::from_secs(ttl),
+                };
+
+                info!("created session {} with TTL {}s", payload.session_id, ttl);
+
+                self.send(
+                    connection,
+                    ServerMessage {
+                        request_id,
+                        typ: ServerMessageType::SessionCreated,
+                        ttl: Some(session.ttl()),
+                        payload: None,
+                    },
+                );
+                self.sessions.insert(payload.session_id, session);
+            }
+            ApiMethod::JoinSession => {
+                let payload = serde_json::from_value::<JoinSessionRequest>(payload)?;
+
+                let session = if let Some(session) = self.sessions.get_mut(&payload.session_id) {
+                    session
+                } else {
+                    self.send_error(
+                        connection,
+                        request_id,
+                        "session-not-found",
+                        "the requested session does not exist",
+                    );
+                    return Ok(());
+                };
+
+                if session.signer.is_some() || session.initiator == connection {
+                    self.send_error(
+                        connection,
+                        request_id,
+                        "session-already-joined",
+                        "the requested session cannot be joined",
+                    );
+                    return Ok(());
+                }
+
+                session.signer = Some(connection);
+                let initiator = session.initiator;
+                let initiator_context = session.initiator_context.clone();
+                let ttl = session.ttl();
+
+                info!("session {} joined", payload.session_id);
+
+                self.send(
+                    connection,
+                    ServerMessage {
+                        request_id,
+                        typ: ServerMessageType::SessionJoined,
+                        ttl: Some(ttl),
+                        payload: Some(serde_json::json!({ "context": initiator_context })),
+                    },
+                );
+                self.send(
+                    initiator,
+                    ServerMessage {
+                        request_id: None,
+                        typ: ServerMessageType::SessionJoined,
+                        ttl: Some(ttl),
+                        payload: Some(serde_json::json!({ "context": payload.context })),
+                    },
+                );
+            }
+            ApiMethod::SendMessage => {
+                let payload = serde_json::from_value::<SendMessageRequest>(payload)?;
+
+                let (peer, ttl) = match self.sessions.get(&payload.session_id) {
+                    Some(session) => (session.peer_of(connection), session.ttl()),
+                    None => (None, 0),
+                };
+
+                let peer = match peer {
+                    Some(Some(peer)) => peer,
+                    Some(None) => {
+                        self.send_error(
+                            connection,
+                            request_id,
+                            "session-not-joined",
+                            "the session has not been joined by a peer",
+                        );
+                        return Ok(());
+                    }
+                    None => {
+                        self.send_error(
+                            connection,
+                            request_id,
+                            "session-not-found",
+                            "the requested session does not exist",
+                        );
+                        return Ok(());
+                    }
+                };
+
+                self.send(
+                    peer,
+                    ServerMessage {
+                        request_id: None,
+                        typ: ServerMessageType::PeerMessage,
+                        ttl: Some(ttl),
+                        payload: Some(serde_json::json!({ "message": payload.message })),
+                    },
+                );
+                self.send(
+                    connection,
+                    ServerMessage {
+                        request_id,
+                        typ: ServerMessageType::MessageSent,
+                        ttl: Some(ttl),
+                        payload: None,
+                    },
+                );
+            }
+            ApiMethod::Goodbye => {
+                let payload = serde_json::from_value::<GoodbyeRequest>(payload)?;
+
+                let is_participant = self
+                    .sessions
+                    .get(&payload.session_id)
+                    .map(|session| session.peer_of(connection).is_some())
+                    .unwrap_or(false);
+
+                if !is_participant {
+                    self.send_error(
+                        connection,
+                        request_id,
+                        "session-not-found",
+                        "the requested session does not exist",
+                    );
+                    return Ok(());
+                }
+
+                let reason = payload
+                    .reason
+                    .unwrap_or_else(|| "peer said goodbye".to_string());
+
+                self.close_session(
+                    &payload.session_id,
+                    &reason,
+                    Some((connection, request.request_id.clone())),
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Settings for a [RelayServer].
+#[derive(Clone, Debug)]
+struct RelayConfig {
+    motd: Option<String>,
+    max_session_ttl: u64,
+}
+
+/// A remote signing relay server.
+///
+/// Instances are bound to a listening socket at construction time. Call
+/// [Self::run] to start accepting connections.
+pub struct RelayServer {
+    listener: TcpListener,
+    config: RelayConfig,
+    state: Arc<Mutex<RelayState>>,
+    next_connection_id: Arc<AtomicU64>,
+}
+
+impl RelayServer {
+    /// Construct a new instance listening on the given address.
+    ///
+    /// Use port 0 to have the operating system choose an available port.
+    pub fn bind(address: impl ToSocketAddrs) -> Result<Self, RemoteSignError> {
+        let listener = TcpListener::bind(address)?;
+
+        Ok(Self {
+            listener,
+            config: RelayConfig {
+                motd: None,
+                max_session_ttl: DEFAULT_MAX_SESSION_TTL,
+            },
+            state: Arc::new(Mutex::new(RelayState::default())),
+            next_connection_id: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// The address the server is listening on.
+    pub fn local_addr(&self) -> Result<SocketAddr, RemoteSignError> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// The `ws://` URL clients can use to connect to this server.
+    pub fn url(&self) -> Result<String, RemoteSignError> {
+        Ok(format!("ws://{}/", self.local_addr()?))
+    }
+
+    /// Set a message of the day to send to connecting clients.
+    pub fn set_motd(&mut self, motd: impl ToString) {
+        self.config.motd = Some(motd.to_string());
+    }
+
+    /// Set the maximum lifetime of signing sessions, in seconds.
+    ///
+    /// Clients requesting a longer lifetime have it capped to this value.
+    pub fn set_max_session_ttl(&mut self, seconds: u64) {
+        self.config.max_session_ttl = seconds;
+    }
+
+    /// Accept and service connections until an error occurs.
+    ///
+    /// Each connection is serviced on its own thread.
+    pub fn run(&self) -> Result<(), RemoteSignError> {
+        warn!("remote signing relay listening on {}", self.url()?);
+
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+
+            let connection = self.next_connection_id.fetch_add(1, Ordering::SeqCst);
+            let state = self.state.clone();
+            let config = self.config.clone();
+
+            std::thread::spawn(move || {
+                let peer = stream
+                    .peer_addr()
+                    .map(|x| x.to_string())
+                    .unwrap_or_else(|_| "(unknown)".to_string());
+                info!("accepted connection {} from {}", connection, peer);
+
+                if let Err(e) = handle_connection(stream, connection, state, config) {
+                    warn!("connection {} from {} failed: {}", connection, peer, e);
+                } else {
+                    info!("connection {} from {} closed", connection, peer);
+                }
+            });
+        }
+
+        Ok(())
+    }
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    connection: ConnectionId,
+    state: Arc<Mutex<RelayState>>,
+    config: RelayConfig,
+) -> Result<(), RemoteSignError> {
+    let mut ws = tungstenite::accept(stream)
+        .map_err(|e| RemoteSignError::WebsocketHandshake(e.to_string()))?;
+
+    // Reads time out periodically so we can deliver messages queued by peers.
+    ws.get_ref().set_read_timeout(Some(POLL_INTERVAL))?;
+
+    let (sender, receiver) = channel();
+    state
+        .lock()
+        .expect("relay state lock poisoned")
+        .connections
+        .insert(connection, sender);
+
+    let res = service_connection(&mut ws, connection, &state, &config, &receiver);
+
+    state
+        .lock()
+        .expect("relay state lock poisoned")
+        .remove_connection(connection);
+
+    res
+}
+
+fn service_connection(
+    ws: &mut WebSocket<TcpStream>,
+    connection: ConnectionId,
+    state: &Mutex<RelayState>,
+    config: &RelayConfig,
+    receiver: &Receiver<String>,
+) -> Result<(), RemoteSignError> {
+    loop {
+        while let Ok(body) = receiver.try_recv() {
+            ws.write_message(Message::Text(body))?;
+        }
+
+        match ws.read_message() {
+            Ok(Message::Text(text)) => {
+                state
+                    .lock()
+                    .expect("relay state lock poisoned")
+                    .handle_text(connection, &text, config);
+            }
+            Ok(Message::Binary(_)) => {
+                state.lock().expect("relay state lock poisoned").send_error(
+                    connection,
+                    None,
+                    "bad-request",
+                    "binary messages not supported",
+                );
+            }
+            Ok(Message::Close(_)) => {
+                return Ok(());
+            }
+            // Pings are answered automatically.
+            Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_)) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                // Flush any pending frames, such as automatic pong replies.
+                match ws.write_pending() {
+                    Ok(()) => {}
+                    Err(tungstenite::Error::Io(e))
+                        if matches!(
+                            e.kind(),
+                            std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                        ) => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                return Ok(());
+            }
+            Err(e) => {
+                return Err(e.into());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            certificate::{create_self_signed_code_signing_certificate, CertificateProfile},
+            cryptography::PrivateKey,
+            remote_signing::{
+                session_negotiation::{
+                    create_session_joiner, SessionInitiatePeer, SessionJoinState,
+                    SharedSecretInitiator,
+                },
+                UnjoinedSigningClient,
+            },
+        },
+        signature::Signer,
+        x509_certificate::KeyAlgorithm,
+    };
+
+    #[test]
+    fn loopback_signing() -> Result<(), Box<dyn std::error::Error>> {
+        let mut server = RelayServer::bind("127.0.0.1:0")?;
+        server.set_motd("loopback test server");
+        let url = server.url()?;
+
+        std::thread::spawn(move || server.run());
+
+        let initiator = SharedSecretInitiator::new(b"secret".to_vec())?;
+        let sjs = initiator.session_join_string_base64()?;
+
+        let signer_url = url.clone();
+        let signer = std::thread::spawn(move || -> Result<(), RemoteSignError> {
+            let (cert, key, _) = create_self_signed_code_signing_certificate(
+                KeyAlgorithm::Ed25519,
+                CertificateProfile::DeveloperIdApplication,
+                "team",
+                "Joe Developer",
+                "US",
+                chrono::Duration::hours(1),
+            )
+            .unwrap();
+
+            // The initiator creates the session concurrently. So retry until it exists.
+            let mut attempts = 0;
+            let client = loop {
+                let mut joiner = create_session_joiner(&sjs)?;
+                joiner.register_state(SessionJoinState::SharedSecret(b"secret".to_vec()))?;
+
+                match UnjoinedSigningClient::new_signer(
+                    joiner,
+                    &key,
+                    cert.clone(),
+                    vec![],
+                    signer_url.clone(),
+                ) {
+                    Ok(client) => break client,
+                    Err(RemoteSignError::ServerError(_)) if attempts < 50 => {
+                        attempts += 1;
+                        std::thread::sleep(Duration::from_millis(100));
+                    }
+                    Err(e) => return Err(e),
+                }
+            };
+
+            client.run()
+        });
+
+        let client = UnjoinedSigningClient::new_initiator(url, Box::new(initiator), None)?;
+
+        // The initiator verifies the signature against the peer's certificate.
+        let message = b"hello, world";
+        let signature = client.try_sign(message)?;
+        client
+            .signing_certificate()
+            .verify_signed_data(message, signature.as_ref())?;
+
+        client.finish()?;
+
+        signer.join().unwrap()?;
+
+        Ok(())
+    }
+}
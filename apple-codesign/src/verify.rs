@@ -5,7 +5,17 @@
 //! Code signing verification.
 //!
 //! This module implements functionality for verifying code signatures on
-//! Mach-O binaries.
+//! Mach-O binaries, bundles, DMGs, and XAR archives (e.g. `.pkg` installers).
+//!
+//! For bundles, we verify the main executable and the digests of files recorded
+//! in the `_CodeSignature/CodeResources` file. Nested bundles are verified
+//! recursively.
+//!
+//! For DMGs, we verify the digests recorded in the code directory against the
+//! DMG content and its `koly` trailer.
+//!
+//! For XAR archives, we verify the table of contents checksum and the RSA and
+//! CMS signatures over it.
 //!
 //! # Verification Caveats
 //!
@@ -23,16 +33,32 @@
 
 use {
     crate::{
+        bundle_signing::SignedMachOInfo,
         code_directory::CodeDirectoryBlob,
         code_hash::compute_code_hashes,
+        code_requirement::{
+            CodeRequirementContext, CodeRequirementEvaluation, CodeRequirementExpression,
+            RequirementType,
+        },
+        code_resources::{CodeResources, FilesValue},
+        dmg::DmgReader,
         embedded_signature::{CodeSigningSlot, DigestType, EmbeddedSignature},
         error::AppleCodesignError,
-        macho::{find_signature_data, AppleSignable},
+        macho::{find_signature_data, iter_macho, AppleSignable},
+        reader::PathType,
     },
+    apple_bundles::DirectoryBundle,
+    apple_xar::reader::XarReader,
     cryptographic_message_syntax::{CmsError, SignedData},
     goblin::mach::{Mach, MachO},
-    std::path::{Path, PathBuf},
-    x509_certificate::{DigestAlgorithm, SignatureAlgorithm},
+    std::{
+        fs::File,
+        io::{Read, Seek},
+        path::{Path, PathBuf},
+    },
+    x509_certificate::{
+        CertificateChainValidator, DigestAlgorithm, SignatureAlgorithm, ValidationProblem,
+    },
 };
 
 /// Context for a verification issue.
@@ -58,6 +84,7 @@ pub enum VerificationProblemType {
     CmsError(CmsError),
     CmsOldDigestAlgorithm(DigestAlgorithm),
     CmsOldSignatureAlgorithm(SignatureAlgorithm),
+    CmsCertificateValidation(ValidationProblem),
     NoCodeDirectory,
     CodeDirectoryOldDigestAlgorithm(DigestType),
     CodeDigestError(AppleCodesignError),
@@ -68,6 +95,23 @@ pub enum VerificationProblemType {
     ExtraSlotDigest(CodeSigningSlot, Vec<u8>),
     SlotDigestMismatch(CodeSigningSlot, Vec<u8>, Vec<u8>),
     SlotDigestError(AppleCodesignError),
+    DmgError(AppleCodesignError),
+    NoDmgSignatureData,
+    XarError(apple_xar::Error),
+    XarTableOfContentsChecksumMismatch,
+    XarRsaSignatureNoCertificates,
+    BundleError(AppleCodesignError),
+    NoCodeResources,
+    CodeResourcesError(AppleCodesignError),
+    ResourceMissing(String),
+    ResourceError(String, AppleCodesignError),
+    ResourceDigestMismatch(String, DigestType, Vec<u8>, Vec<u8>),
+    ResourceSymlinkMismatch(String, String, String),
+    ResourceCodeDirectoryMismatch(String, Vec<u8>, Vec<u8>),
+    NoDesignatedRequirement,
+    RequirementError(AppleCodesignError),
+    RequirementUnsatisfied(CodeRequirementEvaluation),
+    RequirementEvaluationUnsupported,
 }
 
 #[derive(Debug)]
@@ -110,6 +154,9 @@ impl std::fmt::Display for VerificationProblem {
             VerificationProblemType::CmsOldSignatureAlgorithm(alg) => {
                 format!("insecure signature algorithm used: {:?}", alg)
             }
+            VerificationProblemType::CmsCertificateValidation(problem) => {
+                format!("signing certificate validation failed: {}", problem)
+            }
             VerificationProblemType::NoCodeDirectory => "no code directory".to_string(),
             VerificationProblemType::CodeDirectoryOldDigestAlgorithm(hash_type) => {
                 format!(
@@ -163,6 +210,74 @@ impl std::fmt::Display for VerificationProblem {
             VerificationProblemType::SlotDigestError(e) => {
                 format!("error computing slot digest: {:?}", e)
             }
+            VerificationProblemType::DmgError(e) => format!("error reading DMG: {}", e),
+            VerificationProblemType::NoDmgSignatureData => {
+                "DMG signature data not found".to_string()
+            }
+            VerificationProblemType::XarError(e) => format!("error reading XAR: {}", e),
+            VerificationProblemType::XarTableOfContentsChecksumMismatch => {
+                "XAR table of contents checksum does not match recorded checksum".to_string()
+            }
+            VerificationProblemType::XarRsaSignatureNoCertificates => {
+                "XAR RSA signature has no certificates to verify it with".to_string()
+            }
+            VerificationProblemType::BundleError(e) => format!("error reading bundle: {}", e),
+            VerificationProblemType::NoCodeResources => {
+                "bundle does not have a _CodeSignature/CodeResources file".to_string()
+            }
+            VerificationProblemType::CodeResourcesError(e) => {
+                format!("error parsing CodeResources file: {}", e)
+            }
+            VerificationProblemType::ResourceMissing(path) => {
+                format!("sealed resource {} does not exist", path)
+            }
+            VerificationProblemType::ResourceError(path, e) => {
+                format!("error verifying sealed resource {}: {}", path, e)
+            }
+            VerificationProblemType::ResourceDigestMismatch(
+                path,
+                digest_type,
+                recorded_digest,
+                actual_digest,
+            ) => {
+                format!(
+                    "{:?} digest mismatch for sealed resource {}; recorded digest {}, actual {}",
+                    digest_type,
+                    path,
+                    hex::encode(recorded_digest),
+                    hex::encode(actual_digest)
+                )
+            }
+            VerificationProblemType::ResourceSymlinkMismatch(path, recorded, actual) => {
+                format!(
+                    "symlink target mismatch for sealed resource {}; recorded target {}, actual {}",
+                    path, recorded, actual
+                )
+            }
+            VerificationProblemType::ResourceCodeDirectoryMismatch(
+                path,
+                recorded_digest,
+                actual_digest,
+            ) => {
+                format!(
+                    "code directory hash mismatch for sealed resource {}; recorded {}, actual {}",
+                    path,
+                    hex::encode(recorded_digest),
+                    hex::encode(actual_digest)
+                )
+            }
+            VerificationProblemType::NoDesignatedRequirement => {
+                "no designated requirement present".to_string()
+            }
+            VerificationProblemType::RequirementError(e) => {
+                format!("error evaluating code requirement: {}", e)
+            }
+            VerificationProblemType::RequirementUnsatisfied(evaluation) => {
+                format!("code requirement {}", evaluation)
+            }
+            VerificationProblemType::RequirementEvaluationUnsupported => {
+                "code requirement evaluation not supported for this entity".to_string()
+            }
         };
 
         match context {
@@ -172,7 +287,10 @@ impl std::fmt::Display for VerificationProblem {
     }
 }
 
-/// Verifies a binary in a given path.
+/// Verifies an entity in a given path.
+///
+/// The path can be a Mach-O binary, a bundle directory, a DMG, or a XAR
+/// archive. The type of entity is sniffed from the path.
 ///
 /// Returns a vector of problems detected. An empty vector means no
 /// problems were found.
@@ -184,6 +302,18 @@ pub fn verify_path(path: impl AsRef<Path>) -> Vec<VerificationProblem> {
         fat_index: None,
     };
 
+    match PathType::from_path(path) {
+        Ok(PathType::Bundle) => verify_bundle_path_internal(path, context),
+        Ok(PathType::Dmg) => verify_dmg_path_internal(path, context),
+        Ok(PathType::Xar) => verify_xar_path_internal(path, context),
+        Ok(PathType::MachO | PathType::Other) | Err(_) => verify_macho_path_internal(path, context),
+    }
+}
+
+fn verify_macho_path_internal(
+    path: &Path,
+    context: VerificationContext,
+) -> Vec<VerificationProblem> {
     let data = match std::fs::read(path) {
         Ok(data) => data,
         Err(e) => {
@@ -358,6 +488,24 @@ fn verify_cms_signature(data: &[u8], context: VerificationContext) -> Vec<Verifi
         }
     };
 
+    verify_signed_data(&signed_data, context)
+}
+
+fn verify_signed_data(
+    signed_data: &SignedData,
+    context: VerificationContext,
+) -> Vec<VerificationProblem> {
+    verify_signed_data_with_validator(signed_data, &CertificateChainValidator::default(), context)
+}
+
+/// Verify a [SignedData], validating signing certificates with a validator.
+///
+/// The validation time of `validator` is replaced by each signer's signing time, if present.
+fn verify_signed_data_with_validator(
+    signed_data: &SignedData,
+    validator: &CertificateChainValidator,
+    context: VerificationContext,
+) -> Vec<VerificationProblem> {
     let mut problems = vec![];
 
     for signer in signed_data.signers() {
@@ -392,7 +540,7 @@ fn verify_cms_signature(data: &[u8], context: VerificationContext) -> Vec<Verifi
             }
         }
 
-        match signer.verify_signature_with_signed_data(&signed_data) {
+        match signer.verify_signature_with_signed_data(signed_data) {
             Ok(()) => {}
             Err(e) => {
                 problems.push(VerificationProblem {
@@ -402,8 +550,34 @@ fn verify_cms_signature(data: &[u8], context: VerificationContext) -> Vec<Verifi
             }
         }
 
+        // Certificates are validated as of the signing time so signatures made
+        // before a certificate expired remain valid.
+        let mut validator = validator.clone();
+        if let Some(signing_time) = signer
+            .signed_attributes()
+            .and_then(|attrs| attrs.signing_time())
+        {
+            validator.validation_time(*signing_time);
+        }
+
+        match signer.validate_signing_certificate(signed_data, &validator) {
+            Ok(validation_problems) => {
+                problems.extend(validation_problems.into_iter().map(|problem| {
+                    VerificationProblem {
+                        context: context.clone(),
+                        problem: VerificationProblemType::CmsCertificateValidation(problem),
+                    }
+                }));
+            }
+            Err(e) => {
+                problems.push(VerificationProblem {
+                    context: context.clone(),
+                    problem: VerificationProblemType::CmsError(e),
+                });
+            }
+        }
+
         // TODO verify key length meets standards.
-        // TODO verify CA chain is fully present.
         // TODO verify signing cert chains to Apple?
     }
 
@@ -418,13 +592,7 @@ fn verify_code_directory(
 ) -> Vec<VerificationProblem> {
     let mut problems = vec![];
 
-    match cd.digest_type {
-        DigestType::Sha256 | DigestType::Sha384 => {}
-        hash_type => problems.push(VerificationProblem {
-            context: context.clone(),
-            problem: VerificationProblemType::CodeDirectoryOldDigestAlgorithm(hash_type),
-        }),
-    }
+    problems.extend(verify_code_directory_digest_type(cd, context.clone()));
 
     match compute_code_hashes(macho, cd.digest_type, cd.page_size as _) {
         Ok(digests) => {
@@ -477,6 +645,39 @@ fn verify_code_directory(
         }
     }
 
+    problems.extend(verify_slot_digests(signature, cd, &[], context));
+
+    // TODO verify code_limit[_64] is appropriate.
+    // TODO verify exec_seg_base is appropriate.
+
+    problems
+}
+
+fn verify_code_directory_digest_type(
+    cd: &CodeDirectoryBlob,
+    context: VerificationContext,
+) -> Option<VerificationProblem> {
+    match cd.digest_type {
+        DigestType::Sha256 | DigestType::Sha384 => None,
+        hash_type => Some(VerificationProblem {
+            context,
+            problem: VerificationProblemType::CodeDirectoryOldDigestAlgorithm(hash_type),
+        }),
+    }
+}
+
+/// Verify the special/slot digests in a code directory.
+///
+/// `external_slots` defines additional slots whose content lives outside the
+/// signature superblob and is verified by the caller.
+fn verify_slot_digests(
+    signature: &EmbeddedSignature,
+    cd: &CodeDirectoryBlob,
+    external_slots: &[CodeSigningSlot],
+    context: VerificationContext,
+) -> Vec<VerificationProblem> {
+    let mut problems = vec![];
+
     // All slots beneath some threshold should have a special hash.
     // It isn't clear where this threshold is. But the alternate code directory and
     // CMS slots appear to start at 0x1000. We set our limit at 32, which seems
@@ -532,8 +733,8 @@ fn verify_code_directory(
             },
             None => {
                 // Some slots have external provided from somewhere that isn't a blob.
-                if slot.has_external_content() {
-                    // TODO need to validate this external content somewhere.
+                if slot.has_external_content() || external_slots.contains(slot) {
+                    // Callers with access to this content are responsible for verifying it.
                 }
                 // But slots with a null digest (all 0s) exist as placeholders when there
                 // is a higher numbered slot present.
@@ -550,8 +751,1424 @@ fn verify_code_directory(
         }
     }
 
-    // TODO verify code_limit[_64] is appropriate.
-    // TODO verify exec_seg_base is appropriate.
+    problems
+}
+
+/// Verifies a bundle in a given directory.
+///
+/// This verifies the main executable, the resources sealed in the bundle's
+/// `CodeResources` file, and all nested bundles.
+///
+/// Returns a vector of problems detected. An empty vector means no
+/// problems were found.
+pub fn verify_bundle_path(path: impl AsRef<Path>) -> Vec<VerificationProblem> {
+    let path = path.as_ref();
+
+    verify_bundle_path_internal(
+        path,
+        VerificationContext {
+            path: Some(path.to_path_buf()),
+            fat_index: None,
+        },
+    )
+}
+
+fn verify_bundle_path_internal(
+    path: &Path,
+    context: VerificationContext,
+) -> Vec<VerificationProblem> {
+    match DirectoryBundle::new_from_path(path) {
+        Ok(bundle) => verify_bundle_internal(&bundle, context),
+        Err(e) => vec![VerificationProblem {
+            context,
+            problem: VerificationProblemType::BundleError(AppleCodesignError::DirectoryBundle(e)),
+        }],
+    }
+}
+
+/// Resolve the path to the main executable of a bundle, if it has one.
+fn bundle_main_executable_path(
+    bundle: &DirectoryBundle,
+) -> Result<Option<PathBuf>, AppleCodesignError> {
+    let main_exe = bundle
+        .main_executable()
+        .map_err(AppleCodesignError::DirectoryBundle)?;
+
+    Ok(main_exe.map(|main_exe| {
+        if bundle.shallow() {
+            bundle.resolve_path(main_exe)
+        } else {
+            bundle.resolve_path(format!("MacOS/{}", main_exe))
+        }
+    }))
+}
+
+fn verify_bundle_internal(
+    bundle: &DirectoryBundle,
+    context: VerificationContext,
+) -> Vec<VerificationProblem> {
+    let mut problems = vec![];
+
+    // Nested bundles are signed independently. So verify them independently.
+    match bundle.nested_bundles(false) {
+        Ok(nested) => {
+            for (_, nested) in nested {
+                let context = VerificationContext {
+                    path: Some(nested.root_dir().to_path_buf()),
+                    fat_index: None,
+                };
+
+                problems.extend(verify_bundle_internal(&nested, context));
+            }
+        }
+        Err(e) => {
+            problems.push(VerificationProblem {
+                context: context.clone(),
+                problem: VerificationProblemType::BundleError(AppleCodesignError::DirectoryBundle(
+                    e,
+                )),
+            });
+        }
+    }
+
+    let code_resources_data =
+        match std::fs::read(bundle.resolve_path("_CodeSignature/CodeResources")) {
+            Ok(data) => Some(data),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                problems.push(VerificationProblem {
+                    context: context.clone(),
+                    problem: VerificationProblemType::NoCodeResources,
+                });
+                None
+            }
+            Err(e) => {
+                problems.push(VerificationProblem {
+                    context: context.clone(),
+                    problem: VerificationProblemType::IoError(e),
+                });
+                None
+            }
+        };
+
+    match bundle_main_executable_path(bundle) {
+        Ok(Some(exe_path)) => {
+            let context = VerificationContext {
+                path: Some(exe_path.clone()),
+                fat_index: None,
+            };
+
+            match std::fs::read(&exe_path) {
+                Ok(data) => {
+                    problems.extend(verify_macho_data_internal(&data, context.clone()));
+                    problems.extend(verify_bundle_external_slots(
+                        bundle,
+                        &data,
+                        code_resources_data.as_deref(),
+                        context,
+                    ));
+                }
+                Err(e) => {
+                    problems.push(VerificationProblem {
+                        context,
+                        problem: VerificationProblemType::IoError(e),
+                    });
+                }
+            }
+        }
+        // Bundles without a main executable only have their resources sealed.
+        Ok(None) => {}
+        Err(e) => {
+            problems.push(VerificationProblem {
+                context: context.clone(),
+                problem: VerificationProblemType::BundleError(e),
+            });
+        }
+    }
+
+    if let Some(data) = code_resources_data {
+        match CodeResources::from_xml(&data) {
+            Ok(resources) => {
+                problems.extend(verify_code_resources(bundle, &resources, context));
+            }
+            Err(e) => {
+                problems.push(VerificationProblem {
+                    context,
+                    problem: VerificationProblemType::CodeResourcesError(e),
+                });
+            }
+        }
+    }
+
+    problems
+}
+
+/// Verify code directory slots whose content comes from bundle files.
+///
+/// The `Info.plist` and `CodeResources` files are digested into the code directory
+/// of the main executable. This verifies those digests against the files on disk.
+fn verify_bundle_external_slots(
+    bundle: &DirectoryBundle,
+    macho_data: &[u8],
+    code_resources_data: Option<&[u8]>,
+    context: VerificationContext,
+) -> Vec<VerificationProblem> {
+    let mut problems = vec![];
+
+    // Parse and signature errors are reported by Mach-O verification. So ignore them here.
+    let machos = match iter_macho(macho_data) {
+        Ok(machos) => machos,
+        Err(_) => return problems,
+    };
+
+    let is_fat = matches!(Mach::parse(macho_data), Ok(Mach::Fat(_)));
+    let info_plist_data = std::fs::read(bundle.info_plist_path()).ok();
+
+    for (index, (macho, _)) in machos.enumerate() {
+        let mut context = context.clone();
+        if is_fat {
+            context.fat_index = Some(index);
+        }
+
+        let signature = match macho.code_signature() {
+            Ok(Some(signature)) => signature,
+            _ => continue,
+        };
+        let cd = match signature.code_directory() {
+            Ok(Some(cd)) => cd,
+            _ => continue,
+        };
+
+        for (slot, content) in [
+            (CodeSigningSlot::Info, info_plist_data.as_deref()),
+            (CodeSigningSlot::ResourceDir, code_resources_data),
+        ] {
+            let recorded = match cd.slot_digests().get(&slot) {
+                // Null digests are placeholders for slots without content.
+                Some(digest) if !digest.is_null() => digest,
+                _ => continue,
+            };
+
+            let content = match content {
+                Some(content) => content,
+                None => {
+                    problems.push(VerificationProblem {
+                        context: context.clone(),
+                        problem: VerificationProblemType::SlotDigestMissing(slot),
+                    });
+                    continue;
+                }
+            };
+
+            match cd.digest_type.digest_data(content) {
+                Ok(actual) => {
+                    if actual != recorded.to_vec() {
+                        problems.push(VerificationProblem {
+                            context: context.clone(),
+                            problem: VerificationProblemType::SlotDigestMismatch(
+                                slot,
+                                recorded.to_vec(),
+                                actual,
+                            ),
+                        });
+                    }
+                }
+                Err(e) => {
+                    problems.push(VerificationProblem {
+                        context: context.clone(),
+                        problem: VerificationProblemType::SlotDigestError(e),
+                    });
+                }
+            }
+        }
+    }
+
+    problems
+}
+
+/// Verify that files sealed in a `CodeResources` file match files in a bundle.
+fn verify_code_resources(
+    bundle: &DirectoryBundle,
+    resources: &CodeResources,
+    context: VerificationContext,
+) -> Vec<VerificationProblem> {
+    let mut problems = vec![];
+
+    for (path, value) in resources.files2() {
+        let file_path = bundle.resolve_path(path);
+        let optional = value.optional.unwrap_or(false);
+
+        let metadata = match file_path.symlink_metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => {
+                if !optional {
+                    problems.push(VerificationProblem {
+                        context: context.clone(),
+                        problem: VerificationProblemType::ResourceMissing(path.clone()),
+                    });
+                }
+                continue;
+            }
+        };
+
+        if let Some(target) = &value.symlink {
+            let actual = match std::fs::read_link(&file_path) {
+                Ok(actual) => actual.to_string_lossy().replace('\\', "/"),
+                Err(_) => "<not a symlink>".to_string(),
+            };
+
+            if actual != *target {
+                problems.push(VerificationProblem {
+                    context: context.clone(),
+                    problem: VerificationProblemType::ResourceSymlinkMismatch(
+                        path.clone(),
+                        target.clone(),
+                        actual,
+                    ),
+                });
+            }
+        } else if let Some(cdhash) = &value.cdhash {
+            // Nested Mach-O binaries and bundles are sealed by the digest of their code
+            // directory. For bundles, this is the code directory of the main executable.
+            let res = if metadata.is_dir() {
+                DirectoryBundle::new_from_path(&file_path)
+                    .map_err(AppleCodesignError::DirectoryBundle)
+                    .and_then(|nested| bundle_main_executable_path(&nested))
+                    .and_then(|exe| {
+                        exe.ok_or_else(|| {
+                            AppleCodesignError::BundleNoMainExecutable(file_path.clone())
+                        })
+                    })
+            } else {
+                Ok(file_path.clone())
+            }
+            .and_then(|macho_path| std::fs::read(macho_path).map_err(AppleCodesignError::Io))
+            .and_then(|data| SignedMachOInfo::parse_data(&data))
+            .and_then(|info| DigestType::Sha256Truncated.digest_data(&info.code_directory_blob));
+
+            match res {
+                Ok(actual) => {
+                    if actual != *cdhash {
+                        problems.push(VerificationProblem {
+                            context: context.clone(),
+                            problem: VerificationProblemType::ResourceCodeDirectoryMismatch(
+                                path.clone(),
+                                cdhash.clone(),
+                                actual,
+                            ),
+                        });
+                    }
+                }
+                Err(e) => {
+                    problems.push(VerificationProblem {
+                        context: context.clone(),
+                        problem: VerificationProblemType::ResourceError(path.clone(), e),
+                    });
+                }
+            }
+        } else {
+            let digests = [
+                (DigestType::Sha1, value.hash.as_ref()),
+                (DigestType::Sha256, value.hash2.as_ref()),
+            ];
+
+            problems.extend(verify_resource_digests(
+                path,
+                &file_path,
+                digests.into_iter().filter_map(|(t, d)| d.map(|d| (t, d))),
+                context.clone(),
+            ));
+        }
+    }
+
+    for (path, value) in resources.files() {
+        let file_path = bundle.resolve_path(path);
+
+        let (digest, optional) = match value {
+            FilesValue::Required(digest) => (digest, false),
+            FilesValue::Optional(digest) => (digest, true),
+        };
+
+        if !file_path.exists() {
+            if !optional {
+                problems.push(VerificationProblem {
+                    context: context.clone(),
+                    problem: VerificationProblemType::ResourceMissing(path.clone()),
+                });
+            }
+            continue;
+        }
+
+        problems.extend(verify_resource_digests(
+            path,
+            &file_path,
+            std::iter::once((DigestType::Sha1, digest)),
+            context.clone(),
+        ));
+    }
 
     problems
 }
+
+fn verify_resource_digests<'a>(
+    path: &str,
+    file_path: &Path,
+    digests: impl Iterator<Item = (DigestType, &'a Vec<u8>)>,
+    context: VerificationContext,
+) -> Vec<VerificationProblem> {
+    let data = match std::fs::read(file_path) {
+        Ok(data) => data,
+        Err(e) => {
+            return vec![VerificationProblem {
+                context,
+                problem: VerificationProblemType::ResourceError(
+                    path.to_string(),
+                    AppleCodesignError::Io(e),
+                ),
+            }];
+        }
+    };
+
+    let mut problems = vec![];
+
+    for (digest_type, recorded) in digests {
+        match digest_type.digest_data(&data) {
+            Ok(actual) => {
+                if actual != *recorded {
+                    problems.push(VerificationProblem {
+                        context: context.clone(),
+                        problem: VerificationProblemType::ResourceDigestMismatch(
+                            path.to_string(),
+                            digest_type,
+                            recorded.clone(),
+                            actual,
+                        ),
+                    });
+                }
+            }
+            Err(e) => {
+                problems.push(VerificationProblem {
+                    context: context.clone(),
+                    problem: VerificationProblemType::ResourceError(path.to_string(), e),
+                });
+            }
+        }
+    }
+
+    problems
+}
+
+/// Verifies a DMG in a given path.
+///
+/// Returns a vector of problems detected. An empty vector means no
+/// problems were found.
+pub fn verify_dmg_path(path: impl AsRef<Path>) -> Vec<VerificationProblem> {
+    let path = path.as_ref();
+
+    verify_dmg_path_internal(
+        path,
+        VerificationContext {
+            path: Some(path.to_path_buf()),
+            fat_index: None,
+        },
+    )
+}
+
+fn verify_dmg_path_internal(path: &Path, context: VerificationContext) -> Vec<VerificationProblem> {
+    let mut fh = match File::open(path) {
+        Ok(fh) => fh,
+        Err(e) => {
+            return vec![VerificationProblem {
+                context,
+                problem: VerificationProblemType::IoError(e),
+            }];
+        }
+    };
+
+    verify_dmg_internal(&mut fh, context)
+}
+
+fn verify_dmg_internal<R: Read + Seek>(
+    fh: &mut R,
+    context: VerificationContext,
+) -> Vec<VerificationProblem> {
+    let reader = match DmgReader::new(fh) {
+        Ok(reader) => reader,
+        Err(e) => {
+            return vec![VerificationProblem {
+                context,
+                problem: VerificationProblemType::DmgError(e),
+            }];
+        }
+    };
+
+    let signature = match reader.embedded_signature() {
+        Ok(Some(signature)) => signature,
+        Ok(None) => {
+            return vec![VerificationProblem {
+                context,
+                problem: VerificationProblemType::NoDmgSignatureData,
+            }];
+        }
+        Err(e) => {
+            return vec![VerificationProblem {
+                context,
+                problem: VerificationProblemType::DmgError(e),
+            }];
+        }
+    };
+
+    let mut problems = vec![];
+
+    match signature.signature_data() {
+        // The CMS blob can be empty for ad-hoc signed DMGs.
+        Ok(Some(cms_blob)) if !cms_blob.is_empty() => {
+            problems.extend(verify_cms_signature(cms_blob, context.clone()));
+        }
+        Ok(_) => problems.push(VerificationProblem {
+            context: context.clone(),
+            problem: VerificationProblemType::NoCryptographicSignature,
+        }),
+        Err(e) => {
+            problems.push(VerificationProblem {
+                context: context.clone(),
+                problem: VerificationProblemType::DmgError(e),
+            });
+        }
+    }
+
+    match signature.code_directory() {
+        Ok(Some(cd)) => {
+            problems.extend(verify_dmg_code_directory(
+                &reader, fh, &signature, &cd, context,
+            ));
+        }
+        Ok(None) => {
+            problems.push(VerificationProblem {
+                context,
+                problem: VerificationProblemType::NoCodeDirectory,
+            });
+        }
+        Err(e) => {
+            problems.push(VerificationProblem {
+                context,
+                problem: VerificationProblemType::DmgError(e),
+            });
+        }
+    }
+
+    problems
+}
+
+fn verify_dmg_code_directory<R: Read + Seek>(
+    reader: &DmgReader,
+    fh: &mut R,
+    signature: &EmbeddedSignature,
+    cd: &CodeDirectoryBlob,
+    context: VerificationContext,
+) -> Vec<VerificationProblem> {
+    let mut problems = vec![];
+
+    problems.extend(verify_code_directory_digest_type(cd, context.clone()));
+
+    // DMGs have a single code digest covering all content before the signature.
+    match reader.digest_content_with(cd.digest_type, fh) {
+        Ok(actual_digest) => {
+            match cd.code_digests.first() {
+                Some(cd_digest) => {
+                    if cd_digest.data != actual_digest.data {
+                        problems.push(VerificationProblem {
+                            context: context.clone(),
+                            problem: VerificationProblemType::CodeDigestMismatch(
+                                0,
+                                cd_digest.to_vec(),
+                                actual_digest.to_vec(),
+                            ),
+                        });
+                    }
+                }
+                None => {
+                    problems.push(VerificationProblem {
+                        context: context.clone(),
+                        problem: VerificationProblemType::CodeDigestMissingEntry(
+                            0,
+                            actual_digest.to_vec(),
+                        ),
+                    });
+                }
+            }
+
+            for (index, cd_digest) in cd.code_digests.iter().enumerate().skip(1) {
+                problems.push(VerificationProblem {
+                    context: context.clone(),
+                    problem: VerificationProblemType::CodeDigestExtraEntry(
+                        index,
+                        cd_digest.to_vec(),
+                    ),
+                });
+            }
+        }
+        Err(e) => {
+            problems.push(VerificationProblem {
+                context: context.clone(),
+                problem: VerificationProblemType::CodeDigestError(e),
+            });
+        }
+    }
+
+    // The rep specific slot holds the digest of the koly trailer.
+    match reader.koly().digest_for_code_directory(cd.digest_type) {
+        Ok(actual_digest) => match cd.slot_digests().get(&CodeSigningSlot::RepSpecific) {
+            Some(cd_digest) => {
+                if cd_digest.to_vec() != actual_digest {
+                    problems.push(VerificationProblem {
+                        context: context.clone(),
+                        problem: VerificationProblemType::SlotDigestMismatch(
+                            CodeSigningSlot::RepSpecific,
+                            cd_digest.to_vec(),
+                            actual_digest,
+                        ),
+                    });
+                }
+            }
+            None => {
+                problems.push(VerificationProblem {
+                    context: context.clone(),
+                    problem: VerificationProblemType::SlotDigestMissing(
+                        CodeSigningSlot::RepSpecific,
+                    ),
+                });
+            }
+        },
+        Err(e) => {
+            problems.push(VerificationProblem {
+                context: context.clone(),
+                problem: VerificationProblemType::SlotDigestError(e),
+            });
+        }
+    }
+
+    problems.extend(verify_slot_digests(
+        signature,
+        cd,
+        &[CodeSigningSlot::RepSpecific],
+        context,
+    ));
+
+    problems
+}
+
+/// Verifies a XAR archive (e.g. a `.pkg` installer) in a given path.
+///
+/// Returns a vector of problems detected. An empty vector means no
+/// problems were found.
+pub fn verify_xar_path(path: impl AsRef<Path>) -> Vec<VerificationProblem> {
+    let path = path.as_ref();
+
+    verify_xar_path_internal(
+        path,
+        VerificationContext {
+            path: Some(path.to_path_buf()),
+            fat_index: None,
+        },
+    )
+}
+
+fn verify_xar_path_internal(path: &Path, context: VerificationContext) -> Vec<VerificationProblem> {
+    let fh = match File::open(path) {
+        Ok(fh) => fh,
+        Err(e) => {
+            return vec![VerificationProblem {
+                context,
+                problem: VerificationProblemType::IoError(e),
+            }];
+        }
+    };
+
+    let mut reader = match XarReader::new(fh) {
+        Ok(reader) => reader,
+        Err(e) => {
+            return vec![VerificationProblem {
+                context,
+                problem: VerificationProblemType::XarError(e),
+            }];
+        }
+    };
+
+    let mut problems = vec![];
+
+    match reader.verify_table_of_contents_checksum() {
+        Ok(true) => {}
+        Ok(false) => {
+            problems.push(VerificationProblem {
+                context: context.clone(),
+                problem: VerificationProblemType::XarTableOfContentsChecksumMismatch,
+            });
+        }
+        Err(e) => {
+            problems.push(VerificationProblem {
+                context: context.clone(),
+                problem: VerificationProblemType::XarError(e),
+            });
+        }
+    }
+
+    let mut signed = false;
+
+    match reader.rsa_signature() {
+        Ok(Some(_)) => {
+            signed = true;
+
+            match reader.verify_rsa_checksum_signature() {
+                Ok(true) => {}
+                Ok(false) => {
+                    problems.push(VerificationProblem {
+                        context: context.clone(),
+                        problem: VerificationProblemType::XarRsaSignatureNoCertificates,
+                    });
+                }
+                Err(e) => {
+                    problems.push(VerificationProblem {
+                        context: context.clone(),
+                        problem: VerificationProblemType::XarError(e),
+                    });
+                }
+            }
+        }
+        Ok(None) => {}
+        Err(e) => {
+            problems.push(VerificationProblem {
+                context: context.clone(),
+                problem: VerificationProblemType::XarError(e),
+            });
+        }
+    }
+
+    match reader.cms_signature() {
+        Ok(Some(signed_data)) => {
+            signed = true;
+
+            problems.extend(verify_signed_data(&signed_data, context.clone()));
+
+            // The CMS signature signs the table of contents checksum.
+            match reader.checksum() {
+                Ok((_, checksum)) => {
+                    for signer in signed_data.signers() {
+                        if let Err(e) = signer.verify_message_digest_with_content(&checksum) {
+                            problems.push(VerificationProblem {
+                                context: context.clone(),
+                                problem: VerificationProblemType::CmsError(e),
+                            });
+                        }
+                    }
+                }
+                Err(e) => {
+                    problems.push(VerificationProblem {
+                        context: context.clone(),
+                        problem: VerificationProblemType::XarError(e),
+                    });
+                }
+            }
+        }
+        Ok(None) => {}
+        Err(e) => {
+            problems.push(VerificationProblem {
+                context: context.clone(),
+                problem: VerificationProblemType::XarError(e),
+            });
+        }
+    }
+
+    if !signed {
+        problems.push(VerificationProblem {
+            context,
+            problem: VerificationProblemType::NoCryptographicSignature,
+        });
+    }
+
+    problems
+}
+
+/// Verifies that an entity in a given path satisfies a code requirement.
+///
+/// If `requirement` is `None`, the designated requirement embedded in the
+/// entity's signature is used. Bundles are evaluated via their main executable
+/// with the bundle's `Info.plist` available to the expression. Each Mach-O
+/// within a fat binary is evaluated independently.
+///
+/// Returns a vector of problems detected. An empty vector means the
+/// requirement is satisfied.
+pub fn verify_path_code_requirement(
+    path: impl AsRef<Path>,
+    requirement: Option<&CodeRequirementExpression>,
+) -> Vec<VerificationProblem> {
+    let path = path.as_ref();
+
+    let context = VerificationContext {
+        path: Some(path.to_path_buf()),
+        fat_index: None,
+    };
+
+    match PathType::from_path(path) {
+        Ok(PathType::Bundle) => {
+            let bundle = match DirectoryBundle::new_from_path(path) {
+                Ok(bundle) => bundle,
+                Err(e) => {
+                    return vec![VerificationProblem {
+                        context,
+                        problem: VerificationProblemType::BundleError(
+                            AppleCodesignError::DirectoryBundle(e),
+                        ),
+                    }];
+                }
+            };
+
+            let exe_path = match bundle_main_executable_path(&bundle) {
+                Ok(Some(exe_path)) => exe_path,
+                Ok(None) => {
+                    return vec![VerificationProblem {
+                        context,
+                        problem: VerificationProblemType::BundleError(
+                            AppleCodesignError::BundleNoMainExecutable(path.to_path_buf()),
+                        ),
+                    }];
+                }
+                Err(e) => {
+                    return vec![VerificationProblem {
+                        context,
+                        problem: VerificationProblemType::BundleError(e),
+                    }];
+                }
+            };
+
+            let context = VerificationContext {
+                path: Some(exe_path.clone()),
+                fat_index: None,
+            };
+
+            match std::fs::read(&exe_path) {
+                Ok(data) => verify_macho_code_requirement(
+                    &data,
+                    requirement,
+                    Some(bundle.info_plist()),
+                    context,
+                ),
+                Err(e) => vec![VerificationProblem {
+                    context,
+                    problem: VerificationProblemType::IoError(e),
+                }],
+            }
+        }
+        Ok(PathType::Dmg) => {
+            let mut fh = match File::open(path) {
+                Ok(fh) => fh,
+                Err(e) => {
+                    return vec![VerificationProblem {
+                        context,
+                        problem: VerificationProblemType::IoError(e),
+                    }];
+                }
+            };
+
+            let reader = match DmgReader::new(&mut fh) {
+                Ok(reader) => reader,
+                Err(e) => {
+                    return vec![VerificationProblem {
+                        context,
+                        problem: VerificationProblemType::DmgError(e),
+                    }];
+                }
+            };
+
+            match reader.embedded_signature() {
+                Ok(Some(signature)) => {
+                    verify_signature_code_requirement(&signature, requirement, None, context)
+                }
+                Ok(None) => vec![VerificationProblem {
+                    context,
+                    problem: VerificationProblemType::NoDmgSignatureData,
+                }],
+                Err(e) => vec![VerificationProblem {
+                    context,
+                    problem: VerificationProblemType::DmgError(e),
+                }],
+            }
+        }
+        Ok(PathType::Xar) => vec![VerificationProblem {
+            context,
+            problem: VerificationProblemType::RequirementEvaluationUnsupported,
+        }],
+        Ok(PathType::MachO | PathType::Other) | Err(_) => match std::fs::read(path) {
+            Ok(data) => verify_macho_code_requirement(&data, requirement, None, context),
+            Err(e) => vec![VerificationProblem {
+                context,
+                problem: VerificationProblemType::IoError(e),
+            }],
+        },
+    }
+}
+
+fn verify_macho_code_requirement(
+    macho_data: &[u8],
+    requirement: Option<&CodeRequirementExpression>,
+    info_plist: Option<&plist::Dictionary>,
+    context: VerificationContext,
+) -> Vec<VerificationProblem> {
+    let machos = match iter_macho(macho_data) {
+        Ok(machos) => machos,
+        Err(e) => {
+            return vec![VerificationProblem {
+                context,
+                problem: VerificationProblemType::MachOSignatureError(e),
+            }];
+        }
+    };
+
+    let is_fat = matches!(Mach::parse(macho_data), Ok(Mach::Fat(_)));
+    let mut problems = vec![];
+
+    for (index, (macho, _)) in machos.enumerate() {
+        let mut context = context.clone();
+        if is_fat {
+            context.fat_index = Some(index);
+        }
+
+        match macho.code_signature() {
+            Ok(Some(signature)) => {
+                problems.extend(verify_signature_code_requirement(
+                    &signature,
+                    requirement,
+                    info_plist,
+                    context,
+                ));
+            }
+            Ok(None) => {
+                problems.push(VerificationProblem {
+                    context,
+                    problem: VerificationProblemType::NoMachOSignatureData,
+                });
+            }
+            Err(e) => {
+                problems.push(VerificationProblem {
+                    context,
+                    problem: VerificationProblemType::MachOSignatureError(e),
+                });
+            }
+        }
+    }
+
+    problems
+}
+
+fn verify_signature_code_requirement(
+    signature: &EmbeddedSignature,
+    requirement: Option<&CodeRequirementExpression>,
+    info_plist: Option<&plist::Dictionary>,
+    context: VerificationContext,
+) -> Vec<VerificationProblem> {
+    let requirement_context =
+        match CodeRequirementContext::from_embedded_signature(signature, info_plist.cloned()) {
+            Ok(requirement_context) => requirement_context,
+            Err(e) => {
+                return vec![VerificationProblem {
+                    context,
+                    problem: VerificationProblemType::RequirementError(e),
+                }];
+            }
+        };
+
+    let evaluate = |expressions: &[CodeRequirementExpression]| {
+        expressions
+            .iter()
+            .filter_map(|expr| match expr.evaluate(&requirement_context) {
+                Ok(evaluation) if evaluation.is_satisfied() => None,
+                Ok(evaluation) => Some(VerificationProblemType::RequirementUnsatisfied(evaluation)),
+                Err(e) => Some(VerificationProblemType::RequirementError(e)),
+            })
+            .map(|problem| VerificationProblem {
+                context: context.clone(),
+                problem,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    if let Some(requirement) = requirement {
+        return evaluate(std::slice::from_ref(requirement));
+    }
+
+    match signature.code_requirements() {
+        Ok(Some(requirements)) => {
+            match requirements.requirements.get(&RequirementType::Designated) {
+                Some(designated) => match designated.parse_expressions() {
+                    Ok(expressions) => evaluate(expressions.as_slice()),
+                    Err(e) => vec![VerificationProblem {
+                        context,
+                        problem: VerificationProblemType::RequirementError(e),
+                    }],
+                },
+                None => vec![VerificationProblem {
+                    context,
+                    problem: VerificationProblemType::NoDesignatedRequirement,
+                }],
+            }
+        }
+        Ok(None) => vec![VerificationProblem {
+            context,
+            problem: VerificationProblemType::NoDesignatedRequirement,
+        }],
+        Err(e) => vec![VerificationProblem {
+            context,
+            problem: VerificationProblemType::RequirementError(e),
+        }],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::{
+            bundle_signing::BundleSigner,
+            dmg::{DmgSigner, KolyTrailer},
+            signing_settings::{SettingsScope, SigningSettings},
+        },
+        anyhow::Result,
+        apple_xar::{format::XarHeader, signing::XarSigner, table_of_contents::ChecksumType},
+        chrono::{Duration, Utc},
+        cryptographic_message_syntax::{SignedDataBuilder, SignerBuilder},
+        scroll::{IOwrite, Pwrite},
+        std::{fs::create_dir_all, io::Write},
+        x509_certificate::{
+            asn1time::Time, rfc5280, testutil::*, CapturedX509Certificate, InMemorySigningKeyPair,
+            KeyAlgorithm, Sign, X509CertificateBuilder,
+        },
+    };
+
+    fn signer_builder() -> X509CertificateBuilder {
+        let mut builder = X509CertificateBuilder::new(KeyAlgorithm::Ed25519);
+        builder
+            .subject()
+            .append_common_name_utf8_string("signer")
+            .unwrap();
+        builder.serial_number(42);
+
+        builder
+    }
+
+    /// Create a CMS signature made by a certificate issued from a builder by a new CA.
+    ///
+    /// Returns the signature, the CA certificate, and the CA's key.
+    fn cms_signature(
+        builder: &X509CertificateBuilder,
+    ) -> (SignedData, CapturedX509Certificate, InMemorySigningKeyPair) {
+        let (ca, ca_key) = certificate_authority_ed25519();
+        let (cert, key) = issue_certificate(builder, &ca, &ca_key);
+
+        let der = SignedDataBuilder::default()
+            .signed_content(vec![42])
+            .certificate(cert.clone())
+            .certificate(ca.clone())
+            .signer(SignerBuilder::new(&key, cert))
+            .build_der()
+            .unwrap();
+
+        (SignedData::parse_ber(&der).unwrap(), ca, ca_key)
+    }
+
+    fn context() -> VerificationContext {
+        VerificationContext {
+            path: None,
+            fat_index: None,
+        }
+    }
+
+    #[test]
+    fn cms_signer_valid() {
+        let (signed_data, _, _) = cms_signature(&signer_builder());
+
+        assert!(verify_signed_data(&signed_data, context()).is_empty());
+    }
+
+    #[test]
+    fn cms_signer_expired() {
+        let mut builder = signer_builder();
+        builder.validity_duration(Duration::hours(-1));
+        let (signed_data, _, _) = cms_signature(&builder);
+
+        let problems = verify_signed_data(&signed_data, context());
+        assert!(matches!(
+            problems.as_slice(),
+            [VerificationProblem {
+                problem: VerificationProblemType::CmsCertificateValidation(
+                    ValidationProblem::Expired { subject, .. }
+                ),
+                ..
+            }] if subject == "signer"
+        ));
+    }
+
+    #[test]
+    fn cms_signer_revoked() {
+        let (signed_data, ca, ca_key) = cms_signature(&signer_builder());
+
+        let mut validator = CertificateChainValidator::default();
+        validator.add_crl(sign_crl(
+            rfc5280::TbsCertList {
+                version: Some(rfc5280::Version::V2),
+                signature: ca_key.signature_algorithm().unwrap().into(),
+                issuer: ca.subject_name().clone(),
+                this_update: Time::from(Utc::now()),
+                next_update: None,
+                revoked_certificates: vec![rfc5280::RevokedCertificate {
+                    user_certificate: 42i64.into(),
+                    revocation_date: Time::from(Utc::now() - Duration::minutes(5)),
+                    crl_entry_extensions: None,
+                }],
+                crl_extensions: None,
+                raw_data: None,
+            },
+            &ca_key,
+        ));
+
+        let problems = verify_signed_data_with_validator(&signed_data, &validator, context());
+        assert!(matches!(
+            problems.as_slice(),
+            [VerificationProblem {
+                problem: VerificationProblemType::CmsCertificateValidation(
+                    ValidationProblem::Revoked { subject, .. }
+                ),
+                ..
+            }] if subject == "signer"
+        ));
+    }
+
+    /// Create and sign an app bundle without a main executable.
+    ///
+    /// Returns the temporary directory holding everything and the path of the signed bundle.
+    fn signed_app_bundle() -> Result<(tempfile::TempDir, PathBuf)> {
+        let td = tempfile::Builder::new()
+            .prefix("apple-codesign-verify-")
+            .tempdir()?;
+
+        let source = td.path().join("source").join("MyApp.app");
+        let contents = source.join("Contents");
+        let resources = contents.join("Resources");
+        create_dir_all(&resources)?;
+
+        let mut info_plist = plist::Dictionary::new();
+        info_plist.insert(
+            "CFBundleIdentifier".into(),
+            plist::Value::from("com.example.myapp"),
+        );
+        plist::Value::from(info_plist).to_file_xml(contents.join("Info.plist"))?;
+
+        std::fs::write(resources.join("data.txt"), b"original content")?;
+        create_dir_all(resources.join("child"))?;
+        std::fs::write(resources.join("child").join("nested.txt"), b"nested")?;
+
+        let dest = td.path().join("signed").join("MyApp.app");
+        BundleSigner::new_from_path(&source)?
+            .write_signed_bundle(&dest, &SigningSettings::default())?;
+
+        Ok((td, dest))
+    }
+
+    #[test]
+    fn bundle_signed() -> Result<()> {
+        let (_td, bundle) = signed_app_bundle()?;
+
+        assert!(bundle
+            .join("Contents/_CodeSignature/CodeResources")
+            .exists());
+
+        let problems = verify_bundle_path(&bundle);
+        assert!(problems.is_empty(), "unexpected problems: {:?}", problems);
+
+        // Path sniffing should route to bundle verification.
+        assert!(verify_path(&bundle).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn bundle_tampered_resource() -> Result<()> {
+        let (_td, bundle) = signed_app_bundle()?;
+
+        std::fs::write(
+            bundle.join("Contents/Resources/data.txt"),
+            b"modified content",
+        )?;
+
+        let problems = verify_bundle_path(&bundle);
+        assert!(!problems.is_empty());
+        assert!(problems.iter().all(|p| matches!(
+            &p.problem,
+            VerificationProblemType::ResourceDigestMismatch(path, _, _, _)
+                if path == "Resources/data.txt"
+        )));
+
+        // Both SHA-1 and SHA-256 seals are checked.
+        assert!(problems.iter().any(|p| matches!(
+            &p.problem,
+            VerificationProblemType::ResourceDigestMismatch(_, DigestType::Sha1, _, _)
+        )));
+        assert!(problems.iter().any(|p| matches!(
+            &p.problem,
+            VerificationProblemType::ResourceDigestMismatch(_, DigestType::Sha256, _, _)
+        )));
+
+        Ok(())
+    }
+
+    #[test]
+    fn bundle_missing_resource() -> Result<()> {
+        let (_td, bundle) = signed_app_bundle()?;
+
+        std::fs::remove_file(bundle.join("Contents/Resources/child/nested.txt"))?;
+
+        let problems = verify_bundle_path(&bundle);
+        assert!(!problems.is_empty());
+        assert!(problems.iter().all(|p| matches!(
+            &p.problem,
+            VerificationProblemType::ResourceMissing(path)
+                if path == "Resources/child/nested.txt"
+        )));
+
+        Ok(())
+    }
+
+    #[test]
+    fn bundle_missing_code_resources() -> Result<()> {
+        let (_td, bundle) = signed_app_bundle()?;
+
+        std::fs::remove_file(bundle.join("Contents/_CodeSignature/CodeResources"))?;
+
+        let problems = verify_bundle_path(&bundle);
+        assert_eq!(problems.len(), 1);
+        assert!(matches!(
+            problems[0].problem,
+            VerificationProblemType::NoCodeResources
+        ));
+
+        Ok(())
+    }
+
+    /// Write an unsigned DMG holding some data, a plist, and a koly trailer.
+    fn write_unsigned_dmg(path: &Path) -> Result<()> {
+        let data = vec![42u8; 4096];
+        let plist = b"<plist version=\"1.0\"><dict/></plist>\n";
+
+        let koly = KolyTrailer {
+            signature: *b"koly",
+            version: 4,
+            header_size: 512,
+            flags: 1,
+            running_data_fork_offset: 0,
+            data_fork_offset: 0,
+            data_fork_length: data.len() as _,
+            rsrc_fork_offset: 0,
+            rsrc_fork_length: 0,
+            segment_number: 1,
+            segment_count: 1,
+            segment_id: [0; 4],
+            data_fork_digest_type: 0,
+            data_fork_digest_size: 0,
+            data_fork_digest: [0; 32],
+            plist_offset: data.len() as _,
+            plist_length: plist.len() as _,
+            reserved1: [0; 8],
+            code_signature_offset: 0,
+            code_signature_size: 0,
+            reserved2: [0; 5],
+            main_digest_type: 0,
+            main_digest_size: 0,
+            main_digest: [0; 32],
+            image_variant: 1,
+            sector_count: data.len() as u64 / 512,
+        };
+
+        let mut trailer = [0u8; 512];
+        trailer.pwrite_with(koly, 0, scroll::BE)?;
+
+        let mut fh = File::create(path)?;
+        fh.write_all(&data)?;
+        fh.write_all(plist)?;
+        fh.write_all(&trailer)?;
+
+        Ok(())
+    }
+
+    /// Create and sign a DMG with a self-signed certificate.
+    ///
+    /// Returns the temporary directory holding everything and the path of the signed DMG.
+    fn signed_dmg() -> Result<(tempfile::TempDir, PathBuf)> {
+        let td = tempfile::Builder::new()
+            .prefix("apple-codesign-verify-")
+            .tempdir()?;
+
+        let path = td.path().join("image.dmg");
+        write_unsigned_dmg(&path)?;
+
+        let (cert, key) = self_signed_ed25519_key_pair();
+        let mut settings = SigningSettings::default();
+        settings.set_signing_key(&key, cert);
+        settings.set_binary_identifier(SettingsScope::Main, "com.example.image");
+
+        let mut fh = File::options().read(true).write(true).open(&path)?;
+        DmgSigner::default().sign_file(&settings, &mut fh)?;
+
+        Ok((td, path))
+    }
+
+    #[test]
+    fn dmg_signed() -> Result<()> {
+        let (_td, path) = signed_dmg()?;
+
+        let problems = verify_dmg_path(&path);
+        assert!(problems.is_empty(), "unexpected problems: {:?}", problems);
+
+        // Path sniffing should route to DMG verification.
+        assert!(verify_path(&path).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn dmg_tampered_content() -> Result<()> {
+        let (_td, path) = signed_dmg()?;
+
+        let mut data = std::fs::read(&path)?;
+        data[0] = !data[0];
+        std::fs::write(&path, data)?;
+
+        let problems = verify_dmg_path(&path);
+        assert!(matches!(
+            problems.as_slice(),
+            [VerificationProblem {
+                problem: VerificationProblemType::CodeDigestMismatch(0, _, _),
+                ..
+            }]
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn dmg_unsigned() -> Result<()> {
+        let td = tempfile::Builder::new()
+            .prefix("apple-codesign-verify-")
+            .tempdir()?;
+
+        let path = td.path().join("image.dmg");
+        write_unsigned_dmg(&path)?;
+
+        let problems = verify_dmg_path(&path);
+        assert!(matches!(
+            problems.as_slice(),
+            [VerificationProblem {
+                problem: VerificationProblemType::NoDmgSignatureData,
+                ..
+            }]
+        ));
+
+        Ok(())
+    }
+
+    /// Create and sign a XAR archive with a self-signed certificate.
+    ///
+    /// Returns the temporary directory holding everything, the path of the signed
+    /// archive, and the offset of the table of contents checksum within it.
+    fn signed_xar() -> Result<(tempfile::TempDir, PathBuf, usize)> {
+        let td = tempfile::Builder::new()
+            .prefix("apple-codesign-verify-")
+            .tempdir()?;
+
+        let toc = indoc::indoc! {r#"
+            <?xml version="1.0" encoding="UTF-8"?>
+            <xar>
+              <toc>
+                <creation-time>2022-01-01T00:00:00</creation-time>
+                <checksum style="sha1">
+                  <offset>0</offset>
+                  <size>20</size>
+                </checksum>
+                <file id="1">
+                  <name>empty</name>
+                  <type>directory</type>
+                </file>
+              </toc>
+            </xar>
+        "#};
+
+        let mut zlib = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+        zlib.write_all(toc.as_bytes())?;
+        let toc_compressed = zlib.finish()?;
+
+        let header = XarHeader {
+            magic: u32::from_be_bytes(*b"xar!"),
+            size: 28,
+            version: 1,
+            toc_length_compressed: toc_compressed.len() as _,
+            toc_length_uncompressed: toc.len() as _,
+            checksum_algorithm_id: 1,
+        };
+
+        let mut unsigned = vec![];
+        unsigned.iowrite_with(header, scroll::BE)?;
+        unsigned.write_all(&toc_compressed)?;
+        unsigned.write_all(&ChecksumType::Sha1.digest_data(&toc_compressed)?)?;
+
+        let (cert, key) = self_signed_ed25519_key_pair();
+        let mut signed = vec![];
+        XarSigner::new(XarReader::new(std::io::Cursor::new(unsigned))?).sign(
+            &mut signed,
+            &key,
+            &cert,
+            None,
+            std::iter::empty(),
+        )?;
+
+        let checksum_offset = XarReader::new(std::io::Cursor::new(&signed))?.heap_start_offset();
+
+        let path = td.path().join("archive.xar");
+        std::fs::write(&path, signed)?;
+
+        Ok((td, path, checksum_offset as _))
+    }
+
+    #[test]
+    fn xar_signed() -> Result<()> {
+        let (_td, path, _) = signed_xar()?;
+
+        let problems = verify_xar_path(&path);
+        assert!(problems.is_empty(), "unexpected problems: {:?}", problems);
+
+        // Path sniffing should route to XAR verification.
+        assert!(verify_path(&path).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn xar_tampered_checksum() -> Result<()> {
+        let (_td, path, checksum_offset) = signed_xar()?;
+
+        let mut data = std::fs::read(&path)?;
+        data[checksum_offset] = !data[checksum_offset];
+        std::fs::write(&path, data)?;
+
+        let problems = verify_xar_path(&path);
+        assert!(problems.iter().any(|p| matches!(
+            p.problem,
+            VerificationProblemType::XarTableOfContentsChecksumMismatch
+        )));
+        // Both signatures cover the checksum.
+        assert!(problems
+            .iter()
+            .any(|p| matches!(p.problem, VerificationProblemType::XarError(_))));
+        assert!(problems
+            .iter()
+            .any(|p| matches!(p.problem, VerificationProblemType::CmsError(_))));
+
+        Ok(())
+    }
+}
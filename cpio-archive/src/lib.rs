@@ -3,7 +3,7 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 pub mod newc;
-pub use newc::{NewcHeader, NewcReader};
+pub use newc::{NewcBuilder, NewcHeader, NewcReader};
 pub mod odc;
 pub use odc::{OdcBuilder, OdcHeader, OdcReader};
 
@@ -41,6 +41,9 @@ pub enum Error {
 
     #[error("path is not a file: {0}")]
     NotAFile(PathBuf),
+
+    #[error("hard link group must have at least 1 path")]
+    EmptyHardLinkGroup,
 }
 
 /// Result type for this crate.
@@ -128,7 +131,9 @@ pub fn reader<T: 'static + Read + Sized>(mut reader: T) -> CpioResult<Box<Chaine
     reader.read_exact(&mut magic)?;
 
     match magic.as_ref() {
-        crate::newc::MAGIC => Ok(Box::new(NewcReader::new(Cursor::new(magic).chain(reader)))),
+        crate::newc::MAGIC | crate::newc::MAGIC_CRC => {
+            Ok(Box::new(NewcReader::new(Cursor::new(magic).chain(reader))))
+        }
         crate::odc::MAGIC => Ok(Box::new(OdcReader::new(Cursor::new(magic).chain(reader)))),
         _ => Err(Error::BadMagic),
     }
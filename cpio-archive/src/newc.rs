@@ -3,17 +3,51 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 //! New ASCII format support.
+//!
+//! This module implements support for the *New ASCII format* (also known
+//! as *newc* or *SVR4*) and its *New CRC format* variant. The CRC variant
+//! is identical except for its magic value and that the header's checksum
+//! field holds a simple sum of the bytes of the file data.
 
 use {
     crate::{CpioHeader, CpioReader, CpioResult, Error},
+    chrono::{DateTime, Utc},
+    is_executable::IsExecutable,
     std::{
+        collections::HashSet,
         ffi::CStr,
-        io::{Read, Take},
+        io::{Read, Seek, SeekFrom, Take, Write},
+        path::Path,
+    },
+    tugger_file_manifest::{
+        FileManifest, S_IFDIR, S_IFREG, S_IRGRP, S_IROTH, S_IRUSR, S_IWUSR, S_IXGRP, S_IXOTH,
+        S_IXUSR,
     },
 };
 
+/// Header magic for newc entries.
 pub const MAGIC: &[u8] = b"070701";
 
+/// Header magic for newc entries having a CRC checksum.
+pub const MAGIC_CRC: &[u8] = b"070702";
+
+const TRAILER: &str = "TRAILER!!!";
+
+/// Size of the fixed length header, including the magic.
+const HEADER_SIZE: usize = 6 + 13 * 8;
+
+/// Number of bytes needed to pad a value to a 4 byte boundary.
+fn pad_length(size: usize) -> usize {
+    (4 - size % 4) % 4
+}
+
+/// Compute the checksum of file data as used by the *New CRC format*.
+///
+/// Despite the name, this is just the sum of all bytes, truncated to 32 bits.
+pub fn checksum(data: &[u8]) -> u32 {
+    data.iter().fold(0u32, |acc, b| acc.wrapping_add(*b as u32))
+}
+
 fn u32_from_hex(data: &[u8]) -> CpioResult<u32> {
     let s = std::str::from_utf8(data).map_err(|_| Error::BadHeaderString)?;
     u32::from_str_radix(s, 16).map_err(|_| Error::BadHeaderHex(s.to_string()))
@@ -24,6 +58,16 @@ fn u64_from_hex(data: &[u8]) -> CpioResult<u64> {
     u64::from_str_radix(s, 16).map_err(|_| Error::BadHeaderHex(s.to_string()))
 }
 
+fn write_hex(value: u64, writer: &mut impl Write) -> CpioResult<()> {
+    if value > u32::MAX as u64 {
+        return Err(Error::ValueTooLarge);
+    }
+
+    writer.write_all(format!("{:08x}", value).as_bytes())?;
+
+    Ok(())
+}
+
 fn read_hex_u32(reader: &mut impl Read, count: usize) -> CpioResult<u32> {
     let mut buffer = vec![0u8; count];
     reader.read_exact(&mut buffer)?;
@@ -36,6 +80,7 @@ fn read_hex_u64(reader: &mut impl Read, count: usize) -> CpioResult<u64> {
     u64_from_hex(&buffer)
 }
 
+/// Parsed new ASCII format header.
 #[derive(Clone, Debug)]
 pub struct NewcHeader {
     pub inode: u32,
@@ -54,6 +99,9 @@ pub struct NewcHeader {
 }
 
 impl NewcHeader {
+    /// Parse a header from a reader.
+    ///
+    /// The magic is expected to have already been consumed.
     pub fn from_reader(reader: &mut impl Read) -> CpioResult<Self> {
         let inode = read_hex_u32(reader, 8)?;
         let mode = read_hex_u32(reader, 8)?;
@@ -77,8 +125,8 @@ impl NewcHeader {
             .to_string_lossy()
             .to_string();
 
-        // Pad to 4 byte boundary.
-        let mut pad = vec![0u8; name_data.len() % 4];
+        // Header and name are padded to 4 byte boundary.
+        let mut pad = vec![0u8; pad_length(HEADER_SIZE + name_data.len())];
         reader.read_exact(&mut pad)?;
 
         Ok(Self {
@@ -97,6 +145,49 @@ impl NewcHeader {
             name,
         })
     }
+
+    /// Write the header content using the *New ASCII format* to a writer.
+    ///
+    /// Padding following the filename is written. Returns the number of bytes
+    /// written.
+    pub fn write(&self, writer: &mut impl Write) -> CpioResult<u64> {
+        self.write_with_magic(writer, MAGIC)
+    }
+
+    /// Write the header content using the *New CRC format* to a writer.
+    ///
+    /// The checksum field is written as-is: callers are responsible for
+    /// setting it to the [checksum] of the file data.
+    pub fn write_crc(&self, writer: &mut impl Write) -> CpioResult<u64> {
+        self.write_with_magic(writer, MAGIC_CRC)
+    }
+
+    fn write_with_magic(&self, writer: &mut impl Write, magic: &[u8]) -> CpioResult<u64> {
+        let name_length = self.name.len() + 1;
+
+        writer.write_all(magic)?;
+        write_hex(self.inode as _, writer)?;
+        write_hex(self.mode as _, writer)?;
+        write_hex(self.uid as _, writer)?;
+        write_hex(self.gid as _, writer)?;
+        write_hex(self.nlink as _, writer)?;
+        write_hex(self.mtime as _, writer)?;
+        write_hex(self.file_size, writer)?;
+        write_hex(self.dev_major as _, writer)?;
+        write_hex(self.dev_minor as _, writer)?;
+        write_hex(self.rdev_major as _, writer)?;
+        write_hex(self.rdev_minor as _, writer)?;
+        write_hex(name_length as _, writer)?;
+        write_hex(self.checksum as _, writer)?;
+
+        writer.write_all(self.name.as_bytes())?;
+        writer.write_all(b"\0")?;
+
+        let pad = pad_length(HEADER_SIZE + name_length);
+        writer.write_all(&vec![0u8; pad])?;
+
+        Ok((HEADER_SIZE + name_length + pad) as u64)
+    }
 }
 
 impl CpioHeader for NewcHeader {
@@ -175,18 +266,18 @@ impl<T: Read + Sized> CpioReader<T> for NewcReader<T> {
                 }
             }
 
-            if magic != MAGIC {
+            if magic != MAGIC && magic != MAGIC_CRC {
                 return Err(Error::BadMagic);
             }
 
             let header = NewcHeader::from_reader(&mut reader)?;
 
-            if header.name == "TRAILER!!!" {
+            if header.name == TRAILER {
                 self.seen_trailer = true;
                 Ok(None)
             } else {
                 self.entry_reader = Some(reader.take(header.file_size as _));
-                self.entry_data_pad = header.file_size as usize % 4;
+                self.entry_data_pad = pad_length(header.file_size as usize);
                 Ok(Some(Box::new(header)))
             }
         } else {
@@ -244,3 +335,549 @@ impl<T: Read + Sized> Read for NewcReader<T> {
         }
     }
 }
+
+/// Iteratively create a cpio archive using the *New ASCII format*.
+///
+/// This has the same interface and semantics as [crate::OdcBuilder]. See its
+/// documentation for more.
+///
+/// By default, entries are written using the `070701` magic and a 0 checksum.
+/// Calling [Self::crc] will cause the *New CRC format* (`070702`) to be
+/// written instead, with the checksum of file data recorded in each header.
+///
+/// Each entry is assigned a new inode number, unless it is written as part of
+/// a group of hard links via [Self::append_hard_links_from_data], in which case
+/// all members of the group share an inode.
+pub struct NewcBuilder<W: Write + Sized> {
+    writer: W,
+    crc: bool,
+    default_uid: u32,
+    default_gid: u32,
+    default_mtime: DateTime<Utc>,
+    default_mode_file: u32,
+    default_mode_dir: u32,
+    auto_write_dirs: bool,
+    seen_dirs: HashSet<String>,
+    entry_count: u32,
+    finished: bool,
+}
+
+impl<W: Write + Sized> NewcBuilder<W> {
+    /// Construct a new instance which will write data to a writer.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            crc: false,
+            default_uid: 0,
+            default_gid: 0,
+            default_mtime: Utc::now(),
+            default_mode_file: S_IFREG | S_IRUSR | S_IWUSR | S_IRGRP | S_IROTH,
+            default_mode_dir: S_IFDIR
+                | S_IRUSR
+                | S_IWUSR
+                | S_IXUSR
+                | S_IRGRP
+                | S_IXGRP
+                | S_IROTH
+                | S_IXOTH,
+            auto_write_dirs: true,
+            seen_dirs: HashSet::new(),
+            entry_count: 0,
+            finished: false,
+        }
+    }
+
+    /// Set whether to write the *New CRC format* variant.
+    pub fn crc(&mut self, value: bool) {
+        self.crc = value;
+    }
+
+    /// Set the default file mode to use for files.
+    pub fn default_mode_file(&mut self, mode: u32) {
+        self.default_mode_file = mode;
+    }
+
+    /// Set the default file mode to use for directories.
+    pub fn default_mode_directory(&mut self, mode: u32) {
+        self.default_mode_dir = mode;
+    }
+
+    /// Set the default user ID (UID).
+    pub fn default_user_id(&mut self, uid: u32) {
+        self.default_uid = uid;
+    }
+
+    /// Set the default group ID (GID).
+    pub fn default_group_id(&mut self, gid: u32) {
+        self.default_gid = gid;
+    }
+
+    /// Set the default modified time.
+    pub fn default_mtime(&mut self, mtime: DateTime<Utc>) {
+        self.default_mtime = mtime;
+    }
+
+    /// Set the behavior for auto writing directory entries.
+    pub fn auto_write_dirs(&mut self, value: bool) {
+        self.auto_write_dirs = value;
+    }
+
+    /// Obtain a header record representing the next header in the archive.
+    ///
+    /// The header has fields set to default values. Callers should likely
+    /// update at least the name and possibly the file size and mode.
+    ///
+    /// This will increment the inode sequence number when called.
+    pub fn next_header(&mut self) -> NewcHeader {
+        let inode = self.entry_count;
+        self.entry_count += 1;
+
+        NewcHeader {
+            inode,
+            mode: self.default_mode_file,
+            uid: self.default_uid,
+            gid: self.default_gid,
+            nlink: 1,
+            mtime: self.default_mtime.timestamp() as _,
+            file_size: 0,
+            dev_major: 0,
+            dev_minor: 0,
+            rdev_major: 0,
+            rdev_minor: 0,
+            checksum: 0,
+            name: "".to_string(),
+        }
+    }
+
+    fn normalize_archive_path(&self, path: &str) -> String {
+        if path.starts_with("./") {
+            path.to_string()
+        } else {
+            format!("./{}", path)
+        }
+    }
+
+    fn write_header(&mut self, header: &NewcHeader) -> CpioResult<u64> {
+        if self.crc {
+            header.write_crc(&mut self.writer)
+        } else {
+            header.write(&mut self.writer)
+        }
+    }
+
+    /// Write file data followed by padding to a 4 byte boundary.
+    fn write_data(&mut self, data: &[u8]) -> CpioResult<u64> {
+        self.writer.write_all(data)?;
+
+        Ok(data.len() as u64 + self.write_data_pad(data.len() as _)?)
+    }
+
+    fn write_data_pad(&mut self, size: u64) -> CpioResult<u64> {
+        let pad = pad_length(size as usize);
+        self.writer.write_all(&vec![0u8; pad])?;
+
+        Ok(pad as u64)
+    }
+
+    /// Write missing parent directory entries for a given file path.
+    fn emit_parent_directories(&mut self, file_path: &str) -> CpioResult<u64> {
+        if !self.auto_write_dirs {
+            return Ok(0);
+        }
+
+        let parts = file_path.split('/').collect::<Vec<_>>();
+
+        let mut bytes_written = 0;
+
+        for idx in 1..parts.len() {
+            let dir = parts
+                .clone()
+                .into_iter()
+                .take(idx)
+                .collect::<Vec<&str>>()
+                .join("/");
+
+            if !self.seen_dirs.contains(&dir) {
+                let mut header = self.next_header();
+                header.mode = self.default_mode_dir;
+                header.nlink = 2;
+                header.name = dir.clone();
+
+                bytes_written += self.write_header(&header)?;
+                self.seen_dirs.insert(dir);
+            }
+        }
+
+        Ok(bytes_written)
+    }
+
+    /// Append a raw header and corresponding file data to the writer.
+    ///
+    /// The writer and data are written as-is. This includes the header's
+    /// checksum field when writing the CRC variant. Padding is added as
+    /// necessary.
+    ///
+    /// Only simple validation that the data length matches the length advertised
+    /// in the header is performed.
+    ///
+    /// Automatic directory emission is not processed in this mode.
+    pub fn append_header_with_data(
+        &mut self,
+        header: NewcHeader,
+        data: impl AsRef<[u8]>,
+    ) -> CpioResult<u64> {
+        let data = data.as_ref();
+
+        if header.file_size as usize != data.len() {
+            return Err(Error::SizeMismatch);
+        }
+
+        let written = self.write_header(&header)?;
+
+        Ok(written + self.write_data(data)?)
+    }
+
+    /// Append a raw header and corresponding data from a reader to the writer.
+    ///
+    /// The header's file size must match the length of data available in the reader
+    /// or errors could occur. This method will copy all data available in the reader
+    /// to the output stream. If the number of bytes written does not match what is
+    /// reported by the header, the cpio archive stream is effectively corrupted
+    /// and an error is returned.
+    pub fn append_header_with_reader(
+        &mut self,
+        header: NewcHeader,
+        reader: &mut impl Read,
+    ) -> CpioResult<u64> {
+        let written = self.write_header(&header)?;
+        let copied = std::io::copy(reader, &mut self.writer)?;
+
+        if copied != header.file_size {
+            Err(Error::SizeMismatch)
+        } else {
+            Ok(written + copied + self.write_data_pad(copied)?)
+        }
+    }
+
+    /// Write a regular file to the cpio archive with provided file data and file mode.
+    pub fn append_file_from_data(
+        &mut self,
+        archive_path: impl ToString,
+        data: impl AsRef<[u8]>,
+        mode: u32,
+    ) -> CpioResult<u64> {
+        let archive_path = self.normalize_archive_path(&archive_path.to_string());
+        let data = data.as_ref();
+
+        let mut bytes_written = self.emit_parent_directories(&archive_path)?;
+
+        let mut header = self.next_header();
+        header.name = archive_path;
+        header.file_size = data.len() as _;
+        header.mode = mode;
+        if self.crc {
+            header.checksum = checksum(data);
+        }
+
+        bytes_written += self.write_header(&header)?;
+        bytes_written += self.write_data(data)?;
+
+        Ok(bytes_written)
+    }
+
+    /// Write a group of hard links to the same file data.
+    ///
+    /// All entries share a single inode and have their link count set to the
+    /// number of paths. Following the convention of other newc writers, file
+    /// data is attached to the final entry and preceding entries have a file
+    /// size of 0.
+    pub fn append_hard_links_from_data(
+        &mut self,
+        archive_paths: impl IntoIterator<Item = impl ToString>,
+        data: impl AsRef<[u8]>,
+        mode: u32,
+    ) -> CpioResult<u64> {
+        let archive_paths = archive_paths
+            .into_iter()
+            .map(|p| self.normalize_archive_path(&p.to_string()))
+            .collect::<Vec<_>>();
+        let data = data.as_ref();
+
+        if archive_paths.is_empty() {
+            return Err(Error::EmptyHardLinkGroup);
+        }
+
+        let mut bytes_written = 0;
+
+        // Emit parent directories first, so inode number is sequential.
+        for path in &archive_paths {
+            bytes_written += self.emit_parent_directories(path)?;
+        }
+
+        let template = self.next_header();
+        let last_index = archive_paths.len() - 1;
+
+        for (i, path) in archive_paths.into_iter().enumerate() {
+            let mut header = template.clone();
+            header.name = path;
+            header.mode = mode;
+            header.nlink = last_index as u32 + 1;
+
+            if i == last_index {
+                header.file_size = data.len() as _;
+                if self.crc {
+                    header.checksum = checksum(data);
+                }
+
+                bytes_written += self.write_header(&header)?;
+                bytes_written += self.write_data(data)?;
+            } else {
+                bytes_written += self.write_header(&header)?;
+            }
+        }
+
+        Ok(bytes_written)
+    }
+
+    /// Write a regular file to the cpio archive.
+    ///
+    /// This takes the relative path in the archive and the filesystem path of
+    /// the file to write. It resolves header metadata automatically given filesystem
+    /// attributes. However, the UID, GID, and mtime defaults specified on this
+    /// builder are used so archive construction is more deterministic.
+    ///
+    /// When writing the CRC variant, the file is read twice: once to compute
+    /// the checksum and again to copy its content.
+    pub fn append_file_from_path(
+        &mut self,
+        archive_path: impl ToString,
+        path: impl AsRef<Path>,
+    ) -> CpioResult<u64> {
+        let archive_path = self.normalize_archive_path(&archive_path.to_string());
+        let path = path.as_ref();
+
+        let mut fh = std::fs::File::open(path)?;
+        let metadata = fh.metadata()?;
+
+        if !metadata.is_file() {
+            return Err(Error::NotAFile(path.to_path_buf()));
+        }
+
+        // Emit parent directories first, so inode number is sequential.
+        let mut bytes_written = self.emit_parent_directories(&archive_path)?;
+
+        let mut header = self.next_header();
+        header.name = archive_path;
+        header.file_size = metadata.len();
+
+        if path.is_executable() {
+            header.mode |= S_IXUSR | S_IXGRP | S_IXOTH;
+        }
+
+        if self.crc {
+            let mut buffer = vec![0u8; 32768];
+            loop {
+                let count = fh.read(&mut buffer)?;
+                if count == 0 {
+                    break;
+                }
+
+                header.checksum = header.checksum.wrapping_add(checksum(&buffer[0..count]));
+            }
+
+            fh.seek(SeekFrom::Start(0))?;
+        }
+
+        bytes_written += self.append_header_with_reader(header, &mut fh)?;
+
+        Ok(bytes_written)
+    }
+
+    /// Append a [FileManifest] to the archive.
+    pub fn append_file_manifest(&mut self, manifest: &FileManifest) -> CpioResult<u64> {
+        let mut bytes_written = 0;
+
+        for (path, entry) in manifest.iter_entries() {
+            let mode = if entry.is_executable() { 0o755 } else { 0o644 };
+            let data = entry.resolve_content()?;
+
+            bytes_written +=
+                self.append_file_from_data(path.display().to_string(), data, S_IFREG | mode)?;
+        }
+
+        Ok(bytes_written)
+    }
+
+    /// Finish writing the archive.
+    ///
+    /// This will emit a special header denoting the end of archive.
+    ///
+    /// Failure to call this method will result in a malformed cpio archive.
+    /// Readers may or may not handle the missing trailer correctly.
+    pub fn finish(&mut self) -> CpioResult<u64> {
+        if !self.finished {
+            let mut header = self.next_header();
+            header.mode = 0;
+            header.nlink = 1;
+            header.mtime = 0;
+            header.name = TRAILER.to_string();
+            let count = self.write_header(&header)?;
+            self.finished = true;
+
+            Ok(count)
+        } else {
+            Ok(0)
+        }
+    }
+
+    /// Consume self and return the original writer this instance was constructed from.
+    ///
+    /// This will automatically finish the archive if needed.
+    pub fn into_inner(mut self) -> CpioResult<W> {
+        self.finish()?;
+
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::io::Cursor};
+
+    fn read_archive(data: Vec<u8>) -> Vec<(NewcHeader, Vec<u8>)> {
+        let mut reader = Cursor::new(data);
+        let mut entries = vec![];
+
+        loop {
+            let mut magic = [0u8; 6];
+            reader.read_exact(&mut magic).unwrap();
+            assert!(magic == MAGIC || magic == MAGIC_CRC);
+
+            let header = NewcHeader::from_reader(&mut reader).unwrap();
+            if header.name == TRAILER {
+                break;
+            }
+
+            let mut data = vec![0u8; header.file_size as usize];
+            reader.read_exact(&mut data).unwrap();
+            let mut pad = vec![0u8; pad_length(data.len())];
+            reader.read_exact(&mut pad).unwrap();
+
+            entries.push((header, data));
+        }
+
+        assert_eq!(reader.position() as usize % 4, 0);
+
+        entries
+    }
+
+    #[test]
+    fn write_single_file() {
+        let mut builder = NewcBuilder::new(Cursor::new(Vec::<u8>::new()));
+
+        let current_exe = std::env::current_exe().unwrap();
+        let current_exe_data = std::fs::read(&current_exe).unwrap();
+        builder
+            .append_file_from_path("child/grandchild/exe", current_exe)
+            .unwrap();
+        builder
+            .append_file_from_data("child/a", b"a", 0o100644)
+            .unwrap();
+
+        let mut reader = builder.into_inner().unwrap();
+        reader.set_position(0);
+
+        let mut reader = NewcReader::new(reader);
+
+        let mut i = 0;
+        while let Some(header) = reader.read_next().unwrap() {
+            let mut file_data = Vec::<u8>::with_capacity(header.file_size() as _);
+            reader.read_to_end(&mut file_data).unwrap();
+
+            let wanted_filename = match i {
+                0 => ".",
+                1 => "./child",
+                2 => "./child/grandchild",
+                3 => "./child/grandchild/exe",
+                4 => "./child/a",
+                _ => panic!("unexpected entry in archive: {:?}", header),
+            };
+
+            assert_eq!(header.name(), wanted_filename);
+            assert_eq!(header.inode(), i);
+
+            if (0..=2).contains(&i) {
+                assert_eq!(header.file_size(), 0);
+                assert_ne!(header.mode() & S_IFDIR, 0);
+            }
+
+            if i == 3 {
+                assert_eq!(&file_data, &current_exe_data);
+                assert_ne!(header.mode() & S_IFREG, 0);
+            }
+
+            if i == 4 {
+                assert_eq!(file_data, b"a");
+            }
+
+            i += 1;
+        }
+
+        assert_eq!(i, 5);
+    }
+
+    #[test]
+    fn write_crc() {
+        let mut builder = NewcBuilder::new(Vec::<u8>::new());
+        builder.crc(true);
+        builder.auto_write_dirs(false);
+
+        let current_exe = std::env::current_exe().unwrap();
+        let current_exe_data = std::fs::read(&current_exe).unwrap();
+        builder.append_file_from_path("exe", current_exe).unwrap();
+        builder
+            .append_file_from_data("foo", b"foo", 0o100644)
+            .unwrap();
+
+        let data = builder.into_inner().unwrap();
+        assert_eq!(&data[0..6], MAGIC_CRC);
+
+        let entries = read_archive(data);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].0.name, "./exe");
+        assert_eq!(entries[0].1, current_exe_data);
+        assert_eq!(entries[0].0.checksum, checksum(&current_exe_data));
+        assert_eq!(entries[1].0.name, "./foo");
+        assert_eq!(entries[1].0.checksum, 324);
+    }
+
+    #[test]
+    fn write_hard_links() {
+        let mut builder = NewcBuilder::new(Vec::<u8>::new());
+
+        builder
+            .append_hard_links_from_data(["a/link0", "b/link1"], b"data", 0o100755)
+            .unwrap();
+        builder.append_file_from_data("c", b"c", 0o100644).unwrap();
+
+        let entries = read_archive(builder.into_inner().unwrap());
+        let names = entries
+            .iter()
+            .map(|(h, _)| h.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![".", "./a", "./b", "./a/link0", "./b/link1", "./c"]
+        );
+
+        let (link0, link0_data) = &entries[3];
+        let (link1, link1_data) = &entries[4];
+        assert_eq!(link0.inode, link1.inode);
+        assert_eq!(link0.nlink, 2);
+        assert_eq!(link1.nlink, 2);
+        assert!(link0_data.is_empty());
+        assert_eq!(link1_data, b"data");
+        assert_ne!(entries[5].0.inode, link1.inode);
+        assert_eq!(entries[5].0.nlink, 1);
+    }
+}
@@ -13,13 +13,14 @@ this crate relies on for certificate parsing functionality.
 */
 
 use {
-    crate::asn1::rfc3281::AttributeCertificate,
+    crate::{asn1::rfc3281::AttributeCertificate, asn1_value_contents},
     bcder::{
         decode::{Constructed, Malformed, Source, Unimplemented},
         encode,
         encode::{PrimitiveContent, Values},
         BitString, Captured, ConstOid, Integer, Mode, OctetString, Oid, Tag,
     },
+    bytes::Bytes,
     std::{
         fmt::{Debug, Formatter},
         io::Write,
@@ -194,6 +195,20 @@ impl SignedData {
     }
 
     pub fn encode_ref(&self) -> impl Values + '_ {
+        self.encode_ref_with_content_info(self.content_info.encode_ref())
+    }
+
+    /// Encode with PKCS #7 encapsulated content.
+    ///
+    /// See [EncapsulatedContentInfo::encode_ref_pkcs7].
+    pub(crate) fn encode_ref_pkcs7<'a>(&'a self, content: &'a [u8]) -> impl Values + 'a {
+        self.encode_ref_with_content_info(self.content_info.encode_ref_pkcs7(content))
+    }
+
+    fn encode_ref_with_content_info<'a>(
+        &'a self,
+        content_info: impl Values + 'a,
+    ) -> impl Values + 'a {
         encode::sequence((
             OID_ID_SIGNED_DATA.encode_ref(),
             encode::sequence_as(
@@ -201,7 +216,7 @@ impl SignedData {
                 encode::sequence((
                     self.version.encode(),
                     self.digest_algorithms.encode_ref(),
-                    self.content_info.encode_ref(),
+                    content_info,
                     self.certificates
                         .as_ref()
                         .map(|certs| certs.encode_ref_as(Tag::CTX_0)),
@@ -295,6 +310,19 @@ impl SignerInfos {
     }
 }
 
+/// An already DER encoded ASN.1 value that is written as-is.
+struct RawValue<'a>(&'a [u8]);
+
+impl<'a> Values for RawValue<'a> {
+    fn encoded_len(&self, _: Mode) -> usize {
+        self.0.len()
+    }
+
+    fn write_encoded<W: Write>(&self, _: Mode, target: &mut W) -> Result<(), std::io::Error> {
+        target.write_all(self.0)
+    }
+}
+
 /// Encapsulated content info.
 ///
 /// ```ASN.1
@@ -302,6 +330,12 @@ impl SignerInfos {
 ///   eContentType ContentType,
 ///   eContent [0] EXPLICIT OCTET STRING OPTIONAL }
 /// ```
+///
+/// PKCS #7 (RFC 2315) defines `content` as `[0] EXPLICIT ANY DEFINED BY contentType`
+/// instead of an `OCTET STRING`. Some formats (notably Authenticode) rely on this.
+/// When such content is decoded, `content` holds the contents octets of the
+/// embedded value, which is what PKCS #7 digests. Encoding via [Self::encode_ref]
+/// will always emit an `OCTET STRING`.
 #[derive(Clone, Eq, PartialEq)]
 pub struct EncapsulatedContentInfo {
     pub content_type: ContentType,
@@ -329,8 +363,18 @@ impl EncapsulatedContentInfo {
     pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
         cons.take_sequence(|cons| {
             let content_type = ContentType::take_from(cons)?;
-            let content =
-                cons.take_opt_constructed_if(Tag::CTX_0, |cons| OctetString::take_from(cons))?;
+            let content = cons.take_opt_constructed_if(Tag::CTX_0, |cons| {
+                if let Some(content) = OctetString::take_opt_from(cons)? {
+                    Ok(content)
+                } else {
+                    let captured = cons.capture_one()?;
+
+                    match asn1_value_contents(captured.as_slice()) {
+                        Some(data) => Ok(OctetString::new(Bytes::copy_from_slice(data))),
+                        None => Err(Malformed.into()),
+                    }
+                }
+            })?;
 
             Ok(Self {
                 content_type,
@@ -347,6 +391,17 @@ impl EncapsulatedContentInfo {
                 .map(|content| encode::sequence_as(Tag::CTX_0, content.encode_ref())),
         ))
     }
+
+    /// Encode with PKCS #7 content.
+    ///
+    /// `content` is the complete DER encoding of the value to embed in place of
+    /// the `OCTET STRING`. The `content` field of this instance is ignored.
+    pub(crate) fn encode_ref_pkcs7<'a>(&'a self, content: &'a [u8]) -> impl Values + 'a {
+        encode::sequence((
+            self.content_type.encode_ref(),
+            encode::sequence_as(Tag::CTX_0, RawValue(content)),
+        ))
+    }
 }
 
 /// Per-signer information.
@@ -98,7 +98,8 @@ use {
     },
     x509_certificate::{
         certificate::certificate_is_subset_of, rfc3280::Name, CapturedX509Certificate,
-        DigestAlgorithm, SignatureAlgorithm, X509Certificate, X509CertificateError,
+        CertificateChainValidator, DigestAlgorithm, SignatureAlgorithm, ValidationProblem,
+        X509Certificate, X509CertificateError,
     },
 };
 
@@ -270,6 +271,48 @@ impl From<X509CertificateError> for CmsError {
     }
 }
 
+/// Obtain the contents octets of a DER encoded ASN.1 value.
+///
+/// This strips the identifier and length octets from the value. Returns `None`
+/// if the data isn't a single well-formed value using a definite length.
+pub(crate) fn asn1_value_contents(data: &[u8]) -> Option<&[u8]> {
+    let mut offset = 1;
+
+    // High tag number form.
+    if data.first()? & 0x1f == 0x1f {
+        while data.get(offset)? & 0x80 != 0 {
+            offset += 1;
+        }
+        offset += 1;
+    }
+
+    let first = *data.get(offset)?;
+    offset += 1;
+
+    let length = if first & 0x80 == 0 {
+        first as usize
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > std::mem::size_of::<usize>() {
+            return None;
+        }
+
+        let mut length = 0usize;
+        for _ in 0..count {
+            length = (length << 8) | *data.get(offset)? as usize;
+            offset += 1;
+        }
+
+        length
+    };
+
+    if data.len() - offset == length {
+        Some(&data[offset..])
+    } else {
+        None
+    }
+}
+
 /// Represents a CMS SignedData structure.
 ///
 /// This is the high-level type representing a CMS signature of some data.
@@ -638,6 +681,57 @@ impl SignerInfo {
         }
     }
 
+    /// Whether a certificate is the one referenced by this signer.
+    fn certificate_matches(&self, cert: &CapturedX509Certificate) -> bool {
+        // The located certificate determines which key verifies signatures and whose
+        // chain [Self::validate_signing_certificate()] validates, so this matching is
+        // security sensitive. The called function does non-exact matching of the
+        // RdnSequence in case the candidate certs have extra fields. A loosely matched
+        // certificate that didn't produce the signature fails signature verification.
+        // So callers must verify signatures against the same certificates they validate.
+        certificate_is_subset_of(
+            &self.serial_number,
+            &self.issuer,
+            cert.serial_number_asn1(),
+            cert.issuer_name(),
+        )
+    }
+
+    /// Resolve the certificate that produced this signature from a [SignedData].
+    ///
+    /// Returns `None` if the certificate isn't embedded in the [SignedData].
+    pub fn signing_certificate<'a>(
+        &self,
+        signed_data: &'a SignedData,
+    ) -> Option<&'a CapturedX509Certificate> {
+        signed_data
+            .certificates()
+            .find(|cert| self.certificate_matches(cert))
+    }
+
+    /// Validate the certificate chain of the signer using certificates in a [SignedData].
+    ///
+    /// The signing certificate and its issuers are resolved from the certificates
+    /// embedded in `signed_data` and validated with `validator`.
+    ///
+    /// Validation occurs at the time configured on `validator`. Callers will
+    /// typically want to set it to [SignedAttributes::signing_time()] so that
+    /// signatures made while a certificate was valid remain valid after it expires.
+    ///
+    /// Returns problems found with the chain. An empty vector means the chain is
+    /// valid. An error occurs if the signing certificate isn't present.
+    pub fn validate_signing_certificate(
+        &self,
+        signed_data: &SignedData,
+        validator: &CertificateChainValidator,
+    ) -> Result<Vec<ValidationProblem>, CmsError> {
+        let signing_cert = self
+            .signing_certificate(signed_data)
+            .ok_or(CmsError::CertificateNotFound)?;
+
+        Ok(validator.validate(signing_cert, signed_data.certificates()))
+    }
+
     /// Obtain an entity for validating the signature described by this instance.
     ///
     /// This will attempt to locate the certificate used by this signing info
@@ -658,19 +752,7 @@ impl SignerInfo {
     {
         // The issuer of this signature is matched against the list of certificates.
         let signing_cert = certs
-            .find(|cert| {
-                // We're only verifying signatures here, not validating the certificate.
-                // So even if the certificate comparison functionality is incorrect
-                // (the called function does non-exact matching of the RdnSequence in
-                // case the candidate certs have extra fields), that shouldn't have
-                // security implications.
-                certificate_is_subset_of(
-                    &self.serial_number,
-                    &self.issuer,
-                    cert.serial_number_asn1(),
-                    cert.issuer_name(),
-                )
-            })
+            .find(|cert| self.certificate_matches(cert))
             .ok_or(CmsError::CertificateNotFound)?;
 
         let key_algorithm = signing_cert.key_algorithm().ok_or_else(|| {
@@ -709,6 +791,43 @@ impl SignerInfo {
         }
     }
 
+    /// Resolve a [SignedData] stored in an arbitrary unsigned attribute.
+    ///
+    /// This is like [Self::time_stamp_token_signed_data] except the attribute
+    /// type is specified explicitly. This is useful for formats that store
+    /// time-stamp tokens or nested signatures under non-standard attribute types.
+    /// e.g. Authenticode stores RFC 3161 time-stamp tokens under
+    /// `1.3.6.1.4.1.311.3.3.1`.
+    ///
+    /// Returns `Ok(None)` if there is no attribute with the requested type.
+    pub fn unsigned_attribute_signed_data(
+        &self,
+        typ: &Oid,
+    ) -> Result<Option<SignedData>, CmsError> {
+        let attr = if let Some(attr) = self
+            .unsigned_attributes()
+            .and_then(|attrs| attrs.raw.iter().find(|attr| &attr.typ == typ))
+        {
+            attr
+        } else {
+            return Ok(None);
+        };
+
+        if attr.values.len() != 1 {
+            return Err(CmsError::MalformedUnsignedAttributeTimeStampToken);
+        }
+
+        let signed_data = attr
+            .values
+            .get(0)
+            .unwrap()
+            .deref()
+            .clone()
+            .decode(|cons| crate::asn1::rfc5652::SignedData::decode(cons))?;
+
+        Ok(Some(SignedData::try_from(&signed_data)?))
+    }
+
     /// Verify the time-stamp token in this instance.
     ///
     /// The time-stamp token is a SignedData ASN.1 structure embedded as an unsigned
@@ -954,7 +1073,10 @@ impl TryFrom<&crate::asn1::rfc5652::SignerInfo> for SignerInfo {
                         })
                         .transpose()?;
 
-                Some(UnsignedAttributes { time_stamp_token })
+                Some(UnsignedAttributes {
+                    time_stamp_token,
+                    raw: attributes.clone(),
+                })
             } else {
                 None
             };
@@ -1027,6 +1149,15 @@ impl Debug for SignedAttributes {
 pub struct UnsignedAttributes {
     /// Time-Stamp Token from a Time-Stamp Protocol server.
     time_stamp_token: Option<crate::asn1::rfc5652::SignedData>,
+
+    /// The raw ASN.1 unsigned attributes.
+    raw: crate::asn1::rfc5652::UnsignedAttributes,
+}
+
+impl UnsignedAttributes {
+    pub fn attributes(&self) -> &crate::asn1::rfc5652::UnsignedAttributes {
+        &self.raw
+    }
 }
 
 #[cfg(test)]
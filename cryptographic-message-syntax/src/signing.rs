@@ -16,6 +16,7 @@ use {
                 OID_MESSAGE_DIGEST, OID_SIGNING_TIME,
             },
         },
+        asn1_value_contents,
         time_stamp_protocol::{time_stamp_message_http, TimeStampError},
         CmsError,
     },
@@ -63,6 +64,9 @@ pub struct SignerBuilder<'a> {
 
     /// Time-Stamp Protocol (TSP) server HTTP URL to use.
     time_stamp_url: Option<reqwest::Url>,
+
+    /// The unsigned attribute type to store the time-stamp token under.
+    time_stamp_attribute_oid: Oid,
 }
 
 impl<'a> SignerBuilder<'a> {
@@ -81,6 +85,7 @@ impl<'a> SignerBuilder<'a> {
             content_type: Oid(Bytes::copy_from_slice(OID_ID_DATA.as_ref())),
             extra_signed_attributes: Vec::new(),
             time_stamp_url: None,
+            time_stamp_attribute_oid: Oid(Bytes::copy_from_slice(OID_TIME_STAMP_TOKEN.as_ref())),
         }
     }
 
@@ -140,6 +145,17 @@ impl<'a> SignerBuilder<'a> {
         self.time_stamp_url = Some(url.into_url()?);
         Ok(self)
     }
+
+    /// Define the unsigned attribute type the time-stamp token is stored under.
+    ///
+    /// Defaults to `id-aa-timeStampToken` (RFC 3161). Some formats use their own
+    /// attribute type for the same token. e.g. Authenticode uses
+    /// `1.3.6.1.4.1.311.3.3.1`.
+    #[must_use]
+    pub fn time_stamp_attribute_oid(mut self, oid: Oid) -> Self {
+        self.time_stamp_attribute_oid = oid;
+        self
+    }
 }
 
 /// Entity for incrementally deriving a SignedData primitive.
@@ -149,6 +165,9 @@ pub struct SignedDataBuilder<'a> {
     /// Encapsulated content to sign.
     signed_content: Option<Vec<u8>>,
 
+    /// Whether `signed_content` is a DER encoded ASN.1 value to embed as-is.
+    signed_content_is_raw: bool,
+
     /// Entities who will generated signatures.
     signers: Vec<SignerBuilder<'a>>,
 
@@ -167,6 +186,7 @@ impl<'a> Default for SignedDataBuilder<'a> {
     fn default() -> Self {
         Self {
             signed_content: None,
+            signed_content_is_raw: false,
             signers: vec![],
             certificates: vec![],
             content_type: Oid(OID_ID_SIGNED_DATA.as_ref().into()),
@@ -181,9 +201,40 @@ impl<'a> SignedDataBuilder<'a> {
     #[must_use]
     pub fn signed_content(mut self, data: Vec<u8>) -> Self {
         self.signed_content = Some(data);
+        self.signed_content_is_raw = false;
         self
     }
 
+    /// Define DER encoded ASN.1 content to sign.
+    ///
+    /// Unlike [Self::signed_content], the content is embedded directly instead of
+    /// being wrapped in an `OCTET STRING`. This is the PKCS #7 form of signed
+    /// data, which some formats (e.g. Authenticode) require. As PKCS #7 mandates,
+    /// the `message-digest` attribute is computed over the contents octets of the
+    /// value, excluding its identifier and length octets.
+    ///
+    /// Errors if the data isn't a single DER encoded value.
+    pub fn signed_content_asn1(mut self, data: Vec<u8>) -> Result<Self, CmsError> {
+        if asn1_value_contents(&data).is_none() {
+            return Err(CmsError::DecodeErr(bcder::decode::Error::Malformed));
+        }
+
+        self.signed_content = Some(data);
+        self.signed_content_is_raw = true;
+        Ok(self)
+    }
+
+    /// Resolve the octets of signed content that are digested and stored.
+    ///
+    /// For PKCS #7 content, this is the contents octets of the ASN.1 value.
+    fn signed_content_octets<'b>(&self, content: &'b [u8]) -> &'b [u8] {
+        if self.signed_content_is_raw {
+            asn1_value_contents(content).expect("validated when content was registered")
+        } else {
+            content
+        }
+    }
+
     /// Add a signer.
     ///
     /// The signer is the thing generating the cryptographic signature over
@@ -274,7 +325,7 @@ impl<'a> SignedDataBuilder<'a> {
             if let Some(content) = &signer.message_id_content {
                 hasher.update(content);
             } else if let Some(content) = &self.signed_content {
-                hasher.update(content);
+                hasher.update(self.signed_content_octets(content));
             }
 
             signed_attributes.push(Attribute {
@@ -350,7 +401,7 @@ impl<'a> SignedDataBuilder<'a> {
 
                 let mut unsigned_attributes = UnsignedAttributes::default();
                 unsigned_attributes.push(Attribute {
-                    typ: Oid(Bytes::copy_from_slice(OID_TIME_STAMP_TOKEN.as_ref())),
+                    typ: signer.time_stamp_attribute_oid.clone(),
                     values: vec![AttributeValue::new(Captured::from_values(
                         Mode::Der,
                         signed_data.encode_ref(),
@@ -391,10 +442,9 @@ impl<'a> SignedDataBuilder<'a> {
             digest_algorithms,
             content_info: EncapsulatedContentInfo {
                 content_type: self.content_type.clone(),
-                content: self
-                    .signed_content
-                    .as_ref()
-                    .map(|content| OctetString::new(Bytes::copy_from_slice(content))),
+                content: self.signed_content.as_ref().map(|content| {
+                    OctetString::new(Bytes::copy_from_slice(self.signed_content_octets(content)))
+                }),
             },
             certificates: if certificates.is_empty() {
                 None
@@ -406,9 +456,18 @@ impl<'a> SignedDataBuilder<'a> {
         };
 
         let mut ber = Vec::new();
-        signed_data
-            .encode_ref()
-            .write_encoded(Mode::Der, &mut ber)?;
+        match &self.signed_content {
+            Some(content) if self.signed_content_is_raw => {
+                signed_data
+                    .encode_ref_pkcs7(content)
+                    .write_encoded(Mode::Der, &mut ber)?;
+            }
+            _ => {
+                signed_data
+                    .encode_ref()
+                    .write_encoded(Mode::Der, &mut ber)?;
+            }
+        }
 
         Ok(ber)
     }
@@ -419,7 +478,11 @@ mod tests {
     use {
         super::*,
         crate::SignedData,
-        x509_certificate::{testutil::*, EcdsaCurve},
+        chrono::{Duration, Utc},
+        x509_certificate::{
+            asn1time::Time, rfc5280, testutil::*, CertificateChainValidator, EcdsaCurve,
+            KeyAlgorithm, Sign, ValidationProblem, X509CertificateBuilder,
+        },
     };
 
     const DIGICERT_TIMESTAMP_URL: &str = "http://timestamp.digicert.com";
@@ -451,6 +514,56 @@ mod tests {
         }
     }
 
+    #[test]
+    fn asn1_content_signature() {
+        let key = rsa_private_key();
+        let cert = rsa_cert();
+
+        // SEQUENCE { INTEGER 42 }
+        let content = vec![0x30, 0x03, 0x02, 0x01, 0x2a];
+
+        let ber = SignedDataBuilder::default()
+            .signed_content_asn1(content)
+            .unwrap()
+            .signer(SignerBuilder::new(&key, cert))
+            .build_der()
+            .unwrap();
+
+        // The content is embedded directly, not as an OCTET STRING.
+        assert!(ber
+            .windows(7)
+            .any(|x| x == [0xa0, 0x05, 0x30, 0x03, 0x02, 0x01, 0x2a]));
+
+        let raw = crate::asn1::rfc5652::SignedData::decode_ber(&ber).unwrap();
+        assert_eq!(
+            raw.content_info
+                .content
+                .as_ref()
+                .map(|x| x.to_bytes().to_vec()),
+            Some(vec![0x02, 0x01, 0x2a])
+        );
+
+        // The contents octets are what is digested and exposed.
+        let signed_data = crate::SignedData::parse_ber(&ber).unwrap();
+        assert_eq!(
+            signed_data.signed_content(),
+            Some([0x02, 0x01, 0x2a].as_ref())
+        );
+
+        for signer in signed_data.signers() {
+            signer
+                .verify_message_digest_with_signed_data(&signed_data)
+                .unwrap();
+            signer
+                .verify_signature_with_signed_data(&signed_data)
+                .unwrap();
+        }
+
+        assert!(SignedDataBuilder::default()
+            .signed_content_asn1(vec![0x30, 0x05, 0x02])
+            .is_err());
+    }
+
     #[test]
     fn time_stamp_url() {
         let key = rsa_private_key();
@@ -528,4 +641,70 @@ mod tests {
                 .unwrap();
         }
     }
+
+    #[test]
+    fn validate_signing_certificate() {
+        let (ca, ca_key) = certificate_authority_ed25519();
+
+        let mut builder = X509CertificateBuilder::new(KeyAlgorithm::Ed25519);
+        builder
+            .subject()
+            .append_common_name_utf8_string("signer")
+            .unwrap();
+        builder.serial_number(42);
+        let (cert, key) = issue_certificate(&builder, &ca, &ca_key);
+
+        let cms = SignedDataBuilder::default()
+            .signed_content(vec![42])
+            .certificate(cert.clone())
+            .certificate(ca.clone())
+            .signer(SignerBuilder::new(&key, cert))
+            .build_der()
+            .unwrap();
+
+        let signed_data = SignedData::parse_ber(&cms).unwrap();
+        let signer = signed_data.signers().next().unwrap();
+
+        let mut validator = CertificateChainValidator::default();
+        assert!(signer
+            .validate_signing_certificate(&signed_data, &validator)
+            .unwrap()
+            .is_empty());
+
+        // The signing certificate expired.
+        let mut expired = validator.clone();
+        expired.validation_time(Utc::now() + Duration::hours(2));
+        let problems = signer
+            .validate_signing_certificate(&signed_data, &expired)
+            .unwrap();
+        assert!(problems.iter().any(
+            |p| matches!(p, ValidationProblem::Expired { subject, .. } if subject == "signer")
+        ));
+
+        // The signing certificate was revoked.
+        validator.add_crl(sign_crl(
+            rfc5280::TbsCertList {
+                version: Some(rfc5280::Version::V2),
+                signature: ca_key.signature_algorithm().unwrap().into(),
+                issuer: ca.subject_name().clone(),
+                this_update: Time::from(Utc::now()),
+                next_update: None,
+                revoked_certificates: vec![rfc5280::RevokedCertificate {
+                    user_certificate: 42i64.into(),
+                    revocation_date: Time::from(Utc::now() - Duration::minutes(5)),
+                    crl_entry_extensions: None,
+                }],
+                crl_extensions: None,
+                raw_data: None,
+            },
+            &ca_key,
+        ));
+        let problems = signer
+            .validate_signing_certificate(&signed_data, &validator)
+            .unwrap();
+        assert!(matches!(
+            problems.as_slice(),
+            [ValidationProblem::Revoked { subject, .. }] if subject == "signer"
+        ));
+    }
 }
@@ -65,7 +65,7 @@ impl<'a> ChangelogEntry<'a> {
 
 /// Represents a complete `debian/changelog` file.
 ///
-/// Changelogs are an ordered series of `ChangelogEntry` items.
+/// Changelogs are an ordered series of `ChangelogEntry` items, most recent first.
 #[derive(Default)]
 pub struct Changelog<'a> {
     entries: Vec<ChangelogEntry<'a>>,
@@ -77,6 +77,14 @@ impl<'a> Changelog<'a> {
         self.entries.push(entry)
     }
 
+    /// Iterate over entries in this changelog.
+    ///
+    /// Entries are emitted in the order they were added. The first entry is
+    /// conventionally the most recent.
+    pub fn iter_entries(&self) -> impl Iterator<Item = &ChangelogEntry<'a>> {
+        self.entries.iter()
+    }
+
     /// Serialize the changelog to a writer.
     ///
     /// Use of a buffered writer is encouraged if performance is a concern.
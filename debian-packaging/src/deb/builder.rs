@@ -159,7 +159,7 @@ impl<'control> DebBuilder<'control> {
     }
 }
 
-fn new_tar_header(mtime: u64) -> Result<tar::Header> {
+pub(crate) fn new_tar_header(mtime: u64) -> Result<tar::Header> {
     let mut header = tar::Header::new_gnu();
     header.set_uid(0);
     header.set_gid(0);
@@ -10,6 +10,7 @@ use {
         control::ControlParagraphReader,
         error::{DebianError, Result},
     },
+    futures::StreamExt,
     std::{
         io::{Cursor, Read},
         ops::{Deref, DerefMut},
@@ -310,3 +311,37 @@ pub fn resolve_control_file(reader: impl Read) -> Result<BinaryPackageControlFil
 
     Err(DebianError::ControlFileNotFound)
 }
+
+/// Resolve paths of files in the `data.tar` file within a `.deb` archive.
+///
+/// Directories are not included. Paths are normalized to not have a leading `./` or `/`.
+/// e.g. `./usr/bin/foo` becomes `usr/bin/foo`.
+pub async fn resolve_data_paths(reader: impl Read) -> Result<Vec<String>> {
+    let mut reader = BinaryPackageReader::new(reader)?;
+
+    while let Some(entry) = reader.next_entry() {
+        if let BinaryPackageEntry::Data(data) = entry? {
+            let mut entries = data.into_inner().entries()?;
+            let mut paths = vec![];
+
+            while let Some(entry) = entries.next().await {
+                let entry = entry?;
+
+                if entry.header().entry_type().is_dir() {
+                    continue;
+                }
+
+                let path = String::from_utf8_lossy(&entry.path_bytes()).to_string();
+                let path = path.trim_start_matches("./").trim_start_matches('/');
+
+                if !path.is_empty() {
+                    paths.push(path.to_string());
+                }
+            }
+
+            return Ok(paths);
+        }
+    }
+
+    Err(DebianError::DebDataTarNotFound)
+}
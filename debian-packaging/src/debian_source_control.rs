@@ -302,7 +302,7 @@ impl<'a> DebianSourceControlFile<'a> {
         &self,
     ) -> Option<Box<(dyn Iterator<Item = Result<DebianSourceControlFilePackage<'_>>> + '_)>> {
         if let Some(iter) = self.iter_field_lines("Package-List") {
-            Some(Box::new(iter.map(move |v| {
+            Some(Box::new(iter.filter(|v| !v.is_empty()).map(move |v| {
                 let mut words = v.split_ascii_whitespace();
 
                 let name = words
@@ -366,7 +366,8 @@ impl<'a> DebianSourceControlFile<'a> {
         checksum: ChecksumType,
     ) -> Option<Box<(dyn Iterator<Item = Result<DebianSourceControlFileEntry<'_>>> + '_)>> {
         if let Some(iter) = self.iter_field_lines(field) {
-            Some(Box::new(iter.map(move |v| {
+            // Values constructed in memory (as opposed to parsed) begin with an empty line.
+            Some(Box::new(iter.filter(|v| !v.is_empty()).map(move |v| {
                 // Values are of form: <digest> <size> <path>
 
                 let mut parts = v.split_ascii_whitespace();
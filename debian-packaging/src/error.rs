@@ -65,6 +65,9 @@ pub enum DebianError {
     #[error("unknown compression in deb archive file: {0}")]
     DebUnknownCompression(String),
 
+    #[error("data.tar not found in deb archive")]
+    DebDataTarNotFound,
+
     #[error("do not know how to construct repository reader from URL: {0}")]
     RepositoryReaderUnrecognizedUrl(String),
 
@@ -104,6 +107,27 @@ pub enum DebianError {
     #[error(".deb not available: {0}")]
     RepositoryBuildDebNotAvailable(&'static str),
 
+    #[error("index file {0} carried from existing Release file has no {1} digest")]
+    RepositoryBuildUnmanagedIndexMissingDigest(String, &'static str),
+
+    #[error("source package changelog has no entries")]
+    SourcePackageBuildNoChangelogEntries,
+
+    #[error("3.0 (quilt) source packages require a Debian revision in their version: {0}")]
+    SourcePackageBuildNativeVersion(String),
+
+    #[error("changelog package name {0} does not match debian/control Source {1}")]
+    SourcePackageBuildSourceMismatch(String, String),
+
+    #[error("upstream tarball compression not supported by 3.0 (quilt) source packages: {0}")]
+    SourcePackageBuildUnsupportedCompression(String),
+
+    #[error("source package is missing required file: {0}")]
+    SourcePackageBuildMissingFile(&'static str),
+
+    #[error("debian/source/format must be `3.0 (quilt)`; got `{0}`")]
+    SourcePackageBuildBadSourceFormat(String),
+
     #[error("expected 1 paragraph in control file; got {0}")]
     ReleaseControlParagraphMismatch(usize),
 
@@ -128,6 +152,15 @@ pub enum DebianError {
     #[error("No PGP signatures found from the specified key")]
     ReleaseNoSignaturesByKey,
 
+    #[error("No valid PGP signatures found from a trusted key")]
+    ReleaseNoTrustedSignatures,
+
+    #[error("release file expired at {0}")]
+    ReleaseExpired(String),
+
+    #[error("no release verification policy set on repository reader")]
+    ReleaseVerificationPolicyUnset,
+
     #[error("indices files not found in Release file")]
     ReleaseNoIndicesFiles,
 
@@ -152,6 +185,15 @@ pub enum DebianError {
     #[error("unknown verify behavior for null:// destination: {0}")]
     SinkWriterVerifyBehaviorUnknown(String),
 
+    #[error("unknown distribution signing key: {0}")]
+    DistroSigningKeyUnknown(String),
+
+    #[error("no trusted signing keys defined for source repository; set source_unverified to copy without signature verification")]
+    RepositoryCopierNoSigningKeys,
+
+    #[error("source_unverified cannot be combined with source signing keys")]
+    RepositoryCopierConflictingVerification,
+
     #[error("{0}")]
     Other(String),
 }
@@ -63,6 +63,10 @@ This type provides helper functions for resolving common fields on binary contro
 [debian_source_control::DebianSourceControlFile] defines a *control file* for a source package,
 as expressed in a `.dsc` file.
 
+The [source_package_builder] module provides functionality for creating source packages.
+[source_package_builder::SourcePackageBuilder] produces `3.0 (quilt)` source packages from an
+upstream tarball, the content of a `debian/` directory, and a [changelog::Changelog].
+
 There is a meta language for expressing dependencies between Debian packages. The
 [dependency] module defines types for parsing and writing this language. e.g.
 [dependency::DependencyList] represents a parsed list of dependencies like
@@ -128,4 +132,5 @@ pub mod io;
 pub mod package_version;
 pub mod repository;
 pub mod signing_key;
+pub mod source_package_builder;
 pub mod source_package_control;
@@ -16,18 +16,22 @@ use {
     crate::{
         binary_package_control::BinaryPackageControlFile,
         control::{ControlField, ControlParagraph},
-        deb::reader::resolve_control_file,
+        deb::reader::{resolve_control_file, resolve_data_paths},
+        debian_source_control::{DebianSourceControlFile, DebianSourceControlFileFetch},
         error::{DebianError, Result},
         io::{read_compressed, ContentDigest, DataResolver, MultiContentDigest, MultiDigester},
+        package_version::PackageVersion,
         repository::{
-            release::{ChecksumType, ReleaseFile, DATE_FORMAT},
-            Compression, PublishEvent, RepositoryPathVerificationState, RepositoryWriter,
+            contents::ContentsFile,
+            release::{ChecksumType, ClassifiedReleaseFileEntry, ReleaseFile, DATE_FORMAT},
+            Compression, PublishEvent, ReleaseReader, RepositoryPathVerificationState,
+            RepositoryWriter,
         },
     },
     chrono::{DateTime, Utc},
     futures::{AsyncRead, AsyncReadExt, StreamExt, TryStreamExt},
     pgp::{crypto::HashAlgorithm, types::SecretKeyTrait},
-    pgp_cleartext::cleartext_sign,
+    pgp_cleartext::{cleartext_sign, detached_sign},
     std::{
         borrow::Cow,
         collections::{BTreeMap, BTreeSet, HashMap},
@@ -67,8 +71,8 @@ impl Default for PoolLayout {
 }
 
 impl PoolLayout {
-    /// Compute the path to a file given the source package name and its filename.
-    pub fn path(&self, component: &str, package: &str, filename: &str) -> String {
+    /// Compute the directory holding files for a given source package name.
+    pub fn directory(&self, component: &str, package: &str) -> String {
         match self {
             Self::ComponentThenNamePrefix => {
                 let name_prefix = if package.starts_with("lib") {
@@ -77,10 +81,15 @@ impl PoolLayout {
                     format!("{}/{}", &package[0..1], package)
                 };
 
-                format!("pool/{}/{}/{}", component, name_prefix, filename)
+                format!("pool/{}/{}", component, name_prefix)
             }
         }
     }
+
+    /// Compute the path to a file given the source package name and its filename.
+    pub fn path(&self, component: &str, package: &str, filename: &str) -> String {
+        format!("{}/{}", self.directory(component, package), filename)
+    }
 }
 
 /// Describes a reference to a `.deb` Debian package existing somewhere.
@@ -110,6 +119,18 @@ pub trait DebPackageReference<'cf> {
     ///
     /// The control file must have at least `Package`, `Version`, and `Architecture` fields.
     fn control_file_for_packages_index(&self) -> Result<BinaryPackageControlFile<'cf>>;
+
+    /// Obtain the paths of files installed by this package.
+    ///
+    /// Paths are relative to the filesystem root and don't have a leading `/` or `./`.
+    /// e.g. `usr/bin/foo`. They become the content of `Contents` index files.
+    ///
+    /// The default implementation returns `None`, indicating the file list isn't known.
+    /// If any package in a component + architecture doesn't have a known file list, no
+    /// `Contents` file is generated for it.
+    fn data_paths(&self) -> Result<Option<Vec<String>>> {
+        Ok(None)
+    }
 }
 
 /// Holds the content of a `.deb` file in-memory.
@@ -149,6 +170,34 @@ impl<'cf> DebPackageReference<'cf> for InMemoryDebFile {
     fn control_file_for_packages_index(&self) -> Result<BinaryPackageControlFile<'cf>> {
         resolve_control_file(std::io::Cursor::new(&self.data))
     }
+
+    fn data_paths(&self) -> Result<Option<Vec<String>>> {
+        // The data is in memory, so there's no I/O to block on.
+        futures::executor::block_on(resolve_data_paths(std::io::Cursor::new(&self.data))).map(Some)
+    }
+}
+
+/// Describes a reference to a Debian source package existing somewhere.
+///
+/// This is the source package equivalent of [DebPackageReference]. A source package
+/// consists of a `.dsc` file and the files it references.
+pub trait SourcePackageReference<'cf> {
+    /// Obtain the size in bytes of the `.dsc` file.
+    fn dsc_size_bytes(&self) -> Result<u64>;
+
+    /// Obtains the binary digest of the `.dsc` file given a checksum flavor.
+    fn dsc_digest(&self, checksum: ChecksumType) -> Result<ContentDigest>;
+
+    /// Obtain the filename of the `.dsc` file.
+    ///
+    /// This should be just the file name, without any directory components.
+    fn dsc_filename(&self) -> Result<String>;
+
+    /// Obtain a [DebianSourceControlFile] representing the content of the `.dsc` file.
+    ///
+    /// The control file must have at least `Source`, `Version`, and `Files` fields. It
+    /// should also have `Checksums-*` fields for each checksum flavor used by the repository.
+    fn control_file_for_sources_index(&self) -> Result<DebianSourceControlFile<'cf>>;
 }
 
 /// Describes an index file to write.
@@ -209,6 +258,54 @@ type IndexedBinaryPackages<'a> = BTreeMap<(String, String), ControlParagraph<'a>
 // (component, architecture) -> packages.
 type ComponentBinaryPackages<'a> = BTreeMap<(String, String), IndexedBinaryPackages<'a>>;
 
+// (component, architecture) -> (Package, Version) -> paths.
+type ComponentBinaryPackagePaths =
+    BTreeMap<(String, String), BTreeMap<(String, String), Vec<String>>>;
+
+/// Whether a (Package, Version) key matches a package name and optional version.
+fn package_key_matches(key: &(String, String), package: &str, version: Option<&str>) -> bool {
+    key.0 == package
+        && match version {
+            Some(version) => key.1 == version,
+            None => true,
+        }
+}
+
+/// Obtain keys of packages that aren't among the `count` latest versions of a package.
+fn superseded_packages<'a>(
+    keys: impl Iterator<Item = &'a (String, String)>,
+    count: usize,
+) -> Result<Vec<(String, String)>> {
+    let mut versions_by_package: BTreeMap<&str, Vec<(PackageVersion, &(String, String))>> =
+        BTreeMap::new();
+
+    for key in keys {
+        versions_by_package
+            .entry(key.0.as_str())
+            .or_default()
+            .push((PackageVersion::parse(&key.1)?, key));
+    }
+
+    Ok(versions_by_package
+        .into_values()
+        .flat_map(|mut versions| {
+            // Newest first.
+            versions.sort_by(|a, b| b.0.cmp(&a.0));
+
+            versions.into_iter().skip(count).map(|(_, key)| key.clone())
+        })
+        .collect::<Vec<_>>())
+}
+
+/// Obtain the name of the field in `.dsc` and `Sources` files listing files for a checksum.
+fn source_files_field_name(checksum: ChecksumType) -> &'static str {
+    match checksum {
+        ChecksumType::Md5 => "Files",
+        ChecksumType::Sha1 => "Checksums-Sha1",
+        ChecksumType::Sha256 => "Checksums-Sha256",
+    }
+}
+
 /// Build Debian repositories from scratch.
 ///
 /// Instances of this type are used to iteratively construct a Debian repository.
@@ -240,7 +337,9 @@ type ComponentBinaryPackages<'a> = BTreeMap<(String, String), IndexedBinaryPacka
 /// fields are used for.
 ///
 /// After basic metadata is in place, `.deb` packages are registered against the builder via
-/// [Self::add_binary_deb()].
+/// [Self::add_binary_deb()]. Source packages are registered via [Self::add_source_package()].
+/// If the file lists of `.deb`s are known (see [DebPackageReference::data_paths()]), they are
+/// recorded and `Contents` indices are generated.
 ///
 /// Once everything is registered against the builder, it is time to *publish* (read: write)
 /// the repository content.
@@ -265,6 +364,22 @@ type ComponentBinaryPackages<'a> = BTreeMap<(String, String), IndexedBinaryPacka
 /// For convenience, the [Self::publish()] method exists to perform both pool and indices
 /// publishing. It is strongly recommended to call this method instead of the lower-level
 /// methods for writing out content.
+///
+/// # Incremental Publishing
+///
+/// Instead of building a repository from scratch, [Self::from_release_reader()] can be used to
+/// load the state of an existing distribution. Packages can then be added via
+/// [Self::add_binary_deb()] and [Self::add_source_package()], removed via
+/// [Self::remove_binary_package()] and [Self::remove_source_package()], and old versions pruned
+/// via [Self::retain_latest_versions()].
+///
+/// When publishing an instance constructed this way, pool artifacts of loaded packages are
+/// assumed to exist and aren't verified or written. Index files whose content is identical to
+/// what the loaded `Release` file describes aren't written either. The `[In]Release` files are
+/// always regenerated. Entries for index files the builder doesn't generate, such as installer
+/// (udeb) and `Translation` indices, are copied from the loaded `Release` file. Nothing is ever
+/// deleted: pool files of removed packages and `by-hash` files of superseded indices are
+/// retained, so clients holding an older `Release` file can still fetch the files it refers to.
 #[derive(Debug, Default)]
 pub struct RepositoryBuilder<'cf> {
     // Release file fields.
@@ -283,9 +398,17 @@ pub struct RepositoryBuilder<'cf> {
     pool_layout: PoolLayout,
     index_file_compressions: BTreeSet<Compression>,
     binary_packages: ComponentBinaryPackages<'cf>,
+    binary_package_paths: ComponentBinaryPackagePaths,
     installer_packages: ComponentBinaryPackages<'cf>,
     source_packages: BTreeMap<String, IndexedBinaryPackages<'cf>>,
     translations: BTreeMap<String, ()>,
+    // Canonical path -> (size, digest) of index files in a loaded `Release` file.
+    existing_index_files: BTreeMap<String, (u64, ContentDigest)>,
+    // Canonical path -> (size, digests) of index files in a loaded `Release` file that
+    // this builder doesn't generate.
+    unmanaged_index_files: BTreeMap<String, (u64, Vec<ContentDigest>)>,
+    // Pool paths referenced by packages loaded from an existing repository.
+    existing_pool_paths: BTreeSet<String>,
 }
 
 impl<'cf> RepositoryBuilder<'cf> {
@@ -314,9 +437,13 @@ impl<'cf> RepositoryBuilder<'cf> {
                 Compression::Xz,
             ]),
             binary_packages: ComponentBinaryPackages::default(),
+            binary_package_paths: ComponentBinaryPackagePaths::default(),
             installer_packages: ComponentBinaryPackages::default(),
             source_packages: BTreeMap::default(),
             translations: BTreeMap::default(),
+            existing_index_files: BTreeMap::default(),
+            unmanaged_index_files: BTreeMap::default(),
+            existing_pool_paths: BTreeSet::default(),
         }
     }
 
@@ -341,6 +468,188 @@ impl<'cf> RepositoryBuilder<'cf> {
         }
     }
 
+    /// Create a new instance from an existing distribution in a repository.
+    ///
+    /// `Release` file fields (components, architectures, suite, codename, etc) are copied
+    /// from the distribution. The `Date` field is set to the current time and `Valid-Until`
+    /// is not set. Checksums and index file compression formats mirror what the distribution
+    /// uses.
+    ///
+    /// The `Packages` and `Sources` indices of the distribution are loaded. `Contents`
+    /// indices, if present, are used to seed the file lists of binary packages.
+    ///
+    /// Other indices, such as those for installer (udeb) packages and `Translation` files,
+    /// are not loaded. Their `Release` file entries are carried unchanged into `Release`
+    /// files generated by this instance, so the files they refer to must remain in place.
+    ///
+    /// This enables incremental publishing. See the type documentation for more.
+    pub async fn from_release_reader(release: &dyn ReleaseReader) -> Result<Self> {
+        let release_file = release.release_file();
+
+        let mut builder = Self::new_recommended_empty();
+
+        if let Some(architectures) = release_file.architectures() {
+            builder.architectures = architectures.map(|x| x.to_string()).collect();
+        }
+        if let Some(components) = release_file.components() {
+            builder.components = components.map(|x| x.to_string()).collect();
+        }
+        builder.suite = release_file.suite().map(|x| x.to_string());
+        builder.codename = release_file.codename().map(|x| x.to_string());
+        builder.description = release_file.description().map(|x| x.to_string());
+        builder.origin = release_file.origin().map(|x| x.to_string());
+        builder.label = release_file.label().map(|x| x.to_string());
+        builder.version = release_file.version().map(|x| x.to_string());
+        builder.acquire_by_hash = release_file.acquire_by_hash();
+        builder.checksums = [ChecksumType::Md5, ChecksumType::Sha1, ChecksumType::Sha256]
+            .into_iter()
+            .filter(|checksum| release_file.field(checksum.field_name()).is_some())
+            .collect();
+
+        let checksum = release.retrieve_checksum()?;
+
+        if let Some(entries) = release_file.iter_classified_index_files(checksum) {
+            for entry in entries {
+                let entry = entry?;
+
+                let managed = match &entry {
+                    ClassifiedReleaseFileEntry::Contents(entry) => !entry.is_installer,
+                    ClassifiedReleaseFileEntry::Packages(entry) => !entry.is_installer,
+                    ClassifiedReleaseFileEntry::Sources(_) => true,
+                    _ => false,
+                };
+
+                if managed {
+                    builder
+                        .existing_index_files
+                        .insert(entry.path.to_string(), (entry.size, entry.digest.clone()));
+                } else {
+                    builder
+                        .unmanaged_index_files
+                        .insert(entry.path.to_string(), (entry.size, vec![]));
+                }
+            }
+        }
+
+        // Unmanaged entries are carried into regenerated `Release` files, so we need
+        // their digests for every checksum flavor present.
+        for checksum in builder.checksums.clone() {
+            if let Some(entries) = release_file.iter_index_files(checksum) {
+                for entry in entries {
+                    let entry = entry?;
+
+                    if let Some((_, digests)) = builder.unmanaged_index_files.get_mut(entry.path) {
+                        digests.push(entry.digest);
+                    }
+                }
+            }
+        }
+
+        let packages_entries = release
+            .packages_indices_entries()?
+            .into_iter()
+            .filter(|entry| !entry.is_installer)
+            .collect::<Vec<_>>();
+        let sources_entries = release.sources_indices_entries()?;
+
+        let compressions = packages_entries
+            .iter()
+            .map(|entry| entry.compression)
+            .chain(sources_entries.iter().map(|entry| entry.compression))
+            .collect::<BTreeSet<_>>();
+        if !compressions.is_empty() {
+            builder.index_file_compressions = compressions;
+        }
+
+        let binary_keys = packages_entries
+            .iter()
+            .map(|entry| (entry.component.to_string(), entry.architecture.to_string()))
+            .collect::<BTreeSet<_>>();
+
+        for (component, architecture) in binary_keys {
+            let packages = release
+                .resolve_packages(&component, &architecture, false)
+                .await?;
+
+            let indexed = builder
+                .binary_packages
+                .entry((component, architecture))
+                .or_default();
+
+            for cf in packages {
+                let package_key = (cf.package()?.to_string(), cf.version_str()?.to_string());
+
+                if let Some(filename) = cf.field_str("Filename") {
+                    builder.existing_pool_paths.insert(filename.to_string());
+                }
+
+                indexed.insert(package_key, cf.into());
+            }
+        }
+
+        let source_components = sources_entries
+            .iter()
+            .map(|entry| entry.component.to_string())
+            .collect::<BTreeSet<_>>();
+
+        for component in source_components {
+            let packages = release.resolve_sources(&component).await?;
+
+            let indexed = builder.source_packages.entry(component).or_default();
+
+            for cf in packages {
+                let package_key = (
+                    cf.required_field_str("Package")?.to_string(),
+                    cf.version_str()?.to_string(),
+                );
+
+                for fetch in cf.file_fetches(ChecksumType::Md5)? {
+                    builder.existing_pool_paths.insert(fetch?.path);
+                }
+
+                indexed.insert(package_key, cf.into());
+            }
+        }
+
+        let contents_keys = release
+            .contents_indices_entries()?
+            .into_iter()
+            .filter(|entry| !entry.is_installer)
+            .map(|entry| (entry.component.to_string(), entry.architecture.to_string()))
+            .collect::<BTreeSet<_>>();
+
+        for key in contents_keys {
+            let contents = release.resolve_contents(&key.0, &key.1, false).await?;
+
+            let packages = if let Some(packages) = builder.binary_packages.get(&key) {
+                packages
+            } else {
+                continue;
+            };
+
+            for (package_key, para) in packages {
+                let name = match para.field_str("Section") {
+                    Some(section) => format!("{}/{}", section, package_key.0),
+                    None => package_key.0.clone(),
+                };
+
+                let paths = contents
+                    .package_paths(&name)
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>();
+
+                // Packages not in the `Contents` file don't provide any files.
+                builder
+                    .binary_package_paths
+                    .entry(key.clone())
+                    .or_default()
+                    .insert(package_key.clone(), paths);
+            }
+        }
+
+        Ok(builder)
+    }
+
     /// Register an architecture with the builder.
     ///
     /// This defines which platform architectures there will be packages for.
@@ -535,6 +844,9 @@ impl<'cf> RepositoryBuilder<'cf> {
         );
         para.set_field_from_string("Filename".into(), filename.clone().into());
 
+        // The file may have different content than a loaded package occupying the same path.
+        self.existing_pool_paths.remove(&filename);
+
         // `Size` shouldn't be in the original control file, since it is a property of the
         // `.deb` in which the control file is embedded.
         para.set_field_from_string("Size".into(), format!("{}", deb.deb_size_bytes()?).into());
@@ -548,6 +860,16 @@ impl<'cf> RepositoryBuilder<'cf> {
 
         let component_key = (component.to_string(), arch.to_string());
         let package_key = (package.to_string(), version.to_string());
+
+        if let Some(paths) = deb.data_paths()? {
+            self.binary_package_paths
+                .entry(component_key.clone())
+                .or_default()
+                .insert(package_key.clone(), paths);
+        } else if let Some(packages) = self.binary_package_paths.get_mut(&component_key) {
+            packages.remove(&package_key);
+        }
+
         self.binary_packages
             .entry(component_key)
             .or_default()
@@ -556,6 +878,214 @@ impl<'cf> RepositoryBuilder<'cf> {
         Ok(filename)
     }
 
+    /// Add a source package to this repository in the given component.
+    ///
+    /// The package to add is specified as a trait to enable callers to represent source
+    /// packages differently. [crate::source_package_builder::SourcePackage] implements this
+    /// trait.
+    ///
+    /// The specified `component` name must be registered with this instance or an error will
+    /// occur.
+    ///
+    /// Returns the pool directory / `Directory` field that files constituting this source
+    /// package will occupy in the repository. The `.dsc` file and every file it references
+    /// are located in this directory.
+    pub fn add_source_package(
+        &mut self,
+        component: &str,
+        package: &impl SourcePackageReference<'cf>,
+    ) -> Result<String> {
+        if !self.components.contains(component) {
+            return Err(DebianError::RepositoryBuildUnknownComponent(
+                component.to_string(),
+            ));
+        }
+
+        let original_control_file = package.control_file_for_sources_index()?;
+
+        let source = original_control_file.source()?;
+        let version = original_control_file.version_str()?;
+
+        let directory = self.pool_layout.directory(component, source);
+
+        // `Sources` paragraphs are derived from the `.dsc` paragraph. The main differences are
+        // that `Source` is renamed to `Package` and the files lists contain the `.dsc` itself.
+        let mut para = ControlParagraph::default();
+        para.set_field_from_string("Package".into(), source.to_string().into());
+
+        let files_fields = self
+            .checksums
+            .iter()
+            .map(|checksum| source_files_field_name(*checksum))
+            .collect::<Vec<_>>();
+
+        for field in original_control_file.iter_fields() {
+            if !["Source", "Directory"].contains(&field.name())
+                && !files_fields.contains(&field.name())
+            {
+                para.set_field(field.clone());
+            }
+        }
+
+        let dsc_filename = package.dsc_filename()?;
+        let dsc_size = package.dsc_size_bytes()?;
+
+        for checksum in &self.checksums {
+            let field = source_files_field_name(*checksum);
+
+            let lines = original_control_file
+                .iter_field_lines(field)
+                .ok_or_else(|| DebianError::ControlRequiredFieldMissing(field.to_string()))?
+                .filter(|line| !line.is_empty())
+                .map(|line| line.to_string())
+                .collect::<Vec<_>>();
+
+            let dsc_line = format!(
+                "{} {} {}",
+                package.dsc_digest(*checksum)?.digest_hex(),
+                dsc_size,
+                dsc_filename
+            );
+
+            para.set_field(ControlField::from_lines(
+                field.into(),
+                std::iter::once(String::new())
+                    .chain(std::iter::once(dsc_line))
+                    .chain(lines.into_iter()),
+            ));
+        }
+
+        para.set_field_from_string("Directory".into(), directory.clone().into());
+
+        // Files may have different content than a loaded package occupying the same paths.
+        self.existing_pool_paths
+            .remove(&format!("{}/{}", directory, dsc_filename));
+        for entry in original_control_file.files()? {
+            self.existing_pool_paths
+                .remove(&format!("{}/{}", directory, entry?.filename));
+        }
+
+        let package_key = (source.to_string(), version.to_string());
+        self.source_packages
+            .entry(component.to_string())
+            .or_default()
+            .insert(package_key, para);
+
+        Ok(directory)
+    }
+
+    /// Remove binary packages from this repository.
+    ///
+    /// Packages named `package` are removed from all architectures of the given component. If
+    /// `version` is `None`, all versions are removed.
+    ///
+    /// Returns the number of removed packages.
+    ///
+    /// Pool files of removed packages are not deleted when publishing.
+    pub fn remove_binary_package(
+        &mut self,
+        component: &str,
+        package: &str,
+        version: Option<&str>,
+    ) -> usize {
+        let mut removed = 0;
+
+        for ((package_component, _), packages) in self.binary_packages.iter_mut() {
+            if package_component != component {
+                continue;
+            }
+
+            let before = packages.len();
+            packages.retain(|key, _| !package_key_matches(key, package, version));
+            removed += before - packages.len();
+        }
+
+        for ((package_component, _), packages) in self.binary_package_paths.iter_mut() {
+            if package_component == component {
+                packages.retain(|key, _| !package_key_matches(key, package, version));
+            }
+        }
+
+        removed
+    }
+
+    /// Remove source packages from this repository.
+    ///
+    /// If `version` is `None`, all versions of the source package are removed from the
+    /// given component.
+    ///
+    /// Returns the number of removed packages.
+    ///
+    /// Pool files of removed packages are not deleted when publishing.
+    pub fn remove_source_package(
+        &mut self,
+        component: &str,
+        package: &str,
+        version: Option<&str>,
+    ) -> usize {
+        if let Some(packages) = self.source_packages.get_mut(component) {
+            let before = packages.len();
+            packages.retain(|key, _| !package_key_matches(key, package, version));
+
+            before - packages.len()
+        } else {
+            0
+        }
+    }
+
+    /// Remove all but the `count` latest versions of each package.
+    ///
+    /// Binary packages are considered per component and architecture. Source packages are
+    /// considered per component. Versions are ordered according to Debian version comparison
+    /// rules.
+    ///
+    /// Returns the number of removed packages.
+    ///
+    /// Pool files of removed packages are not deleted when publishing.
+    pub fn retain_latest_versions(&mut self, count: usize) -> Result<usize> {
+        let mut removed = 0;
+
+        for (key, packages) in self.binary_packages.iter_mut() {
+            for package_key in superseded_packages(packages.keys(), count)? {
+                packages.remove(&package_key);
+
+                if let Some(paths) = self.binary_package_paths.get_mut(key) {
+                    paths.remove(&package_key);
+                }
+
+                removed += 1;
+            }
+        }
+
+        for packages in self.source_packages.values_mut() {
+            for package_key in superseded_packages(packages.keys(), count)? {
+                packages.remove(&package_key);
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Obtain all components having source packages.
+    pub fn source_package_components(&self) -> impl Iterator<Item = &str> + '_ {
+        self.source_packages.keys().map(|x| x.as_str())
+    }
+
+    /// Obtain an iterator of [ControlParagraph] for source packages in a given component.
+    ///
+    /// This method forms the basic building block for constructing `Sources` files.
+    pub fn iter_component_source_packages(
+        &self,
+        component: impl ToString,
+    ) -> Box<dyn Iterator<Item = &'_ ControlParagraph> + Send + '_> {
+        if let Some(packages) = self.source_packages.get(&component.to_string()) {
+            Box::new(packages.values())
+        } else {
+            Box::new(std::iter::empty())
+        }
+    }
+
     /// Obtain all components having binary packages.
     ///
     /// The iterator contains 2-tuples of `(component, architecture)`.
@@ -651,6 +1181,99 @@ impl<'cf> RepositoryBuilder<'cf> {
         )
     }
 
+    /// Obtain an [AsyncRead] that reads contents of a `Sources` file for source packages.
+    pub fn component_source_packages_reader(
+        &self,
+        component: impl ToString,
+    ) -> impl AsyncRead + '_ {
+        futures::stream::iter(
+            self.iter_component_source_packages(component)
+                .map(|p| Ok(format!("{}\n", p.to_string()))),
+        )
+        .into_async_read()
+    }
+
+    /// Like [Self::component_source_packages_reader()] except data is compressed.
+    pub fn component_source_packages_reader_compression(
+        &self,
+        component: impl ToString,
+        compression: Compression,
+    ) -> Pin<Box<dyn AsyncRead + Send + '_>> {
+        read_compressed(
+            futures::io::BufReader::new(
+                self.component_source_packages_reader(component.to_string()),
+            ),
+            compression,
+        )
+    }
+
+    /// Obtain a [ContentsFile] for binary packages in a given component + architecture.
+    ///
+    /// Only packages whose file list is known are present. Packages are qualified with
+    /// their section, if known. e.g. `utils/foo`.
+    pub fn component_contents(
+        &self,
+        component: impl ToString,
+        architecture: impl ToString,
+    ) -> ContentsFile {
+        let key = (component.to_string(), architecture.to_string());
+
+        let mut contents = ContentsFile::default();
+
+        if let (Some(packages), Some(package_paths)) = (
+            self.binary_packages.get(&key),
+            self.binary_package_paths.get(&key),
+        ) {
+            for (package_key, paths) in package_paths {
+                let name = match packages
+                    .get(package_key)
+                    .and_then(|para| para.field_str("Section"))
+                {
+                    Some(section) => format!("{}/{}", section, package_key.0),
+                    None => package_key.0.clone(),
+                };
+
+                for path in paths {
+                    contents.add_package_path(path.clone(), name.clone());
+                }
+            }
+        }
+
+        contents
+    }
+
+    /// Obtain an [AsyncRead] that reads contents of a `Contents` file for binary packages.
+    ///
+    /// This is a wrapper around [Self::component_contents()] that serializes the
+    /// [ContentsFile] and converts it to an [AsyncRead].
+    pub fn component_contents_reader(
+        &self,
+        component: impl ToString,
+        architecture: impl ToString,
+    ) -> impl AsyncRead + '_ {
+        let lines = self
+            .component_contents(component, architecture)
+            .as_lines()
+            .collect::<Vec<_>>();
+
+        futures::stream::iter(lines.into_iter().map(Ok)).into_async_read()
+    }
+
+    /// Like [Self::component_contents_reader()] except data is compressed.
+    pub fn component_contents_reader_compression(
+        &self,
+        component: impl ToString,
+        architecture: impl ToString,
+        compression: Compression,
+    ) -> Pin<Box<dyn AsyncRead + Send + '_>> {
+        read_compressed(
+            futures::io::BufReader::new(
+                self.component_contents_reader(component.to_string(), architecture.to_string()),
+            ),
+            compression,
+        )
+    }
+
     /// Obtain [IndexFileReader] for each logical `Packages` file.
     pub fn binary_packages_index_readers(&self) -> impl Iterator<Item = IndexFileReader<'_>> + '_ {
         self.binary_packages
@@ -671,11 +1294,61 @@ impl<'cf> RepositoryBuilder<'cf> {
             })
     }
 
-    /// Obtain all [IndexFileReader] to be published.
+    /// Obtain [IndexFileReader] for each logical `Sources` file.
+    pub fn source_packages_index_readers(&self) -> impl Iterator<Item = IndexFileReader<'_>> + '_ {
+        self.source_packages.keys().flat_map(move |component| {
+            self.index_file_compressions
+                .iter()
+                .map(move |compression| IndexFileReader {
+                    reader: self
+                        .component_source_packages_reader_compression(component, *compression),
+                    compression: *compression,
+                    directory: format!("{}/source", component),
+                    filename: "Sources".to_string(),
+                })
+        })
+    }
+
+    /// Obtain [IndexFileReader] for each logical `Contents` file.
     ///
-    /// Each item corresponds to a logical item in an `[In]Release`.
-    pub fn index_file_readers(&self) -> impl Iterator<Item = IndexFileReader<'_>> + '_ {
-        self.binary_packages_index_readers()
+    /// `Contents` files are only emitted for component + architecture pairs where the file
+    /// list of every package is known. Otherwise the file would claim paths aren't provided
+    /// by any package when they are. They are always gzip compressed, as that is the
+    /// only compression clients reliably support for these files.
+    pub fn contents_index_readers(&self) -> impl Iterator<Item = IndexFileReader<'_>> + '_ {
+        self.binary_package_paths
+            .keys()
+            .filter(move |key| self.have_all_package_paths(key))
+            .map(move |(component, architecture)| IndexFileReader {
+                reader: self.component_contents_reader_compression(
+                    component,
+                    architecture,
+                    Compression::Gzip,
+                ),
+                compression: Compression::Gzip,
+                directory: component.to_string(),
+                filename: format!("Contents-{}", architecture),
+            })
+    }
+
+    /// Whether the file list of every binary package in a component + architecture is known.
+    fn have_all_package_paths(&self, key: &(String, String)) -> bool {
+        match (
+            self.binary_packages.get(key),
+            self.binary_package_paths.get(key),
+        ) {
+            (Some(packages), Some(paths)) => packages.keys().all(|k| paths.contains_key(k)),
+            _ => false,
+        }
+    }
+
+    /// Obtain all [IndexFileReader] to be published.
+    ///
+    /// Each item corresponds to a logical item in an `[In]Release`.
+    pub fn index_file_readers(&self) -> impl Iterator<Item = IndexFileReader<'_>> + '_ {
+        self.binary_packages_index_readers()
+            .chain(self.source_packages_index_readers())
+            .chain(self.contents_index_readers())
     }
 
     /// Obtain records describing pool artifacts needed to support binary packages.
@@ -689,6 +1362,35 @@ impl<'cf> RepositoryBuilder<'cf> {
             })
     }
 
+    /// Obtain records describing pool artifacts needed to support source packages.
+    ///
+    /// Each record describes a file referenced by a `Sources` entry, including the `.dsc`
+    /// file.
+    pub fn iter_source_packages_pool_artifacts(
+        &self,
+    ) -> impl Iterator<Item = Result<DebianSourceControlFileFetch>> + '_ {
+        // Use the strongest available checksum.
+        let checksum = *self
+            .checksums
+            .iter()
+            .last()
+            .expect("should have at least 1 checksum defined");
+
+        self.source_packages
+            .values()
+            .flat_map(|packages| packages.values())
+            .flat_map(move |para| {
+                let cf = DebianSourceControlFile::from(para.clone());
+
+                let fetches = match cf.file_fetches(checksum) {
+                    Ok(fetches) => fetches.collect::<Vec<_>>(),
+                    Err(e) => vec![Err(e)],
+                };
+
+                fetches
+            })
+    }
+
     /// Publish artifacts to the *pool*.
     ///
     /// The *pool* is the area of a Debian repository holding files like the .deb packages.
@@ -705,8 +1407,27 @@ impl<'cf> RepositoryBuilder<'cf> {
     where
         F: Fn(PublishEvent),
     {
+        // Multiple source packages can share files. e.g. different Debian revisions of the
+        // same upstream version reference the same `.orig.tar.*`.
+        let source_fetches = self
+            .iter_source_packages_pool_artifacts()
+            .map(|fetch| fetch.map(|fetch| (fetch.path.clone(), fetch)))
+            .collect::<Result<BTreeMap<_, _>>>()?;
+
+        // Artifacts of packages loaded from an existing repository are assumed to be present.
         let artifacts = self
             .iter_binary_packages_pool_artifacts()
+            .chain(source_fetches.values().map(|fetch| {
+                Ok(BinaryPackagePoolArtifact {
+                    path: &fetch.path,
+                    size: fetch.size,
+                    digest: fetch.digest.clone(),
+                })
+            }))
+            .filter(|artifact| match artifact {
+                Ok(artifact) => !self.existing_pool_paths.contains(artifact.path),
+                Err(_) => true,
+            })
             .collect::<Result<Vec<_>>>()?;
 
         if let Some(ref cb) = progress_cb {
@@ -802,6 +1523,16 @@ impl<'cf> RepositoryBuilder<'cf> {
         }
     }
 
+    /// Whether an index file has the same content as described by a loaded `Release` file.
+    fn index_file_is_current(&self, eif: &ExpandedIndexFile) -> bool {
+        if let Some((size, digest)) = self.existing_index_files.get(&eif.canonical_path) {
+            *size == eif.data.len() as u64
+                && digest == eif.digests.digest_from_checksum(digest.checksum_type())
+        } else {
+            false
+        }
+    }
+
     /// Derive fields for `Release` files that aren't related to indices lists.
     fn static_release_fields(&self) -> impl Iterator<Item = ControlField<'_>> {
         let mut fields: BTreeMap<Cow<'_, str>, Cow<'_, str>> = BTreeMap::new();
@@ -891,6 +1622,27 @@ impl<'cf> RepositoryBuilder<'cf> {
             }
         }
 
+        // Generated indices take precedence over entries from a loaded `Release` file.
+        for (path, (size, digests)) in &self.unmanaged_index_files {
+            for checksum in self.checksums.iter() {
+                let digest = digests
+                    .iter()
+                    .find(|digest| digest.checksum_type() == *checksum)
+                    .ok_or_else(|| {
+                        DebianError::RepositoryBuildUnmanagedIndexMissingDigest(
+                            path.clone(),
+                            checksum.field_name(),
+                        )
+                    })?;
+
+                digests_by_field
+                    .entry(checksum.field_name())
+                    .or_insert_with(BTreeMap::new)
+                    .entry(path.clone())
+                    .or_insert((*size, digest.digest_hex()));
+            }
+        }
+
         for checksum in self.checksums.iter() {
             // We can have no entries if there were no indices.
             let default = BTreeMap::new();
@@ -932,6 +1684,9 @@ impl<'cf> RepositoryBuilder<'cf> {
     /// Repository index files describe the contents of the repository. Index files are
     /// referred to by the `InRelease` and `Release` files.
     ///
+    /// If `signing_key` is provided, the `Release` file is signed in both the inline
+    /// (`InRelease`) and detached (`Release.gpg`) forms.
+    ///
     /// Indices should only be published after pool artifacts are published. Otherwise
     /// there is a race condition where an index file could refer to a file in the pool
     /// that does not exist.
@@ -966,15 +1721,23 @@ impl<'cf> RepositoryBuilder<'cf> {
                     eif.write_path = format!("{}/{}", prefix.trim_matches('/'), eif.write_path);
                 }
 
-                if let Some(cb) = progress_cb {
-                    cb(PublishEvent::IndexFileToWrite(eif.write_path.clone()));
-                }
-
                 index_paths.insert(
                     eif.canonical_path.clone(),
                     (eif.data.len() as u64, eif.digests.clone()),
                 );
 
+                if self.index_file_is_current(&eif) {
+                    if let Some(cb) = progress_cb {
+                        cb(PublishEvent::IndexFileCurrent(eif.write_path.clone()));
+                    }
+
+                    continue;
+                }
+
+                if let Some(cb) = progress_cb {
+                    cb(PublishEvent::IndexFileToWrite(eif.write_path.clone()));
+                }
+
                 iters.push(eif);
             }
         }
@@ -1000,13 +1763,18 @@ impl<'cf> RepositoryBuilder<'cf> {
 
         let release = self.create_release_file(index_paths.into_iter())?;
 
-        let (release_path, inrelease_path) = if let Some(prefix) = path_prefix {
+        let (release_path, inrelease_path, release_gpg_path) = if let Some(prefix) = path_prefix {
             (
                 format!("{}/Release", prefix.trim_matches('/')),
                 format!("{}/InRelease", prefix.trim_matches('/')),
+                format!("{}/Release.gpg", prefix.trim_matches('/')),
             )
         } else {
-            ("Release".to_string(), "InRelease".to_string())
+            (
+                "Release".to_string(),
+                "InRelease".to_string(),
+                "Release.gpg".to_string(),
+            )
         };
 
         if let Some(cb) = progress_cb {
@@ -1028,29 +1796,45 @@ impl<'cf> RepositoryBuilder<'cf> {
         }
 
         if let Some((key, password)) = signing_key {
+            // We sign twice but can only call the password function once.
+            let password = password();
+
             let inrelease_content = cleartext_sign(
                 key,
-                password,
+                || password.clone(),
                 HashAlgorithm::SHA2_256,
                 std::io::Cursor::new(release.to_string().as_bytes()),
             )?;
 
-            if let Some(cb) = progress_cb {
-                cb(PublishEvent::IndexFileToWrite(inrelease_path.clone()));
-            }
+            // Older clients only look for `Release` and its detached signature.
+            let release_gpg_content = detached_sign(
+                key,
+                || password.clone(),
+                HashAlgorithm::SHA2_256,
+                std::io::Cursor::new(release.to_string().as_bytes()),
+            )?;
 
-            let inrelease_write = writer
-                .write_path(
-                    inrelease_path.into(),
-                    Box::pin(futures::io::Cursor::new(inrelease_content.into_bytes())),
-                )
-                .await?;
+            for (path, content) in [
+                (inrelease_path, inrelease_content),
+                (release_gpg_path, release_gpg_content),
+            ] {
+                if let Some(cb) = progress_cb {
+                    cb(PublishEvent::IndexFileToWrite(path.clone()));
+                }
 
-            if let Some(cb) = progress_cb {
-                cb(PublishEvent::IndexFileWritten(
-                    inrelease_write.path.to_string(),
-                    inrelease_write.bytes_written,
-                ));
+                let write = writer
+                    .write_path(
+                        path.into(),
+                        Box::pin(futures::io::Cursor::new(content.into_bytes())),
+                    )
+                    .await?;
+
+                if let Some(cb) = progress_cb {
+                    cb(PublishEvent::IndexFileWritten(
+                        write.path.to_string(),
+                        write.bytes_written,
+                    ));
+                }
             }
         }
 
@@ -1065,7 +1849,8 @@ impl<'cf> RepositoryBuilder<'cf> {
     ///
     /// 1. Publish missing pool artifacts.
     /// 2. Publish *indices* files (e.g. `Packages` lists).
-    /// 3. Publish the `InRelease` and `Release` file.
+    /// 3. Publish the `Release` file and, if a signing key is given, the `InRelease` and
+    ///    `Release.gpg` files.
     ///
     /// `writer` is a [RepositoryWriter] used to perform I/O for writing output files.
     /// `resolver` is a [DataResolver] for resolving pool paths. It will be consulted
@@ -1136,10 +1921,13 @@ mod test {
         crate::{
             io::PathMappingDataResolver,
             repository::{
-                http::HttpRepositoryClient, RepositoryPathVerification,
-                RepositoryPathVerificationState, RepositoryRootReader, RepositoryWrite,
+                http::HttpRepositoryClient, release::ReleaseVerificationPolicy,
+                RepositoryPathVerification, RepositoryPathVerificationState, RepositoryRootReader,
+                RepositoryWrite,
+            },
+            signing_key::{
+                create_self_signed_key, signing_secret_key_params_builder, DistroSigningKey,
             },
-            signing_key::{create_self_signed_key, signing_secret_key_params_builder},
         },
         async_trait::async_trait,
         futures::AsyncReadExt,
@@ -1211,7 +1999,10 @@ mod test {
 
     #[tokio::test]
     async fn bullseye_binary_packages_reader() -> Result<()> {
-        let root = HttpRepositoryClient::new(BULLSEYE_URL).unwrap();
+        let mut root = HttpRepositoryClient::new(BULLSEYE_URL).unwrap();
+        root.set_release_verification_policy(ReleaseVerificationPolicy::distro_keys([
+            DistroSigningKey::Debian11Release,
+        ]));
         let release = root.release_reader("bullseye").await.unwrap();
 
         let packages = release
@@ -1290,7 +2081,11 @@ mod test {
             )
             .await?;
 
-        let wanted_paths = vec!["dists/mydist/Release", "dists/mydist/InRelease"];
+        let wanted_paths = vec![
+            "dists/mydist/Release",
+            "dists/mydist/InRelease",
+            "dists/mydist/Release.gpg",
+        ];
 
         assert!(wanted_paths.iter().all(|path| writer
             .paths
@@ -1315,6 +2110,18 @@ mod test {
 
         signatures.verify(&signed_secret_key).unwrap();
 
+        let release = ReleaseFile::from_reader_with_detached_signature(
+            std::io::Cursor::new(writer.get_path("dists/mydist/Release").unwrap()),
+            std::io::Cursor::new(writer.get_path("dists/mydist/Release.gpg").unwrap()),
+        )
+        .unwrap();
+
+        release
+            .detached_signatures()
+            .expect("PGP detached signatures should have been parsed")
+            .verify(&signed_secret_key)
+            .unwrap();
+
         Ok(())
     }
 }
@@ -1324,10 +2131,22 @@ mod tests {
     use {
         super::*,
         crate::{
-            repository::{filesystem::FilesystemRepositoryWriter, reader_from_str},
-            signing_key::{create_self_signed_key, signing_secret_key_params_builder},
+            changelog::{Changelog, ChangelogEntry},
+            control::ControlFile,
+            deb::builder::DebBuilder,
+            io::PathMappingDataResolver,
+            repository::{
+                filesystem::{FilesystemRepositoryReader, FilesystemRepositoryWriter},
+                reader_from_str,
+                release::{ReleaseFileEntry, ReleaseVerificationPolicy},
+            },
+            signing_key::{
+                create_self_signed_key, signing_secret_key_params_builder, DistroSigningKey,
+            },
+            source_package_builder::SourcePackageBuilder,
         },
         tempfile::TempDir,
+        tugger_file_manifest::{FileEntry, FileManifest},
     };
 
     fn temp_dir() -> Result<TempDir> {
@@ -1355,7 +2174,7 @@ mod tests {
         let key_params = signing_secret_key_params_builder("someone@example.com")
             .build()
             .unwrap();
-        let key = create_self_signed_key(key_params, String::new)?.0;
+        let (key, public_key) = create_self_signed_key(key_params, String::new)?;
 
         builder
             .publish_indices(
@@ -1367,13 +2186,585 @@ mod tests {
             )
             .await?;
 
-        let reader = reader_from_str(format!("file://{}", td.path().display()))?;
+        let mut reader = reader_from_str(format!("file://{}", td.path().display()))?;
 
+        // Readers refuse to read releases until a policy is chosen.
+        assert!(matches!(
+            reader.release_reader("dist").await,
+            Err(DebianError::ReleaseVerificationPolicyUnset)
+        ));
+
+        reader.set_release_verification_policy(ReleaseVerificationPolicy::Unverified);
         let release_reader = reader.release_reader("dist").await?;
 
         let indices = release_reader.classified_indices_entries()?;
         assert!(indices.is_empty());
 
+        reader.set_release_verification_policy(ReleaseVerificationPolicy::distro_keys([
+            DistroSigningKey::Debian11Release,
+        ]));
+        assert!(matches!(
+            reader.release_reader("dist").await,
+            Err(DebianError::ReleaseNoTrustedSignatures)
+        ));
+
+        reader
+            .set_release_verification_policy(ReleaseVerificationPolicy::Keyring(vec![public_key]));
+        reader.release_reader("dist").await?;
+
+        // Without `InRelease`, readers fall back to `Release` + `Release.gpg`.
+        std::fs::remove_file(td.path().join("dists/dist/InRelease"))?;
+        let release_reader = reader.release_reader("dist").await?;
+        assert!(release_reader
+            .release_file()
+            .detached_signatures()
+            .is_some());
+
+        std::fs::remove_file(td.path().join("dists/dist/Release.gpg"))?;
+        assert!(matches!(
+            reader.release_reader("dist").await,
+            Err(DebianError::RepositoryIoPath(_, _))
+        ));
+
+        // Unverified readers tolerate a missing signature.
+        reader.set_release_verification_policy(ReleaseVerificationPolicy::Unverified);
+        let release_reader = reader.release_reader("dist").await?;
+        assert!(release_reader
+            .release_file()
+            .detached_signatures()
+            .is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn publish_source_package() -> Result<()> {
+        let td = temp_dir()?;
+
+        let mut debian_files = FileManifest::default();
+        debian_files.add_file_entry(
+            "control",
+            b"Source: mypackage\nMaintainer: Me <me@example.com>\n\nPackage: mypackage\nArchitecture: any\nDescription: my package\n".to_vec(),
+        )?;
+
+        let mut changelog = Changelog::default();
+        changelog.add_entry(ChangelogEntry {
+            package: "mypackage".into(),
+            version: "1.0-1".into(),
+            distributions: vec!["unstable".into()],
+            urgency: "low".into(),
+            details: "  * Initial release.".into(),
+            maintainer_name: "Me".into(),
+            maintainer_email: "me@example.com".into(),
+            date: DateTime::from_utc(
+                chrono::NaiveDateTime::from_timestamp(1420000000, 0),
+                chrono::TimeZone::from_offset(&chrono::FixedOffset::west(3600 * 7)),
+            ),
+        });
+
+        let source_package = SourcePackageBuilder::new(
+            b"upstream".to_vec(),
+            Compression::Gzip,
+            debian_files,
+            changelog,
+        )
+        .build(NO_SIGNING_KEY)?;
+
+        let source_dir = td.path().join("source");
+        source_package.write_to_directory(&source_dir)?;
+
+        let mut builder = RepositoryBuilder::new_recommended(
+            ["amd64"].into_iter(),
+            ["main"].into_iter(),
+            "suite",
+            "codename",
+        );
+
+        let directory = builder.add_source_package("main", &source_package)?;
+        assert_eq!(directory, "pool/main/m/mypackage");
+
+        let mut resolver =
+            PathMappingDataResolver::new(FilesystemRepositoryReader::new(&source_dir));
+        for file in source_package.iter_all_files() {
+            resolver.add_path_map(format!("{}/{}", directory, file.filename), &file.filename);
+        }
+
+        assert_eq!(builder.iter_source_packages_pool_artifacts().count(), 3);
+
+        let repo_dir = td.path().join("repo");
+        let writer = FilesystemRepositoryWriter::new(&repo_dir);
+
+        builder
+            .publish(
+                &writer,
+                &resolver,
+                "dists/dist",
+                1,
+                &NO_PROGRESS_CB,
+                NO_SIGNING_KEY,
+            )
+            .await?;
+
+        for file in source_package.iter_all_files() {
+            assert_eq!(
+                std::fs::read(repo_dir.join(&directory).join(&file.filename))?,
+                file.data
+            );
+        }
+
+        let mut reader = reader_from_str(format!("file://{}", repo_dir.display()))?;
+        reader.set_release_verification_policy(ReleaseVerificationPolicy::Unverified);
+        let release_reader = reader.release_reader("dist").await?;
+
+        let sources = release_reader.resolve_sources("main").await?;
+        assert_eq!(sources.len(), 1);
+
+        let cf = &sources[0];
+        assert_eq!(cf.required_field_str("Package")?, "mypackage");
+        assert_eq!(cf.version_str()?, "1.0-1");
+        assert_eq!(cf.required_field_str("Directory")?, directory);
+
+        let fetches = cf
+            .file_fetches(ChecksumType::Sha256)?
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            fetches.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(),
+            vec![
+                "pool/main/m/mypackage/mypackage_1.0-1.dsc",
+                "pool/main/m/mypackage/mypackage_1.0.orig.tar.gz",
+                "pool/main/m/mypackage/mypackage_1.0-1.debian.tar.xz",
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn publish_contents() -> Result<()> {
+        let td = temp_dir()?;
+
+        let mut control_para = ControlParagraph::default();
+        control_para.set_field_from_string("Package".into(), "mypackage".into());
+        control_para.set_field_from_string("Version".into(), "1.0".into());
+        control_para.set_field_from_string("Architecture".into(), "amd64".into());
+        control_para.set_field_from_string("Section".into(), "utils".into());
+
+        let mut control = ControlFile::default();
+        control.add_paragraph(control_para);
+
+        let mut deb_data = vec![];
+        DebBuilder::new(control)
+            .install_file("usr/bin/myapp", FileEntry::new_from_data(vec![42], true))?
+            .install_file("usr/share/doc/myapp/README", vec![42])?
+            .write(&mut deb_data)?;
+
+        let deb = InMemoryDebFile::new("mypackage_1.0_amd64.deb".into(), deb_data);
+        assert_eq!(
+            deb.data_paths()?,
+            Some(vec![
+                "usr/bin/myapp".to_string(),
+                "usr/share/doc/myapp/README".to_string()
+            ])
+        );
+
+        let mut builder = RepositoryBuilder::new_recommended(
+            ["amd64"].into_iter(),
+            ["main"].into_iter(),
+            "suite",
+            "codename",
+        );
+
+        builder.add_binary_deb("main", &deb)?;
+
+        assert_eq!(
+            builder
+                .component_contents("main", "amd64")
+                .packages_with_path("usr/bin/myapp")
+                .collect::<Vec<_>>(),
+            vec!["utils/mypackage"]
+        );
+        assert_eq!(
+            builder
+                .contents_index_readers()
+                .map(|ifr| ifr.canonical_path())
+                .collect::<Vec<_>>(),
+            vec!["main/Contents-amd64.gz"]
+        );
+
+        builder
+            .publish_indices(
+                &FilesystemRepositoryWriter::new(td.path()),
+                Some("dists/dist"),
+                1,
+                &NO_PROGRESS_CB,
+                NO_SIGNING_KEY,
+            )
+            .await?;
+
+        let mut reader = reader_from_str(format!("file://{}", td.path().display()))?;
+        reader.set_release_verification_policy(ReleaseVerificationPolicy::Unverified);
+        let release_reader = reader.release_reader("dist").await?;
+
+        let contents = release_reader
+            .resolve_contents("main", "amd64", false)
+            .await?;
+        assert_eq!(
+            contents
+                .package_paths("utils/mypackage")
+                .collect::<Vec<_>>(),
+            vec!["usr/bin/myapp", "usr/share/doc/myapp/README"]
+        );
+
+        Ok(())
+    }
+
+    fn deb_with_version(version: &str) -> Result<InMemoryDebFile> {
+        let mut control_para = ControlParagraph::default();
+        control_para.set_field_from_string("Package".into(), "mypackage".into());
+        control_para.set_field_from_string("Version".into(), version.to_string().into());
+        control_para.set_field_from_string("Architecture".into(), "amd64".into());
+
+        let mut control = ControlFile::default();
+        control.add_paragraph(control_para);
+
+        let mut deb_data = vec![];
+        DebBuilder::new(control)
+            .install_file("usr/bin/myapp", vec![42])?
+            .write(&mut deb_data)?;
+
+        Ok(InMemoryDebFile::new(
+            format!("mypackage_{}_amd64.deb", version),
+            deb_data,
+        ))
+    }
+
+    #[derive(Default)]
+    struct PublishEvents {
+        pool_created: std::sync::Mutex<Vec<String>>,
+        index_written: std::sync::Mutex<Vec<String>>,
+        index_current: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl PublishEvents {
+        fn record(&self, event: PublishEvent) {
+            match event {
+                PublishEvent::PoolArtifactCreated(path, _) => {
+                    self.pool_created.lock().unwrap().push(path)
+                }
+                PublishEvent::IndexFileWritten(path, _) => {
+                    self.index_written.lock().unwrap().push(path)
+                }
+                PublishEvent::IndexFileCurrent(path) => {
+                    self.index_current.lock().unwrap().push(path)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn publish_incremental() -> Result<()> {
+        let td = temp_dir()?;
+        let source_dir = td.path().join("source");
+        let repo_dir = td.path().join("repo");
+        std::fs::create_dir_all(&source_dir)?;
+
+        let writer = FilesystemRepositoryWriter::new(&repo_dir);
+        let mut resolver =
+            PathMappingDataResolver::new(FilesystemRepositoryReader::new(&source_dir));
+
+        let mut builder = RepositoryBuilder::new_recommended(
+            ["amd64"].into_iter(),
+            ["main"].into_iter(),
+            "suite",
+            "codename",
+        );
+
+        let deb = deb_with_version("1.0")?;
+        std::fs::write(source_dir.join(&deb.filename), &deb.data)?;
+        let path = builder.add_binary_deb("main", &deb)?;
+        resolver.add_path_map(path, &deb.filename);
+
+        builder
+            .publish(
+                &writer,
+                &resolver,
+                "dists/dist",
+                1,
+                &NO_PROGRESS_CB,
+                NO_SIGNING_KEY,
+            )
+            .await?;
+
+        let mut reader = reader_from_str(format!("file://{}", repo_dir.display()))?;
+        reader.set_release_verification_policy(ReleaseVerificationPolicy::Unverified);
+        let release_reader = reader.release_reader("dist").await?;
+        let original_packages_path = release_reader
+            .packages_entry("main", "amd64", false)?
+            .by_hash_path();
+
+        // Add new versions, keeping the 2 latest.
+        let mut builder = RepositoryBuilder::from_release_reader(&*release_reader).await?;
+        assert_eq!(
+            builder
+                .iter_component_binary_packages("main", "amd64")
+                .count(),
+            1
+        );
+        assert_eq!(
+            builder
+                .component_contents("main", "amd64")
+                .packages_with_path("usr/bin/myapp")
+                .collect::<Vec<_>>(),
+            vec!["mypackage"]
+        );
+
+        for version in ["1.1", "1.2"] {
+            let deb = deb_with_version(version)?;
+            std::fs::write(source_dir.join(&deb.filename), &deb.data)?;
+            let path = builder.add_binary_deb("main", &deb)?;
+            resolver.add_path_map(path, &deb.filename);
+        }
+
+        assert_eq!(builder.remove_binary_package("main", "other", None), 0);
+        assert_eq!(builder.retain_latest_versions(2)?, 1);
+
+        let events = PublishEvents::default();
+        builder
+            .publish(
+                &writer,
+                &resolver,
+                "dists/dist",
+                1,
+                &Some(|event| events.record(event)),
+                NO_SIGNING_KEY,
+            )
+            .await?;
+
+        assert_eq!(
+            events.pool_created.lock().unwrap().clone(),
+            vec![
+                "pool/main/m/mypackage/mypackage_1.1_amd64.deb",
+                "pool/main/m/mypackage/mypackage_1.2_amd64.deb"
+            ]
+        );
+        assert!(events.index_current.lock().unwrap().is_empty());
+
+        let release_reader = reader.release_reader("dist").await?;
+        let packages = release_reader
+            .resolve_packages("main", "amd64", false)
+            .await?;
+        assert_eq!(
+            packages
+                .iter()
+                .map(|cf| cf.version_str())
+                .collect::<Result<Vec<_>>>()?,
+            vec!["1.1", "1.2"]
+        );
+
+        // by-hash files from the previous publish are retained.
+        assert!(repo_dir
+            .join("dists/dist")
+            .join(&original_packages_path)
+            .exists());
+
+        // Republishing without changes only writes the release file.
+        let builder = RepositoryBuilder::from_release_reader(&*release_reader).await?;
+        let events = PublishEvents::default();
+        builder
+            .publish(
+                &writer,
+                &resolver,
+                "dists/dist",
+                1,
+                &Some(|event| events.record(event)),
+                NO_SIGNING_KEY,
+            )
+            .await?;
+
+        assert!(events.pool_created.lock().unwrap().is_empty());
+        assert!(!events.index_current.lock().unwrap().is_empty());
+        assert_eq!(
+            events.index_written.lock().unwrap().clone(),
+            vec!["dists/dist/Release"]
+        );
+
+        Ok(())
+    }
+
+    /// A `.deb` whose file list isn't known.
+    struct UnknownPathsDeb(InMemoryDebFile);
+
+    impl<'cf> DebPackageReference<'cf> for UnknownPathsDeb {
+        fn deb_size_bytes(&self) -> Result<u64> {
+            self.0.deb_size_bytes()
+        }
+
+        fn deb_digest(&self, checksum: ChecksumType) -> Result<ContentDigest> {
+            self.0.deb_digest(checksum)
+        }
+
+        fn deb_filename(&self) -> Result<String> {
+            self.0.deb_filename()
+        }
+
+        fn control_file_for_packages_index(&self) -> Result<BinaryPackageControlFile<'cf>> {
+            self.0.control_file_for_packages_index()
+        }
+    }
+
+    #[test]
+    fn contents_require_all_package_paths() -> Result<()> {
+        let mut builder = RepositoryBuilder::new_recommended(
+            ["amd64"].into_iter(),
+            ["main"].into_iter(),
+            "suite",
+            "codename",
+        );
+
+        builder.add_binary_deb("main", &deb_with_version("1.0")?)?;
+        assert_eq!(builder.contents_index_readers().count(), 1);
+
+        builder.add_binary_deb("main", &UnknownPathsDeb(deb_with_version("1.1")?))?;
+        assert_eq!(builder.contents_index_readers().count(), 0);
+
+        assert_eq!(
+            builder.remove_binary_package("main", "mypackage", Some("1.1")),
+            1
+        );
+        assert_eq!(builder.contents_index_readers().count(), 1);
+
+        Ok(())
+    }
+
+    /// Write an index file and add it to a `Release` file, like a tool other than the builder.
+    fn add_release_index_file(dist_dir: &std::path::Path, path: &str, data: &[u8]) -> Result<()> {
+        let file_path = dist_dir.join(path);
+        std::fs::create_dir_all(file_path.parent().unwrap())?;
+        std::fs::write(&file_path, data)?;
+
+        let mut digester = MultiDigester::default();
+        digester.update(data);
+        let digests = digester.finish();
+
+        let mut release = ReleaseFile::from_reader(std::io::BufReader::new(std::fs::File::open(
+            dist_dir.join("Release"),
+        )?))?;
+
+        for checksum in [ChecksumType::Md5, ChecksumType::Sha256] {
+            let by_hash_path = dist_dir.join(
+                ReleaseFileEntry {
+                    path,
+                    digest: digests.digest_from_checksum(checksum).clone(),
+                    size: data.len() as u64,
+                }
+                .by_hash_path(),
+            );
+            std::fs::create_dir_all(by_hash_path.parent().unwrap())?;
+            std::fs::write(&by_hash_path, data)?;
+
+            let mut lines = vec!["".to_string()];
+            for entry in release.iter_index_files(checksum).unwrap() {
+                let entry = entry?;
+                lines.push(format!(
+                    " {} {} {}",
+                    entry.digest.digest_hex(),
+                    entry.size,
+                    entry.path
+                ));
+            }
+            lines.push(format!(
+                " {} {} {}",
+                digests.digest_from_checksum(checksum).digest_hex(),
+                data.len(),
+                path
+            ));
+
+            release.set_field_from_string(checksum.field_name().into(), lines.join("\n").into());
+        }
+
+        std::fs::write(dist_dir.join("Release"), release.to_string())?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn publish_incremental_unmanaged_indices() -> Result<()> {
+        let td = temp_dir()?;
+        let dist_dir = td.path().join("dists/dist");
+
+        let writer = FilesystemRepositoryWriter::new(td.path());
+
+        let mut builder = RepositoryBuilder::new_recommended(
+            ["amd64"].into_iter(),
+            ["main"].into_iter(),
+            "suite",
+            "codename",
+        );
+        builder.add_binary_deb("main", &deb_with_version("1.0")?)?;
+        builder
+            .publish_indices(
+                &writer,
+                Some("dists/dist"),
+                1,
+                &NO_PROGRESS_CB,
+                NO_SIGNING_KEY,
+            )
+            .await?;
+
+        add_release_index_file(
+            &dist_dir,
+            "main/debian-installer/binary-amd64/Packages",
+            b"",
+        )?;
+        add_release_index_file(
+            &dist_dir,
+            "main/i18n/Translation-en",
+            b"Package: mypackage\nDescription-md5: 00000000000000000000000000000000\n",
+        )?;
+
+        let mut reader = reader_from_str(format!("file://{}", td.path().display()))?;
+        reader.set_release_verification_policy(ReleaseVerificationPolicy::Unverified);
+        let release_reader = reader.release_reader("dist").await?;
+
+        let mut builder = RepositoryBuilder::from_release_reader(&*release_reader).await?;
+        builder.add_binary_deb("main", &deb_with_version("1.1")?)?;
+        builder
+            .publish_indices(
+                &writer,
+                Some("dists/dist"),
+                1,
+                &NO_PROGRESS_CB,
+                NO_SIGNING_KEY,
+            )
+            .await?;
+
+        let release_reader = reader.release_reader("dist").await?;
+
+        for checksum in [ChecksumType::Md5, ChecksumType::Sha256] {
+            let paths = release_reader
+                .release_file()
+                .iter_index_files(checksum)
+                .unwrap()
+                .map(|entry| Ok(entry?.path.to_string()))
+                .collect::<Result<Vec<_>>>()?;
+
+            assert!(paths.contains(&"main/debian-installer/binary-amd64/Packages".to_string()));
+            assert!(paths.contains(&"main/i18n/Translation-en".to_string()));
+        }
+
+        assert!(release_reader
+            .resolve_packages("main", "amd64", true)
+            .await?
+            .is_empty());
+        assert_eq!(
+            release_reader
+                .resolve_packages("main", "amd64", false)
+                .await?
+                .len(),
+            2
+        );
+
         Ok(())
     }
 }
@@ -91,7 +91,7 @@ impl ContentsFile {
             // BTreeSet doesn't have a .join(). So we need to build a collection that does.
             let packages = packages.iter().map(|s| s.as_str()).collect::<Vec<_>>();
 
-            format!("{}    {}\n", path, packages.join(",'"))
+            format!("{}    {}\n", path, packages.join(","))
         })
     }
 
@@ -9,12 +9,15 @@ use {
         error::{DebianError, Result},
         io::ContentDigest,
         repository::{
-            reader_from_str, writer_from_str, CopyPhase, PublishEvent, ReleaseReader,
-            RepositoryRootReader, RepositoryWriteOperation, RepositoryWriter,
+            reader_from_str, release::ReleaseVerificationPolicy, writer_from_str, CopyPhase,
+            PublishEvent, ReleaseReader, RepositoryRootReader, RepositoryWriteOperation,
+            RepositoryWriter,
         },
+        signing_key::DistroSigningKey,
     },
     futures::StreamExt,
     serde::{Deserialize, Serialize},
+    std::{path::PathBuf, str::FromStr},
 };
 
 /// Well-known files at the root of distribution/release directories.
@@ -63,6 +66,47 @@ pub struct RepositoryCopierConfig {
 
     /// Whether to copy source packages.
     pub sources_copy: Option<bool>,
+
+    /// Names of built-in signing keys trusted to sign the source repository.
+    ///
+    /// See [DistroSigningKey::name()] for possible values.
+    #[serde(default)]
+    pub source_signing_keys: Vec<String>,
+
+    /// Paths to files holding ASCII armored PGP public keys trusted to sign the source repository.
+    #[serde(default)]
+    pub source_signing_key_paths: Vec<PathBuf>,
+
+    /// Whether to read the source repository without verifying `InRelease` signatures.
+    ///
+    /// Must be set if no signing keys are defined.
+    #[serde(default)]
+    pub source_unverified: bool,
+}
+
+impl RepositoryCopierConfig {
+    /// Resolve the [ReleaseVerificationPolicy] for the source repository.
+    pub fn source_verification_policy(&self) -> Result<ReleaseVerificationPolicy> {
+        let mut keys = self
+            .source_signing_keys
+            .iter()
+            .map(|name| Ok(DistroSigningKey::from_str(name)?.public_key()))
+            .collect::<Result<Vec<_>>>()?;
+
+        for path in &self.source_signing_key_paths {
+            let fh = std::fs::File::open(path)
+                .map_err(|e| DebianError::RepositoryIoPath(format!("{}", path.display()), e))?;
+
+            keys.extend(ReleaseVerificationPolicy::read_armored_keys(fh)?);
+        }
+
+        match (keys.is_empty(), self.source_unverified) {
+            (true, true) => Ok(ReleaseVerificationPolicy::Unverified),
+            (true, false) => Err(DebianError::RepositoryCopierNoSigningKeys),
+            (false, true) => Err(DebianError::RepositoryCopierConflictingVerification),
+            (false, false) => Ok(ReleaseVerificationPolicy::Keyring(keys)),
+        }
+    }
 }
 
 struct GenericCopy {
@@ -173,8 +217,9 @@ impl RepositoryCopier {
         max_copy_operations: usize,
         progress_cb: &Option<Box<dyn Fn(PublishEvent) + Sync>>,
     ) -> Result<()> {
-        let root_reader = reader_from_str(config.source_url)?;
-        let writer = writer_from_str(config.destination_url).await?;
+        let mut root_reader = reader_from_str(&config.source_url)?;
+        root_reader.set_release_verification_policy(config.source_verification_policy()?);
+        let writer = writer_from_str(&config.destination_url).await?;
 
         let mut copier = Self::default();
 
@@ -667,8 +712,11 @@ mod test {
 
     #[tokio::test]
     async fn bullseye_copy() -> Result<()> {
-        let root =
-            Box::new(HttpRepositoryClient::new(DEBIAN_URL)?) as Box<dyn RepositoryRootReader>;
+        let mut root = HttpRepositoryClient::new(DEBIAN_URL)?;
+        root.set_release_verification_policy(ReleaseVerificationPolicy::distro_keys([
+            DistroSigningKey::Debian11Release,
+        ]));
+        let root = Box::new(root) as Box<dyn RepositoryRootReader>;
         let mut writer = ProxyWriter::new(SinkWriter::default());
         writer.set_verify_behavior(ProxyVerifyBehavior::AlwaysExistsIntegrityVerified);
         let writer: Box<dyn RepositoryWriter> = Box::new(writer);
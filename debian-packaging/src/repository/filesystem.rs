@@ -9,9 +9,9 @@ use {
         error::{DebianError, Result},
         io::{Compression, ContentDigest, DataResolver, DigestingReader},
         repository::{
-            release::ReleaseFile, ReleaseReader, RepositoryPathVerification,
-            RepositoryPathVerificationState, RepositoryRootReader, RepositoryWrite,
-            RepositoryWriter,
+            release::{ReleaseFile, ReleaseVerificationPolicy},
+            ReleaseReader, RepositoryPathVerification, RepositoryPathVerificationState,
+            RepositoryRootReader, RepositoryWrite, RepositoryWriter,
         },
     },
     async_trait::async_trait,
@@ -28,6 +28,7 @@ use {
 #[derive(Clone, Debug)]
 pub struct FilesystemRepositoryReader {
     root_dir: PathBuf,
+    release_verification: Option<ReleaseVerificationPolicy>,
 }
 
 impl FilesystemRepositoryReader {
@@ -37,6 +38,7 @@ impl FilesystemRepositoryReader {
     pub fn new(path: impl AsRef<Path>) -> Self {
         Self {
             root_dir: path.as_ref().to_path_buf(),
+            release_verification: None,
         }
     }
 }
@@ -60,15 +62,22 @@ impl RepositoryRootReader for FilesystemRepositoryReader {
             .map_err(|_| DebianError::Other("error converting filesystem path to URL".to_string()))
     }
 
+    fn release_verification_policy(&self) -> Option<&ReleaseVerificationPolicy> {
+        self.release_verification.as_ref()
+    }
+
+    fn set_release_verification_policy(&mut self, policy: ReleaseVerificationPolicy) {
+        self.release_verification = Some(policy);
+    }
+
     async fn release_reader_with_distribution_path(
         &self,
         path: &str,
     ) -> Result<Box<dyn ReleaseReader>> {
         let distribution_path = path.trim_matches('/').to_string();
-        let release_path = format!("{}/InRelease", distribution_path);
         let distribution_dir = self.root_dir.join(&distribution_path);
 
-        let release = self.fetch_inrelease(&release_path).await?;
+        let release = self.fetch_release(&distribution_path).await?;
 
         let fetch_compression = Compression::default_preferred_order()
             .next()
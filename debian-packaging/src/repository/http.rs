@@ -12,7 +12,10 @@ use {
     crate::{
         error::{DebianError, Result},
         io::DataResolver,
-        repository::{release::ReleaseFile, Compression, ReleaseReader, RepositoryRootReader},
+        repository::{
+            release::{ReleaseFile, ReleaseVerificationPolicy},
+            Compression, ReleaseReader, RepositoryRootReader,
+        },
     },
     async_trait::async_trait,
     futures::{stream::TryStreamExt, AsyncRead},
@@ -83,6 +86,9 @@ pub struct HttpRepositoryClient {
     ///
     /// Contains both distributions and the files pool.
     root_url: Url,
+
+    /// How to verify release files.
+    release_verification: Option<ReleaseVerificationPolicy>,
 }
 
 impl HttpRepositoryClient {
@@ -109,7 +115,11 @@ impl HttpRepositoryClient {
             root_url.set_path(&format!("{}/", root_url.path()));
         }
 
-        Ok(Self { client, root_url })
+        Ok(Self {
+            client,
+            root_url,
+            release_verification: None,
+        })
     }
 }
 
@@ -126,12 +136,19 @@ impl RepositoryRootReader for HttpRepositoryClient {
         Ok(self.root_url.clone())
     }
 
+    fn release_verification_policy(&self) -> Option<&ReleaseVerificationPolicy> {
+        self.release_verification.as_ref()
+    }
+
+    fn set_release_verification_policy(&mut self, policy: ReleaseVerificationPolicy) {
+        self.release_verification = Some(policy);
+    }
+
     async fn release_reader_with_distribution_path(
         &self,
         path: &str,
     ) -> Result<Box<dyn ReleaseReader>> {
         let distribution_path = path.trim_matches('/').to_string();
-        let release_path = join_path(&distribution_path, "InRelease");
         let mut root_url = self.root_url.join(&distribution_path)?;
 
         // Trailing URLs are significant to the Url type when we .join(). So ensure
@@ -140,7 +157,7 @@ impl RepositoryRootReader for HttpRepositoryClient {
             root_url.set_path(&format!("{}/", root_url.path()));
         }
 
-        let release = self.fetch_inrelease(&release_path).await?;
+        let release = self.fetch_release(&distribution_path).await?;
 
         let fetch_compression = Compression::default_preferred_order()
             .next()
@@ -156,10 +173,6 @@ impl RepositoryRootReader for HttpRepositoryClient {
     }
 }
 
-fn join_path(a: &str, b: &str) -> String {
-    format!("{}/{}", a.trim_matches('/'), b.trim_start_matches('/'))
-}
-
 /// Repository HTTP client bound to a parsed `Release` or `InRelease` file.
 pub struct HttpReleaseClient {
     client: Client,
@@ -205,15 +218,43 @@ mod test {
         super::*,
         crate::{
             dependency::BinaryDependency, dependency_resolution::DependencyResolver, error::Result,
-            io::ContentDigest, repository::release::ChecksumType,
+            io::ContentDigest, repository::release::ChecksumType, signing_key::DistroSigningKey,
         },
     };
 
     const BULLSEYE_URL: &str = "http://snapshot.debian.org/archive/debian/20211120T085721Z";
 
+    #[tokio::test]
+    async fn bullseye_release_verification() -> Result<()> {
+        let mut root = HttpRepositoryClient::new(BULLSEYE_URL)?;
+
+        root.set_release_verification_policy(ReleaseVerificationPolicy::distro_keys([
+            DistroSigningKey::Debian10Release,
+        ]));
+        assert!(matches!(
+            root.release_reader("bullseye").await,
+            Err(DebianError::ReleaseNoTrustedSignatures)
+        ));
+
+        root.set_release_verification_policy(ReleaseVerificationPolicy::distro_keys([
+            DistroSigningKey::Debian11Release,
+        ]));
+        root.release_reader("bullseye").await?;
+
+        let release = root
+            .fetch_release_detached("dists/bullseye/Release")
+            .await?;
+        assert!(release.detached_signatures().is_some());
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn bullseye_release() -> Result<()> {
-        let root = HttpRepositoryClient::new(BULLSEYE_URL)?;
+        let mut root = HttpRepositoryClient::new(BULLSEYE_URL)?;
+        root.set_release_verification_policy(ReleaseVerificationPolicy::distro_keys([
+            DistroSigningKey::Debian11Release,
+        ]));
 
         let release = root.release_reader("bullseye").await?;
 
@@ -267,7 +308,10 @@ mod test {
 
     #[tokio::test]
     async fn bullseye_sources() -> Result<()> {
-        let root = HttpRepositoryClient::new(BULLSEYE_URL)?;
+        let mut root = HttpRepositoryClient::new(BULLSEYE_URL)?;
+        root.set_release_verification_policy(ReleaseVerificationPolicy::distro_keys([
+            DistroSigningKey::Debian11Release,
+        ]));
 
         let release = root.release_reader("bullseye").await?;
 
@@ -345,7 +389,10 @@ mod test {
 
     #[tokio::test]
     async fn bullseye_contents() -> Result<()> {
-        let root = HttpRepositoryClient::new(BULLSEYE_URL)?;
+        let mut root = HttpRepositoryClient::new(BULLSEYE_URL)?;
+        root.set_release_verification_policy(ReleaseVerificationPolicy::distro_keys([
+            DistroSigningKey::Debian11Release,
+        ]));
 
         let release = root.release_reader("bullseye").await?;
 
@@ -39,7 +39,9 @@ series of traits.
 
 [RepositoryRootReader] describes an interface for reading from the root of
 a repository. It is used to obtain and parse `[In]Release` files and to read
-from the *pool*.
+from the *pool*. Readers prefer `InRelease` files and fall back to `Release`
+plus its detached `Release.gpg` signature when `InRelease` does not exist. PGP
+signatures are verified according to a [release::ReleaseVerificationPolicy].
 
 [ReleaseReader] describes an interface for reading from a *distribution*
 and a parsed `[In]Release` file describing the distribution.
@@ -80,7 +82,7 @@ use {
             contents::{ContentsFile, ContentsFileAsyncReader},
             release::{
                 ChecksumType, ClassifiedReleaseFileEntry, ContentsFileEntry, PackagesFileEntry,
-                ReleaseFile, SourcesFileEntry,
+                ReleaseFile, ReleaseVerificationPolicy, SourcesFileEntry,
             },
         },
     },
@@ -141,10 +143,28 @@ pub trait RepositoryRootReader: DataResolver + Sync {
     /// Obtain the URL to which this reader is bound.  
     fn url(&self) -> Result<url::Url>;
 
+    /// Obtain the policy for verifying PGP signatures on release files.
+    ///
+    /// `None` if no policy has been set.
+    fn release_verification_policy(&self) -> Option<&ReleaseVerificationPolicy>;
+
+    /// Set the policy for verifying PGP signatures on release files.
+    ///
+    /// There is no default policy: the `fetch_*release*` and `release_reader*`
+    /// functions error until one is set. Subsequent calls to them will error if
+    /// the release file does not satisfy this policy.
+    fn set_release_verification_policy(&mut self, policy: ReleaseVerificationPolicy);
+
+    /// Obtain the policy for verifying PGP signatures on release files or error if unset.
+    fn require_release_verification_policy(&self) -> Result<&ReleaseVerificationPolicy> {
+        self.release_verification_policy()
+            .ok_or(DebianError::ReleaseVerificationPolicyUnset)
+    }
+
     /// Obtain a [ReleaseReader] for a given distribution.
     ///
-    /// This assumes the `InRelease` file is located in `dists/{distribution}/`. This is the case
-    /// for most repositories.
+    /// This assumes the `[In]Release` file is located in `dists/{distribution}/`. This is the
+    /// case for most repositories.
     async fn release_reader(&self, distribution: &str) -> Result<Box<dyn ReleaseReader>> {
         self.release_reader_with_distribution_path(&format!(
             "dists/{}",
@@ -157,7 +177,9 @@ pub trait RepositoryRootReader: DataResolver + Sync {
     ///
     /// Typically distributions exist at `dists/<distribution>/`. However, this may not
     /// always be the case. This method allows explicitly passing in the relative path
-    /// holding the `InRelease` file.
+    /// holding the `[In]Release` file.
+    ///
+    /// Implementations should obtain the release file via [Self::fetch_release()].
     async fn release_reader_with_distribution_path(
         &self,
         path: &str,
@@ -168,16 +190,88 @@ pub trait RepositoryRootReader: DataResolver + Sync {
     /// `path` is typically a value like `dists/<distribution>/InRelease`. e.g.
     /// `dists/bullseye/InRelease`.
     ///
+    /// The release file is verified against [Self::release_verification_policy()].
+    ///
     /// The default implementation of this trait should be sufficient for most types.
     async fn fetch_inrelease(&self, path: &str) -> Result<ReleaseFile<'static>> {
+        let policy = self.require_release_verification_policy()?;
+
         let mut reader = self.get_path(path).await?;
 
         let mut data = vec![];
         reader.read_to_end(&mut data).await?;
 
-        Ok(ReleaseFile::from_armored_reader(std::io::Cursor::new(
-            data,
-        ))?)
+        let release = ReleaseFile::from_armored_reader(std::io::Cursor::new(data))?;
+
+        policy.verify(&release)?;
+
+        Ok(release)
+    }
+
+    /// Fetch and parse a `Release` file and its detached PGP signature.
+    ///
+    /// `path` is typically a value like `dists/<distribution>/Release`. The detached
+    /// signature is read from the same path with a `.gpg` suffix.
+    ///
+    /// The release file is verified against [Self::release_verification_policy()]. If
+    /// the policy is [ReleaseVerificationPolicy::Unverified], a missing signature file is
+    /// tolerated.
+    ///
+    /// The default implementation of this trait should be sufficient for most types.
+    async fn fetch_release_detached(&self, path: &str) -> Result<ReleaseFile<'static>> {
+        let policy = self.require_release_verification_policy()?;
+
+        let mut reader = self.get_path(path).await?;
+
+        let mut data = vec![];
+        reader.read_to_end(&mut data).await?;
+
+        let signature_path = format!("{}.gpg", path);
+
+        let release = match self.get_path(&signature_path).await {
+            Ok(mut reader) => {
+                let mut signature = vec![];
+                reader.read_to_end(&mut signature).await?;
+
+                ReleaseFile::from_reader_with_detached_signature(
+                    std::io::Cursor::new(data),
+                    std::io::Cursor::new(signature),
+                )?
+            }
+            Err(DebianError::RepositoryIoPath(_, err))
+                if err.kind() == std::io::ErrorKind::NotFound
+                    && matches!(policy, ReleaseVerificationPolicy::Unverified) =>
+            {
+                ReleaseFile::from_reader(std::io::Cursor::new(data))?
+            }
+            Err(e) => return Err(e),
+        };
+
+        policy.verify(&release)?;
+
+        Ok(release)
+    }
+
+    /// Fetch and parse the release file for a distribution path.
+    ///
+    /// `path` is the directory holding the release files. e.g. `dists/bullseye`.
+    ///
+    /// `InRelease` is preferred. If it does not exist, `Release` and its detached
+    /// `Release.gpg` signature are used instead.
+    ///
+    /// The default implementation of this trait should be sufficient for most types.
+    async fn fetch_release(&self, path: &str) -> Result<ReleaseFile<'static>> {
+        let path = path.trim_matches('/');
+
+        match self.fetch_inrelease(&format!("{}/InRelease", path)).await {
+            Err(DebianError::RepositoryIoPath(_, err))
+                if err.kind() == std::io::ErrorKind::NotFound =>
+            {
+                self.fetch_release_detached(&format!("{}/Release", path))
+                    .await
+            }
+            res => res,
+        }
     }
 
     /// Fetch a binary package given a [BinaryPackageFetch] instruction.
@@ -815,6 +909,9 @@ pub enum PublishEvent {
     /// An index file that was written.
     IndexFileWritten(String, u64),
 
+    /// An index file with the given path is current and was not written.
+    IndexFileCurrent(String),
+
     /// A path is being verified.
     VerifyingDestinationPath(String),
 
@@ -870,6 +967,9 @@ impl std::fmt::Display for PublishEvent {
             Self::IndexFileWritten(path, size) => {
                 write!(f, "wrote {} bytes to {}", size, path)
             }
+            Self::IndexFileCurrent(path) => {
+                write!(f, "index file {} is current", path)
+            }
             Self::VerifyingDestinationPath(path) => {
                 write!(f, "verifying destination path {}", path)
             }
@@ -1043,6 +1143,9 @@ pub trait RepositoryWriter: Sync {
 ///
 /// Otherwise the string will be interpreted as a filesystem path. No test for whether
 /// the repository exists is performed.
+///
+/// The returned reader has no release verification policy. Call
+/// [RepositoryRootReader::set_release_verification_policy()] before reading releases.
 pub fn reader_from_str(s: impl ToString) -> Result<Box<dyn RepositoryRootReader>> {
     let s = s.to_string();
 
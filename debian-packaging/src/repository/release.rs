@@ -22,6 +22,9 @@ Additional types describe more strongly typed indices file variants:
 
 The [ClassifiedReleaseFileEntry] enum wraps all these types and attempts to
 classify each entry as the strongest type possible.
+
+[ReleaseVerificationPolicy] defines which PGP keys are trusted to sign
+`InRelease` files and `Release` files with a detached `Release.gpg` signature.
 */
 
 use {
@@ -30,12 +33,14 @@ use {
         error::{DebianError, Result},
         io::ContentDigest,
         repository::Compression,
+        signing_key::DistroSigningKey,
     },
     chrono::{DateTime, Utc},
-    pgp_cleartext::CleartextHasher,
+    pgp::{types::PublicKeyTrait, Deserializable, SignedPublicKey},
+    pgp_cleartext::{CleartextHasher, CleartextSignatures, DetachedSignatures},
     std::{
         borrow::Cow,
-        io::BufRead,
+        io::{BufRead, Read},
         ops::{Deref, DerefMut},
         str::FromStr,
     },
@@ -792,12 +797,15 @@ impl<'a> DerefMut for ClassifiedReleaseFileEntry<'a> {
 /// Instances are wrappers around a [ControlParagraph]. [Deref] and [DerefMut] are
 /// implemented to allow obtaining the inner [ControlParagraph]. [From] and [Into]
 /// are implemented to allow cheap type coercions. Note that converting from
-/// [ReleaseFile] to [ControlParagraph] may discard PGP cleartext signature data.
+/// [ReleaseFile] to [ControlParagraph] may discard PGP signature data.
 pub struct ReleaseFile<'a> {
     paragraph: ControlParagraph<'a>,
 
     /// Parsed PGP signatures for this file.
     signatures: Option<pgp_cleartext::CleartextSignatures>,
+
+    /// Parsed PGP detached signatures for this file.
+    detached_signatures: Option<pgp_cleartext::DetachedSignatures>,
 }
 
 impl<'a> From<ControlParagraph<'a>> for ReleaseFile<'a> {
@@ -805,6 +813,7 @@ impl<'a> From<ControlParagraph<'a>> for ReleaseFile<'a> {
         Self {
             paragraph,
             signatures: None,
+            detached_signatures: None,
         }
     }
 }
@@ -854,6 +863,7 @@ impl<'a> ReleaseFile<'a> {
         Ok(Self {
             paragraph,
             signatures: None,
+            detached_signatures: None,
         })
     }
 
@@ -878,11 +888,41 @@ impl<'a> ReleaseFile<'a> {
         Ok(slf)
     }
 
+    /// Construct an instance from a `Release` file and its detached PGP signature.
+    ///
+    /// `reader` provides the content of a `Release` file and `signature` the content of
+    /// the corresponding `Release.gpg` file.
+    ///
+    /// An error occurs if the signature is not well-formed. As with
+    /// [Self::from_armored_reader()], the PGP signature is NOT validated.
+    pub fn from_reader_with_detached_signature<R: Read>(
+        reader: R,
+        signature: impl Read,
+    ) -> Result<Self> {
+        let reader = pgp_cleartext::DetachedSignatureReader::new(reader, signature)?;
+        let mut reader = std::io::BufReader::new(reader);
+
+        let mut slf = Self::from_reader(&mut reader)?;
+
+        // The signature covers the entire file. So make sure everything is digested.
+        std::io::copy(&mut reader, &mut std::io::sink())?;
+        slf.detached_signatures = Some(reader.into_inner().finalize());
+
+        Ok(slf)
+    }
+
     /// Obtain PGP signatures from this `InRelease` file.
     pub fn signatures(&self) -> Option<&pgp_cleartext::CleartextSignatures> {
         self.signatures.as_ref()
     }
 
+    /// Obtain PGP detached signatures from this `Release` file.
+    ///
+    /// Only present if constructed via [Self::from_reader_with_detached_signature()].
+    pub fn detached_signatures(&self) -> Option<&pgp_cleartext::DetachedSignatures> {
+        self.detached_signatures.as_ref()
+    }
+
     /// Description of this repository.
     pub fn description(&self) -> Option<&str> {
         self.field_str("Description")
@@ -1243,6 +1283,128 @@ impl<'a> ReleaseFile<'a> {
     }
 }
 
+/// Defines how PGP signatures on `InRelease` and `Release.gpg` files are verified.
+///
+/// There is no default. Repository readers refuse to read release files until
+/// a policy is set.
+#[derive(Clone, Debug)]
+pub enum ReleaseVerificationPolicy {
+    /// Do not verify signatures or expiration.
+    ///
+    /// This is insecure, as tampered content will not be detected.
+    Unverified,
+
+    /// Require a valid signature from a key in the given keyring.
+    ///
+    /// Signatures made by subkeys of a key in the keyring are also accepted.
+    /// The release file must also not be expired according to its
+    /// `Valid-Until` field.
+    Keyring(Vec<SignedPublicKey>),
+}
+
+impl ReleaseVerificationPolicy {
+    /// Construct an instance trusting keys built into this crate.
+    pub fn distro_keys(keys: impl IntoIterator<Item = DistroSigningKey>) -> Self {
+        Self::Keyring(keys.into_iter().map(|key| key.public_key()).collect())
+    }
+
+    /// Read ASCII armored PGP public keys from a reader.
+    ///
+    /// The source can contain multiple `-----BEGIN PGP PUBLIC KEY BLOCK-----` sections.
+    pub fn read_armored_keys(reader: impl Read) -> Result<Vec<SignedPublicKey>> {
+        let (keys, _) = SignedPublicKey::from_armor_many(reader)?;
+
+        Ok(keys.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    /// Verify a release file against this policy using the current time.
+    pub fn verify(&self, release: &ReleaseFile<'_>) -> Result<()> {
+        self.verify_at(release, Utc::now())
+    }
+
+    /// Verify a release file against this policy as of a specific time.
+    ///
+    /// Errors if the release has no valid signature from a trusted key or if its
+    /// `Valid-Until` time is before `now`.
+    pub fn verify_at(&self, release: &ReleaseFile<'_>, now: DateTime<Utc>) -> Result<()> {
+        let keys = match self {
+            Self::Unverified => return Ok(()),
+            Self::Keyring(keys) => keys,
+        };
+
+        if let Some(signatures) = release.signatures() {
+            verify_trusted_signatures(signatures, keys)?;
+        } else if let Some(signatures) = release.detached_signatures() {
+            verify_trusted_signatures(signatures, keys)?;
+        } else {
+            return Err(DebianError::ReleaseNoSignatures);
+        }
+
+        if let Some(valid_until) = release.valid_until() {
+            if valid_until? < now {
+                return Err(DebianError::ReleaseExpired(
+                    release.valid_until_str().unwrap_or_default().to_string(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// PGP signatures over the content of a release file.
+trait ReleaseSignatures {
+    /// Whether any signatures are present.
+    fn has_signatures(&self) -> bool;
+
+    /// Whether signatures from a key are present and all of them are valid.
+    fn verified_by(&self, key: &impl PublicKeyTrait) -> bool;
+}
+
+impl ReleaseSignatures for CleartextSignatures {
+    fn has_signatures(&self) -> bool {
+        self.iter_signatures().next().is_some()
+    }
+
+    fn verified_by(&self, key: &impl PublicKeyTrait) -> bool {
+        self.verify(key).is_ok()
+    }
+}
+
+impl ReleaseSignatures for DetachedSignatures {
+    fn has_signatures(&self) -> bool {
+        self.iter_signatures().next().is_some()
+    }
+
+    fn verified_by(&self, key: &impl PublicKeyTrait) -> bool {
+        self.verify(key).is_ok()
+    }
+}
+
+/// Verify signatures were made by a key in `keys` or one of its subkeys.
+fn verify_trusted_signatures(
+    signatures: &impl ReleaseSignatures,
+    keys: &[SignedPublicKey],
+) -> Result<()> {
+    if !signatures.has_signatures() {
+        return Err(DebianError::ReleaseNoSignatures);
+    }
+
+    let trusted = keys.iter().any(|key| {
+        signatures.verified_by(key)
+            || key
+                .public_subkeys
+                .iter()
+                .any(|subkey| signatures.verified_by(subkey))
+    });
+
+    if trusted {
+        Ok(())
+    } else {
+        Err(DebianError::ReleaseNoTrustedSignatures)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1664,4 +1826,114 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn verification_policy_distro_keys() -> Result<()> {
+        let release = ReleaseFile::from_armored_reader(std::io::Cursor::new(include_bytes!(
+            "../testdata/inrelease-debian-bullseye"
+        )))?;
+        let now = release.date().unwrap()?;
+
+        ReleaseVerificationPolicy::Unverified.verify_at(&release, now)?;
+        ReleaseVerificationPolicy::distro_keys([DistroSigningKey::Debian11Release])
+            .verify_at(&release, now)?;
+
+        assert!(matches!(
+            ReleaseVerificationPolicy::distro_keys([DistroSigningKey::Debian10Release])
+                .verify_at(&release, now),
+            Err(DebianError::ReleaseNoTrustedSignatures)
+        ));
+
+        let unsigned = ReleaseFile::from_reader(std::io::Cursor::new(include_bytes!(
+            "../testdata/release-debian-bullseye"
+        )))?;
+        assert!(matches!(
+            ReleaseVerificationPolicy::distro_keys([DistroSigningKey::Debian11Release])
+                .verify_at(&unsigned, now),
+            Err(DebianError::ReleaseNoSignatures)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn verification_policy_valid_until() -> Result<()> {
+        let params = crate::signing_key::signing_secret_key_params_builder("someone@example.com")
+            .build()
+            .unwrap();
+        let (private, public) = crate::signing_key::create_self_signed_key(params, String::new)?;
+
+        let signed = pgp_cleartext::cleartext_sign(
+            &private,
+            String::new,
+            pgp::crypto::HashAlgorithm::SHA2_256,
+            std::io::Cursor::new(
+                b"Suite: test\nValid-Until: Sat, 01 Jan 2022 00:00:00 UTC\n".to_vec(),
+            ),
+        )?;
+        let release = ReleaseFile::from_armored_reader(std::io::Cursor::new(signed))?;
+        let policy = ReleaseVerificationPolicy::Keyring(vec![public]);
+
+        let valid_until = release.valid_until().unwrap()?;
+
+        policy.verify_at(&release, valid_until - chrono::Duration::days(1))?;
+        assert!(matches!(
+            policy.verify_at(&release, valid_until + chrono::Duration::days(1)),
+            Err(DebianError::ReleaseExpired(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn verification_policy_detached_signature() -> Result<()> {
+        let params = crate::signing_key::signing_secret_key_params_builder("someone@example.com")
+            .build()
+            .unwrap();
+        let (private, public) = crate::signing_key::create_self_signed_key(params, String::new)?;
+
+        let data = b"Suite: test\nCodename: test\n".to_vec();
+
+        let signature = pgp_cleartext::detached_sign(
+            &private,
+            String::new,
+            pgp::crypto::HashAlgorithm::SHA2_256,
+            std::io::Cursor::new(&data),
+        )?;
+        assert!(signature.starts_with("-----BEGIN PGP SIGNATURE-----"));
+
+        let release = ReleaseFile::from_reader_with_detached_signature(
+            std::io::Cursor::new(&data),
+            std::io::Cursor::new(signature.as_bytes()),
+        )?;
+        assert_eq!(release.suite(), Some("test"));
+        assert!(release.signatures().is_none());
+        assert_eq!(
+            release
+                .detached_signatures()
+                .unwrap()
+                .iter_signatures_from_key(&public)
+                .count(),
+            1
+        );
+
+        ReleaseVerificationPolicy::Keyring(vec![public.clone()]).verify(&release)?;
+        assert!(matches!(
+            ReleaseVerificationPolicy::distro_keys([DistroSigningKey::Debian11Release])
+                .verify(&release),
+            Err(DebianError::ReleaseNoTrustedSignatures)
+        ));
+
+        // Modified content must not verify.
+        let tampered = ReleaseFile::from_reader_with_detached_signature(
+            std::io::Cursor::new(b"Suite: test\nCodename: other\n".to_vec()),
+            std::io::Cursor::new(signature.as_bytes()),
+        )?;
+        assert!(matches!(
+            ReleaseVerificationPolicy::Keyring(vec![public]).verify(&tampered),
+            Err(DebianError::ReleaseNoTrustedSignatures)
+        ));
+
+        Ok(())
+    }
 }
@@ -5,6 +5,7 @@
 /*! PGP signing keys. */
 
 use {
+    crate::error::DebianError,
     pgp::{
         crypto::{HashAlgorithm, SymmetricKeyAlgorithm},
         types::{CompressionAlgorithm, SecretKeyTrait},
@@ -12,8 +13,8 @@ use {
         SignedSecretKey,
     },
     smallvec::smallvec,
-    std::io::Cursor,
-    strum::EnumIter,
+    std::{io::Cursor, str::FromStr},
+    strum::{EnumIter, IntoEnumIterator},
 };
 
 /// Release signing key for Debian 8 Jessie.
@@ -100,6 +101,26 @@ impl DistroSigningKey {
         }
     }
 
+    /// Obtain the name of this key.
+    ///
+    /// Names have the form `<distro>-<version>-<flavor>`. e.g. `debian-11-release`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Debian8Release => "debian-8-release",
+            Self::Debian8Archive => "debian-8-archive",
+            Self::Debian8SecurityArchive => "debian-8-security",
+            Self::Debian9Release => "debian-9-release",
+            Self::Debian9Archive => "debian-9-archive",
+            Self::Debian9SecurityArchive => "debian-9-security",
+            Self::Debian10Release => "debian-10-release",
+            Self::Debian10Archive => "debian-10-archive",
+            Self::Debian10SecurityArchive => "debian-10-security",
+            Self::Debian11Release => "debian-11-release",
+            Self::Debian11Archive => "debian-11-archive",
+            Self::Debian11SecurityArchive => "debian-11-security",
+        }
+    }
+
     /// Obtain the parsed PGP public key for this variant.
     pub fn public_key(&self) -> SignedPublicKey {
         SignedPublicKey::from_armor_single(Cursor::new(self.armored_public_key().as_bytes()))
@@ -108,6 +129,16 @@ impl DistroSigningKey {
     }
 }
 
+impl FromStr for DistroSigningKey {
+    type Err = DebianError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::iter()
+            .find(|key| key.name() == s)
+            .ok_or_else(|| DebianError::DistroSigningKeyUnknown(s.to_string()))
+    }
+}
+
 /// Obtain a [SecretKeyParamsBuilder] defining how to generate a signing key.
 ///
 /// The returned builder will have defaults appropriate for Debian packaging signing keys.
@@ -189,12 +220,15 @@ where
 
 #[cfg(test)]
 mod test {
-    use {super::*, strum::IntoEnumIterator};
+    use super::*;
 
     #[test]
     fn all_distro_signing_keys() {
         for key in DistroSigningKey::iter() {
             key.public_key();
+            assert!(
+                matches!(DistroSigningKey::from_str(key.name()), Ok(k) if k.name() == key.name())
+            );
         }
     }
 
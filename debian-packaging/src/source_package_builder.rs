@@ -0,0 +1,608 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Create Debian source packages.
+
+Source packages consist of a `.dsc` file describing the package plus additional
+files holding the package's sources. [SourcePackageBuilder] produces source
+packages in the `3.0 (quilt)` format, which consist of:
+
+* The upstream tarball (`<source>_<upstream_version>.orig.tar.<ext>`).
+* A tarball of the `debian/` directory (`<source>_<version>.debian.tar.xz`).
+* A `.dsc` file (`<source>_<version>.dsc`), optionally PGP signed.
+
+See <https://manpages.debian.org/unstable/dpkg-dev/dpkg-source.1.en.html> for
+the specification of source package formats.
+*/
+
+use {
+    crate::{
+        changelog::Changelog,
+        control::{ControlField, ControlParagraph},
+        deb::{builder::new_tar_header, DebCompression},
+        debian_source_control::DebianSourceControlFile,
+        error::{DebianError, Result},
+        io::{Compression, ContentDigest, MultiContentDigest, MultiDigester},
+        package_version::PackageVersion,
+        repository::{builder::SourcePackageReference, release::ChecksumType},
+        source_package_control::SourcePackageControlFile,
+    },
+    pgp::{crypto::HashAlgorithm, types::SecretKeyTrait},
+    pgp_cleartext::cleartext_sign,
+    std::{
+        io::Cursor,
+        path::{Path, PathBuf},
+        time::SystemTime,
+    },
+    tugger_file_manifest::FileManifest,
+};
+
+/// The value of the `Format` field for source packages we produce.
+const FORMAT: &str = "3.0 (quilt)";
+
+/// Fields from the source paragraph of `debian/control` that are copied to `.dsc` files.
+///
+/// Fields are emitted in this order after the fields we derive ourselves.
+const DSC_COPIED_FIELDS: &[&str] = &[
+    "Uploaders",
+    "Homepage",
+    "Standards-Version",
+    "Vcs-Browser",
+    "Vcs-Arch",
+    "Vcs-Bzr",
+    "Vcs-Cvs",
+    "Vcs-Darcs",
+    "Vcs-Git",
+    "Vcs-Hg",
+    "Vcs-Mtn",
+    "Vcs-Svn",
+    "Testsuite",
+    "Build-Depends",
+    "Build-Depends-Arch",
+    "Build-Depends-Indep",
+    "Build-Conflicts",
+    "Build-Conflicts-Arch",
+    "Build-Conflicts-Indep",
+];
+
+/// A file constituting a source package.
+#[derive(Clone, Debug)]
+pub struct SourcePackageFile {
+    /// The filename, without any directory components.
+    pub filename: String,
+
+    /// The content of the file.
+    pub data: Vec<u8>,
+}
+
+impl SourcePackageFile {
+    /// Compute content digests of this file.
+    pub fn digests(&self) -> MultiContentDigest {
+        let mut digester = MultiDigester::default();
+        digester.update(&self.data);
+
+        digester.finish()
+    }
+}
+
+/// A source package produced by [SourcePackageBuilder].
+///
+/// All files are held in memory.
+#[derive(Clone, Debug)]
+pub struct SourcePackage {
+    dsc: SourcePackageFile,
+    files: Vec<SourcePackageFile>,
+}
+
+impl SourcePackage {
+    /// The `.dsc` file describing this source package.
+    pub fn dsc(&self) -> &SourcePackageFile {
+        &self.dsc
+    }
+
+    /// Files referenced by the `.dsc` file.
+    ///
+    /// This is the `.orig.tar.*` and `.debian.tar.xz` files.
+    pub fn files(&self) -> impl Iterator<Item = &SourcePackageFile> {
+        self.files.iter()
+    }
+
+    /// All files constituting this source package, starting with the `.dsc` file.
+    pub fn iter_all_files(&self) -> impl Iterator<Item = &SourcePackageFile> {
+        std::iter::once(&self.dsc).chain(self.files.iter())
+    }
+
+    /// Parse the `.dsc` file into a [DebianSourceControlFile].
+    ///
+    /// PGP signatures are parsed but not verified.
+    pub fn control_file(&self) -> Result<DebianSourceControlFile<'static>> {
+        if self
+            .dsc
+            .data
+            .starts_with(b"-----BEGIN PGP SIGNED MESSAGE-----")
+        {
+            DebianSourceControlFile::from_armored_reader(Cursor::new(&self.dsc.data))
+        } else {
+            DebianSourceControlFile::from_reader(Cursor::new(&self.dsc.data))
+        }
+    }
+
+    /// Write all files in this source package to a directory.
+    pub fn write_to_directory(&self, directory: impl AsRef<Path>) -> Result<()> {
+        let directory = directory.as_ref();
+
+        std::fs::create_dir_all(directory)?;
+
+        for file in self.iter_all_files() {
+            std::fs::write(directory.join(&file.filename), &file.data)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'cf> SourcePackageReference<'cf> for SourcePackage {
+    fn dsc_size_bytes(&self) -> Result<u64> {
+        Ok(self.dsc.data.len() as u64)
+    }
+
+    fn dsc_digest(&self, checksum: ChecksumType) -> Result<ContentDigest> {
+        Ok(self.dsc.digests().digest_from_checksum(checksum).clone())
+    }
+
+    fn dsc_filename(&self) -> Result<String> {
+        Ok(self.dsc.filename.clone())
+    }
+
+    fn control_file_for_sources_index(&self) -> Result<DebianSourceControlFile<'cf>> {
+        self.control_file()
+    }
+}
+
+/// A builder for `3.0 (quilt)` Debian source packages.
+///
+/// Instances are constructed from an upstream source tarball, the content of the
+/// `debian/` directory, and a [Changelog]. Call [Self::build()] to produce a
+/// [SourcePackage].
+///
+/// The `debian/` directory must contain a `control` file. Its source paragraph
+/// provides most of the fields in the `.dsc` file. Its binary paragraphs define the
+/// `Binary`, `Architecture`, and `Package-List` fields.
+///
+/// The changelog is written to `debian/changelog`, replacing any existing file. Its
+/// first entry defines the package name and version. The package name must match the
+/// `Source` field in `debian/control` and the version must have a Debian revision.
+///
+/// `debian/source/format` is added if not present.
+///
+/// Patches in `debian/patches/` are included verbatim. They are not applied to or
+/// derived from the upstream sources.
+pub struct SourcePackageBuilder<'a> {
+    upstream_tarball: Vec<u8>,
+    upstream_compression: Compression,
+    debian_files: FileManifest,
+    changelog: Changelog<'a>,
+    mtime: Option<SystemTime>,
+}
+
+impl<'a> SourcePackageBuilder<'a> {
+    /// Construct a new instance.
+    ///
+    /// `upstream_tarball` is the content of the upstream source tarball, which is
+    /// compressed with `compression`. `debian_files` holds the content of the `debian/`
+    /// directory, with paths relative to that directory. e.g. `control` and `rules`.
+    pub fn new(
+        upstream_tarball: Vec<u8>,
+        compression: Compression,
+        debian_files: FileManifest,
+        changelog: Changelog<'a>,
+    ) -> Self {
+        Self {
+            upstream_tarball,
+            upstream_compression: compression,
+            debian_files,
+            changelog,
+            mtime: None,
+        }
+    }
+
+    fn mtime(&self) -> u64 {
+        self.mtime
+            .unwrap_or_else(std::time::SystemTime::now)
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("times before UNIX epoch not accepted")
+            .as_secs()
+    }
+
+    /// Set the modified time to use on members of the `.debian.tar.xz` archive.
+    ///
+    /// If not called, the current time will be used.
+    #[must_use]
+    pub fn set_mtime(mut self, time: Option<SystemTime>) -> Self {
+        self.mtime = time;
+        self
+    }
+
+    /// Build the source package.
+    ///
+    /// `signing_key` provides a signing key for PGP signing the `.dsc` file and a function
+    /// to obtain the password to unlock that key. If `None`, the `.dsc` is not signed.
+    pub fn build<PW>(
+        &self,
+        signing_key: Option<(&impl SecretKeyTrait, PW)>,
+    ) -> Result<SourcePackage>
+    where
+        PW: FnOnce() -> String,
+    {
+        if !matches!(
+            self.upstream_compression,
+            Compression::Gzip | Compression::Bzip2 | Compression::Lzma | Compression::Xz
+        ) {
+            return Err(DebianError::SourcePackageBuildUnsupportedCompression(
+                format!("{:?}", self.upstream_compression),
+            ));
+        }
+
+        let entry = self
+            .changelog
+            .iter_entries()
+            .next()
+            .ok_or(DebianError::SourcePackageBuildNoChangelogEntries)?;
+
+        let version = PackageVersion::parse(&entry.version)?;
+        let debian_revision = version.debian_revision().ok_or_else(|| {
+            DebianError::SourcePackageBuildNativeVersion(entry.version.to_string())
+        })?;
+
+        let control = self
+            .debian_files
+            .get("control")
+            .ok_or(DebianError::SourcePackageBuildMissingFile("debian/control"))?
+            .resolve_content()?;
+        let control = std::str::from_utf8(&control).map_err(|e| {
+            DebianError::ControlParseError(format!("debian/control is not valid UTF-8: {}", e))
+        })?;
+        let control = SourcePackageControlFile::parse_str(control)?;
+        let general = control.general_paragraph();
+
+        let source = general.required_field_str("Source")?;
+        if source != entry.package {
+            return Err(DebianError::SourcePackageBuildSourceMismatch(
+                entry.package.to_string(),
+                source.to_string(),
+            ));
+        }
+
+        let mut debian_files = self.debian_files.clone();
+
+        if let Some(format) = debian_files.get("source/format") {
+            let format = format.resolve_content()?;
+            let format = String::from_utf8_lossy(&format);
+
+            if format.trim() != FORMAT {
+                return Err(DebianError::SourcePackageBuildBadSourceFormat(
+                    format.trim().to_string(),
+                ));
+            }
+        } else {
+            debian_files.add_file_entry("source/format", format!("{}\n", FORMAT).into_bytes())?;
+        }
+
+        let mut changelog = vec![];
+        self.changelog.write(&mut changelog)?;
+        debian_files.add_file_entry("changelog", changelog)?;
+
+        // File names don't contain the epoch.
+        let filename_version = format!("{}-{}", version.upstream_version(), debian_revision);
+
+        let orig = SourcePackageFile {
+            filename: format!(
+                "{}_{}.orig.tar{}",
+                source,
+                version.upstream_version(),
+                self.upstream_compression.extension()
+            ),
+            data: self.upstream_tarball.clone(),
+        };
+
+        let debian_tar = write_debian_tar(&debian_files, self.mtime())?;
+        let debian = SourcePackageFile {
+            filename: format!("{}_{}.debian.tar.xz", source, filename_version),
+            data: DebCompression::Xz(6).compress(&mut Cursor::new(debian_tar))?,
+        };
+
+        let files = vec![orig, debian];
+
+        let mut para = ControlParagraph::default();
+        para.set_field_from_string("Format".into(), FORMAT.into());
+        para.set_field_from_string("Source".into(), source.to_string().into());
+
+        let mut binaries = vec![];
+        let mut architectures = vec![];
+        let mut package_list = vec![];
+
+        for binary in control.binary_paragraphs() {
+            let package = binary.required_field_str("Package")?;
+            let package_architectures = binary
+                .iter_field_words("Architecture")
+                .ok_or_else(|| DebianError::ControlRequiredFieldMissing("Architecture".into()))?
+                .collect::<Vec<_>>();
+
+            binaries.push(package);
+
+            for arch in &package_architectures {
+                if !architectures.contains(arch) {
+                    architectures.push(*arch);
+                }
+            }
+
+            package_list.push(format!(
+                "{} {} {} {} arch={}",
+                package,
+                binary.field_str("Package-Type").unwrap_or("deb"),
+                binary
+                    .field_str("Section")
+                    .or_else(|| general.field_str("Section"))
+                    .unwrap_or("misc"),
+                binary
+                    .field_str("Priority")
+                    .or_else(|| general.field_str("Priority"))
+                    .unwrap_or("optional"),
+                package_architectures.join(",")
+            ));
+        }
+
+        para.set_field_from_string("Binary".into(), binaries.join(", ").into());
+        para.set_field_from_string("Architecture".into(), architectures.join(" ").into());
+        para.set_field_from_string("Version".into(), entry.version.to_string().into());
+        para.set_field_from_string(
+            "Maintainer".into(),
+            general.required_field_str("Maintainer")?.to_string().into(),
+        );
+
+        for name in DSC_COPIED_FIELDS {
+            if let Some(field) = general.field(name) {
+                para.set_field(field.clone());
+            }
+        }
+
+        para.set_field(ControlField::from_lines(
+            "Package-List".into(),
+            std::iter::once(String::new()).chain(package_list.into_iter()),
+        ));
+
+        let digests = files.iter().map(|f| f.digests()).collect::<Vec<_>>();
+
+        for (field, checksum) in [
+            ("Checksums-Sha1", ChecksumType::Sha1),
+            ("Checksums-Sha256", ChecksumType::Sha256),
+            ("Files", ChecksumType::Md5),
+        ] {
+            para.set_field(ControlField::from_lines(
+                field.into(),
+                std::iter::once(String::new()).chain(files.iter().zip(digests.iter()).map(
+                    |(file, digests)| {
+                        format!(
+                            "{} {} {}",
+                            digests.digest_from_checksum(checksum).digest_hex(),
+                            file.data.len(),
+                            file.filename
+                        )
+                    },
+                )),
+            ));
+        }
+
+        let dsc_data = if let Some((key, password)) = signing_key {
+            cleartext_sign(
+                key,
+                password,
+                HashAlgorithm::SHA2_256,
+                Cursor::new(para.to_string().as_bytes()),
+            )?
+        } else {
+            para.to_string()
+        };
+
+        let dsc = SourcePackageFile {
+            filename: format!("{}_{}.dsc", source, filename_version),
+            data: dsc_data.into_bytes(),
+        };
+
+        Ok(SourcePackage { dsc, files })
+    }
+}
+
+/// Write the `.debian.tar` archive holding the `debian/` directory.
+fn write_debian_tar(files: &FileManifest, mtime: u64) -> Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(vec![]);
+
+    let root = Path::new("debian");
+
+    for directory in std::iter::once(PathBuf::new()).chain(files.relative_directories()) {
+        let mut header = new_tar_header(mtime)?;
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_mode(0o755);
+        header.set_size(0);
+        builder.append_data(&mut header, root.join(directory), std::io::empty())?;
+    }
+
+    // FileManifest is backed by a BTreeMap, so iteration is deterministic.
+    for (rel_path, entry) in files.iter_entries() {
+        let data = entry.resolve_content()?;
+
+        let mut header = new_tar_header(mtime)?;
+        header.set_mode(if entry.is_executable() { 0o755 } else { 0o644 });
+        header.set_size(data.len() as _);
+        builder.append_data(&mut header, root.join(rel_path), &*data)?;
+    }
+
+    Ok(builder.into_inner()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            changelog::ChangelogEntry,
+            signing_key::{create_self_signed_key, signing_secret_key_params_builder},
+        },
+        chrono::DateTime,
+        std::io::Read,
+        tugger_file_manifest::FileEntry,
+    };
+
+    const CONTROL: &str = "Source: mypackage\n\
+        Section: utils\n\
+        Priority: optional\n\
+        Maintainer: Me <me@example.com>\n\
+        Build-Depends: debhelper-compat (= 13)\n\
+        Standards-Version: 4.6.0\n\
+        Homepage: https://example.com\n\
+        \n\
+        Package: mypackage\n\
+        Architecture: any\n\
+        Description: my package\n\
+        \n\
+        Package: mypackage-doc\n\
+        Architecture: all\n\
+        Section: doc\n\
+        Description: my package docs\n";
+
+    fn source_package_builder(version: &str) -> Result<SourcePackageBuilder<'_>> {
+        let mut debian_files = FileManifest::default();
+        debian_files.add_file_entry("control", CONTROL.as_bytes().to_vec())?;
+        debian_files.add_file_entry(
+            "rules",
+            FileEntry::new_from_data(b"#!/usr/bin/make -f\n".to_vec(), true),
+        )?;
+
+        let mut changelog = Changelog::default();
+        changelog.add_entry(ChangelogEntry {
+            package: "mypackage".into(),
+            version: version.into(),
+            distributions: vec!["unstable".into()],
+            urgency: "low".into(),
+            details: "  * Initial release.".into(),
+            maintainer_name: "Me".into(),
+            maintainer_email: "me@example.com".into(),
+            date: DateTime::from_utc(
+                chrono::NaiveDateTime::from_timestamp(1420000000, 0),
+                chrono::TimeZone::from_offset(&chrono::FixedOffset::west(3600 * 7)),
+            ),
+        });
+
+        let upstream = DebCompression::Gzip.compress(&mut Cursor::new(b"upstream".to_vec()))?;
+
+        Ok(
+            SourcePackageBuilder::new(upstream, Compression::Gzip, debian_files, changelog)
+                .set_mtime(Some(SystemTime::UNIX_EPOCH)),
+        )
+    }
+
+    #[test]
+    fn build_unsigned() -> Result<()> {
+        let package =
+            source_package_builder("1:1.0-1")?.build(crate::repository::builder::NO_SIGNING_KEY)?;
+
+        assert_eq!(package.dsc().filename, "mypackage_1.0-1.dsc");
+        assert_eq!(
+            package
+                .files()
+                .map(|f| f.filename.as_str())
+                .collect::<Vec<_>>(),
+            vec!["mypackage_1.0.orig.tar.gz", "mypackage_1.0-1.debian.tar.xz"]
+        );
+
+        let cf = package.control_file()?;
+        assert!(cf.signatures().is_none());
+        assert_eq!(cf.format()?, "3.0 (quilt)");
+        assert_eq!(cf.source()?, "mypackage");
+        assert_eq!(
+            cf.binary().unwrap().collect::<Vec<_>>(),
+            vec!["mypackage", "mypackage-doc"]
+        );
+        assert_eq!(
+            cf.architecture().unwrap().collect::<Vec<_>>(),
+            vec!["any", "all"]
+        );
+        assert_eq!(cf.version_str()?, "1:1.0-1");
+        assert_eq!(cf.maintainer()?, "Me <me@example.com>");
+        assert_eq!(cf.standards_version()?, "4.6.0");
+        assert_eq!(cf.homepage(), Some("https://example.com"));
+        assert_eq!(
+            cf.field_str("Build-Depends"),
+            Some("debhelper-compat (= 13)")
+        );
+        assert_eq!(
+            cf.package_list()
+                .unwrap()
+                .map(|p| Ok(p?.section))
+                .collect::<Result<Vec<_>>>()?,
+            vec!["utils", "doc"]
+        );
+
+        let files = cf.files()?.collect::<Result<Vec<_>>>()?;
+        assert_eq!(files.len(), 2);
+        for (entry, file) in files.iter().zip(package.files()) {
+            assert_eq!(entry.filename, file.filename);
+            assert_eq!(entry.size, file.data.len() as u64);
+            assert_eq!(entry.digest, file.digests().md5);
+        }
+        assert_eq!(cf.checksums_sha256().unwrap().count(), 2);
+
+        let mut debian_tar = vec![];
+        xz2::read::XzDecoder::new(Cursor::new(&package.files[1].data))
+            .read_to_end(&mut debian_tar)?;
+        let mut archive = tar::Archive::new(Cursor::new(debian_tar));
+        let paths = archive
+            .entries()?
+            .map(|entry| Ok(entry?.path()?.display().to_string()))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            paths,
+            vec![
+                "debian",
+                "debian/source",
+                "debian/changelog",
+                "debian/control",
+                "debian/rules",
+                "debian/source/format"
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn build_signed() -> Result<()> {
+        let key_params = signing_secret_key_params_builder("someone@example.com")
+            .build()
+            .unwrap();
+        let (key, public_key) = create_self_signed_key(key_params, String::new)?;
+
+        let package = source_package_builder("1.0-1")?.build(Some((&key, String::new)))?;
+
+        let cf = package.control_file()?;
+        assert_eq!(
+            cf.signatures()
+                .expect("PGP signatures should have been parsed")
+                .verify(&public_key)?,
+            1
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn build_native_version_rejected() -> Result<()> {
+        assert!(matches!(
+            source_package_builder("1.0")?.build(crate::repository::builder::NO_SIGNING_KEY),
+            Err(DebianError::SourcePackageBuildNativeVersion(_))
+        ));
+
+        Ok(())
+    }
+}
@@ -134,6 +134,31 @@ installer_binary_packages_only_architectures (optional) (list[string])
 sources_copy (optional) (bool)
    Whether to copy source packages.
 
+source_signing_keys (optional) (list[string])
+   Names of signing keys built into this tool that are trusted to sign the
+   source repository's `InRelease` files. Possible values are of the form
+   `debian-<version>-<flavor>`, where `<version>` is 8, 9, 10, or 11 and
+   `<flavor>` is `release`, `archive`, or `security`. e.g.
+   `debian-11-release`.
+
+source_signing_key_paths (optional) (list[string])
+   Paths to files containing ASCII armored PGP public keys that are trusted
+   to sign the source repository's `InRelease` files.
+
+source_unverified (optional) (bool)
+   Copy from the source repository without verifying PGP signatures on its
+   `InRelease` files.
+
+   Copying fails unless trusted signing keys are defined or this is set.
+
+# Signature Verification
+
+The `InRelease` file of each copied distribution must carry a valid PGP
+signature from one of the keys defined by `source_signing_keys` or
+`source_signing_key_paths`. If its `Valid-Until` field is in the past,
+copying also fails. This guards against copying content from a tampered
+mirror.
+
 # Partial Copying
 
 By default, a copy operation will copy all content in the specified
@@ -5,8 +5,14 @@
 use {
     anyhow::{anyhow, Result},
     clap::{Arg, ArgMatches, Command},
+    debian_packaging::{
+        repository::release::ReleaseVerificationPolicy, signing_key::DistroSigningKey,
+    },
     rpm_repository::RepositoryRootReader,
-    std::collections::{HashMap, HashSet},
+    std::{
+        collections::{HashMap, HashSet},
+        str::FromStr,
+    },
 };
 
 const ABOUT: &str = "\
@@ -31,6 +37,9 @@ values include:
 
 * http://ftp.us.debian.org/debian (Debian)
 * http://us.archive.ubuntu.com/ubuntu (Ubuntu)
+
+The release file must be signed by a key given by --signing-key or
+--signing-key-path. Pass --unverified to skip signature verification.
 ";
 
 pub async fn run() -> Result<()> {
@@ -90,6 +99,26 @@ pub async fn run() -> Result<()> {
                     .default_value("main")
                     .help("Comma delimited list of components to fetch"),
             )
+            .arg(
+                Arg::new("signing_key")
+                    .long("signing-key")
+                    .takes_value(true)
+                    .multiple_occurrences(true)
+                    .help("Name of a built-in PGP key trusted to sign the repository (e.g. debian-11-release)"),
+            )
+            .arg(
+                Arg::new("signing_key_path")
+                    .long("signing-key-path")
+                    .takes_value(true)
+                    .multiple_occurrences(true)
+                    .help("Path to a file of ASCII armored PGP public keys trusted to sign the repository"),
+            )
+            .arg(
+                Arg::new("unverified")
+                    .long("unverified")
+                    .conflicts_with_all(&["signing_key", "signing_key_path"])
+                    .help("Do not verify PGP signatures on the repository's release file"),
+            )
             .arg(
                 Arg::new("url")
                     .required(true)
@@ -258,6 +287,32 @@ async fn command_import_debian_deb(args: &ArgMatches) -> Result<()> {
     Ok(())
 }
 
+fn debian_release_verification_policy(args: &ArgMatches) -> Result<ReleaseVerificationPolicy> {
+    if args.is_present("unverified") {
+        return Ok(ReleaseVerificationPolicy::Unverified);
+    }
+
+    let mut keys = vec![];
+
+    for name in args.values_of("signing_key").into_iter().flatten() {
+        keys.push(DistroSigningKey::from_str(name)?.public_key());
+    }
+
+    for path in args.values_of("signing_key_path").into_iter().flatten() {
+        let fh = std::fs::File::open(path)
+            .map_err(|e| anyhow!("error opening signing key {}: {}", path, e))?;
+        keys.extend(ReleaseVerificationPolicy::read_armored_keys(fh)?);
+    }
+
+    if keys.is_empty() {
+        Err(anyhow!(
+            "--signing-key or --signing-key-path is required to verify the repository; pass --unverified to skip verification"
+        ))
+    } else {
+        Ok(ReleaseVerificationPolicy::Keyring(keys))
+    }
+}
+
 async fn command_import_debian_repository(args: &ArgMatches) -> Result<()> {
     let threads = args.value_of_t::<usize>("threads")?;
     let db_path = args.value_of("db_path").expect("database path is required");
@@ -281,7 +336,8 @@ async fn command_import_debian_repository(args: &ArgMatches) -> Result<()> {
 
     let mut db = crate::db::DatabaseConnection::new_path(db_path)?;
 
-    let root_reader = debian_packaging::repository::reader_from_str(url)?;
+    let mut root_reader = debian_packaging::repository::reader_from_str(url)?;
+    root_reader.set_release_verification_policy(debian_release_verification_policy(args)?);
     eprintln!("fetching InRelease file for {}", distribution);
     let release = root_reader.release_reader(distribution).await?;
 
@@ -1049,8 +1049,12 @@ mod test {
     async fn high_level() -> Result<()> {
         let mut db = DatabaseConnection::new_memory()?;
 
-        let client =
+        let mut client =
             debian_packaging::repository::reader_from_str("http://us.archive.ubuntu.com/ubuntu")?;
+        // Ubuntu's signing keys aren't built in.
+        client.set_release_verification_policy(
+            debian_packaging::repository::release::ReleaseVerificationPolicy::Unverified,
+        );
         let release = client.release_reader("impish").await?;
 
         let libc = release
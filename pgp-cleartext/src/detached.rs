@@ -0,0 +1,236 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! PGP detached signatures.
+
+Detached signatures are stored separately from the data they sign. They are
+ASCII armored documents beginning with `-----BEGIN PGP SIGNATURE-----`. e.g.
+Debian repositories publish a `Release.gpg` file holding a detached signature
+over the sibling `Release` file.
+
+Only signatures over binary documents (signature type 0x00) are supported.
+This is what `gpg --detach-sign` produces by default.
+*/
+
+use {
+    crate::{verify_signatures, CleartextHasher},
+    chrono::SubsecRound,
+    pgp::{
+        crypto::{HashAlgorithm, Hasher},
+        packet::{Packet, SignatureConfig, SignatureType, Subpacket},
+        types::{KeyVersion, PublicKeyTrait, SecretKeyTrait},
+        Signature,
+    },
+    smallvec::SmallVec,
+    std::{
+        collections::HashMap,
+        io::{self, Cursor, Read},
+    },
+};
+
+/// A reader that digests content covered by a detached PGP signature.
+///
+/// Instances are constructed from a reader of the signed data and a reader of the
+/// ASCII armored detached signature. The signature is parsed at construction time.
+///
+/// This type is a pass-through [Read] implementation: bytes from the data reader are
+/// returned as-is and are fed into hashers needed to verify the parsed signatures.
+///
+/// Once EOF is reached, call [Self::finalize()] to consume the reader and return a
+/// [DetachedSignatures] holding signature state.
+///
+/// Important: reading does not validate signatures. Use [DetachedSignatures] after
+/// reading to validate signatures.
+pub struct DetachedSignatureReader<R: Read> {
+    reader: R,
+
+    /// Hashers for each hash algorithm used by a signature.
+    hashers: HashMap<u8, CleartextHasher>,
+
+    /// Parsed PGP signatures.
+    signatures: Vec<Signature>,
+}
+
+impl<R: Read> DetachedSignatureReader<R> {
+    /// Construct a new instance from a data reader and an armored signature reader.
+    ///
+    /// Errors if the signature is not well-formed or if it uses an unsupported hash
+    /// algorithm or signature type.
+    pub fn new(reader: R, signature: impl Read) -> pgp::errors::Result<Self> {
+        let mut dearmor = pgp::armor::Dearmor::new(signature);
+        dearmor.read_header()?;
+
+        if !matches!(dearmor.typ, Some(pgp::armor::BlockType::Signature)) {
+            return Err(pgp::errors::Error::Message(
+                "failed to parse PGP signature armor".to_string(),
+            ));
+        }
+
+        let mut hashers = HashMap::new();
+        let mut signatures = vec![];
+
+        for packet in pgp::packet::PacketParser::new(dearmor) {
+            let signature = match packet? {
+                Packet::Signature(signature) => signature,
+                packet => {
+                    return Err(pgp::errors::Error::Message(format!(
+                        "unexpected PGP packet seen; expected Signature; got {:?}",
+                        packet.tag()
+                    )));
+                }
+            };
+
+            if !matches!(signature.typ(), SignatureType::Binary) {
+                return Err(pgp::errors::Error::Unsupported(format!(
+                    "detached signature type {:?}; only binary signatures are supported",
+                    signature.typ()
+                )));
+            }
+
+            let hasher = match signature.config.hash_alg {
+                HashAlgorithm::MD5 => CleartextHasher::md5(),
+                HashAlgorithm::SHA1 => CleartextHasher::sha1(),
+                HashAlgorithm::SHA2_256 => CleartextHasher::sha256(),
+                HashAlgorithm::SHA2_384 => CleartextHasher::sha384(),
+                HashAlgorithm::SHA2_512 => CleartextHasher::sha512(),
+                alg => {
+                    return Err(pgp::errors::Error::Unsupported(format!(
+                        "unsupported PGP hash type: {:?}",
+                        alg
+                    )));
+                }
+            };
+
+            hashers.entry(hasher.algorithm() as u8).or_insert(hasher);
+            signatures.push(signature);
+        }
+
+        Ok(Self {
+            reader,
+            hashers,
+            signatures,
+        })
+    }
+
+    /// Finalize this reader, returning an object with signature state.
+    ///
+    /// Only data that was read from this instance is covered by the returned hashes.
+    /// Callers should read until EOF before calling this.
+    pub fn finalize(self) -> DetachedSignatures {
+        DetachedSignatures {
+            hashers: self.hashers,
+            signatures: self.signatures,
+        }
+    }
+}
+
+impl<R: Read> Read for DetachedSignatureReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.reader.read(buf)?;
+
+        for hasher in self.hashers.values_mut() {
+            hasher.update(&buf[0..count]);
+        }
+
+        Ok(count)
+    }
+}
+
+/// Parsed detached signatures data.
+///
+/// Instances are obtained from [DetachedSignatureReader::finalize()] and hold digests
+/// of the signed content as well as the parsed PGP signature packets.
+pub struct DetachedSignatures {
+    hashers: HashMap<u8, CleartextHasher>,
+    signatures: Vec<Signature>,
+}
+
+impl DetachedSignatures {
+    /// Parse an armored detached signature and digest signed data until EOF.
+    ///
+    /// This is a convenience method for reading a [DetachedSignatureReader] to completion
+    /// when the signed data itself isn't needed.
+    pub fn from_readers(data: impl Read, signature: impl Read) -> pgp::errors::Result<Self> {
+        let mut reader = DetachedSignatureReader::new(data, signature)?;
+        io::copy(&mut reader, &mut io::sink())?;
+
+        Ok(reader.finalize())
+    }
+
+    /// Iterate over signatures in this instance.
+    pub fn iter_signatures(&self) -> impl Iterator<Item = &Signature> {
+        self.signatures.iter()
+    }
+
+    /// Iterate over signatures made by a specific key.
+    ///
+    /// This is a convenience wrapper for [Self::iter_signatures()] that filters based on the
+    /// signature's issuer matching the key ID of the specified key.
+    pub fn iter_signatures_from_key<'slf, 'key: 'slf>(
+        &'slf self,
+        key: &'key impl PublicKeyTrait,
+    ) -> impl Iterator<Item = &'slf Signature> {
+        self.signatures
+            .iter()
+            .filter(|sig| sig.issuer() == Some(&key.key_id()))
+    }
+
+    /// Verify a signature made from a known key.
+    ///
+    /// Returns the numbers of signatures verified against this key.
+    ///
+    /// Semantics are the same as [crate::CleartextSignatures::verify()].
+    pub fn verify(&self, key: &impl PublicKeyTrait) -> pgp::errors::Result<usize> {
+        if self.signatures.is_empty() {
+            return Err(pgp::errors::Error::Message(
+                "no PGP signatures present".to_string(),
+            ));
+        }
+
+        verify_signatures(&self.hashers, self.iter_signatures_from_key(key), key)
+    }
+}
+
+/// Produce a detached signature over data.
+///
+/// The data to be signed is provided by a reader. It is signed as a binary document:
+/// no line ending normalization is performed.
+///
+/// The returned value is a multiline string with LF line endings containing an ASCII
+/// armored `-----BEGIN PGP SIGNATURE-----` block. The signature is produced by the
+/// provided key using the specified hashing algorithm.
+pub fn detached_sign<PW, R>(
+    key: &impl SecretKeyTrait,
+    key_pw: PW,
+    hash_algorithm: HashAlgorithm,
+    data: R,
+) -> pgp::errors::Result<String>
+where
+    PW: FnOnce() -> String,
+    R: Read,
+{
+    let hashed_subpackets = vec![
+        Subpacket::IssuerFingerprint(KeyVersion::V4, SmallVec::from_slice(&key.fingerprint())),
+        Subpacket::SignatureCreationTime(chrono::Utc::now().trunc_subsecs(0)),
+    ];
+    let unhashed_subpackets = vec![Subpacket::Issuer(key.key_id())];
+
+    let config = SignatureConfig::new_v4(
+        Default::default(),
+        SignatureType::Binary,
+        key.algorithm(),
+        hash_algorithm,
+        hashed_subpackets,
+        unhashed_subpackets,
+    );
+
+    let signature = config.sign(key, key_pw, data)?;
+
+    let packet = Packet::Signature(signature);
+    let mut writer = Cursor::new(Vec::<u8>::new());
+    pgp::armor::write(&packet, pgp::armor::BlockType::Signature, &mut writer, None)?;
+
+    String::from_utf8(writer.into_inner())
+        .map_err(|e| pgp::errors::Error::Utf8Error(e.utf8_error()))
+}
@@ -25,8 +25,15 @@ Hash: <digest>
 <signature data>
 -----END PGP SIGNATURE-----
 ```
+
+PGP detached signatures, which are stored separately from the data they sign,
+are supported via [DetachedSignatureReader], [DetachedSignatures], and
+[detached_sign()].
 */
 
+mod detached;
+pub use detached::{detached_sign, DetachedSignatureReader, DetachedSignatures};
+
 use {
     chrono::SubsecRound,
     digest::Digest,
@@ -487,44 +494,56 @@ impl CleartextSignatures {
             ));
         }
 
-        let mut valid_signatures = 0;
-
-        for sig in self.iter_signatures_from_key(key) {
-            // We need to feed signature-specific state into the hasher (which was previously
-            // fed the cleartext) to verify the signature. Fortunately we can clone hashers.
-            let mut hasher = Box::new(
-                self.hashers
-                    .get(&(sig.config.hash_alg as u8))
-                    .ok_or_else(|| {
-                        pgp::errors::Error::Message(format!(
-                            "could not find hasher matching signature hash algorithm ({:?})",
-                            sig.config.hash_alg
-                        ))
-                    })?
-                    .clone(),
-            );
-
-            let len = sig.config.hash_signature_data(&mut *hasher)?;
-            hasher.update(&sig.config.trailer(len));
-
-            let digest = hasher.finish();
-
-            if digest[0..2] != sig.signed_hash_value {
-                return Err(pgp::errors::Error::Message(
-                    "invalid signed hash value".into(),
-                ));
-            }
+        verify_signatures(&self.hashers, self.iter_signatures_from_key(key), key)
+    }
+}
 
-            key.verify_signature(sig.config.hash_alg, &digest, &sig.signature)?;
-            valid_signatures += 1;
+/// Verify signatures against hashers that were fed the signed content.
+///
+/// Returns the number of verified signatures. Errors if there are no signatures or if
+/// any signature fails to verify.
+pub(crate) fn verify_signatures<'a>(
+    hashers: &HashMap<u8, CleartextHasher>,
+    signatures: impl Iterator<Item = &'a Signature>,
+    key: &impl PublicKeyTrait,
+) -> pgp::errors::Result<usize> {
+    let mut valid_signatures = 0;
+
+    for sig in signatures {
+        // We need to feed signature-specific state into the hasher (which was previously
+        // fed the signed content) to verify the signature. Fortunately we can clone hashers.
+        let mut hasher = Box::new(
+            hashers
+                .get(&(sig.config.hash_alg as u8))
+                .ok_or_else(|| {
+                    pgp::errors::Error::Message(format!(
+                        "could not find hasher matching signature hash algorithm ({:?})",
+                        sig.config.hash_alg
+                    ))
+                })?
+                .clone(),
+        );
+
+        let len = sig.config.hash_signature_data(&mut *hasher)?;
+        hasher.update(&sig.config.trailer(len));
+
+        let digest = hasher.finish();
+
+        if digest[0..2] != sig.signed_hash_value {
+            return Err(pgp::errors::Error::Message(
+                "invalid signed hash value".into(),
+            ));
         }
 
-        match valid_signatures {
-            0 => Err(pgp::errors::Error::Message(
-                "no signatures signed by provided key".into(),
-            )),
-            _ => Ok(valid_signatures),
-        }
+        key.verify_signature(sig.config.hash_alg, &digest, &sig.signature)?;
+        valid_signatures += 1;
+    }
+
+    match valid_signatures {
+        0 => Err(pgp::errors::Error::Message(
+            "no signatures signed by provided key".into(),
+        )),
+        _ => Ok(valid_signatures),
     }
 }
 
@@ -9,14 +9,15 @@ use {
     oxidized_importer::{PackedResourcesSource, PythonResourcesState},
     pyo3::ffi as pyffi,
     python_packaging::interpreter::{
-        MemoryAllocatorBackend, MultiprocessingStartMethod, PythonInterpreterConfig,
-        PythonInterpreterProfile, TerminfoResolution,
+        CrashOutput, ImportTraceFormat, MemoryAllocatorBackend, MultiprocessingStartMethod,
+        PythonInterpreterConfig, PythonInterpreterProfile, TerminfoResolution,
     },
     std::{
         ffi::{CString, OsString},
         ops::Deref,
         path::PathBuf,
     },
+    x509_certificate::CapturedX509Certificate,
 };
 
 #[cfg(feature = "serialization")]
@@ -273,6 +274,69 @@ pub struct OxidizedPythonInterpreterConfig<'a> {
     #[cfg_attr(feature = "serialization", serde(skip))]
     pub packed_resources: Vec<PackedResourcesSource<'a>>,
 
+    /// Trust anchors used to verify memory mapped packed resources.
+    ///
+    /// Each entry is a PEM encoded X.509 certificate.
+    ///
+    /// If non-empty, every [PackedResourcesSource::MemoryMappedPath] in
+    /// [Self::packed_resources] must be accompanied by a detached signature in a
+    /// file having the same path plus a `.sig` suffix. The signature must have
+    /// been produced by one of these certificates or a certificate issued by
+    /// one of them and every resource in the data must carry an integrity digest
+    /// matching its data. See the `python-packed-resources` crate for how this
+    /// data is produced.
+    ///
+    /// Files being verified are read into memory instead of being memory mapped,
+    /// so they can't be modified after verification.
+    ///
+    /// Packed resources held in memory are not verified, as they are typically
+    /// part of the executable itself.
+    ///
+    /// Archive files made importable at run-time via `OxidizedFinder.path_hook()`
+    /// are subject to the same verification. Zip archives can't be verified and
+    /// are rejected. `zipimport.zipimporter` is removed from `sys.path_hooks` so
+    /// they can't be imported through it either.
+    ///
+    /// Default value: `vec![]`
+    ///
+    /// Interpreter initialization behavior: if any memory mapped packed resources
+    /// fail verification, interpreter initialization fails and no resources from
+    /// the offending file are loaded.
+    pub packed_resources_trust_anchors: Vec<String>,
+
+    /// Directories holding Python source files that take precedence over packed resources.
+    ///
+    /// When non-empty, the `OxidizedFinder` consults these directories, in order,
+    /// before its indexed resources. A module `foo.bar` is found at
+    /// `<dir>/foo/bar/__init__.py` or `<dir>/foo/bar.py`. Found source files are
+    /// compiled on the fly each time they are imported and no bytecode is written.
+    /// Modules not present in any directory are loaded from indexed resources, as
+    /// normal.
+    ///
+    /// This facilitates iterating on application code without rebuilding the
+    /// packed resources. Lookups are cached: call `importlib.invalidate_caches()`
+    /// after adding or removing files.
+    ///
+    /// Default value: `vec![]`
+    ///
+    /// [Self::resolve()] behavior: the token `$ORIGIN` is expanded to the resolved
+    /// value of [Self::origin]. If [Self::source_overlay_paths_env] is set, its
+    /// entries are appended.
+    ///
+    /// Interpreter initialization behavior: has no effect unless
+    /// [Self::oxidized_importer] is [true].
+    pub source_overlay_paths: Vec<PathBuf>,
+
+    /// Environment variable defining additional [Self::source_overlay_paths].
+    ///
+    /// If this value is set and the environment variable it refers to is set,
+    /// the variable's value is split into paths using the platform's path list
+    /// separator (`:` on UNIX, `;` on Windows) and the paths are appended to
+    /// [Self::source_overlay_paths].
+    ///
+    /// Default value: [None]
+    pub source_overlay_paths_env: Option<String>,
+
     /// Extra extension modules to make available to the interpreter.
     ///
     /// The values will effectively be passed to ``PyImport_ExtendInitTab()``.
@@ -383,6 +447,57 @@ pub struct OxidizedPythonInterpreterConfig<'a> {
     ///
     /// Default value: [None]
     pub write_modules_directory_env: Option<String>,
+
+    /// Environment variable holding the directory to write an import trace file.
+    ///
+    /// If this value is set and the environment variable it refers to is set,
+    /// the `OxidizedFinder` records every module it loads: the finder that served
+    /// it, where its code came from (``memory``, ``relative-path``, ``builtin``,
+    /// ``frozen``, or ``source-overlay``), the module whose execution triggered
+    /// the import, and the time spent creating the module, obtaining its code, and
+    /// executing it. On interpreter shutdown, an ``imports-<random>.json`` file
+    /// in the format defined by [Self::import_trace_format] is written to the
+    /// directory specified.
+    ///
+    /// Default value: [None]
+    ///
+    /// Interpreter initialization behavior: has no effect unless
+    /// [Self::oxidized_importer] is [true].
+    pub write_import_trace_directory_env: Option<String>,
+
+    /// Format of files written due to [Self::write_import_trace_directory_env].
+    ///
+    /// Default value: [ImportTraceFormat::Json]
+    pub import_trace_format: ImportTraceFormat,
+
+    /// Enable Python's `faulthandler` module, writing to the given destination.
+    ///
+    /// If set, `faulthandler` is enabled for all threads immediately after the
+    /// core of the interpreter is initialized, so crashes during the remainder of
+    /// interpreter initialization are also reported. This is independent of
+    /// `PyConfig.faulthandler`, which always writes to stderr.
+    ///
+    /// Default value: [None]
+    pub faulthandler_output: Option<CrashOutput>,
+
+    /// Write a crash report to the given destination on Rust panics and fatal signals.
+    ///
+    /// If set, a Rust panic hook and (on UNIX) handlers for fatal signals like
+    /// `SIGSEGV` are installed after interpreter initialization. They write the
+    /// Python stack of every thread. Previously installed hooks and handlers run
+    /// afterwards.
+    ///
+    /// Reports for panics also list the loaded modules and where they were
+    /// loaded from if the panicking thread holds the GIL. Reports for signals
+    /// don't, as doing so isn't async-signal-safe.
+    ///
+    /// A report is written for every panic, including panics that are caught
+    /// afterwards. This includes panics in Rust code called from Python, which
+    /// PyO3 converts into `PanicException`. So a report doesn't necessarily mean
+    /// the process crashed.
+    ///
+    /// Default value: [None]
+    pub crash_report_output: Option<CrashOutput>,
 }
 
 impl<'a> Default for OxidizedPythonInterpreterConfig<'a> {
@@ -406,6 +521,9 @@ impl<'a> Default for OxidizedPythonInterpreterConfig<'a> {
             oxidized_importer: false,
             filesystem_importer: true,
             packed_resources: vec![],
+            packed_resources_trust_anchors: vec![],
+            source_overlay_paths: vec![],
+            source_overlay_paths_env: None,
             extra_extension_modules: None,
             argv: None,
             argvb: false,
@@ -416,6 +534,10 @@ impl<'a> Default for OxidizedPythonInterpreterConfig<'a> {
             terminfo_resolution: TerminfoResolution::Dynamic,
             tcl_library: None,
             write_modules_directory_env: None,
+            write_import_trace_directory_env: None,
+            import_trace_format: ImportTraceFormat::Json,
+            faulthandler_output: None,
+            crash_report_output: None,
         }
     }
 }
@@ -488,6 +610,18 @@ impl<'a> OxidizedPythonInterpreterConfig<'a> {
             .as_ref()
             .map(|x| PathBuf::from(x.display().to_string().replace("$ORIGIN", &origin_string)));
 
+        let mut source_overlay_paths = self
+            .source_overlay_paths
+            .iter()
+            .map(|p| PathBuf::from(p.display().to_string().replace("$ORIGIN", &origin_string)))
+            .collect::<Vec<_>>();
+
+        if let Some(key) = &self.source_overlay_paths_env {
+            if let Some(value) = std::env::var_os(key) {
+                source_overlay_paths.extend(std::env::split_paths(&value));
+            }
+        }
+
         Ok(ResolvedOxidizedPythonInterpreterConfig {
             inner: Self {
                 exe: Some(exe),
@@ -498,6 +632,7 @@ impl<'a> OxidizedPythonInterpreterConfig<'a> {
                 },
                 argv,
                 packed_resources,
+                source_overlay_paths,
                 tcl_library,
                 ..self
             },
@@ -542,6 +677,24 @@ impl<'a> ResolvedOxidizedPythonInterpreterConfig<'a> {
             .expect("origin should have a value")
     }
 
+    /// Parse [OxidizedPythonInterpreterConfig::packed_resources_trust_anchors].
+    pub fn resolve_packed_resources_trust_anchors(
+        &self,
+    ) -> Result<Vec<CapturedX509Certificate>, NewInterpreterError> {
+        self.inner
+            .packed_resources_trust_anchors
+            .iter()
+            .map(|pem| {
+                CapturedX509Certificate::from_pem(pem).map_err(|e| {
+                    NewInterpreterError::Dynamic(format!(
+                        "error parsing packed resources trust anchor: {}",
+                        e
+                    ))
+                })
+            })
+            .collect()
+    }
+
     /// Resolve the effective value of `sys.argv`.
     pub fn resolve_sys_argv(&self) -> &[OsString] {
         if let Some(args) = &self.inner.argv {
@@ -577,6 +730,8 @@ impl<'a, 'config: 'a> TryFrom<&ResolvedOxidizedPythonInterpreterConfig<'config>>
         state.set_current_exe(config.exe().to_path_buf());
         state.set_origin(config.origin().to_path_buf());
 
+        let trust_anchors = config.resolve_packed_resources_trust_anchors()?;
+
         for source in &config.packed_resources {
             match source {
                 PackedResourcesSource::Memory(data) => {
@@ -585,9 +740,18 @@ impl<'a, 'config: 'a> TryFrom<&ResolvedOxidizedPythonInterpreterConfig<'config>>
                         .map_err(NewInterpreterError::Simple)?;
                 }
                 PackedResourcesSource::MemoryMappedPath(path) => {
-                    state
-                        .index_path_memory_mapped(path)
-                        .map_err(NewInterpreterError::Dynamic)?;
+                    if trust_anchors.is_empty() {
+                        state
+                            .index_path_memory_mapped(path)
+                            .map_err(NewInterpreterError::Dynamic)?;
+                    } else {
+                        let mut signature_path = path.clone().into_os_string();
+                        signature_path.push(".sig");
+
+                        state
+                            .index_path_verified(path, signature_path, &trust_anchors)
+                            .map_err(NewInterpreterError::Dynamic)?;
+                    }
                 }
             }
         }
@@ -649,4 +813,34 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_source_overlay_paths() -> Result<()> {
+        let env_key = "PYEMBED_TEST_SOURCE_OVERLAY_PATHS";
+        std::env::set_var(
+            env_key,
+            std::env::join_paths(["/env/a", "/env/b"].iter()).unwrap(),
+        );
+
+        let config = OxidizedPythonInterpreterConfig {
+            origin: Some(PathBuf::from("/other/origin")),
+            source_overlay_paths: vec![PathBuf::from("$ORIGIN/src")],
+            source_overlay_paths_env: Some(env_key.to_string()),
+            ..Default::default()
+        };
+
+        let resolved = config.resolve()?;
+        std::env::remove_var(env_key);
+
+        assert_eq!(
+            resolved.source_overlay_paths,
+            vec![
+                PathBuf::from("/other/origin/src"),
+                PathBuf::from("/env/a"),
+                PathBuf::from("/env/b"),
+            ]
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,326 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Crash diagnostics for embedded interpreters.
+
+use {
+    crate::error::NewInterpreterError,
+    pyo3::{ffi as pyffi, prelude::*, types::PyDict, GILPool},
+    python_packaging::interpreter::CrashOutput,
+    std::{
+        os::raw::{c_char, c_int},
+        sync::{
+            atomic::{AtomicBool, AtomicI32, Ordering},
+            Once,
+        },
+    },
+};
+
+extern "C" {
+    // Used by `faulthandler`. Doesn't require the GIL and is safe to call
+    // from a signal handler.
+    fn _Py_DumpTracebackThreads(
+        fd: c_int,
+        interp: *mut pyffi::PyInterpreterState,
+        current_tstate: *mut pyffi::PyThreadState,
+    ) -> *const c_char;
+}
+
+/// File descriptor crash reports are written to. Negative if disabled.
+static CRASH_REPORT_FD: AtomicI32 = AtomicI32::new(-1);
+
+/// Whether a crash report is being written.
+///
+/// Prevents recursion if writing the report itself crashes.
+static WRITING_CRASH_REPORT: AtomicBool = AtomicBool::new(false);
+
+static INSTALL_HOOKS: Once = Once::new();
+
+/// A file descriptor crash output is written to.
+///
+/// Descriptors opened for [CrashOutput::Path] are owned and closed when the
+/// instance is dropped. Other descriptors are left open.
+pub(crate) struct CrashOutputFd {
+    fd: c_int,
+    owned: bool,
+}
+
+impl CrashOutputFd {
+    /// Obtain a file descriptor for a crash output destination.
+    ///
+    /// Files are opened in append mode.
+    pub(crate) fn open(output: &CrashOutput) -> Result<Self, NewInterpreterError> {
+        match output {
+            CrashOutput::Stderr => Ok(Self {
+                fd: 2,
+                owned: false,
+            }),
+            CrashOutput::FileDescriptor(fd) => Ok(Self {
+                fd: *fd,
+                owned: false,
+            }),
+            CrashOutput::Path(path) => {
+                let fh = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| {
+                        NewInterpreterError::Dynamic(format!(
+                            "error opening {} for crash diagnostics: {}",
+                            path.display(),
+                            e
+                        ))
+                    })?;
+
+                Ok(Self {
+                    fd: into_raw_fd(fh),
+                    owned: true,
+                })
+            }
+        }
+    }
+
+    pub(crate) fn fd(&self) -> c_int {
+        self.fd
+    }
+}
+
+impl Drop for CrashOutputFd {
+    fn drop(&mut self) {
+        if self.owned {
+            unsafe {
+                libc::close(self.fd);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn into_raw_fd(fh: std::fs::File) -> c_int {
+    use std::os::unix::io::IntoRawFd;
+
+    fh.into_raw_fd()
+}
+
+#[cfg(windows)]
+fn into_raw_fd(fh: std::fs::File) -> c_int {
+    use std::os::windows::io::IntoRawHandle;
+
+    unsafe { libc::open_osfhandle(fh.into_raw_handle() as libc::intptr_t, libc::O_APPEND) }
+}
+
+/// Enable `faulthandler` for all threads, writing to a destination.
+///
+/// The returned descriptor must outlive the interpreter.
+pub(crate) fn enable_faulthandler(
+    py: Python,
+    output: &CrashOutput,
+) -> Result<CrashOutputFd, NewInterpreterError> {
+    let fd = CrashOutputFd::open(output)?;
+
+    let faulthandler = py
+        .import("faulthandler")
+        .map_err(|e| NewInterpreterError::new_from_pyerr(py, e, "importing faulthandler"))?;
+
+    let kwargs = PyDict::new(py);
+    kwargs
+        .set_item("file", fd.fd())
+        .and_then(|_| kwargs.set_item("all_threads", true))
+        .and_then(|_| faulthandler.call_method("enable", (), Some(kwargs)))
+        .map_err(|e| NewInterpreterError::new_from_pyerr(py, e, "calling faulthandler.enable()"))?;
+
+    Ok(fd)
+}
+
+/// Install hooks writing crash reports to a file descriptor.
+///
+/// The panic hook and signal handlers are only installed once per process.
+/// Subsequent calls change the file descriptor reports are written to.
+/// [disable_crash_reports()] must be called before `fd` is dropped.
+///
+/// The panic hook runs for every panic, including panics that are later
+/// caught, such as those PyO3 converts into Python exceptions. There is no
+/// way to tell from the hook whether a panic will be caught.
+pub(crate) fn install_crash_report_hooks(fd: &CrashOutputFd) {
+    CRASH_REPORT_FD.store(fd.fd(), Ordering::SeqCst);
+
+    INSTALL_HOOKS.call_once(|| {
+        let previous = std::panic::take_hook();
+
+        std::panic::set_hook(Box::new(move |info| {
+            write_crash_report(&[format!("Rust panic: {}\n", info).as_bytes()], true);
+            previous(info);
+        }));
+
+        #[cfg(unix)]
+        install_signal_handlers();
+    });
+}
+
+/// Stop writing crash reports.
+///
+/// Must be called before the interpreter is finalized.
+pub(crate) fn disable_crash_reports() {
+    CRASH_REPORT_FD.store(-1, Ordering::SeqCst);
+}
+
+/// Write data to a file descriptor, ignoring errors.
+fn write_fd(fd: c_int, mut data: &[u8]) {
+    while !data.is_empty() {
+        let written = unsafe { libc::write(fd, data.as_ptr() as *const _, data.len() as _) };
+
+        if written <= 0 {
+            return;
+        }
+
+        data = &data[written as usize..];
+    }
+}
+
+/// Write a crash report if crash reports are enabled.
+///
+/// The report begins with the concatenation of `header` parts followed by the
+/// Python stack of every thread. These steps are async-signal-safe.
+///
+/// If `list_modules` is true, loaded modules are listed afterwards. This calls
+/// into the Python API and allocates, so it must not be done from a signal
+/// handler.
+fn write_crash_report(header: &[&[u8]], list_modules: bool) {
+    let fd = CRASH_REPORT_FD.load(Ordering::SeqCst);
+
+    if fd < 0 || unsafe { pyffi::Py_IsInitialized() } == 0 {
+        return;
+    }
+
+    if WRITING_CRASH_REPORT.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    for part in header {
+        write_fd(fd, part);
+    }
+    write_fd(fd, b"\nPython threads:\n");
+
+    let error = unsafe { _Py_DumpTracebackThreads(fd, std::ptr::null_mut(), std::ptr::null_mut()) };
+    if !error.is_null() {
+        write_fd(fd, unsafe { std::ffi::CStr::from_ptr(error) }.to_bytes());
+        write_fd(fd, b"\n");
+    }
+
+    if list_modules {
+        write_fd(fd, b"\nLoaded modules:\n");
+
+        // We can't wait for the GIL since the thread holding it may never
+        // release it.
+        if unsafe { pyffi::PyGILState_Check() } == 1 {
+            write_loaded_modules(fd);
+        } else {
+            write_fd(fd, b"  <unavailable: GIL not held by crashing thread>\n");
+        }
+    }
+
+    WRITING_CRASH_REPORT.store(false, Ordering::SeqCst);
+}
+
+/// Write modules in `sys.modules` and where they were loaded from.
+///
+/// The GIL must be held.
+fn write_loaded_modules(fd: c_int) {
+    let pool = unsafe { GILPool::new() };
+    let py = pool.python();
+
+    let res = (|| -> PyResult<()> {
+        let modules = py.import("sys")?.getattr("modules")?.cast_as::<PyDict>()?;
+
+        for (name, module) in modules.iter() {
+            let spec = module
+                .getattr("__spec__")
+                .ok()
+                .filter(|spec| !spec.is_none());
+
+            let loader = match spec.and_then(|spec| spec.getattr("loader").ok()) {
+                Some(loader) if !loader.is_none() => loader.get_type().name()?.to_string(),
+                _ => "<unknown>".to_string(),
+            };
+            let origin = match spec.and_then(|spec| spec.getattr("origin").ok()) {
+                Some(origin) if !origin.is_none() => origin.str()?.to_string_lossy().to_string(),
+                _ => "<unknown>".to_string(),
+            };
+
+            write_fd(
+                fd,
+                format!("  {} (loader: {}, origin: {})\n", name, loader, origin).as_bytes(),
+            );
+        }
+
+        Ok(())
+    })();
+
+    if let Err(e) = res {
+        write_fd(fd, format!("  <error listing modules: {}>\n", e).as_bytes());
+    }
+}
+
+#[cfg(unix)]
+const FATAL_SIGNALS: [(c_int, &str); 5] = [
+    (libc::SIGSEGV, "SIGSEGV"),
+    (libc::SIGBUS, "SIGBUS"),
+    (libc::SIGFPE, "SIGFPE"),
+    (libc::SIGILL, "SIGILL"),
+    (libc::SIGABRT, "SIGABRT"),
+];
+
+/// Signal handlers that were installed before ours.
+#[cfg(unix)]
+static PREVIOUS_SIGNAL_ACTIONS: once_cell::sync::OnceCell<[libc::sigaction; FATAL_SIGNALS.len()]> =
+    once_cell::sync::OnceCell::new();
+
+#[cfg(unix)]
+fn install_signal_handlers() {
+    unsafe {
+        let mut previous: [libc::sigaction; FATAL_SIGNALS.len()] = std::mem::zeroed();
+
+        for (i, (signal, _)) in FATAL_SIGNALS.iter().enumerate() {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = handle_fatal_signal as libc::sighandler_t;
+            // SA_NODEFER allows re-raising the signal to the previous handler
+            // from within our handler.
+            action.sa_flags = libc::SA_NODEFER | libc::SA_ONSTACK;
+            libc::sigemptyset(&mut action.sa_mask);
+
+            libc::sigaction(*signal, &action, &mut previous[i]);
+        }
+
+        let _ = PREVIOUS_SIGNAL_ACTIONS.set(previous);
+    }
+}
+
+#[cfg(unix)]
+extern "C" fn handle_fatal_signal(signal: c_int) {
+    let index = FATAL_SIGNALS
+        .iter()
+        .position(|(candidate, _)| *candidate == signal);
+
+    if let Some(index) = index {
+        // Listing modules isn't async-signal-safe.
+        write_crash_report(
+            &[b"Fatal signal ", FATAL_SIGNALS[index].1.as_bytes(), b"\n"],
+            false,
+        );
+
+        // Restore the previous handler and re-raise so it handles the signal
+        // as if we were never installed.
+        unsafe {
+            match PREVIOUS_SIGNAL_ACTIONS.get() {
+                Some(previous) => {
+                    libc::sigaction(signal, &previous[index], std::ptr::null_mut());
+                }
+                None => {
+                    libc::signal(signal, libc::SIG_DFL);
+                }
+            }
+            libc::raise(signal);
+        }
+    }
+}
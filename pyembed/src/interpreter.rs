@@ -8,9 +8,13 @@ use {
     crate::{
         config::{OxidizedPythonInterpreterConfig, ResolvedOxidizedPythonInterpreterConfig},
         conversion::osstring_to_bytes,
+        crash_report::{
+            disable_crash_reports, enable_faulthandler, install_crash_report_hooks, CrashOutputFd,
+        },
         error::NewInterpreterError,
         osutils::resolve_terminfo_dirs,
         pyalloc::PythonMemoryAllocator,
+        subinterpreter::{FrozenModulesTable, SubInterpreter},
     },
     once_cell::sync::Lazy,
     oxidized_importer::{
@@ -19,17 +23,25 @@ use {
         OXIDIZED_IMPORTER_NAME_STR,
     },
     pyo3::{
-        exceptions::PyRuntimeError, ffi as pyffi, prelude::*, types::PyDict, PyTypeInfo,
-        ToBorrowedObject,
+        exceptions::PyRuntimeError,
+        ffi as pyffi,
+        prelude::*,
+        types::{PyDict, PyList},
+        GILPool, PyTypeInfo, ToBorrowedObject,
+    },
+    python_packaging::{
+        interpreter::{MultiprocessingStartMethod, TerminfoResolution},
+        resource::BytecodeOptimizationLevel,
     },
-    python_packaging::interpreter::{MultiprocessingStartMethod, TerminfoResolution},
     std::{
         collections::BTreeSet,
         env, fs,
         io::Write,
         os::raw::c_char,
         path::{Path, PathBuf},
+        sync::Mutex,
     },
+    x509_certificate::CapturedX509Certificate,
 };
 
 static GLOBAL_INTERPRETER_GUARD: Lazy<std::sync::Mutex<()>> =
@@ -71,6 +83,9 @@ static GLOBAL_INTERPRETER_GUARD: Lazy<std::sync::Mutex<()>> =
 /// [pyo3::Python::with_gil()]. But since the function holds a reference to
 /// self, it prevents [MainPythonInterpreter] from being dropped prematurely.
 ///
+/// Isolated interpreters can be created via
+/// [MainPythonInterpreter::new_sub_interpreter()].
+///
 /// # Safety
 ///
 /// Dropping a [MainPythonInterpreter] instance will call `Py_FinalizeEx()` to
@@ -92,6 +107,15 @@ pub struct MainPythonInterpreter<'interpreter, 'resources: 'interpreter> {
     pub(crate) allocator: Option<PythonMemoryAllocator>,
     /// File to write containing list of modules when the interpreter finalizes.
     write_modules_path: Option<PathBuf>,
+    /// Where `faulthandler` writes to. Closed after the interpreter finalizes.
+    faulthandler_fd: Option<CrashOutputFd>,
+    /// Where crash reports are written to. Closed after the interpreter finalizes.
+    crash_report_fd: Option<CrashOutputFd>,
+    /// Frozen modules registered while creating sub-interpreters.
+    ///
+    /// The mutex also serializes sub-interpreter creation, since the table is
+    /// process global.
+    sub_interpreter_frozen_modules: Mutex<Option<FrozenModulesTable<'resources>>>,
 }
 
 impl<'interpreter, 'resources> MainPythonInterpreter<'interpreter, 'resources> {
@@ -120,6 +144,9 @@ impl<'interpreter, 'resources> MainPythonInterpreter<'interpreter, 'resources> {
             interpreter_guard: None,
             allocator: None,
             write_modules_path: None,
+            faulthandler_fd: None,
+            crash_report_fd: None,
+            sub_interpreter_frozen_modules: Mutex::new(None),
         };
 
         res.init()?;
@@ -220,8 +247,19 @@ impl<'interpreter, 'resources> MainPythonInterpreter<'interpreter, 'resources> {
         // importlib._bootstrap_external. This is where we work our magic to
         // inject our custom importer.
 
-        let oxidized_finder_loaded =
-            unsafe { Python::with_gil_unchecked(|py| self.inject_oxidized_importer(py))? };
+        let (faulthandler_fd, oxidized_finder_loaded) = unsafe {
+            Python::with_gil_unchecked(|py| {
+                // Enable faulthandler as early as possible so crashes during the
+                // remainder of initialization are reported.
+                let faulthandler_fd = match &self.config.faulthandler_output {
+                    Some(output) => Some(enable_faulthandler(py, output)?),
+                    None => None,
+                };
+
+                Ok::<_, NewInterpreterError>((faulthandler_fd, self.inject_oxidized_importer(py)?))
+            })?
+        };
+        self.faulthandler_fd = faulthandler_fd;
 
         // The GIL is still held after calling into PyO3.
         debug_assert_eq!(unsafe { pyffi::PyGILState_Check() }, 1);
@@ -249,6 +287,12 @@ impl<'interpreter, 'resources> MainPythonInterpreter<'interpreter, 'resources> {
         self.write_modules_path =
             self.with_gil(|py| self.init_post_main(py, oxidized_finder_loaded))?;
 
+        if let Some(output) = &self.config.crash_report_output {
+            let fd = CrashOutputFd::open(output)?;
+            install_crash_report_hooks(&fd);
+            self.crash_report_fd = Some(fd);
+        }
+
         debug_assert_eq!(unsafe { pyffi::PyGILState_Check() }, 0);
 
         Ok(())
@@ -267,13 +311,50 @@ impl<'interpreter, 'resources> MainPythonInterpreter<'interpreter, 'resources> {
         }
 
         let resources_state = Box::new(PythonResourcesState::try_from(&self.config)?);
+        let trust_anchors = self.config.resolve_packed_resources_trust_anchors()?;
 
         let oxidized_importer = py.import(OXIDIZED_IMPORTER_NAME_STR).map_err(|err| {
             NewInterpreterError::new_from_pyerr(py, err, "import of oxidized importer module")
         })?;
 
-        let cb = |importer_state: &mut ImporterState| match self.config.multiprocessing_start_method
-        {
+        let cb = |importer_state: &mut ImporterState| {
+            self.configure_importer_state(
+                importer_state,
+                self.import_trace_directory().is_some(),
+                trust_anchors,
+            );
+        };
+
+        // Ownership of the resources state is transferred into the importer, where the Box
+        // is summarily leaked. However, the importer tracks a pointer to the resources state
+        // and will constitute the struct for dropping when it itself is dropped. We could
+        // potentially encounter a use-after-free if the importer is used after self.config
+        // is dropped. However, that would require self to be dropped. And if self is dropped,
+        // there should no longer be a Python interpreter around. So it follows that the
+        // importer state cannot be dropped after self.
+
+        replace_meta_path_importers(py, oxidized_importer, resources_state, Some(cb)).map_err(
+            |err| {
+                NewInterpreterError::new_from_pyerr(py, err, "initialization of oxidized importer")
+            },
+        )?;
+
+        Ok(true)
+    }
+
+    /// Apply config options to the state of a new `OxidizedFinder`.
+    fn configure_importer_state(
+        &self,
+        importer_state: &mut ImporterState,
+        import_tracing: bool,
+        trust_anchors: Vec<CapturedX509Certificate>,
+    ) {
+        importer_state.set_source_overlay_paths(self.config.source_overlay_paths.clone());
+        importer_state.set_packed_resources_trust_anchors(trust_anchors);
+        importer_state.set_import_tracing(import_tracing);
+        importer_state.preload_resources();
+
+        match self.config.multiprocessing_start_method {
             MultiprocessingStartMethod::None => {}
             MultiprocessingStartMethod::Fork
             | MultiprocessingStartMethod::ForkServer
@@ -296,23 +377,7 @@ impl<'interpreter, 'resources> MainPythonInterpreter<'interpreter, 'resources> {
 
                 importer_state.set_multiprocessing_set_start_method(Some(method.to_string()));
             }
-        };
-
-        // Ownership of the resources state is transferred into the importer, where the Box
-        // is summarily leaked. However, the importer tracks a pointer to the resources state
-        // and will constitute the struct for dropping when it itself is dropped. We could
-        // potentially encounter a use-after-free if the importer is used after self.config
-        // is dropped. However, that would require self to be dropped. And if self is dropped,
-        // there should no longer be a Python interpreter around. So it follows that the
-        // importer state cannot be dropped after self.
-
-        replace_meta_path_importers(py, oxidized_importer, resources_state, Some(cb)).map_err(
-            |err| {
-                NewInterpreterError::new_from_pyerr(py, err, "initialization of oxidized importer")
-            },
-        )?;
-
-        Ok(true)
+        }
     }
 
     /// Performs interpreter configuration after main interpreter initialization.
@@ -378,8 +443,74 @@ impl<'interpreter, 'resources> MainPythonInterpreter<'interpreter, 'resources> {
                     "installing OxidizedFinder in sys.path_hooks",
                 )
             })?;
+
+            if let Some(path) = self.import_trace_directory() {
+                std::fs::create_dir_all(&path).map_err(|e| {
+                    NewInterpreterError::Dynamic(format!(
+                        "error creating directory for import trace files: {}",
+                        e
+                    ))
+                })?;
+
+                let path = path.join(format!("imports-{}.json", uuid4_str(py)?));
+
+                // The trace is written by an atexit handler so it is written no matter
+                // how the interpreter is finalized, including via Py_RunMain().
+                let atexit = py.import("atexit").map_err(|e| {
+                    NewInterpreterError::new_from_pyerr(py, e, "importing atexit module")
+                })?;
+                let write_import_trace = finder.getattr("write_import_trace").map_err(|e| {
+                    NewInterpreterError::new_from_pyerr(
+                        py,
+                        e,
+                        "obtaining OxidizedFinder.write_import_trace",
+                    )
+                })?;
+                atexit
+                    .call_method1(
+                        "register",
+                        (
+                            write_import_trace,
+                            path,
+                            self.config.import_trace_format.to_string(),
+                        ),
+                    )
+                    .map_err(|e| {
+                        NewInterpreterError::new_from_pyerr(
+                            py,
+                            e,
+                            "registering import trace atexit handler",
+                        )
+                    })?;
+            }
         }
 
+        self.set_sys_attributes(py)?;
+
+        let write_modules_path = if let Some(key) = &self.config.write_modules_directory_env {
+            if let Ok(path) = std::env::var(key) {
+                let path = PathBuf::from(path);
+
+                std::fs::create_dir_all(&path).map_err(|e| {
+                    NewInterpreterError::Dynamic(format!(
+                        "error creating directory for loaded modules files: {}",
+                        e
+                    ))
+                })?;
+
+                Some(path.join(format!("modules-{}", uuid4_str(py)?)))
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        Ok(write_modules_path)
+    }
+
+    /// Set attributes on the `sys` module of the active interpreter.
+    fn set_sys_attributes(&self, py: Python) -> Result<(), NewInterpreterError> {
         if self.config.argvb {
             let args_objs = self
                 .config
@@ -437,44 +568,16 @@ impl<'interpreter, 'resources> MainPythonInterpreter<'interpreter, 'resources> {
             }
         }
 
-        let write_modules_path = if let Some(key) = &self.config.write_modules_directory_env {
-            if let Ok(path) = std::env::var(key) {
-                let path = PathBuf::from(path);
-
-                std::fs::create_dir_all(&path).map_err(|e| {
-                    NewInterpreterError::Dynamic(format!(
-                        "error creating directory for loaded modules files: {}",
-                        e
-                    ))
-                })?;
-
-                // We use Python's uuid module to generate a filename. This avoids
-                // a dependency on a Rust crate, which cuts down on dependency bloat.
-                let uuid_mod = py.import("uuid").map_err(|e| {
-                    NewInterpreterError::new_from_pyerr(py, e, "importing uuid module")
-                })?;
-                let uuid4 = uuid_mod.getattr("uuid4").map_err(|e| {
-                    NewInterpreterError::new_from_pyerr(py, e, "obtaining uuid.uuid4")
-                })?;
-                let uuid = uuid4.call0().map_err(|e| {
-                    NewInterpreterError::new_from_pyerr(py, e, "calling uuid.uuid4()")
-                })?;
-                let uuid_str = uuid
-                    .str()
-                    .map_err(|e| {
-                        NewInterpreterError::new_from_pyerr(py, e, "converting uuid to str")
-                    })?
-                    .to_string();
-
-                Some(path.join(format!("modules-{}", uuid_str)))
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+        Ok(())
+    }
 
-        Ok(write_modules_path)
+    /// Resolve the directory to write an import trace file to, if import tracing is enabled.
+    fn import_trace_directory(&self) -> Option<PathBuf> {
+        self.config
+            .write_import_trace_directory_env
+            .as_ref()
+            .and_then(std::env::var_os)
+            .map(PathBuf::from)
     }
 
     /// Proxy for [Python::with_gil()].
@@ -489,6 +592,152 @@ impl<'interpreter, 'resources> MainPythonInterpreter<'interpreter, 'resources> {
         Python::with_gil(f)
     }
 
+    /// Create a Python sub-interpreter.
+    ///
+    /// This calls `Py_NewInterpreter()` to create an interpreter isolated from
+    /// the main interpreter. If configured, the sub-interpreter receives its own
+    /// `OxidizedFinder` indexing the same packed resources as the main interpreter.
+    /// Filesystem importing and attributes like `sys.oxidized` and `sys.frozen`
+    /// are configured like they are for the main interpreter. Import tracing
+    /// is only performed in the main interpreter.
+    ///
+    /// Sub-interpreters are destroyed when the returned value is dropped.
+    ///
+    /// Creation of sub-interpreters is serialized. This function must not be
+    /// called with the GIL held, as it may deadlock waiting on another thread
+    /// creating a sub-interpreter.
+    pub fn new_sub_interpreter(
+        &self,
+    ) -> Result<SubInterpreter<'_, 'interpreter, 'resources>, NewInterpreterError> {
+        let mut frozen_modules = self.sub_interpreter_frozen_modules.lock().map_err(|_| {
+            NewInterpreterError::Simple("unable to acquire sub-interpreter creation lock")
+        })?;
+
+        self.with_gil(|py| {
+            let resources_state = if self.config.oxidized_importer {
+                if frozen_modules.is_none() {
+                    let optimize_level = py
+                        .import("sys")
+                        .and_then(|sys| sys.getattr("flags")?.getattr("optimize")?.extract::<i32>())
+                        .map_err(|e| {
+                            NewInterpreterError::new_from_pyerr(
+                                py,
+                                e,
+                                "obtaining sys.flags.optimize",
+                            )
+                        })?;
+                    let optimize_level = BytecodeOptimizationLevel::try_from(optimize_level)?;
+
+                    *frozen_modules = Some(FrozenModulesTable::new(&self.config, optimize_level)?);
+                }
+
+                Some(Box::new(PythonResourcesState::try_from(&self.config)?))
+            } else {
+                None
+            };
+
+            unsafe {
+                let main_thread_state = pyffi::PyThreadState_Swap(std::ptr::null_mut());
+
+                let thread_state = match frozen_modules.as_ref() {
+                    Some(table) => table.with_installed(|| pyffi::Py_NewInterpreter()),
+                    None => pyffi::Py_NewInterpreter(),
+                };
+
+                let res = if thread_state.is_null() {
+                    Err(NewInterpreterError::Simple("Py_NewInterpreter() failed"))
+                } else {
+                    // The new interpreter is active. Objects created by PyO3 need
+                    // to be released before switching away from it.
+                    let pool = GILPool::new();
+                    let res = self.init_sub_interpreter(pool.python(), resources_state);
+                    drop(pool);
+
+                    if res.is_err() {
+                        pyffi::Py_EndInterpreter(thread_state);
+                    }
+
+                    res
+                };
+
+                pyffi::PyThreadState_Swap(main_thread_state);
+
+                res.map(|_| SubInterpreter::new(self, thread_state))
+            }
+        })
+    }
+
+    /// Performs configuration of a newly created sub-interpreter.
+    ///
+    /// The sub-interpreter must be active.
+    fn init_sub_interpreter(
+        &self,
+        py: Python,
+        resources_state: Option<Box<PythonResourcesState<'resources, u8>>>,
+    ) -> Result<(), NewInterpreterError> {
+        let sys_module = py
+            .import("sys")
+            .map_err(|e| NewInterpreterError::new_from_pyerr(py, e, "obtaining sys module"))?;
+
+        if let Some(resources_state) = resources_state {
+            let oxidized_importer = py.import(OXIDIZED_IMPORTER_NAME_STR).map_err(|err| {
+                NewInterpreterError::new_from_pyerr(py, err, "import of oxidized importer module")
+            })?;
+
+            let meta_path = sys_module
+                .getattr("meta_path")
+                .and_then(|v| Ok(v.cast_as::<PyList>()?))
+                .map_err(|err| {
+                    NewInterpreterError::new_from_pyerr(py, err, "obtaining sys.meta_path")
+                })?;
+
+            // Unlike the main interpreter, the sub-interpreter is fully initialized
+            // and the "external" importers following BuiltinImporter and
+            // FrozenImporter are already installed. Replacing the meta path
+            // importers removes them. So restore them if filesystem importing is
+            // enabled.
+            let external_importers = meta_path.iter().skip(2).collect::<Vec<_>>();
+
+            let trust_anchors = self.config.resolve_packed_resources_trust_anchors()?;
+
+            let cb = |importer_state: &mut ImporterState| {
+                self.configure_importer_state(importer_state, false, trust_anchors);
+            };
+
+            let finder =
+                replace_meta_path_importers(py, oxidized_importer, resources_state, Some(cb))
+                    .map_err(|err| {
+                        NewInterpreterError::new_from_pyerr(
+                            py,
+                            err,
+                            "initialization of oxidized importer",
+                        )
+                    })?;
+
+            if self.config.filesystem_importer {
+                for importer in external_importers {
+                    meta_path.append(importer).map_err(|err| {
+                        NewInterpreterError::new_from_pyerr(py, err, "restoring sys.meta_path")
+                    })?;
+                }
+            } else {
+                remove_external_importers(sys_module).map_err(|err| {
+                    NewInterpreterError::new_from_pyerr(py, err, "removing external importers")
+                })?;
+            }
+
+            install_path_hook(finder.as_ref(), sys_module).map_err(|err| {
+                NewInterpreterError::new_from_pyerr(
+                    py,
+                    err,
+                    "installing OxidizedFinder in sys.path_hooks",
+                )
+            })?;
+        }
+
+        self.set_sys_attributes(py)
+    }
+
     /// Runs `Py_RunMain()` and finalizes the interpreter.
     ///
     /// This will execute whatever is configured by the Python interpreter config
@@ -675,6 +924,26 @@ fn set_pyimport_inittab(config: &OxidizedPythonInterpreterConfig) {
     }
 }
 
+/// Generate a random UUID string suitable for use in filenames.
+fn uuid4_str(py: Python) -> Result<String, NewInterpreterError> {
+    // We use Python's uuid module to generate a filename. This avoids
+    // a dependency on a Rust crate, which cuts down on dependency bloat.
+    let uuid_mod = py
+        .import("uuid")
+        .map_err(|e| NewInterpreterError::new_from_pyerr(py, e, "importing uuid module"))?;
+    let uuid4 = uuid_mod
+        .getattr("uuid4")
+        .map_err(|e| NewInterpreterError::new_from_pyerr(py, e, "obtaining uuid.uuid4"))?;
+    let uuid = uuid4
+        .call0()
+        .map_err(|e| NewInterpreterError::new_from_pyerr(py, e, "calling uuid.uuid4()"))?;
+
+    Ok(uuid
+        .str()
+        .map_err(|e| NewInterpreterError::new_from_pyerr(py, e, "converting uuid to str"))?
+        .to_string())
+}
+
 /// Write loaded Python modules to a directory.
 ///
 /// Given a Python interpreter and a path to a directory, this will create a
@@ -714,6 +983,12 @@ fn write_modules_to_path(py: Python, path: &Path) -> Result<(), &'static str> {
 
 impl<'interpreter, 'resources> Drop for MainPythonInterpreter<'interpreter, 'resources> {
     fn drop(&mut self) {
+        // Our descriptor is closed when we're dropped, even if the interpreter
+        // was finalized out from under us.
+        if self.crash_report_fd.is_some() {
+            disable_crash_reports();
+        }
+
         // Interpreter may have been finalized already. Possibly through our invocation
         // of Py_RunMain(). Possibly something out-of-band beyond our control. We don't
         // muck with the interpreter after finalization because this will likely result
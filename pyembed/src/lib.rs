@@ -23,6 +23,8 @@ The most important types in this crate are
 [OxidizedPythonInterpreterConfig] defines how a Python interpreter is to
 behave. A [MainPythonInterpreter] creates and manages that interpreter and
 serves as a high-level interface for running code in the interpreter.
+Additional isolated interpreters can be created as [SubInterpreter]
+instances.
 
 # Dependencies
 
@@ -56,11 +58,13 @@ The optional `serialization` feature controls whether configuration types
 #[allow(unused)]
 mod config;
 mod conversion;
+mod crash_report;
 mod error;
 mod interpreter;
 mod interpreter_config;
 mod osutils;
 mod pyalloc;
+mod subinterpreter;
 pub mod technotes;
 #[cfg(test)]
 mod test;
@@ -75,13 +79,14 @@ pub use {
         error::NewInterpreterError,
         interpreter::MainPythonInterpreter,
         pyalloc::PythonMemoryAllocator,
+        subinterpreter::SubInterpreter,
     },
     oxidized_importer::{PackedResourcesSource, PythonResourcesState},
     python_packaging::{
         interpreter::{
-            Allocator, BytesWarning, CheckHashPycsMode, CoerceCLocale, MemoryAllocatorBackend,
-            MultiprocessingStartMethod, PythonInterpreterConfig, PythonInterpreterProfile,
-            TerminfoResolution,
+            Allocator, BytesWarning, CheckHashPycsMode, CoerceCLocale, CrashOutput,
+            ImportTraceFormat, MemoryAllocatorBackend, MultiprocessingStartMethod,
+            PythonInterpreterConfig, PythonInterpreterProfile, TerminfoResolution,
         },
         resource::BytecodeOptimizationLevel,
     },
@@ -0,0 +1,233 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Manage Python sub-interpreters.
+
+use {
+    crate::{
+        config::ResolvedOxidizedPythonInterpreterConfig, error::NewInterpreterError,
+        interpreter::MainPythonInterpreter,
+    },
+    oxidized_importer::PythonResourcesState,
+    pyo3::{ffi as pyffi, prelude::*, GILPool},
+    python_packaging::resource::BytecodeOptimizationLevel,
+    std::{borrow::Cow, ffi::CString, os::raw::c_int},
+};
+
+/// A `PyImport_FrozenModules` table exposing modules with in-memory bytecode.
+///
+/// `Py_NewInterpreter()` fully initializes the new interpreter, importing
+/// modules like `encodings` and `site` with the default `BuiltinImporter`,
+/// `FrozenImporter`, and `PathFinder` meta path importers. We don't have an
+/// opportunity to install `OxidizedFinder` before this happens. So if the
+/// standard library is only available from packed resources, these imports
+/// would fail.
+///
+/// To work around this, modules having in-memory bytecode are registered as
+/// frozen modules for the duration of `Py_NewInterpreter()`.
+pub(crate) struct FrozenModulesTable<'resources> {
+    /// Holds memory referenced by `entries`.
+    _resources: PythonResourcesState<'resources, u8>,
+    /// Holds module names referenced by `entries`.
+    _names: Vec<CString>,
+    /// Holds decompressed bytecode referenced by `entries`.
+    _decompressed: Vec<Vec<u8>>,
+    entries: Vec<pyffi::_frozen>,
+    /// The value of `PyImport_FrozenModules` when we were constructed.
+    original: *const pyffi::_frozen,
+}
+
+// Raw pointers in the table refer to memory owned by the instance or to
+// CPython's static frozen modules table. All access to the table happens with
+// the GIL held.
+unsafe impl<'resources> Send for FrozenModulesTable<'resources> {}
+
+impl<'resources> FrozenModulesTable<'resources> {
+    /// Construct an instance from an interpreter config.
+    ///
+    /// The GIL must be held and `PyImport_FrozenModules` must have its
+    /// original value.
+    pub(crate) fn new(
+        config: &ResolvedOxidizedPythonInterpreterConfig<'resources>,
+        optimize_level: BytecodeOptimizationLevel,
+    ) -> Result<Self, NewInterpreterError> {
+        let resources = PythonResourcesState::try_from(config)?;
+
+        let original = unsafe { pyffi::PyImport_FrozenModules };
+
+        // Existing entries come first so they take precedence.
+        let mut entries = vec![];
+        for i in 0.. {
+            let record = unsafe { *original.offset(i) };
+
+            if record.name.is_null() {
+                break;
+            }
+
+            entries.push(record);
+        }
+
+        let mut names = vec![];
+        let mut decompressed = vec![];
+
+        for module in resources.in_memory_bytecode_modules(optimize_level) {
+            let (name, code, is_package) = module.map_err(NewInterpreterError::Dynamic)?;
+
+            let name = CString::new(name)
+                .map_err(|_| NewInterpreterError::Simple("module name contains NULL byte"))?;
+            let size = c_int::try_from(code.len()).map_err(|_| {
+                NewInterpreterError::Dynamic(format!(
+                    "bytecode for {} is too large to register as a frozen module",
+                    name.to_string_lossy()
+                ))
+            })?;
+
+            // Moving the buffer into `decompressed` doesn't move its heap data, so
+            // the pointer remains valid for as long as we hold the buffer.
+            let code = match code {
+                Cow::Borrowed(data) => data.as_ptr(),
+                Cow::Owned(data) => {
+                    let ptr = data.as_ptr();
+                    decompressed.push(data);
+                    ptr
+                }
+            };
+
+            // A negative size denotes a package.
+            entries.push(pyffi::_frozen {
+                name: name.as_ptr(),
+                code,
+                size: if is_package { -size } else { size },
+            });
+            names.push(name);
+        }
+
+        entries.push(pyffi::_frozen {
+            name: std::ptr::null(),
+            code: std::ptr::null(),
+            size: 0,
+        });
+
+        Ok(Self {
+            _resources: resources,
+            _names: names,
+            _decompressed: decompressed,
+            entries,
+            original,
+        })
+    }
+
+    /// Run a function with this table registered as `PyImport_FrozenModules`.
+    ///
+    /// The GIL must be held.
+    pub(crate) unsafe fn with_installed<T>(&self, f: impl FnOnce() -> T) -> T {
+        pyffi::PyImport_FrozenModules = self.entries.as_ptr();
+        let res = f();
+        pyffi::PyImport_FrozenModules = self.original;
+
+        res
+    }
+}
+
+/// Restores the previously active thread state when dropped.
+struct ThreadStateSwap {
+    previous: *mut pyffi::PyThreadState,
+}
+
+impl ThreadStateSwap {
+    /// Make a thread state active. The GIL must be held.
+    unsafe fn new(thread_state: *mut pyffi::PyThreadState) -> Self {
+        Self {
+            previous: pyffi::PyThreadState_Swap(thread_state),
+        }
+    }
+}
+
+impl Drop for ThreadStateSwap {
+    fn drop(&mut self) {
+        unsafe {
+            pyffi::PyThreadState_Swap(self.previous);
+        }
+    }
+}
+
+/// A Python sub-interpreter.
+///
+/// Sub-interpreters are created via [MainPythonInterpreter::new_sub_interpreter()].
+/// Each has its own `sys.modules`, `sys.meta_path`, and other module state,
+/// isolating it from the main interpreter and other sub-interpreters.
+///
+/// If the main interpreter is configured to use `OxidizedFinder`, each
+/// sub-interpreter receives its own `OxidizedFinder` instance indexing the
+/// same packed resources. The resources data is not copied: memory mapped
+/// files are mapped again and are backed by the same pages.
+///
+/// The GIL is shared with the main interpreter and all other
+/// sub-interpreters. Creating sub-interpreters with their own GIL (PEP 684)
+/// is out of scope: it requires Python 3.12, which isn't supported. Code
+/// running in different sub-interpreters therefore doesn't execute in
+/// parallel.
+///
+/// Instances are bound to the thread that created them and can't be sent to
+/// other threads.
+///
+/// # Safety
+///
+/// Dropping an instance calls `Py_EndInterpreter()`, which will abort the
+/// process if threads started from the sub-interpreter are still running.
+///
+/// Some extension modules don't support being loaded into multiple
+/// interpreters and may misbehave when imported into a sub-interpreter.
+pub struct SubInterpreter<'main, 'interpreter, 'resources> {
+    main: &'main MainPythonInterpreter<'interpreter, 'resources>,
+    thread_state: *mut pyffi::PyThreadState,
+}
+
+impl<'main, 'interpreter, 'resources> SubInterpreter<'main, 'interpreter, 'resources> {
+    pub(crate) fn new(
+        main: &'main MainPythonInterpreter<'interpreter, 'resources>,
+        thread_state: *mut pyffi::PyThreadState,
+    ) -> Self {
+        Self { main, thread_state }
+    }
+
+    /// Run a function with the GIL held and this sub-interpreter active.
+    ///
+    /// This allows running Python code in the sub-interpreter via the PyO3
+    /// Rust APIs. Python objects must not be shared between interpreters.
+    ///
+    /// Calls to [MainPythonInterpreter::with_gil()] from within the function
+    /// will also run in this sub-interpreter.
+    pub fn with_gil<F, R>(&self, f: F) -> R
+    where
+        F: for<'py> FnOnce(Python<'py>) -> R,
+    {
+        self.main.with_gil(|_| unsafe {
+            let _swap = ThreadStateSwap::new(self.thread_state);
+
+            // Objects created by PyO3 are tracked in a per-thread pool. A
+            // dedicated pool ensures objects created here are released while
+            // this interpreter is active.
+            let pool = GILPool::new();
+
+            f(pool.python())
+        })
+    }
+}
+
+impl<'main, 'interpreter, 'resources> Drop for SubInterpreter<'main, 'interpreter, 'resources> {
+    fn drop(&mut self) {
+        if unsafe { pyffi::Py_IsInitialized() } == 0 {
+            return;
+        }
+
+        self.main.with_gil(|_| unsafe {
+            let _swap = ThreadStateSwap::new(self.thread_state);
+
+            // This leaves no thread state active. The swap restores the
+            // previous one.
+            pyffi::Py_EndInterpreter(self.thread_state);
+        });
+    }
+}
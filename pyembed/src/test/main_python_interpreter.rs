@@ -4,9 +4,14 @@
 
 use {
     super::{default_interpreter_config, run_py_test},
-    crate::MainPythonInterpreter,
-    pyo3::ffi as pyffi,
+    crate::{subinterpreter::FrozenModulesTable, CrashOutput, MainPythonInterpreter},
+    oxidized_importer::{PackedResourcesSource, PythonResourcesState},
+    pyo3::{ffi as pyffi, types::PyDict},
+    python_packaging::resource::BytecodeOptimizationLevel,
+    python_packed_resources::{CompressionCodec, Resource},
     rusty_fork::rusty_fork_test,
+    std::panic::AssertUnwindSafe,
+    x509_certificate::{KeyAlgorithm, X509CertificateBuilder},
 };
 
 rusty_fork_test! {
@@ -35,6 +40,209 @@ rusty_fork_test! {
         std::mem::drop(interp);
     }
 
+    #[test]
+    fn sub_interpreter_isolation() {
+        let mut config = default_interpreter_config();
+        config.oxidized_importer = true;
+        let interp = MainPythonInterpreter::new(config).unwrap();
+
+        interp.with_gil(|py| {
+            py.import("sys").unwrap().setattr("main_marker", true).unwrap();
+        });
+
+        let sub = interp.new_sub_interpreter().unwrap();
+
+        sub.with_gil(|py| {
+            let sys = py.import("sys").unwrap();
+            assert!(!sys.hasattr("main_marker").unwrap());
+            assert!(sys.getattr("oxidized").unwrap().extract::<bool>().unwrap());
+
+            let finder = sys.getattr("meta_path").unwrap().get_item(0).unwrap();
+            assert_eq!(finder.get_type().name().unwrap(), "OxidizedFinder");
+
+            // Modules are loaded by the sub-interpreter's own importer.
+            py.import("json").unwrap();
+        });
+
+        std::mem::drop(sub);
+
+        interp.with_gil(|py| {
+            assert!(py.import("sys").unwrap().hasattr("main_marker").unwrap());
+        });
+
+        assert_eq!(unsafe { pyffi::PyGILState_Check() }, 0);
+    }
+
+    #[test]
+    fn sub_interpreter_frozen_modules_compressed() {
+        let interp = MainPythonInterpreter::new(default_interpreter_config()).unwrap();
+
+        let bytecode = interp.with_gil(|py| {
+            let code = py
+                .eval("compile('VALUE = 42', 'compressed', 'exec')", None, None)
+                .unwrap();
+
+            py.import("marshal")
+                .unwrap()
+                .call_method1("dumps", (code,))
+                .unwrap()
+                .extract::<Vec<u8>>()
+                .unwrap()
+        });
+
+        let mut state = PythonResourcesState::default();
+        state
+            .add_resource(
+                Resource {
+                    name: "compressed".into(),
+                    is_python_module: true,
+                    in_memory_bytecode: Some(bytecode.into()),
+                    ..Default::default()
+                }
+                .compress(CompressionCodec::Lz4)
+                .unwrap(),
+            )
+            .unwrap();
+        let data = state.serialize_resources(true, true).unwrap();
+
+        let mut config = default_interpreter_config();
+        config
+            .packed_resources
+            .push(PackedResourcesSource::Memory(&data));
+        let config = config.resolve().unwrap();
+
+        // The frozen modules table must expose decompressed bytecode.
+        let table = FrozenModulesTable::new(&config, BytecodeOptimizationLevel::Zero).unwrap();
+
+        interp.with_gil(|py| {
+            let code = unsafe {
+                table.with_installed(|| {
+                    py.import("_imp")
+                        .unwrap()
+                        .call_method1("get_frozen_object", ("compressed",))
+                        .unwrap()
+                })
+            };
+
+            let globals = PyDict::new(py);
+            py.import("builtins")
+                .unwrap()
+                .call_method1("exec", (code, globals))
+                .unwrap();
+            assert_eq!(
+                globals.get_item("VALUE").unwrap().extract::<i32>().unwrap(),
+                42
+            );
+        });
+    }
+
+    #[test]
+    fn faulthandler_output() {
+        let mut config = default_interpreter_config();
+        config.faulthandler_output = Some(CrashOutput::FileDescriptor(2));
+        let interp = MainPythonInterpreter::new(config).unwrap();
+
+        interp.with_gil(|py| {
+            let faulthandler = py.import("faulthandler").unwrap();
+            assert!(faulthandler
+                .call_method0("is_enabled")
+                .unwrap()
+                .extract::<bool>()
+                .unwrap());
+        });
+    }
+
+    #[test]
+    fn crash_report_panic() {
+        let path = std::env::temp_dir().join(format!(
+            "pyembed-crash-report-{}.log",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        let mut config = default_interpreter_config();
+        config.crash_report_output = Some(CrashOutput::Path(path.clone()));
+        let interp = MainPythonInterpreter::new(config).unwrap();
+
+        let res = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            interp.with_gil(|_| panic!("crash report test"));
+        }));
+        assert!(res.is_err());
+
+        let report = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(report.starts_with("Rust panic: "));
+        assert!(report.contains("crash report test"));
+        assert!(report.contains("\nPython threads:\n"));
+        assert!(report.contains("\nLoaded modules:\n"));
+        assert!(report.contains("\n  sys (loader: "));
+    }
+
+    #[test]
+    fn path_hook_trust_anchors() {
+        let (cert, _, _) = X509CertificateBuilder::new(KeyAlgorithm::Ed25519)
+            .create_with_random_keypair()
+            .unwrap();
+
+        let mut config = default_interpreter_config();
+        config.oxidized_importer = true;
+        config.packed_resources_trust_anchors = vec![cert.encode_pem()];
+        let interp = MainPythonInterpreter::new(config).unwrap();
+
+        interp.with_gil(|py| {
+            py.run(
+                r#"
+import os, sys, tempfile, zipfile
+
+finder = sys.meta_path[0]
+
+def assert_rejected(path, message):
+    try:
+        finder.path_hook(path)
+    except ImportError as e:
+        assert message in str(e.__cause__), e.__cause__
+    else:
+        raise AssertionError("%s accepted" % path)
+
+with tempfile.TemporaryDirectory() as td:
+    path = os.path.join(td, "plugin-1.0-py3-none-any.whl")
+    with zipfile.ZipFile(path, "w") as zf:
+        zf.writestr("plugin.py", "")
+
+    assert not finder.path_hook_zip_archives
+    assert_rejected(path, "zip archive handling is disabled")
+
+    finder.path_hook_zip_archives = True
+    assert_rejected(path, "only signed packed resources files are allowed")
+
+    # The archive must not be importable via zipimport either.
+    assert not any(getattr(h, "__module__", None) == "zipimport" for h in sys.path_hooks)
+
+    sys.path.append(path)
+    try:
+        import plugin
+    except ImportError:
+        pass
+    else:
+        raise AssertionError("unsigned zip archive imported")
+    finally:
+        sys.path.remove(path)
+
+    # No .sig file.
+    path = os.path.join(td, "plugin.pyembed")
+    with open(path, "wb") as fh:
+        fh.write(finder.serialize_indexed_resources())
+
+    assert_rejected(path, "error indexing")
+"#,
+                None,
+                None,
+            )
+            .unwrap();
+        });
+    }
+
     #[test]
     fn multiprocessing_py() {
         run_py_test("test_multiprocessing.py").unwrap()
@@ -6,7 +6,8 @@ use {
     crate::OxidizedPythonInterpreterConfig,
     anyhow::{anyhow, Result},
     oxidized_importer::{PackedResourcesSource, PyTempDir, PythonResourcesState},
-    python_packed_resources::Resource,
+    python_packaging::resource::BytecodeOptimizationLevel,
+    python_packed_resources::{CompressionCodec, Resource},
     rusty_fork::rusty_fork_test,
 };
 
@@ -46,6 +47,50 @@ fn multiple_resource_blobs() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn in_memory_bytecode_modules_compressed() -> Result<()> {
+    let bytecode = b"bytecode".repeat(16);
+
+    let compressed = Resource {
+        name: "compressed".into(),
+        is_python_module: true,
+        in_memory_bytecode: Some(bytecode.clone().into()),
+        ..Default::default()
+    }
+    .compress(CompressionCodec::Lz4)
+    .map_err(|e| anyhow!("{}", e))?;
+    assert_ne!(
+        compressed.in_memory_bytecode.as_ref().unwrap().as_ref(),
+        &bytecode
+    );
+
+    let mut state = PythonResourcesState::default();
+    state.add_resource(compressed).unwrap();
+    state
+        .add_resource(Resource {
+            name: "plain".into(),
+            is_python_module: true,
+            is_python_package: true,
+            in_memory_bytecode: Some(vec![42].into()),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let mut modules = state
+        .in_memory_bytecode_modules(BytecodeOptimizationLevel::Zero)
+        .map(|module| module.map(|(name, code, is_package)| (name, code.to_vec(), is_package)))
+        .collect::<Result<Vec<_>, String>>()
+        .map_err(|e| anyhow!("{}", e))?;
+    modules.sort();
+
+    assert_eq!(
+        modules,
+        vec![("compressed", bytecode, false), ("plain", vec![42], true)]
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_memory_mapped_file_resources() -> Result<()> {
     let current_dir = std::env::current_exe()?
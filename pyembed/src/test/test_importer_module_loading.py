@@ -5,6 +5,7 @@
 import importlib.machinery
 import importlib.util
 import importlib
+import json
 import marshal
 import os
 import pathlib
@@ -45,12 +46,14 @@ class TestImporterModuleLoading(unittest.TestCase):
 
         return package_path
 
-    def _finder_from_td(self):
+    def _finder_from_td(self, source_overlay_paths=None, trace_imports=False):
         collector = OxidizedResourceCollector(allowed_locations=["in-memory"])
         for r in find_resources_in_path(self.td):
             collector.add_in_memory(r)
 
-        f = OxidizedFinder()
+        f = OxidizedFinder(
+            source_overlay_paths=source_overlay_paths, trace_imports=trace_imports
+        )
         f.add_resources(collector.oxidize()[0])
 
         return f
@@ -182,6 +185,136 @@ class TestImporterModuleLoading(unittest.TestCase):
         self.assertIn("dotinit.bar", sys.modules)
         self.assertNotIn("dotinit.__init__", sys.modules)
 
+    def test_source_overlay(self):
+        p = self._make_package("overlay_package")
+
+        with (p / "__init__.py").open("wb") as fh:
+            fh.write(b"origin = 'embedded'\n")
+
+        with (p / "embedded_only.py").open("wb") as fh:
+            fh.write(b"origin = 'embedded'\n")
+
+        with tempfile.TemporaryDirectory(
+            prefix="oxidized_importer-test-overlay-"
+        ) as overlay:
+            overlay = pathlib.Path(overlay)
+            (overlay / "overlay_package").mkdir()
+            init_path = overlay / "overlay_package" / "__init__.py"
+
+            with init_path.open("wb") as fh:
+                fh.write(b"origin = 'overlay'\n")
+
+            f = self._finder_from_td(source_overlay_paths=[overlay])
+            self.assertEqual(f.source_overlay_paths, [str(overlay)])
+
+            spec = f.find_spec("overlay_package", None)
+            self.assertIsInstance(spec, importlib.machinery.ModuleSpec)
+            self.assertIsInstance(spec.loader, OxidizedFinder)
+            self.assertEqual(spec.origin, str(init_path))
+            self.assertTrue(spec.has_location)
+            self.assertEqual(
+                spec.submodule_search_locations, [str(overlay / "overlay_package")]
+            )
+
+            self.assertEqual(f.get_source("overlay_package"), "origin = 'overlay'\n")
+            self.assertEqual(f.get_filename("overlay_package"), str(init_path))
+            self.assertEqual(f.get_data(str(init_path)), b"origin = 'overlay'\n")
+
+            sys.meta_path.insert(0, f)
+
+            try:
+                m = importlib.import_module("overlay_package")
+                self.assertEqual(m.origin, "overlay")
+                self.assertEqual(m.__file__, str(init_path))
+
+                # Modules missing from the overlay fall back to indexed resources.
+                m = importlib.import_module("overlay_package.embedded_only")
+                self.assertEqual(m.origin, "embedded")
+                self.assertFalse(hasattr(m, "__file__"))
+
+                # New files are only seen after caches are invalidated.
+                with (overlay / "overlay_package" / "new.py").open("wb") as fh:
+                    fh.write(b"origin = 'overlay'\n")
+
+                self.assertIsNone(f.find_spec("overlay_package.new", None))
+                importlib.invalidate_caches()
+                m = importlib.import_module("overlay_package.new")
+                self.assertEqual(m.origin, "overlay")
+            finally:
+                for name in list(sys.modules):
+                    if name.startswith("overlay_package"):
+                        del sys.modules[name]
+
+    def test_import_trace(self):
+        p = self._make_package("trace_package")
+
+        with (p / "__init__.py").open("wb") as fh:
+            fh.write(b"from . import child\n")
+
+        with (p / "child.py").open("wb") as fh:
+            fh.write(b"value = 42\n")
+
+        f = self._finder_from_td()
+        self.assertFalse(f.trace_imports)
+        self.assertEqual(f.import_trace(), [])
+
+        with self.assertRaises(ValueError):
+            f.write_import_trace(self.td / "trace.json")
+
+        f = self._finder_from_td(trace_imports=True)
+        self.assertTrue(f.trace_imports)
+
+        sys.meta_path.insert(0, f)
+
+        try:
+            importlib.import_module("trace_package")
+        finally:
+            sys.meta_path.remove(f)
+
+            for name in list(sys.modules):
+                if name.startswith("trace_package"):
+                    del sys.modules[name]
+
+        trace = f.import_trace()
+        self.assertEqual(
+            [entry["name"] for entry in trace],
+            ["trace_package.child", "trace_package"],
+        )
+
+        child, parent = trace
+        self.assertEqual(child["finder"], "OxidizedFinder")
+        self.assertEqual(child["location"], "memory")
+        self.assertEqual(child["parent"], "trace_package")
+        self.assertIsNone(parent["parent"])
+        self.assertEqual(child["thread"], parent["thread"])
+        self.assertIsNone(child["create_module_us"])
+        self.assertIsInstance(child["get_code_us"], int)
+        self.assertGreaterEqual(parent["exec_module_us"], child["exec_module_us"])
+        self.assertGreaterEqual(child["start_us"], parent["start_us"])
+
+        path = self.td / "trace.json"
+        f.write_import_trace(path)
+        with path.open("r", encoding="utf-8") as fh:
+            data = json.load(fh)
+
+        self.assertEqual(data["imports"][0:2], trace)
+        self.assertIn("sys", [entry["name"] for entry in data["imports"]])
+
+        path = self.td / "trace-chrome.json"
+        f.write_import_trace(path, "chrome-trace")
+        with path.open("r", encoding="utf-8") as fh:
+            data = json.load(fh)
+
+        events = [e for e in data["traceEvents"] if e["cat"] == "import"]
+        self.assertEqual(
+            [e["name"] for e in events], ["trace_package.child", "trace_package"]
+        )
+        self.assertEqual(events[0]["ph"], "X")
+        self.assertEqual(events[0]["args"]["parent"], "trace_package")
+
+        with self.assertRaises(ValueError):
+            f.write_import_trace(path, "xml")
+
 
 if __name__ == "__main__":
     unittest.main()
@@ -9,9 +9,11 @@ import marshal
 import os
 from pathlib import Path
 import sys
+import tempfile
 from typing import Iterable, Optional, Tuple, Union, TYPE_CHECKING
 import unittest
 from unittest.mock import patch
+import zipfile
 
 from oxidized_importer import OxidizedFinder, OxidizedResource, OxidizedPathEntryFinder
 
@@ -264,6 +266,133 @@ class TestImporterPathEntryFinder(unittest.TestCase):
             spec, "pwd", is_pkg=False, Loader=sys.__spec__.loader, origin="built-in"
         )
 
+    @contextmanager
+    def archive_on_sys_path(self, path: str) -> Iterable[OxidizedFinder]:
+        "Make ``path`` the only ``sys.path`` entry, serviced by a new path hook."
+        f = OxidizedFinder(path_hook_zip_archives=True)
+
+        meta_path = [p for p in sys.meta_path if p is not PathFinder] + [PathFinder]
+
+        with patch("sys.path", [path]), patch("sys.meta_path", meta_path), patch(
+            "sys.path_hooks", [f.path_hook]
+        ), patch("sys.path_importer_cache", {}), patch.dict(sys.modules):
+            yield f
+
+    def test_path_hook_packed_resources_file(self):
+        data = make_finder(
+            ("plugin_pkg", "", True),
+            ("plugin_pkg.sub", "VALUE = 42", False),
+        ).serialize_indexed_resources()
+
+        with tempfile.TemporaryDirectory(prefix="oxidized_importer-test-") as td:
+            path = os.path.join(td, "plugin.pyembed")
+            with open(path, "wb") as fh:
+                fh.write(data)
+
+            f = OxidizedFinder()
+
+            pef = f.path_hook(path)
+            self.assertIsInstance(pef, OxidizedPathEntryFinder)
+            self.assertIsNone(pef._package)
+            self.assertCountEqual(pef.iter_modules(), [("plugin_pkg", True)])
+            self.assert_spec(pef.find_spec("plugin_pkg"), "plugin_pkg", is_pkg=True)
+            self.assertIsNone(pef.find_spec("plugin_pkg.sub"))
+
+            pef = f.path_hook(os.path.join(path, "plugin_pkg"))
+            self.assertEqual(pef._package, "plugin_pkg")
+            self.assert_spec(
+                pef.find_spec("plugin_pkg.sub"), "plugin_pkg.sub", is_pkg=False
+            )
+
+            with self.archive_on_sys_path(path):
+                import plugin_pkg.sub
+
+                self.assertEqual(plugin_pkg.sub.VALUE, 42)
+                self.assertEqual(
+                    list(plugin_pkg.__path__), [os.path.join(path, "plugin_pkg")]
+                )
+
+    def test_path_hook_zip_file(self):
+        with tempfile.TemporaryDirectory(prefix="oxidized_importer-test-") as td:
+            path = os.path.join(td, "plugin-1.0-py3-none-any.whl")
+            with zipfile.ZipFile(path, "w") as zf:
+                zf.writestr("zip_plugin_pkg/__init__.py", "")
+                zf.writestr("zip_plugin_pkg/sub.py", "VALUE = 42")
+
+            f = OxidizedFinder(path_hook_zip_archives=True)
+            pef = f.path_hook(path)
+            self.assertEqual(type(pef).__name__, "OxidizedZipFinder")
+            self.assertIs(f.path_hook(path), pef)
+            self.assertEqual(
+                [name for name, _ in pef.iter_modules()], ["zip_plugin_pkg"]
+            )
+
+            # Sub-directories are serviced by a finder rooted at that directory.
+            sub_path = os.path.join(path, "zip_plugin_pkg")
+            sub_pef = f.path_hook(sub_path)
+            self.assertEqual(type(sub_pef).__name__, "OxidizedZipFinder")
+            self.assertIsNot(sub_pef, pef)
+            self.assertIs(f.path_hook(sub_path), sub_pef)
+            self.assertEqual([name for name, _ in sub_pef.iter_modules()], ["sub"])
+
+            with self.archive_on_sys_path(path):
+                import zip_plugin_pkg.sub
+
+                self.assertEqual(zip_plugin_pkg.sub.VALUE, 42)
+
+    def test_path_hook_zip_file_sub_directory(self):
+        with tempfile.TemporaryDirectory(prefix="oxidized_importer-test-") as td:
+            path = os.path.join(td, "plugin.zip")
+            with zipfile.ZipFile(path, "w") as zf:
+                zf.writestr("lib/zip_lib_pkg/__init__.py", "")
+                zf.writestr("lib/zip_lib_pkg/sub.py", "VALUE = 42")
+
+            f = OxidizedFinder(path_hook_zip_archives=True)
+            self.assertIsNone(f.path_hook(path).find_spec("zip_lib_pkg", None))
+
+            with self.archive_on_sys_path(os.path.join(path, "lib")):
+                import zip_lib_pkg.sub
+
+                self.assertEqual(zip_lib_pkg.sub.VALUE, 42)
+                self.assertEqual(
+                    zip_lib_pkg.sub.__file__,
+                    os.path.join(path, "lib", "zip_lib_pkg", "sub.py"),
+                )
+
+    def test_path_hook_non_archive_file_rejected(self):
+        with tempfile.TemporaryDirectory(prefix="oxidized_importer-test-") as td:
+            path = os.path.join(td, "not-an-archive")
+            with open(path, "wb") as fh:
+                fh.write(b"foo")
+
+            f = OxidizedFinder(path_hook_zip_archives=True)
+
+            with self.assertRaises(ImportError):
+                f.path_hook(path)
+
+            with self.assertRaises(ImportError):
+                f.path_hook(os.path.join(path, "foo"))
+
+    def test_path_hook_zip_file_disabled(self):
+        with tempfile.TemporaryDirectory(prefix="oxidized_importer-test-") as td:
+            path = os.path.join(td, "plugin.zip")
+            with zipfile.ZipFile(path, "w") as zf:
+                zf.writestr("zip_plugin_pkg/__init__.py", "")
+
+            f = OxidizedFinder()
+            self.assertFalse(f.path_hook_zip_archives)
+
+            with self.assertRaises(ImportError) as e:
+                f.path_hook(path)
+
+            self.assertIn(
+                "zip archive handling is disabled", str(e.exception.__cause__)
+            )
+
+            f.path_hook_zip_archives = True
+            self.assertTrue(f.path_hook_zip_archives)
+            self.assertEqual(type(f.path_hook(path)).__name__, "OxidizedZipFinder")
+
     ############################################################################
     # Error Handling
 
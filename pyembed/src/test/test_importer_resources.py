@@ -653,6 +653,8 @@ class TestImporterResources(unittest.TestCase):
 
         serialized = f.serialize_indexed_resources()
         self.assertIsInstance(serialized, bytes)
+        # Version 4 is only used when its features are needed.
+        self.assertTrue(serialized.startswith(b"pyembed\x03"))
 
         f2 = OxidizedFinder()
         f2.index_bytes(serialized)
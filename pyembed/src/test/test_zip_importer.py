@@ -359,6 +359,123 @@ class TestImporter(unittest.TestCase):
         self.assertEqual(spec.origin, str(p / "foo.py"))
         self.assertIsNone(spec.submodule_search_locations)
 
+    def test_get_data_get_filename(self):
+        zip_data = make_zip(
+            {
+                "foo/__init__.py": (DEFAULT_MTIME, b""),
+                "foo/data.txt": (DEFAULT_MTIME, b"data"),
+            }
+        )
+
+        importer = OxidizedZipFinder.from_zip_data(zip_data)
+
+        self.assertEqual(
+            importer.get_filename("foo"),
+            os.path.join(sys.executable, "foo", "__init__.py"),
+        )
+
+        with self.assertRaises(ImportError):
+            importer.get_filename("missing")
+
+        self.assertEqual(
+            importer.get_data(os.path.join(sys.executable, "foo", "data.txt")),
+            b"data",
+        )
+
+        with self.assertRaises(OSError):
+            importer.get_data(os.path.join(sys.executable, "foo", "missing.txt"))
+
+        with self.assertRaises(OSError):
+            importer.get_data(os.path.join(str(self.td), "foo", "data.txt"))
+
+    def test_resource_reader(self):
+        zip_data = make_zip(
+            {
+                "foo/__init__.py": (DEFAULT_MTIME, b""),
+                "foo/bar.py": (DEFAULT_MTIME, b""),
+                "foo/data.txt": (DEFAULT_MTIME, b"data"),
+                "foo/subdir/other.txt": (DEFAULT_MTIME, b"other"),
+            }
+        )
+
+        importer = OxidizedZipFinder.from_zip_data(zip_data)
+
+        self.assertIsNone(importer.get_resource_reader("missing"))
+        self.assertIsNone(importer.get_resource_reader("foo.bar"))
+
+        reader = importer.get_resource_reader("foo")
+        self.assertEqual(
+            list(reader.contents()), ["__init__.py", "bar.py", "data.txt", "subdir"]
+        )
+        self.assertTrue(reader.is_resource("data.txt"))
+
+        with self.assertRaises(FileNotFoundError):
+            reader.is_resource("missing.txt")
+
+        with reader.open_resource("data.txt") as fh:
+            self.assertEqual(fh.read(), b"data")
+
+        with self.assertRaises(FileNotFoundError):
+            reader.open_resource("missing.txt")
+
+        sys.meta_path.insert(0, importer)
+        import importlib.resources
+
+        self.assertEqual(importlib.resources.read_binary("foo", "data.txt"), b"data")
+
+    def test_find_distributions(self):
+        zip_data = make_zip(
+            {
+                "foo/__init__.py": (DEFAULT_MTIME, b""),
+                "foo-1.0.dist-info/METADATA": (
+                    DEFAULT_MTIME,
+                    b"Name: foo\nVersion: 1.0\n",
+                ),
+                "bar_baz-2.0.dist-info/METADATA": (
+                    DEFAULT_MTIME,
+                    b"Name: bar-baz\nVersion: 2.0\n",
+                ),
+            }
+        )
+
+        importer = OxidizedZipFinder.from_zip_data(zip_data)
+
+        dists = list(importer.find_distributions())
+        self.assertEqual(len(dists), 2)
+        self.assertEqual([d.version for d in dists], ["2.0", "1.0"])
+
+        import importlib.metadata
+
+        dists = list(
+            importer.find_distributions(
+                importlib.metadata.DistributionFinder.Context(name="bar-baz")
+            )
+        )
+        self.assertEqual(len(dists), 1)
+        self.assertEqual(dists[0].metadata["Name"], "bar-baz")
+
+        sys.meta_path.insert(0, importer)
+        self.assertEqual(importlib.metadata.version("foo"), "1.0")
+
+    def test_iter_modules(self):
+        zip_data = make_zip(
+            {
+                "foo/__init__.py": (DEFAULT_MTIME, b""),
+                "foo/bar.py": (DEFAULT_MTIME, b""),
+                "baz.py": (DEFAULT_MTIME, b""),
+                "baz.pyc": (DEFAULT_MTIME, b""),
+                "data.txt": (DEFAULT_MTIME, b""),
+            }
+        )
+
+        importer = OxidizedZipFinder.from_zip_data(zip_data)
+
+        self.assertEqual(importer.iter_modules(), [("baz", False), ("foo", True)])
+        self.assertEqual(
+            importer.iter_modules("prefix."),
+            [("prefix.baz", False), ("prefix.foo", True)],
+        )
+
 
 if __name__ == "__main__":
     unittest.main()
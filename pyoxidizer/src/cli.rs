@@ -5,7 +5,7 @@
 use {
     crate::{
         environment::{default_target_triple, PYOXIDIZER_VERSION},
-        logging, project_building, projectmgmt,
+        logging, project_building, projectmgmt, resource_inspection,
     },
     anyhow::{anyhow, Context, Result},
     clap::{Arg, ArgMatches, Command},
@@ -98,6 +98,29 @@ conversion is critical for properly packaging Python applications and
 bugs can result in incorrect install layouts, missing resources, etc.
 ";
 
+const RESOURCES_ABOUT: &str = "\
+Inspect Python packed resources files.
+
+Packed resources files are produced by PyOxidizer to hold Python resources
+that are indexed and loaded by the `oxidized_importer` meta path finder.
+These commands allow looking inside these files to see what a build
+actually embeds.
+";
+
+const RESOURCES_EXTRACT_ABOUT: &str = "\
+Write the in-memory data of resources to a directory.
+
+Module source is written to `.py` files using the standard filesystem
+layout for Python modules. Bytecode is written to `.bytecode`,
+`.opt-1.bytecode`, and `.opt-2.bytecode` files. These files hold raw
+marshalled code objects and lack the header of `.pyc` files.
+
+Package resources are written next to the package's source. Package
+distribution resources are written to a `<package>.dist-info` directory,
+as the package version isn't recorded in packed resources data.
+Shared libraries and arbitrary files are written under their resource name.
+";
+
 const VAR_HELP: &str = "\
 Defines a single string key to set in the VARS global dict.
 
@@ -408,6 +431,72 @@ pub fn run_cli() -> Result<()> {
             ),
     );
 
+    let app = app.subcommand(
+        Command::new("resources")
+            .about("Inspect Python packed resources files")
+            .long_about(RESOURCES_ABOUT)
+            .arg_required_else_help(true)
+            .subcommand(
+                Command::new("list")
+                    .about("List resources and their flags")
+                    .arg(
+                        Arg::new("path")
+                            .required(true)
+                            .value_name("PATH")
+                            .help("Path to packed resources file"),
+                    ),
+            )
+            .subcommand(
+                Command::new("show")
+                    .about("Show the data sizes and paths of a resource")
+                    .arg(
+                        Arg::new("path")
+                            .required(true)
+                            .value_name("PATH")
+                            .help("Path to packed resources file"),
+                    )
+                    .arg(
+                        Arg::new("name")
+                            .required(true)
+                            .value_name("NAME")
+                            .help("Name of resource to show"),
+                    ),
+            )
+            .subcommand(
+                Command::new("extract")
+                    .about("Write the in-memory data of resources to a directory")
+                    .long_about(RESOURCES_EXTRACT_ABOUT)
+                    .arg(
+                        Arg::new("path")
+                            .required(true)
+                            .value_name("PATH")
+                            .help("Path to packed resources file"),
+                    )
+                    .arg(
+                        Arg::new("dest_path")
+                            .required(true)
+                            .value_name("DESTINATION_PATH")
+                            .help("Directory to write files to"),
+                    ),
+            )
+            .subcommand(
+                Command::new("diff")
+                    .about("Show differences between 2 packed resources files")
+                    .arg(
+                        Arg::new("old_path")
+                            .required(true)
+                            .value_name("OLD_PATH")
+                            .help("Path to original packed resources file"),
+                    )
+                    .arg(
+                        Arg::new("new_path")
+                            .required(true)
+                            .value_name("NEW_PATH")
+                            .help("Path to new packed resources file"),
+                    ),
+            ),
+    );
+
     let app = app.subcommand(add_env_args(
         Command::new("run-build-script")
             .about("Run functionality that a build script would perform")
@@ -617,6 +706,29 @@ pub fn run_cli() -> Result<()> {
             projectmgmt::python_distribution_licenses(path)
         }
 
+        "resources" => {
+            let (command, args) = args
+                .subcommand()
+                .ok_or_else(|| anyhow!("invalid sub-command"))?;
+
+            match command {
+                "list" => resource_inspection::list(Path::new(args.value_of("path").unwrap())),
+                "show" => resource_inspection::show(
+                    Path::new(args.value_of("path").unwrap()),
+                    args.value_of("name").unwrap(),
+                ),
+                "extract" => resource_inspection::extract(
+                    Path::new(args.value_of("path").unwrap()),
+                    Path::new(args.value_of("dest_path").unwrap()),
+                ),
+                "diff" => resource_inspection::diff(
+                    Path::new(args.value_of("old_path").unwrap()),
+                    Path::new(args.value_of("new_path").unwrap()),
+                ),
+                _ => Err(anyhow!("invalid sub-command")),
+            }
+        }
+
         "run-build-script" => {
             let starlark_vars = starlark_vars(args)?;
             let build_script = args.value_of("build-script-name").unwrap();
@@ -18,6 +18,7 @@ pub mod project_layout;
 pub mod projectmgmt;
 pub mod py_packaging;
 pub mod python_distributions;
+pub mod resource_inspection;
 pub mod starlark;
 
 #[cfg(test)]
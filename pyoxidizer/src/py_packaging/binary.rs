@@ -22,7 +22,12 @@ use {
             AddResourceAction, PrePackagedResource, PythonResourceAddCollectionContext,
         },
     },
-    std::{collections::HashMap, path::Path, sync::Arc},
+    python_packed_resources::CompressionCodec,
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+        sync::Arc,
+    },
     tugger_file_manifest::File,
     tugger_windows::VcRedistributablePlatform,
 };
@@ -230,6 +235,24 @@ pub trait PythonBinaryBuilder {
     /// Set how packed Python resources will be loaded by the binary.
     fn set_packed_resources_load_mode(&mut self, load_mode: PackedResourcesLoadMode);
 
+    /// Path to an import trace used to order packed resources.
+    fn packed_resources_import_profile(&self) -> &Option<PathBuf>;
+
+    /// Set the path to an import trace used to order packed resources.
+    ///
+    /// Modules recorded in the trace are flagged for preloading, which places
+    /// their data at the front of packed resources and allows the binary to
+    /// page it in eagerly at start-up.
+    fn set_packed_resources_import_profile(&mut self, path: Option<PathBuf>);
+
+    /// Codec used to compress the in-memory data of packed resources.
+    fn packed_resources_compression(&self) -> Option<CompressionCodec>;
+
+    /// Set the codec used to compress the in-memory data of packed resources.
+    ///
+    /// Compressed data requires version 4 of the packed resources format.
+    fn set_packed_resources_compression(&mut self, codec: Option<CompressionCodec>);
+
     /// Obtain an iterator over all resource entries that will be embedded in the binary.
     ///
     /// This likely does not return extension modules that are statically linked
@@ -7,13 +7,13 @@ Configuring a Python interpreter.
 */
 
 use {
-    anyhow::Result,
+    anyhow::{Context, Result},
     itertools::Itertools,
     python_packaging::{
         interpreter::{
-            Allocator, BytesWarning, CheckHashPycsMode, CoerceCLocale, MemoryAllocatorBackend,
-            MultiprocessingStartMethod, PythonInterpreterConfig, PythonInterpreterProfile,
-            TerminfoResolution,
+            Allocator, BytesWarning, CheckHashPycsMode, CoerceCLocale, CrashOutput,
+            ImportTraceFormat, MemoryAllocatorBackend, MultiprocessingStartMethod,
+            PythonInterpreterConfig, PythonInterpreterProfile, TerminfoResolution,
         },
         resource::BytecodeOptimizationLevel,
     },
@@ -64,6 +64,19 @@ fn optional_pathbuf_to_string(value: &Option<PathBuf>) -> String {
     }
 }
 
+fn optional_crash_output_to_string(value: &Option<CrashOutput>) -> String {
+    match value {
+        Some(CrashOutput::Stderr) => "Some(pyembed::CrashOutput::Stderr)".to_string(),
+        Some(CrashOutput::FileDescriptor(fd)) => {
+            format!("Some(pyembed::CrashOutput::FileDescriptor({}))", fd)
+        }
+        Some(CrashOutput::Path(path)) => {
+            format!("Some(pyembed::CrashOutput::Path({}))", path_to_string(path))
+        }
+        None => "None".to_string(),
+    }
+}
+
 fn optional_vec_string_to_string(value: &Option<Vec<String>>) -> String {
     match value {
         Some(value) => format!(
@@ -127,6 +140,11 @@ pub struct PyembedPythonInterpreterConfig {
     pub oxidized_importer: bool,
     pub filesystem_importer: bool,
     pub packed_resources: Vec<PyembedPackedResourcesSource>,
+    pub packed_resources_signing_certificate_path: Option<PathBuf>,
+    pub packed_resources_signing_key_path: Option<PathBuf>,
+    pub packed_resources_trust_anchors: Option<Vec<PathBuf>>,
+    pub source_overlay_paths: Option<Vec<PathBuf>>,
+    pub source_overlay_paths_env: Option<String>,
     pub argvb: bool,
     pub multiprocessing_auto_dispatch: bool,
     pub multiprocessing_start_method: MultiprocessingStartMethod,
@@ -135,6 +153,10 @@ pub struct PyembedPythonInterpreterConfig {
     pub terminfo_resolution: TerminfoResolution,
     pub tcl_library: Option<PathBuf>,
     pub write_modules_directory_env: Option<String>,
+    pub write_import_trace_directory_env: Option<String>,
+    pub import_trace_format: ImportTraceFormat,
+    pub faulthandler_output: Option<CrashOutput>,
+    pub crash_report_output: Option<CrashOutput>,
 }
 
 impl Default for PyembedPythonInterpreterConfig {
@@ -162,6 +184,11 @@ impl Default for PyembedPythonInterpreterConfig {
             oxidized_importer: true,
             filesystem_importer: false,
             packed_resources: vec![],
+            packed_resources_signing_certificate_path: None,
+            packed_resources_signing_key_path: None,
+            packed_resources_trust_anchors: None,
+            source_overlay_paths: None,
+            source_overlay_paths_env: None,
             argvb: false,
             multiprocessing_auto_dispatch: true,
             multiprocessing_start_method: MultiprocessingStartMethod::Auto,
@@ -170,11 +197,29 @@ impl Default for PyembedPythonInterpreterConfig {
             terminfo_resolution: TerminfoResolution::None,
             tcl_library: None,
             write_modules_directory_env: None,
+            write_import_trace_directory_env: None,
+            import_trace_format: ImportTraceFormat::Json,
+            faulthandler_output: None,
+            crash_report_output: None,
         }
     }
 }
 
 impl PyembedPythonInterpreterConfig {
+    /// Resolve the paths to PEM certificates trusted to sign packed resources.
+    ///
+    /// If trust anchors aren't explicitly defined, the packed resources signing
+    /// certificate, if any, is trusted.
+    pub fn resolve_packed_resources_trust_anchors(&self) -> Vec<PathBuf> {
+        if let Some(paths) = &self.packed_resources_trust_anchors {
+            paths.clone()
+        } else if let Some(path) = &self.packed_resources_signing_certificate_path {
+            vec![path.clone()]
+        } else {
+            vec![]
+        }
+    }
+
     /// Convert the instance to Rust code that constructs a `pyembed::OxidizedPythonInterpreterConfig`.
     pub fn to_oxidized_python_interpreter_config_rs(&self) -> Result<String> {
         // This code is complicated enough. Let's not worry about format! in format!.
@@ -251,6 +296,9 @@ impl PyembedPythonInterpreterConfig {
             oxidized_importer: {},\n    \
             filesystem_importer: {},\n    \
             packed_resources: {},\n    \
+            packed_resources_trust_anchors: {},\n    \
+            source_overlay_paths: {},\n    \
+            source_overlay_paths_env: {},\n    \
             extra_extension_modules: None,\n    \
             argv: None,\n    \
             argvb: {},\n    \
@@ -261,6 +309,10 @@ impl PyembedPythonInterpreterConfig {
             terminfo_resolution: {},\n    \
             tcl_library: {},\n    \
             write_modules_directory_env: {},\n    \
+            write_import_trace_directory_env: {},\n    \
+            import_trace_format: {},\n    \
+            faulthandler_output: {},\n    \
+            crash_report_output: {},\n    \
             }}\n\
             ",
             match self.config.profile {
@@ -389,6 +441,29 @@ impl PyembedPythonInterpreterConfig {
                     .map(|e| e.to_string())
                     .join(", ")
             ),
+            format!(
+                "vec![{}]",
+                self.resolve_packed_resources_trust_anchors()
+                    .iter()
+                    .map(|path| {
+                        let pem = std::fs::read_to_string(path).with_context(|| {
+                            format!("reading trust anchor certificate {}", path.display())
+                        })?;
+
+                        Ok(format!("r###\"{}\"###.to_string()", pem))
+                    })
+                    .collect::<Result<Vec<_>>>()?
+                    .join(", ")
+            ),
+            format!(
+                "vec![{}]",
+                self.source_overlay_paths
+                    .iter()
+                    .flatten()
+                    .map(|p| path_to_string(p.as_path()))
+                    .join(", ")
+            ),
+            optional_string_to_string(&self.source_overlay_paths_env),
             self.argvb,
             self.multiprocessing_auto_dispatch,
             match self.multiprocessing_start_method {
@@ -414,6 +489,13 @@ impl PyembedPythonInterpreterConfig {
             },
             optional_pathbuf_to_string(&self.tcl_library),
             optional_string_to_string(&self.write_modules_directory_env),
+            optional_string_to_string(&self.write_import_trace_directory_env),
+            match self.import_trace_format {
+                ImportTraceFormat::Json => "pyembed::ImportTraceFormat::Json",
+                ImportTraceFormat::ChromeTrace => "pyembed::ImportTraceFormat::ChromeTrace",
+            },
+            optional_crash_output_to_string(&self.faulthandler_output),
+            optional_crash_output_to_string(&self.crash_report_output),
         );
 
         Ok(code)
@@ -515,6 +597,110 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_packed_resources_trust_anchors() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let cert_path = temp_dir.path().join("cert.pem");
+        std::fs::write(&cert_path, "CERTIFICATE")?;
+
+        let mut config = PyembedPythonInterpreterConfig::default();
+        let code = config.to_oxidized_python_interpreter_config_rs()?;
+        assert_contains(&code, "packed_resources_trust_anchors: vec![],")?;
+
+        // The signing certificate is trusted by default.
+        config.packed_resources_signing_certificate_path = Some(cert_path);
+        let code = config.to_oxidized_python_interpreter_config_rs()?;
+        assert_contains(
+            &code,
+            "packed_resources_trust_anchors: vec![r###\"CERTIFICATE\"###.to_string()],",
+        )?;
+
+        config.packed_resources_trust_anchors = Some(vec![]);
+        let code = config.to_oxidized_python_interpreter_config_rs()?;
+        assert_contains(&code, "packed_resources_trust_anchors: vec![],")?;
+
+        config.packed_resources_trust_anchors = Some(vec![temp_dir.path().join("missing.pem")]);
+        assert!(config.to_oxidized_python_interpreter_config_rs().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_import_trace() -> Result<()> {
+        let mut config = PyembedPythonInterpreterConfig::default();
+        let code = config.to_oxidized_python_interpreter_config_rs()?;
+        assert_contains(&code, "write_import_trace_directory_env: None,")?;
+        assert_contains(
+            &code,
+            "import_trace_format: pyembed::ImportTraceFormat::Json,",
+        )?;
+
+        config.write_import_trace_directory_env = Some("IMPORT_TRACE_DIR".into());
+        config.import_trace_format = ImportTraceFormat::ChromeTrace;
+        let code = config.to_oxidized_python_interpreter_config_rs()?;
+        assert_contains(
+            &code,
+            "write_import_trace_directory_env: Some(\"IMPORT_TRACE_DIR\".to_string()),",
+        )?;
+        assert_contains(
+            &code,
+            "import_trace_format: pyembed::ImportTraceFormat::ChromeTrace,",
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_crash_diagnostics() -> Result<()> {
+        let mut config = PyembedPythonInterpreterConfig::default();
+        let code = config.to_oxidized_python_interpreter_config_rs()?;
+        assert_contains(&code, "faulthandler_output: None,")?;
+        assert_contains(&code, "crash_report_output: None,")?;
+
+        config.faulthandler_output = Some(CrashOutput::FileDescriptor(3));
+        config.crash_report_output = Some(CrashOutput::Path("crash.log".into()));
+        let code = config.to_oxidized_python_interpreter_config_rs()?;
+        assert_contains(
+            &code,
+            "faulthandler_output: Some(pyembed::CrashOutput::FileDescriptor(3)),",
+        )?;
+        assert_contains(
+            &code,
+            "crash_report_output: Some(pyembed::CrashOutput::Path(std::path::PathBuf::from(\"crash.log\"))),",
+        )?;
+
+        config.faulthandler_output = Some(CrashOutput::Stderr);
+        let code = config.to_oxidized_python_interpreter_config_rs()?;
+        assert_contains(
+            &code,
+            "faulthandler_output: Some(pyembed::CrashOutput::Stderr),",
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_source_overlay_paths() -> Result<()> {
+        let mut config = PyembedPythonInterpreterConfig::default();
+        let code = config.to_oxidized_python_interpreter_config_rs()?;
+        assert_contains(&code, "source_overlay_paths: vec![],")?;
+        assert_contains(&code, "source_overlay_paths_env: None,")?;
+
+        config.source_overlay_paths = Some(vec!["$ORIGIN/src".into()]);
+        config.source_overlay_paths_env = Some("APP_SOURCE".into());
+        let code = config.to_oxidized_python_interpreter_config_rs()?;
+        assert_contains(
+            &code,
+            "source_overlay_paths: vec![std::path::PathBuf::from(\"$ORIGIN/src\")],",
+        )?;
+        assert_contains(
+            &code,
+            "source_overlay_paths_env: Some(\"APP_SOURCE\".to_string()),",
+        )?;
+
+        Ok(())
+    }
+
     // TODO enable once CI has a linkable Python.
     #[test]
     #[ignore]
@@ -598,12 +784,21 @@ mod tests {
                     "$ORIGIN/packed-resources",
                 )),
             ],
+            packed_resources_signing_certificate_path: None,
+            packed_resources_signing_key_path: None,
+            packed_resources_trust_anchors: None,
+            source_overlay_paths: Some(vec!["$ORIGIN/src".into()]),
+            source_overlay_paths_env: Some("env".into()),
             argvb: true,
             sys_frozen: false,
             sys_meipass: true,
             terminfo_resolution: TerminfoResolution::Dynamic,
             tcl_library: Some("path".into()),
             write_modules_directory_env: Some("env".into()),
+            write_import_trace_directory_env: Some("trace_env".into()),
+            import_trace_format: ImportTraceFormat::ChromeTrace,
+            faulthandler_output: Some(CrashOutput::Stderr),
+            crash_report_output: Some(CrashOutput::Path("crash.log".into())),
             multiprocessing_auto_dispatch: false,
             multiprocessing_start_method: MultiprocessingStartMethod::Spawn,
         };
@@ -41,6 +41,7 @@ use {
             PythonResourceCollector,
         },
     },
+    python_packed_resources::{CompressionCodec, ResourceCompression},
     slog::warn,
     std::{
         collections::{BTreeMap, BTreeSet, HashMap},
@@ -51,8 +52,39 @@ use {
     tugger_file_manifest::{File, FileData, FileEntry, FileManifest},
     tugger_licensing::{ComponentFlavor, LicensedComponent},
     tugger_windows::{find_visual_cpp_redistributable, VcRedistributablePlatform},
+    x509_certificate::{CapturedX509Certificate, InMemorySigningKeyPair},
 };
 
+/// Resources with less in-memory data than this many bytes aren't compressed.
+const PACKED_RESOURCES_COMPRESSION_MINIMUM_SIZE: usize = 256;
+
+/// Obtain the names of modules recorded in an import trace.
+///
+/// The trace is the JSON document written by `pyembed` when import tracing
+/// is enabled. Modules are returned in the order they were recorded.
+fn import_profile_module_names(path: &Path) -> Result<Vec<String>> {
+    let data = std::fs::read(path)
+        .with_context(|| format!("reading import profile {}", path.display()))?;
+    let value: serde_json::Value = serde_json::from_slice(&data)
+        .with_context(|| format!("parsing import profile {}", path.display()))?;
+
+    let imports = value
+        .get("imports")
+        .and_then(|imports| imports.as_array())
+        .ok_or_else(|| {
+            anyhow!(
+                "{} is not a JSON import trace: missing imports array",
+                path.display()
+            )
+        })?;
+
+    Ok(imports
+        .iter()
+        .filter_map(|entry| entry.get("name").and_then(|name| name.as_str()))
+        .map(|name| name.to_string())
+        .collect())
+}
+
 /// Libraries that we should not link against on Linux.
 static LINUX_IGNORE_LIBRARIES: Lazy<Vec<&'static str>> = Lazy::new(|| vec!["dl", "m"]);
 
@@ -104,6 +136,12 @@ pub struct StandalonePythonExecutableBuilder {
     /// How packed resources will be loaded at run-time.
     resources_load_mode: PackedResourcesLoadMode,
 
+    /// Import trace used to flag resources for preloading.
+    resources_import_profile: Option<PathBuf>,
+
+    /// Codec used to compress in-memory resource data.
+    resources_compression: Option<CompressionCodec>,
+
     /// Holds state necessary to link libpython.
     core_build_context: LibPythonBuildContext,
 
@@ -218,6 +256,8 @@ impl StandalonePythonExecutableBuilder {
             resources_load_mode: PackedResourcesLoadMode::EmbeddedInBinary(
                 "packed-resources".to_string(),
             ),
+            resources_import_profile: None,
+            resources_compression: None,
             core_build_context: LibPythonBuildContext::default(),
             extension_build_contexts: BTreeMap::new(),
             config,
@@ -414,6 +454,35 @@ impl StandalonePythonExecutableBuilder {
 
         Ok(manifest)
     }
+
+    /// Resolve the certificate and key used to sign packed resources, if configured.
+    fn packed_resources_signer(
+        &self,
+    ) -> Result<Option<(CapturedX509Certificate, InMemorySigningKeyPair)>> {
+        match (
+            &self.config.packed_resources_signing_certificate_path,
+            &self.config.packed_resources_signing_key_path,
+        ) {
+            (Some(cert_path), Some(key_path)) => {
+                let certificate = CapturedX509Certificate::from_pem(
+                    std::fs::read(cert_path)
+                        .with_context(|| format!("reading {}", cert_path.display()))?,
+                )
+                .with_context(|| format!("parsing certificate {}", cert_path.display()))?;
+                let signing_key = InMemorySigningKeyPair::from_pkcs8_pem(
+                    std::fs::read(key_path)
+                        .with_context(|| format!("reading {}", key_path.display()))?,
+                )
+                .with_context(|| format!("parsing signing key {}", key_path.display()))?;
+
+                Ok(Some((certificate, signing_key)))
+            }
+            (None, None) => Ok(None),
+            _ => Err(anyhow!(
+                "packed resources signing requires both a certificate and a signing key"
+            )),
+        }
+    }
 }
 
 impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
@@ -517,6 +586,22 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         self.resources_load_mode = load_mode;
     }
 
+    fn packed_resources_import_profile(&self) -> &Option<PathBuf> {
+        &self.resources_import_profile
+    }
+
+    fn set_packed_resources_import_profile(&mut self, path: Option<PathBuf>) {
+        self.resources_import_profile = path;
+    }
+
+    fn packed_resources_compression(&self) -> Option<CompressionCodec> {
+        self.resources_compression
+    }
+
+    fn set_packed_resources_compression(&mut self, codec: Option<CompressionCodec>) {
+        self.resources_compression = codec;
+    }
+
     fn iter_resources<'a>(
         &'a self,
     ) -> Box<dyn Iterator<Item = (&'a String, &'a PrePackagedResource)> + 'a> {
@@ -931,12 +1016,31 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
             warn!(logger, "license: {}; packages: {:?}", license, packages);
         }
 
-        let compiled_resources = {
+        let mut compiled_resources = {
             let temp_dir = tempfile::TempDir::new()?;
             let mut compiler = BytecodeCompiler::new(self.host_python_exe_path(), temp_dir.path())?;
             self.resources_collector.compile_resources(&mut compiler)?
         };
 
+        if let Some(path) = &self.resources_import_profile {
+            let names = import_profile_module_names(path)?;
+            let count = compiled_resources.set_preload_resources(names.iter().map(|s| s.as_str()));
+
+            warn!(
+                logger,
+                "flagged {} of {} resources for preloading from {}",
+                count,
+                compiled_resources.resources.len(),
+                path.display()
+            );
+        }
+
+        compiled_resources.compression =
+            self.resources_compression.map(|codec| ResourceCompression {
+                codec,
+                minimum_size: PACKED_RESOURCES_COMPRESSION_MINIMUM_SIZE,
+            });
+
         let mut pending_resources = vec![];
 
         let mut extra_files = compiled_resources.extra_files_manifest()?;
@@ -956,9 +1060,33 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
             PackedResourcesLoadMode::BinaryRelativePathMemoryMapped(path) => {
                 // We need to materialize the file in extra_files. So compile now.
                 let mut buffer = vec![];
-                compiled_resources
-                    .write_packed_resources(&mut buffer)
-                    .context("serializing packed resources")?;
+
+                if let Some((certificate, signing_key)) = self.packed_resources_signer()? {
+                    warn!(
+                        logger,
+                        "signing packed resources {} with {}",
+                        path,
+                        certificate.subject_common_name().unwrap_or_default()
+                    );
+
+                    compiled_resources
+                        .write_packed_resources_with_integrity_digests(&mut buffer)
+                        .context("serializing packed resources")?;
+
+                    let signature = python_packed_resources::sign_packed_resources(
+                        &buffer,
+                        &signing_key,
+                        &certificate,
+                    )
+                    .context("signing packed resources")?;
+
+                    extra_files.add_file_entry(Path::new(&format!("{}.sig", path)), signature)?;
+                } else {
+                    compiled_resources
+                        .write_packed_resources(&mut buffer)
+                        .context("serializing packed resources")?;
+                }
+
                 extra_files.add_file_entry(Path::new(path), buffer)?;
 
                 config
@@ -1356,6 +1484,88 @@ pub mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_packed_resources_import_profile() -> Result<()> {
+        let logger = get_logger()?;
+        let options = StandalonePythonExecutableBuilderOptions::default();
+        let mut exe = options.new_builder()?;
+
+        let temp_dir = tempfile::Builder::new()
+            .prefix("pyoxidizer-test")
+            .tempdir()?;
+
+        let profile_path = temp_dir.path().join("imports.json");
+        std::fs::write(
+            &profile_path,
+            r#"{"imports": [{"name": "encodings"}, {"name": "missing"}, {"name": "abc"}]}"#,
+        )?;
+        exe.set_packed_resources_import_profile(Some(profile_path));
+
+        let embedded = exe.to_embedded_python_context(&logger, &get_env()?, "0")?;
+        embedded.write_files(temp_dir.path())?;
+
+        let data = std::fs::read(temp_dir.path().join("packed-resources"))?;
+        let resources = python_packed_resources::load_resources(&data)
+            .map_err(|e| anyhow!("{}", e))?
+            .collect::<Result<Vec<_>, &'static str>>()
+            .map_err(|e| anyhow!("{}", e))?;
+
+        assert_eq!(
+            resources
+                .iter()
+                .take(2)
+                .map(|r| (r.name.as_ref(), r.preload))
+                .collect::<Vec<_>>(),
+            vec![("encodings", true), ("abc", true)]
+        );
+        assert!(resources.iter().skip(2).all(|r| !r.preload));
+
+        exe.set_packed_resources_import_profile(Some(temp_dir.path().join("missing.json")));
+        assert!(exe
+            .to_embedded_python_context(&logger, &get_env()?, "0")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_packed_resources_compression() -> Result<()> {
+        let logger = get_logger()?;
+        let options = StandalonePythonExecutableBuilderOptions::default();
+        let mut exe = options.new_builder()?;
+
+        let temp_dir = tempfile::Builder::new()
+            .prefix("pyoxidizer-test")
+            .tempdir()?;
+
+        let embedded = exe.to_embedded_python_context(&logger, &get_env()?, "0")?;
+        embedded.write_files(temp_dir.path())?;
+        let data = std::fs::read(temp_dir.path().join("packed-resources"))?;
+        assert!(data.starts_with(python_packed_resources::HEADER_V3));
+
+        for codec in [CompressionCodec::Zstd, CompressionCodec::Lz4] {
+            exe.set_packed_resources_compression(Some(codec));
+
+            let embedded = exe.to_embedded_python_context(&logger, &get_env()?, "0")?;
+            embedded.write_files(temp_dir.path())?;
+
+            let data = std::fs::read(temp_dir.path().join("packed-resources"))?;
+            assert!(data.starts_with(python_packed_resources::HEADER_V4));
+
+            let resources = python_packed_resources::load_resources(&data)
+                .map_err(|e| anyhow!("{}", e))?
+                .collect::<Result<Vec<_>, &'static str>>()
+                .map_err(|e| anyhow!("{}", e))?;
+
+            assert!(resources.iter().any(|r| r.data_compression == Some(codec)));
+            for resource in &resources {
+                resource.decompress().map_err(|e| anyhow!("{}", e))?;
+            }
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_memory_mapped_file_resources() -> Result<()> {
         let logger = get_logger()?;
@@ -0,0 +1,531 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Inspect Python packed resources data.
+
+This module powers the `pyoxidizer resources` family of commands, which
+allow looking inside packed resources files without going through
+`oxidized_importer`.
+*/
+
+use {
+    anyhow::{anyhow, Context, Result},
+    python_packaging::module_util::resolve_path_for_module,
+    python_packed_resources::{load_resources, Resource},
+    std::{
+        borrow::Cow,
+        collections::{BTreeMap, HashMap},
+        path::{Path, PathBuf},
+    },
+    tugger_file_manifest::{FileEntry, FileManifest},
+};
+
+/// Read and parse all resources in a packed resources file.
+///
+/// Resources have their data decompressed, so reported sizes and extracted
+/// content reflect the original data.
+fn read_resources(data: &[u8]) -> Result<Vec<Resource<'_, u8>>> {
+    load_resources(data)
+        .map_err(|e| anyhow!("error loading packed resources: {}", e))?
+        .map(|resource| {
+            resource
+                .and_then(|resource| resource.decompress())
+                .map_err(|e| anyhow!("error parsing resource: {}", e))
+        })
+        .collect::<Result<Vec<_>>>()
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path).with_context(|| format!("reading {}", path.display()))
+}
+
+/// Obtain the names of boolean flags set on a resource.
+fn resource_flags(resource: &Resource<u8>) -> Vec<&'static str> {
+    [
+        ("python-module", resource.is_python_module),
+        (
+            "python-builtin-extension-module",
+            resource.is_python_builtin_extension_module,
+        ),
+        ("python-frozen-module", resource.is_python_frozen_module),
+        (
+            "python-extension-module",
+            resource.is_python_extension_module,
+        ),
+        ("shared-library", resource.is_shared_library),
+        ("utf8-filename-data", resource.is_utf8_filename_data),
+        ("python-package", resource.is_python_package),
+        (
+            "python-namespace-package",
+            resource.is_python_namespace_package,
+        ),
+        ("file-executable", resource.file_executable),
+        ("preload", resource.preload),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| if value { Some(name) } else { None })
+    .collect()
+}
+
+/// Resolve sizes of every piece of in-memory data in a resource.
+///
+/// Keys are field names. Entries of package and distribution resources are
+/// keyed as `<field>/<resource name>`.
+fn resource_data_sizes(resource: &Resource<u8>) -> BTreeMap<String, usize> {
+    let mut res = BTreeMap::new();
+
+    let mut add_value = |field: &str, value: &Option<Cow<[u8]>>| {
+        if let Some(data) = value {
+            res.insert(field.to_string(), data.len());
+        }
+    };
+
+    add_value("in_memory_source", &resource.in_memory_source);
+    add_value("in_memory_bytecode", &resource.in_memory_bytecode);
+    add_value("in_memory_bytecode_opt1", &resource.in_memory_bytecode_opt1);
+    add_value("in_memory_bytecode_opt2", &resource.in_memory_bytecode_opt2);
+    add_value(
+        "in_memory_extension_module_shared_library",
+        &resource.in_memory_extension_module_shared_library,
+    );
+    add_value(
+        "in_memory_shared_library",
+        &resource.in_memory_shared_library,
+    );
+    add_value("file_data_embedded", &resource.file_data_embedded);
+
+    let mut add_resources = |field: &str, value: &Option<HashMap<Cow<str>, Cow<[u8]>>>| {
+        if let Some(resources) = value {
+            for (name, data) in resources {
+                res.insert(format!("{}/{}", field, name), data.len());
+            }
+        }
+    };
+
+    add_resources(
+        "in_memory_package_resources",
+        &resource.in_memory_package_resources,
+    );
+    add_resources(
+        "in_memory_distribution_resources",
+        &resource.in_memory_distribution_resources,
+    );
+
+    res
+}
+
+/// Resolve filesystem paths referenced by a resource.
+///
+/// Keys have the same form as [resource_data_sizes()].
+fn resource_relative_paths(resource: &Resource<u8>) -> BTreeMap<String, String> {
+    let mut res = BTreeMap::new();
+
+    let mut add_value = |field: &str, value: &Option<Cow<Path>>| {
+        if let Some(path) = value {
+            res.insert(field.to_string(), path.display().to_string());
+        }
+    };
+
+    add_value(
+        "relative_path_module_source",
+        &resource.relative_path_module_source,
+    );
+    add_value(
+        "relative_path_module_bytecode",
+        &resource.relative_path_module_bytecode,
+    );
+    add_value(
+        "relative_path_module_bytecode_opt1",
+        &resource.relative_path_module_bytecode_opt1,
+    );
+    add_value(
+        "relative_path_module_bytecode_opt2",
+        &resource.relative_path_module_bytecode_opt2,
+    );
+    add_value(
+        "relative_path_extension_module_shared_library",
+        &resource.relative_path_extension_module_shared_library,
+    );
+
+    let mut add_resources = |field: &str, value: &Option<HashMap<Cow<str>, Cow<Path>>>| {
+        if let Some(resources) = value {
+            for (name, path) in resources {
+                res.insert(format!("{}/{}", field, name), path.display().to_string());
+            }
+        }
+    };
+
+    add_resources(
+        "relative_path_package_resources",
+        &resource.relative_path_package_resources,
+    );
+    add_resources(
+        "relative_path_distribution_resources",
+        &resource.relative_path_distribution_resources,
+    );
+
+    if let Some(path) = &resource.file_data_utf8_relative_path {
+        res.insert("file_data_utf8_relative_path".to_string(), path.to_string());
+    }
+
+    res
+}
+
+/// Print the names and flags of all resources in a packed resources file.
+pub fn list(path: &Path) -> Result<()> {
+    let data = read_file(path)?;
+
+    for resource in read_resources(&data)? {
+        println!(
+            "{} [{}]",
+            resource.name,
+            resource_flags(&resource).join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+/// Print details of a single resource in a packed resources file.
+pub fn show(path: &Path, name: &str) -> Result<()> {
+    let data = read_file(path)?;
+
+    let resource = read_resources(&data)?
+        .into_iter()
+        .find(|resource| resource.name == name)
+        .ok_or_else(|| anyhow!("resource {} not found in {}", name, path.display()))?;
+
+    println!("name: {}", resource.name);
+    println!("flags: {}", resource_flags(&resource).join(", "));
+
+    if let Some(names) = &resource.shared_library_dependency_names {
+        println!("shared_library_dependency_names: {}", names.join(", "));
+    }
+
+    for (field, size) in resource_data_sizes(&resource) {
+        println!("{}: {} bytes", field, size);
+    }
+
+    for (field, path) in resource_relative_paths(&resource) {
+        println!("{}: {}", field, path);
+    }
+
+    Ok(())
+}
+
+/// Resolve files to materialize for the in-memory data of a resource.
+fn resource_files(resource: &Resource<u8>) -> Result<FileManifest> {
+    let mut manifest = FileManifest::default();
+
+    let name = resource.name.as_ref();
+    let is_package = resource.is_python_package;
+
+    let module_path = |extension: &str| {
+        resolve_path_for_module("", name, is_package, None).with_extension(extension)
+    };
+    let package_path = || name.split('.').collect::<PathBuf>();
+
+    if let Some(data) = &resource.in_memory_source {
+        manifest.add_file_entry(module_path("py"), data.to_vec())?;
+    }
+
+    // Bytecode is stored as raw marshal data without a .pyc header. So we
+    // avoid the .pyc extension to not confuse Python.
+    for (value, extension) in [
+        (&resource.in_memory_bytecode, "bytecode"),
+        (&resource.in_memory_bytecode_opt1, "opt-1.bytecode"),
+        (&resource.in_memory_bytecode_opt2, "opt-2.bytecode"),
+    ] {
+        if let Some(data) = value {
+            manifest.add_file_entry(module_path(extension), data.to_vec())?;
+        }
+    }
+
+    if let Some(data) = &resource.in_memory_extension_module_shared_library {
+        let extension = if cfg!(windows) { "pyd" } else { "so" };
+
+        manifest.add_file_entry(
+            module_path(extension),
+            FileEntry::new_from_data(data.to_vec(), true),
+        )?;
+    }
+
+    if let Some(resources) = &resource.in_memory_package_resources {
+        for (resource_name, data) in resources {
+            manifest.add_file_entry(package_path().join(resource_name.as_ref()), data.to_vec())?;
+        }
+    }
+
+    // The package version isn't recorded in packed resources, so we can't
+    // reconstruct the original `<name>-<version>.dist-info` directory name.
+    if let Some(resources) = &resource.in_memory_distribution_resources {
+        for (resource_name, data) in resources {
+            manifest.add_file_entry(
+                PathBuf::from(format!("{}.dist-info", name)).join(resource_name.as_ref()),
+                data.to_vec(),
+            )?;
+        }
+    }
+
+    if let Some(data) = &resource.in_memory_shared_library {
+        manifest.add_file_entry(
+            PathBuf::from(name),
+            FileEntry::new_from_data(data.to_vec(), true),
+        )?;
+    }
+
+    if let Some(data) = &resource.file_data_embedded {
+        manifest.add_file_entry(
+            PathBuf::from(name),
+            FileEntry::new_from_data(data.to_vec(), resource.file_executable),
+        )?;
+    }
+
+    Ok(manifest)
+}
+
+/// Write the in-memory data of all resources in a packed resources file to a directory.
+pub fn extract(path: &Path, dest_path: &Path) -> Result<()> {
+    let data = read_file(path)?;
+
+    let mut manifest = FileManifest::default();
+
+    for resource in read_resources(&data)? {
+        manifest
+            .add_manifest(&resource_files(&resource)?)
+            .with_context(|| format!("resolving files for resource {}", resource.name))?;
+    }
+
+    for path in manifest.materialize_files(dest_path)? {
+        println!("wrote {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Describes how a resource differs between 2 packed resources files.
+#[derive(Clone, Debug, PartialEq)]
+enum ResourceDifference {
+    Added(usize),
+    Removed(usize),
+    Changed {
+        old_size: usize,
+        new_size: usize,
+        fields: Vec<(String, Option<usize>, Option<usize>)>,
+    },
+}
+
+fn diff_resources(
+    old: &[Resource<u8>],
+    new: &[Resource<u8>],
+) -> BTreeMap<String, ResourceDifference> {
+    let old = old
+        .iter()
+        .map(|resource| (resource.name.as_ref(), resource))
+        .collect::<HashMap<_, _>>();
+    let new = new
+        .iter()
+        .map(|resource| (resource.name.as_ref(), resource))
+        .collect::<HashMap<_, _>>();
+
+    let total_size = |resource: &Resource<u8>| resource_data_sizes(resource).values().sum();
+
+    let mut res = BTreeMap::new();
+
+    for (name, resource) in &old {
+        if !new.contains_key(name) {
+            res.insert(
+                name.to_string(),
+                ResourceDifference::Removed(total_size(resource)),
+            );
+        }
+    }
+
+    for (name, new_resource) in &new {
+        let old_resource = if let Some(resource) = old.get(name) {
+            resource
+        } else {
+            res.insert(
+                name.to_string(),
+                ResourceDifference::Added(total_size(new_resource)),
+            );
+            continue;
+        };
+
+        if old_resource == new_resource {
+            continue;
+        }
+
+        let old_sizes = resource_data_sizes(old_resource);
+        let new_sizes = resource_data_sizes(new_resource);
+
+        let mut fields = old_sizes
+            .keys()
+            .chain(new_sizes.keys())
+            .map(|field| {
+                (
+                    field.clone(),
+                    old_sizes.get(field).copied(),
+                    new_sizes.get(field).copied(),
+                )
+            })
+            .filter(|(_, old_size, new_size)| old_size != new_size)
+            .collect::<Vec<_>>();
+        fields.sort();
+        fields.dedup();
+
+        res.insert(
+            name.to_string(),
+            ResourceDifference::Changed {
+                old_size: old_sizes.values().sum(),
+                new_size: new_sizes.values().sum(),
+                fields,
+            },
+        );
+    }
+
+    res
+}
+
+fn format_delta(old_size: usize, new_size: usize) -> String {
+    if new_size >= old_size {
+        format!("+{}", new_size - old_size)
+    } else {
+        format!("-{}", old_size - new_size)
+    }
+}
+
+/// Print differences between resources in 2 packed resources files.
+pub fn diff(old_path: &Path, new_path: &Path) -> Result<()> {
+    let old_data = read_file(old_path)?;
+    let new_data = read_file(new_path)?;
+
+    let differences = diff_resources(&read_resources(&old_data)?, &read_resources(&new_data)?);
+
+    let mut total_old = 0;
+    let mut total_new = 0;
+
+    for (name, difference) in differences {
+        match difference {
+            ResourceDifference::Added(size) => {
+                println!("added {} ({} bytes)", name, size);
+                total_new += size;
+            }
+            ResourceDifference::Removed(size) => {
+                println!("removed {} ({} bytes)", name, size);
+                total_old += size;
+            }
+            ResourceDifference::Changed {
+                old_size,
+                new_size,
+                fields,
+            } => {
+                println!(
+                    "changed {} ({} bytes)",
+                    name,
+                    format_delta(old_size, new_size)
+                );
+                for (field, old_field, new_field) in fields {
+                    println!(
+                        "    {}: {} bytes",
+                        field,
+                        format_delta(old_field.unwrap_or(0), new_field.unwrap_or(0))
+                    );
+                }
+                total_old += old_size;
+                total_new += new_size;
+            }
+        }
+    }
+
+    println!("total: {} bytes", format_delta(total_old, total_new));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_resources() {
+        let foo = Resource {
+            name: Cow::from("foo"),
+            is_python_module: true,
+            in_memory_source: Some(Cow::from(b"import io".to_vec())),
+            ..Resource::default()
+        };
+        let bar = Resource {
+            name: Cow::from("bar"),
+            is_python_module: true,
+            in_memory_bytecode: Some(Cow::from(b"bytecode".to_vec())),
+            ..Resource::default()
+        };
+        let baz = Resource {
+            name: Cow::from("baz"),
+            is_shared_library: true,
+            in_memory_shared_library: Some(Cow::from(b"library".to_vec())),
+            ..Resource::default()
+        };
+
+        let mut foo_changed = foo.clone();
+        foo_changed.in_memory_source = Some(Cow::from(b"import os".to_vec()));
+        foo_changed.in_memory_bytecode = Some(Cow::from(b"abc".to_vec()));
+
+        let differences = diff_resources(
+            &[foo.clone(), bar.clone()],
+            &[foo_changed, bar.clone(), baz],
+        );
+
+        assert_eq!(differences.len(), 2);
+        assert_eq!(
+            differences.get("foo"),
+            Some(&ResourceDifference::Changed {
+                old_size: 9,
+                new_size: 12,
+                fields: vec![("in_memory_bytecode".to_string(), None, Some(3))],
+            })
+        );
+        assert_eq!(differences.get("baz"), Some(&ResourceDifference::Added(7)));
+
+        let differences = diff_resources(&[foo, bar], &[]);
+        assert_eq!(
+            differences.get("bar"),
+            Some(&ResourceDifference::Removed(8))
+        );
+    }
+
+    #[test]
+    fn test_resource_files() -> Result<()> {
+        let mut package_resources = HashMap::new();
+        package_resources.insert(Cow::from("data.txt"), Cow::from(b"data".to_vec()));
+
+        let resource = Resource {
+            name: Cow::from("foo.bar"),
+            is_python_module: true,
+            is_python_package: true,
+            in_memory_source: Some(Cow::from(b"source".to_vec())),
+            in_memory_bytecode_opt1: Some(Cow::from(b"bytecode".to_vec())),
+            in_memory_package_resources: Some(package_resources),
+            ..Resource::default()
+        };
+
+        let manifest = resource_files(&resource)?;
+        let paths = manifest
+            .iter_files()
+            .map(|f| f.path_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            paths,
+            vec![
+                "foo/bar/__init__.opt-1.bytecode",
+                "foo/bar/__init__.py",
+                "foo/bar/data.txt",
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -23,6 +23,7 @@ use {
     anyhow::{anyhow, Context, Result},
     linked_hash_map::LinkedHashMap,
     python_packaging::resource::PythonModuleSource,
+    python_packed_resources::CompressionCodec,
     slog::{info, warn},
     starlark::{
         environment::TypeValues,
@@ -61,6 +62,26 @@ use {
     tugger_wix::target_triple_to_wix_arch,
 };
 
+/// Obtain the Starlark name of a packed resources compression codec.
+fn compression_codec_name(codec: CompressionCodec) -> &'static str {
+    match codec {
+        CompressionCodec::Zstd => "zstd",
+        CompressionCodec::Lz4 => "lz4",
+    }
+}
+
+/// Resolve a packed resources compression codec from its Starlark name.
+fn compression_codec_from_name(name: &str) -> Result<CompressionCodec, String> {
+    match name {
+        "zstd" => Ok(CompressionCodec::Zstd),
+        "lz4" => Ok(CompressionCodec::Lz4),
+        _ => Err(format!(
+            "{} is not a valid compression codec; must be 'zstd' or 'lz4'",
+            name
+        )),
+    }
+}
+
 fn error_context<F, T>(label: &str, f: F) -> Result<T, ValueError>
 where
     F: FnOnce() -> anyhow::Result<T>,
@@ -179,6 +200,14 @@ impl TypedValue for PythonExecutableValue {
         let exe = self.inner(&format!("PythonExecutable.{}", attribute))?;
 
         match attribute {
+            "packed_resources_compression" => match exe.packed_resources_compression() {
+                Some(codec) => Ok(Value::from(compression_codec_name(codec))),
+                None => Ok(Value::from(NoneType::None)),
+            },
+            "packed_resources_import_profile" => match exe.packed_resources_import_profile() {
+                Some(value) => Ok(Value::from(format!("{}", value.display()))),
+                None => Ok(Value::from(NoneType::None)),
+            },
             "packed_resources_load_mode" => {
                 Ok(Value::from(exe.packed_resources_load_mode().to_string()))
             }
@@ -201,7 +230,9 @@ impl TypedValue for PythonExecutableValue {
     fn has_attr(&self, attribute: &str) -> Result<bool, ValueError> {
         Ok(matches!(
             attribute,
-            "packed_resources_load_mode"
+            "packed_resources_compression"
+                | "packed_resources_import_profile"
+                | "packed_resources_load_mode"
                 | "tcl_files_path"
                 | "windows_runtime_dlls_mode"
                 | "windows_subsystem"
@@ -212,6 +243,28 @@ impl TypedValue for PythonExecutableValue {
         let mut exe = self.inner(&format!("PythonExecutable.{}", attribute))?;
 
         match attribute {
+            "packed_resources_compression" => {
+                let name: Option<String> = value.to_optional();
+                let codec = match name {
+                    Some(name) => Some(compression_codec_from_name(&name).map_err(|e| {
+                        ValueError::from(RuntimeError {
+                            code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                            message: e,
+                            label: format!("{}.{}", Self::TYPE, attribute),
+                        })
+                    })?),
+                    None => None,
+                };
+
+                exe.set_packed_resources_compression(codec);
+
+                Ok(())
+            }
+            "packed_resources_import_profile" => {
+                exe.set_packed_resources_import_profile(value.to_optional());
+
+                Ok(())
+            }
             "packed_resources_load_mode" => {
                 exe.set_packed_resources_load_mode(
                     PackedResourcesLoadMode::try_from(value.to_string().as_str()).map_err(|e| {
@@ -1401,6 +1454,56 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_packed_resources_compression() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+
+        let value = env.eval("exe.packed_resources_compression")?;
+        assert_eq!(value.get_type(), "NoneType");
+
+        for codec in ["zstd", "lz4"] {
+            let value = env.eval(&format!(
+                "exe.packed_resources_compression = '{}'; exe.packed_resources_compression",
+                codec
+            ))?;
+            assert_eq!(value.get_type(), "string");
+            assert_eq!(value.to_string(), codec);
+        }
+
+        assert!(env
+            .eval("exe.packed_resources_compression = 'gzip'")
+            .is_err());
+
+        let value =
+            env.eval("exe.packed_resources_compression = None; exe.packed_resources_compression")?;
+        assert_eq!(value.get_type(), "NoneType");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_packed_resources_import_profile() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+
+        let value = env.eval("exe.packed_resources_import_profile")?;
+        assert_eq!(value.get_type(), "NoneType");
+
+        let value = env.eval(
+            "exe.packed_resources_import_profile = 'imports.json'; exe.packed_resources_import_profile",
+        )?;
+        assert_eq!(value.get_type(), "string");
+        assert_eq!(value.to_string(), "imports.json");
+
+        let value = env.eval(
+            "exe.packed_resources_import_profile = None; exe.packed_resources_import_profile",
+        )?;
+        assert_eq!(value.get_type(), "NoneType");
+
+        Ok(())
+    }
+
     #[test]
     fn test_windows_subsystem() -> Result<()> {
         let mut env = test_evaluation_context_builder()?.into_context()?;
@@ -7,8 +7,9 @@ use {
     crate::py_packaging::config::PyembedPythonInterpreterConfig,
     python_packaging::{
         interpreter::{
-            Allocator, BytesWarning, CheckHashPycsMode, CoerceCLocale, MemoryAllocatorBackend,
-            MultiprocessingStartMethod, PythonInterpreterProfile, TerminfoResolution,
+            Allocator, BytesWarning, CheckHashPycsMode, CoerceCLocale, CrashOutput,
+            ImportTraceFormat, MemoryAllocatorBackend, MultiprocessingStartMethod,
+            PythonInterpreterProfile, TerminfoResolution,
         },
         resource::BytecodeOptimizationLevel,
     },
@@ -47,6 +48,15 @@ impl ToValue for Option<CoerceCLocale> {
     }
 }
 
+impl ToValue for Option<CrashOutput> {
+    fn to_value(&self) -> Value {
+        match self {
+            Some(value) => Value::from(value.to_string()),
+            None => Value::from(NoneType::None),
+        }
+    }
+}
+
 impl ToValue for Option<BytesWarning> {
     fn to_value(&self) -> Value {
         match self {
@@ -217,6 +227,15 @@ impl TypedValue for PythonInterpreterConfigValue {
             "allocator_debug" => Value::from(inner.allocator_debug),
             "oxidized_importer" => Value::from(inner.oxidized_importer),
             "filesystem_importer" => Value::from(inner.filesystem_importer),
+            "packed_resources_signing_certificate_path" => {
+                inner.packed_resources_signing_certificate_path.to_value()
+            }
+            "packed_resources_signing_key_path" => {
+                inner.packed_resources_signing_key_path.to_value()
+            }
+            "packed_resources_trust_anchors" => inner.packed_resources_trust_anchors.to_value(),
+            "source_overlay_paths" => inner.source_overlay_paths.to_value(),
+            "source_overlay_paths_env" => inner.source_overlay_paths_env.to_value(),
             "argvb" => Value::from(inner.argvb),
             "multiprocessing_auto_dispatch" => Value::from(inner.multiprocessing_auto_dispatch),
             "multiprocessing_start_method" => {
@@ -226,6 +245,10 @@ impl TypedValue for PythonInterpreterConfigValue {
             "sys_meipass" => Value::from(inner.sys_meipass),
             "terminfo_resolution" => inner.terminfo_resolution.to_value(),
             "write_modules_directory_env" => inner.write_modules_directory_env.to_value(),
+            "write_import_trace_directory_env" => inner.write_import_trace_directory_env.to_value(),
+            "import_trace_format" => Value::from(inner.import_trace_format.to_string()),
+            "faulthandler_output" => inner.faulthandler_output.to_value(),
+            "crash_report_output" => inner.crash_report_output.to_value(),
             attr => {
                 return Err(ValueError::OperationNotSupported {
                     op: UnsupportedOperation::GetAttr(attr.to_string()),
@@ -304,6 +327,11 @@ impl TypedValue for PythonInterpreterConfigValue {
                 | "allocator_debug"
                 | "oxidized_importer"
                 | "filesystem_importer"
+                | "packed_resources_signing_certificate_path"
+                | "packed_resources_signing_key_path"
+                | "packed_resources_trust_anchors"
+                | "source_overlay_paths"
+                | "source_overlay_paths_env"
                 | "argvb"
                 | "multiprocessing_auto_dispatch"
                 | "multiprocessing_start_method"
@@ -311,6 +339,10 @@ impl TypedValue for PythonInterpreterConfigValue {
                 | "sys_meipass"
                 | "terminfo_resolution"
                 | "write_modules_directory_env"
+                | "write_import_trace_directory_env"
+                | "import_trace_format"
+                | "faulthandler_output"
+                | "crash_report_output"
         ))
     }
 
@@ -580,6 +612,21 @@ impl TypedValue for PythonInterpreterConfigValue {
             "filesystem_importer" => {
                 inner.filesystem_importer = value.to_bool();
             }
+            "packed_resources_signing_certificate_path" => {
+                inner.packed_resources_signing_certificate_path = value.to_optional();
+            }
+            "packed_resources_signing_key_path" => {
+                inner.packed_resources_signing_key_path = value.to_optional();
+            }
+            "packed_resources_trust_anchors" => {
+                inner.packed_resources_trust_anchors = value.try_to_optional()?;
+            }
+            "source_overlay_paths" => {
+                inner.source_overlay_paths = value.try_to_optional()?;
+            }
+            "source_overlay_paths_env" => {
+                inner.source_overlay_paths_env = value.to_optional();
+            }
             "argvb" => {
                 inner.argvb = value.to_bool();
             }
@@ -617,6 +664,40 @@ impl TypedValue for PythonInterpreterConfigValue {
             "write_modules_directory_env" => {
                 inner.write_modules_directory_env = value.to_optional();
             }
+            "write_import_trace_directory_env" => {
+                inner.write_import_trace_directory_env = value.to_optional();
+            }
+            "import_trace_format" => {
+                inner.import_trace_format = ImportTraceFormat::from_str(value.to_string().as_str())
+                    .map_err(|e| {
+                        ValueError::from(RuntimeError {
+                            code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                            message: e,
+                            label: format!("{}.{}", Self::TYPE, attribute),
+                        })
+                    })?;
+            }
+            "faulthandler_output" | "crash_report_output" => {
+                let output = if value.get_type() == "NoneType" {
+                    None
+                } else {
+                    Some(
+                        CrashOutput::try_from(value.to_string().as_str()).map_err(|e| {
+                            ValueError::from(RuntimeError {
+                                code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                                message: e,
+                                label: format!("{}.{}", Self::TYPE, attribute),
+                            })
+                        })?,
+                    )
+                };
+
+                if attribute == "faulthandler_output" {
+                    inner.faulthandler_output = output;
+                } else {
+                    inner.crash_report_output = output;
+                }
+            }
             attr => {
                 return Err(ValueError::OperationNotSupported {
                     op: UnsupportedOperation::SetAttr(attr.to_string()),
@@ -1344,6 +1425,54 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_packed_resources_signing() -> Result<()> {
+        let mut env = get_env()?;
+
+        eval_assert(
+            &mut env,
+            "config.packed_resources_signing_certificate_path == None",
+        )?;
+        eval_assert(&mut env, "config.packed_resources_signing_key_path == None")?;
+        eval_assert(&mut env, "config.packed_resources_trust_anchors == None")?;
+
+        env.eval("config.packed_resources_signing_certificate_path = 'cert.pem'")?;
+        eval_assert(
+            &mut env,
+            "config.packed_resources_signing_certificate_path == 'cert.pem'",
+        )?;
+
+        env.eval("config.packed_resources_signing_key_path = 'key.pem'")?;
+        eval_assert(
+            &mut env,
+            "config.packed_resources_signing_key_path == 'key.pem'",
+        )?;
+
+        env.eval("config.packed_resources_trust_anchors = ['ca.pem']")?;
+        eval_assert(
+            &mut env,
+            "config.packed_resources_trust_anchors == ['ca.pem']",
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_source_overlay_paths() -> Result<()> {
+        let mut env = get_env()?;
+
+        eval_assert(&mut env, "config.source_overlay_paths == None")?;
+        eval_assert(&mut env, "config.source_overlay_paths_env == None")?;
+
+        env.eval("config.source_overlay_paths = ['$ORIGIN/src']")?;
+        eval_assert(&mut env, "config.source_overlay_paths == ['$ORIGIN/src']")?;
+
+        env.eval("config.source_overlay_paths_env = 'APP_SOURCE'")?;
+        eval_assert(&mut env, "config.source_overlay_paths_env == 'APP_SOURCE'")?;
+
+        Ok(())
+    }
+
     #[test]
     fn test_sys_frozen() -> Result<()> {
         let mut env = get_env()?;
@@ -1385,4 +1514,50 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_import_trace() -> Result<()> {
+        let mut env = get_env()?;
+
+        eval_assert(&mut env, "config.write_import_trace_directory_env == None")?;
+        eval_assert(&mut env, "config.import_trace_format == 'json'")?;
+
+        env.eval("config.write_import_trace_directory_env = 'IMPORT_TRACE_DIR'")?;
+        eval_assert(
+            &mut env,
+            "config.write_import_trace_directory_env == 'IMPORT_TRACE_DIR'",
+        )?;
+
+        env.eval("config.import_trace_format = 'chrome-trace'")?;
+        eval_assert(&mut env, "config.import_trace_format == 'chrome-trace'")?;
+
+        assert!(env.eval("config.import_trace_format = 'xml'").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_crash_diagnostics() -> Result<()> {
+        let mut env = get_env()?;
+
+        eval_assert(&mut env, "config.faulthandler_output == None")?;
+        eval_assert(&mut env, "config.crash_report_output == None")?;
+
+        env.eval("config.faulthandler_output = 'stderr'")?;
+        eval_assert(&mut env, "config.faulthandler_output == 'stderr'")?;
+
+        env.eval("config.faulthandler_output = 'fd:3'")?;
+        eval_assert(&mut env, "config.faulthandler_output == 'fd:3'")?;
+
+        env.eval("config.crash_report_output = 'path:crash.log'")?;
+        eval_assert(&mut env, "config.crash_report_output == 'path:crash.log'")?;
+
+        env.eval("config.crash_report_output = None")?;
+        eval_assert(&mut env, "config.crash_report_output == None")?;
+
+        assert!(env.eval("config.faulthandler_output = 'fd:-1'").is_err());
+        assert!(env.eval("config.crash_report_output = 'syslog'").is_err());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,391 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*!
+Recording of import activity.
+
+When enabled, `OxidizedFinder` records an entry for every module it loads:
+which finder served it, where its code came from, the module that was
+executing when the import was triggered, and how long module creation, code
+resolution, and module execution took. This data can be emitted as JSON or in
+the Chrome trace event format to identify slow imports and unused modules.
+*/
+
+use {
+    pyo3::{prelude::*, types::PyDict},
+    python_packaging::interpreter::ImportTraceFormat,
+    std::{
+        collections::{HashMap, HashSet},
+        sync::{Mutex, MutexGuard},
+        thread::ThreadId,
+        time::{Duration, Instant},
+    },
+};
+
+/// A time interval relative to the start of tracing.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct TraceSpan {
+    start: Duration,
+    duration: Duration,
+}
+
+impl TraceSpan {
+    fn end(&self) -> Duration {
+        self.start + self.duration
+    }
+}
+
+/// Describes the loading of a single module.
+#[derive(Clone, Debug)]
+struct ImportRecord {
+    /// Name of the module.
+    name: String,
+    /// Name of the finder that served the module.
+    finder: &'static str,
+    /// Where the module's code came from.
+    location: &'static str,
+    /// Module being executed when this import was triggered.
+    parent: Option<String>,
+    /// Sequential identifier of the thread performing the import.
+    thread: u64,
+    /// Time spent in `create_module()`, if it did meaningful work.
+    create_module: Option<TraceSpan>,
+    /// Time spent obtaining a code object, if the module has one.
+    get_code: Option<TraceSpan>,
+    /// Time spent in `exec_module()`, including nested imports.
+    exec_module: TraceSpan,
+}
+
+impl ImportRecord {
+    /// Span covering all recorded activity for this module.
+    fn span(&self) -> TraceSpan {
+        let start = self
+            .create_module
+            .map(|span| span.start)
+            .unwrap_or(self.exec_module.start);
+
+        TraceSpan {
+            start,
+            duration: self.exec_module.end() - start,
+        }
+    }
+}
+
+#[derive(Default)]
+struct TracerState {
+    records: Vec<ImportRecord>,
+    /// Modules currently being executed, per thread.
+    stacks: HashMap<ThreadId, Vec<String>>,
+    thread_ids: HashMap<ThreadId, u64>,
+    /// `create_module()` spans awaiting their `exec_module()`.
+    pending_create_module: HashMap<String, TraceSpan>,
+}
+
+/// Records import activity.
+pub(crate) struct ImportTracer {
+    start: Instant,
+    state: Mutex<TracerState>,
+}
+
+impl ImportTracer {
+    pub(crate) fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            state: Mutex::new(TracerState::default()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, TracerState> {
+        self.state
+            .lock()
+            .expect("unable to lock import tracer state")
+    }
+
+    /// Obtain the span between an instant and now.
+    pub(crate) fn span_since(&self, start: Instant) -> TraceSpan {
+        TraceSpan {
+            start: start.duration_since(self.start),
+            duration: start.elapsed(),
+        }
+    }
+
+    /// Record time spent in `create_module()` for a module.
+    pub(crate) fn record_create_module(&self, name: &str, span: TraceSpan) {
+        self.lock()
+            .pending_create_module
+            .insert(name.to_string(), span);
+    }
+
+    /// Begin tracing execution of a module.
+    ///
+    /// The returned value records the import when dropped.
+    pub(crate) fn begin_exec_module(
+        &self,
+        name: &str,
+        finder: &'static str,
+        location: &'static str,
+    ) -> ExecModuleTrace<'_> {
+        let thread = std::thread::current().id();
+
+        let parent = {
+            let mut state = self.lock();
+            let stack = state.stacks.entry(thread).or_default();
+            let parent = stack.last().cloned();
+            stack.push(name.to_string());
+
+            parent
+        };
+
+        ExecModuleTrace {
+            tracer: self,
+            name: name.to_string(),
+            finder,
+            location,
+            parent,
+            thread,
+            start: Instant::now(),
+            get_code: None,
+        }
+    }
+
+    fn finish_exec_module(&self, trace: &ExecModuleTrace) {
+        let exec_module = self.span_since(trace.start);
+
+        let mut state = self.lock();
+
+        if let Some(stack) = state.stacks.get_mut(&trace.thread) {
+            stack.pop();
+        }
+
+        let next_thread_id = state.thread_ids.len() as u64;
+        let thread = *state
+            .thread_ids
+            .entry(trace.thread)
+            .or_insert(next_thread_id);
+
+        let create_module = state.pending_create_module.remove(&trace.name);
+
+        state.records.push(ImportRecord {
+            name: trace.name.clone(),
+            finder: trace.finder,
+            location: trace.location,
+            parent: trace.parent.clone(),
+            thread,
+            create_module,
+            get_code: trace.get_code,
+            exec_module,
+        });
+    }
+
+    /// Convert recorded imports to a list of dicts.
+    ///
+    /// Durations are expressed in microseconds since tracing started.
+    pub(crate) fn records_to_py<'p>(&self, py: Python<'p>) -> PyResult<Vec<&'p PyDict>> {
+        let span_micros =
+            |span: &Option<TraceSpan>| span.map(|span| span.duration.as_micros() as u64);
+
+        // Clone so the lock isn't held while calling into Python.
+        let records = self.lock().records.clone();
+
+        records
+            .iter()
+            .map(|record| -> PyResult<&PyDict> {
+                let d = PyDict::new(py);
+                d.set_item("name", &record.name)?;
+                d.set_item("finder", record.finder)?;
+                d.set_item("location", record.location)?;
+                d.set_item("parent", &record.parent)?;
+                d.set_item("thread", record.thread)?;
+                d.set_item("start_us", record.span().start.as_micros() as u64)?;
+                d.set_item("create_module_us", span_micros(&record.create_module))?;
+                d.set_item("get_code_us", span_micros(&record.get_code))?;
+                d.set_item(
+                    "exec_module_us",
+                    record.exec_module.duration.as_micros() as u64,
+                )?;
+
+                Ok(d)
+            })
+            .collect()
+    }
+
+    /// Convert recorded imports to Chrome trace events.
+    fn chrome_trace_events<'p>(&self, py: Python<'p>, pid: u32) -> PyResult<Vec<&'p PyDict>> {
+        let event = |name: &str, category: &str, thread: u64, span: TraceSpan| {
+            let d = PyDict::new(py);
+            d.set_item("name", name)?;
+            d.set_item("cat", category)?;
+            d.set_item("ph", "X")?;
+            d.set_item("ts", span.start.as_micros() as u64)?;
+            d.set_item("dur", span.duration.as_micros() as u64)?;
+            d.set_item("pid", pid)?;
+            d.set_item("tid", thread)?;
+
+            Ok::<_, PyErr>(d)
+        };
+
+        let mut events = vec![];
+
+        let records = self.lock().records.clone();
+
+        for record in records.iter() {
+            let import = event(&record.name, "import", record.thread, record.span())?;
+
+            let args = PyDict::new(py);
+            args.set_item("finder", record.finder)?;
+            args.set_item("location", record.location)?;
+            args.set_item("parent", &record.parent)?;
+            import.set_item("args", args)?;
+
+            events.push(import);
+
+            if let Some(span) = record.create_module {
+                events.push(event(
+                    "create_module",
+                    "create_module",
+                    record.thread,
+                    span,
+                )?);
+            }
+            if let Some(span) = record.get_code {
+                events.push(event("get_code", "get_code", record.thread, span)?);
+            }
+        }
+
+        Ok(events)
+    }
+
+    /// Describe modules in `sys.modules` that weren't recorded.
+    ///
+    /// These modules were served by other finders or loaded before tracing
+    /// started. So only the finder and location are known.
+    fn untraced_modules<'p>(&self, py: Python<'p>) -> PyResult<Vec<&'p PyDict>> {
+        let traced = self
+            .lock()
+            .records
+            .iter()
+            .map(|record| record.name.clone())
+            .collect::<HashSet<_>>();
+
+        let modules = py.import("sys")?.getattr("modules")?.cast_as::<PyDict>()?;
+
+        let mut res = vec![];
+
+        for (name, module) in modules.iter() {
+            let name = name.extract::<String>()?;
+            if traced.contains(&name) {
+                continue;
+            }
+
+            let spec = module
+                .getattr("__spec__")
+                .ok()
+                .filter(|spec| !spec.is_none());
+            let loader = spec.and_then(|spec| spec.getattr("loader").ok());
+
+            let d = PyDict::new(py);
+            d.set_item("name", name)?;
+            d.set_item(
+                "finder",
+                match loader {
+                    Some(loader) if !loader.is_none() => {
+                        Some(loader.get_type().name()?.to_string())
+                    }
+                    _ => None,
+                },
+            )?;
+            d.set_item(
+                "location",
+                match spec {
+                    Some(spec) => spec.getattr("origin")?,
+                    None => py.None().into_ref(py),
+                },
+            )?;
+            for key in [
+                "parent",
+                "thread",
+                "start_us",
+                "create_module_us",
+                "get_code_us",
+                "exec_module_us",
+            ] {
+                d.set_item(key, py.None())?;
+            }
+
+            res.push(d);
+        }
+
+        Ok(res)
+    }
+
+    /// Write recorded imports to a file.
+    ///
+    /// JSON output also describes modules in `sys.modules` that weren't
+    /// recorded, without timing information.
+    pub(crate) fn write(
+        &self,
+        py: Python,
+        path: &PyAny,
+        format: ImportTraceFormat,
+    ) -> PyResult<()> {
+        let json = py.import("json")?;
+
+        let document = PyDict::new(py);
+
+        match format {
+            ImportTraceFormat::Json => {
+                let mut records = self.records_to_py(py)?;
+                records.extend(self.untraced_modules(py)?);
+                document.set_item("imports", records)?;
+            }
+            ImportTraceFormat::ChromeTrace => {
+                document.set_item(
+                    "traceEvents",
+                    self.chrome_trace_events(py, std::process::id())?,
+                )?;
+                document.set_item("displayTimeUnit", "ms")?;
+            }
+        }
+
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("encoding", "utf-8")?;
+
+        let fh = py
+            .import("builtins")?
+            .getattr("open")?
+            .call((path, "w"), Some(kwargs))?;
+
+        let res = json.getattr("dump")?.call((document, fh), None);
+        fh.call_method0("close")?;
+
+        res.map(|_| ())
+    }
+}
+
+/// Tracks execution of a single module.
+///
+/// The import is recorded when this instance is dropped, even if execution fails.
+pub(crate) struct ExecModuleTrace<'a> {
+    tracer: &'a ImportTracer,
+    name: String,
+    finder: &'static str,
+    location: &'static str,
+    parent: Option<String>,
+    thread: ThreadId,
+    start: Instant,
+    get_code: Option<TraceSpan>,
+}
+
+impl<'a> ExecModuleTrace<'a> {
+    /// Record the time spent obtaining a code object for the module.
+    pub(crate) fn set_get_code(&mut self, start: Instant) {
+        self.get_code = Some(self.tracer.span_since(start));
+    }
+}
+
+impl<'a> Drop for ExecModuleTrace<'a> {
+    fn drop(&mut self) {
+        self.tracer.finish_exec_module(self);
+    }
+}
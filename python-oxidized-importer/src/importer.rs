@@ -19,12 +19,13 @@ use {
     crate::{
         conversion::pyobject_to_pathbuf,
         get_module_state,
+        import_trace::ImportTracer,
         path_entry_finder::OxidizedPathEntryFinder,
         pkg_resources::register_pkg_resources_with_module,
         python_resources::{
             pyobject_to_resource, ModuleFlavor, OxidizedResource, PythonResourcesState,
         },
-        resource_reader::OxidizedResourceReader,
+        resource_reader::{OxidizedResourceReader, ResourcesBackend},
         OXIDIZED_IMPORTER_NAME_STR,
     },
     pyo3::{
@@ -34,10 +35,24 @@ use {
         types::{PyBytes, PyDict, PyList, PyString, PyTuple},
         AsPyPointer, FromPyPointer, PyNativeType, PyTraverseError, PyVisit,
     },
-    python_packaging::resource::BytecodeOptimizationLevel,
-    std::sync::Arc,
+    python_packaging::{interpreter::ImportTraceFormat, resource::BytecodeOptimizationLevel},
+    std::{
+        collections::HashMap,
+        io::Read,
+        path::{Path, PathBuf},
+        str::FromStr,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+        time::Instant,
+    },
+    x509_certificate::CapturedX509Certificate,
 };
 
+#[cfg(feature = "zipimport")]
+use {crate::zip_import::OxidizedZipFinder, std::io::BufReader};
+
 #[cfg(windows)]
 #[allow(non_camel_case_types)]
 type py_init_fn = extern "C" fn() -> *mut pyffi::PyObject;
@@ -241,6 +256,74 @@ fn load_dynamic_library(
     }
 }
 
+/// A Python module resolved from a source overlay directory.
+#[derive(Clone, Debug)]
+pub(crate) struct SourceOverlayModule {
+    /// Name of the module.
+    name: String,
+    /// Filesystem path of the module's source file.
+    path: PathBuf,
+    /// Whether the module is a package.
+    is_package: bool,
+}
+
+impl SourceOverlayModule {
+    /// Read and decode the source code of this module.
+    fn resolve_source<'p>(&self, py: Python<'p>, state: &ImporterState) -> PyResult<&'p PyAny> {
+        let source = std::fs::read(&self.path).map_err(|e| {
+            PyImportError::new_err((
+                format!(
+                    "error reading module source from {}: {}",
+                    self.path.display(),
+                    e
+                ),
+                self.name.clone(),
+            ))
+        })?;
+
+        state.decode_source.as_ref(py).call(
+            (state.io_module.as_ref(py), PyBytes::new(py, &source)),
+            None,
+        )
+    }
+
+    /// Compile the source code of this module to a code object.
+    fn resolve_code<'p>(&self, py: Python<'p>, state: &ImporterState) -> PyResult<&'p PyAny> {
+        let source = self.resolve_source(py, state)?;
+
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("dont_inherit", true)?;
+
+        py.import("builtins")?
+            .getattr("compile")?
+            .call((source, &self.path, "exec"), Some(kwargs))
+    }
+
+    /// Resolve the `importlib.machinery.ModuleSpec` for this module.
+    fn resolve_module_spec<'p>(
+        &self,
+        py: Python,
+        module_spec_type: &'p PyAny,
+        loader: &PyAny,
+    ) -> PyResult<&'p PyAny> {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("is_package", self.is_package)?;
+        kwargs.set_item("origin", &self.path)?;
+
+        let spec = module_spec_type.call((self.name.as_str(), loader), Some(kwargs))?;
+        spec.setattr("has_location", true)?;
+
+        if self.is_package {
+            if let Some(parent) = self.path.parent() {
+                spec.getattr("submodule_search_locations")?
+                    .call_method1("append", (parent,))?;
+            }
+        }
+
+        Ok(spec)
+    }
+}
+
 /// Holds state for the custom MetaPathFinder.
 pub struct ImporterState {
     /// `imp` Python module.
@@ -271,6 +354,29 @@ pub struct ImporterState {
     pub(crate) multiprocessing_set_start_method: Option<String>,
     /// Whether to automatically register ourself with `pkg_resources` when it is imported.
     pub(crate) pkg_resources_import_auto_register: bool,
+    /// Directories to search for Python source files before indexed resources.
+    pub(crate) source_overlay_paths: Vec<PathBuf>,
+    /// Modules resolved against `source_overlay_paths`.
+    ///
+    /// `None` values record modules not present in any overlay directory. The
+    /// cache is cleared by `invalidate_caches()`.
+    source_overlay_cache: Mutex<HashMap<String, Option<SourceOverlayModule>>>,
+    /// Records import activity when import tracing is enabled.
+    pub(crate) import_tracer: Option<ImportTracer>,
+    /// Finders for archive files referenced by `sys.path` entries.
+    ///
+    /// Keyed by archive path, or by the path of a directory within a zip
+    /// archive. Populated by `OxidizedFinder.path_hook()`.
+    archive_finders: Mutex<HashMap<PathBuf, Py<PyAny>>>,
+    /// Certificates that packed resources files must be signed by.
+    ///
+    /// If non-empty, archive files referenced by `sys.path` entries must be
+    /// packed resources files with a valid signature.
+    packed_resources_trust_anchors: Vec<CapturedX509Certificate>,
+    /// Whether `OxidizedFinder.path_hook()` services zip archives.
+    ///
+    /// Off by default so zip archives on `sys.path` are left to `zipimport`.
+    path_hook_zip_archives: AtomicBool,
     /// Holds state about importable resources.
     ///
     /// This field is a PyCapsule and is a glorified wrapper around
@@ -389,6 +495,12 @@ impl ImporterState {
             multiprocessing_set_start_method: None,
             // TODO value should come from config.
             pkg_resources_import_auto_register: true,
+            source_overlay_paths: vec![],
+            source_overlay_cache: Mutex::new(HashMap::new()),
+            import_tracer: None,
+            archive_finders: Mutex::new(HashMap::new()),
+            packed_resources_trust_anchors: vec![],
+            path_hook_zip_archives: AtomicBool::new(false),
             resources_state: capsule,
         })
     }
@@ -411,6 +523,12 @@ impl ImporterState {
         visit.call(&self.exec_fn)?;
         visit.call(&self.resources_state)?;
 
+        if let Ok(finders) = self.archive_finders.try_lock() {
+            for finder in finders.values() {
+                visit.call(finder)?;
+            }
+        }
+
         Ok(())
     }
 
@@ -448,6 +566,133 @@ impl ImporterState {
     pub fn set_multiprocessing_set_start_method(&mut self, value: Option<String>) {
         self.multiprocessing_set_start_method = value;
     }
+
+    /// Set the directories to search for Python source files before indexed resources.
+    pub fn set_source_overlay_paths(&mut self, paths: Vec<PathBuf>) {
+        self.source_overlay_paths = paths;
+        self.invalidate_source_overlay_cache();
+    }
+
+    /// Set the certificates that archive files loaded by path hooks must be signed by.
+    pub fn set_packed_resources_trust_anchors(&mut self, anchors: Vec<CapturedX509Certificate>) {
+        self.packed_resources_trust_anchors = anchors;
+    }
+
+    /// Whether packed resources trust anchors are configured.
+    pub fn has_packed_resources_trust_anchors(&self) -> bool {
+        !self.packed_resources_trust_anchors.is_empty()
+    }
+
+    /// Set whether archive files referenced by `sys.path` entries may be zip archives.
+    pub fn set_path_hook_zip_archives(&self, enabled: bool) {
+        self.path_hook_zip_archives
+            .store(enabled, Ordering::Relaxed);
+    }
+
+    /// Page in the data of indexed resources flagged for preloading.
+    ///
+    /// Returns the number of resources whose data was requested.
+    pub fn preload_resources(&self) -> usize {
+        self.get_resources_state()
+            .preload_resources(self.optimize_level)
+    }
+
+    /// Enable or disable recording of import activity.
+    ///
+    /// Enabling discards any previously recorded activity.
+    pub fn set_import_tracing(&mut self, enabled: bool) {
+        self.import_tracer = if enabled {
+            Some(ImportTracer::new())
+        } else {
+            None
+        };
+    }
+
+    /// Forget all modules previously resolved from source overlay directories.
+    fn invalidate_source_overlay_cache(&self) {
+        self.source_overlay_cache
+            .lock()
+            .expect("unable to lock source overlay cache")
+            .clear();
+    }
+
+    /// Attempt to resolve a module from the source overlay directories.
+    ///
+    /// Directories are searched in order. Within a directory, a package
+    /// (`foo/bar/__init__.py`) takes precedence over a module (`foo/bar.py`).
+    pub(crate) fn resolve_source_overlay_module(&self, name: &str) -> Option<SourceOverlayModule> {
+        if self.source_overlay_paths.is_empty() {
+            return None;
+        }
+
+        let mut cache = self
+            .source_overlay_cache
+            .lock()
+            .expect("unable to lock source overlay cache");
+
+        if let Some(entry) = cache.get(name) {
+            return entry.clone();
+        }
+
+        let components = name.split('.').collect::<Vec<_>>();
+        let (leaf, parents) = components
+            .split_last()
+            .expect("split() should always yield an element");
+
+        let entry = self.source_overlay_paths.iter().find_map(|root| {
+            let parent = parents.iter().fold(root.clone(), |path, c| path.join(c));
+
+            let package_path = parent.join(leaf).join("__init__.py");
+            let module_path = parent.join(format!("{}.py", leaf));
+
+            if package_path.is_file() {
+                Some((package_path, true))
+            } else if module_path.is_file() {
+                Some((module_path, false))
+            } else {
+                None
+            }
+        });
+
+        let entry = entry.map(|(path, is_package)| SourceOverlayModule {
+            name: name.to_string(),
+            path,
+            is_package,
+        });
+
+        cache.insert(name.to_string(), entry.clone());
+
+        entry
+    }
+
+    /// Whether a filesystem path is located within a source overlay directory.
+    fn is_source_overlay_path(&self, path: &Path) -> bool {
+        self.source_overlay_paths
+            .iter()
+            .any(|root| path.starts_with(root))
+    }
+
+    /// Perform import time side-effects for special modules.
+    fn perform_import_side_effects(&self, py: Python, key: &str, module: &PyAny) -> PyResult<()> {
+        match key {
+            "multiprocessing" => {
+                if let Some(method) = self.multiprocessing_set_start_method.as_ref() {
+                    // We pass force=True to ensure the call doesn't fail.
+                    let kwargs = PyDict::new(py);
+                    kwargs.set_item("force", true)?;
+                    module.call_method("set_start_method", (method,), Some(kwargs))?;
+                }
+            }
+            "pkg_resources" => {
+                if self.pkg_resources_import_auto_register {
+                    register_pkg_resources_with_module(py, module)?;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
 }
 
 impl Drop for ImporterState {
@@ -524,6 +769,14 @@ impl OxidizedFinder {
         let py = slf.py();
         let finder = slf.borrow();
 
+        if let Some(module) = finder.state.resolve_source_overlay_module(&fullname) {
+            return module.resolve_module_spec(
+                py,
+                finder.state.module_spec_type.clone_ref(py).into_ref(py),
+                slf,
+            );
+        }
+
         let module = match finder
             .state
             .get_resources_state()
@@ -573,6 +826,8 @@ impl OxidizedFinder {
     }
 
     fn invalidate_caches(&self) -> PyResult<()> {
+        self.state.invalidate_source_overlay_cache();
+
         Ok(())
     }
 
@@ -588,6 +843,11 @@ impl OxidizedFinder {
         let name = spec.getattr("name")?;
         let key = name.extract::<String>()?;
 
+        // Source overlay modules use the default module creation semantics.
+        if state.resolve_source_overlay_module(&key).is_some() {
+            return Ok(py.None());
+        }
+
         let module = match state
             .get_resources_state()
             .resolve_importable_module(&key, state.optimize_level)
@@ -606,26 +866,35 @@ impl OxidizedFinder {
             // If we ever implement our own lazy module importer, we could
             // potentially work around this and move all extension module
             // initialization into `exec_module()`.
-            if let Some(library_data) = &module.in_memory_extension_module_shared_library() {
-                let sys_modules = state.sys_module.getattr(py, "modules")?;
-
-                extension_module_shared_library_create_module(
-                    state.get_resources_state(),
-                    py,
-                    sys_modules.into_ref(py),
-                    spec,
-                    name,
-                    &key,
-                    library_data,
-                )
-            } else {
-                // Call `imp.create_dynamic()` for dynamic extension modules.
-                let create_dynamic = state.imp_module.getattr(py, "create_dynamic")?;
-
-                state
-                    .call_with_frames_removed
-                    .call(py, (&create_dynamic, spec), None)
+            let start = Instant::now();
+
+            let res =
+                if let Some(library_data) = module.in_memory_extension_module_shared_library()? {
+                    let sys_modules = state.sys_module.getattr(py, "modules")?;
+
+                    extension_module_shared_library_create_module(
+                        state.get_resources_state(),
+                        py,
+                        sys_modules.into_ref(py),
+                        spec,
+                        name,
+                        &key,
+                        &library_data,
+                    )
+                } else {
+                    // Call `imp.create_dynamic()` for dynamic extension modules.
+                    let create_dynamic = state.imp_module.getattr(py, "create_dynamic")?;
+
+                    state
+                        .call_with_frames_removed
+                        .call(py, (&create_dynamic, spec), None)
+                };
+
+            if let Some(tracer) = &state.import_tracer {
+                tracer.record_create_module(&key, tracer.span_since(start));
             }
+
+            res
         } else {
             Ok(py.None())
         }
@@ -639,6 +908,29 @@ impl OxidizedFinder {
         let name = module.getattr("__name__")?;
         let key = name.extract::<String>()?;
 
+        if let Some(overlay) = state.resolve_source_overlay_module(&key) {
+            let mut trace = state
+                .import_tracer
+                .as_ref()
+                .map(|tracer| tracer.begin_exec_module(&key, "OxidizedFinder", "source-overlay"));
+
+            let start = Instant::now();
+            let code = overlay.resolve_code(py, state)?;
+            if let Some(trace) = trace.as_mut() {
+                trace.set_get_code(start);
+            }
+
+            let dict = module.getattr("__dict__")?;
+
+            state
+                .call_with_frames_removed
+                .call(py, (&state.exec_fn, code, dict), None)?;
+
+            state.perform_import_side_effects(py, &key, module)?;
+
+            return Ok(py.None());
+        }
+
         let mut entry = match state
             .get_resources_state()
             .resolve_importable_module(&key, state.optimize_level)
@@ -651,6 +943,18 @@ impl OxidizedFinder {
             }
         };
 
+        let mut trace = state.import_tracer.as_ref().map(|tracer| {
+            let finder = match entry.flavor {
+                ModuleFlavor::Builtin => "BuiltinImporter",
+                ModuleFlavor::Frozen => "FrozenImporter",
+                ModuleFlavor::Extension | ModuleFlavor::SourceBytecode => "OxidizedFinder",
+            };
+
+            tracer.begin_exec_module(&key, finder, entry.location(state.optimize_level))
+        });
+
+        let start = Instant::now();
+
         if let Some(bytecode) = entry.resolve_bytecode(
             py,
             state.optimize_level,
@@ -658,6 +962,10 @@ impl OxidizedFinder {
             state.io_module.as_ref(py),
         )? {
             let code = state.marshal_loads.call(py, (bytecode,), None)?;
+            if let Some(trace) = trace.as_mut() {
+                trace.set_get_code(start);
+            }
+
             let dict = module.getattr("__dict__")?;
 
             state
@@ -682,23 +990,7 @@ impl OxidizedFinder {
             Ok(py.None())
         }?;
 
-        // Perform import time side-effects for special modules.
-        match key.as_str() {
-            "multiprocessing" => {
-                if let Some(method) = state.multiprocessing_set_start_method.as_ref() {
-                    // We pass force=True to ensure the call doesn't fail.
-                    let kwargs = PyDict::new(py);
-                    kwargs.set_item("force", true)?;
-                    module.call_method("set_start_method", (method,), Some(kwargs))?;
-                }
-            }
-            "pkg_resources" => {
-                if state.pkg_resources_import_auto_register {
-                    register_pkg_resources_with_module(py, module)?;
-                }
-            }
-            _ => {}
-        }
+        state.perform_import_side_effects(py, &key, module)?;
 
         Ok(py.None())
     }
@@ -715,7 +1007,17 @@ impl OxidizedFinder {
     /// found. The path is expected to be constructed using a module’s __file__
     /// attribute or an item from a package’s __path__.
     fn get_data<'p>(slf: &'p PyCell<Self>, path: &str) -> PyResult<&'p PyAny> {
-        slf.borrow()
+        let finder = slf.borrow();
+
+        // Files in source overlay directories are read from the filesystem.
+        if finder.state.is_source_overlay_path(Path::new(path)) {
+            // io::Error converts to the appropriate OSError subclass.
+            let data = std::fs::read(path)?;
+
+            return Ok(PyBytes::new(slf.py(), &data));
+        }
+
+        finder
             .state
             .get_resources_state()
             .resolve_resource_data_from_path(slf.py(), path)
@@ -732,6 +1034,10 @@ impl OxidizedFinder {
 
         let key = fullname.to_string();
 
+        if let Some(overlay) = state.resolve_source_overlay_module(&key) {
+            return Ok(overlay.resolve_code(py, state)?.into_py(py));
+        }
+
         let mut module = match state
             .get_resources_state()
             .resolve_importable_module(&key, state.optimize_level)
@@ -762,6 +1068,10 @@ impl OxidizedFinder {
         let state = &finder.state;
         let key = fullname.to_string();
 
+        if let Some(overlay) = state.resolve_source_overlay_module(&key) {
+            return Ok(overlay.resolve_source(py, state)?.into_py(py));
+        }
+
         let module = match state
             .get_resources_state()
             .resolve_importable_module(&key, state.optimize_level)
@@ -799,6 +1109,10 @@ impl OxidizedFinder {
         let make_error =
             |msg: &str| -> PyErr { PyImportError::new_err((msg.to_owned(), key.clone())) };
 
+        if let Some(overlay) = state.resolve_source_overlay_module(&key) {
+            return Ok(overlay.path.into_py(slf.py()).into_ref(slf.py()));
+        }
+
         let module = state
             .get_resources_state()
             .resolve_importable_module(&key, state.optimize_level)
@@ -833,7 +1147,10 @@ impl OxidizedFinder {
         if entry.is_package {
             Ok(PyCell::new(
                 slf.py(),
-                OxidizedResourceReader::new(state.clone(), key.to_string()),
+                OxidizedResourceReader::new(
+                    ResourcesBackend::Importer(state.clone()),
+                    key.to_string(),
+                ),
             )?
             .into_py(slf.py()))
         } else {
@@ -881,8 +1198,13 @@ impl OxidizedFinder {
             (None, None)
         };
 
-        crate::package_metadata::find_distributions(py, state.clone(), name, path)?
-            .call_method0("__iter__")
+        crate::package_metadata::find_distributions(
+            py,
+            ResourcesBackend::Importer(state.clone()),
+            name,
+            path,
+        )?
+        .call_method0("__iter__")
     }
 
     // pkgutil methods.
@@ -902,10 +1224,21 @@ impl OxidizedFinder {
 
     // Additional methods provided for convenience.
 
-    /// OxidizedFinder.__new__(relative_path_origin=None))
+    /// OxidizedFinder.__new__(relative_path_origin=None, source_overlay_paths=None, trace_imports=False, path_hook_zip_archives=False))
     #[new]
-    #[args(relative_path_origin = "None")]
-    fn new(py: Python, relative_path_origin: Option<&PyAny>) -> PyResult<Self> {
+    #[args(
+        relative_path_origin = "None",
+        source_overlay_paths = "None",
+        trace_imports = "false",
+        path_hook_zip_archives = "false"
+    )]
+    fn new(
+        py: Python,
+        relative_path_origin: Option<&PyAny>,
+        source_overlay_paths: Option<&PyAny>,
+        trace_imports: bool,
+        path_hook_zip_archives: bool,
+    ) -> PyResult<Self> {
         // We need to obtain an ImporterState instance. This requires handles on a
         // few items...
 
@@ -921,13 +1254,22 @@ impl OxidizedFinder {
             resources_state.set_origin(pyobject_to_pathbuf(py, py_origin)?);
         }
 
+        let mut state = ImporterState::new(py, m, bootstrap_module, resources_state)?;
+
+        if let Some(paths) = source_overlay_paths {
+            state.set_source_overlay_paths(
+                paths
+                    .iter()?
+                    .map(|path| pyobject_to_pathbuf(py, path?))
+                    .collect::<PyResult<Vec<_>>>()?,
+            );
+        }
+
+        state.set_import_tracing(trace_imports);
+        state.set_path_hook_zip_archives(path_hook_zip_archives);
+
         Ok(OxidizedFinder {
-            state: Arc::new(ImporterState::new(
-                py,
-                m,
-                bootstrap_module,
-                resources_state,
-            )?),
+            state: Arc::new(state),
         })
     }
 
@@ -958,12 +1300,64 @@ impl OxidizedFinder {
             .into_ref(py)
     }
 
+    #[getter]
+    fn path_hook_zip_archives(&self) -> bool {
+        self.state.path_hook_zip_archives.load(Ordering::Relaxed)
+    }
+
+    #[setter]
+    fn set_path_hook_zip_archives(&self, value: bool) {
+        self.state.set_path_hook_zip_archives(value);
+    }
+
     #[getter]
     fn pkg_resources_import_auto_register(&self) -> PyResult<bool> {
         Ok(self.state.pkg_resources_import_auto_register)
     }
 
-    fn path_hook(slf: &PyCell<Self>, path: &PyAny) -> PyResult<OxidizedPathEntryFinder> {
+    #[getter]
+    fn trace_imports(&self) -> bool {
+        self.state.import_tracer.is_some()
+    }
+
+    /// OxidizedFinder.import_trace() -> List[dict]
+    fn import_trace<'p>(&self, py: Python<'p>) -> PyResult<&'p PyList> {
+        Ok(PyList::new(
+            py,
+            if let Some(tracer) = &self.state.import_tracer {
+                tracer.records_to_py(py)?
+            } else {
+                vec![]
+            },
+        ))
+    }
+
+    /// OxidizedFinder.write_import_trace(path, format="json")
+    #[args(format = "\"json\"")]
+    fn write_import_trace(&self, py: Python, path: &PyAny, format: &str) -> PyResult<()> {
+        let format = ImportTraceFormat::from_str(format).map_err(PyValueError::new_err)?;
+
+        let tracer = self
+            .state
+            .import_tracer
+            .as_ref()
+            .ok_or_else(|| PyValueError::new_err("import tracing is not enabled"))?;
+
+        tracer.write(py, path, format)
+    }
+
+    #[getter]
+    fn source_overlay_paths<'p>(&self, py: Python<'p>) -> PyResult<&'p PyList> {
+        Ok(PyList::new(
+            py,
+            self.state
+                .source_overlay_paths
+                .iter()
+                .map(|path| path.into_py(py)),
+        ))
+    }
+
+    fn path_hook(slf: &PyCell<Self>, path: &PyAny) -> PyResult<Py<PyAny>> {
         Self::path_hook_inner(slf, path).map_err(|inner| {
             let err = PyImportError::new_err("error running OxidizedFinder.path_hook");
 
@@ -1075,10 +1469,7 @@ impl OxidizedFinder {
 }
 
 impl OxidizedFinder {
-    fn path_hook_inner(
-        slf: &PyCell<Self>,
-        path_original: &PyAny,
-    ) -> PyResult<OxidizedPathEntryFinder> {
+    fn path_hook_inner(slf: &PyCell<Self>, path_original: &PyAny) -> PyResult<Py<PyAny>> {
         let py = slf.py();
         let finder = slf.borrow();
 
@@ -1086,6 +1477,8 @@ impl OxidizedFinder {
         //
         // * self.path_hook_base_str
         // * virtual sub-directories under self.path_hook_base_str
+        // * archive files and virtual sub-directories under them (see
+        //   archive_path_hook())
         //
         // There is a mismatch between the ways that Rust and Python store paths.
         // self.current_exe is a Rust PathBuf and came from Rust. We can get the raw
@@ -1116,11 +1509,14 @@ impl OxidizedFinder {
                 .call_method("startswith", (prefix,), None)?
                 .extract::<bool>()?
             {
-                return Err(PyValueError::new_err(format!(
-                    "{} is not prefixed by {}",
-                    path.to_string_lossy(),
-                    path_hook_base.to_string_lossy()
-                )));
+                return Self::archive_path_hook(slf, path).map_err(|e| {
+                    PyValueError::new_err(format!(
+                        "{} is not prefixed by {}; {}",
+                        path.to_string_lossy(),
+                        path_hook_base.to_string_lossy(),
+                        e.value(py)
+                    ))
+                });
             }
 
             // Ideally we'd strip the prefix in the domain of Python so we don't have
@@ -1184,18 +1580,233 @@ impl OxidizedFinder {
             }
         };
 
-        Ok(OxidizedPathEntryFinder {
-            finder: PyCell::new(
-                py,
-                OxidizedFinder {
-                    state: finder.state.clone(),
+        Ok(Py::new(
+            py,
+            OxidizedPathEntryFinder {
+                finder: PyCell::new(
+                    py,
+                    OxidizedFinder {
+                        state: finder.state.clone(),
+                    },
+                )?
+                .into(),
+                source_path: path.into_py(py),
+                target_package,
+            },
+        )?
+        .into_py(py))
+    }
+
+    /// Resolve a path entry finder for an archive file referenced by a path.
+    ///
+    /// The path must be an archive file or a virtual sub-directory under one.
+    /// Packed resources files are serviced by a new [OxidizedFinder] indexing
+    /// the file, with sub-directories mapping to packages. If zip archive
+    /// handling is enabled, zip archives (including wheels) are serviced by an
+    /// `OxidizedZipFinder` rooted at the sub-directory within the archive.
+    /// Finders are created on first use and cached on this instance.
+    ///
+    /// If packed resources trust anchors are configured, only packed resources
+    /// files with a valid signature are accepted.
+    fn archive_path_hook(slf: &PyCell<Self>, path: &PyString) -> PyResult<Py<PyAny>> {
+        let py = slf.py();
+        let finder = slf.borrow();
+
+        let path_buf = pyobject_to_pathbuf(py, path)?;
+
+        let archive_path = path_buf
+            .ancestors()
+            .find(|p| !p.as_os_str().is_empty() && p.is_file())
+            .ok_or_else(|| PyValueError::new_err("path does not refer to an archive file"))?;
+
+        let sub_path = path_buf
+            .strip_prefix(archive_path)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+        let is_packed_resources = is_packed_resources_file(archive_path);
+
+        // Leave zip archives to zipimport unless asked to handle them.
+        if !is_packed_resources && !finder.state.path_hook_zip_archives.load(Ordering::Relaxed) {
+            return Err(PyValueError::new_err(format!(
+                "{} is not a packed resources file and zip archive handling is disabled",
+                archive_path.display()
+            )));
+        }
+
+        let mut package_parts = vec![];
+        if is_packed_resources {
+            for component in sub_path.components() {
+                let part = component
+                    .as_os_str()
+                    .to_str()
+                    .ok_or_else(|| PyValueError::new_err("package part is not valid UTF-8"))?;
+
+                if part.starts_with('.') || part.ends_with('.') || part.contains("..") {
+                    return Err(PyValueError::new_err(format!(
+                        "rejecting virtual sub-directory because package part contains illegal dot characters: {}",
+                        part
+                    )));
+                }
+
+                package_parts.push(part);
+            }
+        }
+
+        // All paths within a packed resources file share one finder. Zip finders
+        // only see content under their prefix, so each sub-directory gets its own.
+        let (finder_key, prefix) = if is_packed_resources || sub_path.as_os_str().is_empty() {
+            (archive_path, None)
+        } else {
+            (path_buf.as_path(), Some(sub_path))
+        };
+
+        let archive_finder = finder
+            .state
+            .archive_finders
+            .lock()
+            .map_err(|_| PyValueError::new_err("unable to lock archive finders"))?
+            .get(finder_key)
+            .map(|f| f.clone_ref(py));
+
+        // Construct outside the lock because finder construction runs Python code.
+        let archive_finder = if let Some(f) = archive_finder {
+            f
+        } else {
+            let trust_anchors = &finder.state.packed_resources_trust_anchors;
+
+            let f = if is_packed_resources {
+                new_packed_resources_finder(py, archive_path, trust_anchors)?
+            } else {
+                new_zip_finder(py, archive_path, prefix, trust_anchors)?
+            };
+
+            finder
+                .state
+                .archive_finders
+                .lock()
+                .map_err(|_| PyValueError::new_err("unable to lock archive finders"))?
+                .entry(finder_key.to_path_buf())
+                .or_insert(f)
+                .clone_ref(py)
+        };
+
+        let archive_finder = archive_finder.into_ref(py);
+
+        // OxidizedZipFinder services any path within its prefix.
+        let archive_finder = match archive_finder.cast_as::<PyCell<OxidizedFinder>>() {
+            Ok(cell) => cell,
+            Err(_) => return Ok(archive_finder.into_py(py)),
+        };
+
+        Ok(Py::new(
+            py,
+            OxidizedPathEntryFinder {
+                finder: archive_finder.into(),
+                source_path: path.into_py(py),
+                target_package: if package_parts.is_empty() {
+                    None
+                } else {
+                    Some(package_parts.join("."))
                 },
-            )?
-            .into(),
-            source_path: path.into_py(py),
-            target_package,
-        })
+            },
+        )?
+        .into_py(py))
+    }
+}
+
+/// Whether a file begins with a packed resources header.
+fn is_packed_resources_file(path: &Path) -> bool {
+    let mut header = [0u8; 7];
+
+    std::fs::File::open(path)
+        .and_then(|mut fh| fh.read_exact(&mut header))
+        .map(|_| &header == b"pyembed")
+        .unwrap_or(false)
+}
+
+/// Construct an [OxidizedFinder] indexing a packed resources file.
+///
+/// If `trust_anchors` is non-empty, the file must have a valid signature in a
+/// sibling `.sig` file.
+fn new_packed_resources_finder(
+    py: Python,
+    path: &Path,
+    trust_anchors: &[CapturedX509Certificate],
+) -> PyResult<Py<PyAny>> {
+    let mut resources_state = Box::new(PythonResourcesState::default());
+    resources_state.set_current_exe(path.to_path_buf());
+    if let Some(parent) = path.parent() {
+        resources_state.set_origin(parent.to_path_buf());
+    }
+
+    if trust_anchors.is_empty() {
+        resources_state.index_path_memory_mapped(path)
+    } else {
+        let mut signature_path = path.as_os_str().to_os_string();
+        signature_path.push(".sig");
+
+        resources_state.index_path_verified(path, signature_path, trust_anchors)
     }
+    .map_err(|e| PyValueError::new_err(format!("error indexing {}: {}", path.display(), e)))?;
+
+    let m = py.import(OXIDIZED_IMPORTER_NAME_STR)?;
+    let bootstrap_module = py.import("_frozen_importlib")?;
+
+    let mut state = ImporterState::new(py, m, bootstrap_module, resources_state)?;
+    state.set_packed_resources_trust_anchors(trust_anchors.to_vec());
+
+    Ok(Py::new(
+        py,
+        OxidizedFinder {
+            state: Arc::new(state),
+        },
+    )?
+    .into_py(py))
+}
+
+/// Construct a meta path finder for a zip archive.
+///
+/// `prefix` is the directory within the archive to import from.
+///
+/// Zip archives can't be signed. So they are refused if `trust_anchors` is
+/// non-empty.
+#[allow(unused_variables)]
+fn new_zip_finder(
+    py: Python,
+    path: &Path,
+    prefix: Option<&Path>,
+    trust_anchors: &[CapturedX509Certificate],
+) -> PyResult<Py<PyAny>> {
+    if !trust_anchors.is_empty() {
+        return Err(PyValueError::new_err(format!(
+            "refusing to import from {}: packed resources trust anchors are configured and only signed packed resources files are allowed",
+            path.display()
+        )));
+    }
+
+    #[cfg(feature = "zipimport")]
+    {
+        let fh = std::fs::File::open(path).map_err(|e| {
+            PyValueError::new_err(format!("failed to open path {}: {}", path.display(), e))
+        })?;
+
+        Ok(Py::new(
+            py,
+            OxidizedZipFinder::new_from_reader(
+                py,
+                path.to_path_buf(),
+                Box::new(BufReader::new(fh)),
+                prefix,
+            )?,
+        )?
+        .into_py(py))
+    }
+
+    #[cfg(not(feature = "zipimport"))]
+    Err(PyValueError::new_err(format!(
+        "{} is not a packed resources file",
+        path.display()
+    )))
 }
 
 /// Path-like object facilitating Python resource access.
@@ -1380,12 +1991,40 @@ pub fn remove_external_importers(sys_module: &PyModule) -> PyResult<()> {
 ///
 /// `sys` must be a reference to the [`sys`] module.
 ///
+/// If the finder has packed resources trust anchors, `zipimport.zipimporter` is
+/// removed from [`sys.path_hooks`] so unsigned zip archives on `sys.path` can't
+/// be imported by falling through to it.
+///
 /// [`sys.path_hooks`]: https://docs.python.org/3/library/sys.html#sys.path_hooks
 /// [`sys`]: https://docs.python.org/3/library/sys.html
 pub fn install_path_hook(finder: &PyAny, sys: &PyModule) -> PyResult<()> {
     let hook = finder.getattr("path_hook")?;
     let path_hooks = sys.getattr("path_hooks")?;
-    path_hooks
-        .call_method("insert", (0, hook), None)
-        .map(|_| ())
+    let path_hooks = path_hooks.cast_as::<PyList>()?;
+
+    let has_trust_anchors = finder
+        .cast_as::<PyCell<OxidizedFinder>>()
+        .map(|cell| cell.borrow().state.has_packed_resources_trust_anchors())
+        .unwrap_or(false);
+
+    if has_trust_anchors {
+        if let Ok(zipimporter) = sys
+            .py()
+            .import("zipimport")
+            .and_then(|m| m.getattr("zipimporter"))
+        {
+            let mut index = 0;
+            while index < path_hooks.len() {
+                let entry = path_hooks.get_item(index as _)?;
+
+                if entry.eq(zipimporter)? {
+                    path_hooks.call_method1("pop", (index,))?;
+                } else {
+                    index += 1;
+                }
+            }
+        }
+    }
+
+    path_hooks.insert(0, hook)
 }
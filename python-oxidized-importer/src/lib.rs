@@ -5,6 +5,7 @@
 //! oxidized_importer Python extension.
 
 mod conversion;
+mod import_trace;
 #[allow(clippy::needless_option_as_deref)]
 mod importer;
 #[cfg(windows)]
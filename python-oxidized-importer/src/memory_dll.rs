@@ -157,8 +157,9 @@ extern "C" fn custom_load_library(filename: LPCSTR, user_data: *mut c_void) -> H
             .unwrap()
     };
 
-    if let Some(library_data) = resources_state.resolve_in_memory_shared_library_data(&name) {
-        let res = unsafe { load_library_memory(resources_state, library_data) };
+    // Library data that fails to resolve falls back to LoadLibraryA().
+    if let Ok(Some(library_data)) = resources_state.resolve_in_memory_shared_library_data(&name) {
+        let res = unsafe { load_library_memory(resources_state, &library_data) };
 
         // If we loaded a module, store its state. Otherwise return its failure (NULL).
         if !res.is_null() {
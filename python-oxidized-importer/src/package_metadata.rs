@@ -7,11 +7,12 @@ use {
         importer::ImporterState,
         pkg_resources::create_oxidized_pkg_resources_provider,
         python_resources::{name_at_package_hierarchy, name_within_package_hierarchy},
+        resource_reader::ResourcesBackend,
     },
     pyo3::{
         exceptions::{PyIOError, PyNotImplementedError, PyValueError},
         prelude::*,
-        types::{PyBytes, PyDict, PyList, PyString, PyTuple, PyType},
+        types::{PyDict, PyList, PyString, PyTuple, PyType},
     },
     std::{collections::BTreeMap, sync::Arc},
 };
@@ -37,13 +38,13 @@ fn discover_resolvers(py: Python) -> PyResult<&PyList> {
 /// A importlib.metadata.Distribution allowing access to package distribution data.
 #[pyclass(module = "oxidized_importer")]
 pub(crate) struct OxidizedDistribution {
-    state: Arc<ImporterState>,
+    backend: ResourcesBackend,
     package: String,
 }
 
 impl OxidizedDistribution {
-    pub(crate) fn new(state: Arc<ImporterState>, package: String) -> Self {
-        Self { state, package }
+    pub(crate) fn new(backend: ResourcesBackend, package: String) -> Self {
+        Self { backend, package }
     }
 }
 
@@ -122,11 +123,9 @@ impl OxidizedDistribution {
     }
 
     fn read_text<'p>(&self, py: Python<'p>, filename: String) -> PyResult<&'p PyAny> {
-        let resources_state = self.state.get_resources_state();
-
-        let data = resources_state
-            .resolve_package_distribution_resource(&self.package, &filename)
-            .map_err(|e| PyIOError::new_err(format!("error when resolving resource: {}", e)))?;
+        let data =
+            self.backend
+                .resolve_package_distribution_resource(py, &self.package, &filename)?;
 
         // Missing resource returns None.
         let data = if let Some(data) = data {
@@ -135,8 +134,6 @@ impl OxidizedDistribution {
             return Ok(py.None().into_ref(py));
         };
 
-        let data = PyBytes::new(py, &data);
-
         let io = py.import("io")?;
 
         let bytes_io = io.getattr("BytesIO")?.call((data,), None)?;
@@ -153,22 +150,18 @@ impl OxidizedDistribution {
     /// metadata.
     #[getter]
     fn metadata<'p>(&self, py: Python<'p>) -> PyResult<&'p PyAny> {
-        let resources_state = self.state.get_resources_state();
-
-        let data = resources_state
-            .resolve_package_distribution_resource(&self.package, "METADATA")
-            .map_err(|e| PyIOError::new_err(format!("error when resolving resource: {}", e)))?;
+        let data =
+            self.backend
+                .resolve_package_distribution_resource(py, &self.package, "METADATA")?;
 
         let data = if let Some(data) = data {
             data
         } else {
-            resources_state
-                .resolve_package_distribution_resource(&self.package, "PKG-INFO")
-                .map_err(|e| PyIOError::new_err(format!("error when resolving resource: {}", e)))?
+            self.backend
+                .resolve_package_distribution_resource(py, &self.package, "PKG-INFO")?
                 .ok_or_else(|| PyIOError::new_err("package metadata not found"))?
         };
 
-        let data = PyBytes::new(py, &data);
         let email = py.import("email")?;
 
         email.getattr("message_from_bytes")?.call((data,), None)
@@ -233,13 +226,12 @@ impl OxidizedDistribution {
 /// Find package metadata distributions given search criteria.
 pub(crate) fn find_distributions<'p>(
     py: Python<'p>,
-    state: Arc<ImporterState>,
+    backend: ResourcesBackend,
     name: Option<&PyAny>,
     _path: Option<&PyAny>,
 ) -> PyResult<&'p PyList> {
-    let distributions = state
-        .get_resources_state()
-        .package_distribution_names(|match_name| {
+    let distributions = backend
+        .package_distribution_names(py, |match_name| {
             if let Some(name) = name {
                 // Python normalizes the name. We do the same.
                 let name = name.to_string();
@@ -251,14 +243,9 @@ pub(crate) fn find_distributions<'p>(
             } else {
                 true
             }
-        })
+        })?
         .into_iter()
-        .map(|name| {
-            PyCell::new(
-                py,
-                OxidizedDistribution::new(state.clone(), name.to_string()),
-            )
-        })
+        .map(|name| PyCell::new(py, OxidizedDistribution::new(backend.clone(), name)))
         .collect::<Result<Vec<_>, _>>()?;
 
     Ok(PyList::new(py, &distributions))
@@ -293,7 +280,10 @@ pub(crate) fn find_pkg_resources_distributions<'p>(
         })
         .into_iter()
         .map(|name| {
-            let oxidized_distribution = OxidizedDistribution::new(state.clone(), name.to_string());
+            let oxidized_distribution = OxidizedDistribution::new(
+                ResourcesBackend::Importer(state.clone()),
+                name.to_string(),
+            );
 
             let metadata = oxidized_distribution.metadata(py)?;
 
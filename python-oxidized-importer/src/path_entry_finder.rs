@@ -14,6 +14,9 @@ use {
 /// A (mostly compliant) `importlib.abc.PathEntryFinder` that delegates paths
 /// within the current executable to the `OxidizedFinder` whose `path_hook`
 /// method created it.
+///
+/// Paths within a packed resources file are delegated to an `OxidizedFinder`
+/// indexing that file.
 #[pyclass(module = "oxidized_importer")]
 pub(crate) struct OxidizedPathEntryFinder {
     /// A clone of the meta path finder from which we came.
@@ -31,10 +31,61 @@ use {
         os::raw::c_int,
         path::{Path, PathBuf},
     },
+    x509_certificate::CapturedX509Certificate,
 };
 
 const ENOENT: c_int = 2;
 
+/// Ask the operating system to page in memory ranges.
+///
+/// Ranges are `(address, length)` tuples. Adjacent ranges are coalesced so
+/// contiguous data results in a single request.
+fn prefetch_memory(mut ranges: Vec<(usize, usize)>) {
+    #[cfg(unix)]
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(1) as usize;
+    #[cfg(not(unix))]
+    let page_size = 4096;
+
+    ranges.sort_unstable();
+
+    let mut coalesced: Vec<(usize, usize)> = vec![];
+
+    for (start, length) in ranges {
+        let end = start + length;
+
+        match coalesced.last_mut() {
+            Some((_, last_end)) if start <= *last_end + page_size => {
+                *last_end = (*last_end).max(end);
+            }
+            _ => coalesced.push((start, end)),
+        }
+    }
+
+    for (start, end) in coalesced {
+        let aligned_start = start - start % page_size;
+
+        #[cfg(unix)]
+        unsafe {
+            // This is only a hint. So errors are ignored.
+            libc::madvise(
+                aligned_start as *mut libc::c_void,
+                end - aligned_start,
+                libc::MADV_WILLNEED,
+            );
+        }
+
+        // Without a way to hint the kernel, fault in each page by reading it.
+        #[cfg(not(unix))]
+        for address in
+            std::iter::once(start).chain((aligned_start + page_size..end).step_by(page_size))
+        {
+            unsafe {
+                std::ptr::read_volatile(address as *const u8);
+            }
+        }
+    }
+}
+
 /// Determines whether an entry represents an importable Python module.
 ///
 /// Should only be called on module flavors.
@@ -139,7 +190,12 @@ impl<'a> ImportablePythonModule<'a, u8> {
         io_module: &PyAny,
     ) -> PyResult<Option<&'p PyAny>> {
         let bytes = if let Some(data) = &self.resource.in_memory_source {
-            Some(PyBytes::new(py, data))
+            let data = self
+                .resource
+                .resolve_data(data)
+                .map_err(PyImportError::new_err)?;
+
+            Some(PyBytes::new(py, &data))
         } else if let Some(relative_path) = &self.resource.relative_path_module_source {
             let path = self.origin.join(relative_path);
 
@@ -185,6 +241,17 @@ impl<'a> ImportablePythonModule<'a, u8> {
             BytecodeOptimizationLevel::One => &self.resource.in_memory_bytecode_opt1,
             BytecodeOptimizationLevel::Two => &self.resource.in_memory_bytecode_opt2,
         } {
+            // Compressed bytecode needs to be materialized in a new buffer. Otherwise
+            // we can reference the bytecode in memory without copying it.
+            if self.resource.data_compression.is_some() {
+                let data = self
+                    .resource
+                    .resolve_data(data)
+                    .map_err(PyImportError::new_err)?;
+
+                return Ok(Some(PyBytes::new(py, &data).into_py(py)));
+            }
+
             let ptr = unsafe {
                 pyffi::PyMemoryView_FromMemory(
                     data.as_ptr() as _,
@@ -380,8 +447,52 @@ impl<'a> ImportablePythonModule<'a, u8> {
             .map(|bytecode_path| self.origin.join(bytecode_path))
     }
 
-    pub fn in_memory_extension_module_shared_library(&self) -> &'a Option<Cow<'a, [u8]>> {
-        &self.resource.in_memory_extension_module_shared_library
+    /// Describe where the code for this module is loaded from.
+    ///
+    /// Follows the same order of preference as data resolution when importing.
+    pub fn location(&self, optimize_level: BytecodeOptimizationLevel) -> &'static str {
+        match self.flavor {
+            ModuleFlavor::Builtin => "builtin",
+            ModuleFlavor::Frozen => "frozen",
+            ModuleFlavor::Extension => {
+                if self
+                    .resource
+                    .in_memory_extension_module_shared_library
+                    .is_some()
+                {
+                    "memory"
+                } else {
+                    "relative-path"
+                }
+            }
+            ModuleFlavor::SourceBytecode => {
+                let in_memory_bytecode = match optimize_level {
+                    BytecodeOptimizationLevel::Zero => &self.resource.in_memory_bytecode,
+                    BytecodeOptimizationLevel::One => &self.resource.in_memory_bytecode_opt1,
+                    BytecodeOptimizationLevel::Two => &self.resource.in_memory_bytecode_opt2,
+                };
+
+                if in_memory_bytecode.is_some() {
+                    "memory"
+                } else if self.bytecode_path(optimize_level).is_some() {
+                    "relative-path"
+                } else if self.resource.in_memory_source.is_some() {
+                    "memory"
+                } else {
+                    "relative-path"
+                }
+            }
+        }
+    }
+
+    /// Resolve the data of an extension module shared library to import from memory.
+    pub fn in_memory_extension_module_shared_library(&self) -> PyResult<Option<Cow<'a, [u8]>>> {
+        self.resource
+            .in_memory_extension_module_shared_library
+            .as_ref()
+            .map(|data| self.resource.resolve_data(data))
+            .transpose()
+            .map_err(PyImportError::new_err)
     }
 }
 
@@ -427,6 +538,9 @@ where
 
     /// Holds memory mapped file instances that resources data came from.
     backing_mmaps: Vec<memmap2::Mmap>,
+
+    /// Holds file content read into memory that resources data came from.
+    backing_buffers: Vec<Vec<u8>>,
 }
 
 impl<'a> Default for PythonResourcesState<'a, u8> {
@@ -437,6 +551,7 @@ impl<'a> Default for PythonResourcesState<'a, u8> {
             resources: HashMap::new(),
             backing_py_objects: vec![],
             backing_mmaps: vec![],
+            backing_buffers: vec![],
         }
     }
 }
@@ -490,12 +605,52 @@ impl<'a> PythonResourcesState<'a, u8> {
         // allocations.
         self.resources.reserve(resources.expected_resources_count());
 
+        self.index_resources(resources)
+    }
+
+    /// Load resources by parsing a blob after verifying its authenticity.
+    ///
+    /// `signature` is a detached signature over `data` which must have been
+    /// produced by a certificate in or issued by `trust_anchors`. Every resource
+    /// must carry an integrity digest matching its data.
+    ///
+    /// Verification happens before any resources are indexed. So if an error
+    /// is returned, no resources from `data` have been loaded.
+    pub fn index_data_verified(
+        &mut self,
+        data: &'a [u8],
+        signature: &[u8],
+        trust_anchors: &[CapturedX509Certificate],
+    ) -> Result<(), &'static str> {
+        python_packed_resources::verify_packed_resources_signature(data, signature, trust_anchors)?;
+
+        let resources = python_packed_resources::load_resources_verified(data)?
+            .collect::<Result<Vec<_>, &'static str>>()?;
+
+        self.resources.reserve(resources.len());
+
+        self.index_resources(resources.into_iter().map(Ok))
+    }
+
+    fn index_resources(
+        &mut self,
+        resources: impl Iterator<Item = Result<Resource<'a, u8>, &'static str>>,
+    ) -> Result<(), &'static str> {
         for resource in resources {
             let resource = resource?;
 
             match self.resources.entry(resource.name.clone()) {
                 Entry::Occupied(existing) => {
-                    existing.into_mut().merge_from(resource)?;
+                    let existing = existing.into_mut();
+
+                    // Resources can only be merged if their data is compressed the same
+                    // way. So fall back to uncompressed data if needed.
+                    if existing.data_compression != resource.data_compression {
+                        *existing = existing.decompress()?;
+                        existing.merge_from(resource.decompress()?)?;
+                    } else {
+                        existing.merge_from(resource)?;
+                    }
                 }
                 Entry::Vacant(vacant) => {
                     vacant.insert(resource);
@@ -506,6 +661,84 @@ impl<'a> PythonResourcesState<'a, u8> {
         Ok(())
     }
 
+    /// Page in the data of resources flagged for preloading.
+    ///
+    /// Only borrowed in-memory data needed to import modules at the given
+    /// optimization level is considered. Writers place data for these resources
+    /// contiguously. So this typically results in a handful of requests to the
+    /// operating system, which can read ahead the data before it is needed.
+    ///
+    /// Returns the number of resources whose data was requested.
+    pub fn preload_resources(&self, optimize_level: BytecodeOptimizationLevel) -> usize {
+        let mut count = 0;
+        let mut ranges = vec![];
+
+        for resource in self.resources.values().filter(|resource| resource.preload) {
+            let bytecode = match optimize_level {
+                BytecodeOptimizationLevel::Zero => &resource.in_memory_bytecode,
+                BytecodeOptimizationLevel::One => &resource.in_memory_bytecode_opt1,
+                BytecodeOptimizationLevel::Two => &resource.in_memory_bytecode_opt2,
+            };
+
+            let code = if bytecode.is_some() {
+                bytecode
+            } else {
+                &resource.in_memory_source
+            };
+
+            let mut found = false;
+
+            for data in [code, &resource.in_memory_extension_module_shared_library]
+                .into_iter()
+                .flatten()
+            {
+                if let Cow::Borrowed(data) = data {
+                    ranges.push((data.as_ptr() as usize, data.len()));
+                    found = true;
+                }
+            }
+
+            if found {
+                count += 1;
+            }
+        }
+
+        prefetch_memory(ranges);
+
+        count
+    }
+
+    /// Obtain Python modules having in-memory bytecode.
+    ///
+    /// Yields the module name, its bytecode at the given optimization level, and
+    /// whether it is a package. The bytecode is raw `marshal` data, suitable for
+    /// registering in `PyImport_FrozenModules`. Compressed bytecode is
+    /// decompressed into an owned buffer.
+    pub fn in_memory_bytecode_modules(
+        &self,
+        optimize_level: BytecodeOptimizationLevel,
+    ) -> impl Iterator<Item = Result<(&str, Cow<[u8]>, bool), String>> {
+        self.resources.values().filter_map(move |resource| {
+            if !resource.is_python_module {
+                return None;
+            }
+
+            let bytecode = match optimize_level {
+                BytecodeOptimizationLevel::Zero => &resource.in_memory_bytecode,
+                BytecodeOptimizationLevel::One => &resource.in_memory_bytecode_opt1,
+                BytecodeOptimizationLevel::Two => &resource.in_memory_bytecode_opt2,
+            };
+
+            bytecode.as_ref().map(|data| {
+                let data = resource.resolve_data(data).map_err(|e| {
+                    format!("error resolving bytecode for {}: {}", resource.name, e)
+                })?;
+
+                Ok((resource.name.as_ref(), data, resource.is_python_package))
+            })
+        })
+    }
+
     /// Load resources data from a filesystem path using memory mapped I/O.
     pub fn index_path_memory_mapped(&mut self, path: impl AsRef<Path>) -> Result<(), String> {
         let path = path.as_ref();
@@ -521,6 +754,43 @@ impl<'a> PythonResourcesState<'a, u8> {
         Ok(())
     }
 
+    /// Load resources data from a filesystem path, verifying its authenticity.
+    ///
+    /// The detached signature over the data is read from `signature_path`. See
+    /// [Self::index_data_verified()] for verification semantics.
+    ///
+    /// Resources data is read lazily after indexing. So the file is read into
+    /// memory owned by this instance rather than memory mapped. Otherwise the
+    /// file could be modified after it was verified.
+    pub fn index_path_verified(
+        &mut self,
+        path: impl AsRef<Path>,
+        signature_path: impl AsRef<Path>,
+        trust_anchors: &[CapturedX509Certificate],
+    ) -> Result<(), String> {
+        let path = path.as_ref();
+        let signature_path = signature_path.as_ref();
+
+        let signature = std::fs::read(signature_path).map_err(|e| {
+            format!(
+                "error reading packed resources signature {}: {}",
+                signature_path.display(),
+                e
+            )
+        })?;
+
+        let buffer =
+            std::fs::read(path).map_err(|e| format!("error reading {}: {}", path.display(), e))?;
+
+        let data = unsafe { std::slice::from_raw_parts::<u8>(buffer.as_ptr(), buffer.len()) };
+
+        self.index_data_verified(data, &signature, trust_anchors)
+            .map_err(|e| format!("error verifying {}: {}", path.display(), e))?;
+        self.backing_buffers.push(buffer);
+
+        Ok(())
+    }
+
     /// Load resources from packed data stored in a PyObject.
     ///
     /// The `PyObject` must conform to the buffer protocol.
@@ -755,7 +1025,8 @@ impl<'a> PythonResourcesState<'a, u8> {
                 let io_module = py.import("io")?;
                 let bytes_io = io_module.getattr("BytesIO")?;
 
-                let data = PyBytes::new(py, data);
+                let data = entry.resolve_data(data).map_err(PyOSError::new_err)?;
+                let data = PyBytes::new(py, &data);
                 return Ok(Some(bytes_io.call((data,), None)?));
             }
         }
@@ -1012,7 +1283,9 @@ impl<'a> PythonResourcesState<'a, u8> {
                 if check_in_memory {
                     if let Some(resources) = &entry.in_memory_package_resources {
                         if let Some(data) = resources.get(resource_name_ref) {
-                            return Ok(PyBytes::new(py, data).into());
+                            let data = entry.resolve_data(data).map_err(PyOSError::new_err)?;
+
+                            return Ok(PyBytes::new(py, &data).into());
                         }
                     }
                 }
@@ -1115,7 +1388,7 @@ impl<'a> PythonResourcesState<'a, u8> {
         if let Some(entry) = self.resources.get(package) {
             if let Some(resources) = &entry.in_memory_distribution_resources {
                 if let Some(data) = resources.get(name) {
-                    return Ok(Some(Cow::Borrowed(data.as_ref())));
+                    return Ok(Some(entry.resolve_data(data).map_err(anyhow::Error::msg)?));
                 }
             }
 
@@ -1223,15 +1496,20 @@ impl<'a> PythonResourcesState<'a, u8> {
     }
 
     /// Resolve content of a shared library to load from memory.
-    pub fn resolve_in_memory_shared_library_data(&self, name: &str) -> Option<&[u8]> {
+    ///
+    /// Errors if the library data is compressed and can't be decompressed.
+    pub fn resolve_in_memory_shared_library_data(
+        &self,
+        name: &str,
+    ) -> Result<Option<Cow<'_, [u8]>>, &'static str> {
         if let Some(entry) = &self.resources.get(name) {
             if let Some(library_data) = &entry.in_memory_shared_library {
-                Some(library_data.as_ref())
+                Ok(Some(entry.resolve_data(library_data)?))
             } else {
-                None
+                Ok(None)
             }
         } else {
-            None
+            Ok(None)
         }
     }
 
@@ -1272,7 +1550,22 @@ impl<'a> PythonResourcesState<'a, u8> {
 
         let mut buffer = Vec::new();
 
-        python_packed_resources::write_packed_resources_v3(&resources, &mut buffer, None)?;
+        // Version 4 is only required to preserve compressed resource data and
+        // preload flags. Prefer version 3 so older readers can consume the data.
+        if resources
+            .iter()
+            .any(|resource| resource.data_compression.is_some() || resource.preload)
+        {
+            python_packed_resources::write_packed_resources_v4(
+                &resources,
+                &mut buffer,
+                None,
+                None,
+                false,
+            )?;
+        } else {
+            python_packed_resources::write_packed_resources_v3(&resources, &mut buffer, None)?;
+        }
 
         Ok(buffer)
     }
@@ -1762,6 +2055,9 @@ pub(crate) fn resource_to_pyobject<'p>(
     py: Python<'p>,
     resource: &Resource<u8>,
 ) -> PyResult<&'p PyCell<OxidizedResource>> {
+    // Python code interacting with resources expects data to be usable as-is.
+    let resource = resource.decompress().map_err(PyValueError::new_err)?;
+
     PyCell::new(
         py,
         OxidizedResource {
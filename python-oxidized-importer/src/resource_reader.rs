@@ -2,24 +2,175 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+#[cfg(feature = "zipimport")]
+use crate::zip_import::OxidizedZipFinder;
 use {
     crate::importer::ImporterState,
-    pyo3::{exceptions::PyFileNotFoundError, prelude::*},
+    pyo3::{
+        exceptions::{PyFileNotFoundError, PyIOError},
+        prelude::*,
+        types::PyBytes,
+    },
     std::sync::Arc,
 };
 
+/// Backing store for package resources and package distribution metadata.
+#[derive(Clone)]
+pub(crate) enum ResourcesBackend {
+    /// Resources indexed by the state of an `OxidizedFinder`.
+    Importer(Arc<ImporterState>),
+
+    /// Files within a zip archive indexed by an `OxidizedZipFinder`.
+    ///
+    /// Package distributions are keyed by the name of their `.dist-info` or
+    /// `.egg-info` directory.
+    #[cfg(feature = "zipimport")]
+    Zip(Py<OxidizedZipFinder>),
+}
+
+impl ResourcesBackend {
+    /// Obtain a file-like object for reading a named resource in a package.
+    fn get_package_resource_file<'p>(
+        &self,
+        py: Python<'p>,
+        package: &str,
+        resource_name: &str,
+    ) -> PyResult<Option<&'p PyAny>> {
+        match self {
+            Self::Importer(state) => {
+                state
+                    .get_resources_state()
+                    .get_package_resource_file(py, package, resource_name)
+            }
+            #[cfg(feature = "zipimport")]
+            Self::Zip(finder) => {
+                let data = finder
+                    .as_ref(py)
+                    .try_borrow_mut()?
+                    .index
+                    .resolve_package_resource(package, resource_name)
+                    .map_err(|e| PyIOError::new_err(format!("error reading resource: {}", e)))?;
+
+                if let Some(data) = data {
+                    let io_module = py.import("io")?;
+                    let bytes_io = io_module.getattr("BytesIO")?;
+
+                    Ok(Some(bytes_io.call((PyBytes::new(py, &data),), None)?))
+                } else {
+                    Ok(None)
+                }
+            }
+        }
+    }
+
+    /// Whether a package + name pair is a known package resource.
+    #[cfg_attr(not(feature = "zipimport"), allow(unused_variables))]
+    fn is_package_resource(
+        &self,
+        py: Python,
+        package: &str,
+        resource_name: &str,
+    ) -> PyResult<bool> {
+        match self {
+            Self::Importer(state) => Ok(state
+                .get_resources_state()
+                .is_package_resource(package, resource_name)),
+            #[cfg(feature = "zipimport")]
+            Self::Zip(finder) => Ok(finder
+                .as_ref(py)
+                .try_borrow()?
+                .index
+                .is_package_resource(package, resource_name)),
+        }
+    }
+
+    /// Obtain the sorted names of resources in a package, as a Python list.
+    fn package_resource_names<'p>(&self, py: Python<'p>, package: &str) -> PyResult<&'p PyAny> {
+        match self {
+            Self::Importer(state) => state
+                .get_resources_state()
+                .package_resource_names(py, package),
+            #[cfg(feature = "zipimport")]
+            Self::Zip(finder) => {
+                let names = finder
+                    .as_ref(py)
+                    .try_borrow()?
+                    .index
+                    .package_resource_names(package);
+
+                Ok(pyo3::types::PyList::new(py, &names).into())
+            }
+        }
+    }
+
+    /// Resolve the names of package distributions whose name matches a filter.
+    #[cfg_attr(not(feature = "zipimport"), allow(unused_variables))]
+    pub(crate) fn package_distribution_names(
+        &self,
+        py: Python,
+        filter: impl Fn(&str) -> bool,
+    ) -> PyResult<Vec<String>> {
+        match self {
+            Self::Importer(state) => Ok(state
+                .get_resources_state()
+                .package_distribution_names(filter)
+                .into_iter()
+                .map(|name| name.to_string())
+                .collect()),
+            #[cfg(feature = "zipimport")]
+            Self::Zip(finder) => Ok(finder
+                .as_ref(py)
+                .try_borrow()?
+                .index
+                .distribution_directories()
+                .into_iter()
+                .filter(|directory| filter(crate::zip_import::distribution_name(directory)))
+                .collect()),
+        }
+    }
+
+    /// Resolve data belonging to a package distribution resource.
+    ///
+    /// `Ok(None)` is returned if the resource does not exist.
+    pub(crate) fn resolve_package_distribution_resource<'p>(
+        &self,
+        py: Python<'p>,
+        package: &str,
+        name: &str,
+    ) -> PyResult<Option<&'p PyBytes>> {
+        let map_error =
+            |e: anyhow::Error| PyIOError::new_err(format!("error when resolving resource: {}", e));
+
+        match self {
+            Self::Importer(state) => Ok(state
+                .get_resources_state()
+                .resolve_package_distribution_resource(package, name)
+                .map_err(map_error)?
+                .map(|data| PyBytes::new(py, &data))),
+            #[cfg(feature = "zipimport")]
+            Self::Zip(finder) => Ok(finder
+                .as_ref(py)
+                .try_borrow_mut()?
+                .index
+                .resolve_distribution_resource(package, name)
+                .map_err(map_error)?
+                .map(|data| PyBytes::new(py, &data))),
+        }
+    }
+}
+
 /// Implements in-memory reading of resource data.
 ///
 /// Implements importlib.abc.ResourceReader.
 #[pyclass(module = "oxidized_importer")]
 pub(crate) struct OxidizedResourceReader {
-    state: Arc<ImporterState>,
+    backend: ResourcesBackend,
     package: String,
 }
 
 impl OxidizedResourceReader {
-    pub(crate) fn new(state: Arc<ImporterState>, package: String) -> Self {
-        Self { state, package }
+    pub(crate) fn new(backend: ResourcesBackend, package: String) -> Self {
+        Self { backend, package }
     }
 }
 
@@ -29,11 +180,10 @@ impl OxidizedResourceReader {
     ///
     /// If the resource cannot be found, FileNotFoundError is raised.
     fn open_resource<'p>(&self, py: Python<'p>, resource: &str) -> PyResult<&'p PyAny> {
-        if let Some(file) = self.state.get_resources_state().get_package_resource_file(
-            py,
-            &self.package,
-            resource,
-        )? {
+        if let Some(file) = self
+            .backend
+            .get_package_resource_file(py, &self.package, resource)?
+        {
             Ok(file)
         } else {
             Err(PyFileNotFoundError::new_err("resource not found"))
@@ -53,12 +203,8 @@ impl OxidizedResourceReader {
 
     /// Returns True if the named name is considered a resource. FileNotFoundError
     /// is raised if name does not exist.
-    fn is_resource(&self, name: &str) -> PyResult<bool> {
-        if self
-            .state
-            .get_resources_state()
-            .is_package_resource(&self.package, name)
-        {
+    fn is_resource(&self, py: Python, name: &str) -> PyResult<bool> {
+        if self.backend.is_package_resource(py, &self.package, name)? {
             Ok(true)
         } else {
             Err(PyFileNotFoundError::new_err("resource not found"))
@@ -76,8 +222,6 @@ impl OxidizedResourceReader {
     /// package and resources are stored on the file system then those subdirectory names can be
     /// used directly.
     fn contents<'p>(&self, py: Python<'p>) -> PyResult<&'p PyAny> {
-        self.state
-            .get_resources_state()
-            .package_resource_names(py, &self.package)
+        self.backend.package_resource_names(py, &self.package)
     }
 }
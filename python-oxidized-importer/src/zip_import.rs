@@ -5,18 +5,23 @@
 /*! Support for importing from zip archives. */
 
 use {
-    crate::{conversion::pyobject_to_pathbuf, decode_source},
+    crate::{
+        conversion::pyobject_to_pathbuf,
+        decode_source,
+        package_metadata::find_distributions,
+        resource_reader::{OxidizedResourceReader, ResourcesBackend},
+    },
     anyhow::{anyhow, Result},
     pyo3::{
         buffer::PyBuffer,
-        exceptions::{PyImportError, PyValueError},
+        exceptions::{PyImportError, PyOSError, PyValueError},
         ffi as pyffi,
         prelude::*,
-        types::{PyBytes, PyDict, PyType},
+        types::{PyBytes, PyDict, PyList, PyTuple, PyType},
         PyNativeType, PyTraverseError, PyVisit,
     },
     std::{
-        collections::HashMap,
+        collections::{BTreeMap, BTreeSet, HashMap},
         io::{BufReader, Cursor, Read, Seek},
         path::{Path, PathBuf},
     },
@@ -35,6 +40,18 @@ pub struct ZipPythonModule {
     pub bytecode_path: Option<PathBuf>,
 }
 
+/// Resolve the name of a package distribution from its metadata directory name.
+///
+/// e.g. `foo_bar-1.0.dist-info` resolves to `foo_bar`.
+pub(crate) fn distribution_name(directory: &str) -> &str {
+    let stem = directory
+        .strip_suffix(".dist-info")
+        .or_else(|| directory.strip_suffix(".egg-info"))
+        .unwrap_or(directory);
+
+    stem.split('-').next().unwrap_or(stem)
+}
+
 /// Read Python resources from a zip file.
 ///
 /// Instances are bound to a handle on a zip archive. At open time, the zip
@@ -52,6 +69,12 @@ impl<R: Read + Seek> ZipIndex<R> {
     ///
     /// The path prefix denotes the path within the zip file to look for content.
     /// All paths outside this prefix are ignored.
+    ///
+    /// Without a prefix, the index represents the root of the archive and
+    /// modules are resolved by their full dotted name. With a prefix, the index
+    /// represents a directory on `sys.path` or in a package's `__path__`. Like
+    /// `zipimport`, modules are then resolved by the last component of their
+    /// name.
     pub fn new(reader: R, prefix: Option<&Path>) -> Result<Self> {
         let prefix = prefix.map(|p| p.to_path_buf());
 
@@ -62,6 +85,11 @@ impl<R: Read + Seek> ZipIndex<R> {
         for index in 0..archive.len() {
             let zf = archive.by_index_raw(index)?;
 
+            // Directories are implied by the paths of files within them.
+            if zf.is_dir() {
+                continue;
+            }
+
             // Ignore entries that don't have a valid filename within the archive.
             if let Some(name) = zf.enclosed_name() {
                 // If told to look at paths within a subdirectory of the zip, ignore
@@ -87,12 +115,20 @@ impl<R: Read + Seek> ZipIndex<R> {
         })
     }
 
+    /// Resolve the path of a module relative to the prefix, without extension.
+    fn module_path(&self, full_name: &str) -> PathBuf {
+        if self.prefix.is_some() {
+            PathBuf::from(full_name.rsplit('.').next().unwrap_or(full_name))
+        } else {
+            full_name.split('.').collect()
+        }
+    }
+
     /// Attempt to locate a Python module within the zip archive.
     ///
     /// `full_name` is the fully qualified / dotted Python module name.
     pub fn find_python_module(&mut self, full_name: &str) -> Option<ZipPythonModule> {
-        let mut common_path = self.prefix.clone().unwrap_or_default();
-        common_path.extend(full_name.split('.'));
+        let common_path = self.module_path(full_name);
 
         let package_py_path = common_path.join("__init__").with_extension("py");
         let package_pyc_path = common_path.join("__init__").with_extension("pyc");
@@ -156,6 +192,121 @@ impl<R: Read + Seek> ZipIndex<R> {
 
         Ok(buffer)
     }
+
+    /// Resolve the top-level Python modules in the archive.
+    ///
+    /// Returns a mapping of module name to whether it is a package.
+    pub fn top_level_modules(&self) -> BTreeMap<String, bool> {
+        let mut res = BTreeMap::new();
+
+        for path in self.members.keys() {
+            let components = path.iter().map(|c| c.to_string_lossy()).collect::<Vec<_>>();
+
+            match components.as_slice() {
+                [filename] => {
+                    if let Some(name) = filename
+                        .strip_suffix(".py")
+                        .or_else(|| filename.strip_suffix(".pyc"))
+                    {
+                        if name != "__init__" && !name.contains('.') {
+                            res.entry(name.to_string()).or_insert(false);
+                        }
+                    }
+                }
+                [package, filename] if filename == "__init__.py" || filename == "__init__.pyc" => {
+                    res.insert(package.to_string(), true);
+                }
+                _ => {}
+            }
+        }
+
+        res
+    }
+
+    /// Obtain the names of files and directories directly within a package's directory.
+    ///
+    /// Names are sorted.
+    pub fn package_resource_names(&self, package: &str) -> Vec<String> {
+        let package_path = self.module_path(package);
+
+        self.members
+            .keys()
+            .filter_map(|path| path.strip_prefix(&package_path).ok())
+            .filter_map(|path| path.iter().next())
+            .map(|name| name.to_string_lossy().to_string())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Resolve the path of a file directly within a package's directory.
+    ///
+    /// Returns `None` if `name` has directory components.
+    fn package_resource_path(&self, package: &str, name: &str) -> Option<PathBuf> {
+        if name.contains('/') || name.contains('\\') {
+            return None;
+        }
+
+        let mut path = self.module_path(package);
+        path.push(name);
+
+        Some(path)
+    }
+
+    /// Whether a file exists directly within a package's directory.
+    pub fn is_package_resource(&self, package: &str, name: &str) -> bool {
+        self.package_resource_path(package, name)
+            .map(|path| self.members.contains_key(&path))
+            .unwrap_or(false)
+    }
+
+    /// Resolve the content of a file directly within a package's directory.
+    ///
+    /// `Ok(None)` is returned if the file does not exist.
+    pub fn resolve_package_resource(
+        &mut self,
+        package: &str,
+        name: &str,
+    ) -> Result<Option<Vec<u8>>> {
+        match self.package_resource_path(package, name) {
+            Some(path) if self.members.contains_key(&path) => {
+                Ok(Some(self.resolve_path_content(&path)?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Obtain the names of top-level package distribution metadata directories.
+    ///
+    /// These are `.dist-info` and `.egg-info` directories. Names are sorted.
+    pub fn distribution_directories(&self) -> Vec<String> {
+        self.members
+            .keys()
+            .filter(|path| path.iter().count() > 1)
+            .filter_map(|path| path.iter().next())
+            .map(|name| name.to_string_lossy().to_string())
+            .filter(|name| name.ends_with(".dist-info") || name.ends_with(".egg-info"))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Resolve the content of a file in a package distribution metadata directory.
+    ///
+    /// `Ok(None)` is returned if the file does not exist.
+    pub fn resolve_distribution_resource(
+        &mut self,
+        directory: &str,
+        name: &str,
+    ) -> Result<Option<Vec<u8>>> {
+        let path = Path::new(directory).join(name);
+
+        if self.members.contains_key(&path) {
+            Ok(Some(self.resolve_path_content(&path)?))
+        } else {
+            Ok(None)
+        }
+    }
 }
 
 pub trait SeekableReader: Read + Seek + Send {}
@@ -168,8 +319,6 @@ impl SeekableReader for BufReader<std::fs::File> {}
 ///
 /// Known incompatibilities with `zipimporter`:
 ///
-/// * ResourceReader interface not implemented.
-/// * ResourceLoader interface not implemented.
 /// * Bytecode isn't validated.
 #[pyclass(module = "oxidized_importer")]
 pub struct OxidizedZipFinder {
@@ -183,7 +332,7 @@ pub struct OxidizedZipFinder {
     /// We can't have generic type parameters. So we need to define an explicit
     /// type backing the zip file. We choose `Vec<u8>` because we can always
     /// capture arbitrary data to a `Vec<u8>`.
-    index: ZipIndex<Box<dyn SeekableReader>>,
+    pub(crate) index: ZipIndex<Box<dyn SeekableReader>>,
 
     /// Path to advertise for this zip archive.
     ///
@@ -289,6 +438,24 @@ impl OxidizedZipFinder {
         })
     }
 
+    /// Resolve the path advertised as the location of a module.
+    ///
+    /// This is the path to the zip archive + the path within the archive.
+    fn module_origin(&self, module: &ZipPythonModule) -> PathBuf {
+        let mut origin = self.zip_path.clone();
+        if let Some(prefix) = &self.index.prefix {
+            origin = origin.join(prefix);
+        }
+
+        if let Some(path) = &module.source_path {
+            origin = origin.join(path);
+        } else if let Some(path) = &module.bytecode_path {
+            origin = origin.join(path);
+        }
+
+        origin
+    }
+
     fn resolve_python_module(
         slf: &mut PyRefMut<Self>,
         full_name: &str,
@@ -378,17 +545,7 @@ impl OxidizedZipFinder {
         let kwargs = PyDict::new(py);
         kwargs.set_item("is_package", module.is_package)?;
 
-        // origin is the path to the zip archive + the path within the archive.
-        let mut origin = importer.zip_path.clone();
-        if let Some(prefix) = &importer.index.prefix {
-            origin = origin.join(prefix);
-        }
-
-        if let Some(path) = module.source_path {
-            origin = origin.join(path);
-        } else if let Some(path) = module.bytecode_path {
-            origin = origin.join(path);
-        }
+        let origin = importer.module_origin(&module);
 
         kwargs.set_item("origin", (&origin).into_py(py))?;
 
@@ -468,6 +625,38 @@ impl OxidizedZipFinder {
 
     // End of importlib.abc.Loader interface.
 
+    // Start of importlib.abc.ResourceLoader interface.
+
+    /// Return the bytes for the data located at path.
+    ///
+    /// The path must be within the zip archive, as derived from a module's
+    /// `__file__` or an item from a package's `__path__`. OSError is raised
+    /// if the path cannot be found.
+    fn get_data<'p>(slf: &'p PyCell<Self>, path: &PyAny) -> PyResult<&'p PyBytes> {
+        let py = slf.py();
+        let path = pyobject_to_pathbuf(py, path)?;
+        let mut importer = slf.try_borrow_mut()?;
+
+        let relative_path = path.strip_prefix(&importer.zip_path).map_err(|_| {
+            PyOSError::new_err(format!("{} is not within zip archive", path.display()))
+        })?;
+        let relative_path = if let Some(prefix) = &importer.index.prefix {
+            relative_path.strip_prefix(prefix).unwrap_or(relative_path)
+        } else {
+            relative_path
+        }
+        .to_path_buf();
+
+        let data = importer
+            .index
+            .resolve_path_content(&relative_path)
+            .map_err(|e| PyOSError::new_err(format!("{}", e)))?;
+
+        Ok(PyBytes::new(py, &data))
+    }
+
+    // End of importlib.abc.ResourceLoader interface.
+
     // Start of importlib.abc.InspectLoader interface.
 
     fn get_code(slf: &PyCell<Self>, fullname: &str) -> PyResult<Py<PyAny>> {
@@ -577,5 +766,95 @@ impl OxidizedZipFinder {
         Ok(module.is_package)
     }
 
+    // Start of importlib.abc.ExecutionLoader interface.
+
+    /// Return the value of `__file__` for the specified module.
+    ///
+    /// ImportError is raised if the module is not found.
+    fn get_filename(slf: &PyCell<Self>, fullname: &str) -> PyResult<Py<PyAny>> {
+        let py = slf.py();
+        let mut importer = slf.try_borrow_mut()?;
+
+        let module = Self::resolve_python_module(&mut importer, fullname)?;
+
+        Ok(importer.module_origin(&module).into_py(py))
+    }
+
+    // End of importlib.abc.ExecutionLoader interface.
+
     // End of importlib.abc.InspectLoader interface.
+
+    // Support obtaining ResourceReader instances.
+
+    fn get_resource_reader(slf: &PyCell<Self>, fullname: &str) -> PyResult<Py<PyAny>> {
+        let py = slf.py();
+
+        let is_package = match slf.try_borrow_mut()?.index.find_python_module(fullname) {
+            Some(module) => module.is_package,
+            None => false,
+        };
+
+        // Resources are only available on packages.
+        if is_package {
+            Ok(PyCell::new(
+                py,
+                OxidizedResourceReader::new(
+                    ResourcesBackend::Zip(slf.into()),
+                    fullname.to_string(),
+                ),
+            )?
+            .into_py(py))
+        } else {
+            Ok(py.None())
+        }
+    }
+
+    // importlib.metadata interface.
+
+    /// Return an iterable of `importlib.metadata.Distribution` instances for
+    /// `.dist-info` and `.egg-info` directories at the root of the zip archive.
+    #[args(context = "None")]
+    fn find_distributions<'p>(
+        slf: &'p PyCell<Self>,
+        context: Option<&PyAny>,
+    ) -> PyResult<&'p PyAny> {
+        let py = slf.py();
+
+        let name = if let Some(context) = context {
+            let name = context.getattr("name")?;
+
+            if name.is_none() {
+                None
+            } else {
+                Some(name)
+            }
+        } else {
+            None
+        };
+
+        find_distributions(py, ResourcesBackend::Zip(slf.into()), name, None)?
+            .call_method0("__iter__")
+    }
+
+    // pkgutil methods.
+
+    /// def iter_modules(prefix="")
+    #[args(prefix = "None")]
+    fn iter_modules<'p>(slf: &'p PyCell<Self>, prefix: Option<&str>) -> PyResult<&'p PyList> {
+        let py = slf.py();
+        let importer = slf.try_borrow()?;
+
+        let infos = importer
+            .index
+            .top_level_modules()
+            .into_iter()
+            .map(|(name, is_package)| {
+                let name = format!("{}{}", prefix.unwrap_or_default(), name);
+
+                PyTuple::new(py, &[name.into_py(py), is_package.into_py(py)])
+            })
+            .collect::<Vec<_>>();
+
+        Ok(PyList::new(py, &infos))
+    }
 }
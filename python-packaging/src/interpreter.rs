@@ -484,6 +484,129 @@ impl TryFrom<String> for MultiprocessingStartMethod {
     }
 }
 
+/// Defines the format of import trace files.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serialization", serde(try_from = "String", into = "String"))]
+pub enum ImportTraceFormat {
+    /// A JSON document with a list of import records.
+    Json,
+    /// The Chrome trace event format, viewable in `chrome://tracing` and Perfetto.
+    ChromeTrace,
+}
+
+impl Default for ImportTraceFormat {
+    fn default() -> Self {
+        Self::Json
+    }
+}
+
+impl ToString for ImportTraceFormat {
+    fn to_string(&self) -> String {
+        match self {
+            Self::Json => "json",
+            Self::ChromeTrace => "chrome-trace",
+        }
+        .to_string()
+    }
+}
+
+impl From<ImportTraceFormat> for String {
+    fn from(v: ImportTraceFormat) -> Self {
+        v.to_string()
+    }
+}
+
+impl FromStr for ImportTraceFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "chrome-trace" => Ok(Self::ChromeTrace),
+            _ => Err(format!("{} is not a valid import trace format", s)),
+        }
+    }
+}
+
+impl TryFrom<&str> for ImportTraceFormat {
+    type Error = String;
+
+    fn try_from(v: &str) -> Result<Self, Self::Error> {
+        Self::from_str(v)
+    }
+}
+
+impl TryFrom<String> for ImportTraceFormat {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_str())
+    }
+}
+
+/// Defines where crash diagnostics are written.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serialization", serde(try_from = "String", into = "String"))]
+pub enum CrashOutput {
+    /// Write to the process's stderr.
+    Stderr,
+    /// Write to an already open file descriptor.
+    FileDescriptor(i32),
+    /// Append to the file at a filesystem path, creating it if necessary.
+    Path(PathBuf),
+}
+
+impl ToString for CrashOutput {
+    fn to_string(&self) -> String {
+        match self {
+            Self::Stderr => "stderr".to_string(),
+            Self::FileDescriptor(fd) => format!("fd:{}", fd),
+            Self::Path(path) => format!("path:{}", path.display()),
+        }
+    }
+}
+
+impl From<CrashOutput> for String {
+    fn from(v: CrashOutput) -> Self {
+        v.to_string()
+    }
+}
+
+impl TryFrom<&str> for CrashOutput {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if value == "stderr" {
+            Ok(Self::Stderr)
+        } else if let Some(suffix) = value.strip_prefix("fd:") {
+            suffix
+                .parse::<i32>()
+                .ok()
+                .filter(|fd| *fd >= 0)
+                .map(Self::FileDescriptor)
+                .ok_or_else(|| format!("{} is not a valid file descriptor", suffix))
+        } else if let Some(suffix) = value.strip_prefix("path:") {
+            if suffix.is_empty() {
+                Err("crash output path cannot be empty".to_string())
+            } else {
+                Ok(Self::Path(PathBuf::from(suffix)))
+            }
+        } else {
+            Err(format!("{} is not a valid crash output value", value))
+        }
+    }
+}
+
+impl TryFrom<String> for CrashOutput {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_str())
+    }
+}
+
 /// Holds configuration of a Python interpreter.
 ///
 /// This struct holds fields that are exposed by `PyPreConfig` and
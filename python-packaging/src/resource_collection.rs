@@ -20,7 +20,7 @@ use {
         },
     },
     anyhow::{anyhow, Context, Result},
-    python_packed_resources::Resource,
+    python_packed_resources::{Resource, ResourceCompression},
     std::{
         borrow::Cow,
         collections::{BTreeMap, BTreeSet, HashMap},
@@ -398,6 +398,8 @@ impl PrePackagedResource {
             } else {
                 None
             },
+            data_compression: None,
+            preload: false,
         };
 
         if let Some((prefix, filename, location)) = &self.relative_path_shared_library {
@@ -651,19 +653,100 @@ pub struct CompiledResourcesCollection<'a> {
 
     /// Extra file installs that must be performed so referenced files are available.
     pub extra_files: Vec<FileInstall>,
+
+    /// How to compress in-memory resource data when writing packed resources.
+    pub compression: Option<ResourceCompression>,
+
+    /// Names of resources flagged for preloading, in the order they are written.
+    pub preload_order: Vec<String>,
 }
 
 impl<'a> CompiledResourcesCollection<'a> {
-    /// Write resources to packed resources data, version 1.
+    /// Flag resources for preloading.
+    ///
+    /// Resources whose names are in `names` are written before all other
+    /// resources, in the order of `names`, so their data is contiguous and
+    /// laid out in the order it is likely to be accessed. Readers may load
+    /// their data eagerly.
+    ///
+    /// Returns the number of resources that were flagged.
+    pub fn set_preload_resources<'n>(&mut self, names: impl IntoIterator<Item = &'n str>) -> usize {
+        let mut count = 0;
+
+        for name in names {
+            if let Some(resource) = self.resources.get_mut(name) {
+                if !resource.preload {
+                    resource.preload = true;
+                    self.preload_order.push(name.to_string());
+                    count += 1;
+                }
+            }
+        }
+
+        count
+    }
+
+    /// Obtain resources in the order they should be written.
+    ///
+    /// Resources flagged via [Self::set_preload_resources()] come first, in
+    /// the order they were flagged. Remaining resources are sorted by name.
+    fn ordered_resources(&self) -> Vec<Resource<'a, u8>> {
+        let ordered = self
+            .preload_order
+            .iter()
+            .map(|name| name.as_str())
+            .collect::<BTreeSet<_>>();
+
+        self.preload_order
+            .iter()
+            .filter_map(|name| self.resources.get(name))
+            .chain(
+                self.resources
+                    .values()
+                    .filter(|resource| !ordered.contains(resource.name.as_ref())),
+            )
+            .cloned()
+            .collect::<Vec<_>>()
+    }
+
+    /// Write resources to packed resources data.
+    ///
+    /// Version 3 of the format is used unless compression is enabled or
+    /// resources are flagged for preloading, which require version 4.
     pub fn write_packed_resources<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
-        python_packed_resources::write_packed_resources_v3(
-            &self
-                .resources
-                .values()
-                .cloned()
-                .collect::<Vec<Resource<'a, u8>>>(),
+        let resources = self.ordered_resources();
+
+        if self.compression.is_some()
+            || resources
+                .iter()
+                .any(|resource| resource.preload || resource.data_compression.is_some())
+        {
+            python_packed_resources::write_packed_resources_v4(
+                &resources,
+                writer,
+                None,
+                self.compression,
+                false,
+            )
+        } else {
+            python_packed_resources::write_packed_resources_v3(&resources, writer, None)
+        }
+    }
+
+    /// Write resources to packed resources data, version 4, with integrity digests.
+    ///
+    /// Every resource records a digest of its data, allowing readers to verify
+    /// resources when combined with a signature over the index data.
+    pub fn write_packed_resources_with_integrity_digests<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+    ) -> Result<()> {
+        python_packed_resources::write_packed_resources_v4(
+            &self.ordered_resources(),
             writer,
             None,
+            self.compression,
+            true,
         )
     }
 
@@ -1916,6 +1999,8 @@ impl PythonResourceCollector {
         Ok(CompiledResourcesCollection {
             resources,
             extra_files,
+            compression: None,
+            preload_order: vec![],
         })
     }
 }
@@ -4735,4 +4820,110 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_set_preload_resources() -> Result<()> {
+        let mut r = PythonResourceCollector::new(
+            vec![AbstractResourceLocation::InMemory],
+            vec![],
+            false,
+            false,
+        );
+
+        for name in ["a", "b", "c"] {
+            r.add_python_module_source(
+                &PythonModuleSource {
+                    name: name.to_string(),
+                    source: FileData::Memory(name.as_bytes().to_vec()),
+                    is_package: false,
+                    cache_tag: DEFAULT_CACHE_TAG.to_string(),
+                    is_stdlib: false,
+                    is_test: false,
+                },
+                &ConcreteResourceLocation::InMemory,
+            )?;
+        }
+
+        let mut compiler = FakeBytecodeCompiler { magic_number: 42 };
+        let mut resources = r.compile_resources(&mut compiler)?;
+
+        let mut data = vec![];
+        resources.write_packed_resources(&mut data)?;
+        assert!(data.starts_with(python_packed_resources::HEADER_V3));
+
+        assert_eq!(resources.set_preload_resources(["c", "missing", "a"]), 2);
+        assert_eq!(resources.set_preload_resources(["c"]), 0);
+        assert!(resources.resources.get("c").unwrap().preload);
+        assert!(resources.resources.get("a").unwrap().preload);
+        assert!(!resources.resources.get("b").unwrap().preload);
+
+        let mut data = vec![];
+        resources.write_packed_resources(&mut data)?;
+        assert!(data.starts_with(python_packed_resources::HEADER_V4));
+
+        let names = python_packed_resources::load_resources(&data)
+            .map_err(|e| anyhow!("{}", e))?
+            .map(|resource| resource.map(|r| r.name.to_string()))
+            .collect::<Result<Vec<_>, &'static str>>()
+            .map_err(|e| anyhow!("{}", e))?;
+        assert_eq!(names, vec!["c", "a", "b"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_packed_resources_compression() -> Result<()> {
+        let mut r = PythonResourceCollector::new(
+            vec![AbstractResourceLocation::InMemory],
+            vec![],
+            false,
+            false,
+        );
+
+        let source = b"import foo\n".repeat(64);
+
+        r.add_python_module_source(
+            &PythonModuleSource {
+                name: "foo".to_string(),
+                source: FileData::Memory(source.clone()),
+                is_package: false,
+                cache_tag: DEFAULT_CACHE_TAG.to_string(),
+                is_stdlib: false,
+                is_test: false,
+            },
+            &ConcreteResourceLocation::InMemory,
+        )?;
+
+        let mut compiler = FakeBytecodeCompiler { magic_number: 42 };
+        let mut resources = r.compile_resources(&mut compiler)?;
+        resources.compression = Some(ResourceCompression {
+            codec: python_packed_resources::CompressionCodec::Lz4,
+            minimum_size: 64,
+        });
+
+        let mut data = vec![];
+        resources.write_packed_resources(&mut data)?;
+        assert!(data.starts_with(python_packed_resources::HEADER_V4));
+
+        let loaded = python_packed_resources::load_resources(&data)
+            .map_err(|e| anyhow!("{}", e))?
+            .collect::<Result<Vec<_>, &'static str>>()
+            .map_err(|e| anyhow!("{}", e))?;
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(
+            loaded[0].data_compression,
+            Some(python_packed_resources::CompressionCodec::Lz4)
+        );
+        assert_eq!(
+            loaded[0]
+                .decompress()
+                .map_err(|e| anyhow!("{}", e))?
+                .in_memory_source
+                .unwrap()
+                .as_ref(),
+            &source
+        );
+
+        Ok(())
+    }
 }
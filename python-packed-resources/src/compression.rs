@@ -0,0 +1,64 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Compression of resource data. */
+
+use crate::serialization::CompressionCodec;
+
+/// Compress data using a codec.
+pub fn compress(codec: CompressionCodec, data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    match codec {
+        #[cfg(feature = "zstd")]
+        CompressionCodec::Zstd => zstd::stream::encode_all(data, zstd::DEFAULT_COMPRESSION_LEVEL)
+            .map_err(|_| "error compressing data with zstd"),
+        #[cfg(not(feature = "zstd"))]
+        CompressionCodec::Zstd => Err("zstd support not enabled"),
+        CompressionCodec::Lz4 => Ok(lz4_flex::compress_prepend_size(data)),
+    }
+}
+
+/// Decompress data that was compressed with a codec.
+pub fn decompress(codec: CompressionCodec, data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    match codec {
+        #[cfg(feature = "zstd")]
+        CompressionCodec::Zstd => {
+            zstd::stream::decode_all(data).map_err(|_| "error decompressing zstd data")
+        }
+        #[cfg(not(feature = "zstd"))]
+        CompressionCodec::Zstd => Err("zstd support not enabled"),
+        CompressionCodec::Lz4 => {
+            lz4_flex::decompress_size_prepended(data).map_err(|_| "error decompressing lz4 data")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let data = b"foo bar baz".repeat(32);
+
+        #[allow(unused_mut)]
+        let mut codecs = vec![CompressionCodec::Lz4];
+        #[cfg(feature = "zstd")]
+        codecs.push(CompressionCodec::Zstd);
+
+        for codec in codecs {
+            let compressed = compress(codec, &data).unwrap();
+            assert!(compressed.len() < data.len());
+            assert_eq!(decompress(codec, &compressed).unwrap(), data);
+        }
+
+        assert!(decompress(CompressionCodec::Zstd, b"garbage").is_err());
+        assert!(decompress(CompressionCodec::Lz4, b"garbage").is_err());
+    }
+
+    #[cfg(not(feature = "zstd"))]
+    #[test]
+    fn zstd_disabled() {
+        assert!(compress(CompressionCodec::Zstd, b"foo").is_err());
+    }
+}
@@ -25,14 +25,22 @@ be used outside the PyOxidizer project. See the aforementioned docs
 for the canonical specification of this format.
 */
 
+mod compression;
 mod parser;
 mod resource;
 mod serialization;
+#[cfg(feature = "signing")]
+mod signing;
 mod writer;
 
 pub use crate::{
-    parser::{load_resources, ResourceParserIterator},
+    parser::{index_data, load_resources, load_resources_verified, ResourceParserIterator},
     resource::Resource,
-    serialization::HEADER_V3,
-    writer::write_packed_resources_v3,
+    serialization::{CompressionCodec, HEADER_V3, HEADER_V4, INTEGRITY_DIGEST_LENGTH},
+    writer::{write_packed_resources_v3, write_packed_resources_v4, ResourceCompression},
+};
+
+#[cfg(feature = "signing")]
+pub use crate::signing::{
+    sign_packed_resources, verify_packed_resources_signature, SIGNATURE_HEADER_V1,
 };
@@ -7,10 +7,19 @@
 use {
     crate::{
         resource::Resource,
-        serialization::{BlobInteriorPadding, BlobSectionField, ResourceField, HEADER_V3},
+        serialization::{
+            BlobInteriorPadding, BlobSectionField, CompressionCodec, ResourceField, HEADER_V3,
+            HEADER_V4, INTEGRITY_DIGEST_LENGTH,
+        },
     },
     byteorder::{LittleEndian, ReadBytesExt},
-    std::{borrow::Cow, collections::HashMap, io::Cursor, path::Path},
+    sha2::{Digest, Sha256},
+    std::{
+        borrow::Cow,
+        collections::HashMap,
+        io::{Cursor, Read},
+        path::Path,
+    },
 };
 
 #[cfg(unix)]
@@ -37,9 +46,21 @@ struct BlobSectionReadState {
 ///
 /// The iterator emits [Resource] instances. The index data for a given resource is
 /// not read or validated until the iterator attempts to deserialize it.
+///
+/// Compressed resource data is emitted as-is: decompression is deferred until
+/// consumers request the data via [Resource::resolve_data()] or
+/// [Resource::decompress()]. This allows consumers to only pay the
+/// decompression cost for the resources they use.
+///
+/// Integrity digests are only verified if the iterator was obtained via
+/// [load_resources_verified()].
 pub struct ResourceParserIterator<'a> {
     done: bool,
+    allow_v4_fields: bool,
     data: &'a [u8],
+    /// Digests blob data of the current resource when verifying integrity.
+    digester: Option<Sha256>,
+    expected_digest: Option<[u8; INTEGRITY_DIGEST_LENGTH]>,
     reader: Cursor<&'a [u8]>,
     blob_sections: [Option<BlobSectionReadState>; 256],
     claimed_resources_count: usize,
@@ -64,6 +85,10 @@ impl<'a> ResourceParserIterator<'a> {
 
         let blob = &self.data[state.offset..state.offset + length];
 
+        if let Some(digester) = &mut self.digester {
+            digester.update(blob);
+        }
+
         let increment = match &state.interior_padding {
             BlobInteriorPadding::None => length,
             BlobInteriorPadding::Null => length + 1,
@@ -118,15 +143,31 @@ impl<'a> ResourceParserIterator<'a> {
                     self.read_resources_count += 1;
                     current_resource = Resource::default();
                     current_resource_name = None;
+
+                    if let Some(digester) = &mut self.digester {
+                        Digest::reset(digester);
+                        self.expected_digest = None;
+                    }
                 }
                 ResourceField::EndOfEntry => {
-                    let res = if current_resource_name.is_some() {
-                        Ok(Some(current_resource))
-                    } else {
-                        Err("resource name field is required")
-                    };
+                    if current_resource_name.is_none() {
+                        return Err("resource name field is required");
+                    }
 
-                    return res;
+                    if let Some(digester) = &mut self.digester {
+                        match self.expected_digest.take() {
+                            Some(expected) => {
+                                if digester.finalize_reset().as_slice() != expected.as_slice() {
+                                    return Err("resource integrity digest mismatch");
+                                }
+                            }
+                            None => {
+                                return Err("resource integrity digest is required");
+                            }
+                        }
+                    }
+
+                    return Ok(Some(current_resource));
                 }
                 ResourceField::Name => {
                     let l = self
@@ -481,6 +522,42 @@ impl<'a> ResourceParserIterator<'a> {
                         std::str::from_utf8_unchecked(self.resolve_blob_data(field_type, l))
                     }));
                 }
+
+                ResourceField::DataCompression => {
+                    if !self.allow_v4_fields {
+                        return Err("data compression not supported by format version");
+                    }
+
+                    let codec = self
+                        .reader
+                        .read_u8()
+                        .map_err(|_| "failed reading data compression codec")?;
+
+                    current_resource.data_compression = Some(CompressionCodec::try_from(codec)?);
+                }
+
+                ResourceField::IntegrityDigest => {
+                    if !self.allow_v4_fields {
+                        return Err("integrity digests not supported by format version");
+                    }
+
+                    let mut digest = [0u8; INTEGRITY_DIGEST_LENGTH];
+                    self.reader
+                        .read_exact(&mut digest)
+                        .map_err(|_| "failed reading integrity digest")?;
+
+                    if self.digester.is_some() {
+                        self.expected_digest = Some(digest);
+                    }
+                }
+
+                ResourceField::Preload => {
+                    if !self.allow_v4_fields {
+                        return Err("preload not supported by format version");
+                    }
+
+                    current_resource.preload = true;
+                }
             }
         }
     }
@@ -510,6 +587,60 @@ impl<'a> Iterator for ResourceParserIterator<'a> {
 /// this decreased performance by ~15%. Given the performance sensitivity of this
 /// code, we need to keep error handling primitive.
 pub fn load_resources<'a>(data: &'a [u8]) -> Result<ResourceParserIterator<'a>, &'static str> {
+    load_resources_impl(data, false)
+}
+
+/// Parse a packed resources data structure, verifying resource integrity.
+///
+/// This is like [load_resources()] except every resource must carry an
+/// integrity digest and the digest must match the resource's blob data.
+/// Resources failing verification are emitted as errors.
+///
+/// Integrity digests only detect modification of blob data. To detect
+/// tampering with the index itself, the index data must additionally be
+/// authenticated, e.g. via a signature over [index_data()].
+pub fn load_resources_verified<'a>(
+    data: &'a [u8],
+) -> Result<ResourceParserIterator<'a>, &'static str> {
+    load_resources_impl(data, true)
+}
+
+/// Resolve the header and index data of a packed resources data structure.
+///
+/// The returned slice covers everything up to the start of blob data. Since
+/// index entries can record integrity digests of blob data, authenticating
+/// this slice is sufficient to authenticate the entire data structure when
+/// resources are loaded with [load_resources_verified()].
+pub fn index_data(data: &[u8]) -> Result<&[u8], &'static str> {
+    if data.len() < HEADER_V3.len() {
+        return Err("error reading 8 byte header");
+    }
+
+    let mut reader = Cursor::new(&data[8..]);
+    reader
+        .read_u8()
+        .map_err(|_| "failed reading blob section count")?;
+    let blob_index_length = reader
+        .read_u32::<LittleEndian>()
+        .map_err(|_| "failed reading blob index length")? as usize;
+    reader
+        .read_u32::<LittleEndian>()
+        .map_err(|_| "failed reading resources count")?;
+    let resources_index_length = reader
+        .read_u32::<LittleEndian>()
+        .map_err(|_| "failed reading resources index length")?
+        as usize;
+
+    let end = 8 + 1 + 4 + 4 + 4 + blob_index_length + resources_index_length;
+
+    data.get(0..end)
+        .ok_or("index data extends past end of data")
+}
+
+fn load_resources_impl<'a>(
+    data: &'a [u8],
+    verify_digests: bool,
+) -> Result<ResourceParserIterator<'a>, &'static str> {
     if data.len() < HEADER_V3.len() {
         return Err("error reading 8 byte header");
     }
@@ -517,13 +648,25 @@ pub fn load_resources<'a>(data: &'a [u8]) -> Result<ResourceParserIterator<'a>,
     let header = &data[0..8];
 
     if header == HEADER_V3 {
-        load_resources_v3(&data[8..])
+        if verify_digests {
+            return Err("integrity digests not supported by format version");
+        }
+
+        load_resources_v3(&data[8..], false, false)
+    } else if header == HEADER_V4 {
+        // Version 4 is version 3 plus support for compressed resource data
+        // and integrity digests.
+        load_resources_v3(&data[8..], true, verify_digests)
     } else {
         Err("unrecognized file format")
     }
 }
 
-fn load_resources_v3<'a>(data: &'a [u8]) -> Result<ResourceParserIterator<'a>, &'static str> {
+fn load_resources_v3<'a>(
+    data: &'a [u8],
+    allow_v4_fields: bool,
+    verify_digests: bool,
+) -> Result<ResourceParserIterator<'a>, &'static str> {
     let mut reader = Cursor::new(data);
 
     let blob_section_count = reader
@@ -638,7 +781,14 @@ fn load_resources_v3<'a>(data: &'a [u8]) -> Result<ResourceParserIterator<'a>, &
 
     Ok(ResourceParserIterator {
         done: resources_index_length == 0 || resources_count == 0,
+        allow_v4_fields,
         data,
+        digester: if verify_digests {
+            Some(Sha256::new())
+        } else {
+            None
+        },
+        expected_digest: None,
         reader,
         blob_sections: blob_offsets,
         claimed_resources_count: resources_count,
@@ -651,8 +801,9 @@ mod tests {
     use {
         super::*,
         crate::{
-            resource::Resource, serialization::BlobInteriorPadding,
-            writer::write_packed_resources_v3,
+            resource::Resource,
+            serialization::BlobInteriorPadding,
+            writer::{write_packed_resources_v3, write_packed_resources_v4, ResourceCompression},
         },
     };
 
@@ -670,7 +821,7 @@ mod tests {
         let res = load_resources(data);
         assert_eq!(res.err(), Some("unrecognized file format"));
 
-        let data = b"pyembed\x04";
+        let data = b"pyembed\x05";
         let res = load_resources(data);
         assert_eq!(res.err(), Some("unrecognized file format"));
     }
@@ -1401,6 +1552,8 @@ mod tests {
             file_executable: true,
             file_data_embedded: Some(Cow::from(b"file_data_embedded".to_vec())),
             file_data_utf8_relative_path: Some(Cow::from("file_data_utf8_relative_path")),
+            data_compression: None,
+            preload: false,
         };
 
         let mut data = Vec::new();
@@ -1543,4 +1696,231 @@ mod tests {
 
         assert_eq!(resources, loaded);
     }
+
+    #[test]
+    fn test_compressed_data() {
+        let source = b"import io\n".repeat(64);
+        let mut package_resources = HashMap::new();
+        package_resources.insert(Cow::from("data.txt"), Cow::from(b"data ".repeat(64)));
+
+        let resources: Vec<Resource<u8>> = vec![
+            Resource {
+                name: Cow::from("foo"),
+                is_python_module: true,
+                in_memory_source: Some(Cow::from(source.clone())),
+                in_memory_package_resources: Some(package_resources),
+                ..Resource::default()
+            },
+            Resource {
+                name: Cow::from("small"),
+                is_python_module: true,
+                in_memory_bytecode: Some(Cow::from(b"bytecode".to_vec())),
+                ..Resource::default()
+            },
+        ];
+
+        #[allow(unused_mut)]
+        let mut codecs = vec![CompressionCodec::Lz4];
+        #[cfg(feature = "zstd")]
+        codecs.push(CompressionCodec::Zstd);
+
+        for codec in codecs {
+            let mut data = Vec::new();
+            write_packed_resources_v4(
+                &resources,
+                &mut data,
+                None,
+                Some(ResourceCompression {
+                    codec,
+                    minimum_size: 32,
+                }),
+                false,
+            )
+            .unwrap();
+            assert_eq!(&data[0..8], HEADER_V4);
+
+            let loaded = load_resources(&data)
+                .unwrap()
+                .collect::<Result<Vec<Resource<u8>>, &'static str>>()
+                .unwrap();
+            assert_eq!(loaded.len(), 2);
+
+            // Data remains compressed until it is resolved.
+            let entry = &loaded[0];
+            assert_eq!(entry.data_compression, Some(codec));
+            let data = entry.in_memory_source.as_ref().unwrap();
+            assert!(data.len() < source.len());
+            assert_eq!(entry.resolve_data(data).unwrap().as_ref(), &source);
+
+            // Resources below the size threshold aren't compressed.
+            assert_eq!(loaded[1].data_compression, None);
+            assert_eq!(loaded[1], resources[1]);
+
+            let decompressed = loaded
+                .iter()
+                .map(|resource| resource.decompress())
+                .collect::<Result<Vec<_>, &'static str>>()
+                .unwrap();
+            assert_eq!(decompressed, resources);
+
+            // Compressed resources survive a round trip through the writer.
+            let mut data = Vec::new();
+            write_packed_resources_v4(&loaded, &mut data, None, None, false).unwrap();
+            assert_eq!(
+                load_resources(&data)
+                    .unwrap()
+                    .collect::<Result<Vec<Resource<u8>>, &'static str>>()
+                    .unwrap(),
+                loaded
+            );
+
+            // But can't be written to the version 3 format.
+            assert!(write_packed_resources_v3(&loaded, &mut Vec::new(), None).is_err());
+        }
+    }
+
+    #[test]
+    fn test_compression_not_allowed_v3() {
+        let data = b"pyembed\x03\x00\x01\x00\x00\x00\x01\x00\x00\x00\x04\x00\x00\x00\x00\x01\x1f\x01\xff\x00";
+        let mut res = load_resources(data).unwrap();
+        assert_eq!(
+            res.next(),
+            Some(Err("data compression not supported by format version"))
+        );
+    }
+
+    #[test]
+    fn test_integrity_digests() {
+        let mut package_resources = HashMap::new();
+        package_resources.insert(Cow::from("data.txt"), Cow::from(b"data".to_vec()));
+
+        let resources: Vec<Resource<u8>> = vec![
+            Resource {
+                name: Cow::from("foo"),
+                is_python_module: true,
+                in_memory_source: Some(Cow::from(b"import io".to_vec())),
+                in_memory_package_resources: Some(package_resources),
+                relative_path_module_bytecode: Some(Cow::from(Path::new("foo.pyc"))),
+                ..Resource::default()
+            },
+            Resource {
+                name: Cow::from("bar"),
+                is_python_module: true,
+                in_memory_bytecode: Some(Cow::from(b"bytecode".to_vec())),
+                ..Resource::default()
+            },
+        ];
+
+        let mut data = Vec::new();
+        write_packed_resources_v4(&resources, &mut data, None, None, true).unwrap();
+
+        // Digests are transparent to regular loading.
+        let loaded = load_resources(&data)
+            .unwrap()
+            .collect::<Result<Vec<Resource<u8>>, &'static str>>()
+            .unwrap();
+        assert_eq!(loaded, resources);
+
+        let loaded = load_resources_verified(&data)
+            .unwrap()
+            .collect::<Result<Vec<Resource<u8>>, &'static str>>()
+            .unwrap();
+        assert_eq!(loaded, resources);
+
+        // Modifying blob data is detected by verification only.
+        let index_length = index_data(&data).unwrap().len();
+        assert!(index_length < data.len());
+        // The last byte of blob data is the relative path of the 1st resource.
+        let mut tampered = data.clone();
+        let offset = tampered.len() - 1;
+        tampered[offset] = b'x';
+        assert!(load_resources(&tampered)
+            .unwrap()
+            .all(|resource| resource.is_ok()));
+        let mut res = load_resources_verified(&tampered).unwrap();
+        assert_eq!(res.next(), Some(Err("resource integrity digest mismatch")));
+        assert!(res.next().unwrap().is_ok());
+
+        // Verification requires digests to be present.
+        let mut data = Vec::new();
+        write_packed_resources_v4(&resources, &mut data, None, None, false).unwrap();
+        let mut res = load_resources_verified(&data).unwrap();
+        assert_eq!(
+            res.next(),
+            Some(Err("resource integrity digest is required"))
+        );
+
+        let mut data = Vec::new();
+        write_packed_resources_v3(&resources, &mut data, None).unwrap();
+        assert!(load_resources_verified(&data).is_err());
+    }
+
+    #[test]
+    fn test_preload() {
+        let resource = |name: &'static str, preload: bool| Resource {
+            name: Cow::from(name),
+            is_python_module: true,
+            in_memory_bytecode: Some(Cow::from(name.as_bytes().to_vec())),
+            preload,
+            ..Resource::default()
+        };
+
+        let resources = vec![
+            resource("a", false),
+            resource("b", true),
+            resource("c", false),
+            resource("d", true),
+        ];
+
+        let mut data = Vec::new();
+        write_packed_resources_v4(&resources, &mut data, None, None, false).unwrap();
+
+        let loaded = load_resources(&data)
+            .unwrap()
+            .collect::<Result<Vec<Resource<u8>>, &'static str>>()
+            .unwrap();
+
+        // Preloaded resources come first, retaining their relative order.
+        assert_eq!(
+            loaded.iter().map(|r| r.name.as_ref()).collect::<Vec<_>>(),
+            vec!["b", "d", "a", "c"]
+        );
+        assert_eq!(
+            loaded.iter().map(|r| r.preload).collect::<Vec<_>>(),
+            vec![true, true, false, false]
+        );
+
+        // And their data is contiguous at the start of the blob section.
+        let bytecode = loaded
+            .iter()
+            .map(|r| match r.in_memory_bytecode.as_ref().unwrap() {
+                Cow::Borrowed(data) => data.as_ptr() as usize,
+                Cow::Owned(_) => panic!("expected borrowed data"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(bytecode[1], bytecode[0] + 1);
+        assert_eq!(bytecode[2], bytecode[1] + 1);
+
+        assert!(write_packed_resources_v3(&resources, &mut Vec::new(), None).is_err());
+
+        // Version 3 data can't declare preloading.
+        let mut data = Vec::new();
+        write_packed_resources_v3(&[resource("a", false)], &mut data, None).unwrap();
+        // Data ends with the end of entry and end of index markers followed by
+        // the name and bytecode blobs.
+        let offset = data.len() - 4;
+        assert_eq!(data[offset], u8::from(ResourceField::EndOfEntry));
+        data[offset] = ResourceField::Preload.into();
+        assert!(load_resources(&data)
+            .unwrap()
+            .any(|resource| resource.is_err()));
+    }
+
+    #[test]
+    fn test_index_data() {
+        let data = b"pyembed\x03\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01\x00\x00\x00\x00";
+        assert_eq!(index_data(data).unwrap(), data);
+        assert!(index_data(&data[0..data.len() - 1]).is_err());
+        assert!(index_data(b"pyembed").is_err());
+    }
 }
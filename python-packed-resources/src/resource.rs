@@ -2,7 +2,10 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use std::{borrow::Cow, collections::HashMap, path::Path};
+use {
+    crate::{compression, serialization::CompressionCodec},
+    std::{borrow::Cow, collections::HashMap, path::Path},
+};
 
 /// Represents an indexed resource.
 ///
@@ -111,6 +114,20 @@ where
 
     /// Holds arbitrary file data in a relative path encoded in UTF-8.
     pub file_data_utf8_relative_path: Option<Cow<'a, str>>,
+
+    /// Compression codec applied to in-memory data.
+    ///
+    /// If set, every piece of in-memory data (`in_memory_*` fields holding
+    /// bytes and `file_data_embedded`) is individually compressed with this
+    /// codec. Use [Resource::resolve_data()] or [Resource::decompress()] to
+    /// obtain the original data.
+    pub data_compression: Option<CompressionCodec>,
+
+    /// Whether this resource is used when the application starts.
+    ///
+    /// Writers place the data of these resources before the data of other
+    /// resources and readers may load it eagerly.
+    pub preload: bool,
 }
 
 impl<'a, X> Default for Resource<'a, X>
@@ -147,6 +164,8 @@ where
             file_executable: false,
             file_data_embedded: None,
             file_data_utf8_relative_path: None,
+            data_compression: None,
+            preload: false,
         }
     }
 }
@@ -171,6 +190,9 @@ where
         if self.name != other.name {
             return Err("resource names must be identical to perform a merge");
         }
+        if self.data_compression != other.data_compression {
+            return Err("resources must have identical data compression to perform a merge");
+        }
 
         self.is_python_module |= other.is_python_module;
         self.is_python_builtin_extension_module |= other.is_python_builtin_extension_module;
@@ -239,6 +261,7 @@ where
         if let Some(value) = other.file_data_utf8_relative_path {
             self.file_data_utf8_relative_path.replace(value);
         }
+        self.preload |= other.preload;
 
         Ok(())
     }
@@ -366,6 +389,92 @@ where
                 .file_data_utf8_relative_path
                 .as_ref()
                 .map(|value| Cow::Owned(value.clone().into_owned())),
+            data_compression: self.data_compression,
+            preload: self.preload,
+        }
+    }
+}
+
+impl<'a> Resource<'a, u8> {
+    /// Resolve the original content of a piece of in-memory data from this resource.
+    ///
+    /// `data` should be the value of one of this resource's in-memory data fields.
+    /// If the resource's data is compressed, it is decompressed. Otherwise it is
+    /// returned as-is.
+    pub fn resolve_data<'b>(&self, data: &'b [u8]) -> Result<Cow<'b, [u8]>, &'static str> {
+        match self.data_compression {
+            Some(codec) => Ok(Cow::Owned(compression::decompress(codec, data)?)),
+            None => Ok(Cow::Borrowed(data)),
+        }
+    }
+
+    /// Obtain a copy of this resource with all in-memory data compressed with a codec.
+    ///
+    /// Errors if the resource's data is already compressed.
+    pub fn compress(&self, codec: CompressionCodec) -> Result<Self, &'static str> {
+        if self.data_compression.is_some() {
+            return Err("resource data is already compressed");
+        }
+
+        let mut res = self.map_data(|data| compression::compress(codec, data))?;
+        res.data_compression = Some(codec);
+
+        Ok(res)
+    }
+
+    /// Obtain a copy of this resource with all in-memory data decompressed.
+    ///
+    /// If the resource's data isn't compressed, this is equivalent to `clone()`.
+    pub fn decompress(&self) -> Result<Self, &'static str> {
+        if let Some(codec) = self.data_compression {
+            let mut res = self.map_data(|data| compression::decompress(codec, data))?;
+            res.data_compression = None;
+
+            Ok(res)
+        } else {
+            Ok(self.clone())
         }
     }
+
+    /// Obtain a copy of this resource with a function applied to all in-memory data.
+    fn map_data(
+        &self,
+        f: impl Fn(&[u8]) -> Result<Vec<u8>, &'static str>,
+    ) -> Result<Self, &'static str> {
+        let map_value = |value: &Option<Cow<'a, [u8]>>| -> Result<_, &'static str> {
+            value
+                .as_ref()
+                .map(|data| f(data).map(Cow::Owned))
+                .transpose()
+        };
+
+        let map_resources = |value: &Option<HashMap<Cow<'a, str>, Cow<'a, [u8]>>>| {
+            value
+                .as_ref()
+                .map(|resources| {
+                    resources
+                        .iter()
+                        .map(|(k, v)| Ok((k.clone(), Cow::Owned(f(v)?))))
+                        .collect::<Result<HashMap<_, _>, &'static str>>()
+                })
+                .transpose()
+        };
+
+        Ok(Self {
+            in_memory_source: map_value(&self.in_memory_source)?,
+            in_memory_bytecode: map_value(&self.in_memory_bytecode)?,
+            in_memory_bytecode_opt1: map_value(&self.in_memory_bytecode_opt1)?,
+            in_memory_bytecode_opt2: map_value(&self.in_memory_bytecode_opt2)?,
+            in_memory_extension_module_shared_library: map_value(
+                &self.in_memory_extension_module_shared_library,
+            )?,
+            in_memory_package_resources: map_resources(&self.in_memory_package_resources)?,
+            in_memory_distribution_resources: map_resources(
+                &self.in_memory_distribution_resources,
+            )?,
+            in_memory_shared_library: map_value(&self.in_memory_shared_library)?,
+            file_data_embedded: map_value(&self.file_data_embedded)?,
+            ..self.clone()
+        })
+    }
 }
@@ -4,9 +4,57 @@
 
 /*! Declares the foundational data primitives inside packed resources data. */
 
-/// Header value for version 2 of resources payload.
+/// Header value for version 3 of resources payload.
 pub const HEADER_V3: &[u8] = b"pyembed\x03";
 
+/// Header value for version 4 of resources payload.
+///
+/// Version 4 is identical to version 3 except resources can declare that
+/// their in-memory data is compressed, can carry an integrity digest of
+/// their data, and can be flagged for preloading.
+pub const HEADER_V4: &[u8] = b"pyembed\x04";
+
+/// Length in bytes of a resource's integrity digest.
+///
+/// Integrity digests are SHA-256.
+pub const INTEGRITY_DIGEST_LENGTH: usize = 32;
+
+/// Defines a compression codec applied to resource data.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CompressionCodec {
+    /// Zstandard.
+    ///
+    /// Each piece of data is a standalone zstd frame.
+    Zstd = 0x01,
+
+    /// LZ4 block format.
+    ///
+    /// Each piece of data is an LZ4 block prefixed by its decompressed size
+    /// as a little endian u32.
+    Lz4 = 0x02,
+}
+
+impl From<CompressionCodec> for u8 {
+    fn from(source: CompressionCodec) -> Self {
+        match source {
+            CompressionCodec::Zstd => 0x01,
+            CompressionCodec::Lz4 => 0x02,
+        }
+    }
+}
+
+impl TryFrom<u8> for CompressionCodec {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x01 => Ok(CompressionCodec::Zstd),
+            0x02 => Ok(CompressionCodec::Lz4),
+            _ => Err("invalid compression codec"),
+        }
+    }
+}
+
 /// Defines interior padding mechanism between entries in blob sections.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum BlobInteriorPadding {
@@ -105,6 +153,9 @@ pub enum ResourceField {
     FileExecutable = 0x1c,
     FileDataEmbedded = 0x1d,
     FileDataUtf8RelativePath = 0x1e,
+    DataCompression = 0x1f,
+    IntegrityDigest = 0x20,
+    Preload = 0x21,
 }
 
 impl From<ResourceField> for u8 {
@@ -140,6 +191,9 @@ impl From<ResourceField> for u8 {
             ResourceField::FileExecutable => 0x1c,
             ResourceField::FileDataEmbedded => 0x1d,
             ResourceField::FileDataUtf8RelativePath => 0x1e,
+            ResourceField::DataCompression => 0x1f,
+            ResourceField::IntegrityDigest => 0x20,
+            ResourceField::Preload => 0x21,
             ResourceField::EndOfEntry => 0xff,
         }
     }
@@ -180,6 +234,9 @@ impl TryFrom<u8> for ResourceField {
             0x1c => Ok(ResourceField::FileExecutable),
             0x1d => Ok(ResourceField::FileDataEmbedded),
             0x1e => Ok(ResourceField::FileDataUtf8RelativePath),
+            0x1f => Ok(ResourceField::DataCompression),
+            0x20 => Ok(ResourceField::IntegrityDigest),
+            0x21 => Ok(ResourceField::Preload),
             0xff => Ok(ResourceField::EndOfEntry),
             _ => Err("invalid field type"),
         }
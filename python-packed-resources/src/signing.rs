@@ -0,0 +1,271 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Detached signatures over packed resources data.
+
+A signature covers the header and index data of a packed resources data
+structure, as returned by [crate::index_data()]. Blob data is authenticated
+indirectly via the per-resource integrity digests recorded in the index.
+So signed data should be written with integrity digests and loaded via
+[crate::load_resources_verified()].
+
+Signatures are stored separately from the data they sign. The serialized
+form of a signature is:
+
+* An 8 byte header, [SIGNATURE_HEADER_V1].
+* A `u8` denoting the signature algorithm. RSA with SHA-1 is not accepted.
+* A `u32` little endian length of the DER encoded signing certificate,
+  followed by the certificate.
+* A `u32` little endian length of the signature, followed by the signature.
+*/
+
+use {
+    crate::parser::index_data,
+    anyhow::{anyhow, Context, Result},
+    byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt},
+    std::io::{Cursor, Read, Write},
+    x509_certificate::{
+        CapturedX509Certificate, KeyAlgorithm, KeyInfoSigner, SignatureAlgorithm, Signer,
+    },
+};
+
+/// Header value for version 1 of detached packed resources signatures.
+pub const SIGNATURE_HEADER_V1: &[u8] = b"pyemsig\x01";
+
+fn signature_algorithm_to_u8(algorithm: SignatureAlgorithm) -> u8 {
+    match algorithm {
+        SignatureAlgorithm::RsaSha1 => 0x01,
+        SignatureAlgorithm::RsaSha256 => 0x02,
+        SignatureAlgorithm::RsaSha384 => 0x03,
+        SignatureAlgorithm::RsaSha512 => 0x04,
+        SignatureAlgorithm::EcdsaSha256 => 0x05,
+        SignatureAlgorithm::EcdsaSha384 => 0x06,
+        SignatureAlgorithm::Ed25519 => 0x07,
+    }
+}
+
+fn signature_algorithm_from_u8(value: u8) -> Result<SignatureAlgorithm, &'static str> {
+    match value {
+        0x01 => Err("RSA-SHA1 signatures are not accepted"),
+        0x02 => Ok(SignatureAlgorithm::RsaSha256),
+        0x03 => Ok(SignatureAlgorithm::RsaSha384),
+        0x04 => Ok(SignatureAlgorithm::RsaSha512),
+        0x05 => Ok(SignatureAlgorithm::EcdsaSha256),
+        0x06 => Ok(SignatureAlgorithm::EcdsaSha384),
+        0x07 => Ok(SignatureAlgorithm::Ed25519),
+        _ => Err("invalid signature algorithm"),
+    }
+}
+
+/// Produce a detached signature over packed resources data.
+///
+/// `certificate` is the public certificate corresponding to `signer`. It is
+/// embedded in the signature so verifiers can chain it to a trust anchor.
+pub fn sign_packed_resources(
+    data: &[u8],
+    signer: &dyn KeyInfoSigner,
+    certificate: &CapturedX509Certificate,
+) -> Result<Vec<u8>> {
+    if signer.public_key_data()[..] != certificate.public_key_data()[..] {
+        return Err(anyhow!(
+            "signing key does not correspond to signing certificate"
+        ));
+    }
+
+    let message = index_data(data).map_err(|e| anyhow!("{}", e))?;
+
+    let algorithm = signer
+        .signature_algorithm()
+        .context("resolving signature algorithm")?;
+    if algorithm == SignatureAlgorithm::RsaSha1 {
+        return Err(anyhow!("RSA-SHA1 signatures are not accepted"));
+    }
+    let signature: Vec<u8> = signer
+        .try_sign(message)
+        .map_err(|e| anyhow!("error signing packed resources: {}", e))?
+        .into();
+
+    let certificate = certificate.constructed_data();
+
+    let mut res = Vec::new();
+    res.write_all(SIGNATURE_HEADER_V1)?;
+    res.write_u8(signature_algorithm_to_u8(algorithm))?;
+    res.write_u32::<LittleEndian>(
+        u32::try_from(certificate.len()).context("converting certificate length to u32")?,
+    )?;
+    res.write_all(certificate)?;
+    res.write_u32::<LittleEndian>(
+        u32::try_from(signature.len()).context("converting signature length to u32")?,
+    )?;
+    res.write_all(&signature)?;
+
+    Ok(res)
+}
+
+/// Verify a detached signature over packed resources data.
+///
+/// The signing certificate must either be one of `trust_anchors` or be
+/// issued by one of them. Certificate validity periods are not evaluated,
+/// as signed data is expected to remain loadable after the signing
+/// certificate expires.
+///
+/// Returns the signing certificate on success.
+pub fn verify_packed_resources_signature(
+    data: &[u8],
+    signature: &[u8],
+    trust_anchors: &[CapturedX509Certificate],
+) -> Result<CapturedX509Certificate, &'static str> {
+    if signature.len() < SIGNATURE_HEADER_V1.len() {
+        return Err("error reading signature header");
+    }
+    if &signature[0..SIGNATURE_HEADER_V1.len()] != SIGNATURE_HEADER_V1 {
+        return Err("unrecognized signature format");
+    }
+
+    let mut reader = Cursor::new(&signature[SIGNATURE_HEADER_V1.len()..]);
+
+    let algorithm = signature_algorithm_from_u8(
+        reader
+            .read_u8()
+            .map_err(|_| "failed reading signature algorithm")?,
+    )?;
+
+    let certificate_length = reader
+        .read_u32::<LittleEndian>()
+        .map_err(|_| "failed reading certificate length")? as usize;
+    let mut certificate = vec![0; certificate_length];
+    reader
+        .read_exact(&mut certificate)
+        .map_err(|_| "failed reading certificate")?;
+
+    let signature_length = reader
+        .read_u32::<LittleEndian>()
+        .map_err(|_| "failed reading signature length")? as usize;
+    let mut signature = vec![0; signature_length];
+    reader
+        .read_exact(&mut signature)
+        .map_err(|_| "failed reading signature")?;
+
+    if reader.position() as usize != reader.get_ref().len() {
+        return Err("unexpected trailing signature data");
+    }
+
+    let certificate = CapturedX509Certificate::from_der(certificate)
+        .map_err(|_| "failed parsing signing certificate")?;
+
+    if !trust_anchors.iter().any(|anchor| {
+        anchor == &certificate || certificate.verify_signed_by_certificate(anchor).is_ok()
+    }) {
+        return Err("signing certificate is not trusted");
+    }
+
+    let key_algorithm = KeyAlgorithm::try_from(certificate.key_algorithm_oid())
+        .map_err(|_| "unsupported signing key algorithm")?;
+    let verify_algorithm = algorithm
+        .resolve_verification_algorithm(key_algorithm)
+        .map_err(|_| "unsupported signature algorithm")?;
+
+    let message = index_data(data)?;
+
+    certificate
+        .verify_signed_data_with_algorithm(message, &signature, verify_algorithm)
+        .map_err(|_| "packed resources signature verification failed")?;
+
+    Ok(certificate)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{load_resources_verified, resource::Resource, write_packed_resources_v4},
+        std::borrow::Cow,
+        x509_certificate::{testutil::*, X509CertificateBuilder},
+    };
+
+    fn packed_resources() -> Vec<u8> {
+        let resources = vec![Resource {
+            name: Cow::from("foo"),
+            is_python_module: true,
+            in_memory_source: Some(Cow::from(b"import io".to_vec())),
+            ..Resource::<u8>::default()
+        }];
+
+        let mut data = Vec::new();
+        write_packed_resources_v4(&resources, &mut data, None, None, true).unwrap();
+
+        data
+    }
+
+    #[test]
+    fn sign_verify() -> Result<()> {
+        let data = packed_resources();
+        let (cert, key) = self_signed_ed25519_key_pair();
+        let (other_cert, _) = self_signed_ed25519_key_pair();
+
+        let signature = sign_packed_resources(&data, &key, &cert)?;
+        assert_eq!(
+            verify_packed_resources_signature(&data, &signature, &[cert.clone()]),
+            Ok(cert.clone())
+        );
+
+        assert_eq!(
+            verify_packed_resources_signature(&data, &signature, &[other_cert.clone()]),
+            Err("signing certificate is not trusted")
+        );
+        assert!(verify_packed_resources_signature(&data, &signature, &[]).is_err());
+
+        // The signing key must match the certificate.
+        assert!(sign_packed_resources(&data, &key, &other_cert).is_err());
+
+        // RSA-SHA1 signatures are refused.
+        let mut sha1_signature = signature.clone();
+        sha1_signature[SIGNATURE_HEADER_V1.len()] = 0x01;
+        assert_eq!(
+            verify_packed_resources_signature(&data, &sha1_signature, &[cert.clone()]),
+            Err("RSA-SHA1 signatures are not accepted")
+        );
+
+        // Tampering with the index invalidates the signature.
+        let mut tampered = data.clone();
+        let index_length = index_data(&data).unwrap().len();
+        tampered[index_length - 2] ^= 0xff;
+        assert_eq!(
+            verify_packed_resources_signature(&tampered, &signature, &[cert.clone()]),
+            Err("packed resources signature verification failed")
+        );
+
+        // Tampering with blob data is caught by integrity digests.
+        let mut tampered = data.clone();
+        let offset = tampered.len() - 1;
+        tampered[offset] ^= 0xff;
+        assert!(verify_packed_resources_signature(&tampered, &signature, &[cert]).is_ok());
+        assert!(load_resources_verified(&tampered)
+            .unwrap()
+            .any(|resource| resource.is_err()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn issued_by_trust_anchor() -> Result<()> {
+        let data = packed_resources();
+        let (ca_cert, ca_key) = certificate_authority_ed25519();
+
+        let mut builder = X509CertificateBuilder::new(KeyAlgorithm::Ed25519);
+        builder
+            .subject()
+            .append_common_name_utf8_string("signer")
+            .unwrap();
+        let (cert, key) = issue_certificate(&builder, &ca_cert, &ca_key);
+
+        let signature = sign_packed_resources(&data, &key, &cert)?;
+        assert_eq!(
+            verify_packed_resources_signature(&data, &signature, &[ca_cert]),
+            Ok(cert)
+        );
+
+        Ok(())
+    }
+}
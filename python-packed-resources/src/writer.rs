@@ -7,11 +7,15 @@
 use {
     crate::{
         resource::Resource,
-        serialization::{BlobInteriorPadding, BlobSectionField, ResourceField, HEADER_V3},
+        serialization::{
+            BlobInteriorPadding, BlobSectionField, CompressionCodec, ResourceField, HEADER_V3,
+            HEADER_V4, INTEGRITY_DIGEST_LENGTH,
+        },
     },
     anyhow::{anyhow, Context, Result},
     byteorder::{LittleEndian, WriteBytesExt},
-    std::{collections::BTreeMap, io::Write, path::Path},
+    sha2::{Digest, Sha256},
+    std::{borrow::Cow, collections::BTreeMap, io::Write, path::Path},
 };
 
 #[cfg(unix)]
@@ -260,12 +264,52 @@ where
             index += 5;
         }
 
+        if self.data_compression.is_some() {
+            index += 2;
+        }
+
+        if self.preload {
+            index += 1;
+        }
+
         // End of index entry.
         index += 1;
 
         index
     }
 
+    /// Compute the total length of in-memory data attached to this resource.
+    fn in_memory_data_length(&self) -> usize {
+        let mut length = 0;
+
+        for data in [
+            &self.in_memory_source,
+            &self.in_memory_bytecode,
+            &self.in_memory_bytecode_opt1,
+            &self.in_memory_bytecode_opt2,
+            &self.in_memory_extension_module_shared_library,
+            &self.in_memory_shared_library,
+            &self.file_data_embedded,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            length += data.len();
+        }
+
+        for resources in [
+            &self.in_memory_package_resources,
+            &self.in_memory_distribution_resources,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            length += resources.values().map(|value| value.len()).sum::<usize>();
+        }
+
+        length
+    }
+
     /// Compute the length of a field.
     ///
     /// Interior padding is not part of the returned length.
@@ -422,6 +466,9 @@ where
                     0
                 }
             }
+            ResourceField::DataCompression => 0,
+            ResourceField::IntegrityDigest => 0,
+            ResourceField::Preload => 0,
         }
     }
 
@@ -571,6 +618,9 @@ where
                     0
                 }
             }
+            ResourceField::DataCompression => 0,
+            ResourceField::IntegrityDigest => 0,
+            ResourceField::Preload => 0,
         };
 
         let overhead = match padding {
@@ -583,6 +633,15 @@ where
 
     /// Write the version 1 index entry for a resource instance.
     pub fn write_index_v1<W: Write>(&self, dest: &mut W) -> Result<()> {
+        self.write_index_entry(dest, None)
+    }
+
+    /// Write an index entry, optionally recording an integrity digest.
+    fn write_index_entry<W: Write>(
+        &self,
+        dest: &mut W,
+        integrity_digest: Option<&[u8; INTEGRITY_DIGEST_LENGTH]>,
+    ) -> Result<()> {
         let name_len =
             u16::try_from(self.name.as_bytes().len()).context("converting name to u16")?;
 
@@ -849,6 +908,25 @@ where
                 .context("writing file_data_utf_relative_path field")?;
         }
 
+        if let Some(codec) = self.data_compression {
+            dest.write_u8(ResourceField::DataCompression.into())
+                .context("writing data_compression field")?;
+            dest.write_u8(codec.into())
+                .context("writing data_compression value")?;
+        }
+
+        if self.preload {
+            dest.write_u8(ResourceField::Preload.into())
+                .context("writing preload field")?;
+        }
+
+        if let Some(digest) = integrity_digest {
+            dest.write_u8(ResourceField::IntegrityDigest.into())
+                .context("writing integrity_digest field")?;
+            dest.write_all(digest)
+                .context("writing integrity_digest value")?;
+        }
+
         dest.write_u8(ResourceField::EndOfEntry.into())
             .map_err(|_| anyhow!("error writing end of index entry"))?;
 
@@ -856,12 +934,204 @@ where
     }
 }
 
+impl<'a> Resource<'a, u8> {
+    /// Compute the SHA-256 integrity digest of this resource's blob data.
+    ///
+    /// The digest covers every blob referenced by the resource's index entry,
+    /// excluding interior padding, in the order the index references them.
+    pub fn integrity_digest(&self) -> [u8; INTEGRITY_DIGEST_LENGTH] {
+        let mut hasher = Sha256::new();
+
+        hasher.update(self.name.as_bytes());
+
+        for data in [
+            &self.in_memory_source,
+            &self.in_memory_bytecode,
+            &self.in_memory_bytecode_opt1,
+            &self.in_memory_bytecode_opt2,
+            &self.in_memory_extension_module_shared_library,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            hasher.update(data);
+        }
+
+        for resources in [
+            &self.in_memory_package_resources,
+            &self.in_memory_distribution_resources,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            for (key, value) in resources.iter() {
+                hasher.update(key.as_bytes());
+                hasher.update(value);
+            }
+        }
+
+        if let Some(data) = &self.in_memory_shared_library {
+            hasher.update(data);
+        }
+
+        if let Some(names) = &self.shared_library_dependency_names {
+            for name in names {
+                hasher.update(name.as_bytes());
+            }
+        }
+
+        for path in [
+            &self.relative_path_module_source,
+            &self.relative_path_module_bytecode,
+            &self.relative_path_module_bytecode_opt1,
+            &self.relative_path_module_bytecode_opt2,
+            &self.relative_path_extension_module_shared_library,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            hasher.update(path_to_bytes(path));
+        }
+
+        for resources in [
+            &self.relative_path_package_resources,
+            &self.relative_path_distribution_resources,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            for (key, path) in resources.iter() {
+                hasher.update(key.as_bytes());
+                hasher.update(path_to_bytes(path));
+            }
+        }
+
+        if let Some(data) = &self.file_data_embedded {
+            hasher.update(data);
+        }
+
+        if let Some(path) = &self.file_data_utf8_relative_path {
+            hasher.update(path.as_bytes());
+        }
+
+        hasher.finalize().into()
+    }
+}
+
+/// Describes how to compress resource data when writing packed resources.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ResourceCompression {
+    /// The codec to compress data with.
+    pub codec: CompressionCodec,
+
+    /// Resources with less in-memory data than this many bytes aren't compressed.
+    ///
+    /// Small inputs compress poorly and aren't worth the decompression overhead.
+    pub minimum_size: usize,
+}
+
 /// Write packed resources data, version 3.
-#[allow(clippy::cognitive_complexity)]
+///
+/// Resources having compressed data or flagged for preloading can't be
+/// represented in this version and will result in an error. Use
+/// [write_packed_resources_v4()] instead.
 pub fn write_packed_resources_v3<'a, T: AsRef<Resource<'a, u8>>, W: Write>(
     resources: &[T],
     dest: &mut W,
     interior_padding: Option<BlobInteriorPadding>,
+) -> Result<()> {
+    if let Some(resource) = resources
+        .iter()
+        .find(|resource| resource.as_ref().data_compression.is_some())
+    {
+        return Err(anyhow!(
+            "resource {} has compressed data, which requires version 4 of the format",
+            resource.as_ref().name
+        ));
+    }
+
+    if let Some(resource) = resources.iter().find(|resource| resource.as_ref().preload) {
+        return Err(anyhow!(
+            "resource {} is flagged for preloading, which requires version 4 of the format",
+            resource.as_ref().name
+        ));
+    }
+
+    write_packed_resources(resources, dest, interior_padding, false, HEADER_V3)
+}
+
+/// Write packed resources data, version 4.
+///
+/// If `compression` is defined, in-memory data is compressed on a per-resource
+/// basis. Compression is only retained if it makes a resource's data smaller.
+/// This allows readers to decompress resources individually as they are used.
+///
+/// Resources whose data is already compressed are written as-is.
+///
+/// If `integrity_digests` is true, each index entry records a SHA-256 digest
+/// of the resource's blob data (after compression). Readers can verify these
+/// digests via [crate::load_resources_verified()]. Combined with a signature
+/// over the index data, this allows readers to detect tampering with any part
+/// of the data structure.
+///
+/// Resources flagged for preloading are written before all other resources,
+/// retaining their relative order. So their data is contiguous at the start
+/// of each blob section and can be paged in together.
+pub fn write_packed_resources_v4<'a, T: AsRef<Resource<'a, u8>>, W: Write>(
+    resources: &[T],
+    dest: &mut W,
+    interior_padding: Option<BlobInteriorPadding>,
+    compression: Option<ResourceCompression>,
+    integrity_digests: bool,
+) -> Result<()> {
+    let resources = resources
+        .iter()
+        .filter(|resource| resource.as_ref().preload)
+        .chain(
+            resources
+                .iter()
+                .filter(|resource| !resource.as_ref().preload),
+        )
+        .map(|resource| {
+            let resource = resource.as_ref();
+
+            if let Some(compression) = &compression {
+                let length = resource.in_memory_data_length();
+
+                if resource.data_compression.is_none()
+                    && length > 0
+                    && length >= compression.minimum_size
+                {
+                    let compressed = resource
+                        .compress(compression.codec)
+                        .map_err(|e| anyhow!("error compressing {}: {}", resource.name, e))?;
+
+                    if compressed.in_memory_data_length() < length {
+                        return Ok(Cow::Owned(compressed));
+                    }
+                }
+            }
+
+            Ok(Cow::Borrowed(resource))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    write_packed_resources(
+        &resources,
+        dest,
+        interior_padding,
+        integrity_digests,
+        HEADER_V4,
+    )
+}
+
+#[allow(clippy::cognitive_complexity)]
+fn write_packed_resources<'a, T: AsRef<Resource<'a, u8>>, W: Write>(
+    resources: &[T],
+    dest: &mut W,
+    interior_padding: Option<BlobInteriorPadding>,
+    integrity_digests: bool,
+    header: &[u8],
 ) -> Result<()> {
     let mut blob_sections = BTreeMap::new();
 
@@ -906,6 +1176,11 @@ pub fn write_packed_resources_v3<'a, T: AsRef<Resource<'a, u8>>, W: Write>(
         let resource = resource.as_ref();
         resource_index_length += resource.index_v1_length();
 
+        if integrity_digests {
+            // Field type + digest.
+            resource_index_length += 1 + INTEGRITY_DIGEST_LENGTH;
+        }
+
         process_field(&mut blob_sections, resource, ResourceField::Name);
         process_field(&mut blob_sections, resource, ResourceField::InMemorySource);
         process_field(
@@ -1000,7 +1275,7 @@ pub fn write_packed_resources_v3<'a, T: AsRef<Resource<'a, u8>>, W: Write>(
         blob_index_length += section.index_v1_length();
     }
 
-    dest.write_all(HEADER_V3)?;
+    dest.write_all(header)?;
 
     dest.write_u8(blob_section_count)?;
     dest.write_u32::<LittleEndian>(blob_index_length as u32)?;
@@ -1015,7 +1290,13 @@ pub fn write_packed_resources_v3<'a, T: AsRef<Resource<'a, u8>>, W: Write>(
 
     // Write the resources index.
     for resource in resources {
-        resource.as_ref().write_index_v1(dest)?;
+        let resource = resource.as_ref();
+
+        if integrity_digests {
+            resource.write_index_entry(dest, Some(&resource.integrity_digest()))?;
+        } else {
+            resource.write_index_v1(dest)?;
+        }
     }
     dest.write_u8(ResourceField::EndOfIndex.into())?;
 
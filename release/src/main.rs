@@ -54,7 +54,7 @@ static RELEASE_ORDER: Lazy<Vec<&'static str>> = Lazy::new(|| {
         "tugger-binary-analysis",
         "tugger-licensing",
         "tugger-licensing-net",
-        //"tugger-rpm",
+        "tugger-rpm",
         "tugger-snapcraft",
         "tugger-apple",
         "apple-codesign",
@@ -0,0 +1,1019 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Build RPM repositories from `.rpm` files. */
+
+use {
+    crate::{
+        error::{Result, RpmRepositoryError},
+        io::{read_compressed, Compression, ContentDigest, DigestFlavor},
+        metadata::{
+            filelists::{self, Filelists},
+            other::{self, ChangelogEntry, Other},
+            primary::{
+                self, Entries, FileEntry, HeaderRange, PackageEntry, PackageFormat, PackageSize,
+                PackageTime, PackageVersion, Primary,
+            },
+            repomd::{Location, RepoMd, RepoMdData},
+        },
+        package::{tags, RpmHeader, RpmPackageHeaders},
+        RepositoryWriter,
+    },
+    futures::{AsyncRead, AsyncReadExt},
+    std::{
+        collections::BTreeMap,
+        path::{Path, PathBuf},
+        pin::Pin,
+        time::SystemTime,
+    },
+};
+
+/// File mode bits denoting the type of file.
+const S_IFMT: u64 = 0o170000;
+
+/// File mode bits of a directory.
+const S_IFDIR: u64 = 0o040000;
+
+/// File flag denoting a ghost file (not part of the payload).
+const RPMFILE_GHOST: u64 = 1 << 6;
+
+/// Dependency flags denoting a version comparison.
+const RPMSENSE_LESS: u64 = 1 << 1;
+const RPMSENSE_GREATER: u64 = 1 << 2;
+const RPMSENSE_EQUAL: u64 = 1 << 3;
+
+/// Dependency flags denoting a requirement needed before install.
+const RPMSENSE_PREREQ: u64 = (1 << 6) | (1 << 9) | (1 << 10);
+
+/// Path of the directory holding metadata files, relative to the repository root.
+pub const REPODATA_DIR: &str = "repodata";
+
+/// Represents a repository publishing event.
+///
+/// Instances are sent to callbacks during repository writing to inform of activity.
+pub enum PublishEvent {
+    /// A package with the given path and size was written.
+    PackageWritten(String, u64),
+
+    /// A metadata file with the given path and size was written.
+    MetadataFileWritten(String, u64),
+}
+
+impl std::fmt::Display for PublishEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PackageWritten(path, size) => {
+                write!(f, "wrote {} bytes to {}", size, path)
+            }
+            Self::MetadataFileWritten(path, size) => {
+                write!(f, "wrote {} bytes to {}", size, path)
+            }
+        }
+    }
+}
+
+/// Where the content of a package comes from.
+enum PackageSource {
+    Data(Vec<u8>),
+    Path(PathBuf),
+}
+
+/// A package registered with a [RepositoryBuilder].
+struct BuilderPackage {
+    source: PackageSource,
+    primary: primary::Package,
+    filelists: filelists::Package,
+    other: other::Package,
+}
+
+/// Build RPM repositories from scratch.
+///
+/// Instances of this type are used to iteratively construct an RPM repository
+/// (the equivalent of running `createrepo`).
+///
+/// # Usage
+///
+/// Instances are constructed via [Self::default()]. By default SHA-256 checksums and
+/// gzip compression are used for metadata files and metadata filenames are prefixed
+/// with their content digest. These settings can be changed via:
+///
+/// * [Self::set_checksum()]
+/// * [Self::set_compression()]
+/// * [Self::set_unique_md_filenames()]
+/// * [Self::set_revision()]
+/// * [Self::set_timestamp()]
+///
+/// `.rpm` packages are then registered against the builder via [Self::add_rpm_data()]
+/// or [Self::add_rpm_path()]. Packages produced in-memory (e.g. by
+/// `tugger_rpm::RpmBuilder`) can be registered by passing their serialized bytes to
+/// [Self::add_rpm_data()].
+///
+/// Once everything is registered, call [Self::publish()] to write packages, `primary`,
+/// `filelists`, and `other` metadata, and finally `repomd.xml` through a
+/// [RepositoryWriter].
+pub struct RepositoryBuilder {
+    packages: BTreeMap<String, BuilderPackage>,
+    checksum: DigestFlavor,
+    compression: Compression,
+    unique_md_filenames: bool,
+    revision: Option<String>,
+    timestamp: Option<u64>,
+}
+
+impl Default for RepositoryBuilder {
+    fn default() -> Self {
+        Self {
+            packages: BTreeMap::new(),
+            checksum: DigestFlavor::Sha256,
+            compression: Compression::Gzip,
+            unique_md_filenames: true,
+            revision: None,
+            timestamp: None,
+        }
+    }
+}
+
+impl RepositoryBuilder {
+    /// Set the content digest type used for packages and metadata files.
+    pub fn set_checksum(&mut self, flavor: DigestFlavor) {
+        self.checksum = flavor;
+    }
+
+    /// Set the compression format of metadata files.
+    pub fn set_compression(&mut self, compression: Compression) {
+        self.compression = compression;
+    }
+
+    /// Set whether metadata filenames are prefixed with their content digest.
+    pub fn set_unique_md_filenames(&mut self, value: bool) {
+        self.unique_md_filenames = value;
+    }
+
+    /// Set the revision of the repository.
+    ///
+    /// If not set, the timestamp is used.
+    pub fn set_revision(&mut self, value: impl ToString) {
+        self.revision = Some(value.to_string());
+    }
+
+    /// Set the timestamp of metadata files, as seconds since UNIX epoch.
+    ///
+    /// If not set, the current time at publish time is used.
+    pub fn set_timestamp(&mut self, value: u64) {
+        self.timestamp = Some(value);
+    }
+
+    /// Register an `.rpm` package given its content.
+    ///
+    /// `location` is the path of the package relative to the repository root.
+    /// `file_time` is the modified time of the package file, as seconds since
+    /// UNIX epoch.
+    pub fn add_rpm_data(
+        &mut self,
+        location: impl ToString,
+        data: Vec<u8>,
+        file_time: u64,
+    ) -> Result<()> {
+        let headers = RpmPackageHeaders::from_data(&data)?;
+        let digest = ContentDigest::digest_data(self.checksum, &data);
+        let size = data.len() as u64;
+
+        self.add_package(
+            location.to_string(),
+            PackageSource::Data(data),
+            &headers,
+            digest,
+            size,
+            file_time,
+        )
+    }
+
+    /// Register an `.rpm` package stored on the filesystem.
+    ///
+    /// `location` is the path of the package relative to the repository root.
+    ///
+    /// The file is read to derive metadata and again at publish time.
+    pub fn add_rpm_path(&mut self, location: impl ToString, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let path_error = |e| RpmRepositoryError::IoPath(format!("{}", path.display()), e);
+
+        let data = std::fs::read(path).map_err(path_error)?;
+        let file_time = std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .map_err(path_error)?
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let headers = RpmPackageHeaders::from_data(&data)?;
+        let digest = ContentDigest::digest_data(self.checksum, &data);
+
+        self.add_package(
+            location.to_string(),
+            PackageSource::Path(path.to_path_buf()),
+            &headers,
+            digest,
+            data.len() as u64,
+            file_time,
+        )
+    }
+
+    fn add_package(
+        &mut self,
+        location: String,
+        source: PackageSource,
+        headers: &RpmPackageHeaders,
+        digest: ContentDigest,
+        package_size: u64,
+        file_time: u64,
+    ) -> Result<()> {
+        if self.packages.contains_key(&location) {
+            return Err(RpmRepositoryError::DuplicatePackage(location));
+        }
+
+        let header = &headers.header;
+        let pkg_id = digest.digest_hex();
+
+        let version = PackageVersion {
+            epoch: headers.epoch(),
+            version: headers.version()?.to_string(),
+            release: headers.release()?.to_string(),
+        };
+
+        let files = headers
+            .files()?
+            .into_iter()
+            .map(|(path, mode, flags)| FileEntry {
+                file_type: if flags & RPMFILE_GHOST != 0 {
+                    Some("ghost".to_string())
+                } else if mode & S_IFMT == S_IFDIR {
+                    Some("dir".to_string())
+                } else {
+                    None
+                },
+                value: path,
+            })
+            .collect::<Vec<_>>();
+
+        let primary = primary::Package {
+            package_type: "rpm".to_string(),
+            name: headers.name()?.to_string(),
+            arch: headers.arch()?.to_string(),
+            version: version.clone(),
+            checksum: primary::Checksum {
+                name: digest.digest_type().name().to_string(),
+                value: pkg_id.clone(),
+                pkg_id: Some("YES".to_string()),
+            },
+            summary: header.string(tags::SUMMARY).unwrap_or_default().to_string(),
+            description: header
+                .string(tags::DESCRIPTION)
+                .unwrap_or_default()
+                .to_string(),
+            packager: header.string(tags::PACKAGER).map(|x| x.to_string()),
+            url: header.string(tags::URL).map(|x| x.to_string()),
+            time: PackageTime {
+                file: file_time,
+                build: header.integer(tags::BUILDTIME).unwrap_or(0),
+            },
+            size: PackageSize {
+                package: package_size,
+                installed: headers.installed_size(),
+                archive: headers.archive_size(),
+            },
+            location: Location {
+                href: location.clone(),
+            },
+            format: Some(PackageFormat {
+                license: header.string(tags::LICENSE).map(|x| x.to_string()),
+                vendor: header.string(tags::VENDOR).map(|x| x.to_string()),
+                group: header.string(tags::GROUP).map(|x| x.to_string()),
+                build_host: header.string(tags::BUILDHOST).map(|x| x.to_string()),
+                source_rpm: header.string(tags::SOURCERPM).map(|x| x.to_string()),
+                header_range: Some(HeaderRange {
+                    start: headers.header_start,
+                    end: headers.header_end,
+                }),
+                provides: dependency_entries(
+                    header,
+                    tags::PROVIDENAME,
+                    tags::PROVIDEFLAGS,
+                    tags::PROVIDEVERSION,
+                ),
+                obsoletes: dependency_entries(
+                    header,
+                    tags::OBSOLETENAME,
+                    tags::OBSOLETEFLAGS,
+                    tags::OBSOLETEVERSION,
+                ),
+                requires: dependency_entries(
+                    header,
+                    tags::REQUIRENAME,
+                    tags::REQUIREFLAGS,
+                    tags::REQUIREVERSION,
+                ),
+                conflicts: dependency_entries(
+                    header,
+                    tags::CONFLICTNAME,
+                    tags::CONFLICTFLAGS,
+                    tags::CONFLICTVERSION,
+                ),
+                suggests: dependency_entries(
+                    header,
+                    tags::SUGGESTNAME,
+                    tags::SUGGESTFLAGS,
+                    tags::SUGGESTVERSION,
+                ),
+                recommends: dependency_entries(
+                    header,
+                    tags::RECOMMENDNAME,
+                    tags::RECOMMENDFLAGS,
+                    tags::RECOMMENDVERSION,
+                ),
+                supplements: dependency_entries(
+                    header,
+                    tags::SUPPLEMENTNAME,
+                    tags::SUPPLEMENTFLAGS,
+                    tags::SUPPLEMENTVERSION,
+                ),
+                // Like createrepo, only files commonly depended on are advertised
+                // in primary metadata. The full list is in filelists.
+                files: files
+                    .iter()
+                    .filter(|f| is_primary_file(&f.value))
+                    .cloned()
+                    .collect(),
+            }),
+        };
+
+        let filelists = filelists::Package {
+            pkg_id: pkg_id.clone(),
+            name: primary.name.clone(),
+            arch: primary.arch.clone(),
+            version: version.clone(),
+            files,
+        };
+
+        let changelog_times = header.integers(tags::CHANGELOGTIME);
+        let changelog_names = header.string_array(tags::CHANGELOGNAME);
+        let changelog_texts = header.string_array(tags::CHANGELOGTEXT);
+
+        // Changelogs are stored newest first. Metadata records them oldest first.
+        let changelogs = changelog_times
+            .iter()
+            .zip(changelog_names.iter())
+            .zip(changelog_texts.iter())
+            .rev()
+            .map(|((date, author), text)| ChangelogEntry {
+                author: author.clone(),
+                date: *date,
+                text: text.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        let other = other::Package {
+            pkg_id,
+            name: primary.name.clone(),
+            arch: primary.arch.clone(),
+            version,
+            changelogs,
+        };
+
+        self.packages.insert(
+            location,
+            BuilderPackage {
+                source,
+                primary,
+                filelists,
+                other,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Obtain the `primary.xml` document describing registered packages.
+    pub fn primary(&self) -> Primary {
+        Primary {
+            count: self.packages.len(),
+            packages: self.packages.values().map(|p| p.primary.clone()).collect(),
+        }
+    }
+
+    /// Obtain the `filelists.xml` document describing registered packages.
+    pub fn filelists(&self) -> Filelists {
+        Filelists {
+            count: self.packages.len(),
+            packages: self
+                .packages
+                .values()
+                .map(|p| p.filelists.clone())
+                .collect(),
+        }
+    }
+
+    /// Obtain the `other.xml` document describing registered packages.
+    pub fn other(&self) -> Other {
+        Other {
+            count: self.packages.len(),
+            packages: self.packages.values().map(|p| p.other.clone()).collect(),
+        }
+    }
+
+    /// Write registered packages to a [RepositoryWriter].
+    pub async fn publish_packages<F>(
+        &self,
+        writer: &(impl RepositoryWriter + ?Sized),
+        progress_cb: &Option<F>,
+    ) -> Result<()>
+    where
+        F: Fn(PublishEvent),
+    {
+        for (location, package) in &self.packages {
+            let reader: Pin<Box<dyn AsyncRead + Send>> = match &package.source {
+                PackageSource::Data(data) => Box::pin(futures::io::Cursor::new(data.as_slice())),
+                PackageSource::Path(path) => {
+                    let fh = std::fs::File::open(path).map_err(|e| {
+                        RpmRepositoryError::IoPath(format!("{}", path.display()), e)
+                    })?;
+
+                    Box::pin(futures::io::AllowStdIo::new(fh))
+                }
+            };
+
+            let write = writer.write_path(location.clone(), reader).await?;
+
+            if let Some(cb) = progress_cb {
+                cb(PublishEvent::PackageWritten(
+                    write.path,
+                    write.bytes_written,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write metadata files to a [RepositoryWriter].
+    ///
+    /// `primary`, `filelists`, and `other` files are written first, followed by
+    /// `repomd.xml`. Returns the `repomd.xml` data structure that was written.
+    pub async fn publish_metadata<F>(
+        &self,
+        writer: &(impl RepositoryWriter + ?Sized),
+        progress_cb: &Option<F>,
+    ) -> Result<RepoMd>
+    where
+        F: Fn(PublishEvent),
+    {
+        let timestamp = self.timestamp.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        });
+
+        let mut primary = vec![];
+        self.primary().to_writer(&mut primary)?;
+        let mut filelists = vec![];
+        self.filelists().to_writer(&mut filelists)?;
+        let mut other = vec![];
+        self.other().to_writer(&mut other)?;
+
+        let mut data = vec![];
+
+        for (data_type, content) in [
+            ("primary", primary),
+            ("filelists", filelists),
+            ("other", other),
+        ] {
+            let open_digest = ContentDigest::digest_data(self.checksum, &content);
+            let open_size = content.len() as u64;
+
+            let mut compressed = vec![];
+            read_compressed(futures::io::Cursor::new(content), self.compression)
+                .read_to_end(&mut compressed)
+                .await?;
+
+            let digest = ContentDigest::digest_data(self.checksum, &compressed);
+
+            let filename = format!("{}.xml{}", data_type, self.compression.extension());
+            let path = if self.unique_md_filenames {
+                format!("{}/{}-{}", REPODATA_DIR, digest.digest_hex(), filename)
+            } else {
+                format!("{}/{}", REPODATA_DIR, filename)
+            };
+
+            let write = writer
+                .write_path(path.clone(), Box::pin(futures::io::Cursor::new(compressed)))
+                .await?;
+
+            if let Some(cb) = progress_cb {
+                cb(PublishEvent::MetadataFileWritten(
+                    write.path,
+                    write.bytes_written,
+                ));
+            }
+
+            data.push(RepoMdData {
+                data_type: data_type.to_string(),
+                checksum: (&digest).into(),
+                location: Location { href: path },
+                size: Some(write.bytes_written),
+                timestamp: Some(timestamp),
+                open_checksum: Some((&open_digest).into()),
+                open_size: Some(open_size),
+                header_checksum: None,
+                header_size: None,
+            });
+        }
+
+        let repomd = RepoMd {
+            revision: self
+                .revision
+                .clone()
+                .unwrap_or_else(|| timestamp.to_string()),
+            data,
+        };
+
+        let mut repomd_data = vec![];
+        repomd.to_writer(&mut repomd_data)?;
+
+        let write = writer
+            .write_path(
+                format!("{}/repomd.xml", REPODATA_DIR),
+                Box::pin(futures::io::Cursor::new(repomd_data)),
+            )
+            .await?;
+
+        if let Some(cb) = progress_cb {
+            cb(PublishEvent::MetadataFileWritten(
+                write.path,
+                write.bytes_written,
+            ));
+        }
+
+        Ok(repomd)
+    }
+
+    /// Publish the repository to a [RepositoryWriter].
+    ///
+    /// This writes all packages followed by all metadata files. Metadata is
+    /// written last so readers never observe metadata referring to missing packages.
+    pub async fn publish<F>(
+        &self,
+        writer: &(impl RepositoryWriter + ?Sized),
+        progress_cb: &Option<F>,
+    ) -> Result<RepoMd>
+    where
+        F: Fn(PublishEvent),
+    {
+        self.publish_packages(writer, progress_cb).await?;
+        self.publish_metadata(writer, progress_cb).await
+    }
+}
+
+/// Whether a file path should be advertised in primary metadata.
+fn is_primary_file(path: &str) -> bool {
+    path.starts_with("/etc/") || path.contains("bin/") || path == "/usr/lib/sendmail"
+}
+
+/// Resolve dependency entries from parallel name, flags, and version header arrays.
+fn dependency_entries(
+    header: &RpmHeader,
+    name_tag: u32,
+    flags_tag: u32,
+    version_tag: u32,
+) -> Option<Entries> {
+    let names = header.string_array(name_tag);
+    let flags = header.integers(flags_tag);
+    let versions = header.string_array(version_tag);
+
+    let entries = names
+        .iter()
+        .enumerate()
+        // rpmlib() dependencies are internal to RPM and not advertised.
+        .filter(|(_, name)| !name.starts_with("rpmlib("))
+        .map(|(i, name)| {
+            let flags = flags.get(i).copied().unwrap_or(0);
+            let version = versions.get(i).map(|x| x.as_str()).unwrap_or_default();
+
+            let mut entry = PackageEntry {
+                name: name.clone(),
+                flags: None,
+                epoch: None,
+                version: None,
+                release: None,
+                pre: if flags & RPMSENSE_PREREQ != 0 {
+                    Some(1)
+                } else {
+                    None
+                },
+            };
+
+            if !version.is_empty() {
+                entry.flags = match flags & (RPMSENSE_LESS | RPMSENSE_GREATER | RPMSENSE_EQUAL) {
+                    RPMSENSE_LESS => Some("LT"),
+                    RPMSENSE_GREATER => Some("GT"),
+                    RPMSENSE_EQUAL => Some("EQ"),
+                    x if x == RPMSENSE_LESS | RPMSENSE_EQUAL => Some("LE"),
+                    x if x == RPMSENSE_GREATER | RPMSENSE_EQUAL => Some("GE"),
+                    _ => None,
+                }
+                .map(|x| x.to_string());
+
+                let (epoch, version) = match version.split_once(':') {
+                    Some((epoch, version)) => (epoch.parse::<u64>().ok(), version),
+                    None => (None, version),
+                };
+                let (version, release) = match version.split_once('-') {
+                    Some((version, release)) => (version, Some(release.to_string())),
+                    None => (version, None),
+                };
+
+                entry.epoch = Some(epoch.unwrap_or(0));
+                entry.version = Some(version.to_string());
+                entry.release = release;
+            }
+
+            entry
+        })
+        .collect::<Vec<_>>();
+
+    if entries.is_empty() {
+        None
+    } else {
+        Some(Entries { entries })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::{
+            io::read_decompressed, package::HeaderValue, DataResolver, MetadataReader,
+            RepositoryWrite,
+        },
+        std::{collections::HashMap, future::Future, sync::Mutex},
+    };
+
+    /// A [RepositoryWriter] that records written data in memory.
+    #[derive(Default)]
+    struct CapturingWriter {
+        paths: Mutex<HashMap<String, Vec<u8>>>,
+    }
+
+    impl RepositoryWriter for CapturingWriter {
+        fn write_path<'a>(
+            &'a self,
+            path: String,
+            mut reader: Pin<Box<dyn AsyncRead + Send + 'a>>,
+        ) -> Pin<Box<dyn Future<Output = Result<RepositoryWrite>> + Send + 'a>> {
+            Box::pin(async move {
+                let mut data = vec![];
+                reader.read_to_end(&mut data).await?;
+                let bytes_written = data.len() as u64;
+
+                self.paths.lock().unwrap().insert(path.clone(), data);
+
+                Ok(RepositoryWrite {
+                    path,
+                    bytes_written,
+                })
+            })
+        }
+    }
+
+    fn encode_header(entries: &[(u32, HeaderValue)]) -> Vec<u8> {
+        let mut index = vec![];
+        let mut store = vec![];
+
+        for (tag, value) in entries {
+            let (value_type, count, data, align) = match value {
+                HeaderValue::Int32(v) => (
+                    4u32,
+                    v.len(),
+                    v.iter().flat_map(|x| x.to_be_bytes()).collect::<Vec<_>>(),
+                    4,
+                ),
+                HeaderValue::Int16(v) => (
+                    3,
+                    v.len(),
+                    v.iter().flat_map(|x| x.to_be_bytes()).collect(),
+                    2,
+                ),
+                HeaderValue::String(s) => (6, 1, format!("{}\0", s).into_bytes(), 1),
+                HeaderValue::StringArray(v) => (
+                    8,
+                    v.len(),
+                    v.iter()
+                        .flat_map(|s| format!("{}\0", s).into_bytes())
+                        .collect(),
+                    1,
+                ),
+                _ => panic!("unsupported value type"),
+            };
+
+            store.resize(store.len() + (align - store.len() % align) % align, 0);
+
+            index.extend_from_slice(&tag.to_be_bytes());
+            index.extend_from_slice(&value_type.to_be_bytes());
+            index.extend_from_slice(&(store.len() as u32).to_be_bytes());
+            index.extend_from_slice(&(count as u32).to_be_bytes());
+            store.extend(data);
+        }
+
+        let mut res = vec![0x8e, 0xad, 0xe8, 0x01, 0, 0, 0, 0];
+        res.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+        res.extend_from_slice(&(store.len() as u32).to_be_bytes());
+        res.extend(index);
+        res.extend(store);
+
+        res
+    }
+
+    fn test_rpm() -> Vec<u8> {
+        let mut lead = vec![0u8; 96];
+        lead[0..4].copy_from_slice(&[0xed, 0xab, 0xee, 0xdb]);
+
+        let signature = encode_header(&[(1007, HeaderValue::Int32(vec![4096]))]);
+
+        let header = encode_header(&[
+            (tags::NAME, HeaderValue::String("hello".into())),
+            (tags::VERSION, HeaderValue::String("1.0".into())),
+            (tags::RELEASE, HeaderValue::String("1".into())),
+            (tags::SUMMARY, HeaderValue::String("says hello".into())),
+            (
+                tags::DESCRIPTION,
+                HeaderValue::String("a friendly program".into()),
+            ),
+            (tags::BUILDTIME, HeaderValue::Int32(vec![1600000000])),
+            (tags::SIZE, HeaderValue::Int32(vec![1234])),
+            (tags::LICENSE, HeaderValue::String("MPL-2.0".into())),
+            (tags::ARCH, HeaderValue::String("x86_64".into())),
+            (tags::FILEMODES, HeaderValue::Int16(vec![0o40755, 0o100755])),
+            (tags::FILEFLAGS, HeaderValue::Int32(vec![0, 0])),
+            (
+                tags::SOURCERPM,
+                HeaderValue::String("hello-1.0-1.src.rpm".into()),
+            ),
+            (
+                tags::PROVIDENAME,
+                HeaderValue::StringArray(vec!["hello".into()]),
+            ),
+            (tags::REQUIREFLAGS, HeaderValue::Int32(vec![12, 16777226])),
+            (
+                tags::REQUIRENAME,
+                HeaderValue::StringArray(vec!["libc".into(), "rpmlib(Foo)".into()]),
+            ),
+            (
+                tags::REQUIREVERSION,
+                HeaderValue::StringArray(vec!["1:2.0-3".into(), "4.0".into()]),
+            ),
+            (tags::CHANGELOGTIME, HeaderValue::Int32(vec![200, 100])),
+            (
+                tags::CHANGELOGNAME,
+                HeaderValue::StringArray(vec!["Bob".into(), "Alice".into()]),
+            ),
+            (
+                tags::CHANGELOGTEXT,
+                HeaderValue::StringArray(vec!["- second".into(), "- first".into()]),
+            ),
+            (tags::PROVIDEFLAGS, HeaderValue::Int32(vec![8])),
+            (
+                tags::PROVIDEVERSION,
+                HeaderValue::StringArray(vec!["1.0-1".into()]),
+            ),
+            (tags::DIRINDEXES, HeaderValue::Int32(vec![0, 1])),
+            (
+                tags::BASENAMES,
+                HeaderValue::StringArray(vec!["hello".into(), "hello".into()]),
+            ),
+            (
+                tags::DIRNAMES,
+                HeaderValue::StringArray(vec!["/usr/share/".into(), "/usr/bin/".into()]),
+            ),
+        ]);
+
+        let mut res = lead;
+        res.extend(&signature);
+        res.resize(res.len() + (8 - res.len() % 8) % 8, 0);
+        res.extend(header);
+        res.extend(b"payload");
+
+        res
+    }
+
+    #[test]
+    fn parse_headers() -> Result<()> {
+        let data = test_rpm();
+        let headers = RpmPackageHeaders::from_data(&data)?;
+
+        assert_eq!(headers.name()?, "hello");
+        assert_eq!(headers.epoch(), 0);
+        assert_eq!(headers.arch()?, "x86_64");
+        assert_eq!(headers.archive_size(), 4096);
+        assert_eq!(headers.header_end as usize, data.len() - b"payload".len());
+        assert_eq!(
+            headers.files()?,
+            vec![
+                ("/usr/share/hello".to_string(), 0o40755, 0),
+                ("/usr/bin/hello".to_string(), 0o100755, 0),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn package_metadata() -> Result<()> {
+        let mut builder = RepositoryBuilder::default();
+        builder.add_rpm_data("Packages/hello-1.0-1.x86_64.rpm", test_rpm(), 42)?;
+
+        assert!(matches!(
+            builder.add_rpm_data("Packages/hello-1.0-1.x86_64.rpm", test_rpm(), 42),
+            Err(RpmRepositoryError::DuplicatePackage(_))
+        ));
+
+        let primary = builder.primary();
+        assert_eq!(primary.count, 1);
+        let package = &primary.packages[0];
+        assert_eq!(package.name, "hello");
+        assert_eq!(package.version.version, "1.0");
+        assert_eq!(package.time.file, 42);
+        assert_eq!(package.size.installed, 1234);
+        assert_eq!(package.checksum.value.len(), 64);
+
+        let format = package.format.as_ref().unwrap();
+        assert_eq!(format.license.as_deref(), Some("MPL-2.0"));
+        assert_eq!(format.files.len(), 1);
+        assert_eq!(format.files[0].value, "/usr/bin/hello");
+
+        let requires = &format.requires.as_ref().unwrap().entries;
+        assert_eq!(requires.len(), 1);
+        assert_eq!(requires[0].name, "libc");
+        assert_eq!(requires[0].flags.as_deref(), Some("GE"));
+        assert_eq!(requires[0].epoch, Some(1));
+        assert_eq!(requires[0].version.as_deref(), Some("2.0"));
+        assert_eq!(requires[0].release.as_deref(), Some("3"));
+
+        let filelists = builder.filelists();
+        assert_eq!(filelists.packages[0].files.len(), 2);
+        assert_eq!(
+            filelists.packages[0].files[0].file_type.as_deref(),
+            Some("dir")
+        );
+
+        let other = builder.other();
+        let changelogs = &other.packages[0].changelogs;
+        assert_eq!(changelogs.len(), 2);
+        assert_eq!(changelogs[0].author, "Alice");
+        assert_eq!(changelogs[1].date, 200);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn publish() -> Result<()> {
+        let mut builder = RepositoryBuilder::default();
+        builder.set_timestamp(1700000000);
+        builder.add_rpm_data("Packages/hello-1.0-1.x86_64.rpm", test_rpm(), 42)?;
+
+        let writer = CapturingWriter::default();
+        let events = Mutex::new(vec![]);
+        let cb = Some(|event: PublishEvent| events.lock().unwrap().push(event.to_string()));
+
+        let repomd = builder.publish(&writer, &cb).await?;
+        assert_eq!(repomd.revision, "1700000000");
+        assert_eq!(repomd.data.len(), 3);
+        assert_eq!(events.lock().unwrap().len(), 5);
+
+        let paths = writer.paths.into_inner().unwrap();
+        assert_eq!(paths["Packages/hello-1.0-1.x86_64.rpm"], test_rpm());
+
+        let parsed = RepoMd::from_reader(std::io::Cursor::new(&paths["repodata/repomd.xml"]))?;
+        assert_eq!(parsed.data.len(), 3);
+
+        for entry in parsed.data {
+            assert!(entry.location.href.ends_with(".xml.gz"));
+
+            let data = &paths[&entry.location.href];
+            assert_eq!(Some(data.len() as u64), entry.size);
+            assert_eq!(
+                ContentDigest::digest_data(DigestFlavor::Sha256, data),
+                ContentDigest::try_from(entry.checksum)?
+            );
+
+            let mut decompressed = vec![];
+            read_decompressed(futures::io::Cursor::new(data), Compression::Gzip)
+                .read_to_end(&mut decompressed)
+                .await?;
+            assert_eq!(Some(decompressed.len() as u64), entry.open_size);
+
+            if entry.data_type == "primary" {
+                let primary = Primary::from_reader(std::io::Cursor::new(decompressed))?;
+                assert_eq!(primary.count, 1);
+                assert_eq!(primary.packages[0].name, "hello");
+                assert_eq!(
+                    primary.packages[0].location.href,
+                    "Packages/hello-1.0-1.x86_64.rpm"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// A [MetadataReader] serving content written by a [CapturingWriter].
+    struct CapturedMetadataReader {
+        paths: HashMap<String, Vec<u8>>,
+        repomd: RepoMd,
+    }
+
+    impl DataResolver for CapturedMetadataReader {
+        fn get_path(
+            &self,
+            path: String,
+        ) -> Pin<Box<dyn Future<Output = Result<Pin<Box<dyn AsyncRead + Send>>>> + Send + '_>>
+        {
+            Box::pin(async move {
+                let data = self.paths.get(&path).cloned().ok_or_else(|| {
+                    RpmRepositoryError::IoPath(
+                        path.clone(),
+                        std::io::Error::from(std::io::ErrorKind::NotFound),
+                    )
+                })?;
+
+                Ok(Box::pin(futures::io::Cursor::new(data)) as Pin<Box<dyn AsyncRead + Send>>)
+            })
+        }
+    }
+
+    impl MetadataReader for CapturedMetadataReader {
+        fn url(&self) -> Result<url::Url> {
+            Ok(url::Url::parse("memory://")?)
+        }
+
+        fn root_relative_path(&self) -> &str {
+            ""
+        }
+
+        fn repomd(&self) -> &RepoMd {
+            &self.repomd
+        }
+    }
+
+    #[tokio::test]
+    async fn publish_read_back() -> Result<()> {
+        let mut builder = RepositoryBuilder::default();
+        builder.set_compression(Compression::Xz);
+        builder.add_rpm_data("Packages/hello-1.0-1.x86_64.rpm", test_rpm(), 42)?;
+
+        let writer = CapturingWriter::default();
+        let repomd = builder
+            .publish(&writer, &None::<Box<dyn Fn(PublishEvent)>>)
+            .await?;
+
+        let mut reader = CapturedMetadataReader {
+            paths: writer.paths.into_inner().unwrap(),
+            repomd,
+        };
+
+        let primary = reader.primary_packages().await?;
+        assert_eq!(primary.packages[0].name, "hello");
+
+        let filelists = reader.filelists().await?;
+        assert_eq!(filelists.packages[0].files.len(), 2);
+        assert_eq!(
+            filelists.packages[0].pkg_id,
+            primary.packages[0].checksum.value
+        );
+
+        let other = reader.other().await?;
+        assert_eq!(other.packages[0].changelogs.len(), 2);
+
+        assert!(matches!(
+            reader.updateinfo().await,
+            Err(RpmRepositoryError::MetadataFileNotFound("updateinfo"))
+        ));
+
+        // Tampering with content should be detected.
+        let other_path = reader
+            .repomd
+            .data
+            .iter()
+            .find(|entry| entry.data_type == "other")
+            .unwrap()
+            .location
+            .href
+            .clone();
+        let data = reader.paths.get_mut(&other_path).unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+        assert!(reader.other().await.is_err());
+
+        Ok(())
+    }
+}
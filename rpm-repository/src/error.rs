@@ -16,6 +16,12 @@ pub enum RpmRepositoryError {
     #[error("XML error: {0:?}")]
     Xml(#[from] serde_xml_rs::Error),
 
+    #[error("XML write error: {0:?}")]
+    XmlWrite(#[from] xml::writer::Error),
+
+    #[error("I/O error: {0:?}")]
+    Io(#[from] std::io::Error),
+
     #[error("repository I/O error on path {0}: {1:?}")]
     IoPath(String, std::io::Error),
 
@@ -33,6 +39,12 @@ pub enum RpmRepositoryError {
 
     #[error("content size missing from metadata entry")]
     MetadataMissingSize,
+
+    #[error("RPM parse error: {0}")]
+    RpmParse(String),
+
+    #[error("package already present in repository: {0}")]
+    DuplicatePackage(String),
 }
 
 /// Result type for this crate.
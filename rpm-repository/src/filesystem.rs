@@ -0,0 +1,72 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Filesystem based RPM repositories. */
+
+use {
+    crate::{
+        error::{Result, RpmRepositoryError},
+        RepositoryWrite, RepositoryWriter,
+    },
+    futures::AsyncRead,
+    std::{
+        future::Future,
+        path::{Path, PathBuf},
+        pin::Pin,
+    },
+};
+
+/// A writable RPM repository backed by a filesystem.
+#[derive(Clone, Debug)]
+pub struct FilesystemRepositoryWriter {
+    root_dir: PathBuf,
+}
+
+impl FilesystemRepositoryWriter {
+    /// Construct a new instance, bound to the root directory specified.
+    ///
+    /// No validation of the passed path is performed. The directory does not need to exist.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            root_dir: path.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl RepositoryWriter for FilesystemRepositoryWriter {
+    fn write_path<'a>(
+        &'a self,
+        path: String,
+        reader: Pin<Box<dyn AsyncRead + Send + 'a>>,
+    ) -> Pin<Box<dyn Future<Output = Result<RepositoryWrite>> + Send + 'a>> {
+        async fn run(
+            root_dir: &Path,
+            path: String,
+            reader: Pin<Box<dyn AsyncRead + Send + '_>>,
+        ) -> Result<RepositoryWrite> {
+            let dest_path = root_dir.join(&path);
+
+            if let Some(parent) = dest_path.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| RpmRepositoryError::IoPath(format!("{}", parent.display()), e))?;
+            }
+
+            let fh = std::fs::File::create(&dest_path)
+                .map_err(|e| RpmRepositoryError::IoPath(format!("{}", dest_path.display()), e))?;
+
+            let mut writer = futures::io::AllowStdIo::new(fh);
+
+            let bytes_written = futures::io::copy(reader, &mut writer)
+                .await
+                .map_err(|e| RpmRepositoryError::IoPath(format!("{}", dest_path.display()), e))?;
+
+            Ok(RepositoryWrite {
+                path,
+                bytes_written,
+            })
+        }
+
+        Box::pin(run(&self.root_dir, path, reader))
+    }
+}
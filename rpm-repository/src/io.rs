@@ -4,7 +4,9 @@
 
 use {
     crate::error::{Result, RpmRepositoryError},
-    async_compression::futures::bufread::{GzipDecoder, XzDecoder, ZstdDecoder},
+    async_compression::futures::bufread::{
+        GzipDecoder, GzipEncoder, XzDecoder, XzEncoder, ZstdDecoder, ZstdEncoder,
+    },
     futures::{AsyncBufRead, AsyncRead},
     pin_project::pin_project,
     std::{
@@ -15,6 +17,7 @@ use {
 };
 
 /// Compression format.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Compression {
     /// No compression.
     None,
@@ -26,6 +29,30 @@ pub enum Compression {
     Zstd,
 }
 
+impl Compression {
+    /// Resolve the compression format from a filename extension.
+    pub fn from_path(path: &str) -> Self {
+        match path {
+            _ if path.ends_with(".gz") => Self::Gzip,
+            _ if path.ends_with(".xz") => Self::Xz,
+            _ if path.ends_with(".zst") => Self::Zstd,
+            _ => Self::None,
+        }
+    }
+
+    /// Filename extension for files in this compression format.
+    ///
+    /// Includes the leading period. Empty string for [Self::None].
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::None => "",
+            Self::Gzip => ".gz",
+            Self::Xz => ".xz",
+            Self::Zstd => ".zst",
+        }
+    }
+}
+
 pub fn read_decompressed<'a>(
     stream: impl AsyncBufRead + Send + 'a,
     compression: Compression,
@@ -38,11 +65,43 @@ pub fn read_decompressed<'a>(
     }
 }
 
+/// Obtain a reader that compresses data from a stream.
+pub fn read_compressed<'a>(
+    stream: impl AsyncBufRead + Send + 'a,
+    compression: Compression,
+) -> Pin<Box<dyn AsyncRead + Send + 'a>> {
+    match compression {
+        Compression::None => Box::pin(stream),
+        Compression::Gzip => Box::pin(GzipEncoder::new(stream)),
+        Compression::Xz => Box::pin(XzEncoder::new(stream)),
+        Compression::Zstd => Box::pin(ZstdEncoder::new(stream)),
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum DigestFlavor {
     Sha1,
     Sha256,
 }
 
+impl DigestFlavor {
+    /// The name of this digest as used in repository metadata.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sha1 => "sha1",
+            Self::Sha256 => "sha256",
+        }
+    }
+
+    /// Create a new hasher for this digest type.
+    pub fn new_hasher(&self) -> Box<dyn digest::DynDigest + Send> {
+        match self {
+            Self::Sha1 => Box::new(sha1::Sha1::default()),
+            Self::Sha256 => Box::new(sha2::Sha256::default()),
+        }
+    }
+}
+
 /// Represents a content digest.
 #[derive(Clone, Eq, PartialEq, PartialOrd)]
 pub enum ContentDigest {
@@ -83,12 +142,21 @@ impl ContentDigest {
         })
     }
 
+    /// Compute the content digest of data.
+    pub fn digest_data(flavor: DigestFlavor, data: &[u8]) -> Self {
+        let mut hasher = flavor.new_hasher();
+        hasher.update(data);
+        let digest = hasher.finalize().to_vec();
+
+        match flavor {
+            DigestFlavor::Sha1 => Self::Sha1(digest),
+            DigestFlavor::Sha256 => Self::Sha256(digest),
+        }
+    }
+
     /// Create a new hasher matching for the type of this digest.
     pub fn new_hasher(&self) -> Box<dyn digest::DynDigest + Send> {
-        match self {
-            Self::Sha1(_) => Box::new(sha1::Sha1::default()),
-            Self::Sha256(_) => Box::new(sha2::Sha256::default()),
-        }
+        self.digest_type().new_hasher()
     }
 
     /// Obtain the digest bytes for this content digest.
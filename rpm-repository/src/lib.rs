@@ -21,12 +21,20 @@ via a parsed `repomd.xml` file.
 Concrete repository readers exist. [http::HttpRepositoryClient] provides a reader
 for repositories accessed via HTTP.
 
+Repositories can also be created. [builder::RepositoryBuilder] derives repository
+metadata from `.rpm` files and publishes packages and metadata through a
+[RepositoryWriter]. [filesystem::FilesystemRepositoryWriter] provides a writer
+for repositories stored on the local filesystem.
+
 */
 
+pub mod builder;
 pub mod error;
+pub mod filesystem;
 pub mod http;
 pub mod io;
 pub mod metadata;
+pub mod package;
 
 pub use crate::error::{Result, RpmRepositoryError};
 
@@ -34,8 +42,11 @@ use {
     crate::{
         io::{read_decompressed, Compression, ContentDigest, ContentValidatingReader},
         metadata::{
+            filelists::Filelists,
+            other::Other,
             primary::Primary,
             repomd::{RepoMd, RepoMdData},
+            updateinfo::UpdateInfo,
         },
     },
     futures::{AsyncRead, AsyncReadExt},
@@ -195,11 +206,7 @@ pub trait MetadataReader: DataResolver + Sync {
             let expected_size = data.size.ok_or(RpmRepositoryError::MetadataMissingSize)?;
             let expected_digest = ContentDigest::try_from(data.checksum.clone())?;
 
-            let compression = match path {
-                _ if path.ends_with(".gz") => Compression::Gzip,
-                _ if path.ends_with(".xz") => Compression::Xz,
-                _ => Compression::None,
-            };
+            let compression = Compression::from_path(path);
 
             slf.get_path_decompressed_with_digest_verification(
                 path.to_string(),
@@ -213,27 +220,113 @@ pub trait MetadataReader: DataResolver + Sync {
         Box::pin(run(self, data))
     }
 
+    /// Fetch the decoded content of the metadata file having the given data type.
+    ///
+    /// The size and content digest advertised in `repomd.xml` are verified.
     #[allow(clippy::type_complexity)]
-    fn primary_packages(&self) -> Pin<Box<dyn Future<Output = Result<Primary>> + Send + '_>> {
-        async fn run(slf: &(impl MetadataReader + ?Sized)) -> Result<Primary> {
-            let primary = slf
+    fn fetch_data_type_content(
+        &self,
+        data_type: &'static str,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>>> + Send + '_>> {
+        async fn run(
+            slf: &(impl MetadataReader + ?Sized),
+            data_type: &'static str,
+        ) -> Result<Vec<u8>> {
+            let entry = slf
                 .repomd()
                 .data
                 .iter()
-                .find(|entry| entry.data_type == "primary")
-                .ok_or(RpmRepositoryError::MetadataFileNotFound("primary"))?;
+                .find(|entry| entry.data_type == data_type)
+                .ok_or(RpmRepositoryError::MetadataFileNotFound(data_type))?;
 
-            let mut reader = slf.fetch_data_file(primary).await?;
+            let mut reader = slf.fetch_data_file(entry).await?;
             let mut data = vec![];
 
             reader
                 .read_to_end(&mut data)
                 .await
-                .map_err(|e| RpmRepositoryError::IoPath(primary.location.href.clone(), e))?;
+                .map_err(|e| RpmRepositoryError::IoPath(entry.location.href.clone(), e))?;
+
+            Ok(data)
+        }
+
+        Box::pin(run(self, data_type))
+    }
+
+    /// Fetch and parse the `primary` metadata file.
+    #[allow(clippy::type_complexity)]
+    fn primary_packages(&self) -> Pin<Box<dyn Future<Output = Result<Primary>> + Send + '_>> {
+        async fn run(slf: &(impl MetadataReader + ?Sized)) -> Result<Primary> {
+            let data = slf.fetch_data_type_content("primary").await?;
 
             Primary::from_reader(std::io::Cursor::new(data))
         }
 
         Box::pin(run(self))
     }
+
+    /// Fetch and parse the `filelists` metadata file.
+    #[allow(clippy::type_complexity)]
+    fn filelists(&self) -> Pin<Box<dyn Future<Output = Result<Filelists>> + Send + '_>> {
+        async fn run(slf: &(impl MetadataReader + ?Sized)) -> Result<Filelists> {
+            let data = slf.fetch_data_type_content("filelists").await?;
+
+            Filelists::from_reader(std::io::Cursor::new(data))
+        }
+
+        Box::pin(run(self))
+    }
+
+    /// Fetch and parse the `other` metadata file.
+    #[allow(clippy::type_complexity)]
+    fn other(&self) -> Pin<Box<dyn Future<Output = Result<Other>> + Send + '_>> {
+        async fn run(slf: &(impl MetadataReader + ?Sized)) -> Result<Other> {
+            let data = slf.fetch_data_type_content("other").await?;
+
+            Other::from_reader(std::io::Cursor::new(data))
+        }
+
+        Box::pin(run(self))
+    }
+
+    /// Fetch and parse the `updateinfo` metadata file.
+    ///
+    /// Not all repositories have this file.
+    #[allow(clippy::type_complexity)]
+    fn updateinfo(&self) -> Pin<Box<dyn Future<Output = Result<UpdateInfo>> + Send + '_>> {
+        async fn run(slf: &(impl MetadataReader + ?Sized)) -> Result<UpdateInfo> {
+            let data = slf.fetch_data_type_content("updateinfo").await?;
+
+            UpdateInfo::from_reader(std::io::Cursor::new(data))
+        }
+
+        Box::pin(run(self))
+    }
+}
+
+/// Describes a path written by a [RepositoryWriter].
+#[derive(Clone, Debug)]
+pub struct RepositoryWrite {
+    /// The path that was written.
+    pub path: String,
+    /// The number of bytes written.
+    pub bytes_written: u64,
+}
+
+/// An interface for writing to an RPM repository.
+///
+/// From the perspective of this trait, writing to a repository is a matter of
+/// storing data under a path relative to the repository root. Logic about what
+/// to write where is implemented elsewhere. This allows repositories to be
+/// published to arbitrary storage backends.
+pub trait RepositoryWriter: Sync {
+    /// Write data to a given path.
+    ///
+    /// The data to write is provided by an [AsyncRead] reader.
+    #[allow(clippy::type_complexity)]
+    fn write_path<'a>(
+        &'a self,
+        path: String,
+        reader: Pin<Box<dyn AsyncRead + Send + 'a>>,
+    ) -> Pin<Box<dyn Future<Output = Result<RepositoryWrite>> + Send + 'a>>;
 }
@@ -0,0 +1,163 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! `filelists.xml` file format. */
+
+use {
+    crate::{
+        error::Result,
+        metadata::{
+            new_xml_writer,
+            primary::{FileEntry, PackageVersion},
+            FILELISTS_NAMESPACE,
+        },
+    },
+    serde::{Deserialize, Serialize},
+    std::io::{Read, Write},
+    xml::writer::{EventWriter, XmlEvent},
+};
+
+/// A `filelists.xml` file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Filelists {
+    /// The number of packages expressed by this document.
+    #[serde(rename = "packages")]
+    pub count: usize,
+
+    /// `<package>` elements in this document.
+    #[serde(default, rename = "package")]
+    pub packages: Vec<Package>,
+}
+
+impl Filelists {
+    /// Construct an instance by parsing XML from a reader.
+    pub fn from_reader(reader: impl Read) -> Result<Self> {
+        Ok(serde_xml_rs::from_reader(reader)?)
+    }
+
+    /// Construct an instance by parsing XML from a string.
+    pub fn from_xml(s: &str) -> Result<Self> {
+        Ok(serde_xml_rs::from_str(s)?)
+    }
+
+    /// Write this document as XML to a writer.
+    pub fn to_writer(&self, writer: impl Write) -> Result<()> {
+        let mut writer = new_xml_writer(writer);
+
+        let count = self.count.to_string();
+        writer.write(
+            XmlEvent::start_element("filelists")
+                .default_ns(FILELISTS_NAMESPACE)
+                .attr("packages", &count),
+        )?;
+
+        for package in &self.packages {
+            package.write_xml(&mut writer)?;
+        }
+
+        writer.write(XmlEvent::end_element())?;
+
+        Ok(())
+    }
+}
+
+/// A package as advertised in a `filelists.xml` file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Package {
+    /// Content digest of package file.
+    ///
+    /// Corresponds to the checksum in `primary.xml`.
+    #[serde(rename = "pkgid")]
+    pub pkg_id: String,
+
+    /// The name of the package.
+    pub name: String,
+
+    /// The machine architecture the package is targeting.
+    pub arch: String,
+
+    /// The package version.
+    pub version: PackageVersion,
+
+    /// Files provided by this package.
+    #[serde(default, rename = "file")]
+    pub files: Vec<FileEntry>,
+}
+
+impl Package {
+    fn write_xml<W: Write>(&self, writer: &mut EventWriter<W>) -> Result<()> {
+        writer.write(
+            XmlEvent::start_element("package")
+                .attr("pkgid", &self.pkg_id)
+                .attr("name", &self.name)
+                .attr("arch", &self.arch),
+        )?;
+
+        self.version.write_xml(writer)?;
+
+        for file in &self.files {
+            file.write_xml(writer)?;
+        }
+
+        writer.write(XmlEvent::end_element())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const FILELISTS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<filelists xmlns="http://linux.duke.edu/metadata/filelists" packages="2">
+<package pkgid="a3b2c1" name="bash" arch="x86_64">
+  <version epoch="0" ver="5.1.8" rel="2.fc35"/>
+  <file type="dir">/etc/skel</file>
+  <file>/etc/skel/.bashrc</file>
+  <file>/usr/bin/bash</file>
+  <file type="ghost">/var/log/bash.log</file>
+</package>
+<package pkgid="d4e5f6" name="filesystem-content" arch="noarch">
+  <version epoch="1" ver="3.14" rel="7.fc35"/>
+</package>
+</filelists>
+"#;
+
+    #[test]
+    fn parse() -> Result<()> {
+        let filelists = Filelists::from_xml(FILELISTS_XML)?;
+
+        assert_eq!(filelists.count, 2);
+        assert_eq!(filelists.packages.len(), 2);
+
+        let bash = &filelists.packages[0];
+        assert_eq!(bash.pkg_id, "a3b2c1");
+        assert_eq!(bash.version.version, "5.1.8");
+        assert_eq!(bash.files.len(), 4);
+        assert_eq!(bash.files[0].file_type.as_deref(), Some("dir"));
+        assert_eq!(bash.files[2].file_type, None);
+        assert_eq!(bash.files[2].value, "/usr/bin/bash");
+
+        assert_eq!(filelists.packages[1].version.epoch, 1);
+        assert!(filelists.packages[1].files.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_roundtrip() -> Result<()> {
+        let filelists = Filelists::from_xml(FILELISTS_XML)?;
+
+        let mut data = vec![];
+        filelists.to_writer(&mut data)?;
+        let parsed = Filelists::from_reader(std::io::Cursor::new(data))?;
+
+        assert_eq!(parsed.count, 2);
+        assert_eq!(parsed.packages[0].files.len(), 4);
+        assert_eq!(parsed.packages[0].files[3].value, "/var/log/bash.log");
+
+        Ok(())
+    }
+}
@@ -6,7 +6,72 @@
 
 RPM repositories define metadata via a series of XML files. This module defines these
 XML data structures.
+
+Types representing whole documents can be serialized back to XML via their `to_writer()`
+methods. Serialization is performed manually (as opposed to via serde) because the
+documents make heavy use of XML attributes and namespaces.
 */
 
+pub mod filelists;
+pub mod other;
 pub mod primary;
 pub mod repomd;
+pub mod updateinfo;
+
+use {
+    crate::error::Result,
+    std::io::Write,
+    xml::{
+        writer::{EventWriter, XmlEvent},
+        EmitterConfig,
+    },
+};
+
+/// XML namespace of `primary.xml` documents.
+pub const COMMON_NAMESPACE: &str = "http://linux.duke.edu/metadata/common";
+
+/// XML namespace of RPM specific elements.
+pub const RPM_NAMESPACE: &str = "http://linux.duke.edu/metadata/rpm";
+
+/// XML namespace of `filelists.xml` documents.
+pub const FILELISTS_NAMESPACE: &str = "http://linux.duke.edu/metadata/filelists";
+
+/// XML namespace of `other.xml` documents.
+pub const OTHER_NAMESPACE: &str = "http://linux.duke.edu/metadata/other";
+
+/// XML namespace of `repomd.xml` documents.
+pub const REPO_NAMESPACE: &str = "http://linux.duke.edu/metadata/repo";
+
+pub(crate) fn new_xml_writer<W: Write>(writer: W) -> EventWriter<W> {
+    EmitterConfig::new()
+        .perform_indent(true)
+        .create_writer(writer)
+}
+
+/// Write an element containing only text.
+pub(crate) fn write_text_element<W: Write>(
+    writer: &mut EventWriter<W>,
+    name: &str,
+    value: &str,
+) -> Result<()> {
+    writer.write(XmlEvent::start_element(name))?;
+    if !value.is_empty() {
+        writer.write(XmlEvent::characters(value))?;
+    }
+    writer.write(XmlEvent::end_element())?;
+
+    Ok(())
+}
+
+/// Write an element containing only text, if a value is present.
+pub(crate) fn write_optional_text_element<W: Write>(
+    writer: &mut EventWriter<W>,
+    name: &str,
+    value: &Option<String>,
+) -> Result<()> {
+    if let Some(value) = value {
+        write_text_element(writer, name, value)?;
+    }
+
+    Ok(())
+}
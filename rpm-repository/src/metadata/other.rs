@@ -0,0 +1,157 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! `other.xml` file format. */
+
+use {
+    crate::{
+        error::Result,
+        metadata::{new_xml_writer, primary::PackageVersion, OTHER_NAMESPACE},
+    },
+    serde::{Deserialize, Serialize},
+    std::io::{Read, Write},
+    xml::writer::{EventWriter, XmlEvent},
+};
+
+/// An `other.xml` file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Other {
+    /// The number of packages expressed by this document.
+    #[serde(rename = "packages")]
+    pub count: usize,
+
+    /// `<package>` elements in this document.
+    #[serde(default, rename = "package")]
+    pub packages: Vec<Package>,
+}
+
+impl Other {
+    /// Construct an instance by parsing XML from a reader.
+    pub fn from_reader(reader: impl Read) -> Result<Self> {
+        Ok(serde_xml_rs::from_reader(reader)?)
+    }
+
+    /// Construct an instance by parsing XML from a string.
+    pub fn from_xml(s: &str) -> Result<Self> {
+        Ok(serde_xml_rs::from_str(s)?)
+    }
+
+    /// Write this document as XML to a writer.
+    pub fn to_writer(&self, writer: impl Write) -> Result<()> {
+        let mut writer = new_xml_writer(writer);
+
+        let count = self.count.to_string();
+        writer.write(
+            XmlEvent::start_element("otherdata")
+                .default_ns(OTHER_NAMESPACE)
+                .attr("packages", &count),
+        )?;
+
+        for package in &self.packages {
+            package.write_xml(&mut writer)?;
+        }
+
+        writer.write(XmlEvent::end_element())?;
+
+        Ok(())
+    }
+}
+
+/// A package as advertised in an `other.xml` file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Package {
+    /// Content digest of package file.
+    ///
+    /// Corresponds to the checksum in `primary.xml`.
+    #[serde(rename = "pkgid")]
+    pub pkg_id: String,
+
+    /// The name of the package.
+    pub name: String,
+
+    /// The machine architecture the package is targeting.
+    pub arch: String,
+
+    /// The package version.
+    pub version: PackageVersion,
+
+    /// Changelog entries for this package.
+    #[serde(default, rename = "changelog")]
+    pub changelogs: Vec<ChangelogEntry>,
+}
+
+impl Package {
+    fn write_xml<W: Write>(&self, writer: &mut EventWriter<W>) -> Result<()> {
+        writer.write(
+            XmlEvent::start_element("package")
+                .attr("pkgid", &self.pkg_id)
+                .attr("name", &self.name)
+                .attr("arch", &self.arch),
+        )?;
+
+        self.version.write_xml(writer)?;
+
+        for entry in &self.changelogs {
+            let date = entry.date.to_string();
+            writer.write(
+                XmlEvent::start_element("changelog")
+                    .attr("author", &entry.author)
+                    .attr("date", &date),
+            )?;
+            writer.write(XmlEvent::characters(&entry.text))?;
+            writer.write(XmlEvent::end_element())?;
+        }
+
+        writer.write(XmlEvent::end_element())?;
+
+        Ok(())
+    }
+}
+
+/// A changelog entry for a package.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ChangelogEntry {
+    /// Author of the change.
+    pub author: String,
+
+    /// Time of the change as seconds since UNIX epoch.
+    pub date: u64,
+
+    /// Text describing the change.
+    #[serde(rename = "$value")]
+    pub text: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const OTHER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<otherdata xmlns="http://linux.duke.edu/metadata/other" packages="1">
+<package pkgid="a3b2c1" name="bash" arch="x86_64">
+  <version epoch="0" ver="5.1.8" rel="2.fc35"/>
+  <changelog author="Kamil Dudka &lt;kdudka@redhat.com&gt; - 5.1.8-1" date="1627041600">- new upstream release</changelog>
+  <changelog author="Siteshwar Vashisht &lt;svashisht@redhat.com&gt; - 5.1.8-2" date="1627387200">- Fix a crash</changelog>
+</package>
+</otherdata>
+"#;
+
+    #[test]
+    fn parse() -> Result<()> {
+        let other = Other::from_xml(OTHER_XML)?;
+
+        assert_eq!(other.count, 1);
+        let package = &other.packages[0];
+        assert_eq!(package.name, "bash");
+        assert_eq!(package.changelogs.len(), 2);
+        assert_eq!(
+            package.changelogs[0].author,
+            "Kamil Dudka <kdudka@redhat.com> - 5.1.8-1"
+        );
+        assert_eq!(package.changelogs[1].date, 1627387200);
+        assert_eq!(package.changelogs[1].text, "- Fix a crash");
+
+        Ok(())
+    }
+}
@@ -8,10 +8,14 @@ use {
     crate::{
         error::{Result, RpmRepositoryError},
         io::ContentDigest,
-        metadata::repomd::Location,
+        metadata::{
+            new_xml_writer, repomd::Location, write_optional_text_element, write_text_element,
+            COMMON_NAMESPACE, RPM_NAMESPACE,
+        },
     },
     serde::{Deserialize, Serialize},
-    std::io::Read,
+    std::io::{Read, Write},
+    xml::writer::{EventWriter, XmlEvent},
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,7 +25,7 @@ pub struct Primary {
     pub count: usize,
 
     /// `<package>` elements in this document.
-    #[serde(rename = "package")]
+    #[serde(default, rename = "package")]
     pub packages: Vec<Package>,
 }
 
@@ -35,6 +39,27 @@ impl Primary {
     pub fn from_xml(s: &str) -> Result<Self> {
         Ok(serde_xml_rs::from_str(s)?)
     }
+
+    /// Write this document as XML to a writer.
+    pub fn to_writer(&self, writer: impl Write) -> Result<()> {
+        let mut writer = new_xml_writer(writer);
+
+        let count = self.count.to_string();
+        writer.write(
+            XmlEvent::start_element("metadata")
+                .default_ns(COMMON_NAMESPACE)
+                .ns("rpm", RPM_NAMESPACE)
+                .attr("packages", &count),
+        )?;
+
+        for package in &self.packages {
+            package.write_xml(&mut writer)?;
+        }
+
+        writer.write(XmlEvent::end_element())?;
+
+        Ok(())
+    }
 }
 
 /// A package as advertised in a `primary.xml` file.
@@ -83,6 +108,60 @@ pub struct Package {
     pub format: Option<PackageFormat>,
 }
 
+impl Package {
+    fn write_xml<W: Write>(&self, writer: &mut EventWriter<W>) -> Result<()> {
+        writer.write(XmlEvent::start_element("package").attr("type", &self.package_type))?;
+
+        write_text_element(writer, "name", &self.name)?;
+        write_text_element(writer, "arch", &self.arch)?;
+        self.version.write_xml(writer)?;
+
+        let mut checksum = XmlEvent::start_element("checksum").attr("type", &self.checksum.name);
+        if let Some(pkg_id) = &self.checksum.pkg_id {
+            checksum = checksum.attr("pkgid", pkg_id);
+        }
+        writer.write(checksum)?;
+        writer.write(XmlEvent::characters(&self.checksum.value))?;
+        writer.write(XmlEvent::end_element())?;
+
+        write_text_element(writer, "summary", &self.summary)?;
+        write_text_element(writer, "description", &self.description)?;
+        write_optional_text_element(writer, "packager", &self.packager)?;
+        write_optional_text_element(writer, "url", &self.url)?;
+
+        let file = self.time.file.to_string();
+        let build = self.time.build.to_string();
+        writer.write(
+            XmlEvent::start_element("time")
+                .attr("file", &file)
+                .attr("build", &build),
+        )?;
+        writer.write(XmlEvent::end_element())?;
+
+        let package = self.size.package.to_string();
+        let installed = self.size.installed.to_string();
+        let archive = self.size.archive.to_string();
+        writer.write(
+            XmlEvent::start_element("size")
+                .attr("package", &package)
+                .attr("installed", &installed)
+                .attr("archive", &archive),
+        )?;
+        writer.write(XmlEvent::end_element())?;
+
+        writer.write(XmlEvent::start_element("location").attr("href", &self.location.href))?;
+        writer.write(XmlEvent::end_element())?;
+
+        if let Some(format) = &self.format {
+            format.write_xml(writer)?;
+        }
+
+        writer.write(XmlEvent::end_element())?;
+
+        Ok(())
+    }
+}
+
 /// Describes a package version.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct PackageVersion {
@@ -98,6 +177,21 @@ pub struct PackageVersion {
     pub release: String,
 }
 
+impl PackageVersion {
+    pub(crate) fn write_xml<W: Write>(&self, writer: &mut EventWriter<W>) -> Result<()> {
+        let epoch = self.epoch.to_string();
+        writer.write(
+            XmlEvent::start_element("version")
+                .attr("epoch", &epoch)
+                .attr("ver", &self.version)
+                .attr("rel", &self.release),
+        )?;
+        writer.write(XmlEvent::end_element())?;
+
+        Ok(())
+    }
+}
+
 /// Describes the content checksum of a package.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Checksum {
@@ -192,6 +286,51 @@ pub struct PackageFormat {
     pub files: Vec<FileEntry>,
 }
 
+impl PackageFormat {
+    fn write_xml<W: Write>(&self, writer: &mut EventWriter<W>) -> Result<()> {
+        writer.write(XmlEvent::start_element("format"))?;
+
+        write_optional_text_element(writer, "rpm:license", &self.license)?;
+        write_optional_text_element(writer, "rpm:vendor", &self.vendor)?;
+        write_optional_text_element(writer, "rpm:group", &self.group)?;
+        write_optional_text_element(writer, "rpm:buildhost", &self.build_host)?;
+        write_optional_text_element(writer, "rpm:sourcerpm", &self.source_rpm)?;
+
+        if let Some(range) = &self.header_range {
+            let start = range.start.to_string();
+            let end = range.end.to_string();
+            writer.write(
+                XmlEvent::start_element("rpm:header-range")
+                    .attr("start", &start)
+                    .attr("end", &end),
+            )?;
+            writer.write(XmlEvent::end_element())?;
+        }
+
+        for (name, entries) in [
+            ("rpm:provides", &self.provides),
+            ("rpm:requires", &self.requires),
+            ("rpm:conflicts", &self.conflicts),
+            ("rpm:obsoletes", &self.obsoletes),
+            ("rpm:suggests", &self.suggests),
+            ("rpm:recommends", &self.recommends),
+            ("rpm:supplements", &self.supplements),
+        ] {
+            if let Some(entries) = entries {
+                entries.write_xml(writer, name)?;
+            }
+        }
+
+        for file in &self.files {
+            file.write_xml(writer)?;
+        }
+
+        writer.write(XmlEvent::end_element())?;
+
+        Ok(())
+    }
+}
+
 /// Describes the location of a header in a package.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct HeaderRange {
@@ -209,6 +348,41 @@ pub struct Entries {
     pub entries: Vec<PackageEntry>,
 }
 
+impl Entries {
+    fn write_xml<W: Write>(&self, writer: &mut EventWriter<W>, name: &str) -> Result<()> {
+        writer.write(XmlEvent::start_element(name))?;
+
+        for entry in &self.entries {
+            let epoch = entry.epoch.map(|x| x.to_string());
+            let pre = entry.pre.map(|x| x.to_string());
+
+            let mut element = XmlEvent::start_element("rpm:entry").attr("name", &entry.name);
+            if let Some(flags) = &entry.flags {
+                element = element.attr("flags", flags);
+            }
+            if let Some(epoch) = &epoch {
+                element = element.attr("epoch", epoch);
+            }
+            if let Some(version) = &entry.version {
+                element = element.attr("ver", version);
+            }
+            if let Some(release) = &entry.release {
+                element = element.attr("rel", release);
+            }
+            if let Some(pre) = &pre {
+                element = element.attr("pre", pre);
+            }
+
+            writer.write(element)?;
+            writer.write(XmlEvent::end_element())?;
+        }
+
+        writer.write(XmlEvent::end_element())?;
+
+        Ok(())
+    }
+}
+
 /// Describes a package relationship.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct PackageEntry {
@@ -244,3 +418,18 @@ pub struct FileEntry {
     #[serde(rename = "$value")]
     pub value: String,
 }
+
+impl FileEntry {
+    pub(crate) fn write_xml<W: Write>(&self, writer: &mut EventWriter<W>) -> Result<()> {
+        let mut element = XmlEvent::start_element("file");
+        if let Some(file_type) = &self.file_type {
+            element = element.attr("type", file_type);
+        }
+
+        writer.write(element)?;
+        writer.write(XmlEvent::characters(&self.value))?;
+        writer.write(XmlEvent::end_element())?;
+
+        Ok(())
+    }
+}
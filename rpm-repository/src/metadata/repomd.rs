@@ -8,9 +8,11 @@ use {
     crate::{
         error::{Result, RpmRepositoryError},
         io::ContentDigest,
+        metadata::{new_xml_writer, write_text_element, REPO_NAMESPACE, RPM_NAMESPACE},
     },
     serde::{Deserialize, Serialize},
-    std::io::Read,
+    std::io::{Read, Write},
+    xml::writer::{EventWriter, XmlEvent},
 };
 
 /// A `repomd.xml` file.
@@ -34,6 +36,27 @@ impl RepoMd {
     pub fn from_xml(s: &str) -> Result<Self> {
         Ok(serde_xml_rs::from_str(s)?)
     }
+
+    /// Write this document as XML to a writer.
+    pub fn to_writer(&self, writer: impl Write) -> Result<()> {
+        let mut writer = new_xml_writer(writer);
+
+        writer.write(
+            XmlEvent::start_element("repomd")
+                .default_ns(REPO_NAMESPACE)
+                .ns("rpm", RPM_NAMESPACE),
+        )?;
+
+        write_text_element(&mut writer, "revision", &self.revision)?;
+
+        for data in &self.data {
+            data.write_xml(&mut writer)?;
+        }
+
+        writer.write(XmlEvent::end_element())?;
+
+        Ok(())
+    }
 }
 
 /// A `<data>` element in a `repomd.xml` file.
@@ -64,6 +87,38 @@ pub struct RepoMdData {
     pub header_size: Option<u64>,
 }
 
+impl RepoMdData {
+    fn write_xml<W: Write>(&self, writer: &mut EventWriter<W>) -> Result<()> {
+        writer.write(XmlEvent::start_element("data").attr("type", &self.data_type))?;
+
+        self.checksum.write_xml(writer, "checksum")?;
+        if let Some(checksum) = &self.open_checksum {
+            checksum.write_xml(writer, "open-checksum")?;
+        }
+        if let Some(checksum) = &self.header_checksum {
+            checksum.write_xml(writer, "header-checksum")?;
+        }
+
+        writer.write(XmlEvent::start_element("location").attr("href", &self.location.href))?;
+        writer.write(XmlEvent::end_element())?;
+
+        for (name, value) in [
+            ("timestamp", self.timestamp),
+            ("size", self.size),
+            ("open-size", self.open_size),
+            ("header-size", self.header_size),
+        ] {
+            if let Some(value) = value {
+                write_text_element(writer, name, &value.to_string())?;
+            }
+        }
+
+        writer.write(XmlEvent::end_element())?;
+
+        Ok(())
+    }
+}
+
 /// The content checksum of a `<data>` element.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Checksum {
@@ -75,6 +130,25 @@ pub struct Checksum {
     pub value: String,
 }
 
+impl Checksum {
+    fn write_xml<W: Write>(&self, writer: &mut EventWriter<W>, element: &str) -> Result<()> {
+        writer.write(XmlEvent::start_element(element).attr("type", &self.name))?;
+        writer.write(XmlEvent::characters(&self.value))?;
+        writer.write(XmlEvent::end_element())?;
+
+        Ok(())
+    }
+}
+
+impl From<&ContentDigest> for Checksum {
+    fn from(digest: &ContentDigest) -> Self {
+        Self {
+            name: digest.digest_type().name().to_string(),
+            value: digest.digest_hex(),
+        }
+    }
+}
+
 impl TryFrom<Checksum> for ContentDigest {
     type Error = RpmRepositoryError;
 
@@ -0,0 +1,245 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! `updateinfo.xml` file format.
+
+This file describes advisories (security fixes, bug fixes, enhancements)
+and the packages that address them.
+*/
+
+use {
+    crate::{error::Result, metadata::repomd::Checksum},
+    serde::{Deserialize, Serialize},
+    std::io::Read,
+};
+
+/// An `updateinfo.xml` file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateInfo {
+    /// `<update>` elements in this document.
+    #[serde(default, rename = "update")]
+    pub updates: Vec<Update>,
+}
+
+impl UpdateInfo {
+    /// Construct an instance by parsing XML from a reader.
+    pub fn from_reader(reader: impl Read) -> Result<Self> {
+        Ok(serde_xml_rs::from_reader(reader)?)
+    }
+
+    /// Construct an instance by parsing XML from a string.
+    pub fn from_xml(s: &str) -> Result<Self> {
+        Ok(serde_xml_rs::from_str(s)?)
+    }
+}
+
+/// An advisory in an `updateinfo.xml` file.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Update {
+    /// Entity that issued the advisory.
+    pub from: Option<String>,
+
+    /// Status of the advisory.
+    ///
+    /// e.g. `stable` or `testing`.
+    pub status: Option<String>,
+
+    /// The type of advisory.
+    ///
+    /// e.g. `security`, `bugfix`, `enhancement`, or `newpackage`.
+    #[serde(rename = "type")]
+    pub update_type: Option<String>,
+
+    /// Version of the advisory format.
+    pub version: Option<String>,
+
+    /// Unique identifier of the advisory.
+    pub id: String,
+
+    /// Title of the advisory.
+    pub title: Option<String>,
+
+    /// When the advisory was issued.
+    pub issued: Option<UpdateDate>,
+
+    /// When the advisory was last updated.
+    pub updated: Option<UpdateDate>,
+
+    /// Copyright statement.
+    pub rights: Option<String>,
+
+    /// Name of the release the advisory applies to.
+    pub release: Option<String>,
+
+    /// Severity of the advisory.
+    pub severity: Option<String>,
+
+    /// A short summary of the advisory.
+    pub summary: Option<String>,
+
+    /// A longer description of the advisory.
+    pub description: Option<String>,
+
+    /// Instructions for applying the update.
+    pub solution: Option<String>,
+
+    /// External references (bug trackers, CVEs, etc).
+    pub references: Option<References>,
+
+    /// Packages addressing the advisory.
+    #[serde(rename = "pkglist")]
+    pub package_list: Option<PackageList>,
+}
+
+/// A date in an advisory.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct UpdateDate {
+    /// The date value.
+    ///
+    /// Usually of the form `YYYY-MM-DD HH:MM:SS`. But some repositories use
+    /// seconds since UNIX epoch.
+    pub date: String,
+}
+
+/// A collection of [Reference].
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct References {
+    #[serde(default, rename = "reference")]
+    pub references: Vec<Reference>,
+}
+
+/// A reference to an external resource.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Reference {
+    /// URL of the resource.
+    pub href: Option<String>,
+
+    /// Identifier of the resource in its tracker.
+    pub id: Option<String>,
+
+    /// The type of reference.
+    ///
+    /// e.g. `bugzilla` or `cve`.
+    #[serde(rename = "type")]
+    pub reference_type: Option<String>,
+
+    /// Title of the resource.
+    pub title: Option<String>,
+}
+
+/// A collection of [Collection].
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PackageList {
+    #[serde(default, rename = "collection")]
+    pub collections: Vec<Collection>,
+}
+
+/// A named group of packages in an advisory.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Collection {
+    /// Short name of the collection.
+    pub short: Option<String>,
+
+    /// Name of the collection.
+    pub name: Option<String>,
+
+    /// Packages in this collection.
+    #[serde(default, rename = "package")]
+    pub packages: Vec<Package>,
+}
+
+/// A package addressing an advisory.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Package {
+    /// Name of the package.
+    pub name: String,
+
+    /// Package version.
+    pub version: String,
+
+    /// Package release.
+    pub release: String,
+
+    /// Package epoch.
+    pub epoch: Option<u64>,
+
+    /// The machine architecture the package is targeting.
+    pub arch: String,
+
+    /// Name of the source package.
+    pub src: Option<String>,
+
+    /// Filename of the package.
+    pub filename: Option<String>,
+
+    /// Content digest of the package.
+    pub sum: Option<Checksum>,
+
+    /// Whether a reboot is suggested after installing the package.
+    pub reboot_suggested: Option<String>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const UPDATEINFO_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<updates>
+  <update from="updates@fedoraproject.org" status="stable" type="security" version="2.0">
+    <id>FEDORA-2021-0123456789</id>
+    <title>curl-7.79.1-2.fc35</title>
+    <issued date="2021-11-03 01:04:52"/>
+    <updated date="2021-11-02 17:27:41"/>
+    <rights>Copyright (C) 2021 Red Hat, Inc. and others.</rights>
+    <release>Fedora 35</release>
+    <severity>Moderate</severity>
+    <summary>curl-7.79.1-2.fc35 security update</summary>
+    <description>Security fixes.</description>
+    <solution>This update can be installed with the "dnf" update program.</solution>
+    <references>
+      <reference href="https://bugzilla.redhat.com/show_bug.cgi?id=1" id="1" type="bugzilla" title="CVE-2021-0001"/>
+      <reference href="https://bugzilla.redhat.com/show_bug.cgi?id=2" id="2" type="bugzilla" title="CVE-2021-0002"/>
+    </references>
+    <pkglist>
+      <collection short="F35">
+        <name>Fedora 35</name>
+        <package name="curl" version="7.79.1" release="2.fc35" epoch="0" arch="x86_64" src="https://download.fedoraproject.org/pub/fedora/linux/updates/35/x86_64/c/curl-7.79.1-2.fc35.x86_64.rpm">
+          <filename>curl-7.79.1-2.fc35.x86_64.rpm</filename>
+          <sum type="sha256">9c2a9f5f0a0d1a3b2d4c6e8f9c2a9f5f0a0d1a3b2d4c6e8f9c2a9f5f0a0d1a3b</sum>
+        </package>
+      </collection>
+    </pkglist>
+  </update>
+</updates>
+"#;
+
+    #[test]
+    fn parse() -> Result<()> {
+        let info = UpdateInfo::from_xml(UPDATEINFO_XML)?;
+
+        assert_eq!(info.updates.len(), 1);
+        let update = &info.updates[0];
+        assert_eq!(update.id, "FEDORA-2021-0123456789");
+        assert_eq!(update.update_type.as_deref(), Some("security"));
+        assert_eq!(
+            update.issued.as_ref().map(|x| x.date.as_str()),
+            Some("2021-11-03 01:04:52")
+        );
+        assert_eq!(update.references.as_ref().unwrap().references.len(), 2);
+
+        let collections = &update.package_list.as_ref().unwrap().collections;
+        assert_eq!(collections.len(), 1);
+        assert_eq!(collections[0].name.as_deref(), Some("Fedora 35"));
+        let package = &collections[0].packages[0];
+        assert_eq!(package.name, "curl");
+        assert_eq!(package.epoch, Some(0));
+        assert_eq!(
+            package.filename.as_deref(),
+            Some("curl-7.79.1-2.fc35.x86_64.rpm")
+        );
+        assert_eq!(package.sum.as_ref().unwrap().name, "sha256");
+
+        Ok(())
+    }
+}
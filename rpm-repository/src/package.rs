@@ -0,0 +1,517 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! RPM package file header parsing.
+
+An `.rpm` file consists of a fixed size *lead*, a *signature* header,
+the main *header*, and a compressed cpio archive *payload*.
+
+This module implements just enough parsing of the signature and main headers
+to derive repository metadata. The payload is not examined.
+*/
+
+use {
+    crate::error::{Result, RpmRepositoryError},
+    std::{collections::BTreeMap, io::Read},
+};
+
+/// Size in bytes of the lead at the beginning of an RPM file.
+pub const LEAD_SIZE: usize = 96;
+
+/// Magic bytes at the beginning of an RPM file.
+pub const LEAD_MAGIC: &[u8] = &[0xed, 0xab, 0xee, 0xdb];
+
+/// Magic bytes at the beginning of a header structure.
+pub const HEADER_MAGIC: &[u8] = &[0x8e, 0xad, 0xe8, 0x01];
+
+/// Maximum number of index entries in a header.
+///
+/// This matches the limit enforced by `rpm` itself.
+pub const HEADER_MAX_INDEX_ENTRIES: usize = 0xffff;
+
+/// Maximum size in bytes of the data store of a header.
+///
+/// This matches the limit enforced by `rpm` itself.
+pub const HEADER_MAX_STORE_SIZE: usize = 0x0fff_ffff;
+
+/// Tags in the main header.
+pub mod tags {
+    pub const NAME: u32 = 1000;
+    pub const VERSION: u32 = 1001;
+    pub const RELEASE: u32 = 1002;
+    pub const EPOCH: u32 = 1003;
+    pub const SUMMARY: u32 = 1004;
+    pub const DESCRIPTION: u32 = 1005;
+    pub const BUILDTIME: u32 = 1006;
+    pub const BUILDHOST: u32 = 1007;
+    pub const SIZE: u32 = 1009;
+    pub const VENDOR: u32 = 1011;
+    pub const LICENSE: u32 = 1014;
+    pub const PACKAGER: u32 = 1015;
+    pub const GROUP: u32 = 1016;
+    pub const URL: u32 = 1020;
+    pub const ARCH: u32 = 1022;
+    pub const FILEMODES: u32 = 1030;
+    pub const FILEFLAGS: u32 = 1037;
+    pub const SOURCERPM: u32 = 1044;
+    pub const ARCHIVESIZE: u32 = 1046;
+    pub const PROVIDENAME: u32 = 1047;
+    pub const REQUIREFLAGS: u32 = 1048;
+    pub const REQUIRENAME: u32 = 1049;
+    pub const REQUIREVERSION: u32 = 1050;
+    pub const CONFLICTFLAGS: u32 = 1053;
+    pub const CONFLICTNAME: u32 = 1054;
+    pub const CONFLICTVERSION: u32 = 1055;
+    pub const CHANGELOGTIME: u32 = 1080;
+    pub const CHANGELOGNAME: u32 = 1081;
+    pub const CHANGELOGTEXT: u32 = 1082;
+    pub const OBSOLETENAME: u32 = 1090;
+    pub const PROVIDEFLAGS: u32 = 1112;
+    pub const PROVIDEVERSION: u32 = 1113;
+    pub const OBSOLETEFLAGS: u32 = 1114;
+    pub const OBSOLETEVERSION: u32 = 1115;
+    pub const DIRINDEXES: u32 = 1116;
+    pub const BASENAMES: u32 = 1117;
+    pub const DIRNAMES: u32 = 1118;
+    pub const LONGSIZE: u32 = 5009;
+    pub const RECOMMENDNAME: u32 = 5046;
+    pub const RECOMMENDVERSION: u32 = 5047;
+    pub const RECOMMENDFLAGS: u32 = 5048;
+    pub const SUGGESTNAME: u32 = 5049;
+    pub const SUGGESTVERSION: u32 = 5050;
+    pub const SUGGESTFLAGS: u32 = 5051;
+    pub const SUPPLEMENTNAME: u32 = 5052;
+    pub const SUPPLEMENTVERSION: u32 = 5053;
+    pub const SUPPLEMENTFLAGS: u32 = 5054;
+}
+
+/// Tags in the signature header.
+pub mod signature_tags {
+    pub const LONGARCHIVESIZE: u32 = 271;
+    pub const PAYLOADSIZE: u32 = 1007;
+}
+
+/// A value in a header.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum HeaderValue {
+    Null,
+    Char(Vec<u8>),
+    Int8(Vec<u8>),
+    Int16(Vec<u16>),
+    Int32(Vec<u32>),
+    Int64(Vec<u64>),
+    String(String),
+    Binary(Vec<u8>),
+    StringArray(Vec<String>),
+    I18nString(Vec<String>),
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    let bytes = data
+        .get(offset..offset + 4)
+        .ok_or_else(|| RpmRepositoryError::RpmParse("truncated integer".to_string()))?;
+
+    Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_strings(data: &[u8], offset: usize, count: usize) -> Result<Vec<String>> {
+    let mut remaining = data
+        .get(offset..)
+        .ok_or_else(|| RpmRepositoryError::RpmParse("string offset out of bounds".to_string()))?;
+    // Every string occupies at least its NULL terminator.
+    let mut res = Vec::with_capacity(count.min(remaining.len()));
+
+    for _ in 0..count {
+        let end = remaining
+            .iter()
+            .position(|b| *b == 0)
+            .ok_or_else(|| RpmRepositoryError::RpmParse("unterminated string".to_string()))?;
+
+        res.push(String::from_utf8_lossy(&remaining[0..end]).to_string());
+        remaining = &remaining[end + 1..];
+    }
+
+    Ok(res)
+}
+
+fn read_integers<T>(
+    data: &[u8],
+    offset: usize,
+    count: usize,
+    size: usize,
+    f: impl Fn(&[u8]) -> T,
+) -> Result<Vec<T>> {
+    let data = count
+        .checked_mul(size)
+        .and_then(|len| offset.checked_add(len))
+        .and_then(|end| data.get(offset..end))
+        .ok_or_else(|| RpmRepositoryError::RpmParse("integer array out of bounds".to_string()))?;
+
+    Ok(data.chunks_exact(size).map(f).collect())
+}
+
+/// Read exactly `size` bytes from a reader.
+///
+/// The buffer grows as data is read, so a truncated input claiming a large
+/// size doesn't result in a large allocation.
+fn read_exact_vec(reader: &mut impl Read, size: usize) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    reader.take(size as u64).read_to_end(&mut data)?;
+
+    if data.len() != size {
+        return Err(RpmRepositoryError::RpmParse("header truncated".to_string()));
+    }
+
+    Ok(data)
+}
+
+/// A parsed header structure.
+///
+/// Both the signature and main header use this data structure.
+#[derive(Clone, Debug, Default)]
+pub struct RpmHeader {
+    entries: BTreeMap<u32, HeaderValue>,
+}
+
+impl RpmHeader {
+    /// Parse a header from a reader.
+    ///
+    /// Returns the parsed header and the number of bytes read.
+    pub fn from_reader(reader: &mut impl Read) -> Result<(Self, usize)> {
+        let mut intro = [0u8; 16];
+        reader.read_exact(&mut intro)?;
+
+        if &intro[0..4] != HEADER_MAGIC {
+            return Err(RpmRepositoryError::RpmParse("bad header magic".to_string()));
+        }
+
+        let index_count = read_u32(&intro, 8)? as usize;
+        let store_size = read_u32(&intro, 12)? as usize;
+
+        if index_count > HEADER_MAX_INDEX_ENTRIES {
+            return Err(RpmRepositoryError::RpmParse(format!(
+                "header index entry count {} exceeds maximum of {}",
+                index_count, HEADER_MAX_INDEX_ENTRIES
+            )));
+        }
+        if store_size > HEADER_MAX_STORE_SIZE {
+            return Err(RpmRepositoryError::RpmParse(format!(
+                "header store size {} exceeds maximum of {}",
+                store_size, HEADER_MAX_STORE_SIZE
+            )));
+        }
+
+        let index_size = index_count.checked_mul(16).ok_or_else(|| {
+            RpmRepositoryError::RpmParse("header index size overflow".to_string())
+        })?;
+
+        let index = read_exact_vec(reader, index_size)?;
+        let store = read_exact_vec(reader, store_size)?;
+
+        let mut entries = BTreeMap::new();
+
+        for entry in index.chunks_exact(16) {
+            let tag = read_u32(entry, 0)?;
+            let value_type = read_u32(entry, 4)?;
+            let offset = read_u32(entry, 8)? as usize;
+            let count = read_u32(entry, 12)? as usize;
+
+            let value = match value_type {
+                0 => HeaderValue::Null,
+                1 => HeaderValue::Char(read_integers(&store, offset, count, 1, |b| b[0])?),
+                2 => HeaderValue::Int8(read_integers(&store, offset, count, 1, |b| b[0])?),
+                3 => HeaderValue::Int16(read_integers(&store, offset, count, 2, |b| {
+                    u16::from_be_bytes([b[0], b[1]])
+                })?),
+                4 => HeaderValue::Int32(read_integers(&store, offset, count, 4, |b| {
+                    u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+                })?),
+                5 => HeaderValue::Int64(read_integers(&store, offset, count, 8, |b| {
+                    u64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]])
+                })?),
+                6 => HeaderValue::String(
+                    read_strings(&store, offset, 1)?
+                        .pop()
+                        .expect("1 string should have been read"),
+                ),
+                7 => HeaderValue::Binary(
+                    offset
+                        .checked_add(count)
+                        .and_then(|end| store.get(offset..end))
+                        .ok_or_else(|| {
+                            RpmRepositoryError::RpmParse("binary out of bounds".to_string())
+                        })?
+                        .to_vec(),
+                ),
+                8 => HeaderValue::StringArray(read_strings(&store, offset, count)?),
+                9 => HeaderValue::I18nString(read_strings(&store, offset, count)?),
+                _ => {
+                    return Err(RpmRepositoryError::RpmParse(format!(
+                        "unknown header value type: {}",
+                        value_type
+                    )))
+                }
+            };
+
+            entries.insert(tag, value);
+        }
+
+        Ok((Self { entries }, 16 + index.len() + store.len()))
+    }
+
+    /// Obtain the raw value of a tag.
+    pub fn get(&self, tag: u32) -> Option<&HeaderValue> {
+        self.entries.get(&tag)
+    }
+
+    /// Obtain the value of a tag as a string.
+    ///
+    /// Internationalized strings resolve to their first (default locale) value.
+    pub fn string(&self, tag: u32) -> Option<&str> {
+        match self.get(tag)? {
+            HeaderValue::String(s) => Some(s.as_str()),
+            HeaderValue::I18nString(v) | HeaderValue::StringArray(v) => {
+                v.first().map(|s| s.as_str())
+            }
+            _ => None,
+        }
+    }
+
+    /// Obtain the value of a tag as an array of strings.
+    ///
+    /// Returns an empty slice if the tag isn't present or isn't a string array.
+    pub fn string_array(&self, tag: u32) -> &[String] {
+        match self.get(tag) {
+            Some(HeaderValue::StringArray(v)) | Some(HeaderValue::I18nString(v)) => v,
+            _ => &[],
+        }
+    }
+
+    /// Obtain the value of a tag as an array of integers.
+    ///
+    /// Integers of all widths are widened to [u64]. Returns an empty vector if
+    /// the tag isn't present or isn't an integer array.
+    pub fn integers(&self, tag: u32) -> Vec<u64> {
+        match self.get(tag) {
+            Some(HeaderValue::Int8(v)) => v.iter().map(|x| *x as u64).collect(),
+            Some(HeaderValue::Int16(v)) => v.iter().map(|x| *x as u64).collect(),
+            Some(HeaderValue::Int32(v)) => v.iter().map(|x| *x as u64).collect(),
+            Some(HeaderValue::Int64(v)) => v.clone(),
+            _ => vec![],
+        }
+    }
+
+    /// Obtain the first integer value of a tag.
+    pub fn integer(&self, tag: u32) -> Option<u64> {
+        self.integers(tag).first().copied()
+    }
+}
+
+/// The parsed headers of an RPM package file.
+#[derive(Clone, Debug)]
+pub struct RpmPackageHeaders {
+    /// The signature header.
+    pub signature: RpmHeader,
+
+    /// The main header.
+    pub header: RpmHeader,
+
+    /// Offset in bytes of the start of the main header within the file.
+    pub header_start: u64,
+
+    /// Offset in bytes of the end of the main header within the file.
+    pub header_end: u64,
+}
+
+impl RpmPackageHeaders {
+    /// Parse headers from a reader.
+    ///
+    /// The reader is positioned at the start of the payload upon return.
+    pub fn from_reader(reader: &mut impl Read) -> Result<Self> {
+        let mut lead = [0u8; LEAD_SIZE];
+        reader.read_exact(&mut lead)?;
+
+        if &lead[0..4] != LEAD_MAGIC {
+            return Err(RpmRepositoryError::RpmParse("bad lead magic".to_string()));
+        }
+
+        let (signature, signature_size) = RpmHeader::from_reader(reader)?;
+
+        // The signature header is padded to an 8 byte boundary.
+        let pad = (8 - signature_size % 8) % 8;
+        let mut pad_data = vec![0u8; pad];
+        reader.read_exact(&mut pad_data)?;
+
+        let header_start = (LEAD_SIZE + signature_size + pad) as u64;
+
+        let (header, header_size) = RpmHeader::from_reader(reader)?;
+
+        Ok(Self {
+            signature,
+            header,
+            header_start,
+            header_end: header_start + header_size as u64,
+        })
+    }
+
+    /// Parse headers from data constituting an RPM file.
+    pub fn from_data(data: &[u8]) -> Result<Self> {
+        Self::from_reader(&mut std::io::Cursor::new(data))
+    }
+
+    /// The package name.
+    pub fn name(&self) -> Result<&str> {
+        self.required_string(tags::NAME)
+    }
+
+    /// The package version.
+    pub fn version(&self) -> Result<&str> {
+        self.required_string(tags::VERSION)
+    }
+
+    /// The package release.
+    pub fn release(&self) -> Result<&str> {
+        self.required_string(tags::RELEASE)
+    }
+
+    /// The package epoch.
+    ///
+    /// Defaults to 0 if not defined.
+    pub fn epoch(&self) -> u64 {
+        self.header.integer(tags::EPOCH).unwrap_or(0)
+    }
+
+    /// The machine architecture the package is targeting.
+    ///
+    /// Source packages report `src`.
+    pub fn arch(&self) -> Result<&str> {
+        if self.header.get(tags::SOURCERPM).is_none() {
+            Ok("src")
+        } else {
+            self.required_string(tags::ARCH)
+        }
+    }
+
+    /// Size in bytes of installed files.
+    pub fn installed_size(&self) -> u64 {
+        self.header
+            .integer(tags::LONGSIZE)
+            .or_else(|| self.header.integer(tags::SIZE))
+            .unwrap_or(0)
+    }
+
+    /// Size in bytes of the uncompressed payload archive.
+    pub fn archive_size(&self) -> u64 {
+        self.signature
+            .integer(signature_tags::LONGARCHIVESIZE)
+            .or_else(|| self.signature.integer(signature_tags::PAYLOADSIZE))
+            .or_else(|| self.header.integer(tags::ARCHIVESIZE))
+            .unwrap_or(0)
+    }
+
+    /// Full paths of files in this package, with their mode and flags.
+    pub fn files(&self) -> Result<Vec<(String, u64, u64)>> {
+        let basenames = self.header.string_array(tags::BASENAMES);
+        let dirnames = self.header.string_array(tags::DIRNAMES);
+        let dir_indexes = self.header.integers(tags::DIRINDEXES);
+        let modes = self.header.integers(tags::FILEMODES);
+        let flags = self.header.integers(tags::FILEFLAGS);
+
+        basenames
+            .iter()
+            .enumerate()
+            .map(|(i, basename)| {
+                let dirname = dir_indexes
+                    .get(i)
+                    .and_then(|index| dirnames.get(*index as usize))
+                    .ok_or_else(|| {
+                        RpmRepositoryError::RpmParse(format!(
+                            "could not resolve directory of file {}",
+                            basename
+                        ))
+                    })?;
+
+                Ok((
+                    format!("{}{}", dirname, basename),
+                    modes.get(i).copied().unwrap_or(0),
+                    flags.get(i).copied().unwrap_or(0),
+                ))
+            })
+            .collect()
+    }
+
+    fn required_string(&self, tag: u32) -> Result<&str> {
+        self.header
+            .string(tag)
+            .ok_or_else(|| RpmRepositoryError::RpmParse(format!("missing header tag {}", tag)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn header_intro(index_count: u32, store_size: u32) -> Vec<u8> {
+        let mut data = HEADER_MAGIC.to_vec();
+        data.extend_from_slice(&[0u8; 4]);
+        data.extend_from_slice(&index_count.to_be_bytes());
+        data.extend_from_slice(&store_size.to_be_bytes());
+
+        data
+    }
+
+    #[test]
+    fn header_size_limits() {
+        let data = header_intro(u32::MAX, 0);
+        assert!(matches!(
+            RpmHeader::from_reader(&mut data.as_slice()),
+            Err(RpmRepositoryError::RpmParse(_))
+        ));
+
+        let data = header_intro(0, u32::MAX);
+        assert!(matches!(
+            RpmHeader::from_reader(&mut data.as_slice()),
+            Err(RpmRepositoryError::RpmParse(_))
+        ));
+
+        // Sizes within limits but exceeding the available data.
+        let data = header_intro(1024, 1024 * 1024);
+        assert!(matches!(
+            RpmHeader::from_reader(&mut data.as_slice()),
+            Err(RpmRepositoryError::RpmParse(_))
+        ));
+    }
+
+    #[test]
+    fn header_entry_out_of_bounds() {
+        let mut data = header_intro(1, 4);
+        // Tag, type (INT32), offset, count.
+        for v in [tags::SIZE, 4, 0, u32::MAX] {
+            data.extend_from_slice(&v.to_be_bytes());
+        }
+        data.extend_from_slice(&[0u8; 4]);
+
+        assert!(matches!(
+            RpmHeader::from_reader(&mut data.as_slice()),
+            Err(RpmRepositoryError::RpmParse(_))
+        ));
+    }
+
+    #[test]
+    fn header_parse() -> Result<()> {
+        let mut data = header_intro(2, 8);
+        for v in [tags::SIZE, 4, 0, 1, tags::NAME, 6, 4, 1] {
+            data.extend_from_slice(&v.to_be_bytes());
+        }
+        data.extend_from_slice(&42u32.to_be_bytes());
+        data.extend_from_slice(b"foo\0");
+
+        let (header, size) = RpmHeader::from_reader(&mut data.as_slice())?;
+        assert_eq!(size, data.len());
+        assert_eq!(header.integer(tags::SIZE), Some(42));
+        assert_eq!(header.string(tags::NAME), Some("foo"));
+
+        Ok(())
+    }
+}
@@ -6,8 +6,8 @@
 
 use {
     crate::{
-        find_minimum_distro_version, find_undefined_elf_symbols, UndefinedSymbol,
-        GCC_VERSIONS_BY_DISTRO, GLIBC_VERSIONS_BY_DISTRO, LSB_SHARED_LIBRARIES,
+        find_minimum_distro_version, find_pe_signatures, find_undefined_elf_symbols,
+        UndefinedSymbol, GCC_VERSIONS_BY_DISTRO, GLIBC_VERSIONS_BY_DISTRO, LSB_SHARED_LIBRARIES,
     },
     std::{collections::BTreeMap, fs::File, io::Read, path::PathBuf},
 };
@@ -27,8 +27,9 @@ pub fn analyze_data(buffer: &[u8]) {
 
             analyze_elf_libraries(&elf.libraries, &undefined_symbols);
         }
-        goblin::Object::PE(_pe) => {
-            panic!("PE not yet supported");
+        goblin::Object::PE(pe) => {
+            analyze_pe_libraries(&pe.libraries);
+            analyze_pe_signatures(buffer);
         }
         goblin::Object::Mach(_mach) => {
             panic!("mach not yet supported");
@@ -40,6 +41,41 @@ pub fn analyze_data(buffer: &[u8]) {
     }
 }
 
+pub fn analyze_pe_libraries(libs: &[&str]) {
+    println!("DLL Dependencies");
+    println!("================");
+
+    let mut libs = libs.to_vec();
+    libs.sort_unstable();
+    for lib in libs {
+        println!("{}", lib);
+    }
+
+    println!();
+}
+
+pub fn analyze_pe_signatures(buffer: &[u8]) {
+    println!("Authenticode Signatures");
+    println!("=======================");
+
+    match find_pe_signatures(buffer) {
+        Ok(signatures) if signatures.is_empty() => {
+            println!("(not signed)");
+        }
+        Ok(signatures) => match serde_yaml::to_string(&signatures) {
+            Ok(yaml) => {
+                println!("{}", yaml);
+            }
+            Err(e) => {
+                println!("error formatting signatures: {:?}", e);
+            }
+        },
+        Err(e) => {
+            println!("error reading signatures: {:?}", e);
+        }
+    }
+}
+
 pub fn analyze_elf_libraries(libs: &[&str], undefined_symbols: &[UndefinedSymbol]) {
     let mut latest_symbols: BTreeMap<String, version_compare::Version> = BTreeMap::new();
 
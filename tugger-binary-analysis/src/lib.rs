@@ -5,7 +5,9 @@
 /*! Functionality for analyzing the content of platform binaries. */
 
 mod audit;
-pub use audit::{analyze_data, analyze_elf_libraries, analyze_file};
+pub use audit::{
+    analyze_data, analyze_elf_libraries, analyze_file, analyze_pe_libraries, analyze_pe_signatures,
+};
 mod elf;
 pub use elf::find_undefined_elf_symbols;
 mod linux_distro_versions;
@@ -13,7 +15,10 @@ pub use linux_distro_versions::{
     find_minimum_distro_version, GCC_VERSIONS_BY_DISTRO, GLIBC_VERSIONS_BY_DISTRO,
 };
 mod pe;
-pub use pe::{find_pe_dependencies, find_pe_dependencies_path};
+pub use pe::{
+    find_pe_dependencies, find_pe_dependencies_path, find_pe_signatures, find_pe_signatures_path,
+    AuthenticodeSignature, CertificateInfo, CmsSignature, CmsSigner,
+};
 
 /// Shared libraries defined as part of the Linux Shared Base specification.
 pub const LSB_SHARED_LIBRARIES: &[&str] = &[
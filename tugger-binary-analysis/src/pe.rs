@@ -2,7 +2,18 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use {anyhow::Result, std::path::Path};
+use {
+    anyhow::{anyhow, Context, Result},
+    bcder::{Mode, OctetString, Oid},
+    bytes::Bytes,
+    cryptographic_message_syntax::{SignedData, SignerInfo},
+    serde::Serialize,
+    std::path::Path,
+    tugger_windows_codesign::{
+        PeLayout, OID_RFC3161_COUNTER_SIGNATURE, WIN_CERT_TYPE_PKCS_SIGNED_DATA,
+    },
+    x509_certificate::{CapturedX509Certificate, DigestAlgorithm},
+};
 
 pub fn find_pe_dependencies(data: &[u8]) -> Result<Vec<String>> {
     let pe = goblin::pe::PE::parse(data)?;
@@ -14,3 +25,357 @@ pub fn find_pe_dependencies_path(path: &Path) -> Result<Vec<String>> {
     let data = std::fs::read(path)?;
     find_pe_dependencies(&data)
 }
+
+/// Describes an X.509 certificate embedded in a signature.
+#[derive(Clone, Debug, Serialize)]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub serial_number: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_algorithm: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature_algorithm: Option<String>,
+}
+
+impl TryFrom<&CapturedX509Certificate> for CertificateInfo {
+    type Error = anyhow::Error;
+
+    fn try_from(cert: &CapturedX509Certificate) -> Result<Self, Self::Error> {
+        Ok(Self {
+            subject: cert
+                .subject_name()
+                .user_friendly_str()
+                .map_err(|e| anyhow!("error decoding certificate subject: {:?}", e))?,
+            issuer: cert
+                .issuer_name()
+                .user_friendly_str()
+                .map_err(|e| anyhow!("error decoding certificate issuer: {:?}", e))?,
+            serial_number: hex::encode(cert.serial_number_asn1().as_slice()),
+            key_algorithm: cert.key_algorithm().map(|x| x.to_string()),
+            signature_algorithm: cert.signature_algorithm().map(|x| x.to_string()),
+        })
+    }
+}
+
+/// Describes a signer in a CMS signature.
+#[derive(Clone, Debug, Serialize)]
+pub struct CmsSigner {
+    pub issuer: String,
+    pub digest_algorithm: String,
+    pub signature_algorithm: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_digest: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signing_time: Option<String>,
+    pub signature_verifies: bool,
+    pub message_digest_verifies: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_stamp_token: Option<CmsSignature>,
+}
+
+impl CmsSigner {
+    pub fn from_signer_info_and_signed_data(
+        signer_info: &SignerInfo,
+        signed_data: &SignedData,
+    ) -> Result<Self> {
+        let mut attributes = vec![];
+        let mut content_type = None;
+        let mut message_digest = None;
+        let mut signing_time = None;
+
+        if let Some(sa) = signer_info.signed_attributes() {
+            content_type = Some(sa.content_type().to_string());
+            message_digest = Some(hex::encode(sa.message_digest()));
+            signing_time = sa.signing_time().map(|t| t.to_rfc3339());
+
+            for attr in sa.attributes().iter() {
+                attributes.push(format!("{}", attr.typ));
+            }
+        }
+
+        attributes.sort();
+
+        // Authenticode stores RFC 3161 time-stamp tokens under a Microsoft specific
+        // attribute type. But the standard attribute could also be present.
+        let time_stamp_token = if let Some(signed_data) = signer_info
+            .unsigned_attribute_signed_data(&Oid(OID_RFC3161_COUNTER_SIGNATURE.as_ref().into()))?
+        {
+            Some(signed_data)
+        } else {
+            signer_info.time_stamp_token_signed_data()?
+        };
+
+        let time_stamp_token = time_stamp_token
+            .map(|signed_data| CmsSignature::try_from(&signed_data))
+            .transpose()?;
+
+        let (issuer, _) = signer_info
+            .certificate_issuer_and_serial()
+            .ok_or_else(|| anyhow!("signer does not identify certificate by issuer"))?;
+
+        Ok(Self {
+            issuer: issuer
+                .user_friendly_str()
+                .map_err(|e| anyhow!("error decoding signer issuer: {:?}", e))?,
+            digest_algorithm: signer_info.digest_algorithm().to_string(),
+            signature_algorithm: signer_info.signature_algorithm().to_string(),
+            attributes,
+            content_type,
+            message_digest,
+            signing_time,
+            signature_verifies: signer_info
+                .verify_signature_with_signed_data(signed_data)
+                .is_ok(),
+            message_digest_verifies: signer_info
+                .verify_message_digest_with_signed_data(signed_data)
+                .is_ok(),
+            time_stamp_token,
+        })
+    }
+}
+
+/// High-level representation of a CMS signature.
+#[derive(Clone, Debug, Serialize)]
+pub struct CmsSignature {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub certificates: Vec<CertificateInfo>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub signers: Vec<CmsSigner>,
+}
+
+impl TryFrom<&SignedData> for CmsSignature {
+    type Error = anyhow::Error;
+
+    fn try_from(signed_data: &SignedData) -> Result<Self, Self::Error> {
+        let certificates = signed_data
+            .certificates()
+            .map(|x| x.try_into())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let signers = signed_data
+            .signers()
+            .map(|x| CmsSigner::from_signer_info_and_signed_data(x, signed_data))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            certificates,
+            signers,
+        })
+    }
+}
+
+/// An Authenticode signature embedded in a PE file.
+#[derive(Clone, Debug, Serialize)]
+pub struct AuthenticodeSignature {
+    /// Digest algorithm of the image digest.
+    pub digest_algorithm: String,
+    /// Image digest recorded in the signature.
+    pub signed_digest: String,
+    /// Image digest computed from the PE data.
+    pub computed_digest: String,
+    /// Whether the recorded and computed image digests match.
+    pub digest_matches: bool,
+    pub cms: CmsSignature,
+}
+
+/// Extract the image digest from the content of an Authenticode signature.
+///
+/// The content is the contents octets of a `SpcIndirectDataContent`:
+///
+/// ```ASN.1
+/// SpcIndirectDataContent ::= SEQUENCE {
+///     data                    SpcAttributeTypeAndOptionalValue,
+///     messageDigest           DigestInfo
+/// }
+/// ```
+fn spc_indirect_data_digest(content: &[u8]) -> Result<(DigestAlgorithm, Vec<u8>)> {
+    let (oid, digest) = Mode::Ber
+        .decode(Bytes::copy_from_slice(content), |cons| {
+            cons.take_sequence(|cons| {
+                Oid::take_from(cons)?;
+                cons.skip_all()
+            })?;
+
+            cons.take_sequence(|cons| {
+                let oid = cons.take_sequence(|cons| {
+                    let oid = Oid::take_from(cons)?;
+                    cons.skip_all()?;
+                    Ok(oid)
+                })?;
+                let digest = OctetString::take_from(cons)?;
+
+                Ok((oid, digest.into_bytes()))
+            })
+        })
+        .map_err(|e| anyhow!("error decoding SpcIndirectDataContent: {:?}", e))?;
+
+    let algorithm = DigestAlgorithm::try_from(&oid)
+        .map_err(|e| anyhow!("unsupported image digest algorithm: {:?}", e))?;
+
+    Ok((algorithm, digest.to_vec()))
+}
+
+/// Resolve Authenticode signatures embedded in PE data.
+///
+/// The CMS signatures are parsed and verified and the Authenticode image
+/// digest is recomputed and compared against the signed digest.
+///
+/// Returns an empty `Vec` if the PE isn't signed.
+pub fn find_pe_signatures(data: &[u8]) -> Result<Vec<AuthenticodeSignature>> {
+    let layout = PeLayout::parse(data).context("parsing PE data")?;
+
+    let mut res = vec![];
+
+    for (_, certificate_type, der) in layout.certificates(data)? {
+        if certificate_type != WIN_CERT_TYPE_PKCS_SIGNED_DATA {
+            continue;
+        }
+
+        let signed_data =
+            SignedData::parse_ber(der).context("parsing Authenticode CMS signature")?;
+
+        let content = signed_data
+            .signed_content()
+            .ok_or_else(|| anyhow!("Authenticode signature has no signed content"))?;
+
+        let (digest_algorithm, signed_digest) = spc_indirect_data_digest(content)?;
+        let computed_digest = layout.digest(data, digest_algorithm);
+
+        res.push(AuthenticodeSignature {
+            digest_algorithm: digest_algorithm.to_string(),
+            signed_digest: hex::encode(&signed_digest),
+            computed_digest: hex::encode(&computed_digest),
+            digest_matches: signed_digest == computed_digest,
+            cms: CmsSignature::try_from(&signed_data)?,
+        });
+    }
+
+    Ok(res)
+}
+
+/// Resolve Authenticode signatures embedded in a PE file.
+pub fn find_pe_signatures_path(path: &Path) -> Result<Vec<AuthenticodeSignature>> {
+    let data = std::fs::read(path)?;
+    find_pe_signatures(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        tugger_windows_codesign::AuthenticodeSigner,
+        x509_certificate::testutil::{rsa_cert, rsa_private_key},
+    };
+
+    /// Construct a minimal PE32+ image with a single section of data.
+    fn minimal_pe() -> Vec<u8> {
+        let mut data = vec![0u8; 0x200];
+        data[0..2].copy_from_slice(b"MZ");
+        data[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        data[0x40..0x44].copy_from_slice(b"PE\0\0");
+
+        let coff = 0x44;
+        // IMAGE_FILE_MACHINE_AMD64.
+        data[coff..coff + 2].copy_from_slice(&0x8664u16.to_le_bytes());
+        data[coff + 2..coff + 4].copy_from_slice(&1u16.to_le_bytes());
+        data[coff + 16..coff + 18].copy_from_slice(&240u16.to_le_bytes());
+
+        let optional = coff + 20;
+        data[optional..optional + 2].copy_from_slice(&0x20bu16.to_le_bytes());
+        // SizeOfHeaders.
+        data[optional + 60..optional + 64].copy_from_slice(&0x200u32.to_le_bytes());
+        // NumberOfRvaAndSizes.
+        data[optional + 108..optional + 112].copy_from_slice(&16u32.to_le_bytes());
+
+        data.extend((0..0x200u32).map(|x| x as u8));
+
+        data
+    }
+
+    fn signed_pe() -> Result<Vec<u8>> {
+        let key = rsa_private_key();
+
+        AuthenticodeSigner::new(&key, rsa_cert()).sign_pe_data(&minimal_pe())
+    }
+
+    #[test]
+    fn signed() -> Result<()> {
+        let data = signed_pe()?;
+
+        let signatures = find_pe_signatures(&data)?;
+        assert_eq!(signatures.len(), 1);
+
+        let signature = &signatures[0];
+        assert_eq!(signature.digest_algorithm, "SHA-256");
+        assert_eq!(signature.signed_digest, signature.computed_digest);
+        assert!(signature.digest_matches);
+
+        assert_eq!(signature.cms.certificates.len(), 1);
+        assert_eq!(signature.cms.signers.len(), 1);
+
+        let signer = &signature.cms.signers[0];
+        assert_eq!(signer.digest_algorithm, "SHA-256");
+        assert_eq!(signer.issuer, signature.cms.certificates[0].issuer);
+        assert!(signer.signature_verifies);
+        assert!(signer.message_digest_verifies);
+        assert!(signer.time_stamp_token.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn tampered_image() -> Result<()> {
+        let mut data = signed_pe()?;
+        data[0x300] ^= 0xff;
+
+        let signatures = find_pe_signatures(&data)?;
+        assert_eq!(signatures.len(), 1);
+
+        let signature = &signatures[0];
+        assert!(!signature.digest_matches);
+        assert_ne!(signature.signed_digest, signature.computed_digest);
+
+        // The CMS signature itself is unaffected.
+        assert!(signature.cms.signers[0].signature_verifies);
+        assert!(signature.cms.signers[0].message_digest_verifies);
+
+        Ok(())
+    }
+
+    #[test]
+    fn tampered_signature() -> Result<()> {
+        // Flip a bit in the signed image digest in the signed content.
+        let mut data = signed_pe()?;
+        let digest = PeLayout::parse(&data)?.digest(&data, DigestAlgorithm::Sha256);
+        let digest_offset = data
+            .windows(digest.len())
+            .position(|x| x == digest)
+            .unwrap();
+        data[digest_offset] ^= 0x01;
+
+        let signatures = find_pe_signatures(&data)?;
+        assert_eq!(signatures.len(), 1);
+
+        let signature = &signatures[0];
+        assert!(!signature.digest_matches);
+        assert!(!signature.cms.signers[0].message_digest_verifies);
+
+        Ok(())
+    }
+
+    #[test]
+    fn unsigned() -> Result<()> {
+        assert!(find_pe_signatures(&minimal_pe())?.is_empty());
+
+        // Truncating the certificate table is an error.
+        let data = signed_pe()?;
+        assert!(find_pe_signatures(&data[0..data.len() - 1]).is_err());
+
+        Ok(())
+    }
+}
@@ -76,7 +76,7 @@ use {
     thiserror::Error,
     tugger_file_manifest::{File, FileData, FileEntry},
     tugger_windows_codesign::{
-        CodeSigningCertificate, FileBasedCodeSigningCertificate, SystemStore,
+        AuthenticodeSigner, CodeSigningCertificate, FileBasedCodeSigningCertificate, SystemStore,
     },
     x509_certificate::{CapturedX509Certificate, X509CertificateError},
     yasna::ASN1Error,
@@ -143,6 +143,9 @@ pub enum SigningError {
     #[error("error running signtool: {0}")]
     SigntoolError(anyhow::Error),
 
+    #[error("Authenticode signing error: {0}")]
+    AuthenticodeSigning(anyhow::Error),
+
     #[error("incompatible signing destination: {0}")]
     IncompatibleSigningDestination(&'static str),
 
@@ -212,6 +215,35 @@ pub enum SigningMethod {
     Memory,
 }
 
+/// Describes the mechanism used to sign Windows signables.
+///
+/// Where [SigningMethod] describes where signed output can be written, this
+/// describes which implementation produces the signature.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WindowsSigningMethod {
+    /// Sign by invoking Microsoft's `signtool.exe`.
+    ///
+    /// Only works on Windows. This is the default on Windows.
+    Signtool,
+
+    /// Sign with our pure Rust Authenticode implementation.
+    ///
+    /// Works on any platform. But only PE files (EXEs and DLLs) are supported
+    /// and the signing key must be loaded into memory (e.g. from a PFX file).
+    /// This is the default on non-Windows platforms.
+    Authenticode,
+}
+
+impl Default for WindowsSigningMethod {
+    fn default() -> Self {
+        if cfg!(target_family = "windows") {
+            Self::Signtool
+        } else {
+            Self::Authenticode
+        }
+    }
+}
+
 /// Represents different methods of signing that are supported.
 ///
 /// Just a collection of [SigningMethod] instances.
@@ -392,6 +424,21 @@ impl Signable {
         !self.signing_methods().is_empty()
     }
 
+    /// Whether this is a Windows PE file (EXE or DLL).
+    ///
+    /// PE files can be signed with [WindowsSigningMethod::Authenticode].
+    /// Other Windows signables (e.g. MSI installers) require `signtool.exe`.
+    ///
+    /// Only file headers are examined. So this is cheap to call.
+    pub fn is_windows_pe(&self) -> Result<bool, SigningError> {
+        match self {
+            Self::WindowsFile(path) => tugger_windows_codesign::is_pe_file(path)
+                .map_err(|e| SigningError::SignableTestError(format!("{:?}", e))),
+            Self::WindowsData(data) => Ok(tugger_windows_codesign::is_pe_data(data)),
+            Self::MachOFile(_, _) | Self::MachOData(_) | Self::AppleBundle(_) => Ok(false),
+        }
+    }
+
     /// Obtain the filesystem path of the signable entity, if it is backed by a file.
     pub fn source_file(&self) -> Option<&Path> {
         match self {
@@ -514,15 +561,18 @@ pub fn path_signable(path: impl AsRef<Path>) -> Result<Signability, SigningError
     if path.is_file() {
         match tugger_windows_codesign::is_file_signable(path) {
             Ok(true) => {
-                // But we can only sign Windows binaries on Windows since we call out to
-                // signtool.exe.
-                return if cfg!(target_family = "windows") {
+                // PE files can be signed anywhere via our Authenticode implementation.
+                // Everything else requires signtool.exe and therefore Windows.
+                let is_pe = tugger_windows_codesign::is_pe_file(path)
+                    .map_err(|e| SigningError::SignableTestError(format!("{:?}", e)))?;
+
+                return if is_pe || cfg!(target_family = "windows") {
                     Ok(Signability::Signable(Signable::WindowsFile(
                         path.to_path_buf(),
                     )))
                 } else {
                     Ok(Signability::PlatformUnsupported(
-                        "Windows signing requires running on Windows",
+                        "signing non-PE Windows files requires running on Windows",
                     ))
                 };
             }
@@ -551,15 +601,15 @@ pub fn path_signable(path: impl AsRef<Path>) -> Result<Signability, SigningError
 /// Resolve signability information given a data slice.
 pub fn data_signable(data: &[u8]) -> Result<Signability, SigningError> {
     if tugger_windows_codesign::is_signable_binary_header(data) {
-        // But we can only sign Windows binaries on Windows since we call out to
-        // signtool.exe.
-        return if cfg!(target_family = "windows") {
+        // PE files can be signed anywhere via our Authenticode implementation.
+        // Everything else requires signtool.exe and therefore Windows.
+        return if tugger_windows_codesign::is_pe_data(data) || cfg!(target_family = "windows") {
             Ok(Signability::Signable(Signable::WindowsData(
                 data.as_ref().to_vec(),
             )))
         } else {
             Ok(Signability::PlatformUnsupported(
-                "Windows signing requires running on Windows",
+                "signing non-PE Windows files requires running on Windows",
             ))
         };
     }
@@ -748,6 +798,9 @@ pub struct Signer {
     /// Optional function to influence creation of [tugger_windows_codesign::SigntoolSign]
     /// used for signing Windows signables.
     windows_signer_fn: Option<Arc<WindowsSignerFn>>,
+
+    /// How Windows signables are signed.
+    windows_signing_method: WindowsSigningMethod,
 }
 
 impl From<SigningCertificate> for Signer {
@@ -765,6 +818,7 @@ impl Signer {
             time_stamp_url: None,
             apple_signing_settings_fn: None,
             windows_signer_fn: None,
+            windows_signing_method: WindowsSigningMethod::default(),
         }
     }
 
@@ -904,6 +958,14 @@ impl Signer {
         self.windows_signer_fn = Some(Arc::new(cb));
     }
 
+    /// Set the mechanism used to sign Windows signables.
+    ///
+    /// Defaults to [WindowsSigningMethod::Signtool] on Windows and
+    /// [WindowsSigningMethod::Authenticode] everywhere else.
+    pub fn windows_signing_method(&mut self, method: WindowsSigningMethod) {
+        self.windows_signing_method = method;
+    }
+
     /// Determine the *signability* of a potentially signable entity.
     pub fn resolve_signability(
         &self,
@@ -915,18 +977,44 @@ impl Signer {
             SignableCandidate::Forced(signable) => Ok(Signability::Signable(signable.clone())),
         }?;
 
-        // We don't yet support exporting the key back to PFX for Windows signing.
-        if matches!(
-            signability,
-            Signability::Signable(Signable::WindowsFile(_))
-                | Signability::Signable(Signable::WindowsData(_))
-        ) && matches!(self.signing_certificate, SigningCertificate::Memory(_, _))
-        {
-            Ok(Signability::PlatformUnsupported(
-                "do not support PFX key re-export on Windows",
-            ))
-        } else {
-            Ok(signability)
+        let signable = match &signability {
+            Signability::Signable(
+                signable @ (Signable::WindowsFile(_) | Signable::WindowsData(_)),
+            ) => signable,
+            _ => return Ok(signability),
+        };
+
+        match self.windows_signing_method {
+            WindowsSigningMethod::Authenticode => {
+                if !matches!(
+                    self.signing_certificate,
+                    SigningCertificate::Memory(_, _) | SigningCertificate::PfxFile(_, _, _, _)
+                ) {
+                    Ok(Signability::PlatformUnsupported(
+                        "Authenticode signing requires a signing key loaded into memory",
+                    ))
+                } else if !signable.is_windows_pe()? {
+                    Ok(Signability::PlatformUnsupported(
+                        "Authenticode signing only supports PE files",
+                    ))
+                } else {
+                    Ok(signability)
+                }
+            }
+            WindowsSigningMethod::Signtool => {
+                if !cfg!(target_family = "windows") {
+                    Ok(Signability::PlatformUnsupported(
+                        "signing with signtool.exe requires running on Windows",
+                    ))
+                // We don't yet support exporting the key back to PFX for signtool.exe.
+                } else if matches!(self.signing_certificate, SigningCertificate::Memory(_, _)) {
+                    Ok(Signability::PlatformUnsupported(
+                        "do not support PFX key re-export on Windows",
+                    ))
+                } else {
+                    Ok(signability)
+                }
+            }
         }
     }
 
@@ -979,6 +1067,9 @@ pub struct SignableSigner<'a> {
     /// Optional function to influence creation of [tugger_windows_codesign::SigntoolSign]
     /// used for signing Windows signables.
     windows_signer_fn: Option<Arc<WindowsSignerFn>>,
+
+    /// How Windows signables are signed.
+    windows_signing_method: WindowsSigningMethod,
 }
 
 impl<'a> SignableSigner<'a> {
@@ -994,6 +1085,7 @@ impl<'a> SignableSigner<'a> {
             time_stamp_url,
             apple_signing_settings_fn: signer.apple_signing_settings_fn.clone(),
             windows_signer_fn: signer.windows_signer_fn.clone(),
+            windows_signing_method: signer.windows_signing_method,
         }
     }
 
@@ -1002,6 +1094,19 @@ impl<'a> SignableSigner<'a> {
         &self.signable
     }
 
+    /// The [WindowsSigningMethod] used if the signable is a Windows entity.
+    pub fn windows_signing_method(&self) -> WindowsSigningMethod {
+        self.windows_signing_method
+    }
+
+    /// Whether signing goes through our Authenticode implementation.
+    fn uses_authenticode(&self) -> bool {
+        matches!(
+            self.signable,
+            Signable::WindowsFile(_) | Signable::WindowsData(_)
+        ) && self.windows_signing_method == WindowsSigningMethod::Authenticode
+    }
+
     /// Obtain a [SigningDestination] that is the same as the input.
     pub fn in_place_destination(&self) -> SigningDestination {
         match &self.signable {
@@ -1080,6 +1185,37 @@ impl<'a> SignableSigner<'a> {
         Ok(signer)
     }
 
+    /// Obtain a [tugger_windows_codesign::AuthenticodeSigner] from this instance.
+    ///
+    /// Errors if the signing key isn't loaded into memory.
+    pub fn as_authenticode_signer(&self) -> Result<AuthenticodeSigner<'_>, SigningError> {
+        let (cert, key) = match self.signing_certificate {
+            SigningCertificate::Memory(cert, key) => (cert, key),
+            SigningCertificate::PfxFile(_, _, cert, key) => (cert, key),
+            SigningCertificate::WindowsStoreAuto
+            | SigningCertificate::WindowsStoreSubject(_, _)
+            | SigningCertificate::WindowsStoreSha1Thumbprint(_, _) => {
+                return Err(SigningError::GeneralSigning(
+                    "Authenticode signing requires a signing key loaded into memory".to_string(),
+                ));
+            }
+        };
+
+        let mut signer = AuthenticodeSigner::new(key, cert.clone());
+
+        for cert in &self.certificate_chain {
+            signer.chain_certificate(cert.clone());
+        }
+
+        if let Some(url) = &self.time_stamp_url {
+            signer
+                .time_stamp_url(url.clone())
+                .map_err(SigningError::AuthenticodeSigning)?;
+        }
+
+        Ok(signer)
+    }
+
     /// Compute [SigningDestinationCompatibility] with a given [SigningDestination].
     ///
     /// This takes the current to-be-signed entity into account.
@@ -1112,6 +1248,10 @@ impl<'a> SignableSigner<'a> {
             return Err(SigningError::IncompatibleSigningDestination(reason));
         }
 
+        if self.uses_authenticode() {
+            return self.sign_authenticode(logger, destination);
+        }
+
         let temp_dir = if self.requires_temporary_files(destination) {
             let mut builder = tempfile::Builder::new();
             builder.prefix("tugger-code-sign-");
@@ -1320,18 +1460,75 @@ impl<'a> SignableSigner<'a> {
         }
     }
 
+    /// Sign a Windows PE signable with our Authenticode implementation.
+    fn sign_authenticode(
+        &self,
+        logger: &slog::Logger,
+        destination: &SigningDestination,
+    ) -> Result<SignedOutput, SigningError> {
+        let signer = self.as_authenticode_signer()?;
+
+        let data = match &self.signable {
+            Signable::WindowsData(data) => {
+                warn!(
+                    logger,
+                    "signing PE binary from in-memory data of size {} bytes",
+                    data.len()
+                );
+                Cow::Borrowed(data.as_slice())
+            }
+            Signable::WindowsFile(source_file) => {
+                warn!(logger, "signing {}", source_file.display());
+                Cow::Owned(std::fs::read(source_file)?)
+            }
+            _ => panic!("Authenticode signing requested for non-Windows signable"),
+        };
+
+        let signed = signer
+            .sign_pe_data(&data)
+            .map_err(SigningError::AuthenticodeSigning)?;
+
+        match destination {
+            SigningDestination::Memory => {
+                warn!(
+                    logger,
+                    "Authenticode signing success; new size {}",
+                    signed.len()
+                );
+                Ok(SignedOutput::Memory(signed))
+            }
+            SigningDestination::File(dest_file) => {
+                warn!(
+                    logger,
+                    "Authenticode signing success; writing to {}",
+                    dest_file.display()
+                );
+                std::fs::write(dest_file, &signed)?;
+                Ok(SignedOutput::File(dest_file.clone()))
+            }
+            SigningDestination::Directory(_) => {
+                panic!("illegal signing combination: Windows PE -> Directory");
+            }
+        }
+    }
+
     /// Whether signing to the specified [SigningDestination] will require temporary files.
     ///
     /// Temporary files are used when:
     ///
     /// * Signed content lives in memory and signer only supports signing files.
-    ///   (e.g. signtool.exe)
+    ///   (e.g. signtool.exe but not [WindowsSigningMethod::Authenticode])
     /// * We are sending output to the filesystem and the destination path isn't the
     ///   source path. We could write directly to the destination. However, we choose
     ///   to play it safe and only write to the destination after signing success.
     ///   By going through a temporary directory, we prevent polluting the destination
     ///   with corrupted results.
     pub fn requires_temporary_files(&self, destination: &SigningDestination) -> bool {
+        // Authenticode signing of PE files happens in memory.
+        if self.uses_authenticode() {
+            return false;
+        }
+
         match &self.signable {
             // signtool only supports signing files. We'll have to persist data to a file.
             Signable::WindowsData(_) => true,
@@ -1410,4 +1607,55 @@ mod tests {
             SigningCertificate::WindowsStoreSubject(_, _)
         ));
     }
+
+    #[test]
+    fn windows_signing_method() -> Result<(), SigningError> {
+        // Just enough of a PE file to pass header checks.
+        let mut pe_data = vec![0u8; 0x44];
+        pe_data[0..2].copy_from_slice(b"MZ");
+        pe_data[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        pe_data[0x40..0x44].copy_from_slice(b"PE\0\0");
+
+        let pe = SignableCandidate::Forced(Signable::WindowsData(pe_data));
+        let not_pe = SignableCandidate::Forced(Signable::WindowsData(b"MZ".to_vec()));
+
+        let mut signer = Signer::new(SigningCertificate::from_pfx_data(
+            WINDOWS_PFX_DEFAULT_DATA,
+            "password123",
+        )?);
+
+        signer.windows_signing_method(WindowsSigningMethod::Authenticode);
+        assert!(matches!(
+            signer.resolve_signability(&pe)?,
+            Signability::Signable(_)
+        ));
+        assert!(matches!(
+            signer.resolve_signability(&not_pe)?,
+            Signability::PlatformUnsupported(_)
+        ));
+
+        let resolved = signer.resolve_signer(&pe)?.unwrap();
+        assert_eq!(
+            resolved.windows_signing_method(),
+            WindowsSigningMethod::Authenticode
+        );
+        assert!(!resolved.requires_temporary_files(&SigningDestination::Memory));
+
+        // signtool.exe is only available on Windows.
+        signer.windows_signing_method(WindowsSigningMethod::Signtool);
+        assert_eq!(
+            matches!(signer.resolve_signability(&pe)?, Signability::Signable(_)),
+            cfg!(target_family = "windows")
+        );
+
+        // Authenticode requires the key to be in memory.
+        let mut signer = Signer::new(SigningCertificate::WindowsStoreAuto);
+        signer.windows_signing_method(WindowsSigningMethod::Authenticode);
+        assert!(matches!(
+            signer.resolve_signability(&pe)?,
+            Signability::PlatformUnsupported(_)
+        ));
+
+        Ok(())
+    }
 }
@@ -48,7 +48,32 @@ impl RpmBuilder {
         }
     }
 
+    /// Register files from a [FileManifest] to be installed by this package.
+    ///
+    /// Manifest paths are relative to the filesystem root. e.g. `usr/bin/myapp`
+    /// will be installed as `/usr/bin/myapp`.
+    pub fn add_file_manifest(&mut self, manifest: &FileManifest) -> Result<()> {
+        self.files
+            .add_manifest(manifest)
+            .context("adding files to RPM manifest")?;
+
+        Ok(())
+    }
+
+    /// Modify the wrapped [rpm::RPMBuilder] with a function.
+    ///
+    /// [rpm::RPMBuilder] methods consume `self`, so they can't be called
+    /// through the [AsMut] implementation.
+    #[must_use]
+    pub fn map_inner(mut self, f: impl FnOnce(rpm::RPMBuilder) -> rpm::RPMBuilder) -> Self {
+        self.inner = f(self.inner);
+        self
+    }
+
     /// Populate registered files with the internal RPMBuilder.
+    ///
+    /// Manifest paths are installed relative to the filesystem root, so each
+    /// is registered with a leading `/`.
     pub fn populate_files(mut self) -> Result<Self> {
         self.files
             .materialize_files(&self.build_path)
@@ -57,7 +82,7 @@ impl RpmBuilder {
         for (rel_path, content) in self.files.iter_entries() {
             let real_path = self.build_path.join(rel_path);
 
-            let mut options = RPMFileOptions::new(rel_path.display().to_string());
+            let mut options = RPMFileOptions::new(format!("/{}", rel_path.display()));
 
             if content.is_executable() {
                 options = options.mode(0o100_775);
@@ -112,3 +137,40 @@ impl RpmBuilder {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use {super::*, tugger_file_manifest::FileEntry};
+
+    #[test]
+    fn manifest_paths_absolute() -> Result<()> {
+        let td = tempfile::Builder::new()
+            .prefix("tugger-rpm-test-")
+            .tempdir()?;
+
+        let mut manifest = FileManifest::default();
+        manifest.add_file_entry(
+            "usr/bin/myapp",
+            FileEntry::new_from_data(b"#!/bin/sh\n".to_vec(), true),
+        )?;
+
+        let mut builder = RpmBuilder::new(
+            td.path().join("build"),
+            "myapp",
+            "1.0",
+            "MPL-2.0",
+            "x86_64",
+            "my application",
+        );
+        builder.add_file_manifest(&manifest)?;
+
+        let dest = td.path().join("myapp.rpm");
+        builder.build_to_path(&dest)?;
+
+        // Directory names are stored uncompressed in the package header.
+        let data = std::fs::read(&dest)?;
+        assert!(data.windows(10).any(|w| w == b"/usr/bin/\0"));
+
+        Ok(())
+    }
+}
@@ -7,3 +7,7 @@ mod builder;
 
 #[cfg(target_family = "unix")]
 pub use builder::RpmBuilder;
+
+/// Re-export of the `rpm` crate so consumers can construct its types.
+#[cfg(target_family = "unix")]
+pub use rpm;
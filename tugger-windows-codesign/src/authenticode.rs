@@ -0,0 +1,672 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Pure Rust Authenticode signing of PE files.
+
+Authenticode signatures are PKCS #7 `SignedData` structures whose signed
+content is a `SpcIndirectDataContent` holding a digest of the PE image. The
+signature is stored in a `WIN_CERTIFICATE` structure in the PE's certificate
+table, which lives at the end of the file and is referenced by the
+`IMAGE_DIRECTORY_ENTRY_SECURITY` data directory entry.
+
+The image digest covers the entire file except for the optional header's
+`CheckSum` field, the certificate table data directory entry, and the
+certificate table itself. This means signatures can be added, replaced, or
+removed without invalidating the digest.
+
+This implementation does not require any Windows APIs and works on any host.
+*/
+
+use {
+    anyhow::{anyhow, Context, Result},
+    bcder::{ConstOid, Mode, Oid},
+    bytes::Bytes,
+    cryptographic_message_syntax::{SignedDataBuilder, SignerBuilder},
+    std::{
+        io::{Read, Seek, SeekFrom},
+        path::Path,
+    },
+    x509_certificate::{
+        rfc5652::AttributeValue, CapturedX509Certificate, DigestAlgorithm, KeyInfoSigner,
+    },
+};
+
+/// `SPC_INDIRECT_DATA_OBJID`: the content type of Authenticode signed content.
+///
+/// 1.3.6.1.4.1.311.2.1.4
+pub const OID_SPC_INDIRECT_DATA: ConstOid = Oid(&[43, 6, 1, 4, 1, 130, 55, 2, 1, 4]);
+
+/// `SPC_STATEMENT_TYPE_OBJID`: signed attribute describing the signing key purpose.
+///
+/// 1.3.6.1.4.1.311.2.1.11
+pub const OID_SPC_STATEMENT_TYPE: ConstOid = Oid(&[43, 6, 1, 4, 1, 130, 55, 2, 1, 11]);
+
+/// `SPC_SP_OPUS_INFO_OBJID`: signed attribute holding program name and URL.
+///
+/// 1.3.6.1.4.1.311.2.1.12
+pub const OID_SPC_SP_OPUS_INFO: ConstOid = Oid(&[43, 6, 1, 4, 1, 130, 55, 2, 1, 12]);
+
+/// `SPC_PE_IMAGE_DATA_OBJID`: identifies the signed content as a PE image.
+///
+/// 1.3.6.1.4.1.311.2.1.15
+pub const OID_SPC_PE_IMAGE_DATA: ConstOid = Oid(&[43, 6, 1, 4, 1, 130, 55, 2, 1, 15]);
+
+/// `SPC_INDIVIDUAL_SP_KEY_PURPOSE_OBJID`: individual (as opposed to commercial) code signing.
+///
+/// 1.3.6.1.4.1.311.2.1.21
+pub const OID_SPC_INDIVIDUAL_SP_KEY_PURPOSE: ConstOid = Oid(&[43, 6, 1, 4, 1, 130, 55, 2, 1, 21]);
+
+/// `szOID_RFC3161_counterSign`: unsigned attribute holding an RFC 3161 time-stamp token.
+///
+/// 1.3.6.1.4.1.311.3.3.1
+pub const OID_RFC3161_COUNTER_SIGNATURE: ConstOid = Oid(&[43, 6, 1, 4, 1, 130, 55, 3, 3, 1]);
+
+/// `WIN_CERT_REVISION_2_0`.
+pub const WIN_CERT_REVISION_2_0: u16 = 0x0200;
+
+/// `WIN_CERT_TYPE_PKCS_SIGNED_DATA`.
+pub const WIN_CERT_TYPE_PKCS_SIGNED_DATA: u16 = 0x0002;
+
+/// Index of `IMAGE_DIRECTORY_ENTRY_SECURITY` in the optional header data directories.
+const SECURITY_DIRECTORY_INDEX: usize = 4;
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|x| u16::from_le_bytes([x[0], x[1]]))
+        .ok_or_else(|| anyhow!("PE data truncated at offset {}", offset))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+        .ok_or_else(|| anyhow!("PE data truncated at offset {}", offset))
+}
+
+/// Resolve the file offset of the PE signature from a DOS header.
+fn pe_signature_offset(dos_header: &[u8]) -> Option<usize> {
+    if dos_header.get(0..2) != Some(b"MZ") {
+        return None;
+    }
+
+    read_u32(dos_header, 0x3c).ok().map(|x| x as usize)
+}
+
+/// Whether data looks like a PE file (an EXE or DLL).
+///
+/// Only the DOS header and PE signature are examined.
+///
+/// Unlike [crate::is_signable_binary_header], this does not match MSI files.
+pub fn is_pe_data(data: &[u8]) -> bool {
+    if let Some(offset) = pe_signature_offset(data) {
+        data.get(offset..offset + 4) == Some(b"PE\0\0")
+    } else {
+        false
+    }
+}
+
+/// Whether a filesystem path is a PE file (an EXE or DLL).
+///
+/// Like [is_pe_data], only the headers are read. The file isn't read in its
+/// entirety.
+pub fn is_pe_file(path: impl AsRef<Path>) -> Result<bool> {
+    let mut fh = std::fs::File::open(path.as_ref())?;
+
+    let mut dos_header = [0u8; 64];
+    if !read_exact_or_eof(&mut fh, &mut dos_header)? {
+        return Ok(false);
+    }
+
+    let offset = if let Some(offset) = pe_signature_offset(&dos_header) {
+        offset
+    } else {
+        return Ok(false);
+    };
+
+    fh.seek(SeekFrom::Start(offset as u64))?;
+
+    let mut signature = [0u8; 4];
+    Ok(read_exact_or_eof(&mut fh, &mut signature)? && &signature == b"PE\0\0")
+}
+
+/// Fill a buffer from a reader, returning `false` if EOF was reached first.
+fn read_exact_or_eof(reader: &mut impl Read, buf: &mut [u8]) -> Result<bool> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Locations of PE file structures relevant to Authenticode.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PeLayout {
+    /// File offset of the optional header's `CheckSum` field.
+    pub checksum_offset: usize,
+
+    /// File offset of the certificate table data directory entry.
+    pub certificate_directory_offset: usize,
+
+    /// File offset and size of the certificate table, if present.
+    pub certificate_table: Option<(usize, usize)>,
+}
+
+impl PeLayout {
+    /// Resolve the layout of PE data.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.get(0..2) != Some(b"MZ") {
+            return Err(anyhow!("data does not have a DOS header"));
+        }
+
+        let pe_offset = read_u32(data, 0x3c)? as usize;
+        if data.get(pe_offset..pe_offset + 4) != Some(b"PE\0\0") {
+            return Err(anyhow!("data does not have a PE signature"));
+        }
+
+        let coff_offset = pe_offset + 4;
+        let optional_header_size = read_u16(data, coff_offset + 16)? as usize;
+        let optional_offset = coff_offset + 20;
+
+        let (rva_count_offset, directories_offset) = match read_u16(data, optional_offset)? {
+            // PE32.
+            0x10b => (optional_offset + 92, optional_offset + 96),
+            // PE32+.
+            0x20b => (optional_offset + 108, optional_offset + 112),
+            magic => return Err(anyhow!("unknown optional header magic: {:#x}", magic)),
+        };
+
+        let rva_count = read_u32(data, rva_count_offset)? as usize;
+        let certificate_directory_offset = directories_offset + SECURITY_DIRECTORY_INDEX * 8;
+
+        if rva_count <= SECURITY_DIRECTORY_INDEX
+            || certificate_directory_offset + 8 > optional_offset + optional_header_size
+        {
+            return Err(anyhow!("optional header lacks a certificate table entry"));
+        }
+
+        let table_offset = read_u32(data, certificate_directory_offset)? as usize;
+        let table_size = read_u32(data, certificate_directory_offset + 4)? as usize;
+
+        let certificate_table = if table_offset == 0 || table_size == 0 {
+            None
+        } else if table_offset + table_size > data.len() {
+            return Err(anyhow!("certificate table extends beyond end of file"));
+        } else {
+            Some((table_offset, table_size))
+        };
+
+        Ok(Self {
+            checksum_offset: optional_offset + 64,
+            certificate_directory_offset,
+            certificate_table,
+        })
+    }
+
+    /// Obtain the raw `WIN_CERTIFICATE` entries in the certificate table.
+    ///
+    /// Returns tuples of `(revision, certificate type, certificate data)`.
+    pub fn certificates<'a>(&self, data: &'a [u8]) -> Result<Vec<(u16, u16, &'a [u8])>> {
+        let mut res = vec![];
+
+        if let Some((offset, size)) = self.certificate_table {
+            let table = &data[offset..offset + size];
+            let mut pos = 0;
+
+            while pos + 8 <= table.len() {
+                let length = read_u32(table, pos)? as usize;
+                let revision = read_u16(table, pos + 4)?;
+                let certificate_type = read_u16(table, pos + 6)?;
+
+                if length < 8 || pos + length > table.len() {
+                    return Err(anyhow!("malformed WIN_CERTIFICATE entry"));
+                }
+
+                res.push((revision, certificate_type, &table[pos + 8..pos + length]));
+
+                // Entries are aligned on 8 byte boundaries.
+                pos += (length + 7) & !7;
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Compute the Authenticode digest of PE data.
+    ///
+    /// The `CheckSum` field, certificate table directory entry, and certificate
+    /// table are excluded from the digest.
+    pub fn digest(&self, data: &[u8], algorithm: DigestAlgorithm) -> Vec<u8> {
+        let end = if let Some((offset, _)) = self.certificate_table {
+            offset
+        } else {
+            data.len()
+        };
+
+        let mut hasher = algorithm.digester();
+        hasher.update(&data[0..self.checksum_offset]);
+        hasher.update(&data[self.checksum_offset + 4..self.certificate_directory_offset]);
+        hasher.update(&data[self.certificate_directory_offset + 8..end]);
+
+        hasher.finish().as_ref().to_vec()
+    }
+}
+
+/// Compute the PE image checksum as stored in the optional header's `CheckSum` field.
+///
+/// The existing value of the `CheckSum` field is ignored.
+pub fn pe_checksum(data: &[u8], checksum_offset: usize) -> u32 {
+    let mut sum = 0u64;
+
+    for (i, chunk) in data.chunks(2).enumerate() {
+        let offset = i * 2;
+        if offset >= checksum_offset && offset < checksum_offset + 4 {
+            continue;
+        }
+
+        let word = if chunk.len() == 2 {
+            u16::from_le_bytes([chunk[0], chunk[1]])
+        } else {
+            chunk[0] as u16
+        };
+
+        sum += word as u64;
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+
+    sum = (sum & 0xffff) + (sum >> 16);
+
+    (sum as u32).wrapping_add(data.len() as u32)
+}
+
+/// Encode a DER value from a tag and contents octets.
+fn der_value(tag: u8, contents: &[u8]) -> Vec<u8> {
+    let mut res = vec![tag];
+
+    let len = contents.len();
+    if len < 0x80 {
+        res.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|b| **b == 0).count();
+        res.push(0x80 | (bytes.len() - skip) as u8);
+        res.extend_from_slice(&bytes[skip..]);
+    }
+
+    res.extend_from_slice(contents);
+    res
+}
+
+fn der_oid(oid: &[u8]) -> Vec<u8> {
+    der_value(0x06, oid)
+}
+
+fn attribute_value(der: Vec<u8>) -> Result<AttributeValue> {
+    let captured = Mode::Der
+        .decode(Bytes::from(der), |cons| cons.capture_one())
+        .map_err(|e| anyhow!("error decoding attribute value: {:?}", e))?;
+
+    Ok(AttributeValue::new(captured))
+}
+
+/// Construct the DER encoded `SpcIndirectDataContent` for a PE image digest.
+///
+/// ```ASN.1
+/// SpcIndirectDataContent ::= SEQUENCE {
+///     data                    SpcAttributeTypeAndOptionalValue,
+///     messageDigest           DigestInfo
+/// }
+///
+/// SpcAttributeTypeAndOptionalValue ::= SEQUENCE {
+///     type                    ObjectID,
+///     value                   [0] EXPLICIT ANY OPTIONAL
+/// }
+///
+/// SpcPeImageData ::= SEQUENCE {
+///    flags                   SpcPeImageFlags DEFAULT { includeResources },
+///    file                    SpcLink
+/// }
+///
+/// DigestInfo ::= SEQUENCE {
+///     digestAlgorithm     AlgorithmIdentifier,
+///     digest              OCTETSTRING
+/// }
+/// ```
+pub fn spc_indirect_data_content(algorithm: DigestAlgorithm, digest: &[u8]) -> Vec<u8> {
+    // The `file` link is vestigial. By convention, it is a BMPString with this value.
+    let obsolete = "<<<Obsolete>>>"
+        .encode_utf16()
+        .flat_map(|c| c.to_be_bytes())
+        .collect::<Vec<_>>();
+
+    // SpcLink.file [2] EXPLICIT SpcString.unicode [0] IMPLICIT BMPString.
+    let link = der_value(0xa2, &der_value(0x80, &obsolete));
+
+    // Empty BIT STRING for flags, followed by [0] EXPLICIT SpcLink.
+    let mut pe_image_data = vec![0x03, 0x01, 0x00];
+    pe_image_data.extend(der_value(0xa0, &link));
+    let pe_image_data = der_value(0x30, &pe_image_data);
+
+    let mut attribute = der_oid(OID_SPC_PE_IMAGE_DATA.as_ref());
+    attribute.extend(pe_image_data);
+    let attribute = der_value(0x30, &attribute);
+
+    let algorithm_oid = Oid::from(algorithm);
+    let mut algorithm_identifier = der_oid(algorithm_oid.as_ref());
+    // NULL parameters.
+    algorithm_identifier.extend([0x05, 0x00]);
+    let algorithm_identifier = der_value(0x30, &algorithm_identifier);
+
+    let mut digest_info = algorithm_identifier;
+    digest_info.extend(der_value(0x04, digest));
+    let digest_info = der_value(0x30, &digest_info);
+
+    let mut content = attribute;
+    content.extend(digest_info);
+
+    der_value(0x30, &content)
+}
+
+/// Entity for producing Authenticode signatures of PE files.
+pub struct AuthenticodeSigner<'a> {
+    /// The cryptographic key pair used for signing.
+    signing_key: &'a dyn KeyInfoSigner,
+
+    /// X.509 certificate used for signing.
+    signing_certificate: CapturedX509Certificate,
+
+    /// Additional certificates to embed in the signature.
+    certificate_chain: Vec<CapturedX509Certificate>,
+
+    /// Time-Stamp Protocol server URL to use.
+    time_stamp_url: Option<reqwest::Url>,
+}
+
+impl<'a> AuthenticodeSigner<'a> {
+    /// Construct a new instance from a signing key and its certificate.
+    pub fn new(
+        signing_key: &'a dyn KeyInfoSigner,
+        signing_certificate: CapturedX509Certificate,
+    ) -> Self {
+        Self {
+            signing_key,
+            signing_certificate,
+            certificate_chain: vec![],
+            time_stamp_url: None,
+        }
+    }
+
+    /// Add a certificate to embed in the signature.
+    ///
+    /// This should be used to register the certificates that issued the signing
+    /// certificate so verifiers can build a chain to a trusted root.
+    pub fn chain_certificate(&mut self, certificate: CapturedX509Certificate) {
+        self.certificate_chain.push(certificate);
+    }
+
+    /// Set the URL of an RFC 3161 Time-Stamp Protocol server to counter-sign with.
+    pub fn time_stamp_url(&mut self, url: impl reqwest::IntoUrl) -> Result<()> {
+        self.time_stamp_url = Some(url.into_url()?);
+
+        Ok(())
+    }
+
+    /// Produce the DER encoded PKCS #7 signature for PE data.
+    ///
+    /// The data should already have any existing certificate table removed and
+    /// be padded to an 8 byte boundary, as the digest is computed over it as-is.
+    fn signature_for_image(&self, image: &[u8], layout: &PeLayout) -> Result<Vec<u8>> {
+        let digest_algorithm = DigestAlgorithm::Sha256;
+
+        let content =
+            spc_indirect_data_content(digest_algorithm, &layout.digest(image, digest_algorithm));
+
+        // SpcSpOpusInfo with neither program name nor URL.
+        let opus_info = der_value(0x30, &[]);
+        let statement_type = der_value(0x30, &der_oid(OID_SPC_INDIVIDUAL_SP_KEY_PURPOSE.as_ref()));
+
+        let mut signer = SignerBuilder::new(self.signing_key, self.signing_certificate.clone())
+            .content_type(Oid(OID_SPC_INDIRECT_DATA.as_ref().into()))
+            .signed_attribute(
+                Oid(OID_SPC_SP_OPUS_INFO.as_ref().into()),
+                vec![attribute_value(opus_info)?],
+            )
+            .signed_attribute(
+                Oid(OID_SPC_STATEMENT_TYPE.as_ref().into()),
+                vec![attribute_value(statement_type)?],
+            );
+
+        if let Some(url) = &self.time_stamp_url {
+            signer = signer
+                .time_stamp_url(url.clone())?
+                .time_stamp_attribute_oid(Oid(OID_RFC3161_COUNTER_SIGNATURE.as_ref().into()));
+        }
+
+        let der = SignedDataBuilder::default()
+            .content_type(Oid(OID_SPC_INDIRECT_DATA.as_ref().into()))
+            .signed_content_asn1(content)?
+            .signer(signer)
+            .certificates(self.certificate_chain.iter().cloned())
+            .build_der()?;
+
+        Ok(der)
+    }
+
+    /// Sign PE data, returning the signed PE data.
+    ///
+    /// Any existing signature is replaced.
+    pub fn sign_pe_data(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let layout = PeLayout::parse(data).context("parsing PE data")?;
+
+        // Strip an existing certificate table. It must be at the end of the file.
+        let mut image = if let Some((offset, size)) = layout.certificate_table {
+            if offset + size != data.len() {
+                return Err(anyhow!(
+                    "existing certificate table is not at the end of the file"
+                ));
+            }
+
+            data[0..offset].to_vec()
+        } else {
+            data.to_vec()
+        };
+
+        // The certificate table must be 8 byte aligned. The padding is part of the
+        // digested image.
+        image.resize(image.len() + (8 - image.len() % 8) % 8, 0);
+        image[layout.certificate_directory_offset..layout.certificate_directory_offset + 8].fill(0);
+
+        let layout = PeLayout {
+            certificate_table: None,
+            ..layout
+        };
+
+        let signature = self.signature_for_image(&image, &layout)?;
+
+        let certificate_length = 8 + signature.len();
+        let certificate_size = (certificate_length + 7) & !7;
+        let table_offset = image.len();
+
+        let table_offset_u32 =
+            u32::try_from(table_offset).map_err(|_| anyhow!("PE file too large to sign"))?;
+        let certificate_size_u32 =
+            u32::try_from(certificate_size).map_err(|_| anyhow!("signature too large"))?;
+
+        image.extend_from_slice(&certificate_size_u32.to_le_bytes());
+        image.extend_from_slice(&WIN_CERT_REVISION_2_0.to_le_bytes());
+        image.extend_from_slice(&WIN_CERT_TYPE_PKCS_SIGNED_DATA.to_le_bytes());
+        image.extend_from_slice(&signature);
+        image.resize(table_offset + certificate_size, 0);
+
+        let directory = layout.certificate_directory_offset;
+        image[directory..directory + 4].copy_from_slice(&table_offset_u32.to_le_bytes());
+        image[directory + 4..directory + 8].copy_from_slice(&certificate_size_u32.to_le_bytes());
+
+        let checksum = pe_checksum(&image, layout.checksum_offset);
+        image[layout.checksum_offset..layout.checksum_offset + 4]
+            .copy_from_slice(&checksum.to_le_bytes());
+
+        Ok(image)
+    }
+
+    /// Sign a PE file on the filesystem, writing the signed file to a destination path.
+    ///
+    /// The source and destination paths can be the same.
+    pub fn sign_pe_file(&self, source: impl AsRef<Path>, dest: impl AsRef<Path>) -> Result<()> {
+        let source = source.as_ref();
+        let dest = dest.as_ref();
+
+        let data =
+            std::fs::read(source).with_context(|| format!("reading {}", source.display()))?;
+        let signed = self
+            .sign_pe_data(&data)
+            .with_context(|| format!("signing {}", source.display()))?;
+        std::fs::write(dest, signed).with_context(|| format!("writing {}", dest.display()))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use {
+        super::*,
+        cryptographic_message_syntax::SignedData,
+        tugger_common::testutil::DEFAULT_TEMP_DIR,
+        x509_certificate::testutil::{rsa_cert, rsa_private_key},
+    };
+
+    /// Construct a minimal PE32 image with a single section of data.
+    pub(crate) fn minimal_pe(pe32_plus: bool) -> Vec<u8> {
+        let mut data = vec![0u8; 0x200];
+        data[0..2].copy_from_slice(b"MZ");
+        data[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        data[0x40..0x44].copy_from_slice(b"PE\0\0");
+
+        let coff = 0x44;
+        // IMAGE_FILE_MACHINE_AMD64 / I386.
+        let machine: u16 = if pe32_plus { 0x8664 } else { 0x14c };
+        data[coff..coff + 2].copy_from_slice(&machine.to_le_bytes());
+        data[coff + 2..coff + 4].copy_from_slice(&1u16.to_le_bytes());
+
+        let optional_size: u16 = if pe32_plus { 240 } else { 224 };
+        data[coff + 16..coff + 18].copy_from_slice(&optional_size.to_le_bytes());
+
+        let optional = coff + 20;
+        let magic: u16 = if pe32_plus { 0x20b } else { 0x10b };
+        data[optional..optional + 2].copy_from_slice(&magic.to_le_bytes());
+        // SizeOfHeaders.
+        data[optional + 60..optional + 64].copy_from_slice(&0x200u32.to_le_bytes());
+        let rva_count = if pe32_plus {
+            optional + 108
+        } else {
+            optional + 92
+        };
+        data[rva_count..rva_count + 4].copy_from_slice(&16u32.to_le_bytes());
+
+        data.extend((0..0x200u32).map(|x| x as u8));
+
+        data
+    }
+
+    #[test]
+    fn parse_layout() -> Result<()> {
+        let layout = PeLayout::parse(&minimal_pe(false))?;
+        assert_eq!(layout.checksum_offset, 0x58 + 64);
+        assert_eq!(layout.certificate_directory_offset, 0x58 + 96 + 32);
+        assert!(layout.certificate_table.is_none());
+
+        let layout = PeLayout::parse(&minimal_pe(true))?;
+        assert_eq!(layout.certificate_directory_offset, 0x58 + 112 + 32);
+
+        assert!(!is_pe_data(b"MZ not a PE file"));
+        assert!(is_pe_data(&minimal_pe(false)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn pe_file_detection() -> Result<()> {
+        let temp_dir = DEFAULT_TEMP_DIR.path().join("pe_file_detection");
+        std::fs::create_dir_all(&temp_dir)?;
+
+        let pe_path = temp_dir.join("minimal.exe");
+        std::fs::write(&pe_path, minimal_pe(true))?;
+        assert!(is_pe_file(&pe_path)?);
+
+        let short_path = temp_dir.join("short.exe");
+        std::fs::write(&short_path, b"MZ")?;
+        assert!(!is_pe_file(&short_path)?);
+
+        // PE signature offset beyond the end of the file.
+        let mut data = minimal_pe(false);
+        data[0x3c..0x40].copy_from_slice(&0xffffu32.to_le_bytes());
+        let bad_offset_path = temp_dir.join("bad-offset.exe");
+        std::fs::write(&bad_offset_path, &data)?;
+        assert!(!is_pe_file(&bad_offset_path)?);
+        assert!(!is_pe_data(&data));
+
+        Ok(())
+    }
+
+    #[test]
+    fn digest_excludes_checksum_and_directory() -> Result<()> {
+        let data = minimal_pe(false);
+        let layout = PeLayout::parse(&data)?;
+        let digest = layout.digest(&data, DigestAlgorithm::Sha256);
+
+        let mut modified = data.clone();
+        modified[layout.checksum_offset] = 0x42;
+        assert_eq!(layout.digest(&modified, DigestAlgorithm::Sha256), digest);
+
+        modified[0x300] ^= 0xff;
+        assert_ne!(layout.digest(&modified, DigestAlgorithm::Sha256), digest);
+
+        Ok(())
+    }
+
+    #[test]
+    fn sign_and_resign() -> Result<()> {
+        let key = rsa_private_key();
+        let cert = rsa_cert();
+        let signer = AuthenticodeSigner::new(&key, cert);
+
+        for pe32_plus in [false, true] {
+            let data = minimal_pe(pe32_plus);
+            let signed = signer.sign_pe_data(&data)?;
+
+            let layout = PeLayout::parse(&signed)?;
+            let (offset, size) = layout.certificate_table.unwrap();
+            assert_eq!(offset, data.len());
+            assert_eq!(offset + size, signed.len());
+            assert_eq!(
+                read_u32(&signed, layout.checksum_offset)?,
+                pe_checksum(&signed, layout.checksum_offset)
+            );
+
+            let certificates = layout.certificates(&signed)?;
+            assert_eq!(certificates.len(), 1);
+            let (revision, certificate_type, der) = certificates[0];
+            assert_eq!(revision, WIN_CERT_REVISION_2_0);
+            assert_eq!(certificate_type, WIN_CERT_TYPE_PKCS_SIGNED_DATA);
+
+            let signed_data = SignedData::parse_ber(der)?;
+            for signer in signed_data.signers() {
+                signer.verify_signature_with_signed_data(&signed_data)?;
+                signer.verify_message_digest_with_signed_data(&signed_data)?;
+            }
+
+            // The signed content embeds the digest of the image.
+            let content = signed_data.signed_content().unwrap();
+            let digest = layout.digest(&signed, DigestAlgorithm::Sha256);
+            assert!(content.ends_with(&digest));
+
+            // Re-signing replaces the existing signature.
+            let resigned = signer.sign_pe_data(&signed)?;
+            let layout = PeLayout::parse(&resigned)?;
+            assert_eq!(layout.certificates(&resigned)?.len(), 1);
+            assert_eq!(layout.certificate_table.unwrap().0, data.len());
+        }
+
+        Ok(())
+    }
+}
@@ -4,6 +4,8 @@
 
 /*! Code signing on Windows. */
 
+mod authenticode;
+pub use authenticode::*;
 mod signing;
 pub use signing::*;
 mod signtool;
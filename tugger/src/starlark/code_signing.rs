@@ -28,7 +28,7 @@ use {
     },
     tugger_code_signing::{
         SignableCandidate, SignedOutput, Signer, SigningCertificate, SigningDestination,
-        SigningError,
+        SigningError, WindowsSigningMethod,
     },
     tugger_file_manifest::{FileEntry, FileManifest},
 };
@@ -210,6 +210,29 @@ impl CodeSignerValue {
         })
     }
 
+    fn set_windows_signing_method(&self, method: String) -> ValueResult {
+        let label = "set_windows_signing_method()";
+
+        let method = match method.as_str() {
+            "signtool" => WindowsSigningMethod::Signtool,
+            "authenticode" => WindowsSigningMethod::Authenticode,
+            _ => {
+                return Err(ValueError::Runtime(RuntimeError {
+                    code: "TUGGER_CODE_SIGNING",
+                    message: format!(
+                        "invalid Windows signing method: {}; must be 'signtool' or 'authenticode'",
+                        method
+                    ),
+                    label: label.to_string(),
+                }));
+            }
+        };
+
+        self.signer(label)?.windows_signing_method(method);
+
+        Ok(Value::new(NoneType::None))
+    }
+
     fn set_signing_callback(&mut self, func: Value) -> ValueResult {
         required_type_arg("func", "function", &func)?;
 
@@ -626,6 +649,11 @@ starlark_module! { code_signing_module =>
         this.set_time_stamp_server(url)
     }
 
+    CodeSigner.set_windows_signing_method(this, method: String) {
+        let this = this.downcast_ref::<CodeSignerValue>().unwrap();
+        this.set_windows_signing_method(method)
+    }
+
     CodeSigner.set_signing_callback(this, func) {
         let mut this = this.downcast_mut::<CodeSignerValue>().unwrap().unwrap();
         this.set_signing_callback(func)
@@ -811,6 +839,19 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn set_windows_signing_method() -> Result<()> {
+        let mut env = env_with_pfx_signer()?;
+
+        env.eval("signer.set_windows_signing_method('signtool')")?;
+        env.eval("signer.set_windows_signing_method('authenticode')")?;
+        assert!(env
+            .eval("signer.set_windows_signing_method('invalid')")
+            .is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn activate() -> Result<()> {
         let mut env = StarlarkEnvironment::new()?;
@@ -0,0 +1,453 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use {
+    crate::starlark::{
+        file_content::{FileContentValue, FileContentWrapper},
+        file_manifest::FileManifestValue,
+    },
+    anyhow::{anyhow, Context},
+    debian_packaging::{
+        control::{ControlFile, ControlParagraph},
+        deb::{builder::DebBuilder, DebCompression},
+    },
+    slog::warn,
+    starlark::{
+        environment::TypeValues,
+        values::{
+            error::{RuntimeError, UnsupportedOperation, ValueError},
+            none::NoneType,
+            Mutable, TypedValue, Value, ValueResult,
+        },
+        {
+            starlark_fun, starlark_module, starlark_parse_param_type, starlark_signature,
+            starlark_signature_extraction, starlark_signatures,
+        },
+    },
+    starlark_dialect_build_targets::{
+        get_context_value, optional_str_arg, EnvironmentContext, ResolvedTarget,
+        ResolvedTargetValue, RunMode,
+    },
+    std::{
+        borrow::Cow,
+        io::Write,
+        path::{Path, PathBuf},
+        time::{Duration, SystemTime},
+    },
+    tugger_file_manifest::FileManifest,
+};
+
+fn error_context<F, T>(label: &str, f: F) -> Result<T, ValueError>
+where
+    F: FnOnce() -> anyhow::Result<T>,
+{
+    f().map_err(|e| {
+        ValueError::Runtime(RuntimeError {
+            code: "TUGGER_DEBIAN_PACKAGE_BUILDER",
+            message: format!("{:?}", e),
+            label: label.to_string(),
+        })
+    })
+}
+
+fn deb_compression(value: &str) -> anyhow::Result<DebCompression> {
+    match value {
+        "none" => Ok(DebCompression::Uncompressed),
+        "gzip" => Ok(DebCompression::Gzip),
+        "xz" => Ok(DebCompression::Xz(6)),
+        "zstd" => Ok(DebCompression::Zstandard(3)),
+        _ => Err(anyhow!(
+            "unknown compression format: {} (expected none, gzip, xz, or zstd)",
+            value
+        )),
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DebianPackageBuilderValue {
+    /// The paragraph constituting the `control` file.
+    control: ControlParagraph<'static>,
+    /// Files to install as part of the package.
+    install_files: FileManifest,
+    /// Extra files to add to the `control.tar` archive.
+    control_files: FileManifest,
+    compression: String,
+    mtime: Option<u64>,
+}
+
+impl TypedValue for DebianPackageBuilderValue {
+    type Holder = Mutable<DebianPackageBuilderValue>;
+    const TYPE: &'static str = "DebianPackageBuilder";
+
+    fn values_for_descendant_check_and_freeze(&self) -> Box<dyn Iterator<Item = Value>> {
+        Box::new(std::iter::empty())
+    }
+
+    fn get_attr(&self, attribute: &str) -> ValueResult {
+        Ok(match attribute {
+            "compression" => Value::from(self.compression.as_str()),
+            "mtime" => {
+                if let Some(mtime) = self.mtime {
+                    Value::from(mtime as i64)
+                } else {
+                    Value::from(NoneType::None)
+                }
+            }
+            "package_file_name" => Value::from(self.package_file_name()),
+            _ => {
+                return Err(ValueError::OperationNotSupported {
+                    op: UnsupportedOperation::GetAttr(attribute.to_string()),
+                    left: Self::TYPE.to_string(),
+                    right: None,
+                })
+            }
+        })
+    }
+
+    fn has_attr(&self, attribute: &str) -> Result<bool, ValueError> {
+        Ok(matches!(
+            attribute,
+            "compression" | "mtime" | "package_file_name"
+        ))
+    }
+
+    fn set_attr(&mut self, attribute: &str, value: Value) -> Result<(), ValueError> {
+        match attribute {
+            "compression" => {
+                let compression = value.to_string();
+                error_context("DebianPackageBuilder.compression = ", || {
+                    deb_compression(&compression)
+                })?;
+                self.compression = compression;
+            }
+            "mtime" => {
+                self.mtime = if value.get_type() == "NoneType" {
+                    None
+                } else {
+                    let mtime = value.to_int()?;
+                    Some(error_context("DebianPackageBuilder.mtime = ", || {
+                        u64::try_from(mtime).map_err(|_| anyhow!("mtime must not be negative"))
+                    })?)
+                };
+            }
+            attr => {
+                return Err(ValueError::OperationNotSupported {
+                    op: UnsupportedOperation::SetAttr(attr.to_string()),
+                    left: Self::TYPE.to_string(),
+                    right: None,
+                })
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl DebianPackageBuilderValue {
+    pub fn new_from_args(
+        package: String,
+        version: String,
+        architecture: String,
+        maintainer: String,
+        description: String,
+    ) -> ValueResult {
+        let mut control = ControlParagraph::default();
+        for (name, value) in [
+            ("Package", package),
+            ("Version", version),
+            ("Architecture", architecture),
+            ("Maintainer", maintainer),
+            ("Description", description),
+        ] {
+            control.set_field_from_string(Cow::Borrowed(name), Cow::Owned(value));
+        }
+
+        Ok(Value::new(Self {
+            control,
+            install_files: FileManifest::default(),
+            control_files: FileManifest::default(),
+            compression: "gzip".to_string(),
+            mtime: None,
+        }))
+    }
+
+    /// The filename of the `.deb` file, in the canonical `name_version_arch.deb` form.
+    fn package_file_name(&self) -> String {
+        format!(
+            "{}_{}_{}.deb",
+            self.control.field_str("Package").unwrap_or_default(),
+            self.control.field_str("Version").unwrap_or_default(),
+            self.control.field_str("Architecture").unwrap_or_default()
+        )
+    }
+
+    fn write_deb(&self, writer: &mut impl Write) -> anyhow::Result<()> {
+        let mut control_file = ControlFile::default();
+        control_file.add_paragraph(self.control.clone());
+
+        let mut builder = DebBuilder::new(control_file)
+            .set_compression(deb_compression(&self.compression)?)
+            .set_mtime(
+                self.mtime
+                    .map(|mtime| SystemTime::UNIX_EPOCH + Duration::from_secs(mtime)),
+            );
+
+        for (path, entry) in self.control_files.iter_entries() {
+            builder = builder
+                .extra_control_tar_file(path, entry.clone())
+                .with_context(|| format!("adding control file {}", path.display()))?;
+        }
+
+        for (path, entry) in self.install_files.iter_entries() {
+            builder = builder
+                .install_file(path, entry.clone())
+                .with_context(|| format!("adding file {}", path.display()))?;
+        }
+
+        builder.write(writer).context("writing .deb")?;
+
+        Ok(())
+    }
+
+    fn write_deb_to_directory(&self, dest_dir: &Path) -> anyhow::Result<PathBuf> {
+        std::fs::create_dir_all(dest_dir)
+            .with_context(|| format!("creating directory {}", dest_dir.display()))?;
+
+        let path = dest_dir.join(self.package_file_name());
+
+        let mut fh = std::io::BufWriter::new(
+            std::fs::File::create(&path).with_context(|| format!("creating {}", path.display()))?,
+        );
+        self.write_deb(&mut fh)?;
+        fh.flush()?;
+
+        Ok(path)
+    }
+
+    pub fn set_control_field(&mut self, name: String, value: String) -> ValueResult {
+        self.control
+            .set_field_from_string(Cow::Owned(name), Cow::Owned(value));
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_manifest(&mut self, manifest: FileManifestValue) -> ValueResult {
+        const LABEL: &str = "DebianPackageBuilder.add_manifest()";
+
+        let manifest = manifest.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            self.install_files
+                .add_manifest(&manifest)
+                .context("adding manifest")
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_control_file(&mut self, content: FileContentValue, path: Value) -> ValueResult {
+        const LABEL: &str = "DebianPackageBuilder.add_control_file()";
+
+        let path = optional_str_arg("path", &path)?;
+
+        let inner = content.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            let path = if let Some(path) = path {
+                PathBuf::from(path)
+            } else {
+                PathBuf::from(&inner.filename)
+            };
+
+            self.control_files
+                .add_file_entry(path, inner.content.clone())
+                .context("adding control file")
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn to_file_content(&self) -> ValueResult {
+        const LABEL: &str = "DebianPackageBuilder.to_file_content()";
+
+        let data = error_context(LABEL, || {
+            let mut data = vec![];
+            self.write_deb(&mut data)?;
+
+            Ok(data)
+        })?;
+
+        Ok(FileContentWrapper {
+            content: data.into(),
+            filename: self.package_file_name(),
+        }
+        .into())
+    }
+
+    pub fn write_to_directory(&self, type_values: &TypeValues, path: String) -> ValueResult {
+        const LABEL: &str = "DebianPackageBuilder.write_to_directory()";
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let dest_dir = context.resolve_path(path);
+
+        let deb_path = error_context(LABEL, || self.write_deb_to_directory(&dest_dir))?;
+
+        Ok(Value::from(format!("{}", deb_path.display())))
+    }
+
+    fn build(&self, type_values: &TypeValues, target: String) -> ValueResult {
+        const LABEL: &str = "DebianPackageBuilder.build()";
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let output_directory = context.target_build_path(&target);
+
+        let deb_path = error_context(LABEL, || {
+            warn!(
+                context.logger(),
+                "writing .deb to {}",
+                output_directory.display()
+            );
+
+            self.write_deb_to_directory(&output_directory)
+        })?;
+
+        warn!(context.logger(), "wrote {}", deb_path.display());
+
+        Ok(Value::new(ResolvedTargetValue {
+            inner: ResolvedTarget {
+                run_mode: RunMode::None,
+                output_path: output_directory,
+            },
+        }))
+    }
+}
+
+starlark_module! { debian_package_builder_module =>
+    #[allow(non_snake_case)]
+    DebianPackageBuilder(
+        package: String,
+        version: String,
+        architecture: String,
+        maintainer: String,
+        description: String
+    ) {
+        DebianPackageBuilderValue::new_from_args(package, version, architecture, maintainer, description)
+    }
+
+    DebianPackageBuilder.set_control_field(this, name: String, value: String) {
+        let mut this = this.downcast_mut::<DebianPackageBuilderValue>().unwrap().unwrap();
+        this.set_control_field(name, value)
+    }
+
+    DebianPackageBuilder.add_manifest(this, manifest: FileManifestValue) {
+        let mut this = this.downcast_mut::<DebianPackageBuilderValue>().unwrap().unwrap();
+        this.add_manifest(manifest)
+    }
+
+    DebianPackageBuilder.add_control_file(this, content: FileContentValue, path = NoneType::None) {
+        let mut this = this.downcast_mut::<DebianPackageBuilderValue>().unwrap().unwrap();
+        this.add_control_file(content, path)
+    }
+
+    DebianPackageBuilder.to_file_content(this) {
+        let this = this.downcast_ref::<DebianPackageBuilderValue>().unwrap();
+        this.to_file_content()
+    }
+
+    DebianPackageBuilder.write_to_directory(env env, this, path: String) {
+        let this = this.downcast_ref::<DebianPackageBuilderValue>().unwrap();
+        this.write_to_directory(env, path)
+    }
+
+    DebianPackageBuilder.build(env env, this, target: String) {
+        let this = this.downcast_ref::<DebianPackageBuilderValue>().unwrap();
+        this.build(env, target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::starlark::testutil::*, anyhow::Result,
+        debian_packaging::deb::reader::BinaryPackageReader,
+    };
+
+    #[test]
+    fn type_info() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        let raw = env.eval(
+            "DebianPackageBuilder('myapp', '0.1', 'amd64', 'Me <me@example.com>', 'My app')",
+        )?;
+        assert_eq!(raw.get_type(), DebianPackageBuilderValue::TYPE);
+
+        Ok(())
+    }
+
+    #[test]
+    fn attributes() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval(
+            "b = DebianPackageBuilder('myapp', '0.1', 'amd64', 'Me <me@example.com>', 'My app')",
+        )?;
+
+        let v = env.eval("b.package_file_name")?;
+        assert_eq!(v.to_string(), "myapp_0.1_amd64.deb");
+
+        let v = env.eval("b.compression")?;
+        assert_eq!(v.to_string(), "gzip");
+
+        let v = env.eval("b.mtime")?;
+        assert_eq!(v.get_type(), "NoneType");
+
+        env.eval("b.compression = 'zstd'")?;
+        env.eval("b.mtime = 42")?;
+        assert_eq!(env.eval("b.compression")?.to_string(), "zstd");
+        assert_eq!(env.eval("b.mtime")?.to_int()?, 42);
+
+        assert!(env.eval("b.compression = 'bogus'").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn to_file_content() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("m = FileManifest()")?;
+        env.eval("m.add_file(FileContent(filename = 'myapp', content = 'app', executable = True), directory = 'usr/bin')")?;
+        env.eval(
+            "b = DebianPackageBuilder('myapp', '0.1', 'amd64', 'Me <me@example.com>', 'My app')",
+        )?;
+        env.eval("b.set_control_field('Depends', 'libc6')")?;
+        env.eval("b.add_manifest(m)")?;
+        env.eval("b.mtime = 0")?;
+        let f = env.eval("b.to_file_content()")?;
+
+        assert_eq!(f.get_type(), "FileContent");
+        let value = f.downcast_ref::<FileContentValue>().unwrap();
+        let inner = value.inner("ignored").unwrap();
+        assert_eq!(inner.filename, "myapp_0.1_amd64.deb");
+
+        let data = inner.content.resolve_content()?;
+        let mut reader = BinaryPackageReader::new(std::io::Cursor::new(data))?;
+        let mut entries = 0;
+        while let Some(entry) = reader.next_entry() {
+            entry?;
+            entries += 1;
+        }
+        assert_eq!(entries, 3);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,482 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use {
+    crate::starlark::{file_content::FileContentWrapper, file_manifest::FileManifestValue},
+    anyhow::{anyhow, Context},
+    slog::warn,
+    starlark::{
+        environment::TypeValues,
+        values::{
+            error::{RuntimeError, UnsupportedOperation, ValueError},
+            none::NoneType,
+            Mutable, TypedValue, Value, ValueResult,
+        },
+        {
+            starlark_fun, starlark_module, starlark_parse_param_type, starlark_signature,
+            starlark_signature_extraction, starlark_signatures,
+        },
+    },
+    starlark_dialect_build_targets::{
+        get_context_value, EnvironmentContext, ResolvedTarget, ResolvedTargetValue, RunMode,
+    },
+    std::{
+        io::Write,
+        path::{Path, PathBuf},
+    },
+    tugger_file_manifest::FileManifest,
+    tugger_rpm::{rpm::Dependency, RpmBuilder},
+};
+
+fn error_context<F, T>(label: &str, f: F) -> Result<T, ValueError>
+where
+    F: FnOnce() -> anyhow::Result<T>,
+{
+    f().map_err(|e| {
+        ValueError::Runtime(RuntimeError {
+            code: "TUGGER_RPM_PACKAGE_BUILDER",
+            message: format!("{:?}", e),
+            label: label.to_string(),
+        })
+    })
+}
+
+fn optional_string_value(value: &Option<String>) -> Value {
+    if let Some(value) = value {
+        Value::from(value.as_str())
+    } else {
+        Value::from(NoneType::None)
+    }
+}
+
+fn optional_string_from_value(value: &Value) -> Option<String> {
+    if value.get_type() == "NoneType" {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RpmPackageBuilderValue {
+    name: String,
+    version: String,
+    license: String,
+    arch: String,
+    description: String,
+    release: u16,
+    epoch: i32,
+    vendor: Option<String>,
+    url: Option<String>,
+    pre_install_script: Option<String>,
+    post_install_script: Option<String>,
+    pre_uninstall_script: Option<String>,
+    post_uninstall_script: Option<String>,
+    requires: Vec<String>,
+    provides: Vec<String>,
+    conflicts: Vec<String>,
+    obsoletes: Vec<String>,
+    /// Files to install as part of the package.
+    files: FileManifest,
+}
+
+impl TypedValue for RpmPackageBuilderValue {
+    type Holder = Mutable<RpmPackageBuilderValue>;
+    const TYPE: &'static str = "RpmPackageBuilder";
+
+    fn values_for_descendant_check_and_freeze(&self) -> Box<dyn Iterator<Item = Value>> {
+        Box::new(std::iter::empty())
+    }
+
+    fn get_attr(&self, attribute: &str) -> ValueResult {
+        Ok(match attribute {
+            "epoch" => Value::from(self.epoch as i64),
+            "package_file_name" => Value::from(self.package_file_name()),
+            "post_install_script" => optional_string_value(&self.post_install_script),
+            "post_uninstall_script" => optional_string_value(&self.post_uninstall_script),
+            "pre_install_script" => optional_string_value(&self.pre_install_script),
+            "pre_uninstall_script" => optional_string_value(&self.pre_uninstall_script),
+            "release" => Value::from(self.release as i64),
+            "url" => optional_string_value(&self.url),
+            "vendor" => optional_string_value(&self.vendor),
+            _ => {
+                return Err(ValueError::OperationNotSupported {
+                    op: UnsupportedOperation::GetAttr(attribute.to_string()),
+                    left: Self::TYPE.to_string(),
+                    right: None,
+                })
+            }
+        })
+    }
+
+    fn has_attr(&self, attribute: &str) -> Result<bool, ValueError> {
+        Ok(matches!(
+            attribute,
+            "epoch"
+                | "package_file_name"
+                | "post_install_script"
+                | "post_uninstall_script"
+                | "pre_install_script"
+                | "pre_uninstall_script"
+                | "release"
+                | "url"
+                | "vendor"
+        ))
+    }
+
+    fn set_attr(&mut self, attribute: &str, value: Value) -> Result<(), ValueError> {
+        match attribute {
+            "epoch" => {
+                let epoch = value.to_int()?;
+                self.epoch = error_context("RpmPackageBuilder.epoch = ", || {
+                    i32::try_from(epoch).map_err(|_| anyhow!("epoch out of range"))
+                })?;
+            }
+            "post_install_script" => {
+                self.post_install_script = optional_string_from_value(&value);
+            }
+            "post_uninstall_script" => {
+                self.post_uninstall_script = optional_string_from_value(&value);
+            }
+            "pre_install_script" => {
+                self.pre_install_script = optional_string_from_value(&value);
+            }
+            "pre_uninstall_script" => {
+                self.pre_uninstall_script = optional_string_from_value(&value);
+            }
+            "release" => {
+                let release = value.to_int()?;
+                self.release = error_context("RpmPackageBuilder.release = ", || {
+                    u16::try_from(release).map_err(|_| anyhow!("release out of range"))
+                })?;
+            }
+            "url" => {
+                self.url = optional_string_from_value(&value);
+            }
+            "vendor" => {
+                self.vendor = optional_string_from_value(&value);
+            }
+            attr => {
+                return Err(ValueError::OperationNotSupported {
+                    op: UnsupportedOperation::SetAttr(attr.to_string()),
+                    left: Self::TYPE.to_string(),
+                    right: None,
+                })
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl RpmPackageBuilderValue {
+    pub fn new_from_args(
+        name: String,
+        version: String,
+        license: String,
+        arch: String,
+        description: String,
+    ) -> ValueResult {
+        Ok(Value::new(Self {
+            name,
+            version,
+            license,
+            arch,
+            description,
+            release: 1,
+            epoch: 0,
+            vendor: None,
+            url: None,
+            pre_install_script: None,
+            post_install_script: None,
+            pre_uninstall_script: None,
+            post_uninstall_script: None,
+            requires: vec![],
+            provides: vec![],
+            conflicts: vec![],
+            obsoletes: vec![],
+            files: FileManifest::default(),
+        }))
+    }
+
+    /// The filename of the `.rpm` file, in the canonical `name-version-release.arch.rpm` form.
+    fn package_file_name(&self) -> String {
+        format!(
+            "{}-{}-{}.{}.rpm",
+            self.name, self.version, self.release, self.arch
+        )
+    }
+
+    fn write_rpm(&self, writer: &mut impl Write) -> anyhow::Result<()> {
+        // RPMBuilder reads file content from the filesystem, so files need to be
+        // materialized somewhere first.
+        let build_dir = tempfile::Builder::new()
+            .prefix("tugger-rpm-")
+            .tempdir()
+            .context("creating temporary directory")?;
+
+        let mut builder = RpmBuilder::new(
+            build_dir.path(),
+            &self.name,
+            &self.version,
+            &self.license,
+            &self.arch,
+            &self.description,
+        );
+        builder.add_file_manifest(&self.files)?;
+
+        let builder = builder.map_inner(|mut inner| {
+            inner = inner.release(self.release).epoch(self.epoch);
+
+            if let Some(vendor) = &self.vendor {
+                inner = inner.vendor(vendor.clone());
+            }
+            if let Some(url) = &self.url {
+                inner = inner.url(url.clone());
+            }
+            if let Some(script) = &self.pre_install_script {
+                inner = inner.pre_install_script(script.clone());
+            }
+            if let Some(script) = &self.post_install_script {
+                inner = inner.post_install_script(script.clone());
+            }
+            if let Some(script) = &self.pre_uninstall_script {
+                inner = inner.pre_uninstall_script(script.clone());
+            }
+            if let Some(script) = &self.post_uninstall_script {
+                inner = inner.post_uninstall_script(script.clone());
+            }
+
+            for name in &self.requires {
+                inner = inner.requires(Dependency::any(name.clone()));
+            }
+            for name in &self.provides {
+                inner = inner.provides(Dependency::any(name.clone()));
+            }
+            for name in &self.conflicts {
+                inner = inner.conflicts(Dependency::any(name.clone()));
+            }
+            for name in &self.obsoletes {
+                inner = inner.obsoletes(Dependency::any(name.clone()));
+            }
+
+            inner
+        });
+
+        let package = builder.build()?;
+        package
+            .write(writer)
+            .map_err(|e| anyhow!("error writing RPM: {}", e))?;
+
+        Ok(())
+    }
+
+    fn write_rpm_to_directory(&self, dest_dir: &Path) -> anyhow::Result<PathBuf> {
+        std::fs::create_dir_all(dest_dir)
+            .with_context(|| format!("creating directory {}", dest_dir.display()))?;
+
+        let path = dest_dir.join(self.package_file_name());
+
+        let mut fh = std::io::BufWriter::new(
+            std::fs::File::create(&path).with_context(|| format!("creating {}", path.display()))?,
+        );
+        self.write_rpm(&mut fh)?;
+        fh.flush()?;
+
+        Ok(path)
+    }
+
+    pub fn add_manifest(&mut self, manifest: FileManifestValue) -> ValueResult {
+        const LABEL: &str = "RpmPackageBuilder.add_manifest()";
+
+        let manifest = manifest.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            self.files
+                .add_manifest(&manifest)
+                .context("adding manifest")
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_requires(&mut self, name: String) -> ValueResult {
+        self.requires.push(name);
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_provides(&mut self, name: String) -> ValueResult {
+        self.provides.push(name);
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_conflicts(&mut self, name: String) -> ValueResult {
+        self.conflicts.push(name);
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_obsoletes(&mut self, name: String) -> ValueResult {
+        self.obsoletes.push(name);
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn to_file_content(&self) -> ValueResult {
+        const LABEL: &str = "RpmPackageBuilder.to_file_content()";
+
+        let data = error_context(LABEL, || {
+            let mut data = vec![];
+            self.write_rpm(&mut data)?;
+
+            Ok(data)
+        })?;
+
+        Ok(FileContentWrapper {
+            content: data.into(),
+            filename: self.package_file_name(),
+        }
+        .into())
+    }
+
+    pub fn write_to_directory(&self, type_values: &TypeValues, path: String) -> ValueResult {
+        const LABEL: &str = "RpmPackageBuilder.write_to_directory()";
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let dest_dir = context.resolve_path(path);
+
+        let rpm_path = error_context(LABEL, || self.write_rpm_to_directory(&dest_dir))?;
+
+        Ok(Value::from(format!("{}", rpm_path.display())))
+    }
+
+    fn build(&self, type_values: &TypeValues, target: String) -> ValueResult {
+        const LABEL: &str = "RpmPackageBuilder.build()";
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let output_directory = context.target_build_path(&target);
+
+        let rpm_path = error_context(LABEL, || {
+            warn!(
+                context.logger(),
+                "writing .rpm to {}",
+                output_directory.display()
+            );
+
+            self.write_rpm_to_directory(&output_directory)
+        })?;
+
+        warn!(context.logger(), "wrote {}", rpm_path.display());
+
+        Ok(Value::new(ResolvedTargetValue {
+            inner: ResolvedTarget {
+                run_mode: RunMode::None,
+                output_path: output_directory,
+            },
+        }))
+    }
+}
+
+starlark_module! { rpm_package_builder_module =>
+    #[allow(non_snake_case)]
+    RpmPackageBuilder(
+        name: String,
+        version: String,
+        license: String,
+        arch: String,
+        description: String
+    ) {
+        RpmPackageBuilderValue::new_from_args(name, version, license, arch, description)
+    }
+
+    RpmPackageBuilder.add_manifest(this, manifest: FileManifestValue) {
+        let mut this = this.downcast_mut::<RpmPackageBuilderValue>().unwrap().unwrap();
+        this.add_manifest(manifest)
+    }
+
+    RpmPackageBuilder.add_requires(this, name: String) {
+        let mut this = this.downcast_mut::<RpmPackageBuilderValue>().unwrap().unwrap();
+        this.add_requires(name)
+    }
+
+    RpmPackageBuilder.add_provides(this, name: String) {
+        let mut this = this.downcast_mut::<RpmPackageBuilderValue>().unwrap().unwrap();
+        this.add_provides(name)
+    }
+
+    RpmPackageBuilder.add_conflicts(this, name: String) {
+        let mut this = this.downcast_mut::<RpmPackageBuilderValue>().unwrap().unwrap();
+        this.add_conflicts(name)
+    }
+
+    RpmPackageBuilder.add_obsoletes(this, name: String) {
+        let mut this = this.downcast_mut::<RpmPackageBuilderValue>().unwrap().unwrap();
+        this.add_obsoletes(name)
+    }
+
+    RpmPackageBuilder.to_file_content(this) {
+        let this = this.downcast_ref::<RpmPackageBuilderValue>().unwrap();
+        this.to_file_content()
+    }
+
+    RpmPackageBuilder.write_to_directory(env env, this, path: String) {
+        let this = this.downcast_ref::<RpmPackageBuilderValue>().unwrap();
+        this.write_to_directory(env, path)
+    }
+
+    RpmPackageBuilder.build(env env, this, target: String) {
+        let this = this.downcast_ref::<RpmPackageBuilderValue>().unwrap();
+        this.build(env, target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::starlark::testutil::*, anyhow::Result};
+
+    #[test]
+    fn type_info() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        let raw = env.eval("RpmPackageBuilder('myapp', '0.1', 'MIT', 'x86_64', 'My app')")?;
+        assert_eq!(raw.get_type(), RpmPackageBuilderValue::TYPE);
+
+        Ok(())
+    }
+
+    #[test]
+    fn attributes() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("b = RpmPackageBuilder('myapp', '0.1', 'MIT', 'x86_64', 'My app')")?;
+
+        let v = env.eval("b.package_file_name")?;
+        assert_eq!(v.to_string(), "myapp-0.1-1.x86_64.rpm");
+
+        let v = env.eval("b.vendor")?;
+        assert_eq!(v.get_type(), "NoneType");
+
+        env.eval("b.release = 3")?;
+        env.eval("b.vendor = 'Example'")?;
+        assert_eq!(env.eval("b.vendor")?.to_string(), "Example");
+        assert_eq!(
+            env.eval("b.package_file_name")?.to_string(),
+            "myapp-0.1-3.x86_64.rpm"
+        );
+
+        assert!(env.eval("b.release = -1").is_err());
+
+        Ok(())
+    }
+}
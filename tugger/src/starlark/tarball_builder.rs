@@ -0,0 +1,377 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use {
+    crate::{
+        starlark::{file_content::FileContentWrapper, file_manifest::FileManifestValue},
+        tarball::write_tarball_from_manifest,
+    },
+    anyhow::{anyhow, Context},
+    slog::warn,
+    starlark::{
+        environment::TypeValues,
+        values::{
+            error::{RuntimeError, UnsupportedOperation, ValueError},
+            none::NoneType,
+            Mutable, TypedValue, Value, ValueResult,
+        },
+        {
+            starlark_fun, starlark_module, starlark_parse_param_type, starlark_signature,
+            starlark_signature_extraction, starlark_signatures,
+        },
+    },
+    starlark_dialect_build_targets::{
+        get_context_value, EnvironmentContext, ResolvedTarget, ResolvedTargetValue, RunMode,
+    },
+    std::{
+        io::Write,
+        path::{Path, PathBuf},
+    },
+    tugger_file_manifest::FileManifest,
+};
+
+fn error_context<F, T>(label: &str, f: F) -> Result<T, ValueError>
+where
+    F: FnOnce() -> anyhow::Result<T>,
+{
+    f().map_err(|e| {
+        ValueError::Runtime(RuntimeError {
+            code: "TUGGER_TARBALL_BUILDER",
+            message: format!("{:?}", e),
+            label: label.to_string(),
+        })
+    })
+}
+
+#[derive(Clone, Debug)]
+pub struct TarballBuilderValue {
+    filename: String,
+    /// Files to add to the archive.
+    files: FileManifest,
+    /// Directory all archive members are placed under.
+    archive_prefix: Option<String>,
+    compression: String,
+    mtime: Option<u64>,
+}
+
+impl TypedValue for TarballBuilderValue {
+    type Holder = Mutable<TarballBuilderValue>;
+    const TYPE: &'static str = "TarballBuilder";
+
+    fn values_for_descendant_check_and_freeze(&self) -> Box<dyn Iterator<Item = Value>> {
+        Box::new(std::iter::empty())
+    }
+
+    fn get_attr(&self, attribute: &str) -> ValueResult {
+        Ok(match attribute {
+            "archive_prefix" => {
+                if let Some(prefix) = &self.archive_prefix {
+                    Value::from(prefix.as_str())
+                } else {
+                    Value::from(NoneType::None)
+                }
+            }
+            "compression" => Value::from(self.compression.as_str()),
+            "filename" => Value::from(self.filename.as_str()),
+            "mtime" => {
+                if let Some(mtime) = self.mtime {
+                    Value::from(mtime as i64)
+                } else {
+                    Value::from(NoneType::None)
+                }
+            }
+            _ => {
+                return Err(ValueError::OperationNotSupported {
+                    op: UnsupportedOperation::GetAttr(attribute.to_string()),
+                    left: Self::TYPE.to_string(),
+                    right: None,
+                })
+            }
+        })
+    }
+
+    fn has_attr(&self, attribute: &str) -> Result<bool, ValueError> {
+        Ok(matches!(
+            attribute,
+            "archive_prefix" | "compression" | "filename" | "mtime"
+        ))
+    }
+
+    fn set_attr(&mut self, attribute: &str, value: Value) -> Result<(), ValueError> {
+        match attribute {
+            "archive_prefix" => {
+                self.archive_prefix = if value.get_type() == "NoneType" {
+                    None
+                } else {
+                    Some(value.to_string())
+                };
+            }
+            "compression" => {
+                let compression = value.to_string();
+                if !matches!(compression.as_str(), "none" | "gzip") {
+                    return Err(ValueError::Runtime(RuntimeError {
+                        code: "TUGGER_TARBALL_BUILDER",
+                        message: format!(
+                            "unknown compression format: {} (expected none or gzip)",
+                            compression
+                        ),
+                        label: format!("{}.compression", Self::TYPE),
+                    }));
+                }
+                self.compression = compression;
+            }
+            "filename" => {
+                self.filename = value.to_string();
+            }
+            "mtime" => {
+                self.mtime = if value.get_type() == "NoneType" {
+                    None
+                } else {
+                    let mtime = value.to_int()?;
+                    Some(error_context("TarballBuilder.mtime = ", || {
+                        u64::try_from(mtime).map_err(|_| anyhow!("mtime must not be negative"))
+                    })?)
+                };
+            }
+            attr => {
+                return Err(ValueError::OperationNotSupported {
+                    op: UnsupportedOperation::SetAttr(attr.to_string()),
+                    left: Self::TYPE.to_string(),
+                    right: None,
+                })
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl TarballBuilderValue {
+    pub fn new_from_args(filename: String) -> ValueResult {
+        let compression = if filename.ends_with(".gz") || filename.ends_with(".tgz") {
+            "gzip"
+        } else {
+            "none"
+        };
+
+        Ok(Value::new(Self {
+            filename,
+            files: FileManifest::default(),
+            archive_prefix: None,
+            compression: compression.to_string(),
+            mtime: None,
+        }))
+    }
+
+    fn write_tarball(&self, writer: &mut impl Write) -> anyhow::Result<()> {
+        let mtime = if let Some(mtime) = self.mtime {
+            mtime
+        } else {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)?
+                .as_secs()
+        };
+
+        let prefix = self.archive_prefix.as_ref().map(PathBuf::from);
+
+        match self.compression.as_str() {
+            "gzip" => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+                write_tarball_from_manifest(&mut encoder, &self.files, prefix.as_deref(), mtime)?;
+                encoder.finish()?;
+            }
+            _ => {
+                write_tarball_from_manifest(writer, &self.files, prefix.as_deref(), mtime)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_tarball_to_directory(&self, dest_dir: &Path) -> anyhow::Result<PathBuf> {
+        std::fs::create_dir_all(dest_dir)
+            .with_context(|| format!("creating directory {}", dest_dir.display()))?;
+
+        let path = dest_dir.join(&self.filename);
+
+        let mut fh = std::io::BufWriter::new(
+            std::fs::File::create(&path).with_context(|| format!("creating {}", path.display()))?,
+        );
+        self.write_tarball(&mut fh)?;
+        fh.flush()?;
+
+        Ok(path)
+    }
+
+    pub fn add_manifest(&mut self, manifest: FileManifestValue) -> ValueResult {
+        const LABEL: &str = "TarballBuilder.add_manifest()";
+
+        let manifest = manifest.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            self.files
+                .add_manifest(&manifest)
+                .context("adding manifest")
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn to_file_content(&self) -> ValueResult {
+        const LABEL: &str = "TarballBuilder.to_file_content()";
+
+        let data = error_context(LABEL, || {
+            let mut data = vec![];
+            self.write_tarball(&mut data)?;
+
+            Ok(data)
+        })?;
+
+        Ok(FileContentWrapper {
+            content: data.into(),
+            filename: self.filename.clone(),
+        }
+        .into())
+    }
+
+    pub fn write_to_directory(&self, type_values: &TypeValues, path: String) -> ValueResult {
+        const LABEL: &str = "TarballBuilder.write_to_directory()";
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let dest_dir = context.resolve_path(path);
+
+        let tarball_path = error_context(LABEL, || self.write_tarball_to_directory(&dest_dir))?;
+
+        Ok(Value::from(format!("{}", tarball_path.display())))
+    }
+
+    fn build(&self, type_values: &TypeValues, target: String) -> ValueResult {
+        const LABEL: &str = "TarballBuilder.build()";
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let output_directory = context.target_build_path(&target);
+
+        let tarball_path = error_context(LABEL, || {
+            warn!(
+                context.logger(),
+                "writing tarball to {}",
+                output_directory.display()
+            );
+
+            self.write_tarball_to_directory(&output_directory)
+        })?;
+
+        warn!(context.logger(), "wrote {}", tarball_path.display());
+
+        Ok(Value::new(ResolvedTargetValue {
+            inner: ResolvedTarget {
+                run_mode: RunMode::None,
+                output_path: output_directory,
+            },
+        }))
+    }
+}
+
+starlark_module! { tarball_builder_module =>
+    #[allow(non_snake_case)]
+    TarballBuilder(filename: String) {
+        TarballBuilderValue::new_from_args(filename)
+    }
+
+    TarballBuilder.add_manifest(this, manifest: FileManifestValue) {
+        let mut this = this.downcast_mut::<TarballBuilderValue>().unwrap().unwrap();
+        this.add_manifest(manifest)
+    }
+
+    TarballBuilder.to_file_content(this) {
+        let this = this.downcast_ref::<TarballBuilderValue>().unwrap();
+        this.to_file_content()
+    }
+
+    TarballBuilder.write_to_directory(env env, this, path: String) {
+        let this = this.downcast_ref::<TarballBuilderValue>().unwrap();
+        this.write_to_directory(env, path)
+    }
+
+    TarballBuilder.build(env env, this, target: String) {
+        let this = this.downcast_ref::<TarballBuilderValue>().unwrap();
+        this.build(env, target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::starlark::file_content::FileContentValue, crate::starlark::testutil::*,
+        anyhow::Result, tugger_common::testutil::*,
+    };
+
+    #[test]
+    fn attributes() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("t = TarballBuilder('myapp.tar.gz')")?;
+        assert_eq!(env.eval("t")?.get_type(), TarballBuilderValue::TYPE);
+        assert_eq!(env.eval("t.compression")?.to_string(), "gzip");
+        assert_eq!(env.eval("t.archive_prefix")?.get_type(), "NoneType");
+
+        env.eval("t.archive_prefix = 'myapp'")?;
+        assert_eq!(env.eval("t.archive_prefix")?.to_string(), "myapp");
+
+        assert!(env.eval("t.compression = 'bogus'").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn to_file_content() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("m = FileManifest()")?;
+        env.eval("m.add_file(FileContent(filename = 'file', content = 'foo'))")?;
+        env.eval("t = TarballBuilder('myapp.tar')")?;
+        env.eval("t.add_manifest(m)")?;
+        env.eval("t.mtime = 0")?;
+        let f = env.eval("t.to_file_content()")?;
+
+        let value = f.downcast_ref::<FileContentValue>().unwrap();
+        let inner = value.inner("ignored").unwrap();
+        assert_eq!(inner.filename, "myapp.tar");
+
+        let data = inner.content.resolve_content()?;
+        let mut archive = tar::Archive::new(std::io::Cursor::new(data));
+        let paths = archive
+            .entries()?
+            .map(|entry| Ok(entry?.path()?.display().to_string()))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(paths, vec!["file".to_string()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_to_directory() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        let temp_dir_s = format!("{}", DEFAULT_TEMP_DIR.path().display()).replace('\\', "/");
+
+        env.eval("t = TarballBuilder('myapp.tar.gz')")?;
+        let path = env.eval(&format!("t.write_to_directory('{}')", temp_dir_s))?;
+
+        assert_eq!(path.get_type(), "string");
+        let path = PathBuf::from(path.to_string());
+        assert!(path.exists());
+
+        Ok(())
+    }
+}
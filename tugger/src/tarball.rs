@@ -3,10 +3,11 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use {
-    anyhow::Result,
+    anyhow::{Context, Result},
     slog::warn,
     std::{io::Write, path::Path},
     tar,
+    tugger_file_manifest::FileManifest,
 };
 
 /// Create a tarball from a filesystem path.
@@ -57,3 +58,131 @@ pub fn write_tarball_from_directory<W: Write, P: AsRef<Path>>(
 
     Ok(())
 }
+
+/// Create a tarball from a [FileManifest].
+///
+/// Archive members are emitted in sorted order with normalized ownership,
+/// permissions, and the specified modified time so the output is deterministic.
+/// Parent directories of all files are added as explicit entries.
+///
+/// The uncompressed tar contents will be emitted to the passed writer.
+pub fn write_tarball_from_manifest<W: Write>(
+    fh: &mut W,
+    manifest: &FileManifest,
+    archive_prefix: Option<&Path>,
+    mtime: u64,
+) -> Result<()> {
+    let mut builder = tar::Builder::new(fh);
+
+    let archive_path = |path: &Path| {
+        if let Some(prefix) = archive_prefix {
+            prefix.join(path)
+        } else {
+            path.to_path_buf()
+        }
+    };
+
+    let new_header = |entry_type: tar::EntryType, mode: u32, size: u64| {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(entry_type);
+        header.set_mode(mode);
+        header.set_size(size);
+        header.set_uid(0);
+        header.set_gid(0);
+        header.set_mtime(mtime);
+        header
+    };
+
+    let mut directories = vec![];
+    if let Some(prefix) = archive_prefix {
+        for ancestor in prefix.ancestors() {
+            if !ancestor.as_os_str().is_empty() {
+                directories.push(ancestor.to_path_buf());
+            }
+        }
+    }
+    directories.extend(
+        manifest
+            .relative_directories()
+            .into_iter()
+            .map(|p| archive_path(&p)),
+    );
+    directories.sort();
+    directories.dedup();
+
+    for path in directories {
+        let mut header = new_header(tar::EntryType::Directory, 0o755, 0);
+        builder
+            .append_data(&mut header, &path, std::io::empty())
+            .with_context(|| format!("adding directory {}", path.display()))?;
+    }
+
+    for (path, entry) in manifest.iter_entries() {
+        let path = archive_path(path);
+
+        if let Some(target) = entry.link_target() {
+            let mut header = new_header(tar::EntryType::Symlink, 0o777, 0);
+            builder
+                .append_link(&mut header, &path, target)
+                .with_context(|| format!("adding symlink {}", path.display()))?;
+        } else {
+            let data = entry
+                .resolve_content()
+                .with_context(|| format!("resolving content of {}", path.display()))?;
+            let mode = if entry.is_executable() { 0o755 } else { 0o644 };
+
+            let mut header = new_header(tar::EntryType::Regular, mode, data.len() as u64);
+            builder
+                .append_data(&mut header, &path, std::io::Cursor::new(data))
+                .with_context(|| format!("adding file {}", path.display()))?;
+        }
+    }
+
+    builder.finish()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, tugger_file_manifest::FileEntry};
+
+    #[test]
+    fn manifest_tarball() -> Result<()> {
+        let mut manifest = FileManifest::default();
+        manifest.add_file_entry("bin/app", FileEntry::new_from_data(b"app".to_vec(), true))?;
+        manifest.add_file_entry(
+            "README",
+            FileEntry::new_from_data(b"readme".to_vec(), false),
+        )?;
+
+        let mut data = vec![];
+        write_tarball_from_manifest(&mut data, &manifest, Some(Path::new("app-1.0")), 42)?;
+
+        let mut archive = tar::Archive::new(std::io::Cursor::new(data));
+        let entries = archive
+            .entries()?
+            .map(|entry| {
+                let entry = entry?;
+                let header = entry.header();
+                Ok((
+                    entry.path()?.display().to_string(),
+                    header.mode()?,
+                    header.mtime()?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        assert_eq!(
+            entries,
+            vec![
+                ("app-1.0".to_string(), 0o755, 42),
+                ("app-1.0/bin".to_string(), 0o755, 42),
+                ("app-1.0/README".to_string(), 0o644, 42),
+                ("app-1.0/bin/app".to_string(), 0o755, 42),
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -50,6 +50,20 @@ impl Time {
         })
     }
 
+    pub fn take_opt_from<S: Source>(cons: &mut Constructed<S>) -> Result<Option<Self>, S::Err> {
+        if let Some(utc) =
+            cons.take_opt_primitive_if(Tag::UTC_TIME, |prim| UtcTime::from_primitive(prim))?
+        {
+            Ok(Some(Self::UtcTime(utc)))
+        } else if let Some(gt) = cons.take_opt_primitive_if(Tag::GENERALIZED_TIME, |prim| {
+            GeneralizedTime::from_primitive_no_fractional_or_timezone_offsets(prim)
+        })? {
+            Ok(Some(Self::GeneralTime(gt)))
+        } else {
+            Ok(None)
+        }
+    }
+
     pub fn encode_ref(&self) -> impl Values + '_ {
         match self {
             Self::UtcTime(utc) => (Some(utc.encode()), None),
@@ -64,6 +78,15 @@ impl From<chrono::DateTime<chrono::Utc>> for Time {
     }
 }
 
+impl From<Time> for chrono::DateTime<chrono::Utc> {
+    fn from(t: Time) -> Self {
+        match t {
+            Time::UtcTime(utc) => *utc,
+            Time::GeneralTime(gt) => gt.into(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 enum Zone {
     Utc,
@@ -234,6 +257,19 @@ impl GeneralizedTime {
     }
 }
 
+impl From<chrono::DateTime<chrono::Utc>> for GeneralizedTime {
+    fn from(t: chrono::DateTime<chrono::Utc>) -> Self {
+        Self {
+            time: t
+                .naive_utc()
+                .with_nanosecond(0)
+                .expect("0 nanoseconds is always valid"),
+            fractional_seconds: false,
+            timezone: Zone::Utc,
+        }
+    }
+}
+
 impl ToString for GeneralizedTime {
     fn to_string(&self) -> String {
         format!(
@@ -550,4 +586,19 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn chrono_round_trip() {
+        let now = chrono::Utc.timestamp(1643510772, 123);
+
+        let gt = GeneralizedTime::from(now);
+        assert_eq!(gt.to_string(), "20220130024612Z");
+        assert_eq!(
+            chrono::DateTime::<chrono::Utc>::from(gt),
+            chrono::Utc.timestamp(1643510772, 0)
+        );
+
+        let t = Time::from(now);
+        assert_eq!(chrono::DateTime::<chrono::Utc>::from(t), now);
+    }
 }
@@ -18,7 +18,7 @@ use {
         ConstOid, Mode, Oid,
     },
     bytes::Bytes,
-    chrono::{Duration, Utc},
+    chrono::{DateTime, Duration, Utc},
     der::Document,
     ring::signature as ringsig,
     signature::Signer,
@@ -43,6 +43,11 @@ const OID_EXTENSION_KEY_USAGE: ConstOid = Oid(&[85, 29, 15]);
 /// 2.5.29.19
 const OID_EXTENSION_BASIC_CONSTRAINTS: ConstOid = Oid(&[85, 29, 19]);
 
+/// Extended Key Usage X.509 extension.
+///
+/// 2.5.29.37
+const OID_EXTENSION_EXTENDED_KEY_USAGE: ConstOid = Oid(&[85, 29, 37]);
+
 /// Provides an interface to the RFC 5280 [rfc5280::Certificate] ASN.1 type.
 ///
 /// This type provides the main high-level API that this crate exposes
@@ -164,6 +169,97 @@ impl X509Certificate {
         self.0.iter_extensions()
     }
 
+    /// Obtain the start of the certificate's validity period.
+    pub fn validity_not_before(&self) -> DateTime<Utc> {
+        self.0.tbs_certificate.validity.not_before.clone().into()
+    }
+
+    /// Obtain the end of the certificate's validity period.
+    pub fn validity_not_after(&self) -> DateTime<Utc> {
+        self.0.tbs_certificate.validity.not_after.clone().into()
+    }
+
+    /// Whether a time falls within the certificate's validity period.
+    ///
+    /// If `compare_time` is `None`, the current time is used.
+    pub fn time_constraints_valid(&self, compare_time: Option<DateTime<Utc>>) -> bool {
+        let compare_time = compare_time.unwrap_or_else(Utc::now);
+
+        compare_time >= self.validity_not_before() && compare_time <= self.validity_not_after()
+    }
+
+    /// Obtain the decoded basic constraints extension, if present.
+    pub fn basic_constraints(&self) -> Result<Option<rfc5280::BasicConstraints>, Error> {
+        if let Some(ext) = self
+            .iter_extensions()
+            .find(|ext| ext.id == OID_EXTENSION_BASIC_CONSTRAINTS)
+        {
+            Ok(Some(Constructed::decode(
+                ext.value.to_source(),
+                Mode::Der,
+                |cons| rfc5280::BasicConstraints::take_from(cons),
+            )?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Obtain the key usages declared by the key usage extension, if present.
+    pub fn key_usages(&self) -> Result<Option<Vec<KeyUsage>>, Error> {
+        if let Some(ext) = self
+            .iter_extensions()
+            .find(|ext| ext.id == OID_EXTENSION_KEY_USAGE)
+        {
+            let bits = Constructed::decode(ext.value.to_source(), Mode::Der, |cons| {
+                BitString::take_from(cons)
+            })?;
+            let octets = bits.octet_bytes();
+
+            Ok(Some(
+                KeyUsage::all()
+                    .iter()
+                    .filter(|ku| {
+                        let bit = u8::from(**ku) as usize;
+
+                        octets
+                            .get(bit / 8)
+                            .map(|b| b & (0x80 >> (bit % 8)) != 0)
+                            .unwrap_or(false)
+                    })
+                    .copied()
+                    .collect::<Vec<_>>(),
+            ))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Obtain the OIDs in the extended key usage extension, if present.
+    pub fn extended_key_usage_oids(&self) -> Result<Option<Vec<Oid>>, Error> {
+        if let Some(ext) = self
+            .iter_extensions()
+            .find(|ext| ext.id == OID_EXTENSION_EXTENDED_KEY_USAGE)
+        {
+            Ok(Some(Constructed::decode(
+                ext.value.to_source(),
+                Mode::Der,
+                |cons| {
+                    cons.take_sequence(|cons| {
+                        let mut oids = vec![];
+
+                        while let Some(oid) = Oid::take_opt_from(cons)? {
+                            oids.push(oid);
+                        }
+
+                        Ok(oids)
+                    })
+                },
+            )?))
+        } else {
+            Ok(None)
+        }
+    }
+
     /// Encode the certificate data structure using DER encoding.
     ///
     /// (This is the common ASN.1 encoding format for X.509 certificates.)
@@ -819,6 +915,7 @@ pub fn certificate_is_subset_of(
 ///   cRLSign(6)
 /// }
 /// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum KeyUsage {
     DigitalSignature,
     NonRepudiation,
@@ -829,6 +926,21 @@ pub enum KeyUsage {
     CrlSign,
 }
 
+impl KeyUsage {
+    /// All key usages.
+    pub fn all() -> &'static [Self] {
+        &[
+            Self::DigitalSignature,
+            Self::NonRepudiation,
+            Self::KeyEncipherment,
+            Self::DataEncipherment,
+            Self::KeyAgreement,
+            Self::KeyCertSign,
+            Self::CrlSign,
+        ]
+    }
+}
+
 impl From<KeyUsage> for u8 {
     fn from(ku: KeyUsage) -> Self {
         match ku {
@@ -938,6 +1050,30 @@ impl X509CertificateBuilder {
         });
     }
 
+    /// Add a basic constraint extension that this is a CA certificate.
+    ///
+    /// `path_len` constrains the number of intermediate CA certificates that
+    /// may follow this one in a certificate chain.
+    pub fn constraint_ca(&mut self, path_len: Option<u32>) -> Result<(), Error> {
+        let constraints = rfc5280::BasicConstraints {
+            ca: true,
+            path_len_constraint: path_len,
+        };
+
+        let mut value = vec![];
+        constraints
+            .encode_ref()
+            .write_encoded(Mode::Der, &mut value)?;
+
+        self.extensions.push(rfc5280::Extension {
+            id: Oid(OID_EXTENSION_BASIC_CONSTRAINTS.as_ref().into()),
+            critical: Some(true),
+            value: OctetString::new(Bytes::from(value)),
+        });
+
+        Ok(())
+    }
+
     /// Add a key usage extension.
     pub fn key_usage(&mut self, key_usage: KeyUsage) {
         let value: u8 = key_usage.into();
@@ -946,7 +1082,9 @@ impl X509CertificateBuilder {
             id: Oid(OID_EXTENSION_KEY_USAGE.as_ref().into()),
             critical: Some(true),
             // Value is a bit string. We just encode it manually since it is easy.
-            value: OctetString::new(Bytes::copy_from_slice(&[3, 2, 7, 128 | value])),
+            // Bit 0 is the most significant bit of the first octet and DER requires
+            // the trailing unused bits to be declared.
+            value: OctetString::new(Bytes::copy_from_slice(&[3, 2, 7 - value, 0x80 >> value])),
         });
     }
 
@@ -1113,6 +1251,41 @@ mod test {
         builder.create_with_random_keypair().unwrap();
     }
 
+    #[test]
+    fn build_key_usage() {
+        for (key_usage, bit, expected) in [
+            (KeyUsage::DigitalSignature, 0, [3, 2, 7, 0x80]),
+            (KeyUsage::NonRepudiation, 1, [3, 2, 6, 0x40]),
+            (KeyUsage::KeyEncipherment, 2, [3, 2, 5, 0x20]),
+            (KeyUsage::DataEncipherment, 3, [3, 2, 4, 0x10]),
+            (KeyUsage::KeyAgreement, 4, [3, 2, 3, 0x08]),
+            (KeyUsage::KeyCertSign, 5, [3, 2, 2, 0x04]),
+            (KeyUsage::CrlSign, 6, [3, 2, 1, 0x02]),
+        ] {
+            let mut builder = X509CertificateBuilder::new(KeyAlgorithm::Ed25519);
+            builder.key_usage(key_usage);
+            let cert = builder.create_with_random_keypair().unwrap().0;
+
+            let ext = cert
+                .iter_extensions()
+                .find(|ext| ext.id == OID_EXTENSION_KEY_USAGE)
+                .unwrap();
+            assert_eq!(ext.value.to_bytes().as_ref(), &expected);
+
+            let bits = Constructed::decode(ext.value.to_source(), Mode::Der, |cons| {
+                BitString::take_from(cons)
+            })
+            .unwrap();
+            assert_eq!(bits.unused(), 7 - bit);
+            assert!(bits.bit(bit as usize));
+            assert_eq!(
+                (0..8).filter(|i| bits.bit(*i)).count(),
+                1,
+                "only the requested bit is set"
+            );
+        }
+    }
+
     #[test]
     fn builder_csr_ecdsa() -> Result<(), Error> {
         for curve in EcdsaCurve::all() {
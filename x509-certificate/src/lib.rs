@@ -72,6 +72,8 @@ pub mod certificate;
 pub use certificate::{
     CapturedX509Certificate, MutableX509Certificate, X509Certificate, X509CertificateBuilder,
 };
+pub mod revocation;
+pub use revocation::{CertificateRevocationList, OcspResponse, RevocationStatus};
 pub mod rfc2986;
 pub mod rfc3280;
 pub mod rfc3447;
@@ -81,11 +83,14 @@ pub mod rfc5480;
 pub mod rfc5652;
 pub mod rfc5915;
 pub mod rfc5958;
+pub mod rfc6960;
 pub mod rfc8017;
 pub mod signing;
 pub use signing::{InMemorySigningKeyPair, KeyInfoSigner, Sign, Signature};
 #[cfg(any(feature = "test", test))]
 pub mod testutil;
+pub mod validation;
+pub use validation::{CertificateChainValidator, ValidationProblem};
 
 use thiserror::Error;
 
@@ -142,6 +147,12 @@ pub enum X509CertificateError {
     #[error("target length for PKCS#1 padding to too short")]
     PkcsEncodeTooShort,
 
+    #[error("OCSP response does not contain a basic response (status: {0})")]
+    OcspResponseUnsupported(String),
+
+    #[error("OCSP response not signed by an authorized responder")]
+    OcspResponderNotAuthorized,
+
     #[error("unhandled error: {0}")]
     Other(String),
 }
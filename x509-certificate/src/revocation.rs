@@ -0,0 +1,757 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Certificate revocation lists (CRLs) and Online Certificate Status Protocol (OCSP).
+//!
+//! This module provides high-level interfaces to the RFC 5280
+//! [rfc5280::CertificateList] and RFC 6960 OCSP ASN.1 types. It doesn't
+//! perform any network I/O: callers are responsible for obtaining CRLs and
+//! OCSP responses, e.g. by fetching the URLs advertised by a certificate.
+
+use {
+    crate::{
+        algorithm::DigestAlgorithm, rfc3280::Name, rfc5280, rfc6960, CapturedX509Certificate,
+        KeyAlgorithm, SignatureAlgorithm, X509Certificate, X509CertificateError as Error,
+    },
+    bcder::{decode::Constructed, encode::Values, Integer, Mode, OctetString, Oid},
+    bytes::Bytes,
+    chrono::{DateTime, Utc},
+};
+
+/// Verify a signature made by the key of a certificate.
+///
+/// The verification algorithm is derived from the signature algorithm of the
+/// signed structure and the key algorithm of the signing certificate.
+fn verify_signed_by_certificate(
+    signer: &CapturedX509Certificate,
+    signature_algorithm: &rfc5280::AlgorithmIdentifier,
+    signed_data: &[u8],
+    signature: &[u8],
+) -> Result<(), Error> {
+    let key_algorithm = KeyAlgorithm::try_from(signer.key_algorithm_oid())?;
+    let signature_algorithm = SignatureAlgorithm::try_from(signature_algorithm)?;
+    let verify_algorithm = signature_algorithm.resolve_verification_algorithm(key_algorithm)?;
+
+    signer.verify_signed_data_with_algorithm(signed_data, signature, verify_algorithm)
+}
+
+/// Obtain the bytes that were signed for a structure that may have been parsed.
+///
+/// Parsed structures retain the original bytes, which is what signature verification
+/// must be performed against. Structures constructed in memory are DER encoded.
+fn signed_data(raw_data: &Option<Vec<u8>>, values: impl Values) -> Result<Vec<u8>, Error> {
+    if let Some(data) = raw_data {
+        Ok(data.clone())
+    } else {
+        let mut data = vec![];
+        values.write_encoded(Mode::Der, &mut data)?;
+
+        Ok(data)
+    }
+}
+
+/// A certificate revocation list (CRL).
+///
+/// Instances are backed by an RFC 5280 [rfc5280::CertificateList].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CertificateRevocationList(rfc5280::CertificateList);
+
+impl CertificateRevocationList {
+    /// Construct an instance by parsing DER encoded ASN.1 data.
+    pub fn from_der(data: impl AsRef<[u8]>) -> Result<Self, Error> {
+        let crl = Constructed::decode(data.as_ref(), Mode::Der, |cons| {
+            rfc5280::CertificateList::take_from(cons)
+        })?;
+
+        Ok(Self(crl))
+    }
+
+    /// Construct an instance by parsing BER encoded ASN.1 data.
+    pub fn from_ber(data: impl AsRef<[u8]>) -> Result<Self, Error> {
+        let crl = Constructed::decode(data.as_ref(), Mode::Ber, |cons| {
+            rfc5280::CertificateList::take_from(cons)
+        })?;
+
+        Ok(Self(crl))
+    }
+
+    /// Construct an instance by parsing PEM encoded ASN.1 data.
+    ///
+    /// The data is a human readable string likely containing
+    /// `--------- BEGIN X509 CRL ----------`.
+    pub fn from_pem(data: impl AsRef<[u8]>) -> Result<Self, Error> {
+        let data = pem::parse(data.as_ref()).map_err(Error::PemDecode)?;
+
+        Self::from_der(&data.contents)
+    }
+
+    /// Obtain the issuer of this CRL, as its ASN.1 [Name] type.
+    pub fn issuer_name(&self) -> &Name {
+        &self.0.tbs_cert_list.issuer
+    }
+
+    /// The time this CRL was issued.
+    pub fn this_update(&self) -> DateTime<Utc> {
+        self.0.tbs_cert_list.this_update.clone().into()
+    }
+
+    /// The time by which the next CRL will be issued, if defined.
+    pub fn next_update(&self) -> Option<DateTime<Utc>> {
+        self.0
+            .tbs_cert_list
+            .next_update
+            .as_ref()
+            .map(|t| t.clone().into())
+    }
+
+    /// Whether this CRL is current at a given time.
+    ///
+    /// A CRL is current from its `thisUpdate` time until its `nextUpdate` time.
+    /// A CRL without a `nextUpdate` time never goes stale.
+    pub fn is_current(&self, time: DateTime<Utc>) -> bool {
+        revocation_data_is_current(self.this_update(), self.next_update(), time)
+    }
+
+    /// Iterate over entries for revoked certificates.
+    pub fn iter_revoked_certificates(&self) -> impl Iterator<Item = &rfc5280::RevokedCertificate> {
+        self.0.tbs_cert_list.revoked_certificates.iter()
+    }
+
+    /// Find the revocation entry for a certificate serial number.
+    pub fn find_revoked_serial(&self, serial: &Integer) -> Option<&rfc5280::RevokedCertificate> {
+        self.iter_revoked_certificates()
+            .find(|entry| &entry.user_certificate == serial)
+    }
+
+    /// Whether this CRL is issued by the issuer of the given certificate.
+    ///
+    /// This is a strict compare of the ASN.1 [Name] data. It doesn't verify signatures.
+    pub fn covers_certificate(&self, cert: &X509Certificate) -> bool {
+        self.issuer_name() == cert.issuer_name()
+    }
+
+    /// Verify that this CRL was signed by the given certificate.
+    pub fn verify_signed_by_certificate(
+        &self,
+        issuer: &CapturedX509Certificate,
+    ) -> Result<(), Error> {
+        let signed_data = signed_data(
+            &self.0.tbs_cert_list.raw_data,
+            self.0.tbs_cert_list.encode_ref(),
+        )?;
+
+        verify_signed_by_certificate(
+            issuer,
+            &self.0.signature_algorithm,
+            &signed_data,
+            &self.0.signature.octet_bytes(),
+        )
+    }
+
+    /// Encode the CRL data structure using DER encoding.
+    pub fn encode_der(&self) -> Result<Vec<u8>, std::io::Error> {
+        let mut buffer = Vec::<u8>::new();
+        self.0.encode_ref().write_encoded(Mode::Der, &mut buffer)?;
+
+        Ok(buffer)
+    }
+
+    /// Encode the CRL data structure to PEM.
+    pub fn encode_pem(&self) -> Result<String, std::io::Error> {
+        Ok(pem::encode(&pem::Pem {
+            tag: "X509 CRL".to_string(),
+            contents: self.encode_der()?,
+        }))
+    }
+}
+
+impl From<rfc5280::CertificateList> for CertificateRevocationList {
+    fn from(v: rfc5280::CertificateList) -> Self {
+        Self(v)
+    }
+}
+
+impl From<CertificateRevocationList> for rfc5280::CertificateList {
+    fn from(v: CertificateRevocationList) -> Self {
+        v.0
+    }
+}
+
+impl AsRef<rfc5280::CertificateList> for CertificateRevocationList {
+    fn as_ref(&self) -> &rfc5280::CertificateList {
+        &self.0
+    }
+}
+
+/// Compute the OCSP [rfc6960::CertId] identifying a certificate.
+///
+/// The identifier consists of digests of the issuer's name and public key
+/// and the serial number of the certificate.
+pub fn ocsp_cert_id(
+    cert: &X509Certificate,
+    issuer: &X509Certificate,
+    digest_algorithm: DigestAlgorithm,
+) -> Result<rfc6960::CertId, Error> {
+    let mut name_der = vec![];
+    issuer
+        .subject_name()
+        .encode_ref()
+        .write_encoded(Mode::Der, &mut name_der)?;
+
+    Ok(rfc6960::CertId {
+        hash_algorithm: digest_algorithm.into(),
+        issuer_name_hash: OctetString::new(Bytes::from(digest_algorithm.digest_data(&name_der))),
+        issuer_key_hash: OctetString::new(Bytes::from(
+            digest_algorithm.digest_data(&issuer.public_key_data()),
+        )),
+        serial_number: cert.serial_number_asn1().clone(),
+    })
+}
+
+/// Construct an unsigned OCSP request for the status of certificates.
+///
+/// The request can be serialized with [rfc6960::OcspRequest::encode_ref()].
+///
+/// If `nonce` is defined, it is added as a request extension. Responders echo it
+/// back to prevent replay attacks.
+pub fn create_ocsp_request(
+    cert_ids: impl IntoIterator<Item = rfc6960::CertId>,
+    nonce: Option<&[u8]>,
+) -> Result<rfc6960::OcspRequest, Error> {
+    let request_extensions = if let Some(nonce) = nonce {
+        let mut value = vec![];
+        OctetString::new(Bytes::copy_from_slice(nonce))
+            .encode_ref()
+            .write_encoded(Mode::Der, &mut value)?;
+
+        let mut extensions = rfc5280::Extensions::default();
+        extensions.push(rfc5280::Extension {
+            id: Oid(rfc6960::OID_PKIX_OCSP_NONCE.as_ref().into()),
+            critical: None,
+            value: OctetString::new(Bytes::from(value)),
+        });
+
+        Some(extensions)
+    } else {
+        None
+    };
+
+    Ok(rfc6960::OcspRequest {
+        tbs_request: rfc6960::TbsRequest {
+            version: None,
+            requestor_name: None,
+            request_list: cert_ids
+                .into_iter()
+                .map(|req_cert| rfc6960::Request {
+                    req_cert,
+                    single_request_extensions: None,
+                })
+                .collect::<Vec<_>>(),
+            request_extensions,
+        },
+        optional_signature: None,
+    })
+}
+
+/// A parsed OCSP response.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OcspResponse {
+    response: rfc6960::OcspResponse,
+    basic: Option<rfc6960::BasicOcspResponse>,
+}
+
+impl OcspResponse {
+    /// Construct an instance by parsing DER encoded ASN.1 data.
+    ///
+    /// This is the format used by the `application/ocsp-response` media type.
+    pub fn from_der(data: impl AsRef<[u8]>) -> Result<Self, Error> {
+        let response = Constructed::decode(data.as_ref(), Mode::Der, |cons| {
+            rfc6960::OcspResponse::take_from(cons)
+        })?;
+
+        Self::try_from(response)
+    }
+
+    /// The status of the response.
+    ///
+    /// Only successful responses carry certificate status information.
+    pub fn status(&self) -> rfc6960::OcspResponseStatus {
+        self.response.response_status
+    }
+
+    /// Obtain the basic OCSP response, if present.
+    pub fn basic_response(&self) -> Option<&rfc6960::BasicOcspResponse> {
+        self.basic.as_ref()
+    }
+
+    /// The time the response was signed by the responder.
+    pub fn produced_at(&self) -> Option<DateTime<Utc>> {
+        self.basic
+            .as_ref()
+            .map(|basic| basic.tbs_response_data.produced_at.clone().into())
+    }
+
+    /// Iterate over certificate status entries in this response.
+    pub fn iter_single_responses(&self) -> impl Iterator<Item = &rfc6960::SingleResponse> {
+        self.basic
+            .iter()
+            .flat_map(|basic| basic.tbs_response_data.responses.iter())
+    }
+
+    /// Find the status entry for a certificate issued by `issuer`.
+    ///
+    /// The [rfc6960::CertId] of each entry is recomputed using the entry's digest
+    /// algorithm and compared against the given certificates.
+    pub fn find_single_response(
+        &self,
+        cert: &X509Certificate,
+        issuer: &X509Certificate,
+    ) -> Option<&rfc6960::SingleResponse> {
+        self.iter_single_responses().find(|response| {
+            if let Ok(digest_algorithm) =
+                DigestAlgorithm::try_from(&response.cert_id.hash_algorithm)
+            {
+                if let Ok(cert_id) = ocsp_cert_id(cert, issuer, digest_algorithm) {
+                    cert_id.issuer_name_hash == response.cert_id.issuer_name_hash
+                        && cert_id.issuer_key_hash == response.cert_id.issuer_key_hash
+                        && cert_id.serial_number == response.cert_id.serial_number
+                } else {
+                    false
+                }
+            } else {
+                false
+            }
+        })
+    }
+
+    /// Verify that this response was signed by an authorized responder for `issuer`.
+    ///
+    /// RFC 6960 allows the issuing certificate authority to sign responses
+    /// itself or to delegate signing to a certificate it issued bearing the
+    /// `id-kp-OCSPSigning` extended key usage. The delegated certificate must be
+    /// included in the response.
+    pub fn verify_signed_by_issuer(&self, issuer: &CapturedX509Certificate) -> Result<(), Error> {
+        let basic = self.basic.as_ref().ok_or_else(|| {
+            Error::OcspResponseUnsupported(format!("{:?}", self.response.response_status))
+        })?;
+
+        let signed_data = signed_data(
+            &basic.tbs_response_data.raw_data,
+            basic.tbs_response_data.encode_ref(),
+        )?;
+        let signature = basic.signature.octet_bytes();
+
+        if verify_signed_by_certificate(
+            issuer,
+            &basic.signature_algorithm,
+            &signed_data,
+            &signature,
+        )
+        .is_ok()
+        {
+            return Ok(());
+        }
+
+        for cert in &basic.certs {
+            let cert = CapturedX509Certificate::try_from(X509Certificate::from(cert.clone()))?;
+
+            if cert.verify_signed_by_certificate(issuer).is_err() {
+                continue;
+            }
+
+            let ocsp_signing = cert
+                .extended_key_usage_oids()?
+                .map(|oids| oids.iter().any(|oid| oid == &rfc6960::OID_KP_OCSP_SIGNING))
+                .unwrap_or(false);
+
+            if !ocsp_signing {
+                continue;
+            }
+
+            if verify_signed_by_certificate(
+                &cert,
+                &basic.signature_algorithm,
+                &signed_data,
+                &signature,
+            )
+            .is_ok()
+            {
+                return Ok(());
+            }
+        }
+
+        Err(Error::OcspResponderNotAuthorized)
+    }
+}
+
+impl TryFrom<rfc6960::OcspResponse> for OcspResponse {
+    type Error = Error;
+
+    fn try_from(response: rfc6960::OcspResponse) -> Result<Self, Self::Error> {
+        let basic = if let Some(bytes) = &response.response_bytes {
+            bytes.basic_response()?
+        } else {
+            None
+        };
+
+        Ok(Self { response, basic })
+    }
+}
+
+impl AsRef<rfc6960::OcspResponse> for OcspResponse {
+    fn as_ref(&self) -> &rfc6960::OcspResponse {
+        &self.response
+    }
+}
+
+/// Whether revocation data produced at `this_update` is current at `time`.
+fn revocation_data_is_current(
+    this_update: DateTime<Utc>,
+    next_update: Option<DateTime<Utc>>,
+    time: DateTime<Utc>,
+) -> bool {
+    this_update <= time && next_update.map_or(true, |next_update| time <= next_update)
+}
+
+/// Whether an OCSP status entry is current at a given time.
+///
+/// An entry is current from its `thisUpdate` time until its `nextUpdate` time.
+/// An entry without a `nextUpdate` time never goes stale.
+pub fn single_response_is_current(response: &rfc6960::SingleResponse, time: DateTime<Utc>) -> bool {
+    revocation_data_is_current(
+        response.this_update.clone().into(),
+        response.next_update.clone().map(|x| x.into()),
+        time,
+    )
+}
+
+/// The revocation status of a certificate.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RevocationStatus {
+    /// Revocation data was consulted and the certificate isn't revoked.
+    Good,
+
+    /// The certificate is revoked.
+    Revoked {
+        revocation_time: DateTime<Utc>,
+        reason: Option<rfc5280::CrlReason>,
+    },
+
+    /// No revocation data for the certificate was available.
+    Unknown,
+}
+
+impl RevocationStatus {
+    /// Resolve the status of a certificate at a given time from a CRL.
+    ///
+    /// A CRL that isn't current at `time` can't establish that a certificate
+    /// is good and yields [Self::Unknown]. Revocations it lists are still
+    /// reported, as revocation is permanent.
+    ///
+    /// The CRL isn't verified: callers should verify its signature first.
+    pub fn from_crl(
+        crl: &CertificateRevocationList,
+        cert: &X509Certificate,
+        time: DateTime<Utc>,
+    ) -> Self {
+        if !crl.covers_certificate(cert) {
+            Self::Unknown
+        } else if let Some(entry) = crl.find_revoked_serial(cert.serial_number_asn1()) {
+            Self::Revoked {
+                revocation_time: entry.revocation_date.clone().into(),
+                reason: entry.reason_code(),
+            }
+        } else if crl.is_current(time) {
+            Self::Good
+        } else {
+            Self::Unknown
+        }
+    }
+
+    /// Resolve the status of a certificate at a given time from an OCSP status entry.
+    ///
+    /// Like [Self::from_crl], an entry that isn't current at `time` can only
+    /// report revocations.
+    pub fn from_single_response(response: &rfc6960::SingleResponse, time: DateTime<Utc>) -> Self {
+        match &response.cert_status {
+            rfc6960::CertStatus::Good if single_response_is_current(response, time) => Self::Good,
+            rfc6960::CertStatus::Good => Self::Unknown,
+            rfc6960::CertStatus::Revoked(info) => Self::Revoked {
+                revocation_time: info.revocation_time.clone().into(),
+                reason: info.revocation_reason,
+            },
+            rfc6960::CertStatus::Unknown => Self::Unknown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::{asn1time::Time, certificate::KeyUsage, testutil::*, Sign, X509CertificateBuilder},
+        bcder::BitString,
+        chrono::Duration,
+        signature::Signer,
+    };
+
+    #[test]
+    fn crl_round_trip() -> Result<(), Error> {
+        let (ca, ca_key) = certificate_authority_ed25519();
+        let mut builder = X509CertificateBuilder::new(KeyAlgorithm::Ed25519);
+        builder.serial_number(42);
+        let (leaf, _) = issue_certificate(&builder, &ca, &ca_key);
+
+        let now = Utc::now();
+
+        let crl = sign_crl(
+            rfc5280::TbsCertList {
+                version: Some(rfc5280::Version::V2),
+                signature: ca_key.signature_algorithm()?.into(),
+                issuer: ca.subject_name().clone(),
+                this_update: Time::from(now),
+                next_update: None,
+                revoked_certificates: vec![rfc5280::RevokedCertificate {
+                    user_certificate: 42i64.into(),
+                    revocation_date: Time::from(now),
+                    crl_entry_extensions: None,
+                }],
+                crl_extensions: None,
+                raw_data: None,
+            },
+            &ca_key,
+        );
+
+        crl.verify_signed_by_certificate(&ca)?;
+        assert!(crl.verify_signed_by_certificate(&leaf).is_err());
+        assert!(crl.covers_certificate(&leaf));
+        assert!(matches!(
+            RevocationStatus::from_crl(&crl, &leaf, now),
+            RevocationStatus::Revoked { reason: None, .. }
+        ));
+
+        let pem = crl.encode_pem()?;
+        assert!(pem.starts_with("-----BEGIN X509 CRL-----"));
+        assert_eq!(CertificateRevocationList::from_pem(pem)?, crl);
+
+        Ok(())
+    }
+
+    #[test]
+    fn ocsp_request_round_trip() -> Result<(), Error> {
+        let (ca, ca_key) = certificate_authority_ed25519();
+        let (leaf, _) = issue_certificate(
+            &X509CertificateBuilder::new(KeyAlgorithm::Ed25519),
+            &ca,
+            &ca_key,
+        );
+
+        let cert_id = ocsp_cert_id(&leaf, &ca, DigestAlgorithm::Sha1)?;
+        let request = create_ocsp_request(vec![cert_id.clone()], Some(b"nonce"))?;
+
+        let mut der = vec![];
+        request.encode_ref().write_encoded(Mode::Der, &mut der)?;
+
+        let parsed = Constructed::decode(der.as_ref(), Mode::Der, |cons| {
+            rfc6960::OcspRequest::take_from(cons)
+        })?;
+        assert_eq!(parsed, request);
+        assert_eq!(parsed.tbs_request.request_list[0].req_cert, cert_id);
+
+        Ok(())
+    }
+
+    #[test]
+    fn ocsp_response_delegated_responder() -> Result<(), Error> {
+        let (ca, ca_key) = certificate_authority_ed25519();
+        let (leaf, _) = issue_certificate(
+            &X509CertificateBuilder::new(KeyAlgorithm::Ed25519),
+            &ca,
+            &ca_key,
+        );
+
+        let mut builder = X509CertificateBuilder::new(KeyAlgorithm::Ed25519);
+        builder
+            .subject()
+            .append_common_name_utf8_string("OCSP Responder")
+            .unwrap();
+        builder.key_usage(KeyUsage::DigitalSignature);
+        // Extended key usage extension with id-kp-OCSPSigning.
+        builder.add_extension_der_data(
+            Oid(Bytes::from_static(&[85, 29, 37])),
+            false,
+            &[
+                0x30, 0x0a, 0x06, 0x08, 0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x09,
+            ],
+        );
+        let (responder, responder_key) = issue_certificate(&builder, &ca, &ca_key);
+
+        let now = Utc::now();
+
+        let tbs_response_data = rfc6960::ResponseData {
+            version: None,
+            responder_id: rfc6960::ResponderId::ByName(responder.subject_name().clone()),
+            produced_at: now.into(),
+            responses: vec![rfc6960::SingleResponse {
+                cert_id: ocsp_cert_id(&leaf, &ca, DigestAlgorithm::Sha256)?,
+                cert_status: rfc6960::CertStatus::Revoked(rfc6960::RevokedInfo {
+                    revocation_time: now.into(),
+                    revocation_reason: Some(rfc5280::CrlReason::KeyCompromise),
+                }),
+                this_update: now.into(),
+                next_update: None,
+                single_extensions: None,
+            }],
+            response_extensions: None,
+            raw_data: None,
+        };
+
+        let mut tbs_der = vec![];
+        tbs_response_data
+            .encode_ref()
+            .write_encoded(Mode::Der, &mut tbs_der)?;
+        let signature = responder_key.try_sign(&tbs_der)?;
+
+        let basic = rfc6960::BasicOcspResponse {
+            tbs_response_data,
+            signature_algorithm: responder_key.signature_algorithm()?.into(),
+            signature: BitString::new(0, Bytes::copy_from_slice(signature.as_ref())),
+            certs: vec![responder.clone().into()],
+        };
+
+        let mut basic_der = vec![];
+        basic
+            .encode_ref()
+            .write_encoded(Mode::Der, &mut basic_der)?;
+
+        let response = rfc6960::OcspResponse {
+            response_status: rfc6960::OcspResponseStatus::Successful,
+            response_bytes: Some(rfc6960::ResponseBytes {
+                response_type: Oid(rfc6960::OID_PKIX_OCSP_BASIC.as_ref().into()),
+                response: OctetString::new(Bytes::from(basic_der)),
+            }),
+        };
+
+        let mut der = vec![];
+        response.encode_ref().write_encoded(Mode::Der, &mut der)?;
+
+        let response = OcspResponse::from_der(&der)?;
+        assert_eq!(response.status(), rfc6960::OcspResponseStatus::Successful);
+        response.verify_signed_by_issuer(&ca)?;
+
+        // The responder isn't authorized to speak for other issuers.
+        assert!(matches!(
+            response.verify_signed_by_issuer(&leaf),
+            Err(Error::OcspResponderNotAuthorized)
+        ));
+
+        let single = response.find_single_response(&leaf, &ca).unwrap();
+        assert!(matches!(
+            RevocationStatus::from_single_response(single, now),
+            RevocationStatus::Revoked {
+                reason: Some(rfc5280::CrlReason::KeyCompromise),
+                ..
+            }
+        ));
+        assert!(response.find_single_response(&ca, &ca).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn crl_staleness() -> Result<(), Error> {
+        let (ca, ca_key) = certificate_authority_ed25519();
+        let (leaf, _) = issue_certificate(
+            &X509CertificateBuilder::new(KeyAlgorithm::Ed25519),
+            &ca,
+            &ca_key,
+        );
+
+        let this_update = Utc::now() - Duration::hours(2);
+        let next_update = Utc::now() - Duration::hours(1);
+
+        let crl = sign_crl(
+            rfc5280::TbsCertList {
+                version: Some(rfc5280::Version::V2),
+                signature: ca_key.signature_algorithm()?.into(),
+                issuer: ca.subject_name().clone(),
+                this_update: Time::from(this_update),
+                next_update: Some(Time::from(next_update)),
+                revoked_certificates: vec![],
+                crl_extensions: None,
+                raw_data: None,
+            },
+            &ca_key,
+        );
+
+        let during = this_update + Duration::minutes(30);
+
+        assert!(crl.is_current(during));
+        assert!(!crl.is_current(Utc::now()));
+        assert!(!crl.is_current(this_update - Duration::minutes(1)));
+
+        assert_eq!(
+            RevocationStatus::from_crl(&crl, &leaf, during),
+            RevocationStatus::Good
+        );
+        assert_eq!(
+            RevocationStatus::from_crl(&crl, &leaf, Utc::now()),
+            RevocationStatus::Unknown
+        );
+        assert_eq!(
+            RevocationStatus::from_crl(&crl, &leaf, this_update - Duration::minutes(1)),
+            RevocationStatus::Unknown
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn ocsp_staleness() -> Result<(), Error> {
+        let (ca, ca_key) = certificate_authority_ed25519();
+        let (leaf, _) = issue_certificate(
+            &X509CertificateBuilder::new(KeyAlgorithm::Ed25519),
+            &ca,
+            &ca_key,
+        );
+
+        let this_update = Utc::now() - Duration::hours(2);
+        let next_update = Utc::now() - Duration::hours(1);
+        let during = this_update + Duration::minutes(30);
+
+        let mut response = rfc6960::SingleResponse {
+            cert_id: ocsp_cert_id(&leaf, &ca, DigestAlgorithm::Sha256)?,
+            cert_status: rfc6960::CertStatus::Good,
+            this_update: this_update.into(),
+            next_update: Some(next_update.into()),
+            single_extensions: None,
+        };
+
+        assert!(single_response_is_current(&response, during));
+        assert_eq!(
+            RevocationStatus::from_single_response(&response, during),
+            RevocationStatus::Good
+        );
+        assert_eq!(
+            RevocationStatus::from_single_response(&response, Utc::now()),
+            RevocationStatus::Unknown
+        );
+        assert_eq!(
+            RevocationStatus::from_single_response(&response, this_update - Duration::minutes(1)),
+            RevocationStatus::Unknown
+        );
+
+        // Revocations are reported even from stale responses.
+        response.cert_status = rfc6960::CertStatus::Revoked(rfc6960::RevokedInfo {
+            revocation_time: this_update.into(),
+            revocation_reason: None,
+        });
+        assert!(matches!(
+            RevocationStatus::from_single_response(&response, Utc::now()),
+            RevocationStatus::Revoked { .. }
+        ));
+
+        Ok(())
+    }
+}
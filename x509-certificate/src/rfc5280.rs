@@ -7,10 +7,10 @@
 use {
     crate::{asn1time::*, rfc3280::*},
     bcder::{
-        decode::{Constructed, Malformed, Source, Unimplemented},
+        decode::{Constructed, Malformed, Primitive, Source},
         encode,
         encode::{PrimitiveContent, Values},
-        BitString, Captured, Integer, Mode, OctetString, Oid, Tag,
+        BitString, Captured, ConstOid, Integer, Mode, OctetString, Oid, Tag, Unsigned,
     },
     bytes::Bytes,
     std::{
@@ -217,6 +217,16 @@ impl Certificate {
     }
 }
 
+impl Values for Certificate {
+    fn encoded_len(&self, mode: Mode) -> usize {
+        self.encode_ref().encoded_len(mode)
+    }
+
+    fn write_encoded<W: Write>(&self, mode: Mode, target: &mut W) -> Result<(), std::io::Error> {
+        self.encode_ref().write_encoded(mode, target)
+    }
+}
+
 /// TBS Certificate.
 ///
 /// This holds most of the metadata within an X.509 certificate.
@@ -359,7 +369,15 @@ pub enum Version {
 
 impl Version {
     pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
-        match cons.take_primitive_if(Tag::INTEGER, Integer::i8_from_primitive)? {
+        cons.take_primitive_if(Tag::INTEGER, Self::from_primitive)
+    }
+
+    pub fn take_opt_from<S: Source>(cons: &mut Constructed<S>) -> Result<Option<Self>, S::Err> {
+        cons.take_opt_primitive_if(Tag::INTEGER, Self::from_primitive)
+    }
+
+    pub fn from_primitive<S: Source>(prim: &mut Primitive<S>) -> Result<Self, S::Err> {
+        match Integer::i8_from_primitive(prim)? {
             0 => Ok(Self::V1),
             1 => Ok(Self::V2),
             2 => Ok(Self::V3),
@@ -595,21 +613,44 @@ impl Values for Extension {
 
 /// Certificate list.
 ///
+/// This is the data structure of a certificate revocation list (CRL).
+///
 /// ```ASN.1
 /// CertificateList  ::=  SEQUENCE  {
 ///      tbsCertList          TBSCertList,
 ///      signatureAlgorithm   AlgorithmIdentifier,
 ///      signature            BIT STRING  }
 /// ```
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq)]
 pub struct CertificateList {
     pub tbs_cert_list: TbsCertList,
     pub signature_algorithm: AlgorithmIdentifier,
     pub signature: BitString,
 }
 
+impl Debug for CertificateList {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut s = f.debug_struct("CertificateList");
+        s.field("tbs_cert_list", &self.tbs_cert_list);
+        s.field("signature_algorithm", &self.signature_algorithm);
+        s.field(
+            "signature",
+            &format_args!(
+                "{} (unused {})",
+                hex::encode(self.signature.octet_bytes()),
+                self.signature.unused()
+            ),
+        );
+        s.finish()
+    }
+}
+
 impl CertificateList {
     pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        cons.take_sequence(|cons| Self::from_sequence(cons))
+    }
+
+    pub fn from_sequence<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
         let tbs_cert_list = TbsCertList::take_from(cons)?;
         let signature_algorithm = AlgorithmIdentifier::take_from(cons)?;
         let signature = BitString::take_from(cons)?;
@@ -620,6 +661,22 @@ impl CertificateList {
             signature,
         })
     }
+
+    pub fn encode_ref(&self) -> impl Values + '_ {
+        encode::sequence((
+            self.tbs_cert_list.encode_ref(),
+            &self.signature_algorithm,
+            self.signature.encode_ref(),
+        ))
+    }
+
+    /// Iterate over extensions defined on this certificate list.
+    pub fn iter_extensions(&self) -> impl Iterator<Item = &Extension> {
+        self.tbs_cert_list
+            .crl_extensions
+            .iter()
+            .flat_map(|x| x.iter())
+    }
 }
 
 /// Tbs Certificate list.
@@ -641,19 +698,273 @@ impl CertificateList {
 ///  crlExtensions           [0] Extensions OPTIONAL }
 ///                                -- if present, MUST be v2
 /// ```
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq)]
 pub struct TbsCertList {
     pub version: Option<Version>,
     pub signature: AlgorithmIdentifier,
     pub issuer: Name,
     pub this_update: Time,
     pub next_update: Option<Time>,
-    pub revoked_certificates: Vec<(CertificateSerialNumber, Time, Option<Extensions>)>,
+    pub revoked_certificates: Vec<RevokedCertificate>,
     pub crl_extensions: Option<Extensions>,
+
+    /// Raw bytes this instance was constructed from.
+    ///
+    /// This is what signature verification should be performed against.
+    pub raw_data: Option<Vec<u8>>,
+}
+
+impl Debug for TbsCertList {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut s = f.debug_struct("TbsCertList");
+        s.field("version", &self.version);
+        s.field("signature", &self.signature);
+        s.field("issuer", &self.issuer);
+        s.field("this_update", &self.this_update);
+        s.field("next_update", &self.next_update);
+        s.field("revoked_certificates", &self.revoked_certificates);
+        s.field("crl_extensions", &self.crl_extensions);
+        s.field(
+            "raw_data",
+            &format_args!("{:?}", self.raw_data.as_ref().map(hex::encode)),
+        );
+        s.finish()
+    }
 }
 
 impl TbsCertList {
-    pub fn take_from<S: Source>(_cons: &mut Constructed<S>) -> Result<Self, S::Err> {
-        Err(Unimplemented.into())
+    pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        // Like TbsCertificate, the raw data is what's signed. So we stash it
+        // away to facilitate signature verification.
+        let mut res = None;
+
+        let captured = cons.capture(|cons| {
+            cons.take_sequence(|cons| {
+                let version = Version::take_opt_from(cons)?;
+                let signature = AlgorithmIdentifier::take_from(cons)?;
+                let issuer = Name::take_from(cons)?;
+                let this_update = Time::take_from(cons)?;
+                let next_update = Time::take_opt_from(cons)?;
+                let revoked_certificates = cons
+                    .take_opt_sequence(|cons| {
+                        let mut revoked = vec![];
+
+                        while let Some(entry) = RevokedCertificate::take_opt_from(cons)? {
+                            revoked.push(entry);
+                        }
+
+                        Ok(revoked)
+                    })?
+                    .unwrap_or_default();
+                let crl_extensions =
+                    cons.take_opt_constructed_if(Tag::CTX_0, |cons| Extensions::take_from(cons))?;
+
+                res = Some(Self {
+                    version,
+                    signature,
+                    issuer,
+                    this_update,
+                    next_update,
+                    revoked_certificates,
+                    crl_extensions,
+                    raw_data: None,
+                });
+
+                Ok(())
+            })
+        })?;
+
+        let mut res = res.unwrap();
+        res.raw_data = Some(captured.to_vec());
+
+        Ok(res)
+    }
+
+    pub fn encode_ref(&self) -> impl Values + '_ {
+        encode::sequence((
+            self.version.map(|v| v.encode()),
+            &self.signature,
+            self.issuer.encode_ref(),
+            self.this_update.encode_ref(),
+            self.next_update.as_ref().map(|t| t.encode_ref()),
+            if self.revoked_certificates.is_empty() {
+                None
+            } else {
+                Some(encode::sequence(&self.revoked_certificates))
+            },
+            self.crl_extensions
+                .as_ref()
+                .map(|extensions| encode::Constructed::new(Tag::CTX_0, extensions.encode_ref())),
+        ))
+    }
+}
+
+/// An entry in a certificate revocation list.
+///
+/// ```ASN.1
+/// SEQUENCE  {
+///   userCertificate         CertificateSerialNumber,
+///   revocationDate          Time,
+///   crlEntryExtensions      Extensions OPTIONAL }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RevokedCertificate {
+    pub user_certificate: CertificateSerialNumber,
+    pub revocation_date: Time,
+    pub crl_entry_extensions: Option<Extensions>,
+}
+
+impl RevokedCertificate {
+    pub fn take_opt_from<S: Source>(cons: &mut Constructed<S>) -> Result<Option<Self>, S::Err> {
+        cons.take_opt_sequence(|cons| Self::from_sequence(cons))
+    }
+
+    pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        cons.take_sequence(|cons| Self::from_sequence(cons))
+    }
+
+    fn from_sequence<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        let user_certificate = CertificateSerialNumber::take_from(cons)?;
+        let revocation_date = Time::take_from(cons)?;
+        let crl_entry_extensions = Extensions::take_opt_from(cons)?;
+
+        Ok(Self {
+            user_certificate,
+            revocation_date,
+            crl_entry_extensions,
+        })
+    }
+
+    pub fn encode_ref(&self) -> impl Values + '_ {
+        encode::sequence((
+            (&self.user_certificate).encode(),
+            self.revocation_date.encode_ref(),
+            self.crl_entry_extensions
+                .as_ref()
+                .map(|extensions| extensions.encode_ref()),
+        ))
+    }
+
+    /// Resolve the reason code from the `reasonCode` CRL entry extension, if present.
+    pub fn reason_code(&self) -> Option<CrlReason> {
+        self.crl_entry_extensions
+            .iter()
+            .flat_map(|x| x.iter())
+            .find(|ext| ext.id == OID_CRL_REASON_CODE)
+            .and_then(|ext| {
+                Constructed::decode(ext.value.to_source(), Mode::Der, |cons| {
+                    CrlReason::take_from(cons)
+                })
+                .ok()
+            })
+    }
+}
+
+impl Values for RevokedCertificate {
+    fn encoded_len(&self, mode: Mode) -> usize {
+        self.encode_ref().encoded_len(mode)
+    }
+
+    fn write_encoded<W: Write>(&self, mode: Mode, target: &mut W) -> Result<(), std::io::Error> {
+        self.encode_ref().write_encoded(mode, target)
+    }
+}
+
+/// Reason code CRL entry extension.
+///
+/// 2.5.29.21
+pub const OID_CRL_REASON_CODE: ConstOid = Oid(&[85, 29, 21]);
+
+/// CRL reason.
+///
+/// ```ASN.1
+/// CRLReason ::= ENUMERATED {
+///      unspecified             (0),
+///      keyCompromise           (1),
+///      cACompromise            (2),
+///      affiliationChanged      (3),
+///      superseded              (4),
+///      cessationOfOperation    (5),
+///      certificateHold         (6),
+///           -- value 7 is not used
+///      removeFromCRL           (8),
+///      privilegeWithdrawn      (9),
+///      aACompromise           (10) }
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CrlReason {
+    Unspecified = 0,
+    KeyCompromise = 1,
+    CaCompromise = 2,
+    AffiliationChanged = 3,
+    Superseded = 4,
+    CessationOfOperation = 5,
+    CertificateHold = 6,
+    RemoveFromCrl = 8,
+    PrivilegeWithdrawn = 9,
+    AaCompromise = 10,
+}
+
+impl CrlReason {
+    pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        cons.take_primitive_if(Tag::ENUMERATED, |prim| {
+            match Unsigned::u8_from_primitive(prim)? {
+                0 => Ok(Self::Unspecified),
+                1 => Ok(Self::KeyCompromise),
+                2 => Ok(Self::CaCompromise),
+                3 => Ok(Self::AffiliationChanged),
+                4 => Ok(Self::Superseded),
+                5 => Ok(Self::CessationOfOperation),
+                6 => Ok(Self::CertificateHold),
+                8 => Ok(Self::RemoveFromCrl),
+                9 => Ok(Self::PrivilegeWithdrawn),
+                10 => Ok(Self::AaCompromise),
+                _ => Err(Malformed.into()),
+            }
+        })
+    }
+
+    pub fn encode(self) -> impl Values {
+        (self as u8).encode_as(Tag::ENUMERATED)
+    }
+}
+
+/// Basic constraints extension.
+///
+/// 2.5.29.19
+pub const OID_BASIC_CONSTRAINTS: ConstOid = Oid(&[85, 29, 19]);
+
+/// Basic constraints.
+///
+/// ```ASN.1
+/// BasicConstraints ::= SEQUENCE {
+///      cA                      BOOLEAN DEFAULT FALSE,
+///      pathLenConstraint       INTEGER (0..MAX) OPTIONAL }
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BasicConstraints {
+    pub ca: bool,
+    pub path_len_constraint: Option<u32>,
+}
+
+impl BasicConstraints {
+    pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        cons.take_sequence(|cons| {
+            let ca = cons.take_opt_bool()?.unwrap_or(false);
+            let path_len_constraint =
+                cons.take_opt_primitive_if(Tag::INTEGER, Unsigned::u32_from_primitive)?;
+
+            Ok(Self {
+                ca,
+                path_len_constraint,
+            })
+        })
+    }
+
+    pub fn encode_ref(&self) -> impl Values + '_ {
+        encode::sequence((
+            if self.ca { Some(true.encode()) } else { None },
+            self.path_len_constraint.map(|v| v.encode()),
+        ))
     }
 }
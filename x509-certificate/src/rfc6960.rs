@@ -0,0 +1,952 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! ASN.1 types defined by RFC 6960 (Online Certificate Status Protocol).
+
+use {
+    crate::{
+        asn1time::GeneralizedTime,
+        rfc3280::{GeneralName, Name},
+        rfc5280::{
+            AlgorithmIdentifier, Certificate, CertificateSerialNumber, CrlReason, Extensions,
+            Version,
+        },
+    },
+    bcder::{
+        decode::{Constructed, Malformed, Source},
+        encode::{self, PrimitiveContent, Values},
+        BitString, ConstOid, Mode, OctetString, Oid, Tag, Unsigned,
+    },
+    std::{
+        fmt::{Debug, Formatter},
+        io::Write,
+    },
+};
+
+/// Basic OCSP response type.
+///
+/// 1.3.6.1.5.5.7.48.1.1
+pub const OID_PKIX_OCSP_BASIC: ConstOid = Oid(&[43, 6, 1, 5, 5, 7, 48, 1, 1]);
+
+/// OCSP nonce extension.
+///
+/// 1.3.6.1.5.5.7.48.1.2
+pub const OID_PKIX_OCSP_NONCE: ConstOid = Oid(&[43, 6, 1, 5, 5, 7, 48, 1, 2]);
+
+/// OCSP signing extended key usage.
+///
+/// 1.3.6.1.5.5.7.3.9
+pub const OID_KP_OCSP_SIGNING: ConstOid = Oid(&[43, 6, 1, 5, 5, 7, 3, 9]);
+
+/// Certificates embedded in signed OCSP structures.
+///
+/// ```ASN.1
+/// certs [0] EXPLICIT SEQUENCE OF Certificate OPTIONAL
+/// ```
+fn take_opt_certs<S: Source>(cons: &mut Constructed<S>) -> Result<Vec<Certificate>, S::Err> {
+    Ok(cons
+        .take_opt_constructed_if(Tag::CTX_0, |cons| {
+            cons.take_sequence(|cons| {
+                let mut certs = vec![];
+
+                while let Some(cert) =
+                    cons.take_opt_sequence(|cons| Certificate::from_sequence(cons))?
+                {
+                    certs.push(cert);
+                }
+
+                Ok(certs)
+            })
+        })?
+        .unwrap_or_default())
+}
+
+/// OCSP request.
+///
+/// ```ASN.1
+/// OCSPRequest ::= SEQUENCE {
+///   tbsRequest                  TBSRequest,
+///   optionalSignature   [0]     EXPLICIT Signature OPTIONAL }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OcspRequest {
+    pub tbs_request: TbsRequest,
+    pub optional_signature: Option<Signature>,
+}
+
+impl OcspRequest {
+    pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        cons.take_sequence(|cons| {
+            let tbs_request = TbsRequest::take_from(cons)?;
+            let optional_signature =
+                cons.take_opt_constructed_if(Tag::CTX_0, |cons| Signature::take_from(cons))?;
+
+            Ok(Self {
+                tbs_request,
+                optional_signature,
+            })
+        })
+    }
+
+    pub fn encode_ref(&self) -> impl Values + '_ {
+        encode::sequence((
+            self.tbs_request.encode_ref(),
+            self.optional_signature
+                .as_ref()
+                .map(|sig| encode::Constructed::new(Tag::CTX_0, sig.encode_ref())),
+        ))
+    }
+}
+
+/// TBS request.
+///
+/// ```ASN.1
+/// TBSRequest ::= SEQUENCE {
+///   version             [0]     EXPLICIT Version DEFAULT v1,
+///   requestorName       [1]     EXPLICIT GeneralName OPTIONAL,
+///   requestList                 SEQUENCE OF Request,
+///   requestExtensions   [2]     EXPLICIT Extensions OPTIONAL }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TbsRequest {
+    pub version: Option<Version>,
+    pub requestor_name: Option<GeneralName>,
+    pub request_list: Vec<Request>,
+    pub request_extensions: Option<Extensions>,
+}
+
+impl TbsRequest {
+    pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        cons.take_sequence(|cons| {
+            let version = cons.take_opt_constructed_if(Tag::CTX_0, Version::take_from)?;
+            let requestor_name =
+                cons.take_opt_constructed_if(Tag::CTX_1, |cons| GeneralName::take_from(cons))?;
+            let request_list = cons.take_sequence(|cons| {
+                let mut requests = vec![];
+
+                while let Some(request) = Request::take_opt_from(cons)? {
+                    requests.push(request);
+                }
+
+                Ok(requests)
+            })?;
+            let request_extensions =
+                cons.take_opt_constructed_if(Tag::CTX_2, |cons| Extensions::take_from(cons))?;
+
+            Ok(Self {
+                version,
+                requestor_name,
+                request_list,
+                request_extensions,
+            })
+        })
+    }
+
+    pub fn encode_ref(&self) -> impl Values + '_ {
+        encode::sequence((
+            self.version
+                .map(|v| encode::Constructed::new(Tag::CTX_0, v.encode())),
+            self.requestor_name
+                .as_ref()
+                .map(|name| encode::Constructed::new(Tag::CTX_1, name.encode_ref())),
+            encode::sequence(&self.request_list),
+            self.request_extensions
+                .as_ref()
+                .map(|extensions| encode::Constructed::new(Tag::CTX_2, extensions.encode_ref())),
+        ))
+    }
+}
+
+/// Signature of an OCSP request.
+///
+/// ```ASN.1
+/// Signature ::= SEQUENCE {
+///   signatureAlgorithm      AlgorithmIdentifier,
+///   signature               BIT STRING,
+///   certs               [0] EXPLICIT SEQUENCE OF Certificate OPTIONAL}
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Signature {
+    pub signature_algorithm: AlgorithmIdentifier,
+    pub signature: BitString,
+    pub certs: Vec<Certificate>,
+}
+
+impl Signature {
+    pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        cons.take_sequence(|cons| {
+            let signature_algorithm = AlgorithmIdentifier::take_from(cons)?;
+            let signature = BitString::take_from(cons)?;
+            let certs = take_opt_certs(cons)?;
+
+            Ok(Self {
+                signature_algorithm,
+                signature,
+                certs,
+            })
+        })
+    }
+
+    pub fn encode_ref(&self) -> impl Values + '_ {
+        encode::sequence((
+            &self.signature_algorithm,
+            self.signature.encode_ref(),
+            if self.certs.is_empty() {
+                None
+            } else {
+                Some(encode::Constructed::new(
+                    Tag::CTX_0,
+                    encode::sequence(&self.certs),
+                ))
+            },
+        ))
+    }
+}
+
+/// A request for the status of a single certificate.
+///
+/// ```ASN.1
+/// Request ::= SEQUENCE {
+///   reqCert                     CertID,
+///   singleRequestExtensions [0] EXPLICIT Extensions OPTIONAL }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Request {
+    pub req_cert: CertId,
+    pub single_request_extensions: Option<Extensions>,
+}
+
+impl Request {
+    pub fn take_opt_from<S: Source>(cons: &mut Constructed<S>) -> Result<Option<Self>, S::Err> {
+        cons.take_opt_sequence(|cons| Self::from_sequence(cons))
+    }
+
+    pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        cons.take_sequence(|cons| Self::from_sequence(cons))
+    }
+
+    fn from_sequence<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        let req_cert = CertId::take_from(cons)?;
+        let single_request_extensions =
+            cons.take_opt_constructed_if(Tag::CTX_0, |cons| Extensions::take_from(cons))?;
+
+        Ok(Self {
+            req_cert,
+            single_request_extensions,
+        })
+    }
+
+    pub fn encode_ref(&self) -> impl Values + '_ {
+        encode::sequence((
+            self.req_cert.encode_ref(),
+            self.single_request_extensions
+                .as_ref()
+                .map(|extensions| encode::Constructed::new(Tag::CTX_0, extensions.encode_ref())),
+        ))
+    }
+}
+
+impl Values for Request {
+    fn encoded_len(&self, mode: Mode) -> usize {
+        self.encode_ref().encoded_len(mode)
+    }
+
+    fn write_encoded<W: Write>(&self, mode: Mode, target: &mut W) -> Result<(), std::io::Error> {
+        self.encode_ref().write_encoded(mode, target)
+    }
+}
+
+/// Certificate identifier.
+///
+/// ```ASN.1
+/// CertID ::= SEQUENCE {
+///   hashAlgorithm       AlgorithmIdentifier,
+///   issuerNameHash      OCTET STRING, -- Hash of issuer's DN
+///   issuerKeyHash       OCTET STRING, -- Hash of issuer's public key
+///   serialNumber        CertificateSerialNumber }
+/// ```
+#[derive(Clone, Eq, PartialEq)]
+pub struct CertId {
+    pub hash_algorithm: AlgorithmIdentifier,
+    pub issuer_name_hash: OctetString,
+    pub issuer_key_hash: OctetString,
+    pub serial_number: CertificateSerialNumber,
+}
+
+impl Debug for CertId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut s = f.debug_struct("CertId");
+        s.field("hash_algorithm", &self.hash_algorithm);
+        s.field(
+            "issuer_name_hash",
+            &format_args!(
+                "{}",
+                hex::encode(self.issuer_name_hash.clone().into_bytes())
+            ),
+        );
+        s.field(
+            "issuer_key_hash",
+            &format_args!("{}", hex::encode(self.issuer_key_hash.clone().into_bytes())),
+        );
+        s.field("serial_number", &self.serial_number);
+        s.finish()
+    }
+}
+
+impl CertId {
+    pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        cons.take_sequence(|cons| {
+            let hash_algorithm = AlgorithmIdentifier::take_from(cons)?;
+            let issuer_name_hash = OctetString::take_from(cons)?;
+            let issuer_key_hash = OctetString::take_from(cons)?;
+            let serial_number = CertificateSerialNumber::take_from(cons)?;
+
+            Ok(Self {
+                hash_algorithm,
+                issuer_name_hash,
+                issuer_key_hash,
+                serial_number,
+            })
+        })
+    }
+
+    pub fn encode_ref(&self) -> impl Values + '_ {
+        encode::sequence((
+            &self.hash_algorithm,
+            self.issuer_name_hash.encode_ref(),
+            self.issuer_key_hash.encode_ref(),
+            (&self.serial_number).encode(),
+        ))
+    }
+}
+
+/// OCSP response.
+///
+/// ```ASN.1
+/// OCSPResponse ::= SEQUENCE {
+///   responseStatus         OCSPResponseStatus,
+///   responseBytes          [0] EXPLICIT ResponseBytes OPTIONAL }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OcspResponse {
+    pub response_status: OcspResponseStatus,
+    pub response_bytes: Option<ResponseBytes>,
+}
+
+impl OcspResponse {
+    pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        cons.take_sequence(|cons| {
+            let response_status = OcspResponseStatus::take_from(cons)?;
+            let response_bytes =
+                cons.take_opt_constructed_if(Tag::CTX_0, |cons| ResponseBytes::take_from(cons))?;
+
+            Ok(Self {
+                response_status,
+                response_bytes,
+            })
+        })
+    }
+
+    pub fn encode_ref(&self) -> impl Values + '_ {
+        encode::sequence((
+            self.response_status.encode(),
+            self.response_bytes
+                .as_ref()
+                .map(|bytes| encode::Constructed::new(Tag::CTX_0, bytes.encode_ref())),
+        ))
+    }
+}
+
+/// OCSP response status.
+///
+/// ```ASN.1
+/// OCSPResponseStatus ::= ENUMERATED {
+///   successful            (0),  -- Response has valid confirmations
+///   malformedRequest      (1),  -- Illegal confirmation request
+///   internalError         (2),  -- Internal error in issuer
+///   tryLater              (3),  -- Try again later
+///                               -- (4) is not used
+///   sigRequired           (5),  -- Must sign the request
+///   unauthorized          (6)   -- Request unauthorized
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OcspResponseStatus {
+    Successful = 0,
+    MalformedRequest = 1,
+    InternalError = 2,
+    TryLater = 3,
+    SigRequired = 5,
+    Unauthorized = 6,
+}
+
+impl OcspResponseStatus {
+    pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        cons.take_primitive_if(Tag::ENUMERATED, |prim| {
+            match Unsigned::u8_from_primitive(prim)? {
+                0 => Ok(Self::Successful),
+                1 => Ok(Self::MalformedRequest),
+                2 => Ok(Self::InternalError),
+                3 => Ok(Self::TryLater),
+                5 => Ok(Self::SigRequired),
+                6 => Ok(Self::Unauthorized),
+                _ => Err(Malformed.into()),
+            }
+        })
+    }
+
+    pub fn encode(self) -> impl Values {
+        (self as u8).encode_as(Tag::ENUMERATED)
+    }
+}
+
+/// Response bytes.
+///
+/// ```ASN.1
+/// ResponseBytes ::=       SEQUENCE {
+///   responseType   OBJECT IDENTIFIER,
+///   response       OCTET STRING }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResponseBytes {
+    pub response_type: Oid,
+    pub response: OctetString,
+}
+
+impl ResponseBytes {
+    pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        cons.take_sequence(|cons| {
+            let response_type = Oid::take_from(cons)?;
+            let response = OctetString::take_from(cons)?;
+
+            Ok(Self {
+                response_type,
+                response,
+            })
+        })
+    }
+
+    pub fn encode_ref(&self) -> impl Values + '_ {
+        encode::sequence((self.response_type.encode_ref(), self.response.encode_ref()))
+    }
+
+    /// Attempt to decode the response as a [BasicOcspResponse].
+    ///
+    /// Returns `Ok(None)` if the response type isn't `id-pkix-ocsp-basic`.
+    pub fn basic_response(&self) -> Result<Option<BasicOcspResponse>, bcder::decode::Error> {
+        if self.response_type != OID_PKIX_OCSP_BASIC {
+            return Ok(None);
+        }
+
+        Ok(Some(Constructed::decode(
+            self.response.to_source(),
+            Mode::Der,
+            |cons| BasicOcspResponse::take_from(cons),
+        )?))
+    }
+}
+
+/// Basic OCSP response.
+///
+/// ```ASN.1
+/// BasicOCSPResponse       ::= SEQUENCE {
+///   tbsResponseData      ResponseData,
+///   signatureAlgorithm   AlgorithmIdentifier,
+///   signature            BIT STRING,
+///   certs            [0] EXPLICIT SEQUENCE OF Certificate OPTIONAL }
+/// ```
+#[derive(Clone, Eq, PartialEq)]
+pub struct BasicOcspResponse {
+    pub tbs_response_data: ResponseData,
+    pub signature_algorithm: AlgorithmIdentifier,
+    pub signature: BitString,
+    pub certs: Vec<Certificate>,
+}
+
+impl Debug for BasicOcspResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut s = f.debug_struct("BasicOcspResponse");
+        s.field("tbs_response_data", &self.tbs_response_data);
+        s.field("signature_algorithm", &self.signature_algorithm);
+        s.field(
+            "signature",
+            &format_args!(
+                "{} (unused {})",
+                hex::encode(self.signature.octet_bytes()),
+                self.signature.unused()
+            ),
+        );
+        s.field("certs", &self.certs);
+        s.finish()
+    }
+}
+
+impl BasicOcspResponse {
+    pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        cons.take_sequence(|cons| {
+            let tbs_response_data = ResponseData::take_from(cons)?;
+            let signature_algorithm = AlgorithmIdentifier::take_from(cons)?;
+            let signature = BitString::take_from(cons)?;
+            let certs = take_opt_certs(cons)?;
+
+            Ok(Self {
+                tbs_response_data,
+                signature_algorithm,
+                signature,
+                certs,
+            })
+        })
+    }
+
+    pub fn encode_ref(&self) -> impl Values + '_ {
+        encode::sequence((
+            self.tbs_response_data.encode_ref(),
+            &self.signature_algorithm,
+            self.signature.encode_ref(),
+            if self.certs.is_empty() {
+                None
+            } else {
+                Some(encode::Constructed::new(
+                    Tag::CTX_0,
+                    encode::sequence(&self.certs),
+                ))
+            },
+        ))
+    }
+}
+
+/// Response data.
+///
+/// ```ASN.1
+/// ResponseData ::= SEQUENCE {
+///   version              [0] EXPLICIT Version DEFAULT v1,
+///   responderID              ResponderID,
+///   producedAt               GeneralizedTime,
+///   responses                SEQUENCE OF SingleResponse,
+///   responseExtensions   [1] EXPLICIT Extensions OPTIONAL }
+/// ```
+#[derive(Clone, Eq, PartialEq)]
+pub struct ResponseData {
+    pub version: Option<Version>,
+    pub responder_id: ResponderId,
+    pub produced_at: GeneralizedTime,
+    pub responses: Vec<SingleResponse>,
+    pub response_extensions: Option<Extensions>,
+
+    /// Raw bytes this instance was constructed from.
+    ///
+    /// This is what signature verification should be performed against.
+    pub raw_data: Option<Vec<u8>>,
+}
+
+impl Debug for ResponseData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut s = f.debug_struct("ResponseData");
+        s.field("version", &self.version);
+        s.field("responder_id", &self.responder_id);
+        s.field("produced_at", &self.produced_at);
+        s.field("responses", &self.responses);
+        s.field("response_extensions", &self.response_extensions);
+        s.field(
+            "raw_data",
+            &format_args!("{:?}", self.raw_data.as_ref().map(hex::encode)),
+        );
+        s.finish()
+    }
+}
+
+impl ResponseData {
+    pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        let mut res = None;
+
+        let captured = cons.capture(|cons| {
+            cons.take_sequence(|cons| {
+                let version = cons.take_opt_constructed_if(Tag::CTX_0, Version::take_from)?;
+                let responder_id = ResponderId::take_from(cons)?;
+                let produced_at = GeneralizedTime::take_from_allow_fractional_z(cons)?;
+                let responses = cons.take_sequence(|cons| {
+                    let mut responses = vec![];
+
+                    while let Some(response) = SingleResponse::take_opt_from(cons)? {
+                        responses.push(response);
+                    }
+
+                    Ok(responses)
+                })?;
+                let response_extensions =
+                    cons.take_opt_constructed_if(Tag::CTX_1, |cons| Extensions::take_from(cons))?;
+
+                res = Some(Self {
+                    version,
+                    responder_id,
+                    produced_at,
+                    responses,
+                    response_extensions,
+                    raw_data: None,
+                });
+
+                Ok(())
+            })
+        })?;
+
+        let mut res = res.unwrap();
+        res.raw_data = Some(captured.to_vec());
+
+        Ok(res)
+    }
+
+    pub fn encode_ref(&self) -> impl Values + '_ {
+        encode::sequence((
+            self.version
+                .map(|v| encode::Constructed::new(Tag::CTX_0, v.encode())),
+            self.responder_id.encode_ref(),
+            (&self.produced_at).encode(),
+            encode::sequence(&self.responses),
+            self.response_extensions
+                .as_ref()
+                .map(|extensions| encode::Constructed::new(Tag::CTX_1, extensions.encode_ref())),
+        ))
+    }
+}
+
+/// Responder identifier.
+///
+/// ```ASN.1
+/// ResponderID ::= CHOICE {
+///   byName   [1] Name,
+///   byKey    [2] KeyHash }
+///
+/// KeyHash ::= OCTET STRING -- SHA-1 hash of responder's public key
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ResponderId {
+    ByName(Name),
+    ByKey(OctetString),
+}
+
+impl ResponderId {
+    pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        if let Some(name) =
+            cons.take_opt_constructed_if(Tag::CTX_1, |cons| Name::take_from(cons))?
+        {
+            Ok(Self::ByName(name))
+        } else if let Some(hash) =
+            cons.take_opt_constructed_if(Tag::CTX_2, |cons| OctetString::take_from(cons))?
+        {
+            Ok(Self::ByKey(hash))
+        } else {
+            Err(Malformed.into())
+        }
+    }
+
+    pub fn encode_ref(&self) -> impl Values + '_ {
+        match self {
+            Self::ByName(name) => (
+                Some(encode::Constructed::new(Tag::CTX_1, name.encode_ref())),
+                None,
+            ),
+            Self::ByKey(hash) => (
+                None,
+                Some(encode::Constructed::new(Tag::CTX_2, hash.encode_ref())),
+            ),
+        }
+    }
+}
+
+/// Status of a single certificate.
+///
+/// ```ASN.1
+/// SingleResponse ::= SEQUENCE {
+///   certID                       CertID,
+///   certStatus                   CertStatus,
+///   thisUpdate                   GeneralizedTime,
+///   nextUpdate         [0]       EXPLICIT GeneralizedTime OPTIONAL,
+///   singleExtensions   [1]       EXPLICIT Extensions OPTIONAL }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SingleResponse {
+    pub cert_id: CertId,
+    pub cert_status: CertStatus,
+    pub this_update: GeneralizedTime,
+    pub next_update: Option<GeneralizedTime>,
+    pub single_extensions: Option<Extensions>,
+}
+
+impl SingleResponse {
+    pub fn take_opt_from<S: Source>(cons: &mut Constructed<S>) -> Result<Option<Self>, S::Err> {
+        cons.take_opt_sequence(|cons| Self::from_sequence(cons))
+    }
+
+    pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        cons.take_sequence(|cons| Self::from_sequence(cons))
+    }
+
+    fn from_sequence<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        let cert_id = CertId::take_from(cons)?;
+        let cert_status = CertStatus::take_from(cons)?;
+        let this_update = GeneralizedTime::take_from_allow_fractional_z(cons)?;
+        let next_update = cons.take_opt_constructed_if(Tag::CTX_0, |cons| {
+            GeneralizedTime::take_from_allow_fractional_z(cons)
+        })?;
+        let single_extensions =
+            cons.take_opt_constructed_if(Tag::CTX_1, |cons| Extensions::take_from(cons))?;
+
+        Ok(Self {
+            cert_id,
+            cert_status,
+            this_update,
+            next_update,
+            single_extensions,
+        })
+    }
+
+    pub fn encode_ref(&self) -> impl Values + '_ {
+        encode::sequence((
+            self.cert_id.encode_ref(),
+            self.cert_status.encode_ref(),
+            (&self.this_update).encode(),
+            self.next_update
+                .as_ref()
+                .map(|t| encode::Constructed::new(Tag::CTX_0, t.encode())),
+            self.single_extensions
+                .as_ref()
+                .map(|extensions| encode::Constructed::new(Tag::CTX_1, extensions.encode_ref())),
+        ))
+    }
+}
+
+impl Values for SingleResponse {
+    fn encoded_len(&self, mode: Mode) -> usize {
+        self.encode_ref().encoded_len(mode)
+    }
+
+    fn write_encoded<W: Write>(&self, mode: Mode, target: &mut W) -> Result<(), std::io::Error> {
+        self.encode_ref().write_encoded(mode, target)
+    }
+}
+
+/// Certificate status.
+///
+/// ```ASN.1
+/// CertStatus ::= CHOICE {
+///   good        [0]     IMPLICIT NULL,
+///   revoked     [1]     IMPLICIT RevokedInfo,
+///   unknown     [2]     IMPLICIT UnknownInfo }
+///
+/// UnknownInfo ::= NULL
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CertStatus {
+    Good,
+    Revoked(RevokedInfo),
+    Unknown,
+}
+
+impl CertStatus {
+    pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        if cons
+            .take_opt_primitive_if(Tag::CTX_0, |prim| {
+                prim.take_all()?;
+                Ok(())
+            })?
+            .is_some()
+        {
+            Ok(Self::Good)
+        } else if let Some(info) =
+            cons.take_opt_constructed_if(Tag::CTX_1, |cons| RevokedInfo::from_sequence(cons))?
+        {
+            Ok(Self::Revoked(info))
+        } else if cons
+            .take_opt_primitive_if(Tag::CTX_2, |prim| {
+                prim.take_all()?;
+                Ok(())
+            })?
+            .is_some()
+        {
+            Ok(Self::Unknown)
+        } else {
+            Err(Malformed.into())
+        }
+    }
+
+    pub fn encode_ref(&self) -> impl Values + '_ {
+        match self {
+            Self::Good => (Some(().encode_as(Tag::CTX_0)), None, None),
+            Self::Revoked(info) => (None, Some(info.encode_ref_as(Tag::CTX_1)), None),
+            Self::Unknown => (None, None, Some(().encode_as(Tag::CTX_2))),
+        }
+    }
+}
+
+/// Revocation info.
+///
+/// ```ASN.1
+/// RevokedInfo ::= SEQUENCE {
+///   revocationTime              GeneralizedTime,
+///   revocationReason    [0]     EXPLICIT CRLReason OPTIONAL }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RevokedInfo {
+    pub revocation_time: GeneralizedTime,
+    pub revocation_reason: Option<CrlReason>,
+}
+
+impl RevokedInfo {
+    pub fn take_from<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        cons.take_sequence(|cons| Self::from_sequence(cons))
+    }
+
+    pub fn from_sequence<S: Source>(cons: &mut Constructed<S>) -> Result<Self, S::Err> {
+        let revocation_time = GeneralizedTime::take_from_allow_fractional_z(cons)?;
+        let revocation_reason =
+            cons.take_opt_constructed_if(Tag::CTX_0, |cons| CrlReason::take_from(cons))?;
+
+        Ok(Self {
+            revocation_time,
+            revocation_reason,
+        })
+    }
+
+    pub fn encode_ref(&self) -> impl Values + '_ {
+        self.encode_ref_as(Tag::SEQUENCE)
+    }
+
+    pub fn encode_ref_as(&self, tag: Tag) -> impl Values + '_ {
+        encode::sequence_as(
+            tag,
+            (
+                (&self.revocation_time).encode(),
+                self.revocation_reason
+                    .map(|reason| encode::Constructed::new(Tag::CTX_0, reason.encode())),
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decode_response(der: &[u8]) -> (OcspResponse, BasicOcspResponse) {
+        let response =
+            Constructed::decode(der, Mode::Der, |cons| OcspResponse::take_from(cons)).unwrap();
+
+        let mut encoded = vec![];
+        response
+            .encode_ref()
+            .write_encoded(Mode::Der, &mut encoded)
+            .unwrap();
+        assert_eq!(&encoded, der);
+
+        assert_eq!(response.response_status, OcspResponseStatus::Successful);
+        let response_bytes = response.response_bytes.as_ref().unwrap();
+        let basic = response_bytes.basic_response().unwrap().unwrap();
+
+        let mut encoded = vec![];
+        basic
+            .encode_ref()
+            .write_encoded(Mode::Der, &mut encoded)
+            .unwrap();
+        assert_eq!(encoded, response_bytes.response.to_bytes().as_ref());
+
+        let mut encoded = vec![];
+        basic
+            .tbs_response_data
+            .encode_ref()
+            .write_encoded(Mode::Der, &mut encoded)
+            .unwrap();
+        assert_eq!(
+            Some(&encoded),
+            basic.tbs_response_data.raw_data.as_ref(),
+            "re-encoded response data matches signed bytes"
+        );
+
+        (response, basic)
+    }
+
+    fn assert_statuses(basic: &BasicOcspResponse) {
+        let responses = &basic.tbs_response_data.responses;
+        assert_eq!(responses.len(), 2);
+
+        assert_eq!(responses[0].cert_status, CertStatus::Good);
+        assert!(responses[0].next_update.is_some());
+
+        match &responses[1].cert_status {
+            CertStatus::Revoked(info) => {
+                assert_eq!(info.revocation_reason, Some(CrlReason::KeyCompromise));
+            }
+            status => panic!("unexpected certificate status: {:?}", status),
+        }
+        assert!(responses[1].next_update.is_some());
+    }
+
+    #[test]
+    fn request_round_trip() {
+        let der = include_bytes!("testdata/ocsp-request.der");
+
+        let request =
+            Constructed::decode(der.as_ref(), Mode::Der, |cons| OcspRequest::take_from(cons))
+                .unwrap();
+
+        let mut encoded = vec![];
+        request
+            .encode_ref()
+            .write_encoded(Mode::Der, &mut encoded)
+            .unwrap();
+        assert_eq!(&encoded, der);
+
+        assert!(request.optional_signature.is_none());
+        assert!(request.tbs_request.version.is_none());
+        assert!(request.tbs_request.requestor_name.is_none());
+        assert!(request.tbs_request.request_extensions.is_none());
+        assert_eq!(request.tbs_request.request_list.len(), 2);
+    }
+
+    #[test]
+    fn response_by_name_round_trip() {
+        let (_, basic) = decode_response(include_bytes!("testdata/ocsp-response-by-name.der"));
+
+        assert!(matches!(
+            basic.tbs_response_data.responder_id,
+            ResponderId::ByName(_)
+        ));
+        assert!(basic.tbs_response_data.response_extensions.is_some());
+        assert_eq!(basic.certs.len(), 1);
+        assert_statuses(&basic);
+    }
+
+    #[test]
+    fn response_by_key_round_trip() {
+        let (_, basic) = decode_response(include_bytes!("testdata/ocsp-response-by-key.der"));
+
+        let request = Constructed::decode(
+            include_bytes!("testdata/ocsp-request.der").as_ref(),
+            Mode::Der,
+            |cons| OcspRequest::take_from(cons),
+        )
+        .unwrap();
+
+        match &basic.tbs_response_data.responder_id {
+            ResponderId::ByKey(hash) => {
+                assert_eq!(
+                    hash,
+                    &request.tbs_request.request_list[0].req_cert.issuer_key_hash
+                );
+            }
+            id => panic!("unexpected responder id: {:?}", id),
+        }
+        assert!(basic.tbs_response_data.response_extensions.is_none());
+        assert!(basic.certs.is_empty());
+        assert_statuses(&basic);
+
+        for (response, request) in basic
+            .tbs_response_data
+            .responses
+            .iter()
+            .zip(request.tbs_request.request_list.iter())
+        {
+            assert_eq!(response.cert_id, request.req_cert);
+        }
+    }
+}
@@ -0,0 +1,575 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Certificate chain validation.
+//!
+//! [CertificateChainValidator] checks that a chain of certificates is
+//! suitable for use at a point in time: that every certificate is signed by
+//! the next one and within its validity period, that issuing certificates are
+//! certificate authorities honoring their path length and key usage
+//! constraints, and (optionally) that no certificate has been revoked
+//! according to CRLs and OCSP responses supplied by the caller.
+//!
+//! Validation does not decide whether the chain terminates at a trusted root
+//! certificate. That policy decision is left to the caller.
+
+use {
+    crate::{
+        certificate::KeyUsage,
+        revocation::{CertificateRevocationList, OcspResponse, RevocationStatus},
+        rfc5280::CrlReason,
+        rfc6960::OcspResponseStatus,
+        CapturedX509Certificate,
+    },
+    chrono::{DateTime, Utc},
+    thiserror::Error,
+};
+
+/// A problem found when validating a certificate chain.
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum ValidationProblem {
+    #[error("certificate {subject} is not valid until {not_before}")]
+    NotYetValid {
+        subject: String,
+        not_before: DateTime<Utc>,
+    },
+
+    #[error("certificate {subject} expired at {not_after}")]
+    Expired {
+        subject: String,
+        not_after: DateTime<Utc>,
+    },
+
+    #[error("certificate {subject} issued a certificate but is not a certificate authority")]
+    IssuerNotCertificateAuthority { subject: String },
+
+    #[error("certificate {subject} is followed by more CA certificates than its path length constraint of {path_len}")]
+    PathLengthExceeded { subject: String, path_len: u32 },
+
+    #[error("certificate {subject} lacks the {usage:?} key usage")]
+    MissingKeyUsage { subject: String, usage: KeyUsage },
+
+    #[error("certificate {subject} was revoked at {revocation_time} (reason: {reason:?})")]
+    Revoked {
+        subject: String,
+        revocation_time: DateTime<Utc>,
+        reason: Option<CrlReason>,
+    },
+
+    #[error("revocation status of certificate {subject} is unknown")]
+    RevocationStatusUnknown { subject: String },
+
+    #[error("error decoding certificate {subject}: {error}")]
+    Malformed { subject: String, error: String },
+
+    #[error("certificate {subject} was not signed by the next certificate in the chain, {issuer}: {error}")]
+    NotSignedByIssuer {
+        subject: String,
+        issuer: String,
+        error: String,
+    },
+}
+
+/// Validates chains of X.509 certificates.
+///
+/// Instances are configured incrementally and then used to validate any
+/// number of chains.
+///
+/// By default, validation is performed against the current time, no key
+/// usage is required of the leaf certificate, and revocation status is only
+/// checked against revocation data that has been added to the instance.
+#[derive(Clone, Debug, Default)]
+pub struct CertificateChainValidator {
+    validation_time: Option<DateTime<Utc>>,
+    leaf_key_usages: Vec<KeyUsage>,
+    crls: Vec<CertificateRevocationList>,
+    ocsp_responses: Vec<OcspResponse>,
+    require_revocation_status: bool,
+}
+
+impl CertificateChainValidator {
+    /// Set the time at which certificates must be valid.
+    ///
+    /// For signatures, this is typically the signing time, which allows
+    /// signatures made before a certificate expired to remain valid.
+    pub fn validation_time(&mut self, time: DateTime<Utc>) {
+        self.validation_time = Some(time);
+    }
+
+    /// Require the leaf certificate to allow a key usage.
+    ///
+    /// Certificates without a key usage extension aren't restricted and
+    /// always satisfy this requirement.
+    pub fn require_leaf_key_usage(&mut self, usage: KeyUsage) {
+        if !self.leaf_key_usages.contains(&usage) {
+            self.leaf_key_usages.push(usage);
+        }
+    }
+
+    /// Register a certificate revocation list to consult.
+    ///
+    /// CRLs are only consulted if their signature verifies against the
+    /// issuer of the certificate being checked.
+    pub fn add_crl(&mut self, crl: CertificateRevocationList) {
+        self.crls.push(crl);
+    }
+
+    /// Register an OCSP response to consult.
+    ///
+    /// Responses are only consulted if they are signed by an authorized
+    /// responder for the issuer of the certificate being checked.
+    pub fn add_ocsp_response(&mut self, response: OcspResponse) {
+        self.ocsp_responses.push(response);
+    }
+
+    /// Whether to report certificates whose revocation status is unknown.
+    pub fn require_revocation_status(&mut self, value: bool) {
+        self.require_revocation_status = value;
+    }
+
+    /// Resolve the revocation status of a certificate from registered revocation data.
+    ///
+    /// A certificate is revoked if any CRL or OCSP response says it is. It is good
+    /// if at least one source that is current at the validation time covers it
+    /// and none say it is revoked. Stale revocation data is therefore ignored
+    /// unless it reports a revocation.
+    pub fn revocation_status(
+        &self,
+        cert: &CapturedX509Certificate,
+        issuer: &CapturedX509Certificate,
+    ) -> RevocationStatus {
+        let time = self.validation_time.unwrap_or_else(Utc::now);
+        let mut status = RevocationStatus::Unknown;
+
+        for crl in &self.crls {
+            if !crl.covers_certificate(cert) || crl.verify_signed_by_certificate(issuer).is_err() {
+                continue;
+            }
+
+            match RevocationStatus::from_crl(crl, cert, time) {
+                revoked @ RevocationStatus::Revoked { .. } => return revoked,
+                RevocationStatus::Good => status = RevocationStatus::Good,
+                RevocationStatus::Unknown => {}
+            }
+        }
+
+        for response in &self.ocsp_responses {
+            if response.status() != OcspResponseStatus::Successful {
+                continue;
+            }
+
+            let single = if let Some(single) = response.find_single_response(cert, issuer) {
+                single
+            } else {
+                continue;
+            };
+
+            if response.verify_signed_by_issuer(issuer).is_err() {
+                continue;
+            }
+
+            match RevocationStatus::from_single_response(single, time) {
+                revoked @ RevocationStatus::Revoked { .. } => return revoked,
+                RevocationStatus::Good => status = RevocationStatus::Good,
+                RevocationStatus::Unknown => {}
+            }
+        }
+
+        status
+    }
+
+    /// Validate a chain of certificates.
+    ///
+    /// The first certificate is the leaf certificate and each subsequent
+    /// certificate must be the issuer of the one before it. The signature of
+    /// every certificate is verified against the public key of the next one
+    /// and certificates that don't verify are reported. See
+    /// [CapturedX509Certificate::resolve_signing_chain()] for a way to obtain such a chain.
+    ///
+    /// A revoked certificate is reported if its revocation time is before the
+    /// validation time or if the revocation reason indicates a key compromise,
+    /// as signatures made with a compromised key can't be trusted regardless
+    /// of when they were made.
+    ///
+    /// Returns problems that were found. An empty vector means the chain is valid.
+    pub fn validate_chain(&self, chain: &[&CapturedX509Certificate]) -> Vec<ValidationProblem> {
+        let time = self.validation_time.unwrap_or_else(Utc::now);
+
+        let mut problems = vec![];
+
+        for (i, cert) in chain.iter().enumerate() {
+            let subject = subject_str(cert);
+
+            if let Some(issuer) = chain.get(i + 1) {
+                if let Err(e) = cert.verify_signed_by_certificate(issuer) {
+                    problems.push(ValidationProblem::NotSignedByIssuer {
+                        subject: subject.clone(),
+                        issuer: subject_str(issuer),
+                        error: format!("{}", e),
+                    });
+                }
+            }
+
+            if time < cert.validity_not_before() {
+                problems.push(ValidationProblem::NotYetValid {
+                    subject: subject.clone(),
+                    not_before: cert.validity_not_before(),
+                });
+            }
+            if time > cert.validity_not_after() {
+                problems.push(ValidationProblem::Expired {
+                    subject: subject.clone(),
+                    not_after: cert.validity_not_after(),
+                });
+            }
+
+            let required_usages = if i == 0 {
+                self.leaf_key_usages.clone()
+            } else {
+                match cert.basic_constraints() {
+                    Ok(Some(constraints)) if constraints.ca => {
+                        // The path length constraint counts CA certificates between
+                        // this one and the leaf certificate.
+                        if let Some(path_len) = constraints.path_len_constraint {
+                            if i - 1 > path_len as usize {
+                                problems.push(ValidationProblem::PathLengthExceeded {
+                                    subject: subject.clone(),
+                                    path_len,
+                                });
+                            }
+                        }
+                    }
+                    Ok(_) => {
+                        problems.push(ValidationProblem::IssuerNotCertificateAuthority {
+                            subject: subject.clone(),
+                        });
+                    }
+                    Err(e) => {
+                        problems.push(ValidationProblem::Malformed {
+                            subject: subject.clone(),
+                            error: format!("{}", e),
+                        });
+                    }
+                }
+
+                vec![KeyUsage::KeyCertSign]
+            };
+
+            match cert.key_usages() {
+                Ok(Some(usages)) => {
+                    for usage in required_usages {
+                        if !usages.contains(&usage) {
+                            problems.push(ValidationProblem::MissingKeyUsage {
+                                subject: subject.clone(),
+                                usage,
+                            });
+                        }
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    problems.push(ValidationProblem::Malformed {
+                        subject: subject.clone(),
+                        error: format!("{}", e),
+                    });
+                }
+            }
+
+            let status = if let Some(issuer) = chain.get(i + 1) {
+                self.revocation_status(cert, issuer)
+            } else if cert.subject_is_issuer() {
+                // Self-signed certificates can't be revoked by anyone else.
+                continue;
+            } else {
+                RevocationStatus::Unknown
+            };
+
+            match status {
+                RevocationStatus::Revoked {
+                    revocation_time,
+                    reason,
+                } => {
+                    if revocation_time <= time
+                        || matches!(
+                            reason,
+                            Some(CrlReason::KeyCompromise) | Some(CrlReason::CaCompromise)
+                        )
+                    {
+                        problems.push(ValidationProblem::Revoked {
+                            subject,
+                            revocation_time,
+                            reason,
+                        });
+                    }
+                }
+                RevocationStatus::Unknown if self.require_revocation_status => {
+                    problems.push(ValidationProblem::RevocationStatusUnknown { subject });
+                }
+                _ => {}
+            }
+        }
+
+        problems
+    }
+
+    /// Validate a certificate by resolving its signing chain from a set of certificates.
+    ///
+    /// This is a convenience wrapper around [CapturedX509Certificate::resolve_signing_chain()]
+    /// and [Self::validate_chain()].
+    pub fn validate<'a>(
+        &self,
+        cert: &CapturedX509Certificate,
+        certs: impl Iterator<Item = &'a CapturedX509Certificate>,
+    ) -> Vec<ValidationProblem> {
+        let mut chain = vec![cert];
+        chain.extend(cert.resolve_signing_chain(certs));
+
+        self.validate_chain(&chain)
+    }
+}
+
+fn subject_str(cert: &CapturedX509Certificate) -> String {
+    cert.subject_name()
+        .user_friendly_str()
+        .unwrap_or_else(|_| "<unknown>".to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::{asn1time::Time, rfc5280, testutil::*, KeyAlgorithm, Sign, X509CertificateBuilder},
+        chrono::Duration,
+    };
+
+    fn leaf_builder() -> X509CertificateBuilder {
+        let mut builder = X509CertificateBuilder::new(KeyAlgorithm::Ed25519);
+        builder
+            .subject()
+            .append_common_name_utf8_string("leaf")
+            .unwrap();
+        builder.serial_number(42);
+
+        builder
+    }
+
+    #[test]
+    fn valid_chain() {
+        let (ca, ca_key) = certificate_authority_ed25519();
+        let (leaf, _) = issue_certificate(&leaf_builder(), &ca, &ca_key);
+
+        let mut validator = CertificateChainValidator::default();
+        validator.require_leaf_key_usage(KeyUsage::DigitalSignature);
+
+        assert!(validator.validate(&leaf, vec![&ca].into_iter()).is_empty());
+
+        validator.require_revocation_status(true);
+        assert_eq!(
+            validator.validate_chain(&[&leaf, &ca]),
+            vec![ValidationProblem::RevocationStatusUnknown {
+                subject: "leaf".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn expired() {
+        let (ca, ca_key) = certificate_authority_ed25519();
+        let (leaf, _) = issue_certificate(&leaf_builder(), &ca, &ca_key);
+
+        let mut validator = CertificateChainValidator::default();
+        validator.validation_time(Utc::now() + Duration::hours(2));
+
+        let problems = validator.validate_chain(&[&leaf, &ca]);
+        assert!(problems
+            .iter()
+            .any(|p| matches!(p, ValidationProblem::Expired { subject, .. } if subject == "leaf")));
+
+        validator.validation_time(Utc::now() - Duration::hours(2));
+        let problems = validator.validate_chain(&[&leaf, &ca]);
+        assert!(problems.iter().any(
+            |p| matches!(p, ValidationProblem::NotYetValid { subject, .. } if subject == "leaf")
+        ));
+    }
+
+    #[test]
+    fn forged_chain() {
+        let (ca, ca_key) = certificate_authority_ed25519();
+        let (leaf, _) = issue_certificate(&leaf_builder(), &ca, &ca_key);
+
+        let mut builder = X509CertificateBuilder::new(KeyAlgorithm::Ed25519);
+        builder
+            .subject()
+            .append_common_name_utf8_string("imposter")
+            .unwrap();
+        builder.constraint_ca(None).unwrap();
+        let (imposter, _, _) = builder.create_with_random_keypair().unwrap();
+
+        let validator = CertificateChainValidator::default();
+        assert!(validator.validate_chain(&[&leaf, &ca]).is_empty());
+
+        let problems = validator.validate_chain(&[&leaf, &imposter]);
+        assert!(matches!(
+            problems.as_slice(),
+            [ValidationProblem::NotSignedByIssuer { subject, issuer, .. }]
+                if subject == "leaf" && issuer == "imposter"
+        ));
+    }
+
+    #[test]
+    fn issuer_constraints() {
+        let (ca, ca_key) = certificate_authority_ed25519();
+        let (leaf, leaf_key) = issue_certificate(&leaf_builder(), &ca, &ca_key);
+
+        let mut builder = X509CertificateBuilder::new(KeyAlgorithm::Ed25519);
+        builder
+            .subject()
+            .append_common_name_utf8_string("child")
+            .unwrap();
+        let (child, _) = issue_certificate(&builder, &leaf, &leaf_key);
+
+        let validator = CertificateChainValidator::default();
+        assert!(validator.validate_chain(&[&child, &leaf, &ca]).contains(
+            &ValidationProblem::IssuerNotCertificateAuthority {
+                subject: "leaf".into()
+            }
+        ));
+
+        // A CA that can't issue intermediate CAs.
+        let mut builder = X509CertificateBuilder::new(KeyAlgorithm::Ed25519);
+        builder
+            .subject()
+            .append_common_name_utf8_string("root")
+            .unwrap();
+        builder.constraint_ca(Some(0)).unwrap();
+        let (root, root_key, _) = builder.create_with_random_keypair().unwrap();
+
+        let mut builder = X509CertificateBuilder::new(KeyAlgorithm::Ed25519);
+        builder
+            .subject()
+            .append_common_name_utf8_string("intermediate")
+            .unwrap();
+        builder.constraint_ca(None).unwrap();
+        let (intermediate, intermediate_key) = issue_certificate(&builder, &root, &root_key);
+        let (leaf, _) = issue_certificate(&leaf_builder(), &intermediate, &intermediate_key);
+
+        assert_eq!(
+            validator.validate(&leaf, vec![&root, &intermediate].into_iter()),
+            vec![ValidationProblem::PathLengthExceeded {
+                subject: "root".into(),
+                path_len: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn leaf_key_usage() {
+        let (ca, ca_key) = certificate_authority_ed25519();
+        let mut builder = leaf_builder();
+        builder.key_usage(KeyUsage::KeyEncipherment);
+        let (leaf, _) = issue_certificate(&builder, &ca, &ca_key);
+
+        let mut validator = CertificateChainValidator::default();
+        assert!(validator.validate_chain(&[&leaf, &ca]).is_empty());
+
+        validator.require_leaf_key_usage(KeyUsage::DigitalSignature);
+        assert_eq!(
+            validator.validate_chain(&[&leaf, &ca]),
+            vec![ValidationProblem::MissingKeyUsage {
+                subject: "leaf".into(),
+                usage: KeyUsage::DigitalSignature
+            }]
+        );
+    }
+
+    #[test]
+    fn crl_revocation() {
+        let (ca, ca_key) = certificate_authority_ed25519();
+        let (leaf, _) = issue_certificate(&leaf_builder(), &ca, &ca_key);
+
+        let revocation_time = Utc::now() - Duration::minutes(5);
+
+        let crl = sign_crl(
+            rfc5280::TbsCertList {
+                version: Some(rfc5280::Version::V2),
+                signature: ca_key.signature_algorithm().unwrap().into(),
+                issuer: ca.subject_name().clone(),
+                this_update: Time::from(Utc::now()),
+                next_update: None,
+                revoked_certificates: vec![rfc5280::RevokedCertificate {
+                    user_certificate: 42i64.into(),
+                    revocation_date: Time::from(revocation_time),
+                    crl_entry_extensions: None,
+                }],
+                crl_extensions: None,
+                raw_data: None,
+            },
+            &ca_key,
+        );
+
+        // A CRL signed by someone else is ignored.
+        let (imposter, _) = certificate_authority_ed25519();
+        let mut validator = CertificateChainValidator::default();
+        validator.add_crl(crl.clone());
+        assert_eq!(
+            validator.revocation_status(&leaf, &imposter),
+            RevocationStatus::Unknown
+        );
+
+        let problems = validator.validate_chain(&[&leaf, &ca]);
+        assert!(matches!(
+            problems.as_slice(),
+            [ValidationProblem::Revoked { subject, reason: None, .. }] if subject == "leaf"
+        ));
+
+        // Signatures made before the revocation are still valid.
+        validator.validation_time(revocation_time - Duration::minutes(1));
+        assert!(validator.validate_chain(&[&leaf, &ca]).is_empty());
+    }
+
+    #[test]
+    fn crl_stale() {
+        let (ca, ca_key) = certificate_authority_ed25519();
+        let (leaf, _) = issue_certificate(&leaf_builder(), &ca, &ca_key);
+
+        let this_update = Utc::now() - Duration::minutes(10);
+        let next_update = Utc::now() - Duration::minutes(5);
+
+        let crl = sign_crl(
+            rfc5280::TbsCertList {
+                version: Some(rfc5280::Version::V2),
+                signature: ca_key.signature_algorithm().unwrap().into(),
+                issuer: ca.subject_name().clone(),
+                this_update: Time::from(this_update),
+                next_update: Some(Time::from(next_update)),
+                revoked_certificates: vec![],
+                crl_extensions: None,
+                raw_data: None,
+            },
+            &ca_key,
+        );
+
+        let mut validator = CertificateChainValidator::default();
+        validator.add_crl(crl);
+        validator.require_revocation_status(true);
+
+        // The CRL expired before the validation time.
+        assert_eq!(
+            validator.revocation_status(&leaf, &ca),
+            RevocationStatus::Unknown
+        );
+        assert_eq!(
+            validator.validate_chain(&[&leaf, &ca]),
+            vec![ValidationProblem::RevocationStatusUnknown {
+                subject: "leaf".into()
+            }]
+        );
+
+        validator.validation_time(this_update + Duration::minutes(1));
+        assert_eq!(
+            validator.revocation_status(&leaf, &ca),
+            RevocationStatus::Good
+        );
+    }
+}